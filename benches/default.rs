@@ -6,6 +6,7 @@ mod files;
 mod http;
 mod lua;
 mod metrics_snapshot;
+mod pipeline;
 mod regex;
 mod template;
 mod topology;
@@ -17,6 +18,7 @@ criterion_main!(
     http::benches,
     lua::benches,
     metrics_snapshot::benches,
+    pipeline::benches,
     regex::benches,
     template::benches,
     topology::benches,
@@ -0,0 +1,62 @@
+use criterion::{criterion_group, BatchSize, Criterion, SamplingMode, Throughput};
+use futures::{SinkExt, StreamExt};
+use vector::{event::Event, test_util::runtime, Pipeline};
+
+fn benchmark_pipeline_send(c: &mut Criterion) {
+    let num_events: usize = 100_000;
+
+    let mut group = c.benchmark_group("pipeline");
+    group.throughput(Throughput::Elements(num_events as u64));
+    group.sampling_mode(SamplingMode::Flat);
+
+    let input: Vec<Event> = (0..num_events)
+        .map(|i| Event::from(format!("line {}", i)))
+        .collect();
+
+    group.bench_function("per_event", |b| {
+        b.iter_batched(
+            || (runtime(), input.clone()),
+            |(rt, input)| {
+                rt.block_on(async move {
+                    let (pipeline, rx) = Pipeline::new_with_buffer(1000, vec![]);
+                    let consumer = tokio::spawn(rx.for_each(|_| async {}));
+                    for event in input {
+                        pipeline.clone().send(event).await.unwrap();
+                    }
+                    drop(pipeline);
+                    consumer.await.unwrap();
+                })
+            },
+            BatchSize::LargeInput,
+        )
+    });
+
+    group.bench_function("batched", |b| {
+        b.iter_batched(
+            || (runtime(), input.clone()),
+            |(rt, input)| {
+                rt.block_on(async move {
+                    let (mut pipeline, rx) = Pipeline::new_with_buffer(1000, vec![]);
+                    let consumer = tokio::spawn(rx.for_each(|_| async {}));
+                    let mut input = input.into_iter();
+                    loop {
+                        let chunk = input.by_ref().take(100).collect::<Vec<_>>();
+                        if chunk.is_empty() {
+                            break;
+                        }
+                        pipeline.send_batch(chunk).await.unwrap();
+                    }
+                    drop(pipeline);
+                    consumer.await.unwrap();
+                })
+            },
+            BatchSize::LargeInput,
+        )
+    });
+}
+
+criterion_group!(
+    name = benches;
+    config = Criterion::default().noise_threshold(0.05);
+    targets = benchmark_pipeline_send
+);
@@ -3,9 +3,10 @@ use std::collections::HashMap;
 use chrono::{DateTime, Utc};
 use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
 use indexmap::IndexMap;
+use lookup::LookupBuf;
 use shared::TimeZone;
 use vector::{
-    event::{Event, Value},
+    event::{Event, Metric, MetricKind, MetricValue, Value, VrlTarget},
     transforms::{
         add_fields::AddFields,
         coercer::Coercer,
@@ -21,7 +22,7 @@ criterion_group!(
     // encapsulates CI noise we saw in
     // https://github.com/timberio/vector/issues/5394
     config = Criterion::default().noise_threshold(0.02);
-    targets = benchmark_remap
+    targets = benchmark_remap, benchmark_exists
 );
 criterion_main!(benches);
 
@@ -258,3 +259,33 @@ fn benchmark_remap(c: &mut Criterion) {
         );
     });
 }
+
+fn benchmark_exists(c: &mut Criterion) {
+    let mut group = c.benchmark_group("remap/exists");
+
+    let metric = Metric::new(
+        "counter",
+        MetricKind::Absolute,
+        MetricValue::Counter { value: 1.0 },
+    )
+    .with_tags(Some(
+        (0..50)
+            .map(|i| (format!("tag_{}", i), format!("value_{}", i)))
+            .collect(),
+    ));
+    let target = VrlTarget::new(Event::Metric(metric));
+
+    for path in [".", ".tags", ".tags.tag_25", ".tags.missing"] {
+        let lookup = match path {
+            "." => LookupBuf::root(),
+            path => LookupBuf::from_str(path).unwrap(),
+        };
+
+        group.bench_function(format!("metric/get/{}", path), |b| {
+            b.iter(|| target.get(&lookup).unwrap().is_some())
+        });
+        group.bench_function(format!("metric/contains/{}", path), |b| {
+            b.iter(|| target.contains(&lookup).unwrap())
+        });
+    }
+}
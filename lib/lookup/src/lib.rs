@@ -6,12 +6,14 @@ use std::{
 pub use error::LookupError;
 pub use lookup_buf::{FieldBuf, LookupBuf, SegmentBuf};
 pub use lookup_view::{Field, Lookup, Segment};
+pub use path_ref::{AsPathRef, PathRef};
 
 mod error;
 mod field;
 mod lookup_buf;
 mod lookup_view;
 pub mod parser;
+mod path_ref;
 
 /// This trait, while it is not necessarily imported and used, exists
 /// to enforce parity among view/buf types.
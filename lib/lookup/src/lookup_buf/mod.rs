@@ -157,6 +157,27 @@ impl LookupBuf {
         }
     }
 
+    /// Append a field for the key `name` as it is, so that paths can be built from keys that
+    /// would need escaping in a parsed path, such as ones holding `.`, `[` or quotes.
+    ///
+    /// ```rust
+    /// use lookup::LookupBuf;
+    ///
+    /// let lookup = LookupBuf::root().push_field("a.b").push_index(1);
+    /// assert_eq!(lookup.len(), 2);
+    /// ```
+    pub fn push_field(mut self, name: impl Into<String>) -> Self {
+        self.segments
+            .push_back(SegmentBuf::field_unchecked(name.into()));
+        self
+    }
+
+    /// Append an index. Negative indices count from the end of the array.
+    pub fn push_index(mut self, index: isize) -> Self {
+        self.segments.push_back(SegmentBuf::index(index));
+        self
+    }
+
     /// Return a borrow of the SegmentBuf set.
     pub fn as_segments(&self) -> &VecDeque<SegmentBuf> {
         &self.segments
@@ -14,6 +14,16 @@ pub struct FieldBuf {
 }
 
 impl FieldBuf {
+    /// A field named exactly `name`. Unlike `From<String>`, surrounding quotes are kept as part
+    /// of the name, so any key can be looked up without escaping it.
+    pub fn unchecked(name: String) -> Self {
+        let requires_quoting = !field::is_valid_fieldname(&name);
+        Self {
+            name,
+            requires_quoting,
+        }
+    }
+
     pub fn as_str(&self) -> &str {
         &self.name
     }
@@ -153,6 +163,14 @@ impl<'a> LookSegment<'a> for SegmentBuf {
     }
 }
 
+impl SegmentBuf {
+    /// A field segment for the key `name` as it is, whatever characters it holds. See
+    /// [`FieldBuf::unchecked`].
+    pub fn field_unchecked(name: String) -> SegmentBuf {
+        SegmentBuf::Field(FieldBuf::unchecked(name))
+    }
+}
+
 impl Display for SegmentBuf {
     fn fmt(&self, formatter: &mut Formatter<'_>) -> Result<(), std::fmt::Error> {
         match self {
@@ -240,6 +240,53 @@ fn test_index_parses() {
     assert_eq!("[30]", parsed.to_string());
 }
 
+#[test]
+fn field_unchecked_keeps_quotes() {
+    let segment = SegmentBuf::field_unchecked(r#""quoted""#.to_string());
+    assert_eq!(
+        SegmentBuf::Field(FieldBuf {
+            name: r#""quoted""#.into(),
+            requires_quoting: true,
+        }),
+        segment
+    );
+
+    let segment = SegmentBuf::field_unchecked("zork2".to_string());
+    assert_eq!(SegmentBuf::from("zork2"), segment);
+}
+
+#[test]
+fn push_builds_lookup() {
+    let lookup = LookupBuf::root()
+        .push_field("a.b")
+        .push_field("c[0]")
+        .push_index(-1);
+    assert_eq!(
+        LookupBuf::from_segments(vec![
+            SegmentBuf::field_unchecked("a.b".to_string()),
+            SegmentBuf::field_unchecked("c[0]".to_string()),
+            SegmentBuf::index(-1),
+        ]),
+        lookup
+    );
+    assert_eq!(lookup.len(), 3);
+}
+
+#[test]
+fn strings_and_lookups_are_path_refs() {
+    fn path_ref(path: &impl AsPathRef) -> PathRef<'_> {
+        path.as_path_ref()
+    }
+
+    assert_eq!(path_ref(&"a.b"), PathRef::Str("a.b"));
+    assert_eq!(path_ref(&String::from("a.b")), PathRef::Str("a.b"));
+
+    let lookup = LookupBuf::root().push_field("a.b");
+    assert_eq!(path_ref(&lookup), PathRef::Lookup(&lookup));
+    assert_eq!(path_ref(&&lookup), PathRef::Lookup(&lookup));
+    assert_eq!(path_ref(&lookup).to_string(), r#""a.b""#);
+}
+
 #[test]
 fn parses() {
     fn inner(path: LookupBuf) -> TestResult {
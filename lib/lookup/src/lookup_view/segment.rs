@@ -12,6 +12,15 @@ pub struct Field<'a> {
 }
 
 impl<'a> Field<'a> {
+    /// A field named exactly `name`. Unlike `From<&str>`, surrounding quotes are kept as part of
+    /// the name, so any key can be looked up without escaping it.
+    pub fn unchecked(name: &'a str) -> Self {
+        Self {
+            name,
+            requires_quoting: !field::is_valid_fieldname(name),
+        }
+    }
+
     pub fn as_field_buf(&self) -> FieldBuf {
         FieldBuf {
             name: self.name.to_string(),
@@ -71,6 +80,12 @@ pub enum Segment<'a> {
 }
 
 impl<'a> Segment<'a> {
+    /// A field segment for the key `name` as it is, whatever characters it holds. See
+    /// [`Field::unchecked`].
+    pub fn field_unchecked(name: &'a str) -> Segment<'a> {
+        Segment::Field(Field::unchecked(name))
+    }
+
     pub fn as_segment_buf(&self) -> SegmentBuf {
         match self {
             Segment::Field(field) => SegmentBuf::field(field.as_field_buf()),
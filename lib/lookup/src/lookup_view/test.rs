@@ -42,6 +42,24 @@ fn field_is_quoted() {
     );
 }
 
+#[test]
+fn field_unchecked_keeps_quotes() {
+    let segment = Segment::field_unchecked(r#""quoted""#);
+    assert_eq!(
+        Segment::Field(Field {
+            name: r#""quoted""#,
+            requires_quoting: true,
+        }),
+        segment
+    );
+
+    assert_eq!(Segment::from("zork2"), Segment::field_unchecked("zork2"));
+    assert_eq!(
+        Segment::field_unchecked("a.b").as_segment_buf(),
+        SegmentBuf::field_unchecked("a.b".to_string())
+    );
+}
+
 #[test]
 fn zero_len_not_allowed() {
     let input = "";
@@ -0,0 +1,45 @@
+use std::fmt::{Display, Formatter};
+
+use crate::LookupBuf;
+
+/// A path to a value as the accessors of events take it: either a string, which is parsed as a
+/// path, or a [`LookupBuf`], whose segments are taken as they are.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PathRef<'a> {
+    Str(&'a str),
+    Lookup(&'a LookupBuf),
+}
+
+impl<'a> Display for PathRef<'a> {
+    fn fmt(&self, formatter: &mut Formatter<'_>) -> Result<(), std::fmt::Error> {
+        match self {
+            PathRef::Str(path) => formatter.write_str(path),
+            PathRef::Lookup(lookup) => Display::fmt(lookup, formatter),
+        }
+    }
+}
+
+/// The types that can be given as a path where a [`PathRef`] is taken: strings, and lookups
+/// built with [`LookupBuf::push_field`] and the like.
+pub trait AsPathRef {
+    /// Borrow the path as a [`PathRef`].
+    fn as_path_ref(&self) -> PathRef<'_>;
+}
+
+impl<T: AsRef<str> + ?Sized> AsPathRef for T {
+    fn as_path_ref(&self) -> PathRef<'_> {
+        PathRef::Str(self.as_ref())
+    }
+}
+
+impl AsPathRef for LookupBuf {
+    fn as_path_ref(&self) -> PathRef<'_> {
+        PathRef::Lookup(self)
+    }
+}
+
+impl AsPathRef for &LookupBuf {
+    fn as_path_ref(&self) -> PathRef<'_> {
+        PathRef::Lookup(self)
+    }
+}
@@ -1,6 +1,6 @@
 use std::fmt::Debug;
 
-use chrono::{DateTime, Local, ParseError, TimeZone as _, Utc};
+use chrono::{DateTime, FixedOffset, Local, Offset, ParseError, TimeZone as _, Utc};
 use chrono_tz::Tz;
 use derivative::Derivative;
 
@@ -21,6 +21,16 @@ impl TimeZone {
         }
     }
 
+    /// The offset from UTC of this time zone at the given instant, which changes with
+    /// daylight saving time.
+    pub fn offset_at(&self, at: DateTime<Utc>) -> FixedOffset {
+        let at = at.naive_utc();
+        match self {
+            Self::Local => Local.offset_from_utc_datetime(&at),
+            Self::Named(tz) => tz.offset_from_utc_datetime(&at).fix(),
+        }
+    }
+
     pub fn parse(s: &str) -> Option<Self> {
         match s {
             "" | "local" => Some(Self::Local),
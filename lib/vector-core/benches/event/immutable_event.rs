@@ -0,0 +1,74 @@
+use std::time::Duration;
+
+use criterion::{
+    criterion_group, measurement::WallTime, BatchSize, BenchmarkGroup, Criterion, SamplingMode,
+};
+use vector_core::event::{Event, ImmutableEvent, LogEvent};
+
+fn events() -> Vec<Event> {
+    (0..1000)
+        .map(|i| {
+            let mut log = LogEvent::from(format!("event {} of the fan-out", i).as_str());
+            log.insert("host", "localhost");
+            log.insert("kubernetes.pod_name", format!("pod-{}", i % 10));
+            log.insert("kubernetes.pod_namespace", "default");
+            log.insert("status", 200);
+            Event::from(log)
+        })
+        .collect()
+}
+
+fn two_sink_fanout(c: &mut Criterion) {
+    let mut group: BenchmarkGroup<WallTime> =
+        c.benchmark_group("vector_core::event::immutable::ImmutableEvent");
+    group.sampling_mode(SamplingMode::Auto);
+
+    group.bench_function("fan-out of 1000 events to two sinks, cloned", |b| {
+        b.iter_batched(
+            events,
+            |events| {
+                let (first, second): (Vec<_>, Vec<_>) = events
+                    .into_iter()
+                    .map(|event| (event.clone(), event))
+                    .unzip();
+                // Both sinks only read the events, as one encoding them would.
+                first
+                    .iter()
+                    .chain(&second)
+                    .map(|event| event.as_log().all_fields().count())
+                    .sum::<usize>()
+            },
+            BatchSize::SmallInput,
+        )
+    });
+
+    group.bench_function("fan-out of 1000 events to two sinks, shared", |b| {
+        b.iter_batched(
+            events,
+            |events| {
+                let (first, second): (Vec<_>, Vec<_>) = events
+                    .into_iter()
+                    .map(|event| {
+                        let event = ImmutableEvent::from(event);
+                        (event.clone(), event)
+                    })
+                    .unzip();
+                first
+                    .iter()
+                    .chain(&second)
+                    .map(|event| event.all_fields().count())
+                    .sum::<usize>()
+            },
+            BatchSize::SmallInput,
+        )
+    });
+}
+
+criterion_group!(
+    name = benches;
+    config = Criterion::default()
+        .warm_up_time(Duration::from_secs(5))
+        .measurement_time(Duration::from_secs(30))
+        .noise_threshold(0.01);
+    targets = two_sink_fanout
+);
@@ -1,9 +1,13 @@
-use std::time::Duration;
+use std::{
+    convert::{TryFrom, TryInto},
+    fs::File,
+    time::Duration,
+};
 
 use criterion::{
     criterion_group, measurement::WallTime, BatchSize, BenchmarkGroup, Criterion, SamplingMode,
 };
-use vector_core::event::LogEvent;
+use vector_core::event::{write_ndjson, CompiledLookup, LogEvent};
 
 fn rename_key_flat(c: &mut Criterion) {
     let mut group: BenchmarkGroup<WallTime> =
@@ -43,6 +47,194 @@ fn rename_key_flat(c: &mut Criterion) {
     });
 }
 
+fn fixture_events() -> Vec<LogEvent> {
+    const FIXTURE_ROOT: &str = "tests/data/fixtures/log_event";
+
+    // Only the `.json` files directly in the root, the `ndjson` fixtures sit
+    // in a subdirectory.
+    std::fs::read_dir(FIXTURE_ROOT)
+        .unwrap()
+        .map(|fixture_file| fixture_file.unwrap().path())
+        .filter(|path| path.extension().map_or(false, |ext| ext == "json"))
+        .map(|path| {
+            let file = File::open(path).unwrap();
+            let json: serde_json::Value = serde_json::from_reader(file).unwrap();
+            LogEvent::try_from(json).unwrap()
+        })
+        .collect()
+}
+
+fn to_json(c: &mut Criterion) {
+    let mut group: BenchmarkGroup<WallTime> =
+        c.benchmark_group("vector_core::event::log_event::LogEvent::to_json");
+    group.sampling_mode(SamplingMode::Auto);
+
+    let events = fixture_events();
+
+    group.bench_function("via serde_json::Value", |b| {
+        b.iter(|| {
+            events
+                .iter()
+                .map(|event| {
+                    let value: serde_json::Value = event.clone().try_into().unwrap();
+                    serde_json::to_vec(&value).unwrap()
+                })
+                .collect::<Vec<_>>()
+        })
+    });
+
+    group.bench_function("to_json_vec", |b| {
+        b.iter(|| {
+            events
+                .iter()
+                .map(|event| event.to_json_vec().unwrap())
+                .collect::<Vec<_>>()
+        })
+    });
+
+    group.bench_function("write_ndjson", |b| {
+        b.iter(|| {
+            let mut buffer = Vec::new();
+            write_ndjson(&events, &mut buffer).unwrap();
+            buffer
+        })
+    });
+}
+
+fn clone_then(c: &mut Criterion) {
+    let mut group: BenchmarkGroup<WallTime> =
+        c.benchmark_group("vector_core::event::log_event::LogEvent::clone");
+    group.sampling_mode(SamplingMode::Auto);
+
+    let mut log_event = LogEvent::default();
+    for i in 0..64 {
+        log_event.insert(format!("field_{}", i), "x".repeat(256));
+        log_event.insert(format!("nested_{}.inner[0]", i), i);
+    }
+
+    let read_event = log_event.clone();
+    group.bench_function("clone then read", move |b| {
+        b.iter_batched(
+            || read_event.clone(),
+            |log_event| {
+                let clone = log_event.clone();
+                clone.get("field_32").cloned()
+            },
+            BatchSize::SmallInput,
+        )
+    });
+
+    group.bench_function("clone then write", move |b| {
+        b.iter_batched(
+            || log_event.clone(),
+            |log_event| {
+                let mut clone = log_event.clone();
+                clone.insert("field_32", "y");
+                clone
+            },
+            BatchSize::SmallInput,
+        )
+    });
+}
+
+fn extract(c: &mut Criterion) {
+    let mut group: BenchmarkGroup<WallTime> =
+        c.benchmark_group("vector_core::event::log_event::LogEvent::extract");
+    group.sampling_mode(SamplingMode::Auto);
+
+    let mut log_event = LogEvent::default();
+    for i in 0..16 {
+        log_event.insert(format!("field_{}", i), "x".repeat(64));
+        log_event.insert(format!("kubernetes.labels.label_{}", i), i);
+        log_event.insert(format!("spans[{}].id", i), i);
+    }
+
+    let paths = (0..16)
+        .step_by(2)
+        .flat_map(|i| {
+            vec![
+                format!("field_{}", i),
+                format!("kubernetes.labels.label_{}", i),
+                format!("spans[{}].id", i),
+            ]
+        })
+        .collect::<Vec<_>>();
+    let lookups = paths
+        .iter()
+        .map(|path| CompiledLookup::new(path).unwrap())
+        .collect::<Vec<_>>();
+
+    let event = log_event.clone();
+    group.bench_function("get per path", move |b| {
+        b.iter(|| {
+            paths
+                .iter()
+                .map(|path| event.get(path))
+                .collect::<Vec<_>>()
+                .len()
+        })
+    });
+
+    let event = log_event.clone();
+    let extract_lookups = lookups.clone();
+    group.bench_function("extract", move |b| {
+        b.iter(|| event.extract(&extract_lookups).len())
+    });
+
+    group.bench_function("extract_owned", move |b| {
+        b.iter_batched(
+            || log_event.clone(),
+            |log_event| log_event.extract_owned(&lookups),
+            BatchSize::SmallInput,
+        )
+    });
+}
+
+fn clone_subset(c: &mut Criterion) {
+    let mut group: BenchmarkGroup<WallTime> =
+        c.benchmark_group("vector_core::event::log_event::LogEvent::clone_subset");
+    group.sampling_mode(SamplingMode::Auto);
+
+    let mut log_event = LogEvent::default();
+    for i in 0..64 {
+        log_event.insert(format!("field_{}", i), "x".repeat(256));
+        log_event.insert(format!("nested_{}.inner[0]", i), i);
+    }
+    let keep = ["field_1", "field_32"];
+
+    let event = log_event.clone();
+    group.bench_function("clone then retain", move |b| {
+        b.iter(|| {
+            let mut clone = event.clone();
+            clone
+                .as_map_mut()
+                .retain(|key, _| keep.contains(&key.as_str()));
+            clone
+        })
+    });
+
+    let event = log_event.clone();
+    group.bench_function("clone_subset", move |b| {
+        b.iter(|| event.clone_subset(&keep))
+    });
+
+    let nested = ["field_1", "nested_8.inner[0]", "nested_32"];
+    let event = log_event.clone();
+    group.bench_function("clone then retain (nested)", move |b| {
+        b.iter(|| {
+            let mut clone = event.clone();
+            clone
+                .as_map_mut()
+                .retain(|key, _| key == "field_1" || key == "nested_8" || key == "nested_32");
+            clone
+        })
+    });
+
+    group.bench_function("clone_subset (nested)", move |b| {
+        b.iter(|| log_event.clone_subset(&nested))
+    });
+}
+
 criterion_group!(
     name = benches;
     config = Criterion::default()
@@ -58,5 +250,5 @@ criterion_group!(
         .nresamples(100_000)
         // total samples to collect within the set measurement time
         .sample_size(150);
-    targets = rename_key_flat
+    targets = rename_key_flat, clone_then, to_json, extract, clone_subset
 );
@@ -1,5 +1,11 @@
 use criterion::criterion_main;
 
+mod immutable_event;
 mod log_event;
+mod metric;
 
-criterion_main!(log_event::benches);
+criterion_main!(
+    immutable_event::benches,
+    log_event::benches,
+    metric::benches
+);
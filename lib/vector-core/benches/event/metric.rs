@@ -0,0 +1,64 @@
+use std::time::Duration;
+
+use criterion::{
+    criterion_group, measurement::WallTime, BatchSize, BenchmarkGroup, Criterion, SamplingMode,
+};
+use vector_core::event::{metric::MetricTags, Metric, MetricKind, MetricValue};
+
+fn tagged_metrics() -> Vec<Metric> {
+    (0..10_000)
+        .map(|i| {
+            let tags = (0..16)
+                .map(|tag| (format!("tag_{}", tag), format!("value_{}_{}", tag, i)))
+                .collect::<MetricTags>();
+            Metric::new(
+                format!("metric_{}", i % 100),
+                MetricKind::Incremental,
+                MetricValue::Counter { value: 1.0 },
+            )
+            .with_namespace(Some("vector"))
+            .with_tags(Some(tags))
+        })
+        .collect()
+}
+
+fn clone_then(c: &mut Criterion) {
+    let mut group: BenchmarkGroup<WallTime> =
+        c.benchmark_group("vector_core::event::metric::Metric::clone");
+    group.sampling_mode(SamplingMode::Auto);
+
+    let metrics = tagged_metrics();
+
+    group.bench_function("clone 10k tagged metrics", |b| b.iter(|| metrics.clone()));
+
+    group.bench_function("clone 10k tagged metrics then write", |b| {
+        b.iter_batched(
+            || metrics.clone(),
+            |mut clones| {
+                for metric in &mut clones {
+                    metric.insert_tag("tag_0".into(), "changed".into());
+                }
+                clones
+            },
+            BatchSize::LargeInput,
+        )
+    });
+}
+
+criterion_group!(
+    name = benches;
+    config = Criterion::default()
+        .warm_up_time(Duration::from_secs(5))
+        .measurement_time(Duration::from_secs(120))
+        // degree of noise to ignore in measurements, here 1%
+        .noise_threshold(0.01)
+        // likelihood of noise registering as difference, here 5%
+        .significance_level(0.05)
+        // likelihood of capturing the true runtime, here 95%
+        .confidence_level(0.95)
+        // total number of bootstrap resamples, higher is less noisy but slower
+        .nresamples(100_000)
+        // total samples to collect within the set measurement time
+        .sample_size(150);
+    targets = clone_then
+);
@@ -0,0 +1,98 @@
+use std::convert::TryFrom;
+
+use criterion::{
+    criterion_group, criterion_main,
+    measurement::{Measurement, WallTime},
+    BenchmarkGroup, Criterion, SamplingMode,
+};
+use serde_json::json;
+use vector_core::event::LogEvent;
+
+use self::allocations::Allocations;
+
+mod allocations;
+
+/// A nested document of about 4 KiB, as a source may receive.
+fn document() -> Vec<u8> {
+    let mut spans = Vec::new();
+    let mut document = Vec::new();
+    while document.len() < 4096 {
+        let id = spans.len();
+        spans.push(json!({
+            "id": id,
+            "name": format!("span-{}", id),
+            "duration_ms": id as f64 * 1.5,
+            "error": id % 7 == 0,
+            "attributes": {
+                "http": { "method": "GET", "status": 200, "path": "/api/v1/items" },
+                "tags": ["alpha", "beta", "gamma"],
+            },
+        }));
+        document = serde_json::to_vec(&json!({
+            "message": "GET /api/v1/items 200",
+            "host": "edge-1.example.com",
+            "timestamp": "2021-12-01T00:00:00.123456789Z",
+            "spans": spans,
+        }))
+        .unwrap();
+    }
+    document
+}
+
+fn from_json<M: Measurement>(group: &mut BenchmarkGroup<M>) {
+    let document = document();
+
+    group.bench_function("serde_json::Value then LogEvent::try_from", |b| {
+        b.iter(|| {
+            let value: serde_json::Value = serde_json::from_slice(&document).unwrap();
+            LogEvent::try_from(value).unwrap()
+        })
+    });
+
+    group.bench_function("LogEvent::from_json_slice", |b| {
+        b.iter(|| LogEvent::from_json_slice(&document).unwrap())
+    });
+
+    group.bench_function("LogEvent::from_json_reader", |b| {
+        b.iter(|| LogEvent::from_json_reader(document.as_slice()).unwrap())
+    });
+}
+
+fn from_json_wall_time(c: &mut Criterion) {
+    let mut group: BenchmarkGroup<WallTime> =
+        c.benchmark_group("vector_core::event::log_event::LogEvent::from_json");
+    group.sampling_mode(SamplingMode::Auto);
+    from_json(&mut group);
+}
+
+fn from_json_allocations(c: &mut Criterion<Allocations>) {
+    let mut group =
+        c.benchmark_group("vector_core::event::log_event::LogEvent::from_json (allocations)");
+    group.sampling_mode(SamplingMode::Flat);
+    from_json(&mut group);
+}
+
+criterion_group!(
+    name = wall_time;
+    config = Criterion::default()
+        // degree of noise to ignore in measurements, here 1%
+        .noise_threshold(0.01)
+        // likelihood of noise registering as difference, here 5%
+        .significance_level(0.05)
+        // likelihood of capturing the true runtime, here 95%
+        .confidence_level(0.95)
+        // total number of bootstrap resamples, higher is less noisy but slower
+        .nresamples(100_000)
+        // total samples to collect within the set measurement time
+        .sample_size(150);
+    targets = from_json_wall_time
+);
+
+criterion_group!(
+    name = allocations;
+    // allocation counts don't vary between runs, so few samples are needed
+    config = Criterion::default().with_measurement(Allocations).sample_size(10);
+    targets = from_json_allocations
+);
+
+criterion_main!(wall_time, allocations);
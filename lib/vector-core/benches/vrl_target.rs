@@ -0,0 +1,121 @@
+use std::{collections::BTreeMap, time::Duration};
+
+use criterion::{
+    criterion_group, criterion_main,
+    measurement::{Measurement, WallTime},
+    BatchSize, BenchmarkGroup, Criterion, SamplingMode,
+};
+use lookup::LookupBuf;
+use vector_core::event::{Event, LogEvent, Value, VrlTarget};
+use vrl_core::Target;
+
+use self::allocations::Allocations;
+
+mod allocations;
+
+/// A log event of 100 fields holding about 1 KiB of bytes between them.
+fn log_event() -> LogEvent {
+    let mut log = LogEvent::default();
+    for i in 0..100 {
+        log.insert(format!("field_{}", i), format!("value_{:04}", i));
+    }
+    log
+}
+
+fn get_root(c: &mut Criterion) {
+    let mut group: BenchmarkGroup<WallTime> =
+        c.benchmark_group("vector_core::event::vrl_target::VrlTarget::get");
+    group.sampling_mode(SamplingMode::Auto);
+
+    let log = log_event();
+    let target = VrlTarget::new(Event::Log(log.clone()));
+    let root = LookupBuf::root();
+
+    group.bench_function("get . of 100 fields", |b| {
+        b.iter(|| target.get(&root).unwrap())
+    });
+
+    // The conversion `get` used to do, cloning the fields before converting them.
+    group.bench_function("clone then convert 100 fields", |b| {
+        b.iter(|| {
+            let fields: BTreeMap<String, Value> = log.clone().into();
+            vrl_core::Value::from(Value::Map(fields))
+        })
+    });
+}
+
+fn insert_nested<M: Measurement>(group: &mut BenchmarkGroup<M>) {
+    let log = log_event();
+    let paths = ["a.b.c", "a.b.d", "e.f.g"]
+        .iter()
+        .map(|path| LookupBuf::from_str(path).unwrap())
+        .collect::<Vec<_>>();
+
+    group.bench_function("insert 3 nested fields", |b| {
+        b.iter_batched(
+            || VrlTarget::new(Event::Log(log.clone())),
+            |mut target| {
+                for path in &paths {
+                    target.insert(path, vrl_core::Value::from(1)).unwrap();
+                }
+                target
+            },
+            BatchSize::SmallInput,
+        )
+    });
+
+    // The insert the target used to do, cloning each path before walking it.
+    group.bench_function("clone then insert 3 nested fields", |b| {
+        b.iter_batched(
+            || Value::Map(log.clone().into()),
+            |mut value| {
+                for path in &paths {
+                    value.insert(path.clone(), 1).unwrap();
+                }
+                value
+            },
+            BatchSize::SmallInput,
+        )
+    });
+}
+
+fn insert_nested_wall_time(c: &mut Criterion) {
+    let mut group: BenchmarkGroup<WallTime> =
+        c.benchmark_group("vector_core::event::vrl_target::VrlTarget::insert");
+    group.sampling_mode(SamplingMode::Auto);
+    insert_nested(&mut group);
+}
+
+fn insert_nested_allocations(c: &mut Criterion<Allocations>) {
+    let mut group =
+        c.benchmark_group("vector_core::event::vrl_target::VrlTarget::insert (allocations)");
+    group.sampling_mode(SamplingMode::Flat);
+    insert_nested(&mut group);
+}
+
+criterion_group!(
+    name = benches;
+    config = Criterion::default()
+        .warm_up_time(Duration::from_secs(5))
+        .measurement_time(Duration::from_secs(30))
+        // degree of noise to ignore in measurements, here 1%
+        .noise_threshold(0.01)
+        // likelihood of noise registering as difference, here 5%
+        .significance_level(0.05)
+        // likelihood of capturing the true runtime, here 95%
+        .confidence_level(0.95)
+        // total number of bootstrap resamples, higher is less noisy but slower
+        .nresamples(100_000)
+        // total samples to collect within the set measurement time
+        .sample_size(150);
+    targets = get_root, insert_nested_wall_time
+);
+
+criterion_group!(
+    name = allocations;
+    // allocation counts don't vary between runs, so few samples are needed
+    config = Criterion::default().with_measurement(Allocations).sample_size(10);
+    targets = insert_nested_allocations
+);
+
+criterion_main!(benches, allocations);
@@ -3,6 +3,9 @@ fn main() {
     let _ = prost_build::Config::new()
         .btree_map(&["."])
         .bytes(&["raw_bytes"])
+        // Written by hand in `src/event/proto.rs`, so that they keep the fields they don't know.
+        .extern_path(".event.EventWrapper", "crate::event::proto::EventWrapper")
+        .extern_path(".event.Log", "crate::event::proto::Log")
         .compile_protos(&["proto/event.proto"], &["proto/"])
         .unwrap();
 }
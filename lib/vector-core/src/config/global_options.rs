@@ -4,7 +4,7 @@ use serde::{Deserialize, Serialize};
 use shared::TimeZone;
 use snafu::{ResultExt, Snafu};
 
-use crate::config::{proxy::ProxyConfig, LogSchema};
+use crate::config::{proxy::ProxyConfig, LogSchema, Redaction, Telemetry};
 
 #[derive(Debug, Snafu)]
 pub enum DataDirError {
@@ -38,6 +38,10 @@ pub struct GlobalOptions {
     pub timezone: TimeZone,
     #[serde(skip_serializing_if = "crate::serde::skip_serializing_if_default")]
     pub proxy: ProxyConfig,
+    #[serde(skip_serializing_if = "crate::serde::skip_serializing_if_default")]
+    pub telemetry: Telemetry,
+    #[serde(skip_serializing_if = "crate::serde::skip_serializing_if_default")]
+    pub redaction: Redaction,
     #[serde(skip)]
     pub enterprise: bool,
 }
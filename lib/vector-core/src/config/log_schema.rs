@@ -1,6 +1,12 @@
+use std::convert::TryFrom;
+
+use chrono::{DateTime, FixedOffset, Utc};
 use getset::{Getters, Setters};
 use once_cell::sync::OnceCell;
 use serde::{Deserialize, Serialize};
+use shared::TimeZone;
+
+use crate::event::{PathComponent, PathIter};
 
 static LOG_SCHEMA: OnceCell<LogSchema> = OnceCell::new();
 
@@ -50,6 +56,128 @@ pub struct LogSchema {
     source_type_key: String,
     #[serde(default = "LogSchema::default_metadata_key")]
     metadata_key: String,
+    #[serde(default)]
+    timestamp_timezone: TimestampTimezone,
+    #[serde(default = "LogSchema::default_timestamp_offset_key")]
+    timestamp_offset_key: String,
+}
+
+/// The keys a component sets in place of those of the `LogSchema` it is
+/// given, such as the `log_schema` option of a source. Keys left unset keep
+/// their value in that schema.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct LogSchemaOverrides {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    message_key: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    timestamp_key: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    host_key: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    source_type_key: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    metadata_key: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    timestamp_timezone: Option<TimestampTimezone>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    timestamp_offset_key: Option<String>,
+}
+
+impl LogSchemaOverrides {
+    /// The given schema, with the keys set here replacing its own.
+    #[must_use]
+    pub fn apply(&self, base: &LogSchema) -> LogSchema {
+        let mut schema = base.clone();
+        if let Some(key) = &self.message_key {
+            schema.set_message_key(key.clone());
+        }
+        if let Some(key) = &self.timestamp_key {
+            schema.set_timestamp_key(key.clone());
+        }
+        if let Some(key) = &self.host_key {
+            schema.set_host_key(key.clone());
+        }
+        if let Some(key) = &self.source_type_key {
+            schema.set_source_type_key(key.clone());
+        }
+        if let Some(key) = &self.metadata_key {
+            schema.set_metadata_key(key.clone());
+        }
+        if let Some(timezone) = self.timestamp_timezone {
+            schema.set_timestamp_timezone(timezone);
+        }
+        if let Some(key) = &self.timestamp_offset_key {
+            schema.set_timestamp_offset_key(key.clone());
+        }
+        schema
+    }
+
+    /// Validate that every key set here is a well-formed field path.
+    ///
+    /// # Errors
+    ///
+    /// This function will fail when any key can't be parsed as a path.
+    pub fn validate(&self) -> Result<(), Vec<String>> {
+        // Unset keys take the defaults, which are always valid paths.
+        self.apply(&LOG_SCHEMA_DEFAULT).validate()
+    }
+}
+
+/// The time zone the time an event was created at is recorded in, by the
+/// `timestamp_timezone` option of the `LogSchema`.
+///
+/// The timestamp itself is always stored in UTC, so that it compares and
+/// converts the same whatever the time zone. For any other time zone than
+/// UTC, the offset from UTC in that time zone at that time is stored next to
+/// it, as a string such as `+02:00`, at the `timestamp_offset_key` of the
+/// `LogSchema`. It is stored on the log events a source emits with a
+/// timestamp, by the schema of that source.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Deserialize, Serialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum TimestampTimezone {
+    Utc,
+    /// A time zone of the TZ database, or the local time zone of the system.
+    Zone(TimeZone),
+}
+
+impl TimestampTimezone {
+    /// The offset to record with a timestamp of the given time, if any.
+    pub fn offset_at(self, at: DateTime<Utc>) -> Option<FixedOffset> {
+        match self {
+            Self::Utc => None,
+            Self::Zone(timezone) => Some(timezone.offset_at(at)),
+        }
+    }
+}
+
+impl Default for TimestampTimezone {
+    fn default() -> Self {
+        Self::Utc
+    }
+}
+
+impl TryFrom<String> for TimestampTimezone {
+    type Error = String;
+
+    fn try_from(name: String) -> Result<Self, Self::Error> {
+        if name.eq_ignore_ascii_case("utc") {
+            return Ok(Self::Utc);
+        }
+        TimeZone::parse(&name)
+            .map(Self::Zone)
+            .ok_or_else(|| format!("No such time zone: {:?}", name))
+    }
+}
+
+impl From<TimestampTimezone> for String {
+    fn from(timezone: TimestampTimezone) -> Self {
+        match timezone {
+            TimestampTimezone::Utc => "utc".to_owned(),
+            TimestampTimezone::Zone(TimeZone::Local) => "local".to_owned(),
+            TimestampTimezone::Zone(TimeZone::Named(tz)) => tz.name().to_owned(),
+        }
+    }
 }
 
 impl Default for LogSchema {
@@ -60,6 +188,8 @@ impl Default for LogSchema {
             host_key: Self::default_host_key(),
             source_type_key: Self::default_source_type_key(),
             metadata_key: Self::default_metadata_key(),
+            timestamp_timezone: TimestampTimezone::default(),
+            timestamp_offset_key: Self::default_timestamp_offset_key(),
         }
     }
 }
@@ -85,6 +215,10 @@ impl LogSchema {
         String::from("metadata")
     }
 
+    fn default_timestamp_offset_key() -> String {
+        String::from("timestamp_offset")
+    }
+
     pub fn message_key(&self) -> &str {
         &self.message_key
     }
@@ -105,6 +239,14 @@ impl LogSchema {
         &self.metadata_key
     }
 
+    pub const fn timestamp_timezone(&self) -> TimestampTimezone {
+        self.timestamp_timezone
+    }
+
+    pub fn timestamp_offset_key(&self) -> &str {
+        &self.timestamp_offset_key
+    }
+
     pub fn set_message_key(&mut self, v: String) {
         self.message_key = v;
     }
@@ -125,6 +267,43 @@ impl LogSchema {
         self.metadata_key = v;
     }
 
+    pub fn set_timestamp_timezone(&mut self, v: TimestampTimezone) {
+        self.timestamp_timezone = v;
+    }
+
+    pub fn set_timestamp_offset_key(&mut self, v: String) {
+        self.timestamp_offset_key = v;
+    }
+
+    /// Validate that every configured key is a well-formed field path.
+    ///
+    /// Schema keys are inserted with path-aware APIs, so a key such as
+    /// `event.message` refers to the `message` field nested inside `event`.
+    ///
+    /// # Errors
+    ///
+    /// This function will fail when any key can't be parsed as a path.
+    pub fn validate(&self) -> Result<(), Vec<String>> {
+        let errors: Vec<_> = [
+            ("host_key", self.host_key()),
+            ("message_key", self.message_key()),
+            ("timestamp_key", self.timestamp_key()),
+            ("source_type_key", self.source_type_key()),
+            ("metadata_key", self.metadata_key()),
+            ("timestamp_offset_key", self.timestamp_offset_key()),
+        ]
+        .iter()
+        .filter(|(_, key)| PathIter::new(key).any(|c| c == PathComponent::Invalid))
+        .map(|(name, key)| format!("invalid path {:?} for 'log_schema.{}'", key, name))
+        .collect();
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
     /// Merge two `LogSchema` instances together.
     ///
     /// # Errors
@@ -171,6 +350,24 @@ impl LogSchema {
             } else {
                 self.set_metadata_key(other.metadata_key().to_string());
             }
+            if self.timestamp_timezone() != LOG_SCHEMA_DEFAULT.timestamp_timezone()
+                && self.timestamp_timezone() != other.timestamp_timezone()
+            {
+                errors.push(
+                    "conflicting values for 'log_schema.timestamp_timezone' found".to_owned(),
+                );
+            } else {
+                self.set_timestamp_timezone(other.timestamp_timezone());
+            }
+            if self.timestamp_offset_key() != LOG_SCHEMA_DEFAULT.timestamp_offset_key()
+                && self.timestamp_offset_key() != other.timestamp_offset_key()
+            {
+                errors.push(
+                    "conflicting values for 'log_schema.timestamp_offset_key' found".to_owned(),
+                );
+            } else {
+                self.set_timestamp_offset_key(other.timestamp_offset_key().to_string());
+            }
         }
 
         if errors.is_empty() {
@@ -193,4 +390,72 @@ mod test {
         "#;
         toml::from_str::<LogSchema>(toml).unwrap();
     }
+
+    #[test]
+    fn overrides_merge_with_base() {
+        let mut base = LogSchema::default();
+        base.set_message_key("msg".to_string());
+        base.set_host_key("origin".to_string());
+
+        let overrides = toml::from_str::<LogSchemaOverrides>(r#"timestamp_key = "ts""#).unwrap();
+        let schema = overrides.apply(&base);
+
+        assert_eq!(schema.timestamp_key(), "ts");
+        assert_eq!(schema.message_key(), "msg");
+        assert_eq!(schema.host_key(), "origin");
+        assert_eq!(schema.source_type_key(), base.source_type_key());
+
+        assert_eq!(LogSchemaOverrides::default().apply(&base), base);
+        assert!(toml::from_str::<LogSchemaOverrides>(r#"msg_key = "msg""#).is_err());
+    }
+
+    #[test]
+    fn timestamp_timezone() {
+        let schema = toml::from_str::<LogSchema>("").unwrap();
+        assert_eq!(schema.timestamp_timezone(), TimestampTimezone::Utc);
+
+        for (name, timezone) in [
+            ("UTC", TimestampTimezone::Utc),
+            ("local", TimestampTimezone::Zone(TimeZone::Local)),
+            (
+                "Europe/Berlin",
+                TimestampTimezone::Zone(TimeZone::parse("Europe/Berlin").unwrap()),
+            ),
+        ] {
+            let toml = format!("timestamp_timezone = {:?}", name);
+            let schema = toml::from_str::<LogSchema>(&toml).unwrap();
+            assert_eq!(schema.timestamp_timezone(), timezone);
+        }
+
+        let error = toml::from_str::<LogSchema>(r#"timestamp_timezone = "Mars/Olympus""#)
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("No such time zone"), "{}", error);
+    }
+
+    #[test]
+    fn validate_nested_keys() {
+        let toml = r#"
+            message_key = "event.message"
+            timestamp_key = "event.times[0]"
+        "#;
+        let schema = toml::from_str::<LogSchema>(toml).unwrap();
+        assert_eq!(schema.validate(), Ok(()));
+    }
+
+    #[test]
+    fn validate_invalid_keys() {
+        let toml = r#"
+            message_key = "event..message"
+            host_key = "[0]"
+        "#;
+        let schema = toml::from_str::<LogSchema>(toml).unwrap();
+        assert_eq!(
+            schema.validate(),
+            Err(vec![
+                r#"invalid path "[0]" for 'log_schema.host_key'"#.to_owned(),
+                r#"invalid path "event..message" for 'log_schema.message_key'"#.to_owned(),
+            ])
+        );
+    }
 }
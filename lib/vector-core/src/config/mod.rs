@@ -2,7 +2,13 @@ mod global_options;
 mod id;
 mod log_schema;
 pub mod proxy;
+mod redaction;
+mod telemetry;
 
 pub use global_options::GlobalOptions;
 pub use id::ComponentKey;
-pub use log_schema::{init_log_schema, log_schema, LogSchema};
+pub use log_schema::{
+    init_log_schema, log_schema, LogSchema, LogSchemaOverrides, TimestampTimezone,
+};
+pub use redaction::{sensitive_paths, Redaction, SensitivePath, SensitivePathError};
+pub use telemetry::{event_ids_enabled, Telemetry};
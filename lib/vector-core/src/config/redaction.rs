@@ -0,0 +1,187 @@
+use std::{
+    convert::TryFrom,
+    sync::{Arc, RwLock},
+};
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use snafu::Snafu;
+
+static SENSITIVE_PATHS: Lazy<RwLock<Arc<Vec<SensitivePath>>>> = Lazy::new(Default::default);
+
+#[derive(Debug, PartialEq, Snafu)]
+pub enum SensitivePathError {
+    #[snafu(display("Sensitive path {:?} has an empty segment.", path))]
+    EmptySegment { path: String },
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+enum Segment {
+    Key(String),
+    /// `*`, any one key.
+    AnyKey,
+    /// `**`, any number of keys, including none.
+    AnyKeys,
+}
+
+/// A pattern of paths of log event fields holding secrets, such as `user.password`. A segment can
+/// be `*` to match any one key, or `**` to match any number of keys, so `**.password` matches a
+/// `password` key at any depth. Array indices are passed over, so `tokens.value` matches
+/// `tokens[0].value`, and keys holding a dot can't be matched.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct SensitivePath {
+    path: String,
+    segments: Vec<Segment>,
+}
+
+impl SensitivePath {
+    /// Whether the field at the path made of `keys` matches this pattern.
+    pub fn matches(&self, keys: &[&str]) -> bool {
+        matches_keys(&self.segments, keys)
+    }
+}
+
+fn matches_keys(segments: &[Segment], keys: &[&str]) -> bool {
+    match (segments.split_first(), keys.split_first()) {
+        (None, None) => true,
+        (Some((Segment::AnyKeys, rest)), _) => {
+            matches_keys(rest, keys) || (!keys.is_empty() && matches_keys(segments, &keys[1..]))
+        }
+        (Some((Segment::AnyKey, rest)), Some((_, keys))) => matches_keys(rest, keys),
+        (Some((Segment::Key(segment), rest)), Some((key, keys))) => {
+            segment == key && matches_keys(rest, keys)
+        }
+        _ => false,
+    }
+}
+
+impl TryFrom<String> for SensitivePath {
+    type Error = SensitivePathError;
+
+    fn try_from(path: String) -> Result<Self, Self::Error> {
+        let segments = path
+            .split('.')
+            .map(|segment| match segment {
+                "" => Err(SensitivePathError::EmptySegment { path: path.clone() }),
+                "*" => Ok(Segment::AnyKey),
+                "**" => Ok(Segment::AnyKeys),
+                key => Ok(Segment::Key(key.to_owned())),
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self { path, segments })
+    }
+}
+
+impl From<SensitivePath> for String {
+    fn from(path: SensitivePath) -> Self {
+        path.path
+    }
+}
+
+/// Keeps secrets out of the internal logs that show events.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct Redaction {
+    /// The fields whose values are shown as `"[REDACTED]"` in the debug output of log events,
+    /// such as in trace level logs and in errors about an event.
+    pub sensitive_paths: Vec<SensitivePath>,
+}
+
+impl Redaction {
+    /// Merge in the settings from another config file. The paths of both are sensitive.
+    pub fn merge(&mut self, other: &Self) {
+        for path in &other.sensitive_paths {
+            if !self.sensitive_paths.contains(path) {
+                self.sensitive_paths.push(path.clone());
+            }
+        }
+    }
+
+    /// Make these settings take effect for the whole process.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a thread panicked while holding the sensitive paths.
+    pub fn apply(&self) {
+        *SENSITIVE_PATHS
+            .write()
+            .expect("sensitive paths lock poisoned") = Arc::new(self.sensitive_paths.clone());
+    }
+}
+
+/// The paths whose values are left out of the debug output of log events.
+///
+/// # Panics
+///
+/// Panics if a thread panicked while holding the sensitive paths.
+pub fn sensitive_paths() -> Arc<Vec<SensitivePath>> {
+    Arc::clone(
+        &SENSITIVE_PATHS
+            .read()
+            .expect("sensitive paths lock poisoned"),
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn path(path: &str) -> SensitivePath {
+        SensitivePath::try_from(path.to_owned()).unwrap()
+    }
+
+    #[test]
+    fn patterns_match_paths() {
+        let cases = [
+            ("password", vec!["password"], true),
+            ("password", vec!["user", "password"], false),
+            ("user.password", vec!["user", "password"], true),
+            ("user.password", vec!["user"], false),
+            ("*.password", vec!["user", "password"], true),
+            ("*.password", vec!["password"], false),
+            ("**.password", vec!["password"], true),
+            ("**.password", vec!["a", "b", "password"], true),
+            ("**.password", vec!["a", "password", "b"], false),
+            ("headers.**", vec!["headers"], true),
+            ("headers.**", vec!["headers", "authorization"], true),
+            ("a.**.token", vec!["a", "b", "c", "token"], true),
+            ("a.**.token", vec!["b", "token"], false),
+        ];
+        for (pattern, keys, expected) in cases {
+            assert_eq!(
+                path(pattern).matches(&keys),
+                expected,
+                "{} {:?}",
+                pattern,
+                keys
+            );
+        }
+    }
+
+    #[test]
+    fn empty_segments_are_rejected() {
+        for pattern in ["", "a..b", "a.", ".a"] {
+            assert_eq!(
+                SensitivePath::try_from(pattern.to_owned()),
+                Err(SensitivePathError::EmptySegment {
+                    path: pattern.to_owned()
+                })
+            );
+        }
+    }
+
+    #[test]
+    fn merge_keeps_the_paths_of_both() {
+        let mut redaction = Redaction {
+            sensitive_paths: vec![path("password"), path("token")],
+        };
+        redaction.merge(&Redaction {
+            sensitive_paths: vec![path("token"), path("**.secret")],
+        });
+        assert_eq!(
+            redaction.sensitive_paths,
+            vec![path("password"), path("token"), path("**.secret")]
+        );
+    }
+}
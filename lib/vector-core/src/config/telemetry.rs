@@ -0,0 +1,36 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use serde::{Deserialize, Serialize};
+
+static EVENT_IDS: AtomicBool = AtomicBool::new(false);
+
+/// Controls optional internal telemetry that is too costly to collect by default.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct Telemetry {
+    /// Record a histogram of the size of events flowing through components. Components read it
+    /// from the global options they are built with.
+    pub event_size_histograms: bool,
+    /// Mint an id for each log event created from raw bytes, which follows it to the Vectors it
+    /// is sent to.
+    pub event_ids: bool,
+}
+
+impl Telemetry {
+    /// Merge in the settings from another config file. Options are only ever
+    /// switched on, so the files can't conflict.
+    pub fn merge(&mut self, other: &Self) {
+        self.event_size_histograms |= other.event_size_histograms;
+        self.event_ids |= other.event_ids;
+    }
+
+    /// Make these settings take effect for the whole process.
+    pub fn apply(&self) {
+        EVENT_IDS.store(self.event_ids, Ordering::Relaxed);
+    }
+}
+
+/// Whether an id should be minted for each log event created from raw bytes.
+pub fn event_ids_enabled() -> bool {
+    EVENT_IDS.load(Ordering::Relaxed)
+}
@@ -0,0 +1,321 @@
+//! The canonical JSON form of a log event, for consumers that compare serialized events byte for
+//! byte. It doesn't depend on the order of maps or on how `serde_json` formats values, and is:
+//!
+//! * Keys sorted lexicographically by their UTF-8 bytes, in every map.
+//! * No whitespace outside of strings.
+//! * Strings with only `"`, `\` and control characters escaped. `\b`, `\f`, `\n`, `\r` and `\t`
+//!   use their short escapes, other control characters `\u00xx` with lowercase hex digits.
+//!   Everything else, including non-ASCII characters, is written as is.
+//! * Bytes that aren't valid UTF-8 as a string of their standard, padded base64 encoding.
+//! * Integers in decimal.
+//! * Floats in the shortest form that reads back as the same float, as written by `ryu`: always
+//!   with a fraction or exponent, such as `1.0`, `0.001` or `1e300`. NaN and infinities, which
+//!   JSON can't represent, are written as `null`.
+//! * Timestamps as RFC 3339 strings in UTC with nanosecond precision, such as
+//!   `2021-01-01T00:00:00.000000000Z`.
+
+use std::{collections::BTreeMap, convert::Infallible};
+
+use bytes::Bytes;
+use chrono::{DateTime, SecondsFormat, Utc};
+
+use super::{visit::walk_map, Value, ValueVisitor};
+
+pub(super) fn write_value(value: &Value, out: &mut Vec<u8>) {
+    value
+        .walk(&mut Writer::new(out))
+        .unwrap_or_else(|never| match never {});
+}
+
+pub(super) fn write_map(map: &BTreeMap<String, Value>, out: &mut Vec<u8>) {
+    walk_map(map, &mut Writer::new(out)).unwrap_or_else(|never| match never {});
+}
+
+/// An array or map being written.
+struct Level {
+    is_map: bool,
+    /// Nothing was written in it yet, so its next element or entry needs no comma before it.
+    empty: bool,
+}
+
+/// Writes the values it visits in the canonical form. Keys are written in the order they are
+/// visited, which is the order of their bytes.
+struct Writer<'a> {
+    out: &'a mut Vec<u8>,
+    /// The arrays and maps being written, outermost first.
+    levels: Vec<Level>,
+}
+
+impl<'a> Writer<'a> {
+    fn new(out: &'a mut Vec<u8>) -> Self {
+        Self {
+            out,
+            levels: Vec::new(),
+        }
+    }
+
+    /// Write the comma separating the next element or entry of the current array or map from the
+    /// previous one, if any.
+    fn separate(&mut self) {
+        if let Some(level) = self.levels.last_mut() {
+            if !std::mem::replace(&mut level.empty, false) {
+                self.out.push(b',');
+            }
+        }
+    }
+
+    /// Start a value. The values of maps are separated along with their key instead.
+    fn start_value(&mut self) {
+        if !self.levels.last().map_or(false, |level| level.is_map) {
+            self.separate();
+        }
+    }
+
+    fn enter(&mut self, is_map: bool, open: u8) {
+        self.start_value();
+        self.out.push(open);
+        self.levels.push(Level {
+            is_map,
+            empty: true,
+        });
+    }
+
+    fn leave(&mut self, close: u8) {
+        self.levels.pop();
+        self.out.push(close);
+    }
+}
+
+impl<'a> ValueVisitor for Writer<'a> {
+    type Error = Infallible;
+
+    fn bytes(&mut self, bytes: &Bytes) -> Result<(), Infallible> {
+        self.start_value();
+        match std::str::from_utf8(bytes) {
+            Ok(string) => write_string(string, self.out),
+            Err(_) => write_string(&base64::encode(bytes), self.out),
+        }
+        Ok(())
+    }
+
+    fn integer(&mut self, integer: i64) -> Result<(), Infallible> {
+        self.start_value();
+        self.out.extend_from_slice(integer.to_string().as_bytes());
+        Ok(())
+    }
+
+    fn float(&mut self, float: f64) -> Result<(), Infallible> {
+        self.start_value();
+        if float.is_finite() {
+            self.out
+                .extend_from_slice(ryu::Buffer::new().format_finite(float).as_bytes());
+        } else {
+            self.out.extend_from_slice(b"null");
+        }
+        Ok(())
+    }
+
+    fn boolean(&mut self, boolean: bool) -> Result<(), Infallible> {
+        self.start_value();
+        self.out
+            .extend_from_slice(if boolean { b"true" } else { b"false" });
+        Ok(())
+    }
+
+    fn timestamp(&mut self, timestamp: &DateTime<Utc>) -> Result<(), Infallible> {
+        self.start_value();
+        write_string(
+            &timestamp.to_rfc3339_opts(SecondsFormat::Nanos, true),
+            self.out,
+        );
+        Ok(())
+    }
+
+    fn null(&mut self) -> Result<(), Infallible> {
+        self.start_value();
+        self.out.extend_from_slice(b"null");
+        Ok(())
+    }
+
+    fn enter_map(&mut self, _len: usize) -> Result<(), Infallible> {
+        self.enter(true, b'{');
+        Ok(())
+    }
+
+    fn key(&mut self, key: &str) -> Result<(), Infallible> {
+        self.separate();
+        write_string(key, self.out);
+        self.out.push(b':');
+        Ok(())
+    }
+
+    fn leave_map(&mut self) -> Result<(), Infallible> {
+        self.leave(b'}');
+        Ok(())
+    }
+
+    fn enter_array(&mut self, _len: usize) -> Result<(), Infallible> {
+        self.enter(false, b'[');
+        Ok(())
+    }
+
+    fn leave_array(&mut self) -> Result<(), Infallible> {
+        self.leave(b']');
+        Ok(())
+    }
+}
+
+fn write_string(string: &str, out: &mut Vec<u8>) {
+    const HEX: &[u8; 16] = b"0123456789abcdef";
+
+    out.push(b'"');
+    for byte in string.bytes() {
+        match byte {
+            b'"' => out.extend_from_slice(b"\\\""),
+            b'\\' => out.extend_from_slice(b"\\\\"),
+            b'\x08' => out.extend_from_slice(b"\\b"),
+            b'\x0c' => out.extend_from_slice(b"\\f"),
+            b'\n' => out.extend_from_slice(b"\\n"),
+            b'\r' => out.extend_from_slice(b"\\r"),
+            b'\t' => out.extend_from_slice(b"\\t"),
+            0x00..=0x1f => {
+                out.extend_from_slice(b"\\u00");
+                out.push(HEX[usize::from(byte >> 4)]);
+                out.push(HEX[usize::from(byte & 0xf)]);
+            }
+            // Multi-byte characters never contain bytes below 0x80, so they are copied unchanged.
+            byte => out.push(byte),
+        }
+    }
+    out.push(b'"');
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        collections::BTreeSet,
+        convert::TryFrom,
+        path::{Path, PathBuf},
+    };
+
+    use bytes::Bytes;
+    use chrono::{TimeZone, Utc};
+    use shared::btreemap;
+
+    use super::*;
+    use crate::{
+        event::LogEvent,
+        test_util::{open_fixture, open_fixture_ndjson},
+    };
+
+    const FIXTURE_ROOT: &str = "tests/data/fixtures/log_event";
+    const GOLDEN_ROOT: &str = "tests/data/fixtures/canonical_json";
+
+    fn canonical(value: impl Into<Value>) -> String {
+        let mut out = Vec::new();
+        write_value(&value.into(), &mut out);
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn scalars() {
+        let cases = vec![
+            (Value::from("plain"), r#""plain""#),
+            (
+                Value::from("quote \" backslash \\ \n\t\r\x08\x0c \x01\x1f\x7f é"),
+                "\"quote \\\" backslash \\\\ \\n\\t\\r\\b\\f \\u0001\\u001f\x7f é\"",
+            ),
+            (Value::Bytes(Bytes::from_static(b"\xff\xfe")), r#""//4=""#),
+            (Value::from(-42), "-42"),
+            (Value::from(1.0), "1.0"),
+            (Value::from(0.1), "0.1"),
+            (Value::from(1e300), "1e300"),
+            (Value::from(-2.5e-8), "-2.5e-8"),
+            (Value::from(f64::NAN), "null"),
+            (Value::from(f64::NEG_INFINITY), "null"),
+            (Value::from(true), "true"),
+            (Value::Null, "null"),
+            (
+                Value::from(Utc.ymd(2021, 1, 1).and_hms(0, 0, 0)),
+                r#""2021-01-01T00:00:00.000000000Z""#,
+            ),
+            (
+                Value::from(Utc.ymd(2021, 1, 1).and_hms_nano(0, 0, 0, 123_456)),
+                r#""2021-01-01T00:00:00.000123456Z""#,
+            ),
+        ];
+
+        for (value, expected) in cases {
+            assert_eq!(canonical(value.clone()), expected, "{:?}", value);
+        }
+    }
+
+    #[test]
+    fn maps_are_sorted_by_bytes() {
+        let value: BTreeMap<String, Value> = btreemap! {
+            "b" => vec![Value::from(btreemap! { "z" => 1, "a" => 2 }), Value::Null],
+            "a" => Value::Map(BTreeMap::new()),
+            "B" => "upper",
+            "é" => 1,
+            "e" => 2,
+        };
+        assert_eq!(
+            canonical(value),
+            r#"{"B":"upper","a":{},"b":[{"a":2,"z":1},null],"e":2,"é":1}"#
+        );
+    }
+
+    fn golden_files(directory: &Path) -> BTreeSet<PathBuf> {
+        let mut files = BTreeSet::new();
+        for entry in std::fs::read_dir(directory).unwrap() {
+            let path = entry.unwrap().path();
+            if path.is_dir() {
+                files.extend(golden_files(&path));
+            } else {
+                files.insert(path);
+            }
+        }
+        files
+    }
+
+    fn golden_path(fixture: &Path) -> PathBuf {
+        Path::new(GOLDEN_ROOT).join(fixture.strip_prefix(FIXTURE_ROOT).unwrap())
+    }
+
+    fn assert_golden(fixture: &Path, events: Vec<serde_json::Value>) {
+        let mut output = String::new();
+        for event in events {
+            let event = LogEvent::try_from(event).unwrap();
+            output.push_str(std::str::from_utf8(&event.to_canonical_json()).unwrap());
+            output.push('\n');
+        }
+
+        let golden = golden_path(fixture);
+        let expected = std::fs::read_to_string(&golden)
+            .unwrap_or_else(|error| panic!("{}: {}", golden.display(), error));
+        assert_eq!(output, expected, "{}", fixture.display());
+    }
+
+    // Pins the canonical form of every fixture in `tests/data/fixtures/log_event` to the file at
+    // the same path under `tests/data/fixtures/canonical_json`, one event per line. A change that
+    // fails this test changes the output of every consumer of the canonical form.
+    #[test]
+    fn fixtures_match_golden_files() {
+        let mut checked = BTreeSet::new();
+        for fixture_file in std::fs::read_dir(FIXTURE_ROOT).unwrap() {
+            let path = fixture_file.unwrap().path();
+            if path.is_dir() {
+                for fixture_file in std::fs::read_dir(&path).unwrap() {
+                    let path = fixture_file.unwrap().path();
+                    assert_golden(&path, open_fixture_ndjson(&path).unwrap());
+                    checked.insert(golden_path(&path));
+                }
+            } else {
+                assert_golden(&path, vec![open_fixture(&path).unwrap()]);
+                checked.insert(golden_path(&path));
+            }
+        }
+        // Every golden file belongs to a fixture that was checked.
+        assert_eq!(checked, golden_files(Path::new(GOLDEN_ROOT)));
+    }
+}
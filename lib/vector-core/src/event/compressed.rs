@@ -0,0 +1,137 @@
+//! Field values compressed in place by `LogEvent::compress_field`.
+//!
+//! A compressed value is still a `Value::Bytes` holding nothing but the compressed data, so it
+//! travels through the topology and between Vectors like any other value. Which fields are
+//! compressed, and with what, is recorded next to them in the event metadata as a
+//! [`CompressedField`], never in the bytes themselves, so that no value can pass for a compressed
+//! one.
+
+use std::{
+    fmt,
+    io::{self, Read, Write},
+};
+
+use bytes::Bytes;
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use serde::{Deserialize, Serialize};
+
+/// The codec a field value is compressed with.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Ord, PartialEq, PartialOrd, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FieldCodec {
+    Gzip,
+    Zstd,
+}
+
+impl FieldCodec {
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Gzip => "gzip",
+            Self::Zstd => "zstd",
+        }
+    }
+}
+
+impl fmt::Display for FieldCodec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// How compressed field values are written by
+/// [`LogEvent::to_json_vec_with`](super::LogEvent::to_json_vec_with).
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CompressedJson {
+    /// As the value they were compressed from.
+    Decompress,
+    /// As the base64 encoding of their compressed bytes.
+    Base64,
+}
+
+/// A field compressed by `LogEvent::compress_field`, as recorded in the event metadata.
+///
+/// The record holds on to the compressed bytes it was made for, and only applies while the field
+/// still holds those very bytes: a value written over the field, even an identical one, is not
+/// compressed.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CompressedField {
+    codec: FieldCodec,
+    data: Bytes,
+}
+
+impl CompressedField {
+    pub(super) const fn new(codec: FieldCodec, data: Bytes) -> Self {
+        Self { codec, data }
+    }
+
+    /// The codec the field is compressed with.
+    pub const fn codec(&self) -> FieldCodec {
+        self.codec
+    }
+
+    /// Whether `bytes` are the compressed bytes this record was made for, rather than a value
+    /// that replaced them.
+    pub fn holds(&self, bytes: &Bytes) -> bool {
+        bytes.as_ptr() == self.data.as_ptr() && bytes.len() == self.data.len()
+    }
+}
+
+pub(super) fn compress(data: &[u8], codec: FieldCodec) -> io::Result<Bytes> {
+    let out = match codec {
+        FieldCodec::Gzip => {
+            let mut encoder =
+                GzEncoder::new(Vec::with_capacity(data.len() / 2), Compression::default());
+            encoder.write_all(data)?;
+            encoder.finish()?
+        }
+        FieldCodec::Zstd => zstd::stream::encode_all(data, 0)?,
+    };
+    Ok(out.into())
+}
+
+/// The most bytes a field value is decompressed to. Compressed values can come from another
+/// Vector, so they aren't trusted to expand to a sensible size.
+const MAX_DECOMPRESSED_BYTES: usize = 64 * 1024 * 1024;
+
+pub(super) fn decompress(data: &[u8], codec: FieldCodec) -> io::Result<Bytes> {
+    decompress_with_limit(data, codec, MAX_DECOMPRESSED_BYTES)
+}
+
+/// Decompress `data`, failing once it expands to more than `limit` bytes rather than reading it
+/// to the end.
+fn decompress_with_limit(data: &[u8], codec: FieldCodec, limit: usize) -> io::Result<Bytes> {
+    let mut out = Vec::with_capacity(data.len().saturating_mul(2).min(limit));
+    // One byte over the limit is enough to tell that it was exceeded.
+    let max = limit as u64 + 1;
+    match codec {
+        FieldCodec::Gzip => GzDecoder::new(data).take(max).read_to_end(&mut out)?,
+        FieldCodec::Zstd => zstd::stream::read::Decoder::new(data)?
+            .take(max)
+            .read_to_end(&mut out)?,
+    };
+    if out.len() > limit {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("decompressed value is larger than {} bytes", limit),
+        ));
+    }
+    Ok(out.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decompression_stops_at_the_limit() {
+        let data = vec![b'a'; 10_000];
+        for codec in [FieldCodec::Gzip, FieldCodec::Zstd] {
+            let compressed = compress(&data, codec).unwrap();
+            let error = decompress_with_limit(&compressed, codec, 9_999).unwrap_err();
+            assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+            let restored = decompress_with_limit(&compressed, codec, 10_000).unwrap();
+            assert_eq!(restored, data);
+        }
+    }
+}
@@ -1,7 +1,7 @@
 use lookup::LookupBuf;
 use snafu::Snafu;
 
-use crate::event::Value;
+use crate::event::{FieldCodec, Value, ValueKind};
 
 #[derive(Debug, Snafu)]
 pub enum EventError {
@@ -28,3 +28,115 @@ impl From<lookup::LookupError> for EventError {
         Self::LookupError { source: v }
     }
 }
+
+/// Why a field of a log event could not be compressed or decompressed in place.
+#[derive(Debug, Snafu)]
+pub enum CompressionError {
+    #[snafu(display("Field `{}` does not exist.", path))]
+    MissingValue { path: String },
+    #[snafu(display(
+        "Field `{}` is of kind {}, only strings can be compressed.",
+        path,
+        kind
+    ))]
+    NotBytes { path: String, kind: ValueKind },
+    #[snafu(display("Field `{}` is compressed with {} already.", path, codec))]
+    AlreadyCompressed { path: String, codec: FieldCodec },
+    #[snafu(display("Field `{}` is not compressed.", path))]
+    NotCompressed { path: String },
+    #[snafu(display("Field `{}` is compressed with {}, not {}.", path, actual, expected))]
+    WrongCodec {
+        path: String,
+        expected: FieldCodec,
+        actual: FieldCodec,
+    },
+    #[snafu(display("Could not {} field `{}`: {}", action, path, source))]
+    Codec {
+        path: String,
+        action: &'static str,
+        source: std::io::Error,
+    },
+}
+
+/// Why a log event was not built from the fields it was given.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Snafu)]
+pub enum FieldCountError {
+    #[snafu(display("Event has more than {} fields.", max_fields))]
+    TooManyFields { max_fields: usize },
+}
+
+/// Why a path into the fields of a log event, such as `a.b[1].c`, could not be
+/// followed. `segment` is the zero-based position of the path component that
+/// could not be applied, and `path` is the path as it was given.
+#[derive(Clone, Debug, Eq, PartialEq, Snafu)]
+pub enum PathError {
+    #[snafu(display("Path `{}` is invalid at segment {}.", path, segment))]
+    InvalidPath { path: String, segment: usize },
+    #[snafu(display("Segment {} of `{}` does not exist.", segment, path))]
+    MissingField { path: String, segment: usize },
+    #[snafu(display(
+        "Segment {} of `{}` is out of range of an array of length {}.",
+        segment,
+        path,
+        len
+    ))]
+    IndexOutOfRange {
+        path: String,
+        segment: usize,
+        len: usize,
+    },
+    #[snafu(display(
+        "Segment {} of `{}` is a field, but the value it is looked up in is not a map.",
+        segment,
+        path
+    ))]
+    NotAMap { path: String, segment: usize },
+    #[snafu(display(
+        "Segment {} of `{}` is an index, but the value it is looked up in is not an array.",
+        segment,
+        path
+    ))]
+    NotAnArray { path: String, segment: usize },
+    #[snafu(display("Segment {} of `{}` is not supported here.", segment, path))]
+    UnsupportedSegment { path: String, segment: usize },
+}
+
+impl PathError {
+    /// The zero-based position of the path component that could not be applied.
+    pub const fn segment(&self) -> usize {
+        match self {
+            Self::InvalidPath { segment, .. }
+            | Self::MissingField { segment, .. }
+            | Self::IndexOutOfRange { segment, .. }
+            | Self::NotAMap { segment, .. }
+            | Self::NotAnArray { segment, .. }
+            | Self::UnsupportedSegment { segment, .. } => *segment,
+        }
+    }
+}
+
+/// Why a field of a log event that has to be of a certain kind, such as an array to sort, could
+/// not be worked on.
+#[derive(Clone, Debug, Eq, PartialEq, Snafu)]
+pub enum FieldError {
+    #[snafu(display("{}", source))]
+    Path { source: PathError },
+    #[snafu(display("Field `{}` is of kind {}, not {}.", path, kind, expected))]
+    WrongKind {
+        path: String,
+        expected: ValueKind,
+        kind: ValueKind,
+    },
+}
+
+/// Why a value could not be made into a log event, see [`LogEvent::from_value`].
+///
+/// [`LogEvent::from_value`]: crate::event::LogEvent::from_value
+#[derive(Clone, Debug, Eq, PartialEq, Snafu)]
+pub enum FromValueError {
+    #[snafu(display(
+        "The fields of a log event must be a map, not a value of kind {}.",
+        kind
+    ))]
+    NotAMap { kind: ValueKind },
+}
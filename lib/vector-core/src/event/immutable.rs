@@ -0,0 +1,191 @@
+use std::{collections::BTreeMap, sync::Arc};
+
+use once_cell::sync::Lazy;
+use serde::Serialize;
+
+use super::{util, Event, EventFinalizers, EventMetadata, Value};
+use crate::ByteSizeOf;
+
+/// What the log getters of an [`ImmutableEvent`] look into for a metric, which has no fields.
+static NO_FIELDS: Lazy<BTreeMap<String, Value>> = Lazy::new(BTreeMap::new);
+
+/// An event shared by the components it is handed to, for those that only read it, so that handing
+/// it to several doesn't copy it for each. The getters are those of [`LogEvent`], and find nothing
+/// in a metric.
+///
+/// A component that needs to change the event takes its own copy with
+/// [`into_owned`](Self::into_owned), which only copies the event if it is still shared.
+///
+/// [`LogEvent`]: super::LogEvent
+#[derive(Clone, Debug, PartialEq, PartialOrd)]
+pub struct ImmutableEvent(Arc<Event>);
+
+impl ImmutableEvent {
+    pub fn new(event: Event) -> Self {
+        Self(Arc::new(event))
+    }
+
+    pub fn as_event(&self) -> &Event {
+        &self.0
+    }
+
+    /// Take the event to change it, copying it only if it is shared with another component.
+    pub fn into_owned(self) -> Event {
+        Arc::try_unwrap(self.0).unwrap_or_else(|event| (*event).clone())
+    }
+
+    /// Whether no other component holds the event, so that [`into_owned`](Self::into_owned)
+    /// wouldn't copy it.
+    pub fn is_unique(&self) -> bool {
+        Arc::strong_count(&self.0) == 1
+    }
+
+    pub fn metadata(&self) -> &EventMetadata {
+        self.0.metadata()
+    }
+
+    /// The finalizers of the event, for a component to update the status of the event once it is
+    /// done with it. They are shared with the event rather than taken, so the batch is only
+    /// notified once every component holding the event has dropped it.
+    pub fn finalizers(&self) -> EventFinalizers {
+        self.metadata().finalizers().clone()
+    }
+
+    pub fn get(&self, key: impl AsRef<str>) -> Option<&Value> {
+        match self.as_event() {
+            Event::Log(log) => log.get(key),
+            Event::Metric(_) => None,
+        }
+    }
+
+    pub fn get_flat(&self, key: impl AsRef<str>) -> Option<&Value> {
+        match self.as_event() {
+            Event::Log(log) => log.get_flat(key),
+            Event::Metric(_) => None,
+        }
+    }
+
+    pub fn contains(&self, key: impl AsRef<str>) -> bool {
+        match self.as_event() {
+            Event::Log(log) => log.contains(key),
+            Event::Metric(_) => false,
+        }
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = String> + '_ {
+        util::log::keys(self.as_map())
+    }
+
+    pub fn all_fields(&self) -> impl Iterator<Item = (String, &Value)> + Serialize {
+        util::log::all_fields(self.as_map())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.as_map().is_empty()
+    }
+
+    pub fn as_map(&self) -> &BTreeMap<String, Value> {
+        match self.as_event() {
+            Event::Log(log) => log.as_map(),
+            Event::Metric(_) => &NO_FIELDS,
+        }
+    }
+}
+
+impl From<Event> for ImmutableEvent {
+    fn from(event: Event) -> Self {
+        Self::new(event)
+    }
+}
+
+impl ByteSizeOf for ImmutableEvent {
+    fn allocated_bytes(&self) -> usize {
+        self.0.size_of()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::event::{
+        BatchNotifier, BatchStatus, EventStatus, LogEvent, Metric, MetricKind, MetricValue,
+    };
+
+    const fn assert_send_sync<T: Send + Sync>() {}
+
+    #[test]
+    fn events_can_be_shared_between_threads() {
+        assert_send_sync::<Event>();
+        assert_send_sync::<LogEvent>();
+        assert_send_sync::<Metric>();
+        assert_send_sync::<ImmutableEvent>();
+    }
+
+    #[test]
+    fn into_owned_only_copies_shared_events() {
+        let mut log = LogEvent::from("hello");
+        log.insert("nested.value", 1);
+        let event = ImmutableEvent::from(Event::from(log));
+        let fields: *const _ = event.as_map();
+
+        let other = event.clone();
+        assert!(!event.is_unique());
+        let mut owned = other.into_owned();
+        owned.as_mut_log().insert("changed", true);
+        assert!(!event.contains("changed"));
+        assert!(event.is_unique());
+
+        let owned = event.into_owned();
+        assert!(std::ptr::eq(owned.as_log().as_map(), fields));
+    }
+
+    #[test]
+    fn getters_mirror_the_log() {
+        let mut log = LogEvent::from("hello");
+        log.insert("nested.value", 1);
+        log.insert_flat("dotted.key", 2);
+        let event = ImmutableEvent::from(Event::from(log.clone()));
+
+        assert_eq!(event.get("nested.value"), log.get("nested.value"));
+        assert_eq!(event.get_flat("dotted.key"), log.get_flat("dotted.key"));
+        assert!(event.contains("message"));
+        assert_eq!(
+            event.keys().collect::<Vec<_>>(),
+            log.keys().collect::<Vec<_>>()
+        );
+        assert_eq!(
+            event.all_fields().collect::<Vec<_>>(),
+            log.all_fields().collect::<Vec<_>>()
+        );
+        assert!(!event.is_empty());
+
+        let metric = ImmutableEvent::from(Event::Metric(Metric::new(
+            "requests",
+            MetricKind::Incremental,
+            MetricValue::Counter { value: 1.0 },
+        )));
+        assert_eq!(metric.get("name"), None);
+        assert!(!metric.contains("name"));
+        assert_eq!(metric.all_fields().count(), 0);
+        assert!(metric.is_empty());
+    }
+
+    #[test]
+    fn batch_is_notified_once_every_holder_is_done() {
+        let (batch, mut receiver) = BatchNotifier::new_with_receiver();
+        let event =
+            ImmutableEvent::from(Event::from(LogEvent::from("hello")).with_batch_notifier(&batch));
+        drop(batch);
+
+        let first = event.clone();
+        let finalizers = first.finalizers();
+        drop(first);
+        finalizers.update_status(EventStatus::Delivered);
+        drop(finalizers);
+        assert!(receiver.try_recv().is_err());
+
+        event.finalizers().update_status(EventStatus::Delivered);
+        drop(event);
+        assert_eq!(receiver.try_recv(), Ok(BatchStatus::Delivered));
+    }
+}
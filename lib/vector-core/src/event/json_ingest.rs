@@ -0,0 +1,251 @@
+//! Builds the fields of a log event while JSON is being parsed, without going through a
+//! `serde_json::Value` first. See [`LogEvent::from_json_slice`](super::LogEvent::from_json_slice).
+//!
+//! The values come out the same as converting a parsed `serde_json::Value` would make them:
+//! integers that fit an `i64` are integers, other numbers are floats, and a key given twice keeps
+//! its last value.
+//!
+//! The same visitors deserialize a [`LogEvent`](super::LogEvent) from other self-describing
+//! formats, such as TOML.
+
+use std::{collections::BTreeMap, convert::TryFrom, fmt};
+
+use bytes::Bytes;
+use serde::de::{self, DeserializeSeed, Deserializer, MapAccess, SeqAccess, Visitor};
+
+use super::Value;
+
+/// The fields of a log event, which have to be a JSON object.
+pub(super) struct JsonFields(pub(super) BTreeMap<String, Value>);
+
+impl<'de> de::Deserialize<'de> for JsonFields {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer
+            .deserialize_map(FieldsVisitor {
+                expecting: "a JSON object",
+            })
+            .map(JsonFields)
+    }
+}
+
+/// The fields of a log event in any format, which have to be a map.
+pub(super) fn deserialize_fields<'de, D>(
+    deserializer: D,
+) -> Result<BTreeMap<String, Value>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    deserializer.deserialize_map(LogEventFieldsVisitor)
+}
+
+/// A single value of any kind, in any format.
+pub(super) fn deserialize_value<'de, D>(deserializer: D) -> Result<Value, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    ValueSeed.deserialize(deserializer)
+}
+
+struct FieldsVisitor {
+    expecting: &'static str,
+}
+
+impl<'de> Visitor<'de> for FieldsVisitor {
+    type Value = BTreeMap<String, Value>;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.expecting)
+    }
+
+    fn visit_map<A>(self, map: A) -> Result<Self::Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        visit_fields(map)
+    }
+}
+
+/// Like [`FieldsVisitor`], but fails with an error naming the kind of value found in place of the
+/// map, as in `LogEvent must be a JSON object, got array`.
+struct LogEventFieldsVisitor;
+
+impl LogEventFieldsVisitor {
+    fn not_a_map<E: de::Error>(found: &str) -> E {
+        E::custom(format_args!(
+            "LogEvent must be a JSON object, got {}",
+            found
+        ))
+    }
+}
+
+impl<'de> Visitor<'de> for LogEventFieldsVisitor {
+    type Value = BTreeMap<String, Value>;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("a map of fields")
+    }
+
+    fn visit_map<A>(self, map: A) -> Result<Self::Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        visit_fields(map)
+    }
+
+    fn visit_seq<A>(self, _seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        Err(Self::not_a_map("array"))
+    }
+
+    fn visit_bool<E: de::Error>(self, _value: bool) -> Result<Self::Value, E> {
+        Err(Self::not_a_map("boolean"))
+    }
+
+    fn visit_i64<E: de::Error>(self, _value: i64) -> Result<Self::Value, E> {
+        Err(Self::not_a_map("number"))
+    }
+
+    fn visit_u64<E: de::Error>(self, _value: u64) -> Result<Self::Value, E> {
+        Err(Self::not_a_map("number"))
+    }
+
+    fn visit_f64<E: de::Error>(self, _value: f64) -> Result<Self::Value, E> {
+        Err(Self::not_a_map("number"))
+    }
+
+    fn visit_str<E: de::Error>(self, _value: &str) -> Result<Self::Value, E> {
+        Err(Self::not_a_map("string"))
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<Self::Value, E> {
+        Err(Self::not_a_map("null"))
+    }
+
+    fn visit_none<E: de::Error>(self) -> Result<Self::Value, E> {
+        Err(Self::not_a_map("null"))
+    }
+}
+
+fn visit_fields<'de, A>(mut map: A) -> Result<BTreeMap<String, Value>, A::Error>
+where
+    A: MapAccess<'de>,
+{
+    let mut fields = BTreeMap::new();
+    while let Some(key) = map.next_key::<String>()? {
+        let value = map.next_value_seed(ValueSeed)?;
+        fields.insert(key, value);
+    }
+    Ok(fields)
+}
+
+/// Deserializes a single JSON value of any kind into a [`Value`].
+struct ValueSeed;
+
+impl<'de> DeserializeSeed<'de> for ValueSeed {
+    type Value = Value;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(JsonValueVisitor)
+    }
+}
+
+struct JsonValueVisitor;
+
+impl<'de> Visitor<'de> for JsonValueVisitor {
+    type Value = Value;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("a JSON value")
+    }
+
+    fn visit_bool<E>(self, value: bool) -> Result<Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(Value::Boolean(value))
+    }
+
+    fn visit_i64<E>(self, value: i64) -> Result<Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(Value::Integer(value))
+    }
+
+    // Past `i64::MAX` the number is kept as a float, as `serde_json::Number::as_f64` gives it.
+    #[allow(clippy::cast_precision_loss)]
+    fn visit_u64<E>(self, value: u64) -> Result<Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(i64::try_from(value).map_or(Value::Float(value as f64), Value::Integer))
+    }
+
+    fn visit_f64<E>(self, value: f64) -> Result<Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(Value::Float(value))
+    }
+
+    fn visit_str<E>(self, value: &str) -> Result<Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(Value::Bytes(Bytes::copy_from_slice(value.as_bytes())))
+    }
+
+    fn visit_string<E>(self, value: String) -> Result<Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(Value::Bytes(Bytes::from(value)))
+    }
+
+    fn visit_unit<E>(self) -> Result<Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(Value::Null)
+    }
+
+    fn visit_none<E>(self) -> Result<Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(Value::Null)
+    }
+
+    fn visit_some<D>(self, deserializer: D) -> Result<Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        ValueSeed.deserialize(deserializer)
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let mut values = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+        while let Some(value) = seq.next_element_seed(ValueSeed)? {
+            values.push(value);
+        }
+        Ok(Value::Array(values))
+    }
+
+    fn visit_map<A>(self, map: A) -> Result<Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        visit_fields(map).map(Value::Map)
+    }
+}
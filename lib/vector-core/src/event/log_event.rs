@@ -1,50 +1,170 @@
 use std::{
+    cmp::Ordering,
     collections::{btree_map::Entry, BTreeMap, HashMap},
     convert::{TryFrom, TryInto},
-    fmt::{Debug, Display},
-    iter::FromIterator,
+    fmt::{self, Debug, Display},
+    io,
+    iter::{self, FromIterator},
     sync::Arc,
 };
 
-use bytes::Bytes;
-use chrono::Utc;
-use derivative::Derivative;
+use bytes::{Bytes, BytesMut};
+use chrono::{DateTime, Utc};
 use getset::{Getters, MutGetters};
-use serde::{Deserialize, Serialize, Serializer};
+use lookup::{AsPathRef, LookupBuf, PathRef};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use shared::EventDataEq;
+use snafu::ResultExt;
 
 use super::{
+    canonical_json, compressed,
+    error::{self, CompressionError, FieldCountError, FieldError, FromValueError, PathError},
     finalization::{BatchNotifier, EventFinalizer},
+    json_ingest::{self, JsonFields},
     legacy_lookup::Segment,
-    metadata::EventMetadata,
-    util, EventFinalizers, Finalizable, Lookup, PathComponent, Value,
+    metadata::{EventMetadata, ProvenanceEntry},
+    schema, util, visit, CompiledLookup, CompressedField, CompressedJson, EventFinalizers,
+    EventSchema, FieldCodec, FieldPredicate, Finalizable, Lookup, PathComponent, PathIter,
+    SchemaViolation, Value, ValueKind,
 };
-use crate::{config::log_schema, event::MaybeAsLogMut, ByteSizeOf};
+use crate::{
+    config::{event_ids_enabled, log_schema, sensitive_paths, LogSchema, SensitivePath},
+    event::MaybeAsLogMut,
+    ByteSizeOf,
+};
+
+/// What the values of sensitive fields are shown as in the `Debug` form of events.
+const REDACTED: &str = "[REDACTED]";
 
-#[derive(Clone, Debug, Getters, MutGetters, PartialEq, PartialOrd, Derivative, Deserialize)]
+#[derive(Clone, Getters, MutGetters, PartialEq, PartialOrd)]
 pub struct LogEvent {
-    // **IMPORTANT:** Due to numerous legacy reasons this **must** be a Map variant.
-    #[derivative(Default(value = "Value::from(BTreeMap::default())"))]
-    #[serde(flatten)]
-    fields: Value,
+    // The fields are shared copy-on-write: cloning a `LogEvent` only bumps the reference count,
+    // and the first mutation of a shared event performs the deep copy.
+    fields: Arc<BTreeMap<String, Value>>,
 
     #[getset(get = "pub", get_mut = "pub")]
-    #[serde(skip)]
     metadata: EventMetadata,
 }
 
 impl Default for LogEvent {
     fn default() -> Self {
         Self {
-            fields: Value::Map(BTreeMap::new()),
+            fields: Arc::new(BTreeMap::new()),
             metadata: EventMetadata::default(),
         }
     }
 }
 
+impl Debug for LogEvent {
+    /// Leaves out the values of sensitive fields, see [`fmt_redacted`](Self::fmt_redacted).
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.fmt_redacted(f)
+    }
+}
+
+/// The `Debug` form of a [`LogEvent`] with nothing redacted, see [`LogEvent::debug_raw`].
+struct RawLogEvent<'a>(&'a LogEvent);
+
+impl Debug for RawLogEvent<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LogEvent")
+            .field("fields", &self.0.fields)
+            .field("metadata", &self.0.metadata)
+            .finish()
+    }
+}
+
+/// What a [`Redacted`] shows.
+#[derive(Clone, Copy)]
+enum RedactedNode<'a> {
+    Value(&'a Value),
+    Fields(&'a BTreeMap<String, Value>),
+    Items(&'a [Value]),
+}
+
+/// The `Debug` form of the fields of a log event or a value in them, with `"[REDACTED]"` in place
+/// of the values at sensitive paths.
+struct Redacted<'a> {
+    /// The keys of the path to the node. Array indices are passed over.
+    keys: Vec<&'a str>,
+    node: RedactedNode<'a>,
+    sensitive: &'a [SensitivePath],
+}
+
+impl<'a> Redacted<'a> {
+    fn child(&self, key: Option<&'a str>, node: RedactedNode<'a>) -> Self {
+        let mut keys = self.keys.clone();
+        keys.extend(key);
+        Self {
+            keys,
+            node,
+            sensitive: self.sensitive,
+        }
+    }
+}
+
+impl Debug for Redacted<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.node {
+            RedactedNode::Fields(fields) => {
+                f.debug_map()
+                    .entries(fields.iter().map(|(key, value)| {
+                        (key, self.child(Some(key), RedactedNode::Value(value)))
+                    }))
+                    .finish()
+            }
+            RedactedNode::Items(items) => f
+                .debug_list()
+                .entries(
+                    items
+                        .iter()
+                        .map(|value| self.child(None, RedactedNode::Value(value))),
+                )
+                .finish(),
+            RedactedNode::Value(_)
+                if self.sensitive.iter().any(|path| path.matches(&self.keys)) =>
+            {
+                Debug::fmt(REDACTED, f)
+            }
+            RedactedNode::Value(Value::Map(fields)) => f
+                .debug_tuple("Map")
+                .field(&self.child(None, RedactedNode::Fields(fields)))
+                .finish(),
+            RedactedNode::Value(Value::Array(items)) => f
+                .debug_tuple("Array")
+                .field(&self.child(None, RedactedNode::Items(items)))
+                .finish(),
+            RedactedNode::Value(value) => Debug::fmt(value, f),
+        }
+    }
+}
+
+/// Record `value`, about to be inserted at `path`, in the `value` field of the current span,
+/// redacted if the path is sensitive. Nothing is done unless trace level logs are enabled, as
+/// this is done for every insertion.
+fn record_inserted<'a>(path: impl IntoIterator<Item = PathComponent<'a>>, value: &Value) {
+    if tracing::Level::TRACE > tracing::level_filters::LevelFilter::current() {
+        return;
+    }
+    let path = path.into_iter().collect::<Vec<_>>();
+    let sensitive = sensitive_paths();
+    let redacted = Redacted {
+        keys: path
+            .iter()
+            .filter_map(|component| match component {
+                PathComponent::Key(key) => Some(key.as_ref()),
+                _ => None,
+            })
+            .collect(),
+        node: RedactedNode::Value(value),
+        sensitive: &sensitive,
+    };
+    tracing::Span::current().record("value", &tracing::field::debug(redacted));
+}
+
 impl ByteSizeOf for LogEvent {
     fn allocated_bytes(&self) -> usize {
-        self.fields.allocated_bytes() + self.metadata.allocated_bytes()
+        self.fields.size_of() + self.metadata.allocated_bytes()
     }
 }
 
@@ -58,29 +178,114 @@ impl LogEvent {
     #[must_use]
     pub fn new_with_metadata(metadata: EventMetadata) -> Self {
         Self {
-            fields: Value::Map(Default::default()),
+            fields: Arc::new(BTreeMap::new()),
             metadata,
         }
     }
 
     ///  Create a `LogEvent` into a tuple of its components
     pub fn from_parts(map: BTreeMap<String, Value>, metadata: EventMetadata) -> Self {
-        let fields = Value::Map(map);
+        let fields = Arc::new(map);
         Self { fields, metadata }
     }
 
-    /// Convert a `LogEvent` into a tuple of its components
+    /// Create a `LogEvent` of the top-level `fields`, whose names are taken as they are rather
+    /// than parsed as paths, refusing more than `max_fields` fields. The entries of maps nested in
+    /// the fields, at any depth, count as fields too.
     ///
-    /// # Panics
+    /// A `BTreeMap` can't be sized up front, so the fields are collected into a `Vec` sized from
+    /// the size hint of `fields` and the map is then built from it at once, which takes
+    /// O(n log n) on the calling thread and briefly holds both. An iterator whose size hint is
+    /// already over `max_fields`, such as that of a large `HashMap`, is refused before any of its
+    /// fields are taken, and any other one as soon as it yields a field that goes over.
     ///
-    /// Panics if the fields of the `LogEvent` are not a `Value::Map`.
+    /// # Errors
+    ///
+    /// Fails with `FieldCountError::TooManyFields` if there are more than `max_fields` fields.
+    pub fn try_from_fields<I>(fields: I, max_fields: usize) -> Result<Self, FieldCountError>
+    where
+        I: IntoIterator<Item = (String, Value)>,
+    {
+        let fields = fields.into_iter();
+        let (size_hint, _) = fields.size_hint();
+        if size_hint > max_fields {
+            return Err(FieldCountError::TooManyFields { max_fields });
+        }
+        let mut budget = FieldBudget::new(max_fields);
+        let mut collected = Vec::with_capacity(size_hint);
+        for (name, value) in fields {
+            budget.take()?;
+            budget.take_nested(&value)?;
+            collected.push((name, value));
+        }
+        Ok(collected.into_iter().collect::<BTreeMap<_, _>>().into())
+    }
+
+    /// Convert a `LogEvent` into a tuple of its components
+    ///
+    /// The fields are only copied if they are still shared with a clone of this event.
     pub fn into_parts(self) -> (BTreeMap<String, Value>, EventMetadata) {
-        (
-            self.fields
-                .into_map()
-                .unwrap_or_else(|| unreachable!("fields must be a map")),
-            self.metadata,
-        )
+        let fields = Arc::try_unwrap(self.fields).unwrap_or_else(|fields| (*fields).clone());
+        (fields, self.metadata)
+    }
+
+    /// Swap the fields of this event for `fields` in one go, returning the old ones. The metadata,
+    /// finalizers included, is left as it is, unlike building a new event from the fields.
+    ///
+    /// The old fields are only copied if they are still shared with a clone of this event.
+    pub fn replace_fields(&mut self, fields: BTreeMap<String, Value>) -> BTreeMap<String, Value> {
+        let old = std::mem::replace(&mut self.fields, Arc::new(fields));
+        Arc::try_unwrap(old).unwrap_or_else(|old| (*old).clone())
+    }
+
+    /// Take the fields of this event, leaving it with none but keeping its metadata.
+    pub fn take_fields(&mut self) -> BTreeMap<String, Value> {
+        self.replace_fields(BTreeMap::new())
+    }
+
+    /// Run `f` on this event as a single change of its fields: if it fails, the fields are put
+    /// back as they were before it ran. The metadata is left as `f` leaves it either way.
+    ///
+    /// Taking the snapshot only shares the fields, the first write of `f` copies them. Transactions
+    /// nest, the failure of an inner one only undoes the changes made within it.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of `f`.
+    pub fn transaction<E>(
+        &mut self,
+        f: impl FnOnce(&mut LogEvent) -> Result<(), E>,
+    ) -> Result<(), E> {
+        let snapshot = Arc::clone(&self.fields);
+        let result = f(self);
+        if result.is_err() {
+            self.fields = snapshot;
+        }
+        result
+    }
+
+    /// A snapshot of the fields as a map, which later changes to this event don't show in, such
+    /// as for working on the fields without holding a borrow of the event across an await point.
+    ///
+    /// Nothing is copied: the snapshot shares the fields with the event, which copies them on its
+    /// next change instead, and only if the snapshot is still held by then.
+    pub fn to_value(&self) -> Arc<BTreeMap<String, Value>> {
+        Arc::clone(&self.fields)
+    }
+
+    /// Create a `LogEvent` from a value holding its fields, such as a map taken out of
+    /// [`to_value`](Self::to_value).
+    ///
+    /// # Errors
+    ///
+    /// Fails if the value isn't a map.
+    pub fn from_value(value: Value, metadata: EventMetadata) -> Result<Self, FromValueError> {
+        match value {
+            Value::Map(fields) => Ok(Self::from_parts(fields, metadata)),
+            value => Err(FromValueError::NotAMap {
+                kind: value.value_kind(),
+            }),
+        }
     }
 
     pub fn with_batch_notifier(mut self, batch: &Arc<BatchNotifier>) -> Self {
@@ -97,9 +302,73 @@ impl LogEvent {
         self.metadata.add_finalizer(finalizer);
     }
 
+    /// The value at `key`. A string is parsed as a path, while the keys of a [`LookupBuf`] are
+    /// taken as they are, so a lookup built with [`LookupBuf::push_field`] reaches fields whose
+    /// names hold `.`, `[` or quotes without any escaping. Lookups holding a coalesce or an index
+    /// counting from the end, like invalid paths, have no value.
+    #[instrument(level = "trace", skip(self, key), fields(key = %key.as_path_ref()))]
+    pub fn get(&self, key: impl AsPathRef) -> Option<&Value> {
+        match key.as_path_ref() {
+            PathRef::Str(path) => util::log::get(self.as_map(), path),
+            PathRef::Lookup(lookup) => {
+                util::log::get_path(self.as_map(), util::log::lookup_components(lookup).ok()?)
+            }
+        }
+    }
+
+    /// Like [`get`](Self::get), but says why there is no value at `key`.
+    ///
+    /// # Errors
+    ///
+    /// Fails if `key` is an invalid path, or if following it runs into a missing field, an index
+    /// out of range, or a value that can't be descended into.
     #[instrument(level = "trace", skip(self, key), fields(key = %key.as_ref()))]
-    pub fn get(&self, key: impl AsRef<str>) -> Option<&Value> {
-        util::log::get(self.as_map(), key.as_ref())
+    pub fn try_get(&self, key: impl AsRef<str>) -> Result<&Value, PathError> {
+        util::log::try_get(self.as_map(), key.as_ref())
+    }
+
+    /// Look up the values of several fields at once, walking the fields a single time.
+    ///
+    /// The values are returned in the same order as `paths`. When both a path and a path nested
+    /// below it are given, only the outer one yields a value.
+    #[instrument(level = "trace", skip(self, paths))]
+    pub fn extract(&self, paths: &[CompiledLookup]) -> Vec<Option<&Value>> {
+        util::log::extract(self.as_map(), paths)
+    }
+
+    /// Like [`extract`](Self::extract), but removes the extracted fields, returning them along
+    /// with what remains of the event.
+    ///
+    /// Extracted array elements are replaced with `null` so the indexes of the other elements
+    /// don't change.
+    #[instrument(level = "trace", skip(self, paths))]
+    pub fn extract_owned(mut self, paths: &[CompiledLookup]) -> (Vec<Option<Value>>, LogEvent) {
+        let extracted = util::log::extract_remove(self.as_map_mut(), paths);
+        (extracted, self)
+    }
+
+    /// A new event holding only the fields at `paths`, nested as they are in this one, and the
+    /// metadata of this one. It costs less than cloning the whole event when only a few fields
+    /// are needed, since the other fields are never copied and bytes share their buffers.
+    ///
+    /// Missing and invalid paths are left out, and a path nested below another one is covered
+    /// by the outer one. Array elements that aren't copied are `null`, so that the indexes of
+    /// those that are don't change.
+    #[instrument(level = "trace", skip(self, paths))]
+    pub fn clone_subset(&self, paths: &[&str]) -> LogEvent {
+        let paths = paths
+            .iter()
+            .filter_map(|path| CompiledLookup::new(path).ok())
+            .collect::<Vec<_>>();
+        self.clone_compiled_subset(&paths)
+    }
+
+    /// Like [`clone_subset`](Self::clone_subset), with the paths parsed up front, for cloning
+    /// the same fields of many events.
+    #[instrument(level = "trace", skip(self, paths))]
+    pub fn clone_compiled_subset(&self, paths: &[CompiledLookup]) -> LogEvent {
+        let fields = util::log::extract_subset(self.as_map(), paths);
+        Self::from_parts(fields, self.metadata.clone())
     }
 
     #[instrument(level = "trace", skip(self, key), fields(key = %key.as_ref()))]
@@ -112,34 +381,248 @@ impl LogEvent {
         util::log::get_mut(self.as_map_mut(), key.as_ref())
     }
 
-    #[instrument(level = "trace", skip(self, key), fields(key = %key.as_ref()))]
-    pub fn contains(&self, key: impl AsRef<str>) -> bool {
-        util::log::contains(self.as_map(), key.as_ref())
+    /// Whether there is a value at `key`, which is a path as [`get`](Self::get) takes it.
+    #[instrument(level = "trace", skip(self, key), fields(key = %key.as_path_ref()))]
+    pub fn contains(&self, key: impl AsPathRef) -> bool {
+        match key.as_path_ref() {
+            PathRef::Str(path) => util::log::contains(self.as_map(), path),
+            PathRef::Lookup(lookup) => util::log::lookup_components(lookup)
+                .map_or(false, |path| util::log::contains_path(self.as_map(), path)),
+        }
     }
 
-    #[instrument(level = "trace", skip(self, key), fields(key = %key.as_ref()))]
-    pub fn insert(
+    /// The number of elements of the array, or entries of the map, at `key`. `None` if there is no
+    /// value at `key` or it is neither an array nor a map.
+    pub fn len_at(&self, key: impl AsRef<str>) -> Option<usize> {
+        self.get(key)
+            .and_then(|value| value.len_at(lookup::Lookup::root()))
+    }
+
+    /// The kind of the value at `key`, if there is one.
+    pub fn kind_at(&self, key: impl AsRef<str>) -> Option<ValueKind> {
+        self.get(key).map(Value::value_kind)
+    }
+
+    /// Whether the value at `key` is empty, as defined by [`Value::is_empty`], if there is one.
+    pub fn is_empty_at(&self, key: impl AsRef<str>) -> Option<bool> {
+        self.get(key).map(Value::is_empty)
+    }
+
+    /// Sort the array at `key` by [`Value::total_cmp`]. With `sort_key`, the elements are instead
+    /// sorted by their value at that path, and those without one, including any element that
+    /// isn't a map, go last. The sort is stable.
+    ///
+    /// # Errors
+    ///
+    /// Fails if there is no value at `key` or it isn't an array.
+    pub fn sort_array_at(
+        &mut self,
+        key: impl AsRef<str>,
+        sort_key: Option<&str>,
+    ) -> Result<(), FieldError> {
+        let array = self.array_at_mut(key.as_ref())?;
+        match sort_key {
+            None => array.sort_by(Value::total_cmp),
+            Some(sort_key) => {
+                let get = |value: &Value| match value {
+                    Value::Map(map) => util::log::get(map, sort_key),
+                    _ => None,
+                };
+                array.sort_by(|a, b| match (get(a), get(b)) {
+                    (Some(a), Some(b)) => a.total_cmp(b),
+                    (Some(_), None) => Ordering::Less,
+                    (None, Some(_)) => Ordering::Greater,
+                    (None, None) => Ordering::Equal,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Drop the elements of the array at `key` past the first `max_len`, returning how many were
+    /// dropped.
+    ///
+    /// # Errors
+    ///
+    /// Fails if there is no value at `key` or it isn't an array.
+    pub fn truncate_array_at(
+        &mut self,
+        key: impl AsRef<str>,
+        max_len: usize,
+    ) -> Result<usize, FieldError> {
+        let array = self.array_at_mut(key.as_ref())?;
+        let dropped = array.len().saturating_sub(max_len);
+        array.truncate(max_len);
+        Ok(dropped)
+    }
+
+    /// Truncate the bytes at `key` to at most `max_bytes`, returning whether they were longer.
+    /// Valid UTF-8 is cut at the last character boundary within the limit so that it stays valid,
+    /// anything else at exactly `max_bytes`. `marker`, such as `"…[truncated]"`, is appended to
+    /// truncated bytes within the same limit, unless it is longer than `max_bytes` on its own.
+    ///
+    /// Values other than bytes are left untouched, see
+    /// [`try_truncate_field`](Self::try_truncate_field) to find out why nothing was truncated.
+    pub fn truncate_field(
+        &mut self,
+        key: impl AsRef<str>,
+        max_bytes: usize,
+        marker: Option<&str>,
+    ) -> bool {
+        self.try_truncate_field(key, max_bytes, marker)
+            .unwrap_or(false)
+    }
+
+    /// Like [`truncate_field`](Self::truncate_field), but fails when there are no bytes at `key`.
+    ///
+    /// # Errors
+    ///
+    /// Fails if there is no value at `key` or it isn't bytes. The event is left unchanged in that
+    /// case.
+    pub fn try_truncate_field(
         &mut self,
         key: impl AsRef<str>,
+        max_bytes: usize,
+        marker: Option<&str>,
+    ) -> Result<bool, FieldError> {
+        let key = key.as_ref();
+        let kind = self.try_get(key).context(error::Path)?.value_kind();
+        match self.get_mut(key) {
+            Some(Value::Bytes(bytes)) => Ok(truncate_bytes(bytes, max_bytes, marker)),
+            _ => Err(FieldError::WrongKind {
+                path: key.to_owned(),
+                expected: ValueKind::Bytes,
+                kind,
+            }),
+        }
+    }
+
+    fn array_at_mut(&mut self, key: &str) -> Result<&mut Vec<Value>, FieldError> {
+        let kind = self.try_get(key).context(error::Path)?.value_kind();
+        match self.get_mut(key) {
+            Some(Value::Array(array)) => Ok(array),
+            _ => Err(FieldError::WrongKind {
+                path: key.to_owned(),
+                expected: ValueKind::Array,
+                kind,
+            }),
+        }
+    }
+
+    /// Insert `value` at `key`, which is a path as [`get`](Self::get) takes it, and return the
+    /// value it replaced. Nothing is inserted at a path that has no value.
+    #[instrument(
+        level = "trace",
+        skip(self, key, value),
+        fields(key = %key.as_path_ref(), value = tracing::field::Empty)
+    )]
+    pub fn insert(
+        &mut self,
+        key: impl AsPathRef,
         value: impl Into<Value> + Debug,
     ) -> Option<Value> {
-        util::log::insert(self.as_map_mut(), key.as_ref(), value.into())
+        let value = value.into();
+        match key.as_path_ref() {
+            PathRef::Str(path) => {
+                record_inserted(PathIter::new(path), &value);
+                util::log::insert(self.as_map_mut(), path, value)
+            }
+            PathRef::Lookup(lookup) => {
+                let path = util::log::lookup_components(lookup).ok()?;
+                record_inserted(path.iter().cloned(), &value);
+                util::log::insert_path(self.as_map_mut(), path, value)
+            }
+        }
     }
 
-    #[instrument(level = "trace", skip(self, key), fields(key = %key.as_ref()))]
+    #[instrument(level = "trace", skip(self, key, value), fields(key = %key.as_ref()))]
     pub fn try_insert(&mut self, key: impl AsRef<str>, value: impl Into<Value> + Debug) {
+        self.try_insert_mode(key, value, InsertMode::IfAbsent);
+    }
+
+    /// Insert `value` at `key` if `mode` allows it given what `key` holds now.
+    #[instrument(level = "trace", skip(self, key, value), fields(key = %key.as_ref()))]
+    pub fn try_insert_mode(
+        &mut self,
+        key: impl AsRef<str>,
+        value: impl Into<Value> + Debug,
+        mode: InsertMode,
+    ) {
         let key = key.as_ref();
-        if !self.contains(key) {
+        let insert = match mode {
+            InsertMode::IfAbsent => !self.contains(key),
+            InsertMode::IfAbsentOrNull => matches!(self.get(key), None | Some(Value::Null)),
+            InsertMode::Always => true,
+        };
+        if insert {
             self.insert(key, value);
         }
     }
 
-    #[instrument(level = "trace", skip(self, key), fields(key = ?key))]
+    /// Insert `value` at `key` if the event matches `predicate`, returning whether it did.
+    #[instrument(
+        level = "trace",
+        skip(self, key, value, predicate),
+        fields(key = %key.as_ref())
+    )]
+    pub fn insert_if(
+        &mut self,
+        key: impl AsRef<str>,
+        value: impl Into<Value> + Debug,
+        predicate: &FieldPredicate,
+    ) -> bool {
+        let matches = predicate.matches(self);
+        if matches {
+            self.insert(key, value);
+        }
+        matches
+    }
+
+    /// Like [`insert`](Self::insert), but fails instead of replacing a value other than `null`
+    /// that `key` has to descend through. Not to be confused with
+    /// [`try_insert`](Self::try_insert), which only inserts when no value is present.
+    ///
+    /// # Errors
+    ///
+    /// Fails if `key` is an invalid path, or a lookup that has no value as [`get`](Self::get)
+    /// says, or if a value along it is of the wrong kind to descend into. The event is left
+    /// unchanged in that case.
+    #[instrument(
+        level = "trace",
+        skip(self, key, value),
+        fields(key = %key.as_path_ref(), value = tracing::field::Empty)
+    )]
+    pub fn insert_checked(
+        &mut self,
+        key: impl AsPathRef,
+        value: impl Into<Value> + Debug,
+    ) -> Result<Option<Value>, PathError> {
+        let value = value.into();
+        match key.as_path_ref() {
+            PathRef::Str(path) => {
+                record_inserted(PathIter::new(path), &value);
+                util::log::try_insert(self.as_map_mut(), path, value)
+            }
+            PathRef::Lookup(lookup) => {
+                let path = util::log::lookup_components(lookup)?;
+                record_inserted(path.iter().cloned(), &value);
+                util::log::try_insert_path(self.as_map_mut(), &lookup.to_string(), path, value)
+            }
+        }
+    }
+
+    #[instrument(
+        level = "trace",
+        skip(self, key, value),
+        fields(key = ?key, value = tracing::field::Empty)
+    )]
     pub fn insert_path<V>(&mut self, key: Vec<PathComponent>, value: V) -> Option<Value>
     where
         V: Into<Value> + Debug,
     {
-        util::log::insert_path(self.as_map_mut(), key, value.into())
+        let value = value.into();
+        record_inserted(key.iter().cloned(), &value);
+        util::log::insert_path(self.as_map_mut(), key, value)
     }
 
     /// Rename a key in place without reference to pathing
@@ -158,7 +641,7 @@ impl LogEvent {
         K: AsRef<str> + Into<String> + PartialEq + Display,
     {
         if from_key != to_key {
-            if let Some(val) = self.fields.as_map_mut().remove(from_key.as_ref()) {
+            if let Some(val) = self.as_map_mut().remove(from_key.as_ref()) {
                 self.insert_flat(to_key, val);
             }
         }
@@ -169,16 +652,23 @@ impl LogEvent {
     /// This function will insert a key in place without reference to any
     /// pathing information in the key. It will insert over the top of any value
     /// that exists in the map already.
-    #[instrument(level = "trace", skip(self, key), fields(key = %key))]
+    #[instrument(
+        level = "trace",
+        skip(self, key, value),
+        fields(key = %key, value = tracing::field::Empty)
+    )]
     pub fn insert_flat<K, V>(&mut self, key: K, value: V) -> Option<Value>
     where
         K: Into<String> + Display,
         V: Into<Value> + Debug,
     {
-        self.as_map_mut().insert(key.into(), value.into())
+        let key = key.into();
+        let value = value.into();
+        record_inserted(iter::once(PathComponent::Key(key.as_str().into())), &value);
+        self.as_map_mut().insert(key, value)
     }
 
-    #[instrument(level = "trace", skip(self, key), fields(key = %key.as_ref()))]
+    #[instrument(level = "trace", skip(self, key, value), fields(key = %key.as_ref()))]
     pub fn try_insert_flat(&mut self, key: impl AsRef<str>, value: impl Into<Value> + Debug) {
         let key = key.as_ref();
         if !self.as_map().contains_key(key) {
@@ -186,22 +676,74 @@ impl LogEvent {
         }
     }
 
-    #[instrument(level = "trace", skip(self, key), fields(key = %key.as_ref()))]
-    pub fn remove(&mut self, key: impl AsRef<str>) -> Option<Value> {
-        util::log::remove(self.as_map_mut(), key.as_ref(), false)
+    /// Remove the value at `key`, which is a path as [`get`](Self::get) takes it, and return it.
+    #[instrument(level = "trace", skip(self, key), fields(key = %key.as_path_ref()))]
+    pub fn remove(&mut self, key: impl AsPathRef) -> Option<Value> {
+        self.remove_prune(key, false)
     }
 
+    /// Like [`remove`](Self::remove), compacting what held the value with `prune`, see
+    /// [`util::log::remove_compacted`].
+    #[instrument(level = "trace", skip(self, key), fields(key = %key.as_path_ref()))]
+    pub fn remove_prune(&mut self, key: impl AsPathRef, prune: bool) -> Option<Value> {
+        match key.as_path_ref() {
+            PathRef::Str(path) => util::log::remove(self.as_map_mut(), path, prune),
+            PathRef::Lookup(lookup) => {
+                let path = util::log::lookup_components(lookup).ok()?;
+                util::log::remove_path(self.as_map_mut(), &path, prune)
+            }
+        }
+    }
+
+    /// Like [`remove_prune`](Self::remove_prune), but says why there is no value at `key`.
+    ///
+    /// # Errors
+    ///
+    /// Fails for the same reasons as [`try_get`](Self::try_get), in which case nothing is
+    /// removed.
     #[instrument(level = "trace", skip(self, key), fields(key = %key.as_ref()))]
-    pub fn remove_prune(&mut self, key: impl AsRef<str>, prune: bool) -> Option<Value> {
-        util::log::remove(self.as_map_mut(), key.as_ref(), prune)
+    pub fn try_remove(&mut self, key: impl AsRef<str>, prune: bool) -> Result<Value, PathError> {
+        util::log::try_remove(self.as_map_mut(), key.as_ref(), prune)
     }
 
     #[instrument(level = "trace", skip(self))]
     pub fn keys<'a>(&'a self) -> impl Iterator<Item = String> + 'a {
-        match &self.fields {
-            Value::Map(map) => util::log::keys(map),
-            _ => unreachable!(),
-        }
+        util::log::keys(&self.fields)
+    }
+
+    /// Iterates over the paths of the fields below `prefix`, relative to it or including it if
+    /// `absolute` is set. Only the subtree at `prefix` is visited.
+    ///
+    /// A scalar, or an empty map or array, at `prefix` yields its own path, and a non-empty array or
+    /// missing field yields nothing.
+    #[instrument(level = "trace", skip(self, prefix), fields(prefix = %prefix.as_ref()))]
+    pub fn keys_under<'a>(
+        &'a self,
+        prefix: impl AsRef<str>,
+        absolute: bool,
+    ) -> impl Iterator<Item = String> + 'a {
+        util::log::keys_under(self.as_map(), prefix.as_ref(), absolute)
+    }
+
+    /// Whether [`keys_under`](Self::keys_under) would yield any path for `prefix`.
+    #[instrument(level = "trace", skip(self, prefix), fields(prefix = %prefix.as_ref()))]
+    pub fn has_any_under(&self, prefix: impl AsRef<str>) -> bool {
+        util::log::has_any_under(self.as_map(), prefix.as_ref())
+    }
+
+    /// Check the event against `schema`, returning every required field that is missing or holds
+    /// a value of the wrong kind.
+    pub fn validate(&self, schema: &EventSchema) -> Vec<SchemaViolation> {
+        schema::validate(self, schema)
+    }
+
+    /// Fill in the defaults of missing required fields, and convert the values of required fields
+    /// to the expected kind where that loses nothing, see [`FieldKind::coerce`]. Returns the
+    /// violations that remain, as [`validate`](Self::validate) would.
+    ///
+    /// [`FieldKind::coerce`]: super::FieldKind::coerce
+    pub fn conform(&mut self, schema: &EventSchema) -> Vec<SchemaViolation> {
+        schema::conform(self, schema)
     }
 
     #[instrument(level = "trace", skip(self))]
@@ -209,81 +751,547 @@ impl LogEvent {
         util::log::all_fields(self.as_map())
     }
 
+    /// Iterates over the paths of all leaf fields as lookups, in the order of
+    /// [`all_fields`](Self::all_fields). Empty maps and arrays are leaves.
+    #[instrument(level = "trace", skip(self))]
+    pub fn paths(&self) -> impl Iterator<Item = LookupBuf> + '_ {
+        util::log::paths(self.as_map())
+    }
+
+    /// Iterates over the paths of all leaf fields, as [`paths`](Self::paths) does, and their
+    /// values.
+    #[instrument(level = "trace", skip(self))]
+    pub fn paths_with_values(&self) -> impl Iterator<Item = (LookupBuf, &Value)> {
+        util::log::paths_with_values(self.as_map())
+    }
+
     #[instrument(level = "trace", skip(self))]
     pub fn is_empty(&self) -> bool {
         self.as_map().is_empty()
     }
 
+    /// Format the event as its derived `Debug` form would, except for the values of the fields at
+    /// the sensitive paths of the [`Redaction`] settings, which are shown as `"[REDACTED]"`. The
+    /// keys of all fields are kept, so the structure of the event still shows. This is what the
+    /// `Debug` implementation does, for the events in internal logs.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the formatter does.
+    ///
+    /// [`Redaction`]: crate::config::Redaction
+    pub fn fmt_redacted(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let sensitive = sensitive_paths();
+        let fields = Redacted {
+            keys: Vec::new(),
+            node: RedactedNode::Fields(&self.fields),
+            sensitive: &sensitive,
+        };
+        f.debug_struct("LogEvent")
+            .field("fields", &fields)
+            .field("metadata", &self.metadata)
+            .finish()
+    }
+
+    /// The `Debug` form of the event with nothing redacted, for tests.
+    pub fn debug_raw(&self) -> impl Debug + '_ {
+        RawLogEvent(self)
+    }
+
     #[instrument(level = "trace", skip(self))]
     pub fn as_map(&self) -> &BTreeMap<String, Value> {
-        match &self.fields {
-            Value::Map(map) => map,
-            _ => unreachable!(),
-        }
+        &self.fields
     }
 
+    /// Mutably borrow the fields, copying them first if they are shared with a clone of this
+    /// event.
     #[instrument(level = "trace", skip(self))]
     pub fn as_map_mut(&mut self) -> &mut BTreeMap<String, Value> {
-        match self.fields {
-            Value::Map(ref mut map) => map,
-            _ => unreachable!(),
-        }
+        Arc::make_mut(&mut self.fields)
     }
 
     #[instrument(level = "trace", skip(self, lookup), fields(lookup = %lookup), err)]
-    fn entry(&mut self, lookup: Lookup) -> crate::Result<Entry<String, Value>> {
+    fn entry(&mut self, lookup: Lookup) -> Result<Entry<String, Value>, PathError> {
+        let path = lookup.to_string();
         let mut walker = lookup.into_iter().enumerate();
 
         let mut current_pointer = if let Some((_index, Segment::Field(segment))) = walker.next() {
             self.as_map_mut().entry(segment)
         } else {
-            // It should be noted that Remap can create a lookup without a contained segment.
-            // This is the root `.` path. That is handled explicitly by the Target implementation
-            // on Value so shouldn't reach here.
-            // However, we should probably handle this better.
-            unreachable!(
-                "It is an invariant to have a `Lookup` without a contained `Segment`.\
-                `Lookup::is_valid` should catch this during `Lookup` creation, maybe it was not \
-                called?."
-            );
+            // Remap can create a lookup without a contained segment, the root `.` path, which
+            // has no entry in the fields. A lookup starting with an index doesn't either.
+            return Err(PathError::InvalidPath { path, segment: 0 });
         };
 
-        for (_index, segment) in walker {
+        for (index, segment) in walker {
             current_pointer = match (segment, current_pointer) {
                 (Segment::Field(field), Entry::Occupied(entry)) => match entry.into_mut() {
                     Value::Map(map) => map.entry(field),
-                    v => return Err(format!("Looking up field on a non-map value: {:?}", v).into()),
+                    _ => {
+                        return Err(PathError::NotAMap {
+                            path,
+                            segment: index,
+                        })
+                    }
                 },
-                (Segment::Field(field), Entry::Vacant(entry)) => {
-                    return Err(format!(
-                        "Tried to step into `{}` of `{}`, but it did not exist.",
-                        field,
-                        entry.key()
-                    )
-                    .into());
+                (Segment::Field(_), Entry::Vacant(_)) => {
+                    return Err(PathError::MissingField {
+                        path,
+                        segment: index - 1,
+                    });
+                }
+                // The entry API cannot yet descend into array indices.
+                _ => {
+                    return Err(PathError::UnsupportedSegment {
+                        path,
+                        segment: index,
+                    })
                 }
-                _ => return Err("The entry API cannot yet descend into array indices.".into()),
             };
         }
         Ok(current_pointer)
     }
 
-    /// Merge all fields specified at `fields` from `incoming` to `current`.
-    pub fn merge(&mut self, mut incoming: LogEvent, fields: &[impl AsRef<str>]) {
+    /// Create a `LogEvent` from a message, stamping the message and the
+    /// current time at the keys of the given `LogSchema`, along with the
+    /// offset of its `timestamp_timezone` if that isn't UTC. An id is minted
+    /// for it if `telemetry.event_ids` is enabled.
+    pub fn from_bytes_with_schema(message: impl Into<Bytes>, log_schema: &LogSchema) -> Self {
+        let mut log = LogEvent::default();
+        if event_ids_enabled() {
+            log.metadata.ensure_event_id();
+        }
+
+        log.insert(log_schema.message_key(), message.into());
+        log.insert_timestamp_with_schema(Utc::now(), log_schema);
+
+        log
+    }
+
+    fn insert_timestamp_with_schema(&mut self, timestamp: DateTime<Utc>, log_schema: &LogSchema) {
+        self.insert(log_schema.timestamp_key(), timestamp);
+        self.insert_timestamp_offset(log_schema);
+    }
+
+    /// Store the offset from UTC of the `timestamp_timezone` of the given `LogSchema` at the time
+    /// of the timestamp of the event, at its `timestamp_offset_key`. Nothing is stored if that
+    /// time zone is UTC, the event has no timestamp, or it has an offset already.
+    pub fn insert_timestamp_offset(&mut self, log_schema: &LogSchema) {
+        if self.contains(log_schema.timestamp_offset_key()) {
+            return;
+        }
+        let timestamp = match self.get(log_schema.timestamp_key()) {
+            Some(Value::Timestamp(timestamp)) => *timestamp,
+            _ => return,
+        };
+        if let Some(offset) = log_schema.timestamp_timezone().offset_at(timestamp) {
+            self.insert(log_schema.timestamp_offset_key(), offset.to_string());
+        }
+    }
+
+    /// Serialize the fields directly to JSON bytes.
+    ///
+    /// This produces the same output as converting into a `serde_json::Value`
+    /// and serializing that, without materializing the intermediate tree. The fields compressed
+    /// with [`compress_field`](Self::compress_field) are written as the values they were
+    /// compressed from.
+    ///
+    /// # Errors
+    ///
+    /// This function will fail if any field can't be represented as JSON, or a compressed field
+    /// can't be decompressed.
+    pub fn to_json_vec(&self) -> Result<Vec<u8>, serde_json::Error> {
+        self.to_json_vec_with(CompressedJson::Decompress)
+    }
+
+    /// Like [`to_json_vec`](Self::to_json_vec), but the fields compressed with
+    /// [`compress_field`](Self::compress_field) are written as `compressed` says.
+    ///
+    /// # Errors
+    ///
+    /// This function will fail if any field can't be represented as JSON, or a compressed field
+    /// can't be decompressed.
+    pub fn to_json_vec_with(
+        &self,
+        compressed: CompressedJson,
+    ) -> Result<Vec<u8>, serde_json::Error> {
+        match self
+            .fields_for_output(compressed)
+            .map_err(serde_json::Error::io)?
+        {
+            Some(fields) => serde_json::to_vec(&fields),
+            None => serde_json::to_vec(&*self.fields),
+        }
+    }
+
+    /// A copy of the fields with the compressed ones written as `compressed` says, or `None` if
+    /// no field is compressed.
+    fn fields_for_output(
+        &self,
+        compressed: CompressedJson,
+    ) -> io::Result<Option<BTreeMap<String, Value>>> {
+        let mut fields = self.compressed_fields().peekable();
+        if fields.peek().is_none() {
+            return Ok(None);
+        }
+        let mut copy = Self::from((*self.fields).clone());
+        for (path, codec) in fields {
+            if let Some(Value::Bytes(bytes)) = copy.get_mut(path) {
+                *bytes = match compressed {
+                    CompressedJson::Decompress => compressed::decompress(bytes, codec)?,
+                    CompressedJson::Base64 => base64::encode(&bytes[..]).into(),
+                };
+            }
+        }
+        Ok(Some(copy.into_parts().0))
+    }
+
+    /// Compress the string at `path` in place with `codec`. It is replaced by the compressed bytes
+    /// and recorded as compressed in the event metadata until
+    /// [`decompress_field`](Self::decompress_field) is called on it, so that a large value costs
+    /// less to hold and to send to another Vector. Writing another value to the field drops the
+    /// record.
+    ///
+    /// # Errors
+    ///
+    /// This function will fail if there is no string at `path`, it is compressed already or
+    /// compressing it fails.
+    pub fn compress_field(
+        &mut self,
+        path: impl AsRef<str>,
+        codec: FieldCodec,
+    ) -> Result<(), CompressionError> {
+        let path = path.as_ref();
+        if let Some(codec) = self.compressed_codec(path) {
+            return Err(CompressionError::AlreadyCompressed {
+                path: path.to_owned(),
+                codec,
+            });
+        }
+        let bytes = self.compressed_target(path)?;
+        let data = compressed::compress(bytes, codec).context(error::Codec {
+            path,
+            action: "compress",
+        })?;
+        *bytes = data.clone();
+        self.metadata
+            .record_compressed_field(path, CompressedField::new(codec, data));
+        Ok(())
+    }
+
+    /// Restore the value at `path` that was compressed with `codec` by
+    /// [`compress_field`](Self::compress_field).
+    ///
+    /// # Errors
+    ///
+    /// This function will fail if the value at `path` isn't compressed, is compressed with
+    /// another codec or can't be decompressed, which includes expanding to more than 64 MiB.
+    pub fn decompress_field(
+        &mut self,
+        path: impl AsRef<str>,
+        codec: FieldCodec,
+    ) -> Result<(), CompressionError> {
+        let path = path.as_ref();
+        let actual = self.compressed_codec(path);
+        let bytes = self.compressed_target(path)?;
+        match actual {
+            None => Err(CompressionError::NotCompressed {
+                path: path.to_owned(),
+            }),
+            Some(actual) if actual != codec => Err(CompressionError::WrongCodec {
+                path: path.to_owned(),
+                expected: codec,
+                actual,
+            }),
+            Some(_) => {
+                *bytes = compressed::decompress(bytes, codec).context(error::Codec {
+                    path,
+                    action: "decompress",
+                })?;
+                self.metadata.remove_compressed_field(path);
+                Ok(())
+            }
+        }
+    }
+
+    /// Restore every field compressed with [`compress_field`](Self::compress_field), such as
+    /// before handing the event to something that reads its values directly.
+    ///
+    /// # Errors
+    ///
+    /// This function will fail if a field can't be decompressed. The fields restored before it
+    /// stay restored.
+    pub fn decompress_fields(&mut self) -> Result<(), CompressionError> {
+        let fields = self
+            .compressed_fields()
+            .map(|(path, codec)| (path.to_owned(), codec))
+            .collect::<Vec<_>>();
+        for (path, codec) in fields {
+            self.decompress_field(path, codec)?;
+        }
+        Ok(())
+    }
+
+    /// The paths of the fields compressed with [`compress_field`](Self::compress_field), and
+    /// their codecs.
+    pub fn compressed_fields(&self) -> impl Iterator<Item = (&str, FieldCodec)> + '_ {
+        self.metadata
+            .compressed_fields()
+            .filter(move |(path, field)| self.holds_compressed(path, field))
+            .map(|(path, field)| (path, field.codec()))
+    }
+
+    /// Record the field at `path` as compressed with `codec`, as it was in the Vector the event
+    /// was decoded from. Nothing is recorded unless the field holds bytes.
+    pub(super) fn restore_compressed_field(&mut self, path: String, codec: FieldCodec) {
+        if let Some(Value::Bytes(bytes)) = self.get(&path) {
+            let field = CompressedField::new(codec, bytes.clone());
+            self.metadata.record_compressed_field(path, field);
+        }
+    }
+
+    /// The codec of the field at `path`, if it is compressed.
+    fn compressed_codec(&self, path: &str) -> Option<FieldCodec> {
+        self.metadata
+            .compressed_field(path)
+            .filter(|field| self.holds_compressed(path, field))
+            .map(CompressedField::codec)
+    }
+
+    /// Whether the value at `path` is still the one `field` recorded as compressed.
+    fn holds_compressed(&self, path: &str, field: &CompressedField) -> bool {
+        matches!(self.get(path), Some(Value::Bytes(bytes)) if field.holds(bytes))
+    }
+
+    fn compressed_target(&mut self, path: &str) -> Result<&mut Bytes, CompressionError> {
+        match self.get_mut(path) {
+            Some(Value::Bytes(bytes)) => Ok(bytes),
+            Some(value) => Err(CompressionError::NotBytes {
+                path: path.to_owned(),
+                kind: value.value_kind(),
+            }),
+            None => Err(CompressionError::MissingValue {
+                path: path.to_owned(),
+            }),
+        }
+    }
+
+    /// Serialize the fields to the canonical JSON form documented in `canonical_json.rs`: keys
+    /// sorted, no whitespace, shortest round-trip floats, timestamps in RFC 3339 with nanosecond
+    /// precision, and bytes that aren't valid UTF-8 in base64. Unlike [`LogEvent::to_json_vec`],
+    /// equal events always serialize to the same bytes, so the output can be hashed or diffed.
+    pub fn to_canonical_json(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        canonical_json::write_map(&self.fields, &mut out);
+        out
+    }
+
+    /// Serialize the fields directly as JSON into the given writer.
+    ///
+    /// # Errors
+    ///
+    /// This function will fail if any field can't be represented as JSON or
+    /// writing fails.
+    pub fn to_json_writer(&self, writer: impl io::Write) -> Result<(), serde_json::Error> {
+        serde_json::to_writer(writer, &*self.fields)
+    }
+
+    /// Parse a JSON object into a log event, building the values while parsing rather than going
+    /// through a `serde_json::Value` first. The event is the same as
+    /// `LogEvent::try_from(serde_json::from_slice(json)?)` gives, with fewer allocations.
+    ///
+    /// # Errors
+    ///
+    /// Fails if `json` isn't valid JSON or isn't an object.
+    pub fn from_json_slice(json: &[u8]) -> Result<Self, serde_json::Error> {
+        serde_json::from_slice(json).map(|JsonFields(fields)| fields.into())
+    }
+
+    /// Like [`from_json_slice`](Self::from_json_slice), reading the JSON from `reader`, which
+    /// is best buffered.
+    ///
+    /// # Errors
+    ///
+    /// Fails if reading fails, or the JSON isn't valid or isn't an object.
+    pub fn from_json_reader(reader: impl io::Read) -> Result<Self, serde_json::Error> {
+        serde_json::from_reader(reader).map(|JsonFields(fields)| fields.into())
+    }
+
+    /// Merge all fields specified at `fields` from `incoming` to `current`. Arrays of maps are
+    /// appended to, other values merged with [`Value::merge`], see [`FieldMergeStrategy`].
+    pub fn merge(&mut self, incoming: LogEvent, fields: &[impl AsRef<str>]) {
+        let defaults: &[(&str, FieldMergeStrategy)] = &[];
+        self.merge_with_strategies(incoming, fields, defaults);
+    }
+
+    /// Like [`merge`](Self::merge), but the fields listed in `strategies` are merged with the
+    /// strategy given for them instead of the default one. Only the fields in `fields` are merged
+    /// either way.
+    pub fn merge_with_strategies(
+        &mut self,
+        mut incoming: LogEvent,
+        fields: &[impl AsRef<str>],
+        strategies: &[(impl AsRef<str>, FieldMergeStrategy)],
+    ) {
         for field in fields {
+            let field = field.as_ref();
             let incoming_val = match incoming.remove(field) {
                 None => continue,
                 Some(val) => val,
             };
-            match self.get_mut(&field) {
+            match self.get_mut(field) {
                 None => {
                     self.insert(field, incoming_val);
                 }
-                Some(current_val) => current_val.merge(incoming_val),
+                Some(current_val) => {
+                    let strategy = strategies
+                        .iter()
+                        .find(|(strategy_field, _)| strategy_field.as_ref() == field)
+                        .map_or_else(
+                            || FieldMergeStrategy::default_for(current_val, &incoming_val),
+                            |(_, strategy)| *strategy,
+                        );
+                    strategy.apply(current_val, incoming_val);
+                }
             }
         }
         self.metadata.merge(incoming.metadata);
     }
+
+    /// Like [`merge`](Self::merge), but records `source_label` and the ingest timestamp of
+    /// `incoming` as the provenance of each field it contributed to, for
+    /// [`provenance`](Self::provenance) to tell where a merged value came from.
+    pub fn merge_with_provenance(
+        &mut self,
+        incoming: LogEvent,
+        fields: &[impl AsRef<str>],
+        source_label: impl Into<Arc<str>>,
+    ) {
+        let merged = fields
+            .iter()
+            .map(AsRef::as_ref)
+            .filter(|field| incoming.contains(field))
+            .map(str::to_owned)
+            .collect::<Vec<_>>();
+        let entry = ProvenanceEntry {
+            label: source_label.into(),
+            ingest_timestamp: incoming.metadata.ingest_timestamp(),
+        };
+        self.merge(incoming, fields);
+        for field in merged {
+            self.metadata.record_provenance(field, entry.clone());
+        }
+    }
+
+    /// The events that contributed to the field at `path` when merged with
+    /// [`merge_with_provenance`](Self::merge_with_provenance), oldest first.
+    pub fn provenance(&self, path: impl AsRef<str>) -> Option<&[ProvenanceEntry]> {
+        self.metadata.provenance(path.as_ref())
+    }
+}
+
+/// When [`LogEvent::try_insert_mode`] inserts its value.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum InsertMode {
+    /// Only when the field is absent, as [`LogEvent::try_insert`] does.
+    IfAbsent,
+    /// When the field is absent or `null`, as when filling in a default such as the time an
+    /// event was received at.
+    IfAbsentOrNull,
+    /// Always, as [`LogEvent::insert`] does.
+    Always,
+}
+
+/// How [`LogEvent::merge_with_strategies`] combines a field of the incoming event with the same
+/// field of the current one.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum FieldMergeStrategy {
+    /// Concatenate bytes, and replace values of any other kind, as [`Value::merge`] does. The
+    /// default, except for arrays of maps.
+    Concat,
+    /// Add the incoming elements after the current ones. A value that isn't an array is added as
+    /// a single element. The default when both values are arrays of nothing but maps, such as the
+    /// frames of a stack trace spread over several events.
+    Append,
+    /// Replace the current value with the incoming one.
+    Replace,
+}
+
+impl FieldMergeStrategy {
+    /// The strategy to merge `incoming` into `current` with when none was given.
+    fn default_for(current: &Value, incoming: &Value) -> Self {
+        let is_array_of_maps = |value: &Value| match value {
+            Value::Array(array) => array.iter().all(|value| matches!(value, Value::Map(_))),
+            _ => false,
+        };
+        if is_array_of_maps(current) && is_array_of_maps(incoming) {
+            Self::Append
+        } else {
+            Self::Concat
+        }
+    }
+
+    fn apply(self, current: &mut Value, incoming: Value) {
+        match self {
+            Self::Concat => current.merge(incoming),
+            Self::Append => {
+                if !matches!(current, Value::Array(_)) {
+                    let first = std::mem::replace(current, Value::Array(Vec::new()));
+                    current.as_array_mut().push(first);
+                }
+                match incoming {
+                    Value::Array(elements) => current.as_array_mut().extend(elements),
+                    element => current.as_array_mut().push(element),
+                }
+            }
+            Self::Replace => *current = incoming,
+        }
+    }
+}
+
+/// Truncate `bytes` as described by [`LogEvent::truncate_field`].
+fn truncate_bytes(bytes: &mut Bytes, max_bytes: usize, marker: Option<&str>) -> bool {
+    if bytes.len() <= max_bytes {
+        return false;
+    }
+
+    let marker = marker
+        .filter(|marker| marker.len() <= max_bytes)
+        .unwrap_or("");
+    let max_len = max_bytes - marker.len();
+    let len = match std::str::from_utf8(bytes) {
+        Ok(string) => (0..=max_len)
+            .rev()
+            .find(|len| string.is_char_boundary(*len))
+            .unwrap_or(0),
+        Err(_) => max_len,
+    };
+    if marker.is_empty() {
+        bytes.truncate(len);
+    } else {
+        let mut truncated = BytesMut::with_capacity(len + marker.len());
+        truncated.extend_from_slice(&bytes[..len]);
+        truncated.extend_from_slice(marker.as_bytes());
+        *bytes = truncated.freeze();
+    }
+    true
+}
+
+/// Write the given events as newline-delimited JSON, one event per line.
+///
+/// # Errors
+///
+/// This function will fail if any event can't be represented as JSON or
+/// writing fails.
+pub fn write_ndjson<'a>(
+    events: impl IntoIterator<Item = &'a LogEvent>,
+    mut writer: impl io::Write,
+) -> Result<(), serde_json::Error> {
+    for event in events {
+        event.to_json_writer(&mut writer)?;
+        writer.write_all(b"\n").map_err(serde_json::Error::io)?;
+    }
+    Ok(())
 }
 
 impl MaybeAsLogMut for LogEvent {
@@ -300,12 +1308,7 @@ impl EventDataEq for LogEvent {
 
 impl From<Bytes> for LogEvent {
     fn from(message: Bytes) -> Self {
-        let mut log = LogEvent::default();
-
-        log.insert(log_schema().message_key(), message);
-        log.insert(log_schema().timestamp_key(), Utc::now());
-
-        log
+        LogEvent::from_bytes_with_schema(message, log_schema())
     }
 }
 
@@ -324,7 +1327,7 @@ impl From<String> for LogEvent {
 impl From<BTreeMap<String, Value>> for LogEvent {
     fn from(map: BTreeMap<String, Value>) -> Self {
         LogEvent {
-            fields: Value::Map(map),
+            fields: Arc::new(map),
             metadata: EventMetadata::default(),
         }
     }
@@ -332,17 +1335,67 @@ impl From<BTreeMap<String, Value>> for LogEvent {
 
 impl From<LogEvent> for BTreeMap<String, Value> {
     fn from(event: LogEvent) -> BTreeMap<String, Value> {
-        match event.fields {
-            Value::Map(map) => map,
-            _ => unreachable!(),
+        event.into_parts().0
+    }
+}
+
+#[cfg(feature = "vrl")]
+impl From<&LogEvent> for vrl_core::Value {
+    fn from(log: &LogEvent) -> Self {
+        vrl_core::Value::Object(
+            log.fields
+                .iter()
+                .map(|(key, value)| (key.clone(), value.into()))
+                .collect(),
+        )
+    }
+}
+
+/// The fields a log event may still have before it has more than `max_fields`, for building one
+/// under that limit. Every field counts, those of maps nested at any depth included.
+pub(super) struct FieldBudget {
+    max_fields: usize,
+    remaining: usize,
+}
+
+impl FieldBudget {
+    pub(super) const fn new(max_fields: usize) -> Self {
+        Self {
+            max_fields,
+            remaining: max_fields,
+        }
+    }
+
+    /// Count one field.
+    pub(super) fn take(&mut self) -> Result<(), FieldCountError> {
+        self.remaining = self
+            .remaining
+            .checked_sub(1)
+            .ok_or(FieldCountError::TooManyFields {
+                max_fields: self.max_fields,
+            })?;
+        Ok(())
+    }
+
+    /// Count the fields of the maps in `value`, at every depth, stopping at the first one over.
+    pub(super) fn take_nested(&mut self, value: &Value) -> Result<(), FieldCountError> {
+        match value {
+            Value::Map(map) => map.values().try_for_each(|value| {
+                self.take()?;
+                self.take_nested(value)
+            }),
+            Value::Array(array) => array.iter().try_for_each(|value| self.take_nested(value)),
+            _ => Ok(()),
         }
     }
 }
 
+/// Takes O(n log n) to sort the entries into the fields, with nothing to stop a huge map. See
+/// [`LogEvent::try_from_fields`] to refuse maps of too many entries.
 impl From<HashMap<String, Value>> for LogEvent {
     fn from(map: HashMap<String, Value>) -> Self {
         LogEvent {
-            fields: map.into_iter().collect(),
+            fields: Arc::new(map.into_iter().collect()),
             metadata: EventMetadata::default(),
         }
     }
@@ -363,12 +1416,7 @@ impl TryFrom<serde_json::Value> for LogEvent {
 
     fn try_from(map: serde_json::Value) -> Result<Self, Self::Error> {
         match map {
-            serde_json::Value::Object(fields) => Ok(LogEvent::from(
-                fields
-                    .into_iter()
-                    .map(|(k, v)| (k, v.into()))
-                    .collect::<BTreeMap<_, _>>(),
-            )),
+            serde_json::Value::Object(fields) => Ok(LogEvent::from_json_object(fields)),
             _ => Err(crate::Error::from(
                 "Attempted to convert non-Object JSON into a LogEvent.",
             )),
@@ -380,7 +1428,7 @@ impl TryInto<serde_json::Value> for LogEvent {
     type Error = crate::Error;
 
     fn try_into(self) -> Result<serde_json::Value, Self::Error> {
-        Ok(serde_json::to_value(self.fields)?)
+        Ok(serde_json::to_value(&*self.fields)?)
     }
 }
 
@@ -408,7 +1456,9 @@ where
     }
 }
 
-// Allow converting any kind of appropriate key/value iterator directly into a LogEvent.
+// Allow converting any kind of appropriate key/value iterator directly into a LogEvent. Each key
+// is parsed as a path and inserted on its own, see `LogEvent::try_from_fields` for top-level
+// fields in bulk.
 impl<K: AsRef<str>, V: Into<Value>> FromIterator<(K, V)> for LogEvent {
     fn from_iter<T: IntoIterator<Item = (K, V)>>(iter: T) -> Self {
         let mut log_event = Self::default();
@@ -417,12 +1467,66 @@ impl<K: AsRef<str>, V: Into<Value>> FromIterator<(K, V)> for LogEvent {
     }
 }
 
+/// Serialized through a [`ValueVisitor`](super::ValueVisitor), as [`Value`] is. Only an event with
+/// compressed fields is copied, to write them decompressed.
 impl Serialize for LogEvent {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: Serializer,
     {
-        serializer.collect_map(self.as_map().iter())
+        use serde::ser::Error;
+
+        match self
+            .fields_for_output(CompressedJson::Decompress)
+            .map_err(S::Error::custom)?
+        {
+            Some(fields) => visit::serialize_map(&fields, serializer),
+            None => visit::serialize_map(self.as_map(), serializer),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for LogEvent {
+    /// Only accepts a map, such as a JSON object or a TOML table. Anything else fails with an
+    /// error naming what was found instead, see [`LogEvent::deserialize_lenient`] to accept it.
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        json_ingest::deserialize_fields(deserializer).map(Into::into)
+    }
+}
+
+impl LogEvent {
+    /// Like deserializing a `LogEvent`, but a value that isn't a map is placed at the message key
+    /// of the global log schema, with the current time at the timestamp key, the way an event is
+    /// built from `Bytes`. Can be used with
+    /// `#[serde(deserialize_with = "LogEvent::deserialize_lenient")]`.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the input can't be deserialized at all.
+    pub fn deserialize_lenient<'de, D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Ok(match json_ingest::deserialize_value(deserializer)? {
+            Value::Map(fields) => fields.into(),
+            other => {
+                let mut log = LogEvent::default();
+                log.insert(log_schema().message_key(), other);
+                log.insert(log_schema().timestamp_key(), Utc::now());
+                log
+            }
+        })
+    }
+
+    fn from_json_object(fields: serde_json::Map<String, serde_json::Value>) -> Self {
+        fields
+            .into_iter()
+            .map(|(key, value)| (key, Value::from(value)))
+            .collect::<BTreeMap<_, _>>()
+            .into()
     }
 }
 
@@ -483,19 +1587,472 @@ impl tracing::field::Visit for MakeLogEvent {
         };
     }
 
-    fn record_bool(&mut self, field: &tracing::field::Field, value: bool) {
-        self.0.insert(field.name(), value);
-    }
-}
+    fn record_bool(&mut self, field: &tracing::field::Field, value: bool) {
+        self.0.insert(field.name(), value);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{fs, path::Path, str::FromStr};
+
+    use serde_json::json;
+    use shared::btreemap;
+
+    use super::*;
+    use crate::{
+        event::{BatchStatus, ComparisonOp, Event, EventStatus, Literal},
+        test_util::{fixture_log_events, open_fixture, open_fixture_ndjson, EventGenerator},
+    };
+
+    #[test]
+    fn try_from_fields_limits_the_field_count() {
+        let fields = |count: usize| {
+            (0..count)
+                .map(|i| (format!("field{}", i), Value::from(i as i64)))
+                .collect::<HashMap<_, _>>()
+        };
+
+        let log = LogEvent::try_from_fields(fields(3), 3).unwrap();
+        assert_eq!(log.as_map().len(), 3);
+        assert_eq!(log["field2"], Value::from(2));
+        assert_eq!(
+            LogEvent::try_from_fields(fields(4), 3).unwrap_err(),
+            FieldCountError::TooManyFields { max_fields: 3 }
+        );
+
+        // Names are taken as they are, not parsed as paths.
+        let log = LogEvent::try_from_fields(vec![("a.b".to_owned(), Value::from(1))], 1).unwrap();
+        assert!(log.as_map().contains_key("a.b"));
+
+        // Without a size hint the fields are counted as they are taken, and no more are taken
+        // past the limit.
+        let mut taken = 0;
+        let counting = fields(5).into_iter().filter(|_| {
+            taken += 1;
+            true
+        });
+        assert_eq!(
+            LogEvent::try_from_fields(counting, 3).unwrap_err(),
+            FieldCountError::TooManyFields { max_fields: 3 }
+        );
+        assert_eq!(taken, 4);
+
+        // The fields of nested maps count too, including those of maps within arrays.
+        let nested = || {
+            vec![(
+                "a".to_owned(),
+                Value::from(btreemap! {
+                    "b" => vec![Value::from(btreemap! { "c" => 1, "d" => 2 })],
+                }),
+            )]
+        };
+        assert_eq!(
+            LogEvent::try_from_fields(nested(), 4).unwrap()["a.b[0].d"],
+            2.into()
+        );
+        assert_eq!(
+            LogEvent::try_from_fields(nested(), 3).unwrap_err(),
+            FieldCountError::TooManyFields { max_fields: 3 }
+        );
+    }
+
+    #[test]
+    fn lookups_take_keys_as_they_are() {
+        let mut log = LogEvent::default();
+        let keys = ["a.b", "c[0]", r#""quoted""#, "line\nbreak", "plain"];
+        for key in keys {
+            let path = LookupBuf::root().push_field(key).push_field(key);
+            assert_eq!(log.insert(&path, key), None);
+            assert_eq!(log.get(&path), Some(&Value::from(key)));
+            assert!(log.contains(&path));
+        }
+        for key in keys {
+            assert_eq!(log.as_map()[key], Value::from(btreemap! { key => key }));
+        }
+        // None of the keys were parsed as paths.
+        assert!(log.get("a.b").is_none());
+        assert!(log.get("c[0]").is_none());
+
+        let path = LookupBuf::root().push_field("a.b").push_field("a.b");
+        assert_eq!(log.remove_prune(&path, true), Some(Value::from("a.b")));
+        assert!(!log.contains(&path));
+        assert!(!log.as_map().contains_key("a.b"));
+        let path = LookupBuf::root().push_field("plain").push_field("plain");
+        assert_eq!(log.remove(path), Some(Value::from("plain")));
+        assert_eq!(log.as_map()["plain"], Value::from(BTreeMap::new()));
+    }
+
+    #[test]
+    fn lookups_follow_indices() {
+        let mut log = LogEvent::default();
+        let path = LookupBuf::root().push_field("x.y").push_index(1);
+        assert_eq!(log.insert(&path, 2), None);
+        assert_eq!(
+            log.as_map()["x.y"],
+            Value::from(vec![Value::Null, 2.into()])
+        );
+        assert_eq!(log.get(&path), Some(&Value::from(2)));
+
+        // Neither the root nor an index counting from the end stand for a field.
+        let before = log.clone();
+        for path in [
+            LookupBuf::root(),
+            LookupBuf::root().push_field("x.y").push_index(-1),
+        ] {
+            assert_eq!(log.get(&path), None);
+            assert!(!log.contains(&path));
+            assert_eq!(log.insert(&path, 3), None);
+            assert!(log.insert_checked(&path, 3).is_err());
+            assert_eq!(log.remove(&path), None);
+        }
+        assert_eq!(log, before);
+    }
+
+    #[test]
+    fn insert_checked_lookup() {
+        let mut log = LogEvent::default();
+        log.insert("a.b", "scalar");
+        let error = log
+            .insert_checked(
+                LookupBuf::root()
+                    .push_field("a")
+                    .push_field("b")
+                    .push_field("c"),
+                1,
+            )
+            .unwrap_err();
+        assert_eq!(error.segment(), 2);
+        assert_eq!(log.get("a.b"), Some(&Value::from("scalar")));
+
+        let path = LookupBuf::root().push_field("a").push_field("b.c");
+        assert_eq!(log.insert_checked(&path, 1), Ok(None));
+        assert_eq!(log.get(&path), Some(&Value::from(1)));
+        assert_eq!(log.get("a.b"), Some(&Value::from("scalar")));
+
+        let error = log
+            .insert_checked(LookupBuf::root().push_field("a").push_index(-1), 1)
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            PathError::UnsupportedSegment { segment: 1, .. }
+        ));
+    }
+
+    #[test]
+    fn clone_subset_keeps_paths_and_metadata() {
+        let mut log = LogEvent::try_from(json!({
+            "message": "hello",
+            "host": "edge-1",
+            "http": {
+                "request": { "method": "GET", "path": "/" },
+                "status": 200,
+            },
+            "spans": [{ "id": 1 }, { "id": 2 }],
+        }))
+        .unwrap();
+        log.metadata_mut().set_dropped("filtered");
+
+        let subset = log.clone_subset(&[
+            "message",
+            "http.request",
+            "http.request.method",
+            "spans[1].id",
+            "missing",
+            "bad..path",
+        ]);
+        let json: serde_json::Value = subset.clone().try_into().unwrap();
+        assert_eq!(
+            json,
+            json!({
+                "message": "hello",
+                "http": {
+                    "request": { "method": "GET", "path": "/" },
+                },
+                "spans": [null, { "id": 2 }],
+            })
+        );
+        assert_eq!(subset.metadata().dropped(), Some("filtered"));
+        // The event it was cloned from is left as it was.
+        assert!(log.contains("host"));
+        assert!(log.contains("http.status"));
+    }
+
+    #[test]
+    fn merge_with_provenance_records_contributors() {
+        use chrono::TimeZone;
+
+        let event = |fields: BTreeMap<String, Value>, secs: i64| {
+            let mut log = LogEvent::from(fields);
+            log.metadata_mut()
+                .set_ingest_timestamp(Utc.timestamp(secs, 0));
+            log
+        };
+        let first = event(btreemap! { "message" => "a", "host" => "one" }, 1);
+        let second = event(btreemap! { "message" => "b" }, 2);
+        let third = event(btreemap! { "message" => "c", "host" => "three" }, 3);
+
+        let mut merged = LogEvent::default();
+        assert!(merged.metadata().provenance("message").is_none());
+        let fields = ["message", "host"];
+        merged.merge_with_provenance(first, &fields, "first");
+        merged.merge_with_provenance(second, &fields, "second");
+        merged.merge_with_provenance(third, &fields, "third");
+
+        let chain = |path: &str| {
+            merged
+                .provenance(path)
+                .unwrap()
+                .iter()
+                .map(|entry| {
+                    (
+                        entry.label.to_string(),
+                        entry.ingest_timestamp.unwrap().timestamp(),
+                    )
+                })
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            chain("message"),
+            vec![
+                ("first".to_owned(), 1),
+                ("second".to_owned(), 2),
+                ("third".to_owned(), 3)
+            ]
+        );
+        assert_eq!(
+            chain("host"),
+            vec![("first".to_owned(), 1), ("third".to_owned(), 3)]
+        );
+        assert_eq!(merged.provenance("missing"), None);
+        // The provenance stays out of the fields.
+        assert_eq!(merged.keys().collect::<Vec<_>>(), vec!["host", "message"]);
+    }
+
+    #[test]
+    fn merging_events_keeps_their_provenance() {
+        let mut left = LogEvent::from(btreemap! { "message" => "a" });
+        left.merge_with_provenance(
+            LogEvent::from(btreemap! { "message" => "b" }),
+            &["message"],
+            "b",
+        );
+        let mut right = LogEvent::from(btreemap! { "message" => "c" });
+        right.merge_with_provenance(
+            LogEvent::from(btreemap! { "message" => "d" }),
+            &["message"],
+            "d",
+        );
+
+        left.merge_with_provenance(right, &["message"], "right");
+        let labels = left
+            .provenance("message")
+            .unwrap()
+            .iter()
+            .map(|entry| entry.label.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(labels, vec!["b", "d", "right"]);
+    }
+
+    #[test]
+    fn compress_field_round_trip() {
+        let body = "a large, repetitive body ".repeat(100);
+        for codec in [FieldCodec::Gzip, FieldCodec::Zstd] {
+            let mut log = LogEvent::from(btreemap! {
+                "request" => Value::from(btreemap! { "body" => body.clone() }),
+                "message" => "hello",
+            });
+
+            log.compress_field("request.body", codec).unwrap();
+            let compressed = log.get("request.body").unwrap().as_bytes();
+            assert!(compressed.len() < body.len());
+            assert_eq!(
+                log.compressed_fields().collect::<Vec<_>>(),
+                vec![("request.body", codec)]
+            );
+
+            log.decompress_field("request.body", codec).unwrap();
+            assert_eq!(log.get("request.body"), Some(&Value::from(body.clone())));
+            assert_eq!(log.compressed_fields().count(), 0);
+        }
+    }
+
+    #[test]
+    fn compress_field_errors() {
+        let mut log = LogEvent::from(btreemap! {
+            "body" => "hello",
+            "count" => 1,
+        });
+
+        assert!(matches!(
+            log.compress_field("missing", FieldCodec::Gzip),
+            Err(CompressionError::MissingValue { .. })
+        ));
+        assert!(matches!(
+            log.compress_field("count", FieldCodec::Gzip),
+            Err(CompressionError::NotBytes {
+                kind: ValueKind::Integer,
+                ..
+            })
+        ));
+        assert!(matches!(
+            log.decompress_field("body", FieldCodec::Gzip),
+            Err(CompressionError::NotCompressed { .. })
+        ));
+
+        log.compress_field("body", FieldCodec::Gzip).unwrap();
+        assert!(matches!(
+            log.compress_field("body", FieldCodec::Zstd),
+            Err(CompressionError::AlreadyCompressed {
+                codec: FieldCodec::Gzip,
+                ..
+            })
+        ));
+        let error = log.decompress_field("body", FieldCodec::Zstd).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Field `body` is compressed with gzip, not zstd."
+        );
+        // Neither error touched the value.
+        log.decompress_field("body", FieldCodec::Gzip).unwrap();
+        assert_eq!(log.get("body"), Some(&Value::from("hello")));
+    }
+
+    #[test]
+    fn compressed_fields_are_tracked_outside_the_value() {
+        let body = "a large, repetitive body ".repeat(100);
+        let mut log = LogEvent::from(btreemap! { "body" => body.clone() });
+        log.compress_field("body", FieldCodec::Zstd).unwrap();
+        let compressed = log.get("body").cloned().unwrap();
+
+        // The same bytes in another field, or in another event, aren't compressed.
+        log.insert("copy", compressed.clone());
+        let mut other = LogEvent::from(btreemap! { "body" => compressed.as_bytes().to_vec() });
+        assert_eq!(
+            log.compressed_fields().collect::<Vec<_>>(),
+            vec![("body", FieldCodec::Zstd)]
+        );
+        assert!(matches!(
+            other.decompress_field("body", FieldCodec::Zstd),
+            Err(CompressionError::NotCompressed { .. })
+        ));
+        assert_eq!(
+            other.to_json_vec().unwrap(),
+            serde_json::to_vec(other.as_map()).unwrap()
+        );
+
+        // Neither is a value written over the field, even when it is the same.
+        log.insert("body", compressed.as_bytes().to_vec());
+        assert_eq!(log.compressed_fields().count(), 0);
+        log.compress_field("body", FieldCodec::Gzip).unwrap();
+
+        // A clone keeps its compressed fields.
+        let mut clone = log.clone();
+        clone.decompress_fields().unwrap();
+        assert_eq!(clone.get("body"), Some(&compressed));
+    }
+
+    #[test]
+    fn to_json_vec_with_compressed_fields() {
+        let mut log = LogEvent::from(btreemap! {
+            "body" => "hello",
+            "nested" => vec![Value::from("world")],
+        });
+        let plain = log.to_json_vec().unwrap();
+        assert_eq!(log.to_json_vec_with(CompressedJson::Base64).unwrap(), plain);
+
+        log.compress_field("body", FieldCodec::Zstd).unwrap();
+        log.compress_field("nested[0]", FieldCodec::Gzip).unwrap();
+        assert_eq!(log.to_json_vec().unwrap(), plain);
+        assert_eq!(serde_json::to_vec(&log).unwrap(), plain);
+
+        let json: serde_json::Value =
+            serde_json::from_slice(&log.to_json_vec_with(CompressedJson::Base64).unwrap()).unwrap();
+        let body = base64::decode(json["body"].as_str().unwrap()).unwrap();
+        assert_eq!(body, log.get("body").unwrap().as_bytes());
+        let element = base64::decode(json["nested"][0].as_str().unwrap()).unwrap();
+        assert_eq!(element, log.get("nested[0]").unwrap().as_bytes());
+    }
+
+    #[test]
+    fn transaction_rolls_back_on_error() {
+        let mut log = LogEvent::from(btreemap! {
+            "keep" => "kept",
+            "gone" => "removed",
+            "old" => "renamed",
+        });
+        let expected = log.clone();
+
+        let result = log.transaction(|log| {
+            log.insert("added.nested", 1);
+            log.insert("keep", "overwritten");
+            log.remove("gone");
+            log.rename_key_flat("old", "new");
+            log.metadata_mut().set_dropped("parse failure");
+            Err("parse failure")
+        });
+
+        assert_eq!(result, Err("parse failure"));
+        assert_eq!(log.as_map(), expected.as_map());
+        assert_eq!(log.metadata().dropped(), Some("parse failure"));
+    }
+
+    #[test]
+    fn transaction_keeps_changes_on_success() {
+        let mut log = LogEvent::from(btreemap! { "old" => "renamed" });
+
+        let result = log.transaction::<()>(|log| {
+            log.insert("added", 1);
+            log.rename_key_flat("old", "new");
+            Ok(())
+        });
+
+        assert_eq!(result, Ok(()));
+        assert_eq!(
+            log.as_map(),
+            &btreemap! {
+                "added" => 1,
+                "new" => "renamed",
+            }
+        );
+    }
+
+    #[test]
+    fn nested_transaction_rolls_back_inner_changes() {
+        let mut log = LogEvent::default();
+
+        let result = log.transaction::<&str>(|log| {
+            log.insert("outer", 1);
+            let inner = log.transaction(|log| {
+                log.insert("inner", 2);
+                log.remove("outer");
+                Err("inner failure")
+            });
+            assert_eq!(inner, Err("inner failure"));
+            log.insert("after", 3);
+            Ok(())
+        });
 
-#[cfg(test)]
-mod test {
-    use std::str::FromStr;
+        assert_eq!(result, Ok(()));
+        assert_eq!(
+            log.as_map(),
+            &btreemap! {
+                "outer" => 1,
+                "after" => 3,
+            }
+        );
 
-    use serde_json::json;
+        let result = log.transaction(|log| {
+            log.transaction::<&str>(|log| {
+                log.insert("inner", 2);
+                Ok(())
+            })?;
+            Err("outer failure")
+        });
 
-    use super::*;
-    use crate::test_util::open_fixture;
+        assert_eq!(result, Err("outer failure"));
+        assert!(!log.contains("inner"));
+    }
 
     // The following two tests assert that renaming a key has no effect if the
     // keys are equivalent, whether the key exists in the log or not.
@@ -599,6 +2156,115 @@ mod test {
         assert_eq!(old, Some("foo".into()));
     }
 
+    #[test]
+    fn clone_is_copy_on_write() {
+        let mut original = LogEvent::default();
+        original.insert("foo", "foo");
+
+        let mut clone = original.clone();
+        assert!(Arc::ptr_eq(&original.fields, &clone.fields));
+
+        clone.insert("foo", "bar");
+        assert!(!Arc::ptr_eq(&original.fields, &clone.fields));
+        assert_eq!(original.get("foo"), Some(&"foo".into()));
+        assert_eq!(clone.get("foo"), Some(&"bar".into()));
+    }
+
+    #[test]
+    fn serde_round_trip_unchanged() {
+        let mut log = LogEvent::default();
+        log.insert("foo.bar", 1);
+        log.insert("baz", "qux");
+
+        let json = serde_json::to_string(&log).unwrap();
+        assert_eq!(json, r#"{"baz":"qux","foo":{"bar":1}}"#);
+
+        let back: LogEvent = serde_json::from_str(&json).unwrap();
+        assert_eq!(back, log);
+    }
+
+    #[test]
+    fn deserialize_non_map_errors() {
+        for input in &[
+            "[1, 2]",
+            r#""message""#,
+            "null",
+            "1",
+            "true",
+            "[{}]",
+            r#"{"message": "a"} {"message": "b"}"#,
+            r#"{"message": "a""#,
+        ] {
+            assert!(
+                serde_json::from_str::<LogEvent>(input).is_err(),
+                "{} deserialized",
+                input
+            );
+        }
+
+        // Non-map values are only rejected at the root, where they would be the fields.
+        let log: LogEvent = serde_json::from_str(r#"{"a": [1, {"b": null}], "c": "d"}"#).unwrap();
+        assert_eq!(log.len_at("a"), Some(2));
+        assert_eq!(log["a[1].b"], Value::Null);
+        assert_eq!(log["c"], Value::from("d"));
+
+        assert!(toml::from_str::<LogEvent>("a = [1, 2]").is_ok());
+        assert!(toml::from_str::<LogEvent>("a = ").is_err());
+
+        #[derive(Debug, Deserialize)]
+        struct Wrapper {
+            #[allow(dead_code)]
+            log: LogEvent,
+        }
+        let error = toml::from_str::<Wrapper>("log = 1").unwrap_err();
+        assert!(
+            error
+                .to_string()
+                .contains("LogEvent must be a JSON object, got number"),
+            "{}",
+            error
+        );
+    }
+
+    #[test]
+    fn deserialize_strict_and_lenient() {
+        let lenient = |input: &str| {
+            LogEvent::deserialize_lenient(&mut serde_json::Deserializer::from_str(input)).unwrap()
+        };
+
+        let object = r#"{"message": "hello", "status": 200}"#;
+        let expected = LogEvent::from(btreemap! { "message" => "hello", "status" => 200 });
+        assert_eq!(serde_json::from_str::<LogEvent>(object).unwrap(), expected);
+        assert_eq!(lenient(object), expected);
+
+        let cases = vec![
+            ("[1, 2, 3]", "array", Value::from(vec![1, 2, 3])),
+            (r#""hello""#, "string", Value::from("hello")),
+            ("1.5", "number", Value::from(1.5)),
+            ("false", "boolean", Value::from(false)),
+            ("null", "null", Value::Null),
+        ];
+        for (input, found, message) in cases {
+            let error = serde_json::from_str::<LogEvent>(input).unwrap_err();
+            assert!(
+                error
+                    .to_string()
+                    .starts_with(&format!("LogEvent must be a JSON object, got {}", found)),
+                "{}",
+                error
+            );
+
+            let log = lenient(input);
+            assert_eq!(log[log_schema().message_key()], message, "{}", input);
+            assert!(log.contains(log_schema().timestamp_key()));
+            assert_eq!(log.keys().count(), 2);
+        }
+
+        assert!(
+            LogEvent::deserialize_lenient(&mut serde_json::Deserializer::from_str("[1,")).is_err()
+        );
+    }
+
     #[test]
     fn try_insert() {
         let mut log = LogEvent::default();
@@ -618,6 +2284,77 @@ mod test {
         assert_eq!(log.get("foo"), Some(&"foo".into()));
     }
 
+    #[test]
+    fn try_insert_mode_null() {
+        for (mode, replaced) in [
+            (InsertMode::IfAbsent, false),
+            (InsertMode::IfAbsentOrNull, true),
+            (InsertMode::Always, true),
+        ] {
+            let mut log = LogEvent::default();
+            log.insert("foo.bar", Value::Null);
+            log.insert("baz", "baz");
+
+            log.try_insert_mode("foo.bar", "bar", mode);
+            log.try_insert_mode("baz", "qux", mode);
+            log.try_insert_mode("absent", "absent", mode);
+
+            let expected = if replaced { "bar".into() } else { Value::Null };
+            assert_eq!(log["foo.bar"], expected, "{:?}", mode);
+            let expected = if mode == InsertMode::Always {
+                "qux"
+            } else {
+                "baz"
+            };
+            assert_eq!(log["baz"], expected.into(), "{:?}", mode);
+            assert_eq!(log["absent"], "absent".into(), "{:?}", mode);
+        }
+    }
+
+    #[test]
+    fn replace_fields_keeps_metadata() {
+        let (batch, mut receiver) = BatchNotifier::new_with_receiver();
+        let mut log = LogEvent::from("raw line").with_batch_notifier(&batch);
+        drop(batch);
+        let event_id = log.metadata_mut().ensure_event_id();
+
+        let old = log.replace_fields(btreemap! { "parsed" => true });
+        assert_eq!(old, btreemap! { "message" => "raw line" });
+        assert_eq!(log.as_map(), &btreemap! { "parsed" => true });
+        assert_eq!(log.metadata().event_id(), Some(event_id));
+
+        // A clone still sees the fields it was made with.
+        let clone = log.clone();
+        assert_eq!(log.take_fields(), btreemap! { "parsed" => true });
+        assert!(log.is_empty());
+        assert_eq!(clone.as_map(), &btreemap! { "parsed" => true });
+        drop(clone);
+
+        // The event still holds the finalizer it was created with.
+        log.metadata().update_status(EventStatus::Rejected);
+        drop(log);
+        assert_eq!(receiver.try_recv(), Ok(BatchStatus::Rejected));
+    }
+
+    #[test]
+    fn insert_if() {
+        let severity = FieldPredicate::Compare {
+            path: CompiledLookup::new("status").unwrap(),
+            op: ComparisonOp::Ge,
+            value: Literal::Integer(500),
+        };
+
+        let mut log = LogEvent::default();
+        log.insert("status", 503);
+        assert!(log.insert_if("severity", "high", &severity));
+        assert_eq!(log["severity"], "high".into());
+
+        let mut log = LogEvent::default();
+        log.insert("status", 200);
+        assert!(!log.insert_if("severity", "high", &severity));
+        assert!(!log.contains("severity"));
+    }
+
     #[test]
     fn try_insert_dotted() {
         let mut log = LogEvent::default();
@@ -639,6 +2376,108 @@ mod test {
         assert_eq!(log.get_flat("foo.bar"), None);
     }
 
+    #[test]
+    fn from_bytes_with_schema() {
+        let mut schema = LogSchema::default();
+        schema.set_message_key("event.msg".to_string());
+        schema.set_timestamp_key("ts".to_string());
+
+        let log = LogEvent::from_bytes_with_schema("hello", &schema);
+
+        assert_eq!(log.get("event.msg"), Some(&"hello".into()));
+        assert!(log.get("ts").is_some());
+        assert_eq!(log.get(log_schema().message_key()), None);
+        assert_eq!(log.get("timestamp_offset"), None);
+    }
+
+    #[test]
+    fn timestamp_offset_across_dst_transitions() {
+        use chrono::TimeZone as _;
+        use shared::TimeZone;
+
+        use crate::config::TimestampTimezone;
+
+        let mut schema = LogSchema::default();
+        schema.set_timestamp_timezone(TimestampTimezone::Zone(
+            TimeZone::parse("Europe/Berlin").unwrap(),
+        ));
+
+        // Berlin moves from +01:00 to +02:00 at 01:00 UTC on the last Sunday of
+        // March, and back at 01:00 UTC on the last Sunday of October.
+        for (timestamp, offset) in [
+            (Utc.ymd(2021, 3, 28).and_hms(0, 59, 59), "+01:00"),
+            (Utc.ymd(2021, 3, 28).and_hms(1, 0, 0), "+02:00"),
+            (Utc.ymd(2021, 10, 31).and_hms(0, 59, 59), "+02:00"),
+            (Utc.ymd(2021, 10, 31).and_hms(1, 0, 0), "+01:00"),
+        ] {
+            let mut log = LogEvent::default();
+            log.insert_timestamp_with_schema(timestamp, &schema);
+            // The timestamp itself stays in UTC.
+            assert_eq!(log.get("timestamp"), Some(&Value::Timestamp(timestamp)));
+            assert_eq!(log.get("timestamp_offset"), Some(&offset.into()));
+        }
+    }
+
+    #[test]
+    fn to_value_is_a_snapshot() {
+        let mut log = LogEvent::from("hello");
+        log.insert("nested.list", vec![1, 2]);
+        let snapshot = log.to_value();
+
+        log.insert("message", "changed");
+        log.insert("nested.list[2]", 3);
+        log.insert("added", true);
+        log.remove("nested.list[0]");
+
+        let expected: BTreeMap<String, Value> = btreemap! {
+            "message" => "hello",
+            "nested" => btreemap! { "list" => vec![1, 2] },
+        };
+        assert_eq!(*snapshot, expected);
+    }
+
+    #[test]
+    fn to_value_shares_the_fields() {
+        let mut log = LogEvent::from("hello");
+        let snapshot = log.to_value();
+        assert!(Arc::ptr_eq(&snapshot, &log.fields));
+
+        log.insert("message", "changed");
+        assert!(!Arc::ptr_eq(&snapshot, &log.fields));
+        assert_eq!(snapshot["message"], "hello".into());
+
+        // Without a snapshot held, the fields are changed in place.
+        drop(snapshot);
+        let fields = Arc::as_ptr(&log.fields);
+        log.insert("message", "again");
+        assert_eq!(Arc::as_ptr(&log.fields), fields);
+    }
+
+    #[test]
+    fn from_value() {
+        let mut log = LogEvent::from("hello");
+        log.insert("count", 1);
+        let mut metadata = EventMetadata::default();
+        metadata.set_datadog_api_key(Some(Arc::from("key")));
+        let fields = Value::Map((*log.to_value()).clone());
+        let round_trip = LogEvent::from_value(fields, metadata.clone()).unwrap();
+        assert_eq!(round_trip.as_map(), log.as_map());
+        assert_eq!(round_trip.metadata(), &metadata);
+
+        assert_eq!(
+            LogEvent::from_value(Value::from(vec![1]), EventMetadata::default()),
+            Err(FromValueError::NotAMap {
+                kind: ValueKind::Array
+            })
+        );
+        assert_eq!(
+            LogEvent::from_value(Value::Null, EventMetadata::default())
+                .unwrap_err()
+                .to_string(),
+            "The fields of a log event must be a map, not a value of kind null."
+        );
+    }
+
     #[test]
     fn try_insert_flat() {
         let mut log = LogEvent::default();
@@ -694,6 +2533,7 @@ mod test {
         std::fs::read_dir(FIXTURE_ROOT)
             .unwrap()
             .for_each(|fixture_file| match fixture_file {
+                Ok(fixture_file) if fixture_file.path().is_dir() => {}
                 Ok(fixture_file) => {
                     let path = fixture_file.path();
                     tracing::trace!(?path, "Opening.");
@@ -708,6 +2548,235 @@ mod test {
             });
     }
 
+    // Same as above, but for every line of the NDJSON fixtures in the
+    // `ndjson` subdirectory.
+    #[test]
+    fn ndjson_value_to_vector_log_event_to_json_value() {
+        const FIXTURE_ROOT: &str = "tests/data/fixtures/log_event/ndjson";
+
+        for fixture_file in std::fs::read_dir(FIXTURE_ROOT).unwrap() {
+            let path = fixture_file.unwrap().path();
+            let serde_values = open_fixture_ndjson(&path).unwrap();
+            assert!(!serde_values.is_empty(), "{:?} is empty", path);
+
+            for (index, serde_value) in serde_values.into_iter().enumerate() {
+                let vector_value = LogEvent::try_from(serde_value.clone()).unwrap();
+                let serde_value_again: serde_json::Value = vector_value.try_into().unwrap();
+
+                assert_eq!(
+                    serde_value,
+                    serde_value_again,
+                    "{}: line {}",
+                    path.display(),
+                    index + 1
+                );
+            }
+        }
+    }
+
+    fn two_step(json: &[u8]) -> Option<LogEvent> {
+        let value: serde_json::Value = serde_json::from_slice(json).ok()?;
+        LogEvent::try_from(value).ok()
+    }
+
+    #[test]
+    fn from_json_slice_matches_two_step_over_fixtures() {
+        let mut documents = Vec::new();
+        for dir in ["log_event", "log_event/ndjson", "canonical_json"] {
+            let dir = Path::new("tests/data/fixtures").join(dir);
+            for entry in fs::read_dir(dir).unwrap() {
+                let path = entry.unwrap().path();
+                match path.extension().and_then(|ext| ext.to_str()) {
+                    Some("json") => documents.push(fs::read(&path).unwrap()),
+                    Some("ndjson") => documents.extend(
+                        fs::read_to_string(&path)
+                            .unwrap()
+                            .lines()
+                            .filter(|line| !line.trim().is_empty())
+                            .map(|line| line.as_bytes().to_vec()),
+                    ),
+                    _ => {}
+                }
+            }
+        }
+        // The value fixtures cover every kind of value, within an object.
+        for kind in fs::read_dir("tests/data/fixtures/value").unwrap() {
+            for entry in fs::read_dir(kind.unwrap().path()).unwrap() {
+                let mut document = b"{\"value\": ".to_vec();
+                document.extend(fs::read(entry.unwrap().path()).unwrap());
+                document.push(b'}');
+                documents.push(document);
+            }
+        }
+        documents.extend(
+            [
+                r#"{"big": 18446744073709551615, "min": -9223372036854775808}"#,
+                r#"{"float": 1.0, "exp": 1e3, "negative_zero": -0, "tiny": 5e-324}"#,
+                r#"{"escaped": "a\"b\\cé\n", "empty": "", "nested": [[{}], []]}"#,
+            ]
+            .iter()
+            .map(|document| document.as_bytes().to_vec()),
+        );
+
+        for document in documents {
+            let expected = two_step(&document)
+                .unwrap_or_else(|| panic!("{}", String::from_utf8_lossy(&document)));
+            let log = LogEvent::from_json_slice(&document).unwrap();
+            shared::assert_event_data_eq!(log, expected);
+            let log = LogEvent::from_json_reader(document.as_slice()).unwrap();
+            shared::assert_event_data_eq!(log, expected);
+        }
+    }
+
+    #[test]
+    fn from_json_slice_requires_an_object() {
+        for document in [
+            r#""message""#,
+            "[1, 2]",
+            "null",
+            r#"{"a": 1} x"#,
+            r#"{"a": "#,
+        ] {
+            assert!(
+                LogEvent::from_json_slice(document.as_bytes()).is_err(),
+                "{}",
+                document
+            );
+            assert!(two_step(document.as_bytes()).is_none(), "{}", document);
+        }
+        let error = LogEvent::from_json_slice(b"[1, 2]").unwrap_err();
+        assert!(
+            error.to_string().contains("expected a JSON object"),
+            "{}",
+            error
+        );
+    }
+
+    #[test]
+    fn fixture_log_events_reads_every_line() {
+        let events = fixture_log_events("tests/data/fixtures/log_event/ndjson").unwrap();
+        assert_eq!(events.len(), 4);
+        assert_eq!(events[0]["message"], Value::from("first"));
+        assert_eq!(events[3]["message"], Value::from("last"));
+    }
+
+    // The generated counterpart of the fixture tests above. Generated events
+    // can hold values JSON can't represent exactly (timestamps, non-UTF-8
+    // bytes), so we assert the JSON form is stable after one round trip.
+    #[test]
+    fn generated_log_event_to_json_value_and_back() {
+        let mut generator = EventGenerator::from_env();
+
+        for _ in 0..100 {
+            let event = generator.arbitrary_log_event(3, 4);
+            let serde_value: serde_json::Value = event.try_into().unwrap();
+
+            let vector_value = LogEvent::try_from(serde_value.clone()).unwrap();
+            let serde_value_again: serde_json::Value = vector_value.try_into().unwrap();
+
+            assert_eq!(serde_value, serde_value_again, "seed: {}", generator.seed());
+        }
+    }
+
+    // Ensures serializing directly to JSON bytes matches the two-step path
+    // through `serde_json::Value` for every fixture.
+    #[test]
+    fn to_json_vec_matches_json_value() {
+        const FIXTURE_ROOT: &str = "tests/data/fixtures/log_event";
+
+        let mut events = Vec::new();
+        for fixture_file in std::fs::read_dir(FIXTURE_ROOT).unwrap() {
+            let path = fixture_file.unwrap().path();
+            if path.is_dir() {
+                continue;
+            }
+            let event = LogEvent::try_from(open_fixture(&path).unwrap()).unwrap();
+
+            let json_value: serde_json::Value = event.clone().try_into().unwrap();
+            let expected = serde_json::to_vec(&json_value).unwrap();
+            assert_eq!(event.to_json_vec().unwrap(), expected, "{:?}", path);
+
+            events.push(event);
+        }
+
+        let mut ndjson = Vec::new();
+        write_ndjson(&events, &mut ndjson).unwrap();
+        let lines = ndjson.split(|b| *b == b'\n').collect::<Vec<_>>();
+        assert_eq!(lines.len(), events.len() + 1);
+        for (line, event) in lines.iter().zip(&events) {
+            assert_eq!(*line, event.to_json_vec().unwrap().as_slice());
+        }
+    }
+
+    #[test]
+    fn inspect_without_cloning() {
+        let log = LogEvent::try_from(json!({
+            "message": "hello",
+            "tags": ["a", "b", "c"],
+            "nested": { "matrix": [[1, 2], []], "labels": { "app": "vector" } },
+            "empty": {},
+            "nothing": null,
+        }))
+        .unwrap();
+
+        let cases = vec![
+            ("message", None, Some(ValueKind::Bytes), Some(false)),
+            ("tags", Some(3), Some(ValueKind::Array), Some(false)),
+            ("tags[1]", None, Some(ValueKind::Bytes), Some(false)),
+            ("nested", Some(2), Some(ValueKind::Map), Some(false)),
+            (
+                "nested.matrix",
+                Some(2),
+                Some(ValueKind::Array),
+                Some(false),
+            ),
+            (
+                "nested.matrix[0]",
+                Some(2),
+                Some(ValueKind::Array),
+                Some(false),
+            ),
+            (
+                "nested.matrix[1]",
+                Some(0),
+                Some(ValueKind::Array),
+                Some(true),
+            ),
+            (
+                "nested.matrix[0][1]",
+                None,
+                Some(ValueKind::Integer),
+                Some(false),
+            ),
+            ("nested.labels", Some(1), Some(ValueKind::Map), Some(false)),
+            ("empty", Some(0), Some(ValueKind::Map), Some(true)),
+            ("nothing", None, Some(ValueKind::Null), Some(true)),
+            ("missing", None, None, None),
+            ("tags[3]", None, None, None),
+            ("message.length", None, None, None),
+        ];
+
+        for (path, len, kind, is_empty) in cases {
+            assert_eq!(log.len_at(path), len, "{}", path);
+            assert_eq!(log.kind_at(path), kind, "{}", path);
+            assert_eq!(log.is_empty_at(path), is_empty, "{}", path);
+        }
+
+        // The same, on the value of a field.
+        let nested = &log["nested"];
+        let matrix = lookup::Lookup::from_str("matrix[1]").unwrap();
+        assert_eq!(nested.len_at(matrix.clone()), Some(0));
+        assert_eq!(nested.kind_at(matrix.clone()), Some(ValueKind::Array));
+        assert_eq!(nested.is_empty_at(matrix), Some(true));
+        assert_eq!(nested.len_at("labels"), Some(1));
+        assert_eq!(nested.len_at(lookup::Lookup::root()), Some(2));
+        assert_eq!(nested.kind_at("missing"), None);
+        assert_eq!(nested.is_empty_at("missing"), None);
+
+        assert_eq!(ValueKind::Bytes.to_string(), "string");
+        assert_eq!(log["nested.matrix"].kind(), "array");
+    }
+
     // We use `serde_json` pointers in this test to ensure we're validating that Vector correctly inputs and outputs things as expected.
     #[test]
     fn entry() {
@@ -767,6 +2836,94 @@ mod test {
         assert_eq!(json.pointer("/map/map/non-existing"), Some(&fallback));
     }
 
+    #[test]
+    fn entry_errors() {
+        let mut event = LogEvent::default();
+        event.insert("basic", true);
+        event.insert("map.inner", 1);
+
+        let error = |event: &mut LogEvent, lookup: &str| {
+            event
+                .entry(Lookup::from_str(lookup).unwrap())
+                .map(|_| ())
+                .unwrap_err()
+        };
+        assert_eq!(
+            error(&mut event, "basic.field"),
+            PathError::NotAMap {
+                path: "basic.field".into(),
+                segment: 1
+            }
+        );
+        assert_eq!(
+            error(&mut event, "map.missing.field"),
+            PathError::MissingField {
+                path: "map.missing.field".into(),
+                segment: 1
+            }
+        );
+    }
+
+    #[test]
+    fn extract_fields() {
+        let mut event = LogEvent::default();
+        event.insert("message", "hello");
+        event.insert("kubernetes.pod_name", "pod");
+        event.insert("kubernetes.labels.app", "vector");
+
+        let paths = ["kubernetes.labels.app", "missing", "message"]
+            .iter()
+            .map(|path| CompiledLookup::new(path).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            event.extract(&paths),
+            vec![
+                Some(&Value::from("vector")),
+                None,
+                Some(&Value::from("hello"))
+            ]
+        );
+
+        let (extracted, rest) = event.extract_owned(&paths);
+        assert_eq!(
+            extracted,
+            vec![
+                Some(Value::from("vector")),
+                None,
+                Some(Value::from("hello"))
+            ]
+        );
+        assert!(!rest.contains("message"));
+        assert!(!rest.contains("kubernetes.labels.app"));
+        assert_eq!(rest.get("kubernetes.pod_name"), Some(&Value::from("pod")));
+    }
+
+    #[test]
+    fn fallible_path_operations() {
+        let mut event = LogEvent::default();
+        event.insert("a.b", 1);
+
+        assert_eq!(event.try_get("a.b"), Ok(&Value::Integer(1)));
+        assert_eq!(event.try_get("a.b.c").unwrap_err().segment(), 2);
+        assert_eq!(
+            event.insert_checked("a.b.c", 2),
+            Err(PathError::NotAMap {
+                path: "a.b.c".into(),
+                segment: 2
+            })
+        );
+        assert_eq!(event.insert_checked("a.c", 2), Ok(None));
+        assert_eq!(event.try_remove("a.b", true), Ok(Value::Integer(1)));
+        assert_eq!(
+            event.try_remove("a.b", true),
+            Err(PathError::MissingField {
+                path: "a.b".into(),
+                segment: 1
+            })
+        );
+        assert_eq!(event.try_get("a.c"), Ok(&Value::Integer(2)));
+    }
+
     fn assert_merge_value(
         current: impl Into<Value>,
         incoming: impl Into<Value>,
@@ -854,4 +3011,333 @@ mod test {
 
         shared::assert_event_data_eq!(merged, expected);
     }
+
+    fn frames(functions: &[&str]) -> Value {
+        functions
+            .iter()
+            .map(|function| {
+                Value::from(btreemap! {
+                    "function" => *function,
+                    "args" => vec![Value::from(*function)],
+                })
+            })
+            .collect::<Vec<_>>()
+            .into()
+    }
+
+    #[test]
+    fn merge_appends_arrays_of_maps() {
+        let mut current = LogEvent::default();
+        current.insert("error.frames", frames(&["main", "run"]));
+        current.insert("error.codes", vec![1, 2]);
+        current.insert("message", "Traceback ");
+        let mut incoming = LogEvent::default();
+        incoming.insert("error.frames", frames(&["parse", "read"]));
+        incoming.insert("error.codes", vec![3]);
+        incoming.insert("message", "(most recent call last)");
+
+        current.merge(incoming, &["error.frames", "error.codes", "message"]);
+
+        // The frames keep their own arrays, with the current ones first.
+        assert_eq!(
+            current["error.frames"],
+            frames(&["main", "run", "parse", "read"])
+        );
+        // Arrays of anything else are replaced, and bytes concatenated, as before.
+        assert_eq!(current["error.codes"], Value::from(vec![3]));
+        assert_eq!(
+            current["message"],
+            "Traceback (most recent call last)".into()
+        );
+    }
+
+    #[test]
+    fn merge_strategies_override_defaults() {
+        let mut current = LogEvent::default();
+        current.insert("frames", frames(&["main"]));
+        current.insert("codes", vec![1, 2]);
+        current.insert("message", "first");
+        current.insert("tag", "a");
+        let mut incoming = LogEvent::default();
+        incoming.insert("frames", frames(&["run"]));
+        incoming.insert("codes", vec![3]);
+        incoming.insert("message", "second");
+        incoming.insert("tag", "b");
+
+        current.merge_with_strategies(
+            incoming,
+            &["frames", "codes", "message", "tag"],
+            &[
+                ("frames", FieldMergeStrategy::Replace),
+                ("codes", FieldMergeStrategy::Append),
+                ("message", FieldMergeStrategy::Append),
+            ],
+        );
+
+        assert_eq!(current["frames"], frames(&["run"]));
+        assert_eq!(current["codes"], Value::from(vec![1, 2, 3]));
+        assert_eq!(current["message"], Value::from(vec!["first", "second"]));
+        // Fields without a strategy are merged by default.
+        assert_eq!(current["tag"], "ab".into());
+    }
+
+    #[test]
+    fn sort_array_of_mixed_kinds() {
+        let mut log = LogEvent::default();
+        log.insert(
+            "a.b",
+            vec![
+                Value::from("b"),
+                Value::Null,
+                Value::from(2),
+                Value::from(1.5),
+                Value::from(vec![Value::from(1)]),
+                Value::from(true),
+                Value::from("a"),
+                Value::from(1),
+            ],
+        );
+
+        assert_eq!(log.sort_array_at("a.b", None), Ok(()));
+        assert_eq!(
+            log["a.b"],
+            Value::from(vec![
+                Value::from(true),
+                Value::from(1),
+                Value::from(1.5),
+                Value::from(2),
+                Value::from("a"),
+                Value::from("b"),
+                Value::from(vec![Value::from(1)]),
+                Value::Null,
+            ])
+        );
+    }
+
+    #[test]
+    fn sort_array_of_maps_by_key() {
+        let mut log = LogEvent::try_from(json!({
+            "items": [
+                { "name": "c", "rank": { "score": 3 } },
+                { "name": "no rank" },
+                { "name": "a", "rank": { "score": 1 } },
+                "not a map",
+                { "name": "b", "rank": { "score": 2 } },
+                { "name": "null rank", "rank": { "score": null } },
+            ]
+        }))
+        .unwrap();
+
+        assert_eq!(log.sort_array_at("items", Some("rank.score")), Ok(()));
+        let names = log["items"]
+            .as_array()
+            .iter()
+            .map(|item| match item {
+                Value::Map(map) => map["name"].to_string_lossy(),
+                other => other.to_string_lossy(),
+            })
+            .collect::<Vec<_>>();
+        // Elements without the key keep their order, after all the others.
+        assert_eq!(names, ["a", "b", "c", "null rank", "no rank", "not a map"]);
+    }
+
+    #[test]
+    fn truncate_array() {
+        let mut log = LogEvent::default();
+        log.insert("a", vec![1, 2, 3, 4]);
+
+        assert_eq!(log.truncate_array_at("a", 5), Ok(0));
+        assert_eq!(log.truncate_array_at("a", 2), Ok(2));
+        assert_eq!(log["a"], Value::from(vec![1, 2]));
+        assert_eq!(log.truncate_array_at("a", 0), Ok(2));
+        assert_eq!(log["a"], Value::Array(Vec::new()));
+    }
+
+    #[test]
+    fn array_helpers_reject_other_kinds() {
+        let mut log = LogEvent::default();
+        log.insert("a.b", "not an array");
+
+        let not_an_array = Err(FieldError::WrongKind {
+            path: "a.b".to_owned(),
+            expected: ValueKind::Array,
+            kind: ValueKind::Bytes,
+        });
+        assert_eq!(log.sort_array_at("a.b", None), not_an_array);
+        assert_eq!(log.truncate_array_at("a.b", 1).map(drop), not_an_array);
+        assert_eq!(
+            log.sort_array_at("a.c", Some("d")),
+            Err(FieldError::Path {
+                source: PathError::MissingField {
+                    path: "a.c".to_owned(),
+                    segment: 1,
+                }
+            })
+        );
+        assert_eq!(log["a.b"], Value::from("not an array"));
+    }
+
+    #[test]
+    fn truncate_field_keeps_utf8_valid() {
+        // Characters of one, two, three and four bytes.
+        let text = "aé€😀";
+        let cases = [
+            (10, text),
+            (9, "aé€"),
+            (6, "aé€"),
+            (5, "aé"),
+            (3, "aé"),
+            (2, "a"),
+            (1, "a"),
+            (0, ""),
+        ];
+        for (max_bytes, expected) in cases {
+            let mut log = LogEvent::default();
+            log.insert("message", text);
+            let truncated = log.truncate_field("message", max_bytes, None);
+            assert_eq!(truncated, max_bytes < text.len(), "{}", max_bytes);
+            assert_eq!(log["message"], expected.into(), "{}", max_bytes);
+        }
+    }
+
+    #[test]
+    fn truncate_field_appends_marker_within_limit() {
+        let marker = Some("…[truncated]");
+        let mut log = LogEvent::default();
+        log.insert("message", "héllo wörld, and then some");
+
+        // The marker takes 14 bytes, leaving 9, which would split the `ö`.
+        assert!(log.truncate_field("message", 23, marker));
+        assert_eq!(log["message"], "héllo w…[truncated]".into());
+        assert_eq!(log["message"].as_bytes().len(), 22);
+
+        // Already short enough, marker included.
+        assert!(!log.truncate_field("message", 23, marker));
+
+        // A marker that doesn't fit on its own is left out.
+        assert!(log.truncate_field("message", 3, marker));
+        assert_eq!(log["message"], "hé".into());
+    }
+
+    #[test]
+    fn truncate_field_cuts_invalid_utf8_at_limit() {
+        let mut log = LogEvent::default();
+        log.insert("raw", Bytes::from_static(b"\xff\xfe\xfd\xfc"));
+
+        assert!(log.truncate_field("raw", 3, Some("!")));
+        assert_eq!(log["raw"], Value::from(Bytes::from_static(b"\xff\xfe!")));
+    }
+
+    #[test]
+    fn truncate_field_leaves_other_kinds() {
+        let mut log = LogEvent::default();
+        log.insert("count", 123_456);
+
+        assert!(!log.truncate_field("count", 2, None));
+        assert_eq!(
+            log.try_truncate_field("count", 2, None),
+            Err(FieldError::WrongKind {
+                path: "count".to_owned(),
+                expected: ValueKind::Bytes,
+                kind: ValueKind::Integer,
+            })
+        );
+        assert_eq!(log["count"], 123_456.into());
+        assert!(matches!(
+            log.try_truncate_field("missing", 2, None),
+            Err(FieldError::Path { .. })
+        ));
+    }
+
+    /// Sets the sensitive paths for the whole process, so the tests setting them all set the same.
+    fn apply_test_redaction() {
+        crate::config::Redaction {
+            sensitive_paths: ["redaction_secret", "**.redaction_token"]
+                .iter()
+                .map(|path| SensitivePath::try_from((*path).to_owned()).unwrap())
+                .collect(),
+        }
+        .apply();
+    }
+
+    #[test]
+    fn debug_redacts_sensitive_fields() {
+        apply_test_redaction();
+        let mut log = LogEvent::from("hello");
+        log.insert("redaction_secret", "hunter2");
+        log.insert("user.redaction_token", "abc123");
+        log.insert("user.name", "alice");
+        log.insert("sessions[1].redaction_token", "def456");
+        log.insert("nested.redaction_secret", "visible");
+
+        let debug = format!("{:?}", Event::from(log.clone()));
+        for secret in ["hunter2", "abc123", "def456"] {
+            assert!(!debug.contains(secret), "{}", debug);
+        }
+        for shown in [
+            "redaction_secret",
+            "redaction_token",
+            "alice",
+            "visible",
+            "hello",
+        ] {
+            assert!(debug.contains(shown), "{}", debug);
+        }
+        assert_eq!(debug.matches("\"[REDACTED]\"").count(), 3, "{}", debug);
+
+        let raw = format!("{:?}", log.debug_raw());
+        assert!(raw.contains("hunter2"), "{}", raw);
+        assert!(!raw.contains("[REDACTED]"), "{}", raw);
+        // Without sensitive fields, the redacted form is the derived one.
+        let log = LogEvent::from("hello");
+        assert_eq!(format!("{:?}", log), format!("{:?}", log.debug_raw()));
+    }
+
+    #[test]
+    fn insert_spans_redact_sensitive_values() {
+        use std::sync::Mutex;
+
+        use tracing_subscriber::fmt::format::FmtSpan;
+
+        #[derive(Clone, Default)]
+        struct Output(Arc<Mutex<Vec<u8>>>);
+
+        impl io::Write for Output {
+            fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+                self.0.lock().unwrap().extend_from_slice(buf);
+                Ok(buf.len())
+            }
+
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+
+        apply_test_redaction();
+        let output = Output::default();
+        let writer = output.clone();
+        let subscriber = tracing_subscriber::fmt()
+            .with_max_level(tracing::Level::TRACE)
+            .with_span_events(FmtSpan::CLOSE)
+            .with_writer(move || writer.clone())
+            .with_ansi(false)
+            .finish();
+        tracing::subscriber::with_default(subscriber, || {
+            let mut log = LogEvent::default();
+            log.insert("redaction_secret", "hunter2");
+            log.insert_flat("redaction_secret", "hunter3");
+            log.insert(
+                "user.redaction_token",
+                Value::Map(btreemap! { "inner" => "abc123" }),
+            );
+            log.insert("user.name", "alice");
+        });
+
+        let output = String::from_utf8(output.0.lock().unwrap().clone()).unwrap();
+        for secret in ["hunter2", "hunter3", "abc123"] {
+            assert!(!output.contains(secret), "{}", output);
+        }
+        assert!(output.contains("[REDACTED]"), "{}", output);
+        assert!(output.contains("alice"), "{}", output);
+    }
 }
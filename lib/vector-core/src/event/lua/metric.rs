@@ -76,7 +76,7 @@ impl<'a> ToLua<'a> for Metric {
         if let Some(ts) = self.data.timestamp {
             tbl.raw_set("timestamp", timestamp_to_table(lua, ts)?)?;
         }
-        if let Some(tags) = self.series.tags {
+        if let Some(tags) = self.tags().cloned() {
             tbl.raw_set("tags", tags)?;
         }
         tbl.raw_set("kind", self.data.kind)?;
@@ -1,19 +1,27 @@
 #![deny(missing_docs)]
 
-use std::sync::Arc;
+use std::{borrow::Cow, cmp::Ordering, collections::BTreeMap, sync::Arc};
 
+use bytes::Bytes;
+use chrono::{DateTime, Duration, Utc};
+use derivative::Derivative;
 use getset::{Getters, Setters};
 use serde::{Deserialize, Serialize};
 use shared::EventDataEq;
+use uuid::Uuid;
 
-use super::{BatchNotifier, EventFinalizer, EventFinalizers, EventStatus};
+use super::{
+    compressed::CompressedField, BatchNotifier, EventFinalizer, EventFinalizers, EventStatus,
+};
 use crate::ByteSizeOf;
 
+/// The id of an event, see [`EventMetadata::event_id`].
+pub type EventId = Uuid;
+
 /// The top-level metadata structure contained by both `struct Metric`
 /// and `struct LogEvent` types.
-#[derive(
-    Clone, Debug, Default, Deserialize, Getters, PartialEq, PartialOrd, Serialize, Setters,
-)]
+#[derive(Clone, Debug, Default, Derivative, Deserialize, Getters, Serialize, Setters)]
+#[derivative(PartialEq, PartialOrd)]
 pub struct EventMetadata {
     /// Used to store the datadog API from sources to sinks
     #[getset(get = "pub", set = "pub")]
@@ -25,6 +33,116 @@ pub struct EventMetadata {
     splunk_hec_token: Option<Arc<str>>,
     #[serde(default, skip)]
     finalizers: EventFinalizers,
+    /// The reason the event was marked for the `dropped` output of the component handling it.
+    #[serde(default, skip)]
+    dropped: Option<Cow<'static, str>>,
+    /// When the event entered the topology. It depends on the wall clock, so it doesn't take part
+    /// in comparisons.
+    #[serde(default, skip)]
+    #[derivative(PartialEq = "ignore", PartialOrd = "ignore")]
+    ingest_timestamp: Option<DateTime<Utc>>,
+    /// The id minted for the event where ids are enabled, passed on from one Vector to the next
+    /// so that the event can be followed across them. It is random, so it doesn't take part in
+    /// comparisons.
+    #[serde(default, skip)]
+    #[derivative(PartialEq = "ignore", PartialOrd = "ignore")]
+    event_id: Option<EventId>,
+    /// The metadata that few events carry. It is only allocated once some of it is set, so the
+    /// other events pay for a single pointer. Only its unknown protobuf fields take part in
+    /// comparisons.
+    #[serde(default, skip)]
+    #[derivative(
+        PartialEq(compare_with = "MetadataExtension::unknown_proto_fields_eq"),
+        PartialOrd(compare_with = "MetadataExtension::unknown_proto_fields_partial_cmp")
+    )]
+    extension: Option<Box<MetadataExtension>>,
+}
+
+/// See [`EventMetadata::extension`].
+#[derive(Clone, Debug, Default)]
+struct MetadataExtension {
+    /// The protobuf fields of the event that weren't known when it was decoded, passed on when it
+    /// is encoded again.
+    unknown_proto_fields: Option<UnknownProtoFields>,
+    /// Where the values of the fields of a merged event came from, by the path of the field.
+    provenance: BTreeMap<String, Vec<ProvenanceEntry>>,
+    /// The fields compressed in place, by the path they were compressed at. The values of the
+    /// fields already differ from those of an uncompressed event, so they don't take part in
+    /// comparisons.
+    compressed_fields: BTreeMap<String, CompressedField>,
+}
+
+impl MetadataExtension {
+    fn unknown_proto_fields(this: &Option<Box<Self>>) -> Option<&UnknownProtoFields> {
+        this.as_ref()
+            .and_then(|extension| extension.unknown_proto_fields.as_ref())
+    }
+
+    fn unknown_proto_fields_eq(this: &Option<Box<Self>>, other: &Option<Box<Self>>) -> bool {
+        Self::unknown_proto_fields(this) == Self::unknown_proto_fields(other)
+    }
+
+    fn unknown_proto_fields_partial_cmp(
+        this: &Option<Box<Self>>,
+        other: &Option<Box<Self>>,
+    ) -> Option<Ordering> {
+        Self::unknown_proto_fields(this).partial_cmp(&Self::unknown_proto_fields(other))
+    }
+
+    fn merge(&mut self, other: Self) {
+        if self.unknown_proto_fields.is_none() {
+            self.unknown_proto_fields = other.unknown_proto_fields;
+        }
+        for (path, entries) in other.provenance {
+            self.provenance.entry(path).or_default().extend(entries);
+        }
+        for (path, field) in other.compressed_fields {
+            self.compressed_fields.entry(path).or_insert(field);
+        }
+    }
+}
+
+impl ByteSizeOf for MetadataExtension {
+    fn allocated_bytes(&self) -> usize {
+        self.unknown_proto_fields
+            .as_ref()
+            .map_or(0, |unknown| unknown.wrapper.len() + unknown.log.len())
+            + self
+                .provenance
+                .iter()
+                .map(|(path, entries)| {
+                    path.capacity() + entries.capacity() * std::mem::size_of::<ProvenanceEntry>()
+                })
+                .sum::<usize>()
+            + self
+                .compressed_fields
+                .keys()
+                // The compressed bytes are shared with the field value, which counts them.
+                .map(|path| path.capacity() + std::mem::size_of::<CompressedField>())
+                .sum::<usize>()
+    }
+}
+
+/// An event that contributed to the value of a field of a merged event, see
+/// [`EventMetadata::provenance`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct ProvenanceEntry {
+    /// The label the event was merged with, such as the name of its source.
+    pub label: Arc<str>,
+    /// When the event entered the topology, if that was recorded.
+    pub ingest_timestamp: Option<DateTime<Utc>>,
+}
+
+/// The fields of the protobuf encoding of an event that the Vector decoding it doesn't know of,
+/// such as those added by a newer Vector, as they were received. They are written back as they
+/// are when the event is encoded again, so that a Vector older than the one that sent the event
+/// doesn't drop them on the way to one that knows them.
+#[derive(Clone, Debug, Default, PartialEq, PartialOrd)]
+pub struct UnknownProtoFields {
+    /// The unknown fields of the `EventWrapper` message.
+    pub wrapper: Bytes,
+    /// The unknown fields of the `Log` message of a log event.
+    pub log: Bytes,
 }
 
 impl ByteSizeOf for EventMetadata {
@@ -33,6 +151,14 @@ impl ByteSizeOf for EventMetadata {
         // else. We're just moving around the pointer, which is already captured
         // by `ByteSizeOf::size_of`.
         self.finalizers.allocated_bytes()
+            + match &self.dropped {
+                Some(Cow::Owned(reason)) => reason.capacity(),
+                _ => 0,
+            }
+            + self
+                .extension
+                .as_ref()
+                .map_or(0, |extension| extension.size_of())
     }
 }
 
@@ -59,6 +185,12 @@ impl EventMetadata {
     /// Merge the other `EventMetadata` into this.
     /// If a Datadog API key is not set in `self`, the one from `other` will be used.
     /// If a Splunk HEC token is not set in `self`, the one from `other` will be used.
+    /// If `self` isn't marked as dropped, the mark from `other` will be used.
+    /// If `self` has no unknown protobuf fields, those of `other` will be used.
+    /// If `self` has no event id, the one of `other` will be used.
+    /// The earlier of the two ingest timestamps will be kept.
+    /// The provenance of each field of `other` is appended to that of `self`.
+    /// The compressed fields of `other` are kept where `self` has none at the same path.
     pub fn merge(&mut self, other: Self) {
         self.finalizers.merge(other.finalizers);
         if self.datadog_api_key.is_none() {
@@ -67,6 +199,22 @@ impl EventMetadata {
         if self.splunk_hec_token.is_none() {
             self.splunk_hec_token = other.splunk_hec_token;
         }
+        if self.dropped.is_none() {
+            self.dropped = other.dropped;
+        }
+        if self.event_id.is_none() {
+            self.event_id = other.event_id;
+        }
+        self.ingest_timestamp = match (self.ingest_timestamp, other.ingest_timestamp) {
+            (Some(ours), Some(theirs)) => Some(ours.min(theirs)),
+            (ours, theirs) => ours.or(theirs),
+        };
+        if let Some(theirs) = other.extension {
+            match &mut self.extension {
+                Some(ours) => ours.merge(*theirs),
+                None => self.extension = Some(theirs),
+            }
+        }
     }
 
     /// Update the finalizer(s) status.
@@ -84,6 +232,10 @@ impl EventMetadata {
         self.finalizers.add(finalizer);
     }
 
+    pub const fn finalizers(&self) -> &EventFinalizers {
+        &self.finalizers
+    }
+
     /// Swap the finalizers list with an empty list and return the original.
     pub fn take_finalizers(&mut self) -> EventFinalizers {
         std::mem::take(&mut self.finalizers)
@@ -93,6 +245,127 @@ impl EventMetadata {
     pub fn merge_finalizers(&mut self, finalizers: EventFinalizers) {
         self.finalizers.merge(finalizers);
     }
+
+    /// Mark the event for the `dropped` output, with the reason it was dropped.
+    ///
+    /// The mark is kept out of the event fields, so it is never serialized.
+    pub fn set_dropped(&mut self, reason: impl Into<Cow<'static, str>>) {
+        self.dropped = Some(reason.into());
+    }
+
+    /// The reason the event was marked for the `dropped` output, if it was.
+    pub fn dropped(&self) -> Option<&str> {
+        self.dropped.as_deref()
+    }
+
+    /// Remove the `dropped` mark and return its reason.
+    pub fn take_dropped(&mut self) -> Option<Cow<'static, str>> {
+        self.dropped.take()
+    }
+
+    /// Record when the event entered the topology.
+    pub fn set_ingest_timestamp(&mut self, timestamp: DateTime<Utc>) {
+        self.ingest_timestamp = Some(timestamp);
+    }
+
+    /// When the event entered the topology, if that was recorded.
+    pub fn ingest_timestamp(&self) -> Option<DateTime<Utc>> {
+        self.ingest_timestamp
+    }
+
+    /// How long ago, as of `now`, the event entered the topology, if that was recorded.
+    ///
+    /// The age is negative if the ingest timestamp is after `now`, which happens when the clock
+    /// that recorded it is ahead of the one `now` was read from.
+    pub fn age(&self, now: DateTime<Utc>) -> Option<Duration> {
+        self.ingest_timestamp.map(|timestamp| now - timestamp)
+    }
+
+    /// The id of the event, if one was minted for it.
+    pub const fn event_id(&self) -> Option<EventId> {
+        self.event_id
+    }
+
+    /// Set the id of the event, such as the one it had in the Vector it came from.
+    pub fn set_event_id(&mut self, event_id: Option<EventId>) {
+        self.event_id = event_id;
+    }
+
+    /// Mint an id for the event unless it has one already, and return its id.
+    pub fn ensure_event_id(&mut self) -> EventId {
+        *self.event_id.get_or_insert_with(EventId::new_v4)
+    }
+
+    /// The extension of the metadata, allocated if it wasn't yet.
+    fn extension_mut(&mut self) -> &mut MetadataExtension {
+        self.extension.get_or_insert_with(Box::default)
+    }
+
+    /// The protobuf fields of the event that weren't known when it was decoded, passed on when it
+    /// is encoded again.
+    pub fn unknown_proto_fields(&self) -> Option<&UnknownProtoFields> {
+        MetadataExtension::unknown_proto_fields(&self.extension)
+    }
+
+    /// Set the unknown protobuf fields of the event, see [`EventMetadata::unknown_proto_fields`].
+    pub fn set_unknown_proto_fields(&mut self, unknown: Option<UnknownProtoFields>) {
+        if unknown.is_some() {
+            self.extension_mut().unknown_proto_fields = unknown;
+        } else if let Some(extension) = &mut self.extension {
+            extension.unknown_proto_fields = None;
+        }
+    }
+
+    /// The events that contributed to the field at `path`, oldest first, if any were recorded.
+    pub fn provenance(&self, path: &str) -> Option<&[ProvenanceEntry]> {
+        self.extension
+            .as_ref()
+            .and_then(|extension| extension.provenance.get(path))
+            .map(Vec::as_slice)
+    }
+
+    /// Record that `entry` contributed to the field at `path`, after those recorded before.
+    pub fn record_provenance(&mut self, path: impl Into<String>, entry: ProvenanceEntry) {
+        self.extension_mut()
+            .provenance
+            .entry(path.into())
+            .or_default()
+            .push(entry);
+    }
+
+    /// The record of the field compressed at `path`, if there is one. It may be stale, see
+    /// [`CompressedField::holds`].
+    pub fn compressed_field(&self, path: &str) -> Option<&CompressedField> {
+        self.extension
+            .as_ref()
+            .and_then(|extension| extension.compressed_fields.get(path))
+    }
+
+    /// The records of the compressed fields by their path, stale ones included.
+    pub fn compressed_fields(&self) -> impl Iterator<Item = (&str, &CompressedField)> {
+        self.extension
+            .iter()
+            .flat_map(|extension| extension.compressed_fields.iter())
+            .map(|(path, field)| (path.as_str(), field))
+    }
+
+    /// Record that the field at `path` was compressed, replacing any earlier record for it.
+    pub(crate) fn record_compressed_field(
+        &mut self,
+        path: impl Into<String>,
+        field: CompressedField,
+    ) {
+        self.extension_mut()
+            .compressed_fields
+            .insert(path.into(), field);
+    }
+
+    /// Forget the record of the field compressed at `path`.
+    pub(crate) fn remove_compressed_field(&mut self, path: &str) {
+        if let Some(extension) = &mut self.extension {
+            extension.compressed_fields.remove(path);
+        }
+    }
 }
 
 impl EventDataEq for EventMetadata {
@@ -13,16 +13,20 @@ use serde::{Deserialize, Serialize};
 use shared::EventDataEq;
 
 use crate::{
-    event::{BatchNotifier, EventFinalizer, EventFinalizers, EventMetadata, Finalizable},
+    event::{
+        BatchNotifier, EventFinalizer, EventFinalizers, EventMetadata, Finalizable, MetricTagsExt,
+        TagLimitReport, TagLimits,
+    },
     metrics::{AgentDDSketch, Handle},
     ByteSizeOf,
 };
 
 #[derive(Clone, Debug, Deserialize, Getters, MutGetters, PartialEq, PartialOrd, Serialize)]
 pub struct Metric {
-    #[getset(get = "pub")]
-    #[serde(flatten)]
-    pub(super) series: MetricSeries,
+    // The series is shared copy-on-write: cloning a `Metric` only bumps the reference count, and
+    // the first change to the name, namespace or tags of a shared metric performs the deep copy.
+    #[serde(flatten, with = "shared_series")]
+    pub(super) series: Arc<MetricSeries>,
 
     #[getset(get = "pub", get_mut = "pub")]
     #[serde(flatten)]
@@ -59,6 +63,28 @@ impl AsRef<MetricValue> for Metric {
     }
 }
 
+mod shared_series {
+    use std::sync::Arc;
+
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    use super::MetricSeries;
+
+    pub(super) fn serialize<S>(series: &Arc<MetricSeries>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        series.as_ref().serialize(serializer)
+    }
+
+    pub(super) fn deserialize<'de, D>(deserializer: D) -> Result<Arc<MetricSeries>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        MetricSeries::deserialize(deserializer).map(Arc::new)
+    }
+}
+
 #[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, PartialOrd, Serialize)]
 pub struct MetricSeries {
     #[serde(flatten)]
@@ -124,19 +150,61 @@ pub enum MetricKind {
     Absolute,
 }
 
+/// The longest rendering of a rejected value included in a `MetricKindError`.
+#[cfg(feature = "vrl")]
+const MAX_RENDERED_KIND_LEN: usize = 64;
+
+/// Returned when a VRL value isn't a valid `MetricKind`.
+#[cfg(feature = "vrl")]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MetricKindError {
+    received: String,
+}
+
+#[cfg(feature = "vrl")]
+impl MetricKindError {
+    fn new(value: &vrl_core::Value) -> Self {
+        let mut received = value.to_string();
+        if received.len() > MAX_RENDERED_KIND_LEN {
+            let mut end = MAX_RENDERED_KIND_LEN;
+            while !received.is_char_boundary(end) {
+                end -= 1;
+            }
+            received.truncate(end);
+            received.push_str("...");
+        }
+        Self { received }
+    }
+}
+
+#[cfg(feature = "vrl")]
+impl Display for MetricKindError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            r#"invalid metric kind {}: expected one of "absolute" or "incremental""#,
+            self.received
+        )
+    }
+}
+
+#[cfg(feature = "vrl")]
+impl std::error::Error for MetricKindError {}
+
 #[cfg(feature = "vrl")]
 impl TryFrom<vrl_core::Value> for MetricKind {
-    type Error = String;
+    type Error = MetricKindError;
 
+    /// Accepts `"absolute"` and `"incremental"`, ignoring case.
     fn try_from(value: vrl_core::Value) -> Result<Self, Self::Error> {
-        let value = value.try_bytes().map_err(|e| e.to_string())?;
-        match std::str::from_utf8(&value).map_err(|e| e.to_string())? {
-            "incremental" => Ok(Self::Incremental),
-            "absolute" => Ok(Self::Absolute),
-            value => Err(format!(
-                "invalid metric kind {}, metric kind must be `absolute` or `incremental`",
-                value
-            )),
+        let kind = value
+            .as_bytes()
+            .and_then(|bytes| std::str::from_utf8(bytes).ok())
+            .map(str::to_ascii_lowercase);
+        match kind.as_deref() {
+            Some("incremental") => Ok(Self::Incremental),
+            Some("absolute") => Ok(Self::Absolute),
+            _ => Err(MetricKindError::new(&value)),
         }
     }
 }
@@ -151,6 +219,16 @@ impl From<MetricKind> for vrl_core::Value {
     }
 }
 
+/// The magnitude, 2^53, up to which counter and gauge values hold every integer exactly.
+///
+/// Counter and gauge values are `f64` throughout, including in the protobuf encoding used between
+/// Vector instances, so once a value is part of a metric it is carried bit for bit. Larger integers
+/// are rounded when they are converted to `f64`, to the nearest representable value with ties going
+/// to the even one, which is deterministic but means the value can't be relied on for exact
+/// comparisons. Values at or beyond this magnitude are flagged by
+/// [`MetricValue::exceeds_exact_integer_range`], since 2^53 itself may be a rounded 2^53 + 1.
+pub const MAX_EXACT_INTEGER_VALUE: f64 = 9_007_199_254_740_992.0;
+
 #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
 #[serde(rename_all = "snake_case")]
 /// A `MetricValue` is the container for the actual value of a metric.
@@ -223,6 +301,17 @@ impl MetricValue {
             Self::Sketch { sketch } => sketch.as_name(),
         }
     }
+
+    /// Whether this is a counter or gauge whose value may not be the exact integer it was created
+    /// from. See [`MAX_EXACT_INTEGER_VALUE`].
+    pub fn exceeds_exact_integer_range(&self) -> bool {
+        match self {
+            Self::Counter { value } | Self::Gauge { value } => {
+                value.abs() >= MAX_EXACT_INTEGER_VALUE
+            }
+            _ => false,
+        }
+    }
 }
 
 impl ByteSizeOf for MetricValue {
@@ -364,13 +453,386 @@ pub fn zip_quantiles(
         .collect()
 }
 
-/// Convert the Metric value into a vrl value.
-/// Currently vrl can only read the type of the value and doesn't consider
-/// any actual metric values.
+/// The value of a metric as VRL sees it, an object with the type of the value as its only key,
+/// holding the fields of the value:
+///
+/// - `{ "counter": { "value": 1.0 } }`, and the same for `gauge`
+/// - `{ "set": { "values": ["a", "b"] } }`
+/// - `{ "distribution": { "samples": [{ "value": 1.0, "rate": 2 }], "statistic": "histogram" } }`,
+///   where `statistic` is `"histogram"` or `"summary"`
+/// - `{ "aggregated_histogram": { "buckets": [{ "upper_limit": 1.0, "count": 2 }], "count": 2,
+///   "sum": 1.5 } }`
+/// - `{ "aggregated_summary": { "quantiles": [{ "quantile": 0.5, "value": 1.0 }], "count": 2,
+///   "sum": 1.5 } }`
+/// - `{ "sketch": { "sketch": ... } }`, holding the serialized form of the sketch
+///
+/// Remap programs depend on this shape, so it mustn't change. `TryFrom<vrl_core::Value>` reads it
+/// back.
 #[cfg(feature = "vrl")]
 impl From<MetricValue> for vrl_core::Value {
     fn from(value: MetricValue) -> Self {
-        value.as_name().into()
+        let (name, fields) = match value {
+            MetricValue::Counter { value } => ("counter", vrl_object([("value", value.into())])),
+            MetricValue::Gauge { value } => ("gauge", vrl_object([("value", value.into())])),
+            MetricValue::Set { values } => (
+                "set",
+                vrl_object([("values", values.into_iter().collect::<Vec<_>>().into())]),
+            ),
+            MetricValue::Distribution { samples, statistic } => (
+                "distribution",
+                vrl_object([
+                    (
+                        "samples",
+                        vrl_array(samples, |sample| {
+                            vrl_object([
+                                ("value", sample.value.into()),
+                                ("rate", sample.rate.into()),
+                            ])
+                        }),
+                    ),
+                    (
+                        "statistic",
+                        match statistic {
+                            StatisticKind::Histogram => "histogram",
+                            StatisticKind::Summary => "summary",
+                        }
+                        .into(),
+                    ),
+                ]),
+            ),
+            MetricValue::AggregatedHistogram {
+                buckets,
+                count,
+                sum,
+            } => (
+                "aggregated_histogram",
+                vrl_object([
+                    (
+                        "buckets",
+                        vrl_array(buckets, |bucket| {
+                            vrl_object([
+                                ("upper_limit", bucket.upper_limit.into()),
+                                ("count", bucket.count.into()),
+                            ])
+                        }),
+                    ),
+                    ("count", count.into()),
+                    ("sum", sum.into()),
+                ]),
+            ),
+            MetricValue::AggregatedSummary {
+                quantiles,
+                count,
+                sum,
+            } => (
+                "aggregated_summary",
+                vrl_object([
+                    (
+                        "quantiles",
+                        vrl_array(quantiles, |quantile| {
+                            vrl_object([
+                                ("quantile", quantile.quantile.into()),
+                                ("value", quantile.value.into()),
+                            ])
+                        }),
+                    ),
+                    ("count", count.into()),
+                    ("sum", sum.into()),
+                ]),
+            ),
+            MetricValue::Sketch { sketch } => (
+                "sketch",
+                vrl_object([(
+                    "sketch",
+                    serde_json::to_value(sketch).map_or(vrl_core::Value::Null, Into::into),
+                )]),
+            ),
+        };
+        vrl_object([(name, fields)])
+    }
+}
+
+#[cfg(feature = "vrl")]
+fn vrl_object<const N: usize>(fields: [(&str, vrl_core::Value); N]) -> vrl_core::Value {
+    fields
+        .into_iter()
+        .map(|(key, value)| (key.to_owned(), value))
+        .collect::<BTreeMap<_, _>>()
+        .into()
+}
+
+#[cfg(feature = "vrl")]
+fn vrl_array<T>(items: Vec<T>, f: impl Fn(T) -> vrl_core::Value) -> vrl_core::Value {
+    items.into_iter().map(f).collect::<Vec<_>>().into()
+}
+
+/// The keys the value of a metric can have in VRL, see `From<MetricValue> for vrl_core::Value`.
+#[cfg(feature = "vrl")]
+const VRL_METRIC_VALUE_TYPES: [&str; 7] = [
+    "counter",
+    "gauge",
+    "set",
+    "distribution",
+    "aggregated_histogram",
+    "aggregated_summary",
+    "sketch",
+];
+
+/// Returned when a VRL value doesn't have the shape of a `MetricValue`. The paths name the keys
+/// from the type of the value down, like `distribution.samples[1].rate`.
+#[cfg(feature = "vrl")]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum MetricValueError {
+    /// The value isn't an object with a single key.
+    NotTyped {
+        received: String,
+    },
+    /// The key of the object isn't a type of metric value.
+    UnknownType {
+        received: String,
+    },
+    MissingKey {
+        path: String,
+    },
+    InvalidKey {
+        path: String,
+        expected: &'static str,
+        received: String,
+    },
+}
+
+#[cfg(feature = "vrl")]
+impl Display for MetricValueError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotTyped { received } => write!(
+                f,
+                "expected an object with the type of the value as its only key, got {}",
+                received
+            ),
+            Self::UnknownType { received } => write!(
+                f,
+                "unknown type {}: expected one of {}",
+                received,
+                VRL_METRIC_VALUE_TYPES.join(", ")
+            ),
+            Self::MissingKey { path } => write!(f, "missing key {}", path),
+            Self::InvalidKey {
+                path,
+                expected,
+                received,
+            } => write!(
+                f,
+                "invalid key {}: expected {}, got {}",
+                path, expected, received
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "vrl")]
+impl std::error::Error for MetricValueError {}
+
+#[cfg(feature = "vrl")]
+impl TryFrom<vrl_core::Value> for MetricValue {
+    type Error = MetricValueError;
+
+    /// Reads the shape written by `From<MetricValue> for vrl_core::Value`. Integers are accepted
+    /// where floats are expected, and keys beyond those of the value are ignored.
+    fn try_from(value: vrl_core::Value) -> Result<Self, Self::Error> {
+        let typed = match value {
+            vrl_core::Value::Object(object) if object.len() == 1 => object.into_iter().next(),
+            vrl_core::Value::Object(object) => {
+                return Err(MetricValueError::NotTyped {
+                    received: format!("an object with {} keys", object.len()),
+                })
+            }
+            value => {
+                return Err(MetricValueError::NotTyped {
+                    received: value.kind().to_string(),
+                })
+            }
+        };
+        let (name, fields) = typed.ok_or_else(|| MetricValueError::NotTyped {
+            received: "an empty object".to_owned(),
+        })?;
+        if !VRL_METRIC_VALUE_TYPES.contains(&name.as_str()) {
+            return Err(MetricValueError::UnknownType { received: name });
+        }
+        let mut fields = VrlFields::new(name.clone(), fields)?;
+
+        Ok(match name.as_str() {
+            "counter" => MetricValue::Counter {
+                value: fields.float("value")?,
+            },
+            "gauge" => MetricValue::Gauge {
+                value: fields.float("value")?,
+            },
+            "set" => MetricValue::Set {
+                values: fields
+                    .array("values")?
+                    .map(|item| item.string())
+                    .collect::<Result<_, MetricValueError>>()?,
+            },
+            "distribution" => MetricValue::Distribution {
+                samples: fields
+                    .array("samples")?
+                    .map(|item| {
+                        let mut sample = item.fields()?;
+                        Ok(Sample {
+                            value: sample.float("value")?,
+                            rate: sample.count("rate")?,
+                        })
+                    })
+                    .collect::<Result<_, MetricValueError>>()?,
+                statistic: fields.statistic("statistic")?,
+            },
+            "aggregated_histogram" => MetricValue::AggregatedHistogram {
+                buckets: fields
+                    .array("buckets")?
+                    .map(|item| {
+                        let mut bucket = item.fields()?;
+                        Ok(Bucket {
+                            upper_limit: bucket.float("upper_limit")?,
+                            count: bucket.count("count")?,
+                        })
+                    })
+                    .collect::<Result<_, MetricValueError>>()?,
+                count: fields.count("count")?,
+                sum: fields.float("sum")?,
+            },
+            "aggregated_summary" => MetricValue::AggregatedSummary {
+                quantiles: fields
+                    .array("quantiles")?
+                    .map(|item| {
+                        let mut quantile = item.fields()?;
+                        Ok(Quantile {
+                            quantile: quantile.float("quantile")?,
+                            value: quantile.float("value")?,
+                        })
+                    })
+                    .collect::<Result<_, MetricValueError>>()?,
+                count: fields.count("count")?,
+                sum: fields.float("sum")?,
+            },
+            _ => MetricValue::Sketch {
+                sketch: fields.sketch("sketch")?,
+            },
+        })
+    }
+}
+
+/// A value found at `path` while reading a `MetricValue` from VRL.
+#[cfg(feature = "vrl")]
+struct VrlItem {
+    path: String,
+    value: vrl_core::Value,
+}
+
+#[cfg(feature = "vrl")]
+impl VrlItem {
+    fn invalid(self, expected: &'static str) -> MetricValueError {
+        MetricValueError::InvalidKey {
+            path: self.path,
+            expected,
+            received: self.value.kind().to_string(),
+        }
+    }
+
+    fn fields(self) -> Result<VrlFields, MetricValueError> {
+        VrlFields::new(self.path, self.value)
+    }
+
+    fn string(self) -> Result<String, MetricValueError> {
+        match self.value {
+            vrl_core::Value::Bytes(bytes) => Ok(String::from_utf8_lossy(&bytes).into_owned()),
+            _ => Err(self.invalid("a string")),
+        }
+    }
+}
+
+/// The fields of an object found at `path` while reading a `MetricValue` from VRL.
+#[cfg(feature = "vrl")]
+struct VrlFields {
+    path: String,
+    fields: BTreeMap<String, vrl_core::Value>,
+}
+
+#[cfg(feature = "vrl")]
+impl VrlFields {
+    fn new(path: String, value: vrl_core::Value) -> Result<Self, MetricValueError> {
+        match value {
+            vrl_core::Value::Object(fields) => Ok(Self { path, fields }),
+            value => Err(VrlItem { path, value }.invalid("an object")),
+        }
+    }
+
+    /// A key set to `null` is treated the same as a missing key.
+    fn take(&mut self, key: &str) -> Result<VrlItem, MetricValueError> {
+        let path = format!("{}.{}", self.path, key);
+        match self.fields.remove(key) {
+            Some(value) if !value.is_null() => Ok(VrlItem { path, value }),
+            _ => Err(MetricValueError::MissingKey { path }),
+        }
+    }
+
+    fn float(&mut self, key: &str) -> Result<f64, MetricValueError> {
+        let item = self.take(key)?;
+        f64::try_from(&item.value).map_err(|_| item.invalid("a number"))
+    }
+
+    fn count(&mut self, key: &str) -> Result<u32, MetricValueError> {
+        let item = self.take(key)?;
+        match item.value.as_integer() {
+            Some(count) => u32::try_from(count).map_err(|_| MetricValueError::InvalidKey {
+                path: item.path,
+                expected: "a count from 0 to 4294967295",
+                received: count.to_string(),
+            }),
+            None => Err(item.invalid("an integer")),
+        }
+    }
+
+    fn statistic(&mut self, key: &str) -> Result<StatisticKind, MetricValueError> {
+        let item = self.take(key)?;
+        match item.value.as_bytes().map(|bytes| bytes.as_ref()) {
+            Some(b"histogram") => Ok(StatisticKind::Histogram),
+            Some(b"summary") => Ok(StatisticKind::Summary),
+            Some(_) => Err(MetricValueError::InvalidKey {
+                received: item.value.to_string(),
+                path: item.path,
+                expected: r#""histogram" or "summary""#,
+            }),
+            None => Err(item.invalid("a string")),
+        }
+    }
+
+    fn array(&mut self, key: &str) -> Result<impl Iterator<Item = VrlItem>, MetricValueError> {
+        let item = self.take(key)?;
+        match item.value {
+            vrl_core::Value::Array(values) => {
+                let path = item.path;
+                Ok(values
+                    .into_iter()
+                    .enumerate()
+                    .map(move |(i, value)| VrlItem {
+                        path: format!("{}[{}]", path, i),
+                        value,
+                    }))
+            }
+            _ => Err(item.invalid("an array")),
+        }
+    }
+
+    fn sketch(&mut self, key: &str) -> Result<MetricSketch, MetricValueError> {
+        let item = self.take(key)?;
+        serde_json::to_value(&item.value)
+            .and_then(serde_json::from_value)
+            .map_err(|error| MetricValueError::InvalidKey {
+                path: item.path,
+                expected: "a serialized sketch",
+                received: error.to_string(),
+            })
     }
 }
 
@@ -441,13 +903,13 @@ impl Metric {
         metadata: EventMetadata,
     ) -> Self {
         Self {
-            series: MetricSeries {
+            series: Arc::new(MetricSeries {
                 name: MetricName {
                     name: name.into(),
                     namespace: None,
                 },
                 tags: None,
-            },
+            }),
             data: MetricData {
                 timestamp: None,
                 kind,
@@ -459,13 +921,13 @@ impl Metric {
 
     #[inline]
     pub fn with_name(mut self, name: impl Into<String>) -> Self {
-        self.series.name.name = name.into();
+        self.series_mut().name.name = name.into();
         self
     }
 
     #[inline]
     pub fn with_namespace<T: Into<String>>(mut self, namespace: Option<T>) -> Self {
-        self.series.name.namespace = namespace.map(Into::into);
+        self.series_mut().name.namespace = namespace.map(Into::into);
         self
     }
 
@@ -491,7 +953,7 @@ impl Metric {
 
     #[inline]
     pub fn with_tags(mut self, tags: Option<MetricTags>) -> Self {
-        self.series.tags = tags;
+        self.series_mut().tags = tags;
         self
     }
 
@@ -501,15 +963,19 @@ impl Metric {
         self
     }
 
+    /// Convert a `Metric` into a tuple of its components
+    ///
+    /// The series is only copied if it is still shared with a clone of this metric.
     #[inline]
     pub fn into_parts(self) -> (MetricSeries, MetricData, EventMetadata) {
-        (self.series, self.data, self.metadata)
+        let series = Arc::try_unwrap(self.series).unwrap_or_else(|series| (*series).clone());
+        (series, self.data, self.metadata)
     }
 
     #[inline]
     pub fn from_parts(series: MetricSeries, data: MetricData, metadata: EventMetadata) -> Self {
         Self {
-            series,
+            series: Arc::new(series),
             data,
             metadata,
         }
@@ -575,6 +1041,17 @@ impl Metric {
             })
     }
 
+    #[inline]
+    pub fn series(&self) -> &MetricSeries {
+        &self.series
+    }
+
+    /// Mutably borrow the series, copying it first if it is shared with a clone of this metric.
+    #[inline]
+    pub(super) fn series_mut(&mut self) -> &mut MetricSeries {
+        Arc::make_mut(&mut self.series)
+    }
+
     #[inline]
     pub fn name(&self) -> &str {
         &self.series.name.name
@@ -587,7 +1064,11 @@ impl Metric {
 
     #[inline]
     pub fn take_namespace(&mut self) -> Option<String> {
-        self.series.name.namespace.take()
+        if self.series.name.namespace.is_some() {
+            self.series_mut().name.namespace.take()
+        } else {
+            None
+        }
     }
 
     #[inline]
@@ -595,6 +1076,15 @@ impl Metric {
         self.series.tags.as_ref()
     }
 
+    #[inline]
+    pub fn take_tags(&mut self) -> Option<MetricTags> {
+        if self.series.tags.is_some() {
+            self.series_mut().tags.take()
+        } else {
+            None
+        }
+    }
+
     #[inline]
     pub fn timestamp(&self) -> Option<DateTime<Utc>> {
         self.data.timestamp
@@ -614,7 +1104,10 @@ impl Metric {
     /// the old value. *Note:* This will drop the tags map if the tag
     /// was the last entry in it.
     pub fn remove_tag(&mut self, key: &str) -> Option<String> {
-        self.series.remove_tag(key)
+        if !self.series.tags.as_ref()?.contains_key(key) {
+            return None;
+        }
+        self.series_mut().remove_tag(key)
     }
 
     /// Returns `true` if `name` tag is present, and matches the provided `value`
@@ -632,14 +1125,46 @@ impl Metric {
     /// Set or updates the string value of a tag. *Note:* This will
     /// create the tags map if it is not present.
     pub fn insert_tag(&mut self, name: String, value: String) -> Option<String> {
-        self.series.insert_tag(name, value)
+        self.series_mut().insert_tag(name, value)
     }
 
     /// Get the tag entry for the named key. *Note:* This will create
     /// the tags map if it is not present, even if nothing is later
     /// inserted.
     pub fn tag_entry(&mut self, key: String) -> btree_map::Entry<String, String> {
-        self.series.tag_entry(key)
+        self.series_mut().tag_entry(key)
+    }
+
+    /// Truncate or drop the tags exceeding `limits`, emitting an internal
+    /// event if any were changed.
+    pub fn enforce_tag_limits(&mut self, limits: &TagLimits) -> TagLimitReport {
+        if self.series.tags.is_none() {
+            return TagLimitReport::default();
+        }
+        let report = self
+            .series_mut()
+            .tags
+            .as_mut()
+            .map(|tags| tags.enforce_limits(limits))
+            .unwrap_or_default();
+        report.emit(self.name());
+        report
+    }
+
+    /// Set or update the string value of a tag within `limits`, emitting an
+    /// internal event if the tag was truncated or dropped. *Note:* This will
+    /// create the tags map if it is not present.
+    pub fn insert_tag_limited(
+        &mut self,
+        name: String,
+        value: String,
+        limits: &TagLimits,
+    ) -> TagLimitReport {
+        let mut report = TagLimitReport::default();
+        let tags = self.series_mut().tags.get_or_insert_with(Default::default);
+        limits.insert(tags, name, value, &mut report);
+        report.emit(self.name());
+        report
     }
 
     /// Zero out the data in this metric
@@ -1126,6 +1651,187 @@ mod test {
         .collect()
     }
 
+    #[cfg(feature = "vrl")]
+    #[test]
+    fn metric_value_vrl_shapes() {
+        use shared::btreemap;
+
+        let cases = vec![
+            (
+                MetricValue::Counter { value: 1.5 },
+                vrl_core::Value::from(btreemap! { "counter" => btreemap! { "value" => 1.5 } }),
+            ),
+            (
+                MetricValue::Gauge { value: -2.0 },
+                vrl_core::Value::from(btreemap! { "gauge" => btreemap! { "value" => -2.0 } }),
+            ),
+            (
+                MetricValue::Set {
+                    values: vec!["a".to_owned(), "b".to_owned()].into_iter().collect(),
+                },
+                vrl_core::Value::from(
+                    btreemap! { "set" => btreemap! { "values" => vec!["a", "b"] } },
+                ),
+            ),
+            (
+                MetricValue::Distribution {
+                    samples: crate::samples![1.5 => 2, 3.0 => 1],
+                    statistic: StatisticKind::Summary,
+                },
+                vrl_core::Value::from(btreemap! {
+                    "distribution" => btreemap! {
+                        "samples" => vec![
+                            btreemap! { "value" => 1.5, "rate" => 2 },
+                            btreemap! { "value" => 3.0, "rate" => 1 },
+                        ],
+                        "statistic" => "summary",
+                    },
+                }),
+            ),
+            (
+                MetricValue::AggregatedHistogram {
+                    buckets: crate::buckets![1.0 => 3, 2.0 => 5],
+                    count: 8,
+                    sum: 9.5,
+                },
+                vrl_core::Value::from(btreemap! {
+                    "aggregated_histogram" => btreemap! {
+                        "buckets" => vec![
+                            btreemap! { "upper_limit" => 1.0, "count" => 3 },
+                            btreemap! { "upper_limit" => 2.0, "count" => 5 },
+                        ],
+                        "count" => 8,
+                        "sum" => 9.5,
+                    },
+                }),
+            ),
+            (
+                MetricValue::AggregatedSummary {
+                    quantiles: crate::quantiles![0.5 => 1.0, 0.99 => 3.0],
+                    count: 10,
+                    sum: 12.0,
+                },
+                vrl_core::Value::from(btreemap! {
+                    "aggregated_summary" => btreemap! {
+                        "quantiles" => vec![
+                            btreemap! { "quantile" => 0.5, "value" => 1.0 },
+                            btreemap! { "quantile" => 0.99, "value" => 3.0 },
+                        ],
+                        "count" => 10,
+                        "sum" => 12.0,
+                    },
+                }),
+            ),
+            (
+                AgentDDSketch::from_raw(3, 1.0, 4.0, 6.0, 2.0, &[-2, 0, 5], &[1, 1, 1])
+                    .unwrap()
+                    .into(),
+                vrl_core::Value::from(btreemap! {
+                    "sketch" => btreemap! {
+                        "sketch" => btreemap! {
+                            "AgentDDSketch" => btreemap! {
+                                "bins" => btreemap! {
+                                    "k" => vec![-2, 0, 5],
+                                    "n" => vec![1, 1, 1],
+                                },
+                                "count" => 3,
+                                "min" => 1.0,
+                                "max" => 4.0,
+                                "sum" => 6.0,
+                                "avg" => 2.0,
+                            },
+                        },
+                    },
+                }),
+            ),
+        ];
+
+        for (value, shape) in cases {
+            let name = value.as_name();
+            assert_eq!(vrl_core::Value::from(value.clone()), shape, "{}", name);
+            assert_eq!(MetricValue::try_from(shape), Ok(value), "{}", name);
+        }
+    }
+
+    #[cfg(feature = "vrl")]
+    #[test]
+    fn metric_value_from_vrl_errors() {
+        use shared::btreemap;
+
+        let cases = vec![
+            (
+                vrl_core::Value::from("counter"),
+                r#"expected an object with the type of the value as its only key, got "string""#,
+            ),
+            (
+                vrl_core::Value::from(btreemap! {
+                    "counter" => btreemap! { "value" => 1 },
+                    "gauge" => btreemap! { "value" => 1 },
+                }),
+                "expected an object with the type of the value as its only key, got an object with 2 keys",
+            ),
+            (
+                vrl_core::Value::from(btreemap! { "timer" => btreemap! { "value" => 1 } }),
+                "unknown type timer: expected one of counter, gauge, set, distribution, \
+                 aggregated_histogram, aggregated_summary, sketch",
+            ),
+            (
+                vrl_core::Value::from(btreemap! { "counter" => 1 }),
+                r#"invalid key counter: expected an object, got "integer""#,
+            ),
+            (
+                vrl_core::Value::from(btreemap! { "gauge" => btreemap! { "values" => 1 } }),
+                "missing key gauge.value",
+            ),
+            (
+                vrl_core::Value::from(btreemap! { "gauge" => btreemap! { "value" => "1" } }),
+                r#"invalid key gauge.value: expected a number, got "string""#,
+            ),
+            (
+                vrl_core::Value::from(btreemap! { "set" => btreemap! { "values" => vec![1] } }),
+                r#"invalid key set.values[0]: expected a string, got "integer""#,
+            ),
+            (
+                vrl_core::Value::from(btreemap! {
+                    "distribution" => btreemap! {
+                        "samples" => vec![
+                            btreemap! { "value" => 1.5, "rate" => 2 },
+                            btreemap! { "value" => 3.0, "rate" => -1 },
+                        ],
+                        "statistic" => "summary",
+                    },
+                }),
+                "invalid key distribution.samples[1].rate: expected a count from 0 to 4294967295, \
+                 got -1",
+            ),
+            (
+                vrl_core::Value::from(btreemap! {
+                    "distribution" => btreemap! {
+                        "samples" => Vec::<vrl_core::Value>::new(),
+                        "statistic" => "median",
+                    },
+                }),
+                r#"invalid key distribution.statistic: expected "histogram" or "summary", got "median""#,
+            ),
+            (
+                vrl_core::Value::from(btreemap! {
+                    "aggregated_summary" => btreemap! {
+                        "quantiles" => Vec::<vrl_core::Value>::new(),
+                        "sum" => 1.0,
+                    },
+                }),
+                "missing key aggregated_summary.count",
+            ),
+        ];
+
+        for (value, error) in cases {
+            assert_eq!(
+                MetricValue::try_from(value).map_err(|error| error.to_string()),
+                Err(error.to_owned())
+            );
+        }
+    }
+
     #[test]
     fn merge_counters() {
         let mut counter = Metric::new(
@@ -1478,4 +2184,82 @@ mod test {
             assert_eq!(result, expected);
         }
     }
+
+    #[test]
+    fn exact_integer_range() {
+        let max = 1_i64 << 53;
+        let cases = [
+            (0, false),
+            (max - 1, false),
+            (-(max - 1), false),
+            (max, true),
+            (max + 1, true),
+            (-max, true),
+            (i64::MAX, true),
+        ];
+
+        for (value, expected) in cases {
+            #[allow(clippy::cast_precision_loss)]
+            let value = value as f64;
+            assert_eq!(
+                MetricValue::Counter { value }.exceeds_exact_integer_range(),
+                expected
+            );
+            assert_eq!(
+                MetricValue::Gauge { value }.exceeds_exact_integer_range(),
+                expected
+            );
+        }
+        assert!(!MetricValue::Set {
+            values: BTreeSet::new()
+        }
+        .exceeds_exact_integer_range());
+    }
+
+    #[test]
+    fn clone_is_copy_on_write() {
+        let original = Metric::new(
+            "counter",
+            MetricKind::Incremental,
+            MetricValue::Counter { value: 1.0 },
+        )
+        .with_tags(Some(tags()));
+
+        let mut clone = original.clone();
+        assert!(Arc::ptr_eq(&original.series, &clone.series));
+
+        // Removing a tag that isn't there leaves the series shared.
+        assert_eq!(clone.remove_tag("missing"), None);
+        assert_eq!(clone.take_namespace(), None);
+        assert!(Arc::ptr_eq(&original.series, &clone.series));
+
+        clone.insert_tag("normal_tag".into(), "changed".into());
+        assert!(!Arc::ptr_eq(&original.series, &clone.series));
+        assert_eq!(original.tag_value("normal_tag"), Some("value".into()));
+        assert_eq!(clone.tag_value("normal_tag"), Some("changed".into()));
+    }
+
+    #[test]
+    fn serde_round_trip_unchanged() {
+        let metric = Metric::new(
+            "counter",
+            MetricKind::Incremental,
+            MetricValue::Counter { value: 1.0 },
+        )
+        .with_namespace(Some("vector"))
+        .with_tags(Some(
+            vec![("host".to_owned(), "localhost".to_owned())]
+                .into_iter()
+                .collect(),
+        ));
+
+        let json = serde_json::to_string(&metric).unwrap();
+        assert_eq!(
+            json,
+            r#"{"name":"counter","namespace":"vector","tags":{"host":"localhost"},"kind":"incremental","counter":{"value":1.0}}"#
+        );
+
+        let back: Metric = serde_json::from_str(&json).unwrap();
+        assert_eq!(back, metric);
+    }
 }
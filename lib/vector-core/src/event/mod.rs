@@ -1,4 +1,5 @@
 use std::{
+    borrow::Cow,
     collections::{BTreeMap, HashMap},
     convert::{TryFrom, TryInto},
     fmt::Debug,
@@ -8,26 +9,39 @@ use std::{
 use buffers::encoding::{DecodeBytes, EncodeBytes};
 use bytes::{Buf, BufMut, Bytes};
 use chrono::{DateTime, SecondsFormat, Utc};
+pub use compressed::{CompressedField, CompressedJson, FieldCodec};
 pub use finalization::{
     BatchNotifier, BatchStatus, BatchStatusReceiver, EventFinalizer, EventFinalizers, EventStatus,
     Finalizable,
 };
+pub use immutable::ImmutableEvent;
 pub use legacy_lookup::Lookup;
-pub use log_event::LogEvent;
-pub use metadata::{EventMetadata, WithMetadata};
+pub use log_event::{write_ndjson, FieldMergeStrategy, InsertMode, LogEvent};
+pub use metadata::{EventId, EventMetadata, ProvenanceEntry, UnknownProtoFields, WithMetadata};
 pub use metric::{Metric, MetricKind, MetricValue, StatisticKind};
+pub use predicate::{ComparisonOp, FieldPredicate, Literal};
 use prost::{DecodeError, EncodeError, Message};
+pub use schema::{EventSchema, FieldKind, RequiredField, SchemaViolation};
 use shared::EventDataEq;
-pub use util::log::{PathComponent, PathIter};
-pub use value::Value;
+pub use tag_limits::{MetricTagsExt, TagLimitAction, TagLimitReport, TagLimits};
+pub use util::log::{CompiledLookup, PathComponent, PathIter};
+pub use value::{Value, ValueKind};
+pub use visit::ValueVisitor;
 #[cfg(feature = "vrl")]
-pub use vrl_target::VrlTarget;
+pub use vrl_target::{
+    annotate_error, EventFailure, PathCounts, PathDescriptor, PathOp, PathStats, PathStatsReport,
+    ProtectedPaths, TargetOutput, Utf8Handling, VrlTarget,
+};
 
 use crate::ByteSizeOf;
 
+mod canonical_json;
+mod compressed;
 pub mod discriminant;
 pub mod error;
 mod finalization;
+mod immutable;
+mod json_ingest;
 mod legacy_lookup;
 mod log_event;
 #[cfg(feature = "lua")]
@@ -35,11 +49,15 @@ pub mod lua;
 pub mod merge_state;
 mod metadata;
 pub mod metric;
+mod predicate;
 pub mod proto;
+mod schema;
+mod tag_limits;
 #[cfg(test)]
 mod test;
 pub mod util;
 mod value;
+mod visit;
 #[cfg(feature = "vrl")]
 mod vrl_target;
 
@@ -181,6 +199,16 @@ impl Event {
         }
     }
 
+    /// Mark the event for the `dropped` output of the component handling it.
+    pub fn set_dropped(&mut self, reason: impl Into<Cow<'static, str>>) {
+        self.metadata_mut().set_dropped(reason);
+    }
+
+    /// The reason the event was marked for the `dropped` output, if it was.
+    pub fn dropped(&self) -> Option<&str> {
+        self.metadata().dropped()
+    }
+
     /// Destroy the event and return the metadata.
     pub fn into_metadata(self) -> EventMetadata {
         match self {
@@ -0,0 +1,259 @@
+//! Conditions on the fields of a log event that are cheap enough to check for every event, for
+//! enrichments and routing that don't warrant a VRL program.
+
+use std::cmp::Ordering;
+
+use serde::{Deserialize, Serialize};
+
+use super::{util, CompiledLookup, LogEvent, Value};
+
+/// How the value at a path is compared with the literal of a [`FieldPredicate`].
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ComparisonOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl ComparisonOp {
+    const fn holds(self, ordering: Ordering) -> bool {
+        match self {
+            Self::Eq => matches!(ordering, Ordering::Equal),
+            Self::Ne => !matches!(ordering, Ordering::Equal),
+            Self::Lt => matches!(ordering, Ordering::Less),
+            Self::Le => !matches!(ordering, Ordering::Greater),
+            Self::Gt => matches!(ordering, Ordering::Greater),
+            Self::Ge => !matches!(ordering, Ordering::Less),
+        }
+    }
+}
+
+/// The value a field is compared with, written as a plain boolean, number or string in a config.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum Literal {
+    Boolean(bool),
+    Integer(i64),
+    Float(f64),
+    String(String),
+}
+
+impl Literal {
+    /// How `value` compares with this literal, `None` if they can't be compared:
+    ///
+    /// * Integers and floats are compared by their numeric value, whichever of the two each is.
+    /// * Bytes are compared with a string as UTF-8, and can't be compared with it if they aren't
+    ///   valid UTF-8.
+    /// * Booleans are compared with booleans, `false` going first.
+    /// * Values of any other kind can't be compared, nor can NaN be.
+    #[allow(clippy::cast_precision_loss)]
+    fn compare(&self, value: &Value) -> Option<Ordering> {
+        match (value, self) {
+            (Value::Integer(value), Literal::Integer(literal)) => Some(value.cmp(literal)),
+            (Value::Integer(value), Literal::Float(literal)) => {
+                (*value as f64).partial_cmp(literal)
+            }
+            (Value::Float(value), Literal::Integer(literal)) => {
+                value.partial_cmp(&(*literal as f64))
+            }
+            (Value::Float(value), Literal::Float(literal)) => value.partial_cmp(literal),
+            (Value::Bytes(value), Literal::String(literal)) => std::str::from_utf8(value)
+                .ok()
+                .map(|value| value.cmp(literal.as_str())),
+            (Value::Boolean(value), Literal::Boolean(literal)) => Some(value.cmp(literal)),
+            _ => None,
+        }
+    }
+}
+
+/// A condition on the fields of a log event, built from comparisons of the value at a path with
+/// a literal.
+///
+/// A comparison whose path holds no value, or a value that can't be compared with the literal,
+/// doesn't match, whatever its operator, so `ne` only matches values that are comparable with
+/// its literal.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FieldPredicate {
+    Compare {
+        path: CompiledLookup,
+        op: ComparisonOp,
+        value: Literal,
+    },
+    /// Matches when all of the predicates do, including when there are none.
+    All(Vec<FieldPredicate>),
+    /// Matches when any of the predicates does, so never when there are none.
+    Any(Vec<FieldPredicate>),
+}
+
+impl FieldPredicate {
+    /// Whether `log` meets this condition. Nothing is allocated to find out.
+    pub fn matches(&self, log: &LogEvent) -> bool {
+        match self {
+            Self::Compare { path, op, value } => util::log::get_compiled(log.as_map(), path)
+                .and_then(|field| value.compare(field))
+                .map_or(false, |ordering| op.holds(ordering)),
+            Self::All(predicates) => predicates.iter().all(|predicate| predicate.matches(log)),
+            Self::Any(predicates) => predicates.iter().any(|predicate| predicate.matches(log)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn compare(path: &str, op: ComparisonOp, value: Literal) -> FieldPredicate {
+        FieldPredicate::Compare {
+            path: CompiledLookup::new(path).unwrap(),
+            op,
+            value,
+        }
+    }
+
+    fn log() -> LogEvent {
+        let mut log = LogEvent::default();
+        log.insert("integer", 500);
+        log.insert("float", 2.5);
+        log.insert("nan", f64::NAN);
+        log.insert("message", "hello");
+        log.insert("invalid", Value::Bytes(bytes::Bytes::from_static(b"\xff")));
+        log.insert("flag", true);
+        log.insert("nested.list[1]", 7);
+        log
+    }
+
+    #[test]
+    fn comparison_matrix() {
+        use ComparisonOp::{Eq, Ge, Gt, Le, Lt, Ne};
+        use Literal::{Boolean, Float, Integer, String};
+
+        let log = log();
+        // The path and literal compared, and which operators match: eq, ne, lt, le, gt, ge.
+        let cases = [
+            (
+                "integer",
+                Integer(500),
+                [true, false, false, true, false, true],
+            ),
+            (
+                "integer",
+                Integer(501),
+                [false, true, true, true, false, false],
+            ),
+            (
+                "integer",
+                Float(500.0),
+                [true, false, false, true, false, true],
+            ),
+            (
+                "integer",
+                Float(499.5),
+                [false, true, false, false, true, true],
+            ),
+            ("float", Float(2.5), [true, false, false, true, false, true]),
+            ("float", Integer(2), [false, true, false, false, true, true]),
+            ("float", Integer(3), [false, true, true, true, false, false]),
+            (
+                "message",
+                String("hello".into()),
+                [true, false, false, true, false, true],
+            ),
+            (
+                "message",
+                String("world".into()),
+                [false, true, true, true, false, false],
+            ),
+            (
+                "message",
+                String("hell".into()),
+                [false, true, false, false, true, true],
+            ),
+            (
+                "flag",
+                Boolean(true),
+                [true, false, false, true, false, true],
+            ),
+            (
+                "flag",
+                Boolean(false),
+                [false, true, false, false, true, true],
+            ),
+            (
+                "nested.list[1]",
+                Integer(7),
+                [true, false, false, true, false, true],
+            ),
+            // Nothing matches values that can't be compared.
+            ("nan", Float(f64::NAN), [false; 6]),
+            ("nan", Integer(0), [false; 6]),
+            ("invalid", String("\u{fffd}".into()), [false; 6]),
+            ("integer", String("500".into()), [false; 6]),
+            ("message", Integer(5), [false; 6]),
+            ("flag", Integer(1), [false; 6]),
+            ("nested", Integer(1), [false; 6]),
+            ("missing", Integer(1), [false; 6]),
+            ("nested.list[5]", Integer(7), [false; 6]),
+            ("message.inner", String("hello".into()), [false; 6]),
+        ];
+        for (path, literal, expected) in cases {
+            for (op, expected) in [Eq, Ne, Lt, Le, Gt, Ge].into_iter().zip(expected) {
+                assert_eq!(
+                    compare(path, op, literal.clone()).matches(&log),
+                    expected,
+                    "{} {:?} {:?}",
+                    path,
+                    op,
+                    literal
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn combinators() {
+        let log = log();
+        let yes = compare("integer", ComparisonOp::Ge, Literal::Integer(500));
+        let no = compare("missing", ComparisonOp::Eq, Literal::Integer(1));
+
+        assert!(FieldPredicate::All(vec![]).matches(&log));
+        assert!(FieldPredicate::All(vec![yes.clone(), yes.clone()]).matches(&log));
+        assert!(!FieldPredicate::All(vec![yes.clone(), no.clone()]).matches(&log));
+        assert!(!FieldPredicate::Any(vec![]).matches(&log));
+        assert!(FieldPredicate::Any(vec![no.clone(), yes.clone()]).matches(&log));
+        assert!(!FieldPredicate::Any(vec![no.clone(), no.clone()]).matches(&log));
+        assert!(FieldPredicate::Any(vec![no, FieldPredicate::All(vec![yes])]).matches(&log));
+    }
+
+    fn from_toml(config: &str) -> Result<FieldPredicate, toml::de::Error> {
+        toml::from_str::<toml::Value>(config).unwrap().try_into()
+    }
+
+    #[test]
+    fn deserializes_from_config() {
+        let predicate = from_toml(
+            r#"
+            [[any]]
+            compare = { path = "status", op = "ge", value = 500 }
+
+            [[any]]
+            compare = { path = "level", op = "eq", value = "error" }
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            predicate,
+            FieldPredicate::Any(vec![
+                compare("status", ComparisonOp::Ge, Literal::Integer(500)),
+                compare("level", ComparisonOp::Eq, Literal::String("error".into())),
+            ])
+        );
+
+        let error = from_toml(r#"compare = { path = "a..b", op = "eq", value = 1 }"#).unwrap_err();
+        assert!(error.to_string().contains("a..b"), "{}", error);
+    }
+}
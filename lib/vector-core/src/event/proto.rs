@@ -1,7 +1,15 @@
+use bytes::{Buf, BufMut, Bytes};
 use chrono::TimeZone;
+use prost::{
+    encoding::{self, btree_map, message, string, DecodeContext, WireType},
+    DecodeError, Message,
+};
 
 use crate::{
-    event::{self, BTreeMap, WithMetadata},
+    event::{
+        self, error::FieldCountError, log_event::FieldBudget, BTreeMap, EventId,
+        UnknownProtoFields, WithMetadata,
+    },
     metrics::AgentDDSketch,
 };
 
@@ -11,9 +19,178 @@ pub use metric::Value as MetricValue;
 
 use super::metric::MetricSketch;
 
+/// The `EventWrapper` message, written by hand rather than generated so that it keeps the fields
+/// it doesn't know.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct EventWrapper {
+    pub event: Option<Event>,
+    /// The id of the event as 16 bytes, or empty if it has none.
+    pub event_id: Vec<u8>,
+    /// The encoded fields that aren't known to this Vector, as they were decoded.
+    pub unknown_fields: Vec<u8>,
+}
+
+/// The tag of `EventWrapper.event_id`. The tags below it are kept for the event types of the
+/// `event` oneof.
+const EVENT_ID_TAG: u32 = 16;
+
+/// The tag of `Log.compressed_fields`. The tags below it are kept for the fields of the log.
+const COMPRESSED_FIELDS_TAG: u32 = 16;
+
+pub mod event_wrapper {
+    #[derive(Clone, PartialEq, ::prost::Oneof)]
+    pub enum Event {
+        #[prost(message, tag = "1")]
+        Log(super::Log),
+        #[prost(message, tag = "2")]
+        Metric(super::Metric),
+    }
+}
+
+impl Message for EventWrapper {
+    fn encode_raw<B: BufMut>(&self, buf: &mut B) {
+        if let Some(event) = &self.event {
+            event.encode(buf);
+        }
+        if !self.event_id.is_empty() {
+            encoding::bytes::encode(EVENT_ID_TAG, &self.event_id, buf);
+        }
+        buf.put_slice(&self.unknown_fields);
+    }
+
+    fn merge_field<B: Buf>(
+        &mut self,
+        tag: u32,
+        wire_type: WireType,
+        buf: &mut B,
+        ctx: DecodeContext,
+    ) -> Result<(), DecodeError> {
+        match tag {
+            1 | 2 => Event::merge(&mut self.event, tag, wire_type, buf, ctx),
+            EVENT_ID_TAG => encoding::bytes::merge(wire_type, &mut self.event_id, buf, ctx),
+            _ => merge_unknown_field(&mut self.unknown_fields, tag, wire_type, buf, ctx),
+        }
+    }
+
+    fn encoded_len(&self) -> usize {
+        let event_id_len = if self.event_id.is_empty() {
+            0
+        } else {
+            encoding::bytes::encoded_len(EVENT_ID_TAG, &self.event_id)
+        };
+        self.event.as_ref().map_or(0, Event::encoded_len) + event_id_len + self.unknown_fields.len()
+    }
+
+    fn clear(&mut self) {
+        self.event = None;
+        self.event_id.clear();
+        self.unknown_fields.clear();
+    }
+}
+
+/// The `Log` message, written by hand rather than generated so that it keeps the fields it
+/// doesn't know.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Log {
+    pub fields: BTreeMap<String, Value>,
+    /// The fields compressed in place, see `LogEvent::compress_field`.
+    pub compressed_fields: Vec<CompressedField>,
+    /// The encoded fields that aren't known to this Vector, as they were decoded.
+    pub unknown_fields: Vec<u8>,
+}
+
+impl Message for Log {
+    fn encode_raw<B: BufMut>(&self, buf: &mut B) {
+        btree_map::encode(
+            string::encode,
+            string::encoded_len,
+            message::encode,
+            message::encoded_len,
+            1,
+            &self.fields,
+            buf,
+        );
+        message::encode_repeated(COMPRESSED_FIELDS_TAG, &self.compressed_fields, buf);
+        buf.put_slice(&self.unknown_fields);
+    }
+
+    fn merge_field<B: Buf>(
+        &mut self,
+        tag: u32,
+        wire_type: WireType,
+        buf: &mut B,
+        ctx: DecodeContext,
+    ) -> Result<(), DecodeError> {
+        match tag {
+            1 => btree_map::merge(string::merge, message::merge, &mut self.fields, buf, ctx),
+            COMPRESSED_FIELDS_TAG => {
+                message::merge_repeated(wire_type, &mut self.compressed_fields, buf, ctx)
+            }
+            _ => merge_unknown_field(&mut self.unknown_fields, tag, wire_type, buf, ctx),
+        }
+    }
+
+    fn encoded_len(&self) -> usize {
+        btree_map::encoded_len(string::encoded_len, message::encoded_len, 1, &self.fields)
+            + message::encoded_len_repeated(COMPRESSED_FIELDS_TAG, &self.compressed_fields)
+            + self.unknown_fields.len()
+    }
+
+    fn clear(&mut self) {
+        self.fields.clear();
+        self.compressed_fields.clear();
+        self.unknown_fields.clear();
+    }
+}
+
+/// Append the field `tag` to `unknown_fields`, encoded as it was received. Groups are dropped,
+/// they are deprecated and never used by Vector.
+fn merge_unknown_field<B: Buf>(
+    unknown_fields: &mut Vec<u8>,
+    tag: u32,
+    wire_type: WireType,
+    buf: &mut B,
+    ctx: DecodeContext,
+) -> Result<(), DecodeError> {
+    let len = match wire_type {
+        WireType::Varint => {
+            let value = encoding::decode_varint(buf)?;
+            encoding::encode_key(tag, wire_type, unknown_fields);
+            encoding::encode_varint(value, unknown_fields);
+            return Ok(());
+        }
+        WireType::SixtyFourBit => {
+            encoding::encode_key(tag, wire_type, unknown_fields);
+            8
+        }
+        WireType::ThirtyTwoBit => {
+            encoding::encode_key(tag, wire_type, unknown_fields);
+            4
+        }
+        WireType::LengthDelimited => {
+            let len = encoding::decode_varint(buf)?;
+            encoding::encode_key(tag, wire_type, unknown_fields);
+            encoding::encode_varint(len, unknown_fields);
+            usize::try_from(len).map_err(|_| DecodeError::new("field too long"))?
+        }
+        WireType::StartGroup | WireType::EndGroup => {
+            return encoding::skip_field(wire_type, tag, buf, ctx);
+        }
+    };
+    if buf.remaining() < len {
+        return Err(DecodeError::new("buffer underflow"));
+    }
+    unknown_fields.put(buf.take(len));
+    Ok(())
+}
+
 impl From<Event> for EventWrapper {
     fn from(event: Event) -> Self {
-        Self { event: Some(event) }
+        Self {
+            event: Some(event),
+            event_id: Vec::new(),
+            unknown_fields: Vec::new(),
+        }
     }
 }
 
@@ -31,13 +208,45 @@ impl From<Metric> for Event {
 
 impl From<Log> for event::LogEvent {
     fn from(log: Log) -> Self {
-        let fields = log
-            .fields
-            .into_iter()
-            .filter_map(|(k, v)| decode_value(v).map(|value| (k, value)))
-            .collect::<BTreeMap<_, _>>();
+        log.try_into_log_event(usize::MAX)
+            .expect("no limit on the number of fields")
+    }
+}
 
-        Self::from(fields)
+impl Log {
+    /// The log event of this message, refused if it has more than `max_fields` fields, counting
+    /// those of nested maps at every depth. The fields are counted as they are decoded, and
+    /// decoding stops at the first one over the limit.
+    ///
+    /// # Errors
+    ///
+    /// Fails with `FieldCountError::TooManyFields` if there are more than `max_fields` fields.
+    pub fn try_into_log_event(self, max_fields: usize) -> Result<event::LogEvent, FieldCountError> {
+        let mut budget = FieldBudget::new(max_fields);
+        let mut fields = BTreeMap::new();
+        for (key, value) in self.fields {
+            budget.take()?;
+            if let Some(value) = decode_value(value, &mut budget)? {
+                fields.insert(key, value);
+            }
+        }
+        let mut log = event::LogEvent::from(fields);
+        for field in self.compressed_fields {
+            // A codec added by a newer Vector can't be decompressed here, so the field is passed
+            // on as plain bytes.
+            if let Some(codec) = compressed_field::Codec::from_i32(field.codec) {
+                log.restore_compressed_field(field.path, codec.into());
+            }
+        }
+
+        if !self.unknown_fields.is_empty() {
+            log.metadata_mut()
+                .set_unknown_proto_fields(Some(UnknownProtoFields {
+                    log: Bytes::from(self.unknown_fields),
+                    ..UnknownProtoFields::default()
+                }));
+        }
+        Ok(log)
     }
 }
 
@@ -118,12 +327,41 @@ impl From<Metric> for event::Metric {
 
 impl From<EventWrapper> for event::Event {
     fn from(proto: EventWrapper) -> Self {
-        let event = proto.event.unwrap();
+        proto
+            .try_into_event(usize::MAX)
+            .expect("no limit on the number of fields")
+    }
+}
 
-        match event {
-            Event::Log(proto) => Self::Log(proto.into()),
-            Event::Metric(proto) => Self::Metric(proto.into()),
+impl EventWrapper {
+    /// The event of this message, refused if it is a log event with more than `max_fields`
+    /// top-level fields. The fields past the limit are never decoded into values.
+    ///
+    /// # Errors
+    ///
+    /// Fails with `FieldCountError::TooManyFields` if there are more than `max_fields` fields.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the event is of a type unknown to this Vector, that is if `event` is `None`.
+    pub fn try_into_event(self, max_fields: usize) -> Result<event::Event, FieldCountError> {
+        let event = self.event.unwrap();
+
+        let mut event = match event {
+            Event::Log(proto) => event::Event::Log(proto.try_into_log_event(max_fields)?),
+            Event::Metric(proto) => event::Event::Metric(proto.into()),
+        };
+        if !self.unknown_fields.is_empty() {
+            let metadata = event.metadata_mut();
+            let mut unknown = metadata.unknown_proto_fields().cloned().unwrap_or_default();
+            unknown.wrapper = Bytes::from(self.unknown_fields);
+            metadata.set_unknown_proto_fields(Some(unknown));
+        }
+        // An id of the wrong length can't be one Vector minted, it is dropped.
+        if let Ok(event_id) = EventId::from_slice(&self.event_id) {
+            event.metadata_mut().set_event_id(Some(event_id));
         }
+        Ok(event)
     }
 }
 
@@ -135,13 +373,22 @@ impl From<event::LogEvent> for Log {
 
 impl From<event::LogEvent> for WithMetadata<Log> {
     fn from(log_event: event::LogEvent) -> Self {
+        let compressed_fields = encode_compressed_fields(&log_event);
         let (fields, metadata) = log_event.into_parts();
         let fields = fields
             .into_iter()
             .map(|(k, v)| (k, encode_value(v)))
             .collect::<BTreeMap<_, _>>();
 
-        let data = Log { fields };
+        let unknown_fields = metadata
+            .unknown_proto_fields()
+            .map(|unknown| unknown.log.to_vec())
+            .unwrap_or_default();
+        let data = Log {
+            fields,
+            compressed_fields,
+            unknown_fields,
+        };
         Self { data, metadata }
     }
 }
@@ -262,7 +509,81 @@ impl From<event::Event> for EventWrapper {
 
 impl From<event::Event> for WithMetadata<EventWrapper> {
     fn from(event: event::Event) -> Self {
-        WithMetadata::<Event>::from(event).into()
+        let WithMetadata { data, metadata } = WithMetadata::<Event>::from(event);
+        let unknown_fields = metadata
+            .unknown_proto_fields()
+            .map(|unknown| unknown.wrapper.to_vec())
+            .unwrap_or_default();
+        let data = EventWrapper {
+            event: Some(data),
+            event_id: metadata
+                .event_id()
+                .map(|event_id| event_id.as_bytes().to_vec())
+                .unwrap_or_default(),
+            unknown_fields,
+        };
+        Self { data, metadata }
+    }
+}
+
+impl From<&event::Event> for EventWrapper {
+    /// Encode an event that is shared with other components without taking it. Only what the
+    /// protobuf form holds is copied, and the finalizers are left with the event.
+    fn from(event: &event::Event) -> Self {
+        let metadata = event.metadata();
+        let unknown = metadata.unknown_proto_fields();
+        let data = match event {
+            event::Event::Log(log) => Event::Log(Log {
+                fields: log
+                    .as_map()
+                    .iter()
+                    .map(|(k, v)| (k.clone(), encode_value_ref(v)))
+                    .collect(),
+                compressed_fields: encode_compressed_fields(log),
+                unknown_fields: unknown
+                    .map(|unknown| unknown.log.to_vec())
+                    .unwrap_or_default(),
+            }),
+            // Metrics are small, and have no shared parts to copy them from.
+            event::Event::Metric(metric) => Event::Metric(metric.clone().into()),
+        };
+        EventWrapper {
+            event: Some(data),
+            event_id: metadata
+                .event_id()
+                .map(|event_id| event_id.as_bytes().to_vec())
+                .unwrap_or_default(),
+            unknown_fields: unknown
+                .map(|unknown| unknown.wrapper.to_vec())
+                .unwrap_or_default(),
+        }
+    }
+}
+
+fn encode_compressed_fields(log: &event::LogEvent) -> Vec<CompressedField> {
+    log.compressed_fields()
+        .map(|(path, codec)| CompressedField {
+            path: path.to_owned(),
+            codec: compressed_field::Codec::from(codec) as i32,
+        })
+        .collect()
+}
+
+impl From<event::FieldCodec> for compressed_field::Codec {
+    fn from(codec: event::FieldCodec) -> Self {
+        match codec {
+            event::FieldCodec::Gzip => Self::Gzip,
+            event::FieldCodec::Zstd => Self::Zstd,
+        }
+    }
+}
+
+impl From<compressed_field::Codec> for event::FieldCodec {
+    fn from(codec: compressed_field::Codec) -> Self {
+        match codec {
+            compressed_field::Codec::Gzip => Self::Gzip,
+            compressed_field::Codec::Zstd => Self::Zstd,
+        }
     }
 }
 
@@ -318,8 +639,13 @@ impl From<sketch::AgentDdSketch> for MetricSketch {
     }
 }
 
-fn decode_value(input: Value) -> Option<event::Value> {
-    match input.kind {
+/// Decode a value of a log event, counting the fields of the maps in it against `budget`. A
+/// value of an unknown kind, as well as any map or array holding one, is left out.
+fn decode_value(
+    input: Value,
+    budget: &mut FieldBudget,
+) -> Result<Option<event::Value>, FieldCountError> {
+    Ok(match input.kind {
         Some(value::Kind::RawBytes(data)) => Some(event::Value::Bytes(data)),
         Some(value::Kind::Timestamp(ts)) => Some(event::Value::Timestamp(
             chrono::Utc.timestamp(ts.seconds, ts.nanos as u32),
@@ -327,38 +653,45 @@ fn decode_value(input: Value) -> Option<event::Value> {
         Some(value::Kind::Integer(value)) => Some(event::Value::Integer(value)),
         Some(value::Kind::Float(value)) => Some(event::Value::Float(value)),
         Some(value::Kind::Boolean(value)) => Some(event::Value::Boolean(value)),
-        Some(value::Kind::Map(map)) => decode_map(map.fields),
-        Some(value::Kind::Array(array)) => decode_array(array.items),
+        Some(value::Kind::Map(map)) => decode_map(map.fields, budget)?,
+        Some(value::Kind::Array(array)) => decode_array(array.items, budget)?,
         Some(value::Kind::Null(_)) => Some(event::Value::Null),
         None => {
             error!("Encoded event contains unknown value kind.");
             None
         }
-    }
+    })
 }
 
-fn decode_map(fields: BTreeMap<String, Value>) -> Option<event::Value> {
+fn decode_map(
+    fields: BTreeMap<String, Value>,
+    budget: &mut FieldBudget,
+) -> Result<Option<event::Value>, FieldCountError> {
     let mut accum: BTreeMap<String, event::Value> = BTreeMap::new();
     for (key, value) in fields {
-        match decode_value(value) {
+        budget.take()?;
+        match decode_value(value, budget)? {
             Some(value) => {
                 accum.insert(key, value);
             }
-            None => return None,
+            None => return Ok(None),
         }
     }
-    Some(event::Value::Map(accum))
+    Ok(Some(event::Value::Map(accum)))
 }
 
-fn decode_array(items: Vec<Value>) -> Option<event::Value> {
+fn decode_array(
+    items: Vec<Value>,
+    budget: &mut FieldBudget,
+) -> Result<Option<event::Value>, FieldCountError> {
     let mut accum = Vec::with_capacity(items.len());
     for value in items {
-        match decode_value(value) {
+        match decode_value(value, budget)? {
             Some(value) => accum.push(value),
-            None => return None,
+            None => return Ok(None),
         }
     }
-    Some(event::Value::Array(accum))
+    Ok(Some(event::Value::Array(accum)))
 }
 
 fn encode_value(value: event::Value) -> Value {
@@ -379,6 +712,26 @@ fn encode_value(value: event::Value) -> Value {
     }
 }
 
+/// Like `encode_value`, copying the value. Bytes are only copied by reference.
+fn encode_value_ref(value: &event::Value) -> Value {
+    match value {
+        event::Value::Map(fields) => Value {
+            kind: Some(value::Kind::Map(ValueMap {
+                fields: fields
+                    .iter()
+                    .map(|(key, value)| (key.clone(), encode_value_ref(value)))
+                    .collect(),
+            })),
+        },
+        event::Value::Array(items) => Value {
+            kind: Some(value::Kind::Array(ValueArray {
+                items: items.iter().map(encode_value_ref).collect(),
+            })),
+        },
+        value => encode_value(value.clone()),
+    }
+}
+
 fn encode_map(fields: BTreeMap<String, event::Value>) -> ValueMap {
     ValueMap {
         fields: fields
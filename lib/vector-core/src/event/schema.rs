@@ -0,0 +1,385 @@
+use std::fmt;
+
+use chrono::{DateTime, Utc};
+
+use super::{metric::MAX_EXACT_INTEGER_VALUE, LogEvent, Value};
+
+/// The kind of value a field of an [`EventSchema`] must hold.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum FieldKind {
+    String,
+    Integer,
+    Float,
+    Boolean,
+    Timestamp,
+    Map,
+    Array,
+}
+
+impl FieldKind {
+    /// The kind of `value`, or `None` for `null`.
+    pub const fn of(value: &Value) -> Option<Self> {
+        match value {
+            Value::Bytes(_) => Some(Self::String),
+            Value::Integer(_) => Some(Self::Integer),
+            Value::Float(_) => Some(Self::Float),
+            Value::Boolean(_) => Some(Self::Boolean),
+            Value::Timestamp(_) => Some(Self::Timestamp),
+            Value::Map(_) => Some(Self::Map),
+            Value::Array(_) => Some(Self::Array),
+            Value::Null => None,
+        }
+    }
+
+    /// The name of the kind, as returned by [`Value::kind`].
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::String => "string",
+            Self::Integer => "integer",
+            Self::Float => "float",
+            Self::Boolean => "boolean",
+            Self::Timestamp => "timestamp",
+            Self::Map => "map",
+            Self::Array => "array",
+        }
+    }
+
+    /// Convert `value` to this kind, if that can be done without losing anything. Strings are
+    /// parsed, integers and floats convert to each other when exact, and any scalar converts to a
+    /// string.
+    #[allow(
+        clippy::cast_possible_truncation,
+        clippy::cast_precision_loss,
+        clippy::cast_sign_loss
+    )]
+    pub fn coerce(self, value: &Value) -> Option<Value> {
+        let coerced = match (self, value) {
+            (Self::String, Value::Map(_) | Value::Array(_) | Value::Null) => return None,
+            (Self::String, value) => Value::Bytes(value.as_bytes()),
+            (Self::Integer, Value::Float(float))
+                if float.fract() == 0.0 && float.abs() <= MAX_EXACT_INTEGER_VALUE =>
+            {
+                Value::Integer(*float as i64)
+            }
+            (Self::Integer, Value::Bytes(bytes)) => {
+                Value::Integer(std::str::from_utf8(bytes).ok()?.parse().ok()?)
+            }
+            (Self::Float, Value::Integer(integer))
+                if integer.unsigned_abs() <= MAX_EXACT_INTEGER_VALUE as u64 =>
+            {
+                Value::Float(*integer as f64)
+            }
+            (Self::Float, Value::Bytes(bytes)) => {
+                let float: f64 = std::str::from_utf8(bytes).ok()?.parse().ok()?;
+                if !float.is_finite() {
+                    return None;
+                }
+                Value::Float(float)
+            }
+            (Self::Boolean, Value::Bytes(bytes)) => match bytes.as_ref() {
+                b"true" => Value::Boolean(true),
+                b"false" => Value::Boolean(false),
+                _ => return None,
+            },
+            (Self::Timestamp, Value::Bytes(bytes)) => {
+                let timestamp = DateTime::parse_from_rfc3339(std::str::from_utf8(bytes).ok()?)
+                    .ok()?
+                    .with_timezone(&Utc);
+                Value::Timestamp(timestamp)
+            }
+            _ => return None,
+        };
+        Some(coerced)
+    }
+}
+
+impl fmt::Display for FieldKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A field an [`EventSchema`] requires.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RequiredField {
+    pub path: String,
+    pub kind: FieldKind,
+    /// The value [`LogEvent::conform`] fills in when the field is missing or `null`.
+    pub default: Option<Value>,
+}
+
+/// The fields a log event must have, and the kinds of their values, for a strict downstream
+/// schema.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct EventSchema {
+    pub fields: Vec<RequiredField>,
+}
+
+impl EventSchema {
+    /// Require the field at `path` to hold a value of `kind`.
+    #[must_use]
+    pub fn required(mut self, path: impl Into<String>, kind: FieldKind) -> Self {
+        self.fields.push(RequiredField {
+            path: path.into(),
+            kind,
+            default: None,
+        });
+        self
+    }
+
+    /// Require the field at `path` to hold a value of `kind`, which is `default` when it is
+    /// missing.
+    #[must_use]
+    pub fn required_or(
+        mut self,
+        path: impl Into<String>,
+        kind: FieldKind,
+        default: impl Into<Value>,
+    ) -> Self {
+        self.fields.push(RequiredField {
+            path: path.into(),
+            kind,
+            default: Some(default.into()),
+        });
+        self
+    }
+}
+
+/// A field of a log event that doesn't conform to an [`EventSchema`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SchemaViolation {
+    pub path: String,
+    pub expected: FieldKind,
+    /// The kind of the value the field holds, or `None` if it is missing or `null`.
+    pub found: Option<FieldKind>,
+}
+
+impl fmt::Display for SchemaViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.found {
+            Some(found) => write!(
+                f,
+                "field {} should be of kind {}, found {}",
+                self.path, self.expected, found
+            ),
+            None => write!(
+                f,
+                "missing required field {} of kind {}",
+                self.path, self.expected
+            ),
+        }
+    }
+}
+
+impl std::error::Error for SchemaViolation {}
+
+pub(super) fn validate(log: &LogEvent, schema: &EventSchema) -> Vec<SchemaViolation> {
+    schema
+        .fields
+        .iter()
+        .filter_map(|field| check(log, field))
+        .collect()
+}
+
+pub(super) fn conform(log: &mut LogEvent, schema: &EventSchema) -> Vec<SchemaViolation> {
+    schema
+        .fields
+        .iter()
+        .filter_map(|field| {
+            let violation = check(log, field)?;
+            let fixed = match log.get(&field.path) {
+                None | Some(Value::Null) => field.default.clone(),
+                Some(value) => field.kind.coerce(value),
+            };
+            match fixed {
+                Some(value) if FieldKind::of(&value) == Some(field.kind) => {
+                    log.insert(&field.path, value);
+                    None
+                }
+                _ => Some(violation),
+            }
+        })
+        .collect()
+}
+
+fn check(log: &LogEvent, field: &RequiredField) -> Option<SchemaViolation> {
+    let found = log.get(&field.path).and_then(FieldKind::of);
+    (found != Some(field.kind)).then(|| SchemaViolation {
+        path: field.path.clone(),
+        expected: field.kind,
+        found,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::TimeZone;
+    use shared::btreemap;
+
+    use super::*;
+
+    fn schema() -> EventSchema {
+        EventSchema::default()
+            .required("message", FieldKind::String)
+            .required("status", FieldKind::Integer)
+            .required_or("request.duration", FieldKind::Float, 0.0)
+            .required("sampled", FieldKind::Boolean)
+    }
+
+    fn violation(path: &str, expected: FieldKind, found: Option<FieldKind>) -> SchemaViolation {
+        SchemaViolation {
+            path: path.to_owned(),
+            expected,
+            found,
+        }
+    }
+
+    #[test]
+    fn validate_reports_every_violation() {
+        let cases = vec![
+            (
+                btreemap! {
+                    "message" => "ok",
+                    "status" => 200,
+                    "request" => btreemap! { "duration" => 1.5 },
+                    "sampled" => true,
+                },
+                vec![],
+            ),
+            (
+                btreemap! {
+                    "message" => Value::Null,
+                    "status" => "200",
+                    "sampled" => true,
+                },
+                vec![
+                    violation("message", FieldKind::String, None),
+                    violation("status", FieldKind::Integer, Some(FieldKind::String)),
+                    violation("request.duration", FieldKind::Float, None),
+                ],
+            ),
+            (
+                btreemap! {
+                    "message" => vec![1],
+                    "status" => 200.0,
+                    "request" => "slow",
+                    "sampled" => btreemap! {},
+                },
+                vec![
+                    violation("message", FieldKind::String, Some(FieldKind::Array)),
+                    violation("status", FieldKind::Integer, Some(FieldKind::Float)),
+                    violation("request.duration", FieldKind::Float, None),
+                    violation("sampled", FieldKind::Boolean, Some(FieldKind::Map)),
+                ],
+            ),
+        ];
+
+        for (fields, expected) in cases {
+            let log = LogEvent::from(fields);
+            assert_eq!(log.validate(&schema()), expected);
+        }
+    }
+
+    #[test]
+    fn conform_fills_defaults_and_coerces() {
+        let cases = vec![
+            (
+                btreemap! {
+                    "message" => 42,
+                    "status" => "404",
+                    "sampled" => "false",
+                },
+                btreemap! {
+                    "message" => "42",
+                    "status" => 404,
+                    "request" => btreemap! { "duration" => 0.0 },
+                    "sampled" => false,
+                },
+                vec![],
+            ),
+            (
+                btreemap! {
+                    "message" => "ok",
+                    "status" => 200.5,
+                    "request" => btreemap! { "duration" => 3 },
+                    "sampled" => "yes",
+                },
+                btreemap! {
+                    "message" => "ok",
+                    "status" => 200.5,
+                    "request" => btreemap! { "duration" => 3.0 },
+                    "sampled" => "yes",
+                },
+                vec![
+                    violation("status", FieldKind::Integer, Some(FieldKind::Float)),
+                    violation("sampled", FieldKind::Boolean, Some(FieldKind::String)),
+                ],
+            ),
+        ];
+
+        for (fields, expected_fields, expected) in cases {
+            let mut log = LogEvent::from(fields);
+            assert_eq!(log.conform(&schema()), expected);
+            assert_eq!(log, LogEvent::from(expected_fields));
+            assert_eq!(log.validate(&schema()), expected);
+        }
+    }
+
+    #[test]
+    #[allow(clippy::cast_possible_truncation, clippy::cast_precision_loss)]
+    fn coerce() {
+        let timestamp = Utc.ymd(2021, 1, 1).and_hms(0, 0, 0);
+        let too_large = MAX_EXACT_INTEGER_VALUE as i64 + 1;
+        let cases = vec![
+            (
+                FieldKind::String,
+                Value::from(1.5),
+                Some(Value::from("1.5")),
+            ),
+            (
+                FieldKind::String,
+                Value::from(true),
+                Some(Value::from("true")),
+            ),
+            (
+                FieldKind::String,
+                Value::from(timestamp),
+                Some(Value::from("2021-01-01T00:00:00Z")),
+            ),
+            (FieldKind::String, Value::Array(vec![]), None),
+            (FieldKind::Integer, Value::from(" 1"), None),
+            (FieldKind::Integer, Value::from(1e300), None),
+            (
+                FieldKind::Float,
+                Value::from("1e3"),
+                Some(Value::from(1000.0)),
+            ),
+            (FieldKind::Float, Value::from("NaN"), None),
+            (FieldKind::Float, Value::from(too_large), None),
+            (FieldKind::Boolean, Value::from(1), None),
+            (
+                FieldKind::Timestamp,
+                Value::from("2021-01-01T01:00:00+01:00"),
+                Some(Value::from(timestamp)),
+            ),
+            (FieldKind::Timestamp, Value::from(1_609_459_200), None),
+            (FieldKind::Map, Value::from("{}"), None),
+        ];
+
+        for (kind, value, expected) in cases {
+            assert_eq!(kind.coerce(&value), expected, "{} from {:?}", kind, value);
+        }
+    }
+
+    #[test]
+    fn violation_display() {
+        assert_eq!(
+            violation("status", FieldKind::Integer, Some(FieldKind::String)).to_string(),
+            "field status should be of kind integer, found string"
+        );
+        assert_eq!(
+            violation("message", FieldKind::String, None).to_string(),
+            "missing required field message of kind string"
+        );
+    }
+}
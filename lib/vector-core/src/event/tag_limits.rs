@@ -0,0 +1,252 @@
+use core_common::internal_event::{emit, InternalEvent};
+use metrics::counter;
+use serde::{Deserialize, Serialize};
+
+use super::metric::MetricTags;
+
+/// Limits on the tags of a metric, to keep tag cardinality from running away when unbounded
+/// values, such as user or request ids, end up in tags.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct TagLimits {
+    /// The most tags a metric may have.
+    pub max_tags: Option<usize>,
+    /// The longest tag key, in bytes.
+    pub max_key_length: Option<usize>,
+    /// The longest tag value, in bytes.
+    pub max_value_length: Option<usize>,
+    /// What to do with tags whose key or value is too long.
+    #[serde(default)]
+    pub action: TagLimitAction,
+}
+
+/// What to do with a tag whose key or value is longer than allowed.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TagLimitAction {
+    /// Shorten the key or value to the limit.
+    Truncate,
+    /// Remove the tag.
+    Drop,
+}
+
+impl Default for TagLimitAction {
+    fn default() -> Self {
+        Self::Truncate
+    }
+}
+
+/// The tags changed by enforcing [`TagLimits`].
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct TagLimitReport {
+    /// The original keys of the tags that were removed.
+    pub dropped: Vec<String>,
+    /// The keys, as they are now, of the tags whose key or value was shortened.
+    pub truncated: Vec<String>,
+}
+
+impl TagLimitReport {
+    /// Whether no tag was changed.
+    pub fn is_empty(&self) -> bool {
+        self.dropped.is_empty() && self.truncated.is_empty()
+    }
+
+    /// Emit an internal event for the metric named `name` if any of its tags were changed.
+    pub(super) fn emit(&self, name: &str) {
+        if !self.is_empty() {
+            emit(&MetricTagsLimited {
+                name,
+                dropped: self.dropped.len(),
+                truncated: self.truncated.len(),
+            });
+        }
+    }
+}
+
+/// Enforces [`TagLimits`] on the tags of a metric.
+pub trait MetricTagsExt {
+    /// Truncate or drop the tags exceeding the given limits, returning what was changed.
+    ///
+    /// Key and value lengths are enforced first. When there are more tags than allowed, the tags
+    /// after the first `max_tags` in key order are dropped. A tag whose truncated key matches
+    /// another tag's key is dropped as well.
+    fn enforce_limits(&mut self, limits: &TagLimits) -> TagLimitReport;
+}
+
+impl MetricTagsExt for MetricTags {
+    fn enforce_limits(&mut self, limits: &TagLimits) -> TagLimitReport {
+        let mut report = TagLimitReport::default();
+        for (key, value) in std::mem::take(self) {
+            limits.insert(self, key, value, &mut report);
+        }
+        report
+    }
+}
+
+impl TagLimits {
+    /// Insert a tag into `tags`, within these limits. Overwriting an existing tag is always
+    /// allowed, but a new tag is dropped if `tags` already holds `max_tags` tags.
+    pub(super) fn insert(
+        &self,
+        tags: &mut MetricTags,
+        key: String,
+        value: String,
+        report: &mut TagLimitReport,
+    ) {
+        let (key, original_key) = match self.limit(key, self.max_key_length) {
+            Ok(key) => key,
+            Err(key) => return report.dropped.push(key),
+        };
+        let (value, original_value) = match self.limit(value, self.max_value_length) {
+            Ok(value) => value,
+            Err(_) => return report.dropped.push(original_key.unwrap_or(key)),
+        };
+
+        let exists = tags.contains_key(&key);
+        let full = self
+            .max_tags
+            .map_or(false, |max_tags| tags.len() >= max_tags);
+        if (original_key.is_some() && exists) || (!exists && full) {
+            return report.dropped.push(original_key.unwrap_or(key));
+        }
+
+        if original_key.is_some() || original_value.is_some() {
+            report.truncated.push(key.clone());
+        }
+        tags.insert(key, value);
+    }
+
+    /// Apply a length limit to a tag key or value. Returns the original as well if it was
+    /// truncated, or as the error if it must be dropped.
+    fn limit(
+        &self,
+        s: String,
+        max_length: Option<usize>,
+    ) -> Result<(String, Option<String>), String> {
+        match max_length {
+            Some(max_length) if s.len() > max_length => match self.action {
+                TagLimitAction::Truncate => {
+                    let mut len = max_length;
+                    while !s.is_char_boundary(len) {
+                        len -= 1;
+                    }
+                    Ok((s[..len].to_owned(), Some(s)))
+                }
+                TagLimitAction::Drop => Err(s),
+            },
+            _ => Ok((s, None)),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct MetricTagsLimited<'a> {
+    name: &'a str,
+    dropped: usize,
+    truncated: usize,
+}
+
+impl<'a> InternalEvent for MetricTagsLimited<'a> {
+    fn emit_logs(&self) {
+        warn!(
+            message = "Metric tags exceeded the configured limits.",
+            name = %self.name,
+            dropped = %self.dropped,
+            truncated = %self.truncated,
+            internal_log_rate_secs = 10,
+        );
+    }
+
+    fn emit_metrics(&self) {
+        counter!("metric_tags_dropped_total", self.dropped as u64);
+        counter!("metric_tags_truncated_total", self.truncated as u64);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn tags(tags: &[(&str, &str)]) -> MetricTags {
+        tags.iter()
+            .map(|(key, value)| ((*key).to_owned(), (*value).to_owned()))
+            .collect()
+    }
+
+    fn limits(action: TagLimitAction) -> TagLimits {
+        TagLimits {
+            max_tags: Some(3),
+            max_key_length: Some(4),
+            max_value_length: Some(5),
+            action,
+        }
+    }
+
+    #[test]
+    fn within_limits() {
+        let mut within = tags(&[("host", "local"), ("a", ""), ("b", "12345")]);
+        let expected = within.clone();
+
+        for action in [TagLimitAction::Truncate, TagLimitAction::Drop] {
+            assert!(within.enforce_limits(&limits(action)).is_empty());
+            assert_eq!(within, expected);
+        }
+        assert!(within.enforce_limits(&TagLimits::default()).is_empty());
+    }
+
+    #[test]
+    fn truncates_at_boundaries() {
+        let mut truncated = tags(&[("hostname", "localhost"), ("user", "123456"), ("ü", "ééé")]);
+
+        let report = truncated.enforce_limits(&limits(TagLimitAction::Truncate));
+        assert_eq!(
+            truncated,
+            tags(&[("host", "local"), ("user", "12345"), ("ü", "éé")])
+        );
+        assert_eq!(report.dropped, Vec::<String>::new());
+        assert_eq!(report.truncated, vec!["host", "user", "ü"]);
+    }
+
+    #[test]
+    fn truncated_key_collision_drops() {
+        let mut colliding = tags(&[("host", "a"), ("hostname", "b")]);
+
+        let report = colliding.enforce_limits(&limits(TagLimitAction::Truncate));
+        assert_eq!(colliding, tags(&[("host", "a")]));
+        assert_eq!(report.dropped, vec!["hostname"]);
+        assert!(report.truncated.is_empty());
+    }
+
+    #[test]
+    fn drops_too_long() {
+        let mut dropped = tags(&[("hostname", "local"), ("user", "123456"), ("ok", "12345")]);
+
+        let report = dropped.enforce_limits(&limits(TagLimitAction::Drop));
+        assert_eq!(dropped, tags(&[("ok", "12345")]));
+        assert_eq!(report.dropped, vec!["hostname", "user"]);
+        assert!(report.truncated.is_empty());
+    }
+
+    #[test]
+    fn drops_beyond_max_tags() {
+        for action in [TagLimitAction::Truncate, TagLimitAction::Drop] {
+            let mut many = tags(&[("a", "1"), ("b", "2"), ("c", "3"), ("d", "4"), ("e", "5")]);
+
+            let report = many.enforce_limits(&limits(action));
+            assert_eq!(many, tags(&[("a", "1"), ("b", "2"), ("c", "3")]));
+            assert_eq!(report.dropped, vec!["d", "e"]);
+        }
+    }
+
+    #[test]
+    fn insert_overwrites_when_full() {
+        let limits = limits(TagLimitAction::Truncate);
+        let mut full = tags(&[("a", "1"), ("b", "2"), ("c", "3")]);
+        let mut report = TagLimitReport::default();
+
+        limits.insert(&mut full, "b".into(), "22".into(), &mut report);
+        limits.insert(&mut full, "d".into(), "4".into(), &mut report);
+        assert_eq!(full, tags(&[("a", "1"), ("b", "22"), ("c", "3")]));
+        assert_eq!(report.dropped, vec!["d"]);
+    }
+}
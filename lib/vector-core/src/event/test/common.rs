@@ -1,6 +1,7 @@
 use std::{
     collections::{BTreeMap, BTreeSet},
     iter,
+    sync::Arc,
 };
 
 use bytes::Bytes;
@@ -100,7 +101,7 @@ impl Arbitrary for Metric {
         let metadata = EventMetadata::arbitrary(g);
         let mut metric = Metric::new_with_metadata(name, kind, value, metadata);
         metric.data = MetricData::arbitrary(g);
-        metric.series = MetricSeries::arbitrary(g);
+        metric.series = Arc::new(MetricSeries::arbitrary(g));
 
         metric
     }
@@ -121,10 +122,10 @@ impl Arbitrary for Metric {
                     })
                 })
                 .flat_map(|metric| {
-                    let series = metric.series.clone();
+                    let series = metric.series().clone();
                     series.shrink().map(move |series| {
                         let mut new_metric = metric.clone();
-                        new_metric.series = series;
+                        new_metric.series = Arc::new(series);
                         new_metric
                     })
                 }),
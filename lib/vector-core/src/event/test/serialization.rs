@@ -4,7 +4,7 @@ use quickcheck::{QuickCheck, TestResult};
 use regex::Regex;
 
 use super::*;
-use crate::config::log_schema;
+use crate::{config::log_schema, test_util::EventGenerator};
 
 // Ser/De the Event never loses bytes
 #[test]
@@ -65,6 +65,27 @@ fn back_and_forth_through_bytes() {
         .quickcheck(inner as fn(Event) -> TestResult);
 }
 
+// Ser/De generated events covering every `Value` and `MetricValue` variant
+#[test]
+fn generated_back_and_forth_through_bytes() {
+    let mut generator = EventGenerator::from_env();
+
+    for i in 0..1_000 {
+        let event = if i % 2 == 0 {
+            Event::Log(generator.arbitrary_log_event(3, 4))
+        } else {
+            Event::Metric(generator.arbitrary_metric())
+        };
+        let expected = event.clone();
+
+        let mut buffer = BytesMut::with_capacity(64);
+        Event::encode(event, &mut buffer).unwrap();
+        let actual = Event::decode(buffer).unwrap();
+
+        assert_eq!(expected, actual, "seed: {}", generator.seed());
+    }
+}
+
 #[test]
 fn serialization() {
     let mut event = Event::from("raw log line");
@@ -101,3 +122,159 @@ fn type_serialization() {
     assert_eq!(map["bool"], json!(true));
     assert_eq!(map["string"], json!("thisisastring"));
 }
+
+// Counter and gauge values are carried bit for bit, including beyond the exact integer range
+#[test]
+#[allow(clippy::cast_precision_loss)]
+fn metric_value_precision_through_bytes() {
+    let max = metric::MAX_EXACT_INTEGER_VALUE;
+    let values = [
+        0.0,
+        -0.0,
+        max - 1.0,
+        max,
+        max + 2.0,
+        -max,
+        // The integer 2^53 + 1 isn't representable, and rounds to the even neighbour.
+        9_007_199_254_740_993_i64 as f64,
+        u64::MAX as f64,
+        f64::MAX,
+        f64::MIN_POSITIVE,
+        f64::INFINITY,
+    ];
+
+    for value in values {
+        for metric_value in [MetricValue::Counter { value }, MetricValue::Gauge { value }] {
+            let event = Event::Metric(Metric::new("precision", MetricKind::Absolute, metric_value));
+
+            let mut buffer = BytesMut::with_capacity(64);
+            Event::encode(event, &mut buffer).unwrap();
+            let actual = Event::decode(buffer).unwrap();
+
+            match actual.as_metric().value() {
+                MetricValue::Counter { value: actual } | MetricValue::Gauge { value: actual } => {
+                    assert_eq!(actual.to_bits(), value.to_bits(), "{}", value)
+                }
+                other => panic!("unexpected value {:?}", other),
+            }
+        }
+    }
+    assert_eq!((9_007_199_254_740_993_i64 as f64).to_bits(), max.to_bits());
+}
+
+/// The messages of a Vector newer than this one, which added fields to them.
+mod newer {
+    use std::collections::BTreeMap;
+
+    use crate::event::proto::Value;
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct EventWrapper {
+        #[prost(message, optional, tag = "1")]
+        pub log: Option<Log>,
+        #[prost(string, tag = "10")]
+        pub origin: String,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct Log {
+        #[prost(btree_map = "string, message", tag = "1")]
+        pub fields: BTreeMap<String, Value>,
+        #[prost(uint64, tag = "2")]
+        pub sequence: u64,
+        #[prost(bytes = "vec", tag = "3")]
+        pub trace_id: Vec<u8>,
+    }
+}
+
+// Fields added by a newer Vector make it through this one as they were
+#[test]
+fn unknown_proto_fields_through_bytes() {
+    use prost::Message;
+
+    let sent = newer::EventWrapper {
+        log: Some(newer::Log {
+            fields: proto::Log::from(LogEvent::from("hello")).fields,
+            sequence: 42,
+            trace_id: vec![0, 0xff, 1],
+        }),
+        origin: "edge".to_owned(),
+    };
+
+    let mut event = Event::decode(sent.encode_to_vec().as_slice()).unwrap();
+    assert_eq!(event.as_log()["message"], "hello".into());
+    event.as_mut_log().insert("hops", 1);
+    let mut buffer = BytesMut::with_capacity(64);
+    Event::encode(event, &mut buffer).unwrap();
+
+    let received = newer::EventWrapper::decode(buffer).unwrap();
+    assert_eq!(received.origin, "edge");
+    let log = received.log.unwrap();
+    assert_eq!(log.sequence, 42);
+    assert_eq!(log.trace_id, vec![0, 0xff, 1]);
+    assert!(log.fields.contains_key("message"));
+    assert!(log.fields.contains_key("hops"));
+}
+
+// The id of an event makes it through bytes, and an event without one gets none
+#[test]
+fn event_id_through_bytes() {
+    let mut event = Event::from(LogEvent::from("hello"));
+    let event_id = event.metadata_mut().ensure_event_id();
+    assert_eq!(event.metadata_mut().ensure_event_id(), event_id);
+
+    let mut buffer = BytesMut::with_capacity(64);
+    Event::encode(event, &mut buffer).unwrap();
+    let actual = Event::decode(buffer).unwrap();
+    assert_eq!(actual.metadata().event_id(), Some(event_id));
+
+    let mut buffer = BytesMut::with_capacity(64);
+    Event::encode(Event::from(LogEvent::from("hello")), &mut buffer).unwrap();
+    let actual = Event::decode(buffer).unwrap();
+    assert_eq!(actual.metadata().event_id(), None);
+}
+
+// A compressed field makes it through bytes untouched, as it does when a `vector` sink sends it
+#[test]
+fn compressed_field_through_bytes() {
+    let mut log = LogEvent::from("hello");
+    log.insert("body", "a large, repetitive body ".repeat(100));
+    log.compress_field("body", FieldCodec::Zstd).unwrap();
+    let compressed = log.get("body").cloned();
+
+    let mut buffer = BytesMut::with_capacity(256);
+    Event::encode(Event::from(log), &mut buffer).unwrap();
+    let mut actual = Event::decode(buffer).unwrap().into_log();
+    assert_eq!(actual.get("body").cloned(), compressed);
+    assert_eq!(
+        actual.compressed_fields().collect::<Vec<_>>(),
+        vec![("body", FieldCodec::Zstd)]
+    );
+
+    actual.decompress_field("body", FieldCodec::Zstd).unwrap();
+    assert_eq!(
+        actual.get("body"),
+        Some(&Value::from("a large, repetitive body ".repeat(100)))
+    );
+}
+
+// Encoding a shared event gives what encoding it by value does
+#[test]
+fn encode_by_reference() {
+    fn inner(event: Event) -> TestResult {
+        use prost::Message;
+
+        // The bytes are compared, as floats may be NaN.
+        let by_reference = proto::EventWrapper::from(&event).encode_to_vec();
+        assert_eq!(
+            by_reference,
+            proto::EventWrapper::from(event).encode_to_vec()
+        );
+        TestResult::passed()
+    }
+
+    QuickCheck::new()
+        .tests(1_000)
+        .max_tests(10_000)
+        .quickcheck(inner as fn(Event) -> TestResult);
+}
@@ -3,6 +3,7 @@ use std::{
     iter, slice,
 };
 
+use lookup::{LookupBuf, SegmentBuf};
 use serde::{Serialize, Serializer};
 
 use super::Value;
@@ -15,6 +16,19 @@ pub fn all_fields(
     FieldsIter::new(fields)
 }
 
+/// Iterates over the paths of all leaves in the same order as [`all_fields`], as typed lookups
+/// that need no parsing or escaping.
+pub fn paths(fields: &BTreeMap<String, Value>) -> impl Iterator<Item = LookupBuf> + '_ {
+    paths_with_values(fields).map(|(path, _)| path)
+}
+
+/// Iterates over the paths of all leaves, as [`paths`] does, and their corresponding values.
+pub fn paths_with_values(
+    fields: &BTreeMap<String, Value>,
+) -> impl Iterator<Item = (LookupBuf, &Value)> {
+    PathsIter(FieldsIter::new(fields))
+}
+
 #[derive(Clone)]
 enum LeafIter<'a> {
     Map(btree_map::Iter<'a, String, Value>),
@@ -67,6 +81,45 @@ impl<'a> FieldsIter<'a> {
         self.path.pop();
     }
 
+    /// Advances to the next leaf, returning its own path component. The components of its
+    /// parents stay in `path` until the next call.
+    fn next_leaf(&mut self) -> Option<(PathComponent<'a>, &'a Value)> {
+        loop {
+            match self.stack.last_mut() {
+                None => return None,
+                Some(LeafIter::Map(map_iter)) => match map_iter.next() {
+                    None => self.pop(),
+                    Some((key, value)) => {
+                        if let Some(scalar_value) = self.push(value, PathComponent::Key(key)) {
+                            return Some((PathComponent::Key(key), scalar_value));
+                        }
+                    }
+                },
+                Some(LeafIter::Array(array_iter)) => match array_iter.next() {
+                    None => self.pop(),
+                    Some((index, value)) => {
+                        if let Some(scalar_value) = self.push(value, PathComponent::Index(index)) {
+                            return Some((PathComponent::Index(index), scalar_value));
+                        }
+                    }
+                },
+            };
+        }
+    }
+
+    fn make_lookup(&self, component: PathComponent<'a>) -> LookupBuf {
+        let segments = self
+            .path
+            .iter()
+            .chain(iter::once(&component))
+            .map(|component| match component {
+                PathComponent::Key(key) => SegmentBuf::field_unchecked((*key).clone()),
+                PathComponent::Index(index) => SegmentBuf::Index(*index as isize),
+            })
+            .collect::<Vec<_>>();
+        LookupBuf::from_segments(segments)
+    }
+
     fn make_path(&mut self, component: PathComponent<'a>) -> String {
         let mut res = String::new();
         let mut path_iter = self.path.iter().chain(iter::once(&component)).peekable();
@@ -93,30 +146,20 @@ impl<'a> Iterator for FieldsIter<'a> {
     type Item = (String, &'a Value);
 
     fn next(&mut self) -> Option<Self::Item> {
-        loop {
-            match self.stack.last_mut() {
-                None => return None,
-                Some(LeafIter::Map(map_iter)) => match map_iter.next() {
-                    None => self.pop(),
-                    Some((key, value)) => {
-                        if let Some(scalar_value) = self.push(value, PathComponent::Key(key)) {
-                            return Some((self.make_path(PathComponent::Key(key)), scalar_value));
-                        }
-                    }
-                },
-                Some(LeafIter::Array(array_iter)) => match array_iter.next() {
-                    None => self.pop(),
-                    Some((index, value)) => {
-                        if let Some(scalar_value) = self.push(value, PathComponent::Index(index)) {
-                            return Some((
-                                self.make_path(PathComponent::Index(index)),
-                                scalar_value,
-                            ));
-                        }
-                    }
-                },
-            };
-        }
+        let (component, value) = self.next_leaf()?;
+        Some((self.make_path(component), value))
+    }
+}
+
+/// The leaves of a [`FieldsIter`] with their paths as lookups.
+struct PathsIter<'a>(FieldsIter<'a>);
+
+impl<'a> Iterator for PathsIter<'a> {
+    type Item = (LookupBuf, &'a Value);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (component, value) = self.0.next_leaf()?;
+        Some((self.0.make_lookup(component), value))
     }
 }
 
@@ -190,4 +233,38 @@ mod test {
         let collected: Vec<_> = all_fields(&fields).map(|(k, v)| (k, v.clone())).collect();
         assert_eq!(collected, expected);
     }
+
+    #[test]
+    fn paths_get_their_values() {
+        let fields = fields_from_json(json!({
+            "a": {
+                "b": [1, {"c": true}, [null, "x"]],
+                "a.b": 2,
+            },
+            "\"quoted\"": 3,
+            "with space": {"[0]": 4},
+            "d": {},
+            "e": [],
+        }));
+        let root = Value::Map(fields.clone());
+
+        let collected: Vec<_> = paths_with_values(&fields).collect();
+        assert_eq!(collected.len(), all_fields(&fields).count());
+        for (path, value) in &collected {
+            assert_eq!(root.get(path).unwrap(), Some(*value), "{}", path);
+        }
+
+        let paths: Vec<_> = paths(&fields).collect();
+        assert_eq!(
+            paths,
+            collected
+                .into_iter()
+                .map(|(path, _)| path)
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            paths[2],
+            LookupBuf::from_segments(vec!["a".into(), "b".into(), 1.into(), "c".into()])
+        );
+    }
 }
@@ -0,0 +1,99 @@
+use std::{convert::TryFrom, fmt, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+
+use super::{path_iter::parse_path, PathComponent};
+use crate::event::error::PathError;
+
+/// A path in `a.b[1].c` notation that has been parsed up front, for looking up
+/// the same fields in many events without parsing the path each time.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct CompiledLookup {
+    path: String,
+    components: Vec<PathComponent<'static>>,
+}
+
+impl CompiledLookup {
+    /// Parse the given path.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the path is invalid.
+    pub fn new(path: &str) -> Result<Self, PathError> {
+        let components = parse_path(path)?
+            .into_iter()
+            .map(PathComponent::into_static)
+            .collect();
+        Ok(Self {
+            path: path.to_owned(),
+            components,
+        })
+    }
+
+    /// The path as it was given.
+    pub fn as_str(&self) -> &str {
+        &self.path
+    }
+
+    /// The components of the path. There is always at least one, and the
+    /// first is always a key.
+    pub fn components(&self) -> &[PathComponent<'static>] {
+        &self.components
+    }
+}
+
+impl FromStr for CompiledLookup {
+    type Err = PathError;
+
+    fn from_str(path: &str) -> Result<Self, Self::Err> {
+        Self::new(path)
+    }
+}
+
+impl TryFrom<String> for CompiledLookup {
+    type Error = PathError;
+
+    fn try_from(path: String) -> Result<Self, Self::Error> {
+        Self::new(&path)
+    }
+}
+
+impl From<CompiledLookup> for String {
+    fn from(lookup: CompiledLookup) -> Self {
+        lookup.path
+    }
+}
+
+impl fmt::Display for CompiledLookup {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.path)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn compiles_paths() {
+        let lookup = CompiledLookup::new("a.b[1]").unwrap();
+        assert_eq!(lookup.as_str(), "a.b[1]");
+        assert_eq!(
+            lookup.components(),
+            &[
+                PathComponent::Key("a".into()),
+                PathComponent::Key("b".into()),
+                PathComponent::Index(1),
+            ]
+        );
+
+        assert_eq!(
+            "a..b".parse::<CompiledLookup>(),
+            Err(PathError::InvalidPath {
+                path: "a..b".into(),
+                segment: 1
+            })
+        );
+    }
+}
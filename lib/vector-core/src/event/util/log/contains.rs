@@ -4,7 +4,15 @@ use super::{PathComponent, PathIter, Value};
 
 /// Checks whether a field specified by a given path is present.
 pub fn contains(fields: &BTreeMap<String, Value>, path: &str) -> bool {
-    let mut path_iter = PathIter::new(path);
+    contains_path(fields, PathIter::new(path))
+}
+
+/// Checks whether a field specified by the given path components is present.
+pub fn contains_path<'a>(
+    fields: &BTreeMap<String, Value>,
+    path: impl IntoIterator<Item = PathComponent<'a>>,
+) -> bool {
+    let mut path_iter = path.into_iter();
 
     match path_iter.next() {
         Some(PathComponent::Key(key)) => match fields.get(key.as_ref()) {
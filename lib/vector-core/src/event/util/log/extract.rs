@@ -0,0 +1,372 @@
+use std::{collections::BTreeMap, mem};
+
+use super::{CompiledLookup, PathComponent, Value};
+
+/// The requested paths merged into a tree, so that shared prefixes are only
+/// followed once.
+#[derive(Default)]
+struct Node<'a> {
+    /// The positions of the paths ending at this node.
+    requested: Vec<usize>,
+    children: Vec<(&'a PathComponent<'static>, Node<'a>)>,
+}
+
+impl<'a> Node<'a> {
+    fn build(paths: &'a [CompiledLookup]) -> Self {
+        let mut root = Self::default();
+        for (position, path) in paths.iter().enumerate() {
+            let mut node = &mut root;
+            for component in path.components() {
+                let child = match node.children.iter().position(|(c, _)| *c == component) {
+                    Some(child) => child,
+                    None => {
+                        node.children.push((component, Self::default()));
+                        node.children.len() - 1
+                    }
+                };
+                node = &mut node.children[child].1;
+            }
+            node.requested.push(position);
+        }
+        root
+    }
+}
+
+/// Returns references to the field values specified by the given paths, in
+/// the same order as the paths.
+///
+/// The fields are walked once for all of the paths. When both a path and a
+/// path nested below it are given, only the outer one yields a value.
+pub fn extract<'a>(
+    fields: &'a BTreeMap<String, Value>,
+    paths: &[CompiledLookup],
+) -> Vec<Option<&'a Value>> {
+    let mut extracted = vec![None; paths.len()];
+    let root = Node::build(paths);
+    for (component, node) in &root.children {
+        if let PathComponent::Key(key) = component {
+            if let Some(value) = fields.get(key.as_ref()) {
+                visit(node, value, &mut extracted);
+            }
+        }
+    }
+    extracted
+}
+
+fn visit<'a>(node: &Node<'_>, value: &'a Value, extracted: &mut [Option<&'a Value>]) {
+    if !node.requested.is_empty() {
+        for &position in &node.requested {
+            extracted[position] = Some(value);
+        }
+        return;
+    }
+
+    for (component, node) in &node.children {
+        let nested = match (component, value) {
+            (PathComponent::Key(key), Value::Map(map)) => map.get(key.as_ref()),
+            (PathComponent::Index(index), Value::Array(array)) => array.get(*index),
+            _ => None,
+        };
+        if let Some(nested) = nested {
+            visit(node, nested, extracted);
+        }
+    }
+}
+
+/// Removes the field values specified by the given paths and returns them, in
+/// the same order as the paths.
+///
+/// This walks the fields like [`extract`] does. Extracted array elements are
+/// replaced with `null`, so that the indexes of the remaining elements don't
+/// change, and maps left empty are kept. A path given more than once yields
+/// the value at each of its positions.
+pub fn extract_remove(
+    fields: &mut BTreeMap<String, Value>,
+    paths: &[CompiledLookup],
+) -> Vec<Option<Value>> {
+    let mut extracted = vec![None; paths.len()];
+    let root = Node::build(paths);
+    for (component, node) in &root.children {
+        if let PathComponent::Key(key) = component {
+            if node.requested.is_empty() {
+                if let Some(value) = fields.get_mut(key.as_ref()) {
+                    visit_remove(node, value, &mut extracted);
+                }
+            } else if let Some(value) = fields.remove(key.as_ref()) {
+                hand_out(node, value, &mut extracted);
+            }
+        }
+    }
+    extracted
+}
+
+fn visit_remove(node: &Node<'_>, value: &mut Value, extracted: &mut [Option<Value>]) {
+    for (component, node) in &node.children {
+        if node.requested.is_empty() {
+            let nested = match (component, &mut *value) {
+                (PathComponent::Key(key), Value::Map(map)) => map.get_mut(key.as_ref()),
+                (PathComponent::Index(index), Value::Array(array)) => array.get_mut(*index),
+                _ => None,
+            };
+            if let Some(nested) = nested {
+                visit_remove(node, nested, extracted);
+            }
+        } else {
+            let removed = match (component, &mut *value) {
+                (PathComponent::Key(key), Value::Map(map)) => map.remove(key.as_ref()),
+                (PathComponent::Index(index), Value::Array(array)) => array
+                    .get_mut(*index)
+                    .map(|element| mem::replace(element, Value::Null)),
+                _ => None,
+            };
+            if let Some(removed) = removed {
+                hand_out(node, removed, extracted);
+            }
+        }
+    }
+}
+
+fn hand_out(node: &Node<'_>, value: Value, extracted: &mut [Option<Value>]) {
+    let (first, rest) = node
+        .requested
+        .split_first()
+        .expect("only requested nodes hand out values");
+    for &position in rest {
+        extracted[position] = Some(value.clone());
+    }
+    extracted[*first] = Some(value);
+}
+
+/// Copies the field values specified by the given paths into a new map, nested
+/// as they are in `fields`.
+///
+/// The fields are walked once for all of the paths, like [`extract`] does.
+/// Missing paths are left out, and a path nested below another one is covered
+/// by the outer one. Array elements that aren't copied are `null`, so that the
+/// indexes of those that are don't change. Bytes share their buffers with the
+/// originals.
+pub fn extract_subset(
+    fields: &BTreeMap<String, Value>,
+    paths: &[CompiledLookup],
+) -> BTreeMap<String, Value> {
+    copy_map(&Node::build(paths), fields)
+}
+
+fn copy_map(node: &Node<'_>, map: &BTreeMap<String, Value>) -> BTreeMap<String, Value> {
+    node.children
+        .iter()
+        .filter_map(|(component, node)| match component {
+            PathComponent::Key(key) => {
+                let (key, value) = map.get_key_value(key.as_ref())?;
+                copy(node, value).map(|value| (key.clone(), value))
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+fn copy(node: &Node<'_>, value: &Value) -> Option<Value> {
+    if !node.requested.is_empty() {
+        return Some(value.clone());
+    }
+
+    match value {
+        Value::Map(map) => {
+            let copied = copy_map(node, map);
+            (!copied.is_empty()).then(|| Value::Map(copied))
+        }
+        Value::Array(array) => {
+            let mut elements = node
+                .children
+                .iter()
+                .filter_map(|(component, node)| match component {
+                    PathComponent::Index(index) => {
+                        let element = copy(node, array.get(*index)?)?;
+                        Some((*index, element))
+                    }
+                    _ => None,
+                })
+                .collect::<Vec<_>>();
+            elements.sort_by_key(|(index, _)| *index);
+            let len = elements.last()?.0 + 1;
+            let mut copied = vec![Value::Null; len];
+            for (index, element) in elements {
+                copied[index] = element;
+            }
+            Some(Value::Array(copied))
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+
+    use super::{super::test::fields_from_json, *};
+
+    fn lookups(paths: &[&str]) -> Vec<CompiledLookup> {
+        paths
+            .iter()
+            .map(|path| CompiledLookup::new(path).unwrap())
+            .collect()
+    }
+
+    fn fields() -> BTreeMap<String, Value> {
+        fields_from_json(json!({
+            "message": "hello",
+            "a": {
+                "b": {
+                    "c": 5
+                },
+                "d": 4,
+                "array": [null, 3, {
+                    "x": 1
+                }]
+            }
+        }))
+    }
+
+    #[test]
+    fn extract_keeps_order() {
+        let fields = fields();
+        let paths = lookups(&[
+            "a.array[2].x",
+            "missing",
+            "message",
+            "a.d",
+            "a.array[5]",
+            "a.d.e",
+            "a.b.c",
+        ]);
+
+        assert_eq!(
+            extract(&fields, &paths),
+            vec![
+                Some(&Value::Integer(1)),
+                None,
+                Some(&Value::from("hello")),
+                Some(&Value::Integer(4)),
+                None,
+                None,
+                Some(&Value::Integer(5)),
+            ]
+        );
+    }
+
+    #[test]
+    fn extract_matches_get() {
+        let fields = fields();
+        let paths = [
+            "a.b.c",
+            "a.array[1]",
+            "a.array[2].x",
+            "a.x",
+            "a.d.e",
+            "message",
+        ];
+
+        let extracted = extract(&fields, &lookups(&paths));
+        for (path, extracted) in paths.iter().zip(extracted) {
+            assert_eq!(extracted, super::super::get(&fields, path), "{}", path);
+        }
+    }
+
+    #[test]
+    fn extract_overlapping_paths() {
+        let fields = fields();
+        let paths = lookups(&["a.b.c", "a.b", "message", "a.b"]);
+
+        assert_eq!(
+            extract(&fields, &paths),
+            vec![
+                None,
+                Some(&Value::from(fields_from_json(json!({ "c": 5 })))),
+                Some(&Value::from("hello")),
+                Some(&Value::from(fields_from_json(json!({ "c": 5 })))),
+            ]
+        );
+    }
+
+    #[test]
+    fn extract_remove_leaves_remainder() {
+        let mut fields = fields();
+        let paths = lookups(&["a.array[1]", "a.b.c", "a.b", "message", "missing"]);
+
+        assert_eq!(
+            extract_remove(&mut fields, &paths),
+            vec![
+                Some(Value::Integer(3)),
+                None,
+                Some(Value::from(fields_from_json(json!({ "c": 5 })))),
+                Some(Value::from("hello")),
+                None,
+            ]
+        );
+        assert_eq!(
+            fields,
+            fields_from_json(json!({
+                "a": {
+                    "d": 4,
+                    "array": [null, null, {
+                        "x": 1
+                    }]
+                }
+            }))
+        );
+    }
+
+    #[test]
+    fn extract_remove_repeated_path() {
+        let mut fields = fields();
+        let paths = lookups(&["a.d", "a.d"]);
+
+        assert_eq!(
+            extract_remove(&mut fields, &paths),
+            vec![Some(Value::Integer(4)), Some(Value::Integer(4))]
+        );
+        assert!(!super::super::contains(&fields, "a.d"));
+    }
+
+    #[test]
+    fn extract_subset_copies_nested_paths() {
+        let fields = fields();
+        let paths = lookups(&[
+            "message",
+            "a.array[2].x",
+            "a.b.c",
+            "a.b",
+            "a.d.e",
+            "missing.field",
+        ]);
+
+        assert_eq!(
+            extract_subset(&fields, &paths),
+            fields_from_json(json!({
+                "message": "hello",
+                "a": {
+                    "b": {
+                        "c": 5
+                    },
+                    "array": [null, null, {
+                        "x": 1
+                    }]
+                }
+            }))
+        );
+        assert!(extract_subset(&fields, &lookups(&["missing", "a.x"])).is_empty());
+    }
+
+    #[test]
+    fn extract_subset_shares_bytes() {
+        let fields = fields();
+        let subset = extract_subset(&fields, &lookups(&["message"]));
+
+        match (&fields["message"], &subset["message"]) {
+            (Value::Bytes(original), Value::Bytes(copied)) => {
+                assert_eq!(original.as_ptr(), copied.as_ptr());
+            }
+            other => panic!("Expected bytes, got {:?}", other),
+        }
+    }
+}
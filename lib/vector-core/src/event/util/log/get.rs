@@ -1,10 +1,19 @@
 use std::collections::BTreeMap;
 
-use super::{PathComponent, PathIter, Value};
+use super::{path_iter::parse_path, CompiledLookup, PathComponent, PathIter, Value};
+use crate::event::error::PathError;
 
 /// Returns a reference to a field value specified by the given path.
 pub fn get<'a>(fields: &'a BTreeMap<String, Value>, path: &str) -> Option<&'a Value> {
-    let mut path_iter = PathIter::new(path);
+    get_path(fields, PathIter::new(path))
+}
+
+/// Returns a reference to a field value specified by the given path components.
+pub fn get_path<'a, 'b>(
+    fields: &'a BTreeMap<String, Value>,
+    path: impl IntoIterator<Item = PathComponent<'b>>,
+) -> Option<&'a Value> {
+    let mut path_iter = path.into_iter();
 
     match path_iter.next() {
         Some(PathComponent::Key(key)) => match fields.get(key.as_ref()) {
@@ -15,6 +24,20 @@ pub fn get<'a>(fields: &'a BTreeMap<String, Value>, path: &str) -> Option<&'a Va
     }
 }
 
+/// Returns a reference to a field value specified by the given compiled path,
+/// without copying its components.
+pub fn get_compiled<'a>(
+    fields: &'a BTreeMap<String, Value>,
+    path: &CompiledLookup,
+) -> Option<&'a Value> {
+    let mut components = path.components().iter().map(PathComponent::as_borrowed);
+
+    match components.next() {
+        Some(PathComponent::Key(key)) => get_value(fields.get(key.as_ref())?, components),
+        _ => None,
+    }
+}
+
 /// Returns a reference to a field value specified by a path iter.
 pub fn get_value<'a, I>(mut value: &Value, mut path_iter: I) -> Option<&Value>
 where
@@ -40,6 +63,70 @@ where
     }
 }
 
+/// Returns a reference to a field value specified by the given path, or why
+/// there is none.
+///
+/// # Errors
+///
+/// Fails if the path is invalid, or if following it runs into a missing field,
+/// an index out of range, or a value that can't be descended into.
+pub fn try_get<'a>(
+    fields: &'a BTreeMap<String, Value>,
+    path: &str,
+) -> Result<&'a Value, PathError> {
+    let mut components = parse_path(path)?.into_iter().enumerate();
+    let rendered = || path.to_owned();
+
+    let mut value = match components.next() {
+        Some((segment, PathComponent::Key(key))) => {
+            fields
+                .get(key.as_ref())
+                .ok_or_else(|| PathError::MissingField {
+                    path: rendered(),
+                    segment,
+                })?
+        }
+        _ => {
+            return Err(PathError::NotAnArray {
+                path: rendered(),
+                segment: 0,
+            })
+        }
+    };
+
+    for (segment, component) in components {
+        value = match (component, value) {
+            (PathComponent::Key(key), Value::Map(map)) => {
+                map.get(key.as_ref())
+                    .ok_or_else(|| PathError::MissingField {
+                        path: rendered(),
+                        segment,
+                    })?
+            }
+            (PathComponent::Index(index), Value::Array(array)) => {
+                array.get(index).ok_or_else(|| PathError::IndexOutOfRange {
+                    path: rendered(),
+                    segment,
+                    len: array.len(),
+                })?
+            }
+            (PathComponent::Index(_), _) => {
+                return Err(PathError::NotAnArray {
+                    path: rendered(),
+                    segment,
+                })
+            }
+            (_, _) => {
+                return Err(PathError::NotAMap {
+                    path: rendered(),
+                    segment,
+                })
+            }
+        };
+    }
+    Ok(value)
+}
+
 #[cfg(test)]
 mod test {
     use serde_json::json;
@@ -82,6 +169,59 @@ mod test {
 
         for (query, expected) in &queries {
             assert_eq!(get(&fields, query), expected.as_ref(), "{}", query);
+            if let Ok(path) = CompiledLookup::new(query) {
+                assert_eq!(get_compiled(&fields, &path), expected.as_ref(), "{}", query);
+            }
         }
     }
+
+    #[test]
+    fn try_get_errors() {
+        let fields = fields_from_json(json!({
+            "a": {
+                "b": 5,
+                "array": [null, 3]
+            }
+        }));
+        let error = |query: &str| try_get(&fields, query).unwrap_err();
+
+        assert_eq!(try_get(&fields, "a.array[1]"), Ok(&Value::Integer(3)));
+        assert_eq!(
+            error("a..b"),
+            PathError::InvalidPath {
+                path: "a..b".into(),
+                segment: 1
+            }
+        );
+        assert_eq!(
+            error("a.c.d"),
+            PathError::MissingField {
+                path: "a.c.d".into(),
+                segment: 1
+            }
+        );
+        assert_eq!(
+            error("a.array[2]"),
+            PathError::IndexOutOfRange {
+                path: "a.array[2]".into(),
+                segment: 2,
+                len: 2
+            }
+        );
+        assert_eq!(
+            error("a.b.c"),
+            PathError::NotAMap {
+                path: "a.b.c".into(),
+                segment: 2
+            }
+        );
+        assert_eq!(
+            error("a.b[0]"),
+            PathError::NotAnArray {
+                path: "a.b[0]".into(),
+                segment: 2
+            }
+        );
+        assert_eq!(error("a.array.x").segment(), 2);
+    }
 }
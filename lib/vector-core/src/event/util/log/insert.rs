@@ -1,6 +1,7 @@
 use std::{collections::BTreeMap, iter::Peekable};
 
-use super::{PathComponent, PathIter, Value};
+use super::{path_iter::parse_path, PathComponent, PathIter, Value};
+use crate::event::error::PathError;
 
 /// Inserts field value using a path specified using `a.b[1].c` notation.
 pub fn insert(fields: &mut BTreeMap<String, Value>, path: &str, value: Value) -> Option<Value> {
@@ -15,6 +16,70 @@ pub fn insert_path(
     map_insert(fields, path.into_iter().peekable(), value)
 }
 
+/// Inserts field value using a path specified using `a.b[1].c` notation,
+/// creating missing maps and arrays along the way.
+///
+/// Unlike [`insert`], this never replaces an existing value that the path
+/// has to descend through, other than `null`, and leaves the fields untouched
+/// when it fails.
+///
+/// # Errors
+///
+/// Fails if the path is invalid, or if a value along the path is neither
+/// `null` nor of the kind the next segment of the path requires.
+pub fn try_insert(
+    fields: &mut BTreeMap<String, Value>,
+    path: &str,
+    value: Value,
+) -> Result<Option<Value>, PathError> {
+    try_insert_path(fields, path, parse_path(path)?, value)
+}
+
+/// Inserts field value using the given path components, as [`try_insert`]
+/// does. `path` is the path the components were made from, for the errors.
+///
+/// # Errors
+///
+/// Fails if a value along the path is neither `null` nor of the kind the next
+/// segment of the path requires.
+pub fn try_insert_path(
+    fields: &mut BTreeMap<String, Value>,
+    path: &str,
+    components: Vec<PathComponent>,
+    value: Value,
+) -> Result<Option<Value>, PathError> {
+    let mut current = match components.first() {
+        Some(PathComponent::Key(key)) => fields.get(key.as_ref()),
+        _ => {
+            return Err(PathError::NotAnArray {
+                path: path.to_owned(),
+                segment: 0,
+            })
+        }
+    };
+    for (segment, component) in components.iter().enumerate().skip(1) {
+        current = match (component, current) {
+            (_, None | Some(Value::Null)) => break,
+            (PathComponent::Key(key), Some(Value::Map(map))) => map.get(key.as_ref()),
+            (PathComponent::Index(index), Some(Value::Array(array))) => array.get(*index),
+            (PathComponent::Index(_), Some(_)) => {
+                return Err(PathError::NotAnArray {
+                    path: path.to_owned(),
+                    segment,
+                })
+            }
+            (_, Some(_)) => {
+                return Err(PathError::NotAMap {
+                    path: path.to_owned(),
+                    segment,
+                })
+            }
+        };
+    }
+
+    Ok(insert_path(fields, components, value))
+}
+
 fn map_insert<'a, I>(
     fields: &mut BTreeMap<String, Value>,
     mut path_iter: Peekable<I>,
@@ -128,4 +193,61 @@ mod test {
         }));
         assert_eq!(fields, expected);
     }
+
+    #[test]
+    fn try_insert_creates_missing_containers() {
+        let mut fields = fields_from_json(json!({
+            "a": {
+                "b": null
+            }
+        }));
+        assert!(try_insert(&mut fields, "a.b.c[1]", Value::Integer(1)).is_ok());
+        assert_eq!(
+            try_insert(&mut fields, "a.b.c[1]", Value::Integer(2)),
+            Ok(Some(Value::Integer(1)))
+        );
+
+        let expected = fields_from_json(json!({
+            "a": {
+                "b": {
+                    "c": [null, 2]
+                }
+            }
+        }));
+        assert_eq!(fields, expected);
+    }
+
+    #[test]
+    fn try_insert_errors_leave_fields_untouched() {
+        let original = fields_from_json(json!({
+            "a": {
+                "b": 5,
+                "array": [1]
+            }
+        }));
+        let mut fields = original.clone();
+
+        assert_eq!(
+            try_insert(&mut fields, "x.y..z", Value::Integer(1)),
+            Err(PathError::InvalidPath {
+                path: "x.y..z".into(),
+                segment: 2
+            })
+        );
+        assert_eq!(
+            try_insert(&mut fields, "a.b.c", Value::Integer(1)),
+            Err(PathError::NotAMap {
+                path: "a.b.c".into(),
+                segment: 2
+            })
+        );
+        assert_eq!(
+            try_insert(&mut fields, "a.array[0][1]", Value::Integer(1)),
+            Err(PathError::NotAnArray {
+                path: "a.array[0][1]".into(),
+                segment: 3
+            })
+        );
+        assert_eq!(fields, original);
+    }
 }
@@ -1,6 +1,6 @@
 use std::collections::BTreeMap;
 
-use super::{all_fields, Value};
+use super::{all_fields, get, Value};
 
 /// Iterates over all paths in form `a.b[0].c[1]` in alphabetical order.
 /// It is implemented as a wrapper around `all_fields` to reduce code
@@ -9,6 +9,48 @@ pub fn keys(fields: &BTreeMap<String, Value>) -> impl Iterator<Item = String> +
     all_fields(fields).map(|(k, _)| k)
 }
 
+/// Iterates over the paths below the map at `prefix`, in the same order as
+/// [`keys`], without visiting the rest of the fields. The paths are relative to
+/// `prefix`, or include it if `absolute` is set.
+///
+/// If `prefix` holds a scalar, or an empty map or array, which [`keys`] yields
+/// as leaves, its own path is the only one yielded, which is empty when
+/// relative. Nothing is yielded if `prefix` holds a non-empty array or doesn't
+/// exist.
+pub fn keys_under<'a>(
+    fields: &'a BTreeMap<String, Value>,
+    prefix: &str,
+    absolute: bool,
+) -> impl Iterator<Item = String> + 'a {
+    let (map, leaf) = match get(fields, prefix) {
+        Some(Value::Map(map)) if !map.is_empty() => (Some(map), None),
+        Some(Value::Array(array)) if !array.is_empty() => (None, None),
+        None => (None, None),
+        Some(_) if absolute => (None, Some(prefix.to_owned())),
+        Some(_) => (None, Some(String::new())),
+    };
+    let prefix = absolute.then(|| prefix.to_owned());
+
+    map.into_iter()
+        .flat_map(all_fields)
+        .map(move |(key, _)| match &prefix {
+            Some(prefix) => format!("{}.{}", prefix, key),
+            None => key,
+        })
+        .chain(leaf)
+}
+
+/// Whether [`keys_under`] would yield anything for `prefix`, answered without
+/// iterating.
+pub fn has_any_under(fields: &BTreeMap<String, Value>, prefix: &str) -> bool {
+    match get(fields, prefix) {
+        Some(Value::Array(array)) => array.is_empty(),
+        None => false,
+        // Empty nested collections are leaves of their own, so any map has one.
+        Some(_) => true,
+    }
+}
+
 #[cfg(test)]
 mod test {
     use serde_json::json;
@@ -59,4 +101,101 @@ mod test {
         let collected: Vec<_> = keys(&fields).collect();
         assert_eq!(collected, expected);
     }
+
+    // Shaped like a log from the kubernetes_logs source.
+    fn kubernetes_fields() -> BTreeMap<String, Value> {
+        fields_from_json(json!({
+            "message": "hello",
+            "kubernetes": {
+                "container_name": "app",
+                "pod_labels": {
+                    "app.kubernetes.io/name": "app",
+                    "tier": "web"
+                },
+                "pod_ips": ["10.0.0.1", "10.0.0.2"],
+                "pod_annotations": {},
+                "pod_owner": { "kind": "ReplicaSet", "names": [["a", "b"]] },
+                "pod_volumes": []
+            },
+            "kubernetes.pod_name": "escaped",
+            "stream": "stdout"
+        }))
+    }
+
+    #[test]
+    fn keys_under_map() {
+        let fields = kubernetes_fields();
+        let relative = vec![
+            "container_name",
+            "pod_annotations",
+            "pod_ips[0]",
+            "pod_ips[1]",
+            "pod_labels.app\\.kubernetes\\.io/name",
+            "pod_labels.tier",
+            "pod_owner.kind",
+            "pod_owner.names[0][0]",
+            "pod_owner.names[0][1]",
+            "pod_volumes",
+        ];
+
+        let collected: Vec<_> = keys_under(&fields, "kubernetes", false).collect();
+        assert_eq!(collected, relative);
+
+        let collected: Vec<_> = keys_under(&fields, "kubernetes", true).collect();
+        let absolute: Vec<_> = relative
+            .iter()
+            .map(|key| format!("kubernetes.{}", key))
+            .collect();
+        assert_eq!(collected, absolute);
+        assert!(collected
+            .iter()
+            .all(|key| keys(&fields).any(|all| &all == key)));
+
+        let collected: Vec<_> = keys_under(&fields, "kubernetes.pod_owner", true).collect();
+        assert_eq!(
+            collected,
+            vec![
+                "kubernetes.pod_owner.kind",
+                "kubernetes.pod_owner.names[0][0]",
+                "kubernetes.pod_owner.names[0][1]",
+            ]
+        );
+        assert!(has_any_under(&fields, "kubernetes"));
+        assert!(has_any_under(&fields, "kubernetes.pod_owner"));
+    }
+
+    #[test]
+    fn keys_under_scalar_array_and_missing() {
+        let fields = kubernetes_fields();
+
+        let collected: Vec<_> = keys_under(&fields, "kubernetes.container_name", true).collect();
+        assert_eq!(collected, vec!["kubernetes.container_name"]);
+        let collected: Vec<_> = keys_under(&fields, "kubernetes.container_name", false).collect();
+        assert_eq!(collected, vec![""]);
+        assert!(has_any_under(&fields, "kubernetes.container_name"));
+
+        // Empty maps and arrays are leaves, as in `keys`.
+        for prefix in ["kubernetes.pod_annotations", "kubernetes.pod_volumes"] {
+            let collected: Vec<_> = keys_under(&fields, prefix, true).collect();
+            assert_eq!(collected, vec![prefix]);
+            let collected: Vec<_> = keys_under(&fields, prefix, false).collect();
+            assert_eq!(collected, vec![""]);
+            assert!(has_any_under(&fields, prefix), "{}", prefix);
+            assert!(keys(&fields).any(|key| key == prefix), "{}", prefix);
+        }
+
+        for prefix in [
+            "kubernetes.pod_ips",
+            "kubernetes.missing",
+            "kubernetes.container_name.nested",
+            "",
+        ] {
+            assert_eq!(keys_under(&fields, prefix, true).count(), 0, "{}", prefix);
+            assert!(!has_any_under(&fields, prefix), "{}", prefix);
+        }
+
+        // An array element is a valid prefix too.
+        let collected: Vec<_> = keys_under(&fields, "kubernetes.pod_ips[1]", true).collect();
+        assert_eq!(collected, vec!["kubernetes.pod_ips[1]"]);
+    }
 }
@@ -1,5 +1,7 @@
 mod all_fields;
+mod compiled_lookup;
 mod contains;
+mod extract;
 mod get;
 mod get_mut;
 mod insert;
@@ -7,14 +9,16 @@ mod keys;
 mod path_iter;
 mod remove;
 
-pub use all_fields::all_fields;
-pub use contains::contains;
-pub use get::{get, get_value};
+pub use all_fields::{all_fields, paths, paths_with_values};
+pub use compiled_lookup::CompiledLookup;
+pub use contains::{contains, contains_path};
+pub use extract::{extract, extract_remove, extract_subset};
+pub use get::{get, get_compiled, get_path, get_value, try_get};
 pub use get_mut::get_mut;
-pub use insert::{insert, insert_path};
-pub use keys::keys;
-pub use path_iter::{PathComponent, PathIter};
-pub use remove::remove;
+pub use insert::{insert, insert_path, try_insert, try_insert_path};
+pub use keys::{has_any_under, keys, keys_under};
+pub use path_iter::{lookup_components, PathComponent, PathIter};
+pub use remove::{remove, remove_compacted, remove_path, try_remove};
 
 pub(self) use super::Value;
 
@@ -1,8 +1,11 @@
-use std::{borrow::Cow, mem, str::Chars};
+use std::{borrow::Cow, convert::TryFrom, mem, str::Chars};
 
+use lookup::{LookupBuf, SegmentBuf};
 use serde::{Deserialize, Serialize};
 use substring::Substring;
 
+use crate::event::error::PathError;
+
 #[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
 pub enum PathComponent<'a> {
     /// For example, in `a.b[0].c[2]` the keys are "a", "b", and "c".
@@ -21,6 +24,15 @@ impl<'a> PathComponent<'a> {
             PathComponent::Invalid => PathComponent::Invalid,
         }
     }
+
+    /// A copy of this component that borrows its key rather than cloning it.
+    pub fn as_borrowed(&self) -> PathComponent<'_> {
+        match self {
+            PathComponent::Key(k) => PathComponent::Key(Cow::Borrowed(k.as_ref())),
+            PathComponent::Index(u) => PathComponent::Index(*u),
+            PathComponent::Invalid => PathComponent::Invalid,
+        }
+    }
 }
 
 /// Iterator over components of paths specified in form `a.b[0].c[2]`.
@@ -153,6 +165,50 @@ impl<'a> Iterator for PathIter<'a> {
     }
 }
 
+/// Split a path into its components, failing if any part of it is invalid.
+pub(super) fn parse_path(path: &str) -> Result<Vec<PathComponent<'_>>, PathError> {
+    PathIter::new(path)
+        .enumerate()
+        .map(|(segment, component)| match component {
+            PathComponent::Invalid => Err(PathError::InvalidPath {
+                path: path.to_owned(),
+                segment,
+            }),
+            component => Ok(component),
+        })
+        .collect()
+}
+
+/// The components of `lookup`, with its keys taken as they are. The root, coalesces and indices
+/// counting from the end have no components to stand for them.
+///
+/// # Errors
+///
+/// Fails with the first segment of `lookup` that has no component, or if `lookup` is the root.
+pub fn lookup_components(lookup: &LookupBuf) -> Result<Vec<PathComponent<'_>>, PathError> {
+    if lookup.is_root() {
+        return Err(PathError::InvalidPath {
+            path: String::new(),
+            segment: 0,
+        });
+    }
+    lookup
+        .iter()
+        .enumerate()
+        .map(|(segment, component)| {
+            let component = match component {
+                SegmentBuf::Field(field) => Some(PathComponent::Key(field.as_str().into())),
+                SegmentBuf::Index(index) => usize::try_from(*index).ok().map(PathComponent::Index),
+                SegmentBuf::Coalesce(_) => None,
+            };
+            component.ok_or_else(|| PathError::UnsupportedSegment {
+                path: lookup.to_string(),
+                segment,
+            })
+        })
+        .collect()
+}
+
 #[inline]
 fn char_to_state(c: Option<char>) -> State {
     match c {
@@ -236,4 +292,29 @@ mod test {
             assert_eq!(PathIter::new(i).last(), Some(PathComponent::Invalid));
         }
     }
+
+    #[test]
+    fn parse_path_reports_invalid_segment() {
+        assert_eq!(
+            parse_path("a.b[0]"),
+            Ok(vec![
+                PathComponent::Key("a".into()),
+                PathComponent::Key("b".into()),
+                PathComponent::Index(0),
+            ])
+        );
+
+        let inputs = [("", 0), ("fly[asdf]", 1), ("a.b..c", 2), ("a[0]]", 2)];
+        for (path, segment) in inputs {
+            assert_eq!(
+                parse_path(path),
+                Err(PathError::InvalidPath {
+                    path: path.to_owned(),
+                    segment
+                }),
+                "{}",
+                path
+            );
+        }
+    }
 }
@@ -1,59 +1,159 @@
 use std::{cmp::Ordering, collections::BTreeMap, iter::Peekable, mem};
 
-use super::{PathComponent, PathIter, Value};
+use super::{try_get, PathComponent, PathIter, Value};
+use crate::event::error::PathError;
 
 /// Removes field value specified by the given path and return its value.
 ///
 /// A special case worth mentioning: if there is a nested array and an item is removed
-/// from the middle of this array, then it is just replaced by `Value::Null`.
+/// from the middle of this array, then it is just replaced by `Value::Null`, unless
+/// `prune` is set. With `prune` the removal is compacted as [`remove_compacted`]
+/// describes.
 pub fn remove(fields: &mut BTreeMap<String, Value>, path: &str, prune: bool) -> Option<Value> {
-    remove_map(fields, PathIter::new(path).peekable(), prune).map(|(value, _)| value)
+    if prune {
+        let path = PathIter::new(path).collect::<Vec<_>>();
+        return remove_compacted_map(fields, &path);
+    }
+    remove_map(fields, PathIter::new(path).peekable())
+}
+
+/// Removes field value specified by the given path components and return its value, as
+/// [`remove`] does.
+pub fn remove_path(
+    fields: &mut BTreeMap<String, Value>,
+    path: &[PathComponent],
+    prune: bool,
+) -> Option<Value> {
+    if prune {
+        return remove_compacted_map(fields, path);
+    }
+    remove_map(
+        fields,
+        path.iter().map(PathComponent::as_borrowed).peekable(),
+    )
+}
+
+/// Removes field value specified by the given path and return its value, or
+/// why there is none. Removal works as in [`remove`].
+///
+/// # Errors
+///
+/// Fails for the same reasons as [`try_get`], in which case nothing is removed.
+pub fn try_remove(
+    fields: &mut BTreeMap<String, Value>,
+    path: &str,
+    prune: bool,
+) -> Result<Value, PathError> {
+    // Finding the value first keeps the removal itself infallible.
+    try_get(fields, path)?;
+    remove(fields, path, prune).ok_or_else(|| PathError::MissingField {
+        path: path.to_owned(),
+        segment: PathIter::new(path).count() - 1,
+    })
+}
+
+/// Removes the value at `path` from `value` and compacts what held it, returning the removed
+/// value. This is the one meaning of pruning, or compacting, a removal, for both
+/// `LogEvent::remove_prune` and `Value::remove`, which the `del` of VRL uses:
+///
+/// * A value removed from an array is taken out of it, moving the elements after it down,
+///   rather than replaced by `null`.
+/// * Then each map and array along `path` that the removal left empty is removed from what
+///   holds it in turn, from the innermost out. The fields of an event are never removed
+///   themselves, but the fields at their top level are.
+/// * Nothing else is compacted: empty maps and arrays off `path` and `null`s are left as they
+///   are, and nothing at all is when there was nothing to remove.
+///
+/// `path` is made of keys and indices that are already resolved: negative indices and
+/// coalesces are the caller's to resolve against `value`.
+pub fn remove_compacted(value: &mut Value, path: &[PathComponent]) -> Option<Value> {
+    match value {
+        Value::Map(map) => remove_compacted_map(map, path),
+        Value::Array(array) => {
+            let (index, rest) = match path.split_first()? {
+                (PathComponent::Index(index), rest) if *index < array.len() => (*index, rest),
+                _ => return None,
+            };
+            if rest.is_empty() {
+                return Some(array.remove(index));
+            }
+            let removed = remove_compacted(&mut array[index], rest)?;
+            if is_empty_container(&array[index]) {
+                array.remove(index);
+            }
+            Some(removed)
+        }
+        _ => None,
+    }
+}
+
+fn remove_compacted_map(
+    fields: &mut BTreeMap<String, Value>,
+    path: &[PathComponent],
+) -> Option<Value> {
+    let (key, rest) = match path.split_first()? {
+        (PathComponent::Key(key), rest) => (key.as_ref(), rest),
+        _ => return None,
+    };
+    if rest.is_empty() {
+        return fields.remove(key);
+    }
+    let inner = fields.get_mut(key)?;
+    let removed = remove_compacted(inner, rest)?;
+    if is_empty_container(inner) {
+        fields.remove(key);
+    }
+    Some(removed)
+}
+
+/// Only maps and arrays are compacted, not `null`s.
+fn is_empty_container(value: &Value) -> bool {
+    match value {
+        Value::Map(map) => map.is_empty(),
+        Value::Array(array) => array.is_empty(),
+        _ => false,
+    }
 }
 
 /// Recursively iterate through the path, and remove the last path
 /// element. This is the top-level function which can remove from any
 /// type of `Value`.
-fn remove_rec(value: &mut Value, path: Peekable<PathIter>, prune: bool) -> Option<(Value, bool)> {
+fn remove_rec<'a, I>(value: &mut Value, path: Peekable<I>) -> Option<Value>
+where
+    I: Iterator<Item = PathComponent<'a>>,
+{
     match value {
-        Value::Map(map) => remove_map(map, path, prune),
-        Value::Array(map) => remove_array(map, path, prune),
+        Value::Map(map) => remove_map(map, path),
+        Value::Array(map) => remove_array(map, path),
         _ => None,
     }
 }
 
-fn remove_array(
-    array: &mut Vec<Value>,
-    mut path: Peekable<PathIter>,
-    prune: bool,
-) -> Option<(Value, bool)> {
+fn remove_array<'a, I>(array: &mut Vec<Value>, mut path: Peekable<I>) -> Option<Value>
+where
+    I: Iterator<Item = PathComponent<'a>>,
+{
     match path.next()? {
         PathComponent::Index(index) => match path.peek() {
-            None => array_remove(array, index).map(|v| (v, array.is_empty())),
+            None => array_remove(array, index),
             Some(_) => array
                 .get_mut(index)
-                .and_then(|value| remove_rec(value, path, prune)),
+                .and_then(|value| remove_rec(value, path)),
         },
         _ => None,
     }
 }
 
-fn remove_map(
-    fields: &mut BTreeMap<String, Value>,
-    mut path: Peekable<PathIter>,
-    prune: bool,
-) -> Option<(Value, bool)> {
+fn remove_map<'a, I>(fields: &mut BTreeMap<String, Value>, mut path: Peekable<I>) -> Option<Value>
+where
+    I: Iterator<Item = PathComponent<'a>>,
+{
     match path.next()? {
         PathComponent::Key(key) => match path.peek() {
-            None => fields.remove(key.as_ref()).map(|v| (v, fields.is_empty())),
-            Some(_) => {
-                let (result, empty) = fields
-                    .get_mut(key.as_ref())
-                    .and_then(|value| remove_rec(value, path, prune))?;
-                if prune && empty {
-                    fields.remove(key.as_ref());
-                }
-                Some((result, fields.is_empty()))
-            }
+            None => fields.remove(key.as_ref()),
+            Some(_) => fields
+                .get_mut(key.as_ref())
+                .and_then(|value| remove_rec(value, path)),
         },
         _ => None,
     }
@@ -154,6 +254,52 @@ mod test {
         );
     }
 
+    #[test]
+    fn try_remove_errors() {
+        let mut fields = fields_from_json(json!({
+            "a": {
+                "b": 5,
+                "array": [null, 3]
+            }
+        }));
+
+        assert_eq!(
+            try_remove(&mut fields, "a.array[1]", false),
+            Ok(Value::Integer(3))
+        );
+        assert_eq!(
+            try_remove(&mut fields, "a.array[1]", false),
+            Err(PathError::IndexOutOfRange {
+                path: "a.array[1]".into(),
+                segment: 2,
+                len: 1
+            })
+        );
+        assert_eq!(
+            try_remove(&mut fields, "a.b.c", false),
+            Err(PathError::NotAMap {
+                path: "a.b.c".into(),
+                segment: 2
+            })
+        );
+        assert_eq!(
+            try_remove(&mut fields, "a.", false),
+            Err(PathError::InvalidPath {
+                path: "a.".into(),
+                segment: 1
+            })
+        );
+        assert_eq!(try_remove(&mut fields, "a.b", true), Ok(Value::Integer(5)));
+        assert_eq!(
+            fields,
+            fields_from_json(json!({
+                "a": {
+                    "array": [null]
+                }
+            }))
+        );
+    }
+
     #[test]
     fn remove_prune() {
         let mut fields = fields_from_json(json!({
@@ -1,7 +1,8 @@
 use std::{
+    cmp::Ordering,
     collections::BTreeMap,
     convert::{TryFrom, TryInto},
-    fmt::Debug,
+    fmt::{self, Debug},
     hash::{Hash, Hasher},
     iter::FromIterator,
 };
@@ -13,7 +14,13 @@ use serde::{Deserialize, Serialize, Serializer};
 use toml::value::Value as TomlValue;
 
 use crate::{
-    event::{error::EventError, timestamp_to_string},
+    event::{
+        canonical_json,
+        error::EventError,
+        timestamp_to_string,
+        util::log::{self as log_util, PathComponent},
+        visit,
+    },
     ByteSizeOf, Result,
 };
 
@@ -29,6 +36,41 @@ pub enum Value {
     Null,
 }
 
+/// The kind of a [`Value`], without its contents.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum ValueKind {
+    Bytes,
+    Integer,
+    Float,
+    Boolean,
+    Timestamp,
+    Map,
+    Array,
+    Null,
+}
+
+impl ValueKind {
+    /// The name of the kind, as returned by [`Value::kind`].
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            ValueKind::Bytes => "string",
+            ValueKind::Integer => "integer",
+            ValueKind::Float => "float",
+            ValueKind::Boolean => "boolean",
+            ValueKind::Timestamp => "timestamp",
+            ValueKind::Map => "map",
+            ValueKind::Array => "array",
+            ValueKind::Null => "null",
+        }
+    }
+}
+
+impl fmt::Display for ValueKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
 impl Eq for Value {}
 
 impl PartialEq<Value> for Value {
@@ -121,17 +163,7 @@ impl Serialize for Value {
     where
         S: Serializer,
     {
-        match &self {
-            Value::Integer(i) => serializer.serialize_i64(*i),
-            Value::Float(f) => serializer.serialize_f64(*f),
-            Value::Boolean(b) => serializer.serialize_bool(*b),
-            Value::Bytes(_) | Value::Timestamp(_) => {
-                serializer.serialize_str(&self.to_string_lossy())
-            }
-            Value::Map(m) => serializer.collect_map(m),
-            Value::Array(a) => serializer.collect_seq(a),
-            Value::Null => serializer.serialize_none(),
-        }
+        visit::serialize_value(self, serializer)
     }
 }
 
@@ -336,6 +368,64 @@ impl From<Value> for vrl_core::Value {
     }
 }
 
+/// Builds the VRL view of a value without cloning it first. Bytes are shared with the original, as
+/// cloning them only bumps a reference count.
+#[cfg(feature = "vrl")]
+impl From<&Value> for vrl_core::Value {
+    fn from(v: &Value) -> Self {
+        use vrl_core::Value::{Array, Object};
+
+        match v {
+            Value::Bytes(v) => v.clone().into(),
+            Value::Integer(v) => (*v).into(),
+            Value::Float(v) => (*v).into(),
+            Value::Boolean(v) => (*v).into(),
+            Value::Map(v) => Object(v.iter().map(|(k, v)| (k.clone(), v.into())).collect()),
+            Value::Array(v) => Array(v.iter().map(Into::into).collect()),
+            Value::Timestamp(v) => (*v).into(),
+            Value::Null => ().into(),
+        }
+    }
+}
+
+/// Orders NaN after every other float.
+fn cmp_floats(a: f64, b: f64) -> Ordering {
+    a.partial_cmp(&b)
+        .unwrap_or_else(|| a.is_nan().cmp(&b.is_nan()))
+}
+
+/// Compare an integer with a float by their exact numeric values, which converting either to the
+/// other's type can't do for all values. NaN goes after every integer.
+#[allow(clippy::cast_possible_truncation)]
+fn cmp_integer_float(integer: i64, float: f64) -> Ordering {
+    // `i64::MIN` is -2^63, which is exact as a float, while `i64::MAX` rounds up to 2^63.
+    const LIMIT: f64 = 9_223_372_036_854_775_808.0;
+    if float.is_nan() || float >= LIMIT {
+        Ordering::Less
+    } else if float < -LIMIT {
+        Ordering::Greater
+    } else {
+        let whole = float.trunc();
+        // In range, so the conversion is exact.
+        integer
+            .cmp(&(whole as i64))
+            .then_with(|| cmp_floats(whole, float))
+    }
+}
+
+/// The position of the kind of `value` in the order of [`Value::total_cmp`].
+const fn kind_rank(value: &Value) -> u8 {
+    match value {
+        Value::Boolean(_) => 0,
+        Value::Integer(_) | Value::Float(_) => 1,
+        Value::Bytes(_) => 2,
+        Value::Timestamp(_) => 3,
+        Value::Array(_) => 4,
+        Value::Map(_) => 5,
+        Value::Null => 6,
+    }
+}
+
 impl Value {
     // TODO: return Cow
     pub fn to_string_lossy(&self) -> String {
@@ -351,6 +441,16 @@ impl Value {
         }
     }
 
+    /// Serialize the value to the canonical JSON form, as [`LogEvent::to_canonical_json`] does
+    /// for the fields of an event. Equal values always serialize to the same bytes.
+    ///
+    /// [`LogEvent::to_canonical_json`]: crate::event::LogEvent::to_canonical_json
+    pub fn to_canonical_json(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        canonical_json::write_value(self, &mut out);
+        out
+    }
+
     pub fn as_bytes(&self) -> Bytes {
         match self {
             Value::Bytes(bytes) => bytes.clone(), // cloning a Bytes is cheap
@@ -428,15 +528,77 @@ impl Value {
     }
 
     pub fn kind(&self) -> &str {
+        self.value_kind().as_str()
+    }
+
+    pub const fn value_kind(&self) -> ValueKind {
         match self {
-            Value::Bytes(_) => "string",
-            Value::Timestamp(_) => "timestamp",
-            Value::Integer(_) => "integer",
-            Value::Float(_) => "float",
-            Value::Boolean(_) => "boolean",
-            Value::Map(_) => "map",
-            Value::Array(_) => "array",
-            Value::Null => "null",
+            Value::Bytes(_) => ValueKind::Bytes,
+            Value::Timestamp(_) => ValueKind::Timestamp,
+            Value::Integer(_) => ValueKind::Integer,
+            Value::Float(_) => ValueKind::Float,
+            Value::Boolean(_) => ValueKind::Boolean,
+            Value::Map(_) => ValueKind::Map,
+            Value::Array(_) => ValueKind::Array,
+            Value::Null => ValueKind::Null,
+        }
+    }
+
+    /// The number of elements of the array, or entries of the map, at `lookup`, as
+    /// [`get`](Self::get) finds it. `None` if there is no value at `lookup` or it is neither an
+    /// array nor a map. The root lookup gives the length of the value itself.
+    pub fn len_at<'a>(&self, lookup: impl Into<Lookup<'a>> + Debug) -> Option<usize> {
+        match self.get(lookup).ok().flatten()? {
+            Value::Map(map) => Some(map.len()),
+            Value::Array(array) => Some(array.len()),
+            _ => None,
+        }
+    }
+
+    /// The kind of the value at `lookup`, if there is one.
+    pub fn kind_at<'a>(&self, lookup: impl Into<Lookup<'a>> + Debug) -> Option<ValueKind> {
+        self.get(lookup).ok().flatten().map(Value::value_kind)
+    }
+
+    /// Whether the value at `lookup` is empty, as defined by [`is_empty`](Self::is_empty), if
+    /// there is one.
+    pub fn is_empty_at<'a>(&self, lookup: impl Into<Lookup<'a>> + Debug) -> Option<bool> {
+        self.get(lookup).ok().flatten().map(Value::is_empty)
+    }
+
+    /// Compare two values of any kinds, for sorting. Unlike the derived `PartialOrd`, every pair
+    /// of values has an order:
+    ///
+    /// * Values of different kinds are ordered booleans, numbers, bytes, timestamps, arrays, maps
+    ///   and then `null`.
+    /// * Integers and floats are compared by their numeric value, an integer going first when
+    ///   they are equal. NaN goes after every other number.
+    /// * Arrays are compared element by element, and maps entry by entry in key order, a shorter
+    ///   one going first when it is a prefix of the other.
+    pub fn total_cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (Value::Boolean(a), Value::Boolean(b)) => a.cmp(b),
+            (Value::Integer(a), Value::Integer(b)) => a.cmp(b),
+            (Value::Float(a), Value::Float(b)) => cmp_floats(*a, *b),
+            (Value::Integer(a), Value::Float(b)) => cmp_integer_float(*a, *b).then(Ordering::Less),
+            (Value::Float(a), Value::Integer(b)) => {
+                cmp_integer_float(*b, *a).reverse().then(Ordering::Greater)
+            }
+            (Value::Bytes(a), Value::Bytes(b)) => a.cmp(b),
+            (Value::Timestamp(a), Value::Timestamp(b)) => a.cmp(b),
+            (Value::Array(a), Value::Array(b)) => a
+                .iter()
+                .zip(b)
+                .map(|(a, b)| a.total_cmp(b))
+                .find(|ordering| ordering.is_ne())
+                .unwrap_or_else(|| a.len().cmp(&b.len())),
+            (Value::Map(a), Value::Map(b)) => a
+                .iter()
+                .zip(b)
+                .map(|((a_key, a), (b_key, b))| a_key.cmp(b_key).then_with(|| a.total_cmp(b)))
+                .find(|ordering| ordering.is_ne())
+                .unwrap_or_else(|| a.len().cmp(&b.len())),
+            (a, b) => kind_rank(a).cmp(&kind_rank(b)),
         }
     }
 
@@ -532,6 +694,19 @@ impl Value {
         }
     }
 
+    /// Like `correct_type`, for a borrowed segment.
+    fn correct_segment_type(value: &mut Value, segment: &Segment<'_>) {
+        match segment {
+            Segment::Index(next_len) if !matches!(value, Value::Array(_)) => {
+                *value = Value::Array(Vec::with_capacity(next_len.abs() as usize));
+            }
+            Segment::Field(_) | Segment::Coalesce(_) if !matches!(value, Value::Map(_)) => {
+                *value = Value::Map(Default::default());
+            }
+            _ => (),
+        }
+    }
+
     fn insert_map(
         name: &str,
         requires_quoting: bool,
@@ -791,9 +966,87 @@ impl Value {
         }
     }
 
+    /// Insert a value at a given lookup, like `insert`, but borrowing the lookup.
+    ///
+    /// The segments of the lookup aren't copied while walking maps, so a field name is only
+    /// allocated when the entry it names is created. Arrays and coalesces are descended into by
+    /// `insert`, with the rest of the lookup.
+    ///
+    /// ```rust
+    /// use vector_core::event::Value;
+    /// use lookup::LookupBuf;
+    /// use std::collections::BTreeMap;
+    ///
+    /// let path = LookupBuf::from_str("foo.bar").unwrap();
+    /// let mut map = Value::from(BTreeMap::default());
+    ///
+    /// assert_eq!(map.insert_borrowed(&path, 1).unwrap(), None);
+    /// assert_eq!(map.insert_borrowed(&path, 2).unwrap(), Some(Value::from(1)));
+    /// assert_eq!(map.get(&path).unwrap(), Some(&Value::from(2)));
+    /// ```
+    #[allow(clippy::missing_errors_doc)]
+    pub fn insert_borrowed<'a>(
+        &mut self,
+        lookup: impl Into<Lookup<'a>> + Debug,
+        value: impl Into<Value> + Debug,
+    ) -> std::result::Result<Option<Value>, EventError> {
+        let mut working_lookup: Lookup<'a> = lookup.into();
+        let value = value.into();
+        let span = trace_span!("insert_borrowed", lookup = %working_lookup);
+        let _guard = span.enter();
+
+        match (working_lookup.pop_front(), self) {
+            (None, item) => Ok(Some(std::mem::replace(item, value))),
+            (Some(Segment::Field(field)), Value::Map(map)) => {
+                let next_segment = match working_lookup.iter().next() {
+                    Some(segment) => segment,
+                    None => {
+                        return Ok(match map.get_mut(field.name) {
+                            Some(entry) => Some(std::mem::replace(entry, value)),
+                            None => map.insert(field.name.to_owned(), value),
+                        });
+                    }
+                };
+
+                let result = match map.get_mut(field.name) {
+                    Some(entry) => {
+                        Value::correct_segment_type(entry, next_segment);
+                        entry.insert_borrowed(working_lookup, value)
+                    }
+                    None => {
+                        let mut entry = Value::Null;
+                        Value::correct_segment_type(&mut entry, next_segment);
+                        let result = entry.insert_borrowed(working_lookup, value);
+                        map.insert(field.name.to_owned(), entry);
+                        result
+                    }
+                };
+                result.map_err(|mut e| {
+                    if let EventError::PrimitiveDescent {
+                        original_target,
+                        primitive_at,
+                        original_value: _,
+                    } = &mut e
+                    {
+                        let segment = SegmentBuf::Field(field.as_field_buf());
+                        original_target.push_front(segment.clone());
+                        primitive_at.push_front(segment);
+                    };
+                    e
+                })
+            }
+            (Some(segment), item) => {
+                working_lookup.push_front(segment);
+                item.insert(working_lookup.into_buf(), value)
+            }
+        }
+    }
+
     /// Remove a value that exists at a given lookup.
     ///
-    /// Setting `prune` to true will also remove the entries of maps and arrays that are emptied.
+    /// Setting `prune` to true compacts the removal, as `util::log::remove_compacted` describes:
+    /// a value removed from an array is taken out of it, and the maps and arrays along the lookup
+    /// that it leaves empty are removed as well.
     ///
     /// ```rust
     /// use vector_core::event::Value;
@@ -813,17 +1066,62 @@ impl Value {
     /// assert_eq!(map.remove(lookup_key, true).unwrap(), Some(Value::from(1)));
     /// assert!(!map.contains("star"));
     /// ```
-    #[allow(clippy::too_many_lines)]
     #[allow(clippy::missing_errors_doc)]
     pub fn remove<'a>(
         &mut self,
         lookup: impl Into<Lookup<'a>> + Debug,
         prune: bool,
     ) -> std::result::Result<Option<Value>, EventError> {
-        let mut working_lookup = lookup.into();
-        let span = trace_span!("remove", lookup = %working_lookup, %prune);
+        let lookup = lookup.into();
+        let span = trace_span!("remove", lookup = %lookup, %prune);
         let _guard = span.enter();
 
+        if prune {
+            // Compacting is shared with the removals from log events, which are made by path.
+            if let Some(path) = self.resolve(lookup.clone()) {
+                return Ok(log_util::remove_compacted(self, &path));
+            }
+        }
+        self.remove_unpruned(lookup)
+    }
+
+    /// Resolve `lookup` to the keys and indices of the value it points to, if there is one.
+    fn resolve<'a>(&self, mut lookup: Lookup<'a>) -> Option<Vec<PathComponent<'a>>> {
+        let segment = match lookup.pop_front() {
+            Some(segment) => segment,
+            None => return Some(Vec::new()),
+        };
+        let (component, inner) = match (segment, self) {
+            (Segment::Field(Field { name, .. }), Value::Map(map)) => {
+                (PathComponent::Key(name.into()), map.get(name)?)
+            }
+            (Segment::Index(i), Value::Array(array)) => {
+                let index = if i.is_negative() {
+                    array.len().checked_sub(i.unsigned_abs())?
+                } else {
+                    i as usize
+                };
+                (PathComponent::Index(index), array.get(index)?)
+            }
+            (Segment::Coalesce(sub_segments), value) => {
+                return sub_segments.into_iter().find_map(|sub_segment| {
+                    let mut needle = Lookup::from(sub_segment);
+                    needle.extend(lookup.clone());
+                    value.resolve(needle)
+                });
+            }
+            _ => return None,
+        };
+        let mut path = inner.resolve(lookup)?;
+        path.insert(0, component);
+        Some(path)
+    }
+
+    #[allow(clippy::too_many_lines)]
+    fn remove_unpruned(
+        &mut self,
+        mut working_lookup: Lookup<'_>,
+    ) -> std::result::Result<Option<Value>, EventError> {
         let this_segment = working_lookup.pop_front();
 
         let retval = match (this_segment, &mut *self) {
@@ -870,7 +1168,7 @@ impl Value {
                     }
                 }
                 match needle {
-                    Some(needle) => value.remove(needle, prune),
+                    Some(needle) => value.remove_unpruned(needle),
                     None => Ok(None),
                 }
             }
@@ -879,21 +1177,10 @@ impl Value {
                 if working_lookup.is_empty() {
                     Ok(map.remove(name))
                 } else {
-                    let mut inner_is_empty = false;
-                    let retval = match map.get_mut(name) {
-                        Some(inner) => {
-                            let ret = inner.remove(working_lookup.clone(), prune);
-                            if inner.is_empty() {
-                                inner_is_empty = true;
-                            };
-                            ret
-                        }
+                    match map.get_mut(name) {
+                        Some(inner) => inner.remove_unpruned(working_lookup),
                         None => Ok(None),
-                    };
-                    if inner_is_empty && prune {
-                        map.remove(name);
                     }
-                    retval
                 }
             }
             (Some(Segment::Index(_)), Value::Map(_))
@@ -911,29 +1198,16 @@ impl Value {
                 };
 
                 if working_lookup.is_empty() {
-                    // We don't **actually** want to remove the index, we just
-                    // want to swap it with a null.
                     if array.len() > index {
                         Ok(Some(array.remove(index)))
                     } else {
                         Ok(None)
                     }
                 } else {
-                    let mut inner_is_empty = false;
-                    let retval = match array.get_mut(index) {
-                        Some(inner) => {
-                            let ret = inner.remove(working_lookup.clone(), prune);
-                            if inner.is_empty() {
-                                inner_is_empty = true;
-                            }
-                            ret
-                        }
+                    match array.get_mut(index) {
+                        Some(inner) => inner.remove_unpruned(working_lookup),
                         None => Ok(None),
-                    };
-                    if inner_is_empty && prune {
-                        array.remove(index);
                     }
-                    retval
                 }
             }
         };
@@ -1380,6 +1654,64 @@ mod test {
         }
     }
 
+    mod value_total_cmp {
+        use chrono::TimeZone;
+
+        use super::*;
+
+        #[test]
+        fn orders_every_pair() {
+            let sorted = vec![
+                Value::Boolean(false),
+                Value::Boolean(true),
+                Value::Float(f64::NEG_INFINITY),
+                Value::Integer(-1),
+                Value::Integer(1),
+                Value::Float(1.0),
+                Value::Float(1.5),
+                Value::Integer(2),
+                Value::Float(f64::NAN),
+                Value::from("a"),
+                Value::from("b"),
+                Value::Timestamp(Utc.timestamp(0, 0)),
+                Value::Array(vec![Value::Integer(1)]),
+                Value::Array(vec![Value::Integer(1), Value::Integer(0)]),
+                Value::Array(vec![Value::Integer(2)]),
+                Value::from(BTreeMap::from([("a".to_owned(), Value::Integer(2))])),
+                Value::from(BTreeMap::from([("b".to_owned(), Value::Integer(1))])),
+                Value::Null,
+            ];
+            for (i, a) in sorted.iter().enumerate() {
+                for (j, b) in sorted.iter().enumerate() {
+                    assert_eq!(a.total_cmp(b), i.cmp(&j), "{:?} and {:?}", a, b);
+                }
+            }
+        }
+
+        #[test]
+        fn compares_integers_and_floats_exactly() {
+            // Including integers that are only equal to a float once converted to one.
+            let sorted = vec![
+                Value::Integer(i64::MIN),
+                Value::Float(-9_223_372_036_854_775_808.0),
+                Value::Integer(i64::MIN + 1),
+                Value::Float(-0.5),
+                Value::Integer(0),
+                Value::Float(0.5),
+                Value::Float(9_007_199_254_740_992.0),
+                Value::Integer(9_007_199_254_740_993),
+                Value::Integer(i64::MAX),
+                Value::Float(9_223_372_036_854_775_808.0),
+                Value::Float(f64::INFINITY),
+            ];
+            for (i, a) in sorted.iter().enumerate() {
+                for (j, b) in sorted.iter().enumerate() {
+                    assert_eq!(a.total_cmp(b), i.cmp(&j), "{:?} and {:?}", a, b);
+                }
+            }
+        }
+    }
+
     mod value_hash {
         use super::*;
 
@@ -1645,6 +1977,32 @@ mod test {
             );
             assert_eq!(value.as_map().unwrap()["a"].as_array()[0], marker);
         }
+
+        #[test]
+        fn insert_borrowed_matches_insert() {
+            let paths = [
+                "root",
+                "root.doot",
+                "root.doot[1]",
+                "a[-2].b",
+                "(x | root).doot",
+                "\"quoted key\".inner",
+                "root[0]",
+            ];
+            let mut owned = Value::from(BTreeMap::default());
+            let mut borrowed = Value::from(BTreeMap::default());
+            for (i, path) in (0_i64..).zip(paths) {
+                let lookup = LookupBuf::from_str(path).unwrap();
+                let marker = Value::from(i);
+                assert_eq!(
+                    borrowed.insert_borrowed(&lookup, marker.clone()).unwrap(),
+                    owned.insert(lookup.clone(), marker).unwrap(),
+                    "{}",
+                    path
+                );
+                assert_eq!(borrowed, owned, "{}", path);
+            }
+        }
     }
 
     mod corner_cases {
@@ -1791,4 +2149,60 @@ mod test {
                 _ => panic!("This test should never read Err'ing type folders."),
             });
     }
+
+    /// The externally tagged representation `Value` has always deserialized from, as its derived
+    /// `Serialize` would write it.
+    #[derive(Serialize)]
+    enum Tagged {
+        Bytes(Bytes),
+        Integer(i64),
+        Float(f64),
+        Boolean(bool),
+        Timestamp(DateTime<Utc>),
+        Map(BTreeMap<String, Tagged>),
+        Array(Vec<Tagged>),
+        Null,
+    }
+
+    impl From<&Value> for Tagged {
+        fn from(value: &Value) -> Self {
+            match value {
+                Value::Bytes(bytes) => Tagged::Bytes(bytes.clone()),
+                Value::Integer(i) => Tagged::Integer(*i),
+                Value::Float(f) => Tagged::Float(*f),
+                Value::Boolean(b) => Tagged::Boolean(*b),
+                Value::Timestamp(ts) => Tagged::Timestamp(*ts),
+                Value::Map(map) => Tagged::Map(
+                    map.iter()
+                        .map(|(key, value)| (key.clone(), value.into()))
+                        .collect(),
+                ),
+                Value::Array(array) => Tagged::Array(array.iter().map(Into::into).collect()),
+                Value::Null => Tagged::Null,
+            }
+        }
+    }
+
+    #[test]
+    fn deserialize_tagged_round_trip() {
+        use shared::btreemap;
+
+        let timestamp = DateTime::parse_from_rfc3339("2021-01-01T12:34:56.789Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let value = Value::from(btreemap! {
+            "bytes" => "foo",
+            "integer" => 1,
+            "float" => 1.5,
+            "boolean" => true,
+            "timestamp" => timestamp,
+            "array" => vec![Value::from(2), Value::Null],
+        });
+
+        let json = serde_json::to_string(&Tagged::from(&value)).unwrap();
+        assert_eq!(serde_json::from_str::<Value>(&json).unwrap(), value);
+
+        let json = r#"{"Map":{"array":{"Array":[{"Integer":2},"Null"]},"boolean":{"Boolean":true},"bytes":{"Bytes":[102,111,111]},"float":{"Float":1.5},"integer":{"Integer":1},"timestamp":{"Timestamp":"2021-01-01T12:34:56.789Z"}}}"#;
+        assert_eq!(serde_json::from_str::<Value>(json).unwrap(), value);
+    }
 }
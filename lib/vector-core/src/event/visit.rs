@@ -0,0 +1,382 @@
+//! Walking a [`Value`] with a [`ValueVisitor`], for encoders that write their own format straight
+//! to the output instead of going through an intermediate representation.
+//!
+//! The order of the callbacks is guaranteed:
+//!
+//! * Values are visited depth first, each exactly once.
+//! * A map calls [`ValueVisitor::enter_map`], then for each entry [`ValueVisitor::key`] followed by
+//!   the visit of its value, then [`ValueVisitor::leave_map`]. Entries are visited in the order of
+//!   the bytes of their keys, which is the order of the map.
+//! * An array calls [`ValueVisitor::enter_array`], then visits its elements in order, then
+//!   [`ValueVisitor::leave_array`].
+//! * The first error returned by a callback stops the walk and is returned by it.
+//!
+//! A visitor that overrides [`ValueVisitor::visit_map`] or [`ValueVisitor::visit_array`] takes
+//! those maps or arrays whole instead, and nothing in them is visited unless it walks them itself.
+
+use std::collections::BTreeMap;
+
+use bytes::Bytes;
+use chrono::{DateTime, Utc};
+use serde::Serializer;
+
+use super::{timestamp_to_string, LogEvent, Value};
+
+/// The callbacks of [`Value::walk`], see the [module documentation](self) for the order they are
+/// called in.
+#[allow(clippy::missing_errors_doc)]
+pub trait ValueVisitor {
+    /// Returned by the callbacks to stop the walk, such as when the output can't be written to.
+    type Error;
+
+    /// Bytes as they are stored, which need not be valid UTF-8.
+    fn bytes(&mut self, bytes: &Bytes) -> Result<(), Self::Error>;
+    fn integer(&mut self, integer: i64) -> Result<(), Self::Error>;
+    fn float(&mut self, float: f64) -> Result<(), Self::Error>;
+    fn boolean(&mut self, boolean: bool) -> Result<(), Self::Error>;
+    fn timestamp(&mut self, timestamp: &DateTime<Utc>) -> Result<(), Self::Error>;
+    fn null(&mut self) -> Result<(), Self::Error>;
+
+    /// The start of a map of `len` entries.
+    fn enter_map(&mut self, len: usize) -> Result<(), Self::Error>;
+    /// The key of the next entry of the current map, right before its value is visited.
+    fn key(&mut self, key: &str) -> Result<(), Self::Error>;
+    fn leave_map(&mut self) -> Result<(), Self::Error>;
+
+    /// The start of an array of `len` elements.
+    fn enter_array(&mut self, len: usize) -> Result<(), Self::Error>;
+    fn leave_array(&mut self) -> Result<(), Self::Error>;
+
+    /// A map, walked entry by entry unless overridden, such as by a visitor that writes to
+    /// something that takes nested maps whole.
+    fn visit_map(&mut self, map: &BTreeMap<String, Value>) -> Result<(), Self::Error> {
+        walk_map(map, self)
+    }
+
+    /// An array, walked element by element unless overridden, see
+    /// [`visit_map`](Self::visit_map).
+    fn visit_array(&mut self, array: &[Value]) -> Result<(), Self::Error> {
+        self.enter_array(array.len())?;
+        for value in array {
+            value.walk(self)?;
+        }
+        self.leave_array()
+    }
+}
+
+impl Value {
+    /// Visit this value and everything in it with `visitor`.
+    ///
+    /// # Errors
+    ///
+    /// Fails with the first error returned by the visitor.
+    pub fn walk<V: ValueVisitor + ?Sized>(&self, visitor: &mut V) -> Result<(), V::Error> {
+        match self {
+            Value::Bytes(bytes) => visitor.bytes(bytes),
+            Value::Integer(integer) => visitor.integer(*integer),
+            Value::Float(float) => visitor.float(*float),
+            Value::Boolean(boolean) => visitor.boolean(*boolean),
+            Value::Timestamp(timestamp) => visitor.timestamp(timestamp),
+            Value::Null => visitor.null(),
+            Value::Map(map) => visitor.visit_map(map),
+            Value::Array(array) => visitor.visit_array(array),
+        }
+    }
+}
+
+impl LogEvent {
+    /// Visit the fields of this event with `visitor`, as a map.
+    ///
+    /// # Errors
+    ///
+    /// Fails with the first error returned by the visitor.
+    pub fn walk<V: ValueVisitor + ?Sized>(&self, visitor: &mut V) -> Result<(), V::Error> {
+        visitor.visit_map(self.as_map())
+    }
+}
+
+pub(super) fn walk_map<V: ValueVisitor + ?Sized>(
+    map: &BTreeMap<String, Value>,
+    visitor: &mut V,
+) -> Result<(), V::Error> {
+    visitor.enter_map(map.len())?;
+    for (key, value) in map {
+        visitor.key(key)?;
+        value.walk(visitor)?;
+    }
+    visitor.leave_map()
+}
+
+/// Serialize a value with a serde serializer, through [`SerdeVisitor`].
+pub(super) fn serialize_value<S: Serializer>(
+    value: &Value,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    let mut visitor = SerdeVisitor::new(serializer);
+    value.walk(&mut visitor)?;
+    Ok(visitor.finish())
+}
+
+/// Serialize the fields of a log event with a serde serializer, through [`SerdeVisitor`].
+pub(super) fn serialize_map<S: Serializer>(
+    map: &BTreeMap<String, Value>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    let mut visitor = SerdeVisitor::new(serializer);
+    visitor.visit_map(map)?;
+    Ok(visitor.finish())
+}
+
+/// Writes the one value it visits to a serde serializer. Bytes are written as a string, lossily
+/// if they aren't valid UTF-8, and timestamps as RFC 3339 strings.
+///
+/// A serializer takes the entries of a map or the elements of an array as values to serialize
+/// on their own, so maps and arrays are taken whole and each value in them goes through another
+/// visitor, rather than being walked into.
+struct SerdeVisitor<S: Serializer> {
+    serializer: Option<S>,
+    ok: Option<S::Ok>,
+}
+
+impl<S: Serializer> SerdeVisitor<S> {
+    fn new(serializer: S) -> Self {
+        Self {
+            serializer: Some(serializer),
+            ok: None,
+        }
+    }
+
+    fn write(&mut self, write: impl FnOnce(S) -> Result<S::Ok, S::Error>) -> Result<(), S::Error> {
+        let serializer = self
+            .serializer
+            .take()
+            .expect("a serializer writes a single value");
+        self.ok = Some(write(serializer)?);
+        Ok(())
+    }
+
+    fn finish(self) -> S::Ok {
+        self.ok.expect("the value was visited")
+    }
+}
+
+impl<S: Serializer> ValueVisitor for SerdeVisitor<S> {
+    type Error = S::Error;
+
+    fn bytes(&mut self, bytes: &Bytes) -> Result<(), S::Error> {
+        self.write(|serializer| serializer.serialize_str(&String::from_utf8_lossy(bytes)))
+    }
+
+    fn integer(&mut self, integer: i64) -> Result<(), S::Error> {
+        self.write(|serializer| serializer.serialize_i64(integer))
+    }
+
+    fn float(&mut self, float: f64) -> Result<(), S::Error> {
+        self.write(|serializer| serializer.serialize_f64(float))
+    }
+
+    fn boolean(&mut self, boolean: bool) -> Result<(), S::Error> {
+        self.write(|serializer| serializer.serialize_bool(boolean))
+    }
+
+    fn timestamp(&mut self, timestamp: &DateTime<Utc>) -> Result<(), S::Error> {
+        self.write(|serializer| serializer.serialize_str(&timestamp_to_string(timestamp)))
+    }
+
+    fn null(&mut self) -> Result<(), S::Error> {
+        self.write(Serializer::serialize_none)
+    }
+
+    fn visit_map(&mut self, map: &BTreeMap<String, Value>) -> Result<(), S::Error> {
+        self.write(|serializer| serializer.collect_map(map))
+    }
+
+    fn visit_array(&mut self, array: &[Value]) -> Result<(), S::Error> {
+        self.write(|serializer| serializer.collect_seq(array))
+    }
+
+    // Maps and arrays are taken whole above, so they are never entered.
+
+    fn enter_map(&mut self, _len: usize) -> Result<(), S::Error> {
+        unreachable!("maps are serialized by visit_map")
+    }
+
+    fn key(&mut self, _key: &str) -> Result<(), S::Error> {
+        unreachable!("maps are serialized by visit_map")
+    }
+
+    fn leave_map(&mut self) -> Result<(), S::Error> {
+        unreachable!("maps are serialized by visit_map")
+    }
+
+    fn enter_array(&mut self, _len: usize) -> Result<(), S::Error> {
+        unreachable!("arrays are serialized by visit_array")
+    }
+
+    fn leave_array(&mut self) -> Result<(), S::Error> {
+        unreachable!("arrays are serialized by visit_array")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::TimeZone;
+    use serde_json::json;
+    use shared::btreemap;
+
+    use super::*;
+
+    /// Records every callback.
+    #[derive(Default)]
+    struct Recorder {
+        calls: Vec<String>,
+        fail_at: Option<usize>,
+    }
+
+    impl Recorder {
+        fn record(&mut self, call: String) -> Result<(), usize> {
+            if self.fail_at == Some(self.calls.len()) {
+                return Err(self.calls.len());
+            }
+            self.calls.push(call);
+            Ok(())
+        }
+    }
+
+    impl ValueVisitor for Recorder {
+        type Error = usize;
+
+        fn bytes(&mut self, bytes: &Bytes) -> Result<(), usize> {
+            self.record(format!("bytes {:?}", bytes))
+        }
+
+        fn integer(&mut self, integer: i64) -> Result<(), usize> {
+            self.record(format!("integer {}", integer))
+        }
+
+        fn float(&mut self, float: f64) -> Result<(), usize> {
+            self.record(format!("float {}", float))
+        }
+
+        fn boolean(&mut self, boolean: bool) -> Result<(), usize> {
+            self.record(format!("boolean {}", boolean))
+        }
+
+        fn timestamp(&mut self, timestamp: &DateTime<Utc>) -> Result<(), usize> {
+            self.record(format!("timestamp {}", timestamp.timestamp()))
+        }
+
+        fn null(&mut self) -> Result<(), usize> {
+            self.record("null".to_owned())
+        }
+
+        fn enter_map(&mut self, len: usize) -> Result<(), usize> {
+            self.record(format!("enter_map {}", len))
+        }
+
+        fn key(&mut self, key: &str) -> Result<(), usize> {
+            self.record(format!("key {}", key))
+        }
+
+        fn leave_map(&mut self) -> Result<(), usize> {
+            self.record("leave_map".to_owned())
+        }
+
+        fn enter_array(&mut self, len: usize) -> Result<(), usize> {
+            self.record(format!("enter_array {}", len))
+        }
+
+        fn leave_array(&mut self) -> Result<(), usize> {
+            self.record("leave_array".to_owned())
+        }
+    }
+
+    fn nested() -> Value {
+        Value::from(btreemap! {
+            "b" => vec![
+                Value::from(1),
+                Value::from(btreemap! { "z" => 2.5, "a" => Value::Null }),
+                Value::from(Vec::<Value>::new()),
+            ],
+            "é" => Value::Bytes(Bytes::from_static(b"\xff")),
+            "a" => true,
+            "B" => Value::from(Utc.timestamp(10, 0)),
+        })
+    }
+
+    #[test]
+    fn walks_depth_first_in_key_order() {
+        let mut recorder = Recorder::default();
+        nested().walk(&mut recorder).unwrap();
+        assert_eq!(
+            recorder.calls,
+            vec![
+                "enter_map 4",
+                "key B",
+                "timestamp 10",
+                "key a",
+                "boolean true",
+                "key b",
+                "enter_array 3",
+                "integer 1",
+                "enter_map 2",
+                "key a",
+                "null",
+                "key z",
+                "float 2.5",
+                "leave_map",
+                "enter_array 0",
+                "leave_array",
+                "leave_array",
+                "key é",
+                r#"bytes b"\xff""#,
+                "leave_map",
+            ]
+        );
+    }
+
+    #[test]
+    fn log_event_walks_its_fields() {
+        let mut log = LogEvent::default();
+        log.insert("message", "hello");
+        log.insert("count", 1);
+
+        let mut recorder = Recorder::default();
+        log.walk(&mut recorder).unwrap();
+        assert_eq!(
+            recorder.calls,
+            vec![
+                "enter_map 2",
+                "key count",
+                "integer 1",
+                "key message",
+                r#"bytes b"hello""#,
+                "leave_map",
+            ]
+        );
+    }
+
+    #[test]
+    fn serializes_through_serde() {
+        let expected = json!({
+            "B": "1970-01-01T00:00:10Z",
+            "a": true,
+            "b": [1, { "a": null, "z": 2.5 }, []],
+            "é": "\u{fffd}",
+        });
+        assert_eq!(serde_json::to_value(nested()).unwrap(), expected);
+
+        let log = LogEvent::from(nested().into_map().unwrap());
+        assert_eq!(serde_json::to_value(&log).unwrap(), expected);
+    }
+
+    #[test]
+    fn first_error_stops_the_walk() {
+        let mut recorder = Recorder {
+            fail_at: Some(7),
+            ..Recorder::default()
+        };
+        assert_eq!(nested().walk(&mut recorder), Err(7));
+        assert_eq!(recorder.calls.len(), 7);
+    }
+}
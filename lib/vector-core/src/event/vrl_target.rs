@@ -1,102 +1,869 @@
-use std::{collections::BTreeMap, convert::TryFrom, sync::Arc};
+use std::{
+    collections::{BTreeMap, HashMap},
+    convert::TryFrom,
+    ops::Bound,
+    sync::{Arc, Mutex},
+};
 
-use lookup::LookupBuf;
+use chrono::{DateTime, Utc};
+use lookup::{LookupBuf, SegmentBuf};
+use serde::{Deserialize, Serialize};
 use snafu::Snafu;
+use vrl_core::Target;
 
-use super::{Event, EventMetadata, LogEvent, Metric, MetricKind, Value};
+use super::{
+    error::{EventError, PathError},
+    metric::MetricTags,
+    Event, EventMetadata, LogEvent, Metric, MetricKind, MetricValue, TagLimits, Value, ValueKind,
+};
 use crate::config::log_schema;
 
-const VALID_METRIC_PATHS_SET: &str = ".name, .namespace, .timestamp, .kind, .tags";
+/// An operation of a VRL program on a path of a target.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum PathOp {
+    Get,
+    Set,
+    Remove,
+}
+
+/// A path of a target, as listed by [`VrlTarget::allowed_paths`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PathDescriptor {
+    /// The path, where `*` stands for any one segment. `.**` stands for every path, `.` included.
+    pub pattern: &'static str,
+    /// The kind of the value at the path, or `None` if it can be of any kind.
+    pub kind: Option<ValueKind>,
+    /// Whether the path can be set.
+    pub writable: bool,
+}
+
+/// A path of a metric, in the order the paths are listed in errors.
+struct MetricPath {
+    pattern: &'static str,
+    kind: ValueKind,
+    writable: bool,
+    removable: bool,
+}
+
+const METRIC_PATHS: [MetricPath; 9] = [
+    MetricPath {
+        pattern: ".",
+        kind: ValueKind::Map,
+        writable: true,
+        removable: false,
+    },
+    MetricPath {
+        pattern: ".name",
+        kind: ValueKind::Bytes,
+        writable: true,
+        removable: false,
+    },
+    MetricPath {
+        pattern: ".namespace",
+        kind: ValueKind::Bytes,
+        writable: true,
+        removable: true,
+    },
+    MetricPath {
+        pattern: ".timestamp",
+        kind: ValueKind::Timestamp,
+        writable: true,
+        removable: true,
+    },
+    MetricPath {
+        pattern: ".kind",
+        kind: ValueKind::Bytes,
+        writable: true,
+        removable: false,
+    },
+    MetricPath {
+        pattern: ".tags",
+        kind: ValueKind::Map,
+        writable: true,
+        removable: true,
+    },
+    MetricPath {
+        pattern: ".tags.*",
+        kind: ValueKind::Bytes,
+        writable: true,
+        removable: true,
+    },
+    // We can get the `type` of the metric in Remap, but can't set it.
+    MetricPath {
+        pattern: ".type",
+        kind: ValueKind::Map,
+        writable: false,
+        removable: false,
+    },
+    // The value can only be set by assigning the whole metric to `.`.
+    MetricPath {
+        pattern: ".value",
+        kind: ValueKind::Map,
+        writable: false,
+        removable: false,
+    },
+];
+
+fn metric_paths(op: PathOp) -> Vec<PathDescriptor> {
+    METRIC_PATHS
+        .iter()
+        .filter(|path| match op {
+            PathOp::Get => true,
+            PathOp::Set => path.writable,
+            PathOp::Remove => path.removable,
+        })
+        .map(|path| PathDescriptor {
+            pattern: path.pattern,
+            kind: Some(path.kind),
+            writable: path.writable,
+        })
+        .collect()
+}
 
-/// We can get the `type` of the metric in Remap, but can't set it.
-const VALID_METRIC_PATHS_GET: &str = ".name, .namespace, .timestamp, .kind, .tags, .type";
+/// The paths listed by the error for an invalid path of a metric: the top-level fields the
+/// operation applies to. Removing an invalid path lists the fields that can be set, as it always
+/// has.
+fn expected_metric_paths(op: PathOp) -> String {
+    let op = match op {
+        PathOp::Get => PathOp::Get,
+        PathOp::Set | PathOp::Remove => PathOp::Set,
+    };
+    metric_paths(op)
+        .into_iter()
+        .map(|path| path.pattern)
+        .filter(|pattern| *pattern != "." && !pattern[1..].contains('.'))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// The keys accepted when assigning an object to `.` of a metric: the top-level fields of
+/// [`METRIC_PATHS`].
+fn metric_root_keys() -> impl Iterator<Item = &'static str> {
+    METRIC_PATHS
+        .iter()
+        .map(|path| &path.pattern[1..])
+        .filter(|key| !key.is_empty() && !key.contains('.'))
+}
 
 /// Metrics aren't interested in paths that have a length longer than 3.
 ///
 /// The longest path is 2, and we need to check that a third segment doesn't exist as we don't want
-/// fields such as `.tags.host.thing`.
+/// fields such as `.tags.host.thing`. Paths below `.value` are read from the object it is, see
+/// [`metric_value_path`].
 const MAX_METRIC_PATH_DEPTH: usize = 3;
 
+/// The path inside `.value` of a metric that `path` reads, such as `.counter.value` for
+/// `.value.counter.value`, or `None` if `path` doesn't reach inside it.
+fn metric_value_path(path: &LookupBuf) -> Option<LookupBuf> {
+    let mut segments = path.iter();
+    match segments.next() {
+        Some(SegmentBuf::Field(field)) if field.name == "value" && path.len() > 1 => {
+            Some(LookupBuf::from_segments(segments.cloned().collect()))
+        }
+        _ => None,
+    }
+}
+
 /// An adapter to turn `Event`s into `vrl_core::Target`s.
 #[derive(Debug, Clone)]
-pub enum VrlTarget {
+pub struct VrlTarget {
+    event: TargetEvent,
+    protected_paths: Option<Arc<ProtectedPaths>>,
+    tag_limits: Option<TagLimits>,
+    utf8_handling: Utf8Handling,
+    path_stats: Option<Arc<PathStats>>,
+    // Set when `.` of a log is removed, and cleared by the next insert, so that a target emptied
+    // by `del(.)` turns back into no events at all rather than a single empty one.
+    root_removed: bool,
+}
+
+#[derive(Debug, Clone)]
+enum TargetEvent {
     // `LogEvent` is essentially just a destructured `event::LogEvent`, but without the semantics
     // that `fields` must always be a `Map` variant.
     LogEvent(Value, EventMetadata),
     Metric(Metric),
 }
 
+/// The output of the transform that an event coming out of a VRL program belongs to.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum TargetOutput {
+    /// The main output.
+    Primary,
+    /// The `dropped` output, for events the program marked as dropped.
+    Dropped,
+}
+
+/// How bytes that aren't valid UTF-8 are turned into the name, namespace or tags of a metric.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Utf8Handling {
+    /// Replace invalid sequences with `U+FFFD REPLACEMENT CHARACTER`.
+    Lossy,
+    /// Fail the write.
+    Reject,
+    /// Write the standard base64 encoding of the bytes instead. Valid UTF-8 is written as is.
+    Base64,
+}
+
+impl Default for Utf8Handling {
+    fn default() -> Self {
+        Self::Lossy
+    }
+}
+
+impl Utf8Handling {
+    // Turn the bytes written to `path` into a string.
+    fn decode(self, path: &str, bytes: &[u8]) -> Result<String, String> {
+        let error = match std::str::from_utf8(bytes) {
+            Ok(string) => return Ok(string.to_owned()),
+            Err(error) => error,
+        };
+
+        match self {
+            Self::Lossy => Ok(String::from_utf8_lossy(bytes).into_owned()),
+            Self::Reject => {
+                let offset = error.valid_up_to();
+                let invalid = error.error_len().unwrap_or(bytes.len() - offset);
+                Err(MetricPathError::InvalidUtf8 {
+                    path,
+                    offset,
+                    preview: &hex_preview(&bytes[offset..offset + invalid]),
+                }
+                .to_string())
+            }
+            Self::Base64 => Ok(base64::encode(bytes)),
+        }
+    }
+
+    fn decode_value(self, path: &str, value: vrl_core::Value) -> Result<String, String> {
+        let bytes = value.try_bytes().map_err(|e| e.to_string())?;
+        self.decode(path, &bytes)
+    }
+}
+
+fn hex_preview(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Why processing an event failed, to be attached to the event when it is routed to a dead-letter
+/// output. See [`annotate_error`] and [`VrlTarget::into_error_event`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct EventFailure {
+    pub message: String,
+    /// The component the event failed in.
+    pub component_id: Option<String>,
+    pub timestamp: DateTime<Utc>,
+    /// Where the failure is written: the path of an object holding `message`, `component_id` and
+    /// `timestamp` on logs, and the prefix of tags of those names on metrics. Defaults to `error`
+    /// under the metadata key of the global log schema, such as `metadata.error`.
+    pub namespace: String,
+}
+
+impl EventFailure {
+    /// A failure with the given error message, that happened now.
+    pub fn new(message: impl ToString) -> Self {
+        Self {
+            message: message.to_string(),
+            component_id: None,
+            timestamp: Utc::now(),
+            namespace: format!("{}.error", log_schema().metadata_key()),
+        }
+    }
+
+    #[must_use]
+    pub fn with_component_id(mut self, component_id: impl Into<String>) -> Self {
+        self.component_id = Some(component_id.into());
+        self
+    }
+
+    #[must_use]
+    pub const fn with_timestamp(mut self, timestamp: DateTime<Utc>) -> Self {
+        self.timestamp = timestamp;
+        self
+    }
+
+    #[must_use]
+    pub fn with_namespace(mut self, namespace: impl Into<String>) -> Self {
+        self.namespace = namespace.into();
+        self
+    }
+}
+
+/// Attach `failure` to `event` under its namespace, keeping everything else about the event.
+///
+/// Metric tags are strings, so the timestamp of a failure is written to metrics in RFC 3339.
+pub fn annotate_error(mut event: Event, failure: &EventFailure) -> Event {
+    let path = |field: &str| format!("{}.{}", failure.namespace, field);
+    match &mut event {
+        Event::Log(log) => {
+            log.insert(path("message"), failure.message.clone());
+            if let Some(component_id) = &failure.component_id {
+                log.insert(path("component_id"), component_id.clone());
+            }
+            log.insert(path("timestamp"), failure.timestamp);
+        }
+        Event::Metric(metric) => {
+            metric.insert_tag(path("message"), failure.message.clone());
+            if let Some(component_id) = &failure.component_id {
+                metric.insert_tag(path("component_id"), component_id.clone());
+            }
+            metric.insert_tag(path("timestamp"), failure.timestamp.to_rfc3339());
+        }
+    }
+    event
+}
+
+/// Paths that a VRL program may read, but not modify or delete.
+///
+/// A protected path also covers everything nested below it, so protecting `.tags.tenant` on a
+/// metric prevents rewriting that tag, and protecting `.user` on a log prevents touching
+/// `.user.id`.
+#[derive(Debug, Clone, Default)]
+pub struct ProtectedPaths {
+    paths: Vec<LookupBuf>,
+}
+
+impl ProtectedPaths {
+    pub fn new(paths: impl IntoIterator<Item = LookupBuf>) -> Self {
+        Self {
+            paths: paths.into_iter().collect(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.paths.is_empty()
+    }
+
+    /// The protected path equal to, or an ancestor of, `path`.
+    fn covering(&self, path: &LookupBuf) -> Option<&LookupBuf> {
+        self.paths
+            .iter()
+            .find(|protected| path.len() >= protected.len() && path.starts_with(protected))
+    }
+
+    /// The protected paths nested strictly below `path`.
+    fn below<'a>(&'a self, path: &'a LookupBuf) -> impl Iterator<Item = &'a LookupBuf> + 'a {
+        self.paths
+            .iter()
+            .filter(move |protected| protected.len() > path.len() && protected.starts_with(path))
+    }
+}
+
+/// How often a VRL program applied each [`PathOp`] to a path, and how many of its reads found
+/// nothing there.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct PathCounts {
+    /// Reads of the path, `exists` included.
+    pub gets: u64,
+    /// The reads that found no value at the path.
+    pub misses: u64,
+    pub sets: u64,
+    pub removes: u64,
+}
+
+impl PathCounts {
+    fn record(&mut self, op: PathOp, found: bool) {
+        match op {
+            PathOp::Get => {
+                self.gets += 1;
+                if !found {
+                    self.misses += 1;
+                }
+            }
+            PathOp::Set => self.sets += 1,
+            PathOp::Remove => self.removes += 1,
+        }
+    }
+}
+
+/// The counts taken from a [`PathStats`].
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct PathStatsReport {
+    pub paths: BTreeMap<String, PathCounts>,
+    /// The operations on the paths that didn't fit in `max_paths`, counted together.
+    pub other: PathCounts,
+}
+
+/// Counts of the operations of a VRL program on each path of the targets it runs on, across
+/// events, for finding the paths a program uses most and the reads that always miss.
+///
+/// Up to `max_paths` paths are counted apart, in the order they were first used, and the
+/// operations on any other path are counted together.
+#[derive(Debug)]
+pub struct PathStats {
+    max_paths: usize,
+    counts: Mutex<PathCounter>,
+}
+
+#[derive(Debug, Default)]
+struct PathCounter {
+    paths: HashMap<LookupBuf, PathCounts>,
+    other: PathCounts,
+}
+
+impl PathStats {
+    pub fn new(max_paths: usize) -> Self {
+        Self {
+            max_paths,
+            counts: Mutex::default(),
+        }
+    }
+
+    /// Count `op` on `path`, where `found` is whether a read found a value.
+    fn record(&self, path: &LookupBuf, op: PathOp, found: bool) {
+        let mut counter = self.counts.lock().expect("mutex poisoned");
+        let counter = &mut *counter;
+        match counter.paths.get_mut(path) {
+            Some(counts) => counts.record(op, found),
+            None => {
+                if counter.paths.len() < self.max_paths {
+                    let mut counts = PathCounts::default();
+                    counts.record(op, found);
+                    counter.paths.insert(path.clone(), counts);
+                } else {
+                    counter.other.record(op, found);
+                }
+            }
+        }
+    }
+
+    /// Take the counts so far, starting over from none. The paths counted apart are forgotten
+    /// as well, so that the next report has room for the paths used from then on.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a thread panicked while counting.
+    pub fn take(&self) -> PathStatsReport {
+        let counter = std::mem::take(&mut *self.counts.lock().expect("mutex poisoned"));
+        PathStatsReport {
+            paths: counter
+                .paths
+                .into_iter()
+                .map(|(path, counts)| (display_path(&path), counts))
+                .collect(),
+            other: counter.other,
+        }
+    }
+}
+
 impl VrlTarget {
     pub fn new(event: Event) -> Self {
-        match event {
+        let event = match event {
             Event::Log(event) => {
                 let (fields, metadata) = event.into_parts();
-                VrlTarget::LogEvent(Value::Map(fields), metadata)
+                TargetEvent::LogEvent(Value::Map(fields), metadata)
             }
-            Event::Metric(event) => VrlTarget::Metric(event),
+            Event::Metric(event) => TargetEvent::Metric(event),
+        };
+
+        Self {
+            event,
+            protected_paths: None,
+            tag_limits: None,
+            utf8_handling: Utf8Handling::default(),
+            path_stats: None,
+            root_removed: false,
         }
     }
 
+    /// Reject any attempt by the VRL program to modify or delete the given paths.
+    #[must_use]
+    pub fn with_protected_paths(mut self, protected_paths: Arc<ProtectedPaths>) -> Self {
+        self.protected_paths = Some(protected_paths);
+        self
+    }
+
+    /// Enforce the given limits on the tags the VRL program writes to a metric.
+    #[must_use]
+    pub const fn with_tag_limits(mut self, tag_limits: TagLimits) -> Self {
+        self.tag_limits = Some(tag_limits);
+        self
+    }
+
+    /// Set how bytes that aren't valid UTF-8 are turned into the name, namespace or tags of a
+    /// metric. The invalid sequences are replaced unless this says otherwise.
+    #[must_use]
+    pub const fn with_utf8_handling(mut self, utf8_handling: Utf8Handling) -> Self {
+        self.utf8_handling = utf8_handling;
+        self
+    }
+
+    /// Count the operations of the VRL program on each path in `path_stats`.
+    #[must_use]
+    pub fn with_path_stats(mut self, path_stats: Arc<PathStats>) -> Self {
+        self.path_stats = Some(path_stats);
+        self
+    }
+
+    /// The paths a VRL program can apply `op` to on this target, such as for checking the paths of
+    /// a program or suggesting them in an editor.
+    pub fn allowed_paths(&self, op: PathOp) -> Vec<PathDescriptor> {
+        self.event.allowed_paths(op)
+    }
+
     /// Turn the target back into events.
     ///
     /// This returns an iterator of events as one event can be turned into multiple by assigning an
-    /// array to `.` in VRL.
+    /// array to `.` in VRL, or none by removing `.` without assigning anything after.
     pub fn into_events(self) -> impl Iterator<Item = Event> {
-        match self {
-            VrlTarget::LogEvent(value, metadata) => {
-                Box::new(value_into_log_events(value, metadata)) as Box<dyn Iterator<Item = Event>>
+        match self.event {
+            TargetEvent::LogEvent(..) if self.root_removed => {
+                Box::new(std::iter::empty()) as Box<dyn Iterator<Item = Event>>
             }
-            VrlTarget::Metric(metric) => {
+            TargetEvent::LogEvent(value, metadata) => Box::new(value_into_log_events(
+                value,
+                metadata,
+                log_schema().message_key(),
+            ))
+                as Box<dyn Iterator<Item = Event>>,
+            TargetEvent::Metric(metric) => {
                 Box::new(std::iter::once(Event::Metric(metric))) as Box<dyn Iterator<Item = Event>>
             }
         }
     }
+
+    /// Turn the target into a single event annotated with `failure`, for routing an event the VRL
+    /// program failed or aborted on.
+    ///
+    /// Mutations the program made before it failed are kept, the event is the target as the
+    /// program left it. To route the event as it was before the program ran, annotate a copy of
+    /// the original event with [`annotate_error`] instead.
+    ///
+    /// Unlike [`into_events`](Self::into_events), this always returns exactly one event: a log
+    /// whose `.` was removed has no fields but the annotation, and a `.` that isn't an object, such
+    /// as an array that would have been split into several events, is kept whole under the
+    /// message key.
+    pub fn into_error_event(self, failure: &EventFailure) -> Event {
+        let event = match self.event {
+            TargetEvent::LogEvent(_, metadata) if self.root_removed => {
+                Event::from(LogEvent::new_with_metadata(metadata))
+            }
+            TargetEvent::LogEvent(Value::Map(fields), metadata) => {
+                Event::from(LogEvent::from_parts(fields, metadata))
+            }
+            TargetEvent::LogEvent(value, metadata) => {
+                let mut log = LogEvent::new_with_metadata(metadata);
+                log.insert(log_schema().message_key(), value);
+                Event::from(log)
+            }
+            TargetEvent::Metric(metric) => Event::Metric(metric),
+        };
+        annotate_error(event, failure)
+    }
+
+    /// The number of events created by splitting an array assigned to `.`, or `None` if the
+    /// target isn't split and turns back into a single event.
+    pub fn fanout(&self) -> Option<usize> {
+        match &self.event {
+            TargetEvent::LogEvent(Value::Array(values), _) => Some(values.len()),
+            _ => None,
+        }
+    }
+
+    /// Keep only the first `limit` events that splitting an array assigned to `.` creates.
+    ///
+    /// Returns the number of events there would have been, if any were discarded.
+    pub fn truncate_fanout(&mut self, limit: usize) -> Option<usize> {
+        match &mut self.event {
+            TargetEvent::LogEvent(Value::Array(values), _) if values.len() > limit => {
+                let fanout = values.len();
+                values.truncate(limit);
+                Some(fanout)
+            }
+            _ => None,
+        }
+    }
+
+    /// The tags of a metric whose keys start with `prefix`, such as every `aws_` tag, as an
+    /// object. Unlike reading `.tags` and filtering it in the program, only the matching tags are
+    /// copied. Logs have no tags, so for them the object is empty.
+    ///
+    /// Programs read these with the `get_tags_matching` function.
+    pub fn tags_matching(&self, prefix: &str) -> vrl_core::Value {
+        match &self.event {
+            TargetEvent::Metric(metric) => metric
+                .tags()
+                .into_iter()
+                .flat_map(|tags| tags_with_prefix(tags, prefix))
+                .map(|(key, value)| (key.clone(), value.clone().into()))
+                .collect(),
+            TargetEvent::LogEvent(..) => BTreeMap::<String, vrl_core::Value>::new().into(),
+        }
+    }
+
+    /// Remove the tags of a metric whose keys start with `prefix`, returning them as an object
+    /// like [`tags_matching`](Self::tags_matching) does. Programs remove these with the
+    /// `remove_tags_matching` function.
+    ///
+    /// # Errors
+    ///
+    /// Fails without removing any tag if one of the matching tags is protected.
+    pub fn delete_tags_matching(&mut self, prefix: &str) -> Result<vrl_core::Value, String> {
+        let keys = match &self.event {
+            TargetEvent::Metric(metric) => metric
+                .tags()
+                .into_iter()
+                .flat_map(|tags| tags_with_prefix(tags, prefix))
+                .map(|(key, _)| key.clone())
+                .collect::<Vec<_>>(),
+            TargetEvent::LogEvent(..) => Vec::new(),
+        };
+        for key in &keys {
+            self.check_writable(&LookupBuf::from_segments(vec![
+                "tags".into(),
+                key.as_str().into(),
+            ]))?;
+        }
+
+        let mut removed = BTreeMap::<String, vrl_core::Value>::new();
+        if let TargetEvent::Metric(metric) = &mut self.event {
+            for key in keys {
+                if let Some(value) = metric.remove_tag(&key) {
+                    removed.insert(key, value.into());
+                }
+            }
+        }
+        Ok(removed.into())
+    }
+
+    /// Turn the target back into events, along with the output each of them belongs to.
+    ///
+    /// Events whose metadata is marked as dropped belong to the `dropped` output. When an array is
+    /// assigned to `.`, every event created from it carries the mark.
+    pub fn into_events_with_status(self) -> impl Iterator<Item = (TargetOutput, Event)> {
+        self.into_events().map(|event| {
+            let output = if event.dropped().is_some() {
+                TargetOutput::Dropped
+            } else {
+                TargetOutput::Primary
+            };
+            (output, event)
+        })
+    }
+}
+
+impl VrlTarget {
+    // Writing to, or deleting, a path is rejected if it is protected itself, or if it would clobber a
+    // protected path nested below it that currently holds a value. For `.` that is every protected
+    // path present on the event.
+    fn check_writable(&self, path: &LookupBuf) -> Result<(), String> {
+        let protected_paths = match &self.protected_paths {
+            Some(protected_paths) => protected_paths,
+            None => return Ok(()),
+        };
+
+        let protected = protected_paths.covering(path).or_else(|| {
+            protected_paths
+                .below(path)
+                .find(|protected| matches!(self.event.get(protected), Ok(Some(_))))
+        });
+
+        match protected {
+            Some(protected) => Err(ProtectedPathError::ReadOnly {
+                path: &display_path(path),
+                protected: &display_path(protected),
+            }
+            .to_string()),
+            None => Ok(()),
+        }
+    }
+}
+
+fn display_path(path: &LookupBuf) -> String {
+    if path.is_root() {
+        ".".to_owned()
+    } else {
+        path.to_string()
+    }
+}
+
+fn insert_tag(metric: &mut Metric, key: String, value: String, tag_limits: Option<&TagLimits>) {
+    match tag_limits {
+        Some(tag_limits) => {
+            metric.insert_tag_limited(key, value, tag_limits);
+        }
+        None => {
+            metric.insert_tag(key, value);
+        }
+    }
+}
+
+/// The tags whose keys start with `prefix`, found without walking the others.
+fn tags_with_prefix<'a>(
+    tags: &'a MetricTags,
+    prefix: &'a str,
+) -> impl Iterator<Item = (&'a String, &'a String)> + 'a {
+    tags.range::<str, _>((Bound::Included(prefix), Bound::Unbounded))
+        .take_while(move |(key, _)| key.starts_with(prefix))
+}
+
+/// Describe a failed lookup of `path` in the fields of a log event. Lookups that ran into a
+/// primitive value point at the segment that couldn't be descended into.
+fn log_path_error(path: &LookupBuf, error: EventError) -> String {
+    let segment = match error {
+        EventError::PrimitiveDescent {
+            ref original_target,
+            ..
+        } => path.len().saturating_sub(original_target.len()),
+        // Only returned when the last segment of the path is looked up in a primitive.
+        EventError::RemovingSelf => path.len().saturating_sub(1),
+        error => return error.to_string(),
+    };
+
+    let rendered = display_path(path);
+    match path.iter().nth(segment) {
+        Some(SegmentBuf::Index(_)) => PathError::NotAnArray {
+            path: rendered,
+            segment,
+        },
+        _ => PathError::NotAMap {
+            path: rendered,
+            segment,
+        },
+    }
+    .to_string()
 }
 
 impl vrl_core::Target for VrlTarget {
     fn insert(&mut self, path: &LookupBuf, value: vrl_core::Value) -> Result<(), String> {
+        if let Some(path_stats) = &self.path_stats {
+            path_stats.record(path, PathOp::Set, true);
+        }
+        self.check_writable(path)?;
+        self.event
+            .insert_limited(path, value, self.tag_limits.as_ref(), self.utf8_handling)?;
+        self.root_removed = false;
+        Ok(())
+    }
+
+    fn get(&self, path: &LookupBuf) -> std::result::Result<Option<vrl_core::Value>, String> {
+        let value = self.event.get(path);
+        if let Some(path_stats) = &self.path_stats {
+            path_stats.record(path, PathOp::Get, matches!(value, Ok(Some(_))));
+        }
+        value
+    }
+
+    fn contains(&self, path: &LookupBuf) -> Result<bool, String> {
+        let contains = self.event.contains(path);
+        if let Some(path_stats) = &self.path_stats {
+            path_stats.record(path, PathOp::Get, matches!(contains, Ok(true)));
+        }
+        contains
+    }
+
+    fn remove(
+        &mut self,
+        path: &LookupBuf,
+        compact: bool,
+    ) -> Result<Option<vrl_core::Value>, String> {
+        if let Some(path_stats) = &self.path_stats {
+            path_stats.record(path, PathOp::Remove, true);
+        }
+        self.check_writable(path)?;
+        let removed = self.event.remove(path, compact)?;
+        if path.is_root() && matches!(self.event, TargetEvent::LogEvent(..)) {
+            self.root_removed = true;
+        }
+        Ok(removed)
+    }
+
+    fn get_metadata(&self, key: &str) -> Result<Option<vrl_core::Value>, String> {
+        self.event.get_metadata(key)
+    }
+
+    fn set_metadata(&mut self, key: &str, value: String) -> Result<(), String> {
+        self.event.set_metadata(key, value)
+    }
+
+    fn remove_metadata(&mut self, key: &str) -> Result<(), String> {
+        self.event.remove_metadata(key)
+    }
+
+    fn get_tags_matching(&self, prefix: &str) -> Result<vrl_core::Value, String> {
+        Ok(self.tags_matching(prefix))
+    }
+
+    fn remove_tags_matching(&mut self, prefix: &str) -> Result<vrl_core::Value, String> {
+        self.delete_tags_matching(prefix)
+    }
+}
+
+impl TargetEvent {
+    fn allowed_paths(&self, op: PathOp) -> Vec<PathDescriptor> {
+        match self {
+            TargetEvent::LogEvent(..) => vec![PathDescriptor {
+                pattern: ".**",
+                kind: None,
+                writable: true,
+            }],
+            TargetEvent::Metric(_) => metric_paths(op),
+        }
+    }
+
+    // Inserts like `Target::insert`, but the tags written to a metric are held to `tag_limits`, and
+    // the strings written to it are decoded with `utf8_handling`.
+    fn insert_limited(
+        &mut self,
+        path: &LookupBuf,
+        value: vrl_core::Value,
+        tag_limits: Option<&TagLimits>,
+        utf8_handling: Utf8Handling,
+    ) -> Result<(), String> {
         match self {
-            VrlTarget::LogEvent(ref mut log, _) => log
-                .insert(path.clone(), value)
+            TargetEvent::LogEvent(ref mut log, _) => log
+                .insert_borrowed(path, value)
                 .map(|_| ())
-                .map_err(|err| err.to_string()),
-            VrlTarget::Metric(ref mut metric) => {
+                .map_err(|err| log_path_error(path, err)),
+            TargetEvent::Metric(ref mut metric) => {
                 if path.is_root() {
-                    return Err(MetricPathError::SetPathError.to_string());
+                    let mut new_metric = metric_from_value(value, utf8_handling)?;
+                    std::mem::swap(new_metric.metadata_mut(), metric.metadata_mut());
+                    if let Some(tag_limits) = tag_limits {
+                        new_metric.enforce_tag_limits(tag_limits);
+                    }
+                    *metric = new_metric;
+                    return Ok(());
                 }
 
                 if let Some(paths) = path.to_alternative_components(MAX_METRIC_PATH_DEPTH).get(0) {
                     match paths.as_slice() {
                         ["tags"] => {
                             let value = value.try_object().map_err(|e| e.to_string())?;
-                            for (field, value) in &value {
-                                metric.insert_tag(
-                                    field.as_str().to_owned(),
-                                    value
-                                        .try_bytes_utf8_lossy()
-                                        .map_err(|e| e.to_string())?
-                                        .into_owned(),
-                                );
+                            // Decode every tag before writing any, so that a rejected tag leaves the
+                            // metric as it was.
+                            let tags = value
+                                .into_iter()
+                                .map(|(field, value)| {
+                                    let tag_path = format!("{}.{}", display_path(path), field);
+                                    utf8_handling
+                                        .decode_value(&tag_path, value)
+                                        .map(|value| (field, value))
+                                })
+                                .collect::<Result<Vec<_>, _>>()?;
+                            for (field, value) in tags {
+                                insert_tag(metric, field, value, tag_limits);
                             }
                             return Ok(());
                         }
                         ["tags", field] => {
-                            let value = value.try_bytes().map_err(|e| e.to_string())?;
-                            metric.insert_tag(
-                                (*field).to_owned(),
-                                String::from_utf8_lossy(&value).into_owned(),
-                            );
+                            let value = utf8_handling.decode_value(&display_path(path), value)?;
+                            insert_tag(metric, (*field).to_owned(), value, tag_limits);
                             return Ok(());
                         }
                         ["name"] => {
-                            let value = value.try_bytes().map_err(|e| e.to_string())?;
-                            metric.series.name.name = String::from_utf8_lossy(&value).into_owned();
+                            let value = utf8_handling.decode_value(&display_path(path), value)?;
+                            metric.series_mut().name.name = value;
                             return Ok(());
                         }
                         ["namespace"] => {
-                            let value = value.try_bytes().map_err(|e| e.to_string())?;
-                            metric.series.name.namespace =
-                                Some(String::from_utf8_lossy(&value).into_owned());
+                            let value = utf8_handling.decode_value(&display_path(path), value)?;
+                            metric.series_mut().name.namespace = Some(value);
                             return Ok(());
                         }
                         ["timestamp"] => {
@@ -105,13 +872,19 @@ impl vrl_core::Target for VrlTarget {
                             return Ok(());
                         }
                         ["kind"] => {
-                            metric.data.kind = MetricKind::try_from(value)?;
+                            metric.data.kind = MetricKind::try_from(value).map_err(|error| {
+                                MetricPathError::InvalidField {
+                                    path: &path.to_string(),
+                                    error: error.to_string(),
+                                }
+                                .to_string()
+                            })?;
                             return Ok(());
                         }
                         _ => {
                             return Err(MetricPathError::InvalidPath {
                                 path: &path.to_string(),
-                                expected: VALID_METRIC_PATHS_SET,
+                                expected: &expected_metric_paths(PathOp::Set),
                             }
                             .to_string())
                         }
@@ -120,20 +893,35 @@ impl vrl_core::Target for VrlTarget {
 
                 Err(MetricPathError::InvalidPath {
                     path: &path.to_string(),
-                    expected: VALID_METRIC_PATHS_SET,
+                    expected: &expected_metric_paths(PathOp::Set),
                 }
                 .to_string())
             }
         }
     }
+}
+
+impl vrl_core::Target for TargetEvent {
+    fn insert(&mut self, path: &LookupBuf, value: vrl_core::Value) -> Result<(), String> {
+        self.insert_limited(path, value, None, Utf8Handling::default())
+    }
 
     fn get(&self, path: &LookupBuf) -> std::result::Result<Option<vrl_core::Value>, String> {
         match self {
-            VrlTarget::LogEvent(log, _) => log
+            TargetEvent::LogEvent(log, _) => log
                 .get(path)
-                .map(|val| val.map(|val| val.clone().into()))
-                .map_err(|err| err.to_string()),
-            VrlTarget::Metric(metric) => {
+                .map(|val| val.map(Into::into))
+                .map_err(|err| log_path_error(path, err)),
+            TargetEvent::Metric(metric) => {
+                // `.` of a metric is the object below, which remap programs depend on, so it
+                // mustn't change. Assigning it back to `.` gives the same metric, see
+                // `metric_from_value`.
+                //
+                // { "name": .., "namespace": .., "timestamp": .., "kind": "absolute" | "incremental",
+                //   "tags": { .. }, "type": "counter" | .., "value": { "counter": { "value": .. } } }
+                //
+                // `namespace`, `timestamp` and `tags` are left out when the metric has none, and
+                // `value` has the shape of `From<MetricValue> for vrl_core::Value`.
                 if path.is_root() {
                     let mut map = BTreeMap::<String, vrl_core::Value>::new();
                     map.insert("name".to_string(), metric.series.name.name.clone().into());
@@ -153,11 +941,17 @@ impl vrl_core::Target for VrlTarget {
                                 .into(),
                         );
                     }
-                    map.insert("type".to_string(), metric.data.value.clone().into());
+                    map.insert("type".to_string(), metric.data.value.as_name().into());
+                    map.insert("value".to_string(), metric.data.value.clone().into());
 
                     return Ok(Some(map.into()));
                 }
 
+                if let Some(value_path) = metric_value_path(path) {
+                    let value = vrl_core::Value::from(metric.data.value.clone());
+                    return Ok(value.get_by_path(&value_path).cloned());
+                }
+
                 for paths in path.to_alternative_components(MAX_METRIC_PATH_DEPTH) {
                     match paths.as_slice() {
                         ["name"] => return Ok(Some(metric.name().to_string().into())),
@@ -181,11 +975,12 @@ impl vrl_core::Target for VrlTarget {
                             Some(value) => return Ok(Some(value.into())),
                             None => continue,
                         },
-                        ["type"] => return Ok(Some(metric.data.value.clone().into())),
+                        ["type"] => return Ok(Some(metric.data.value.as_name().into())),
+                        ["value"] => return Ok(Some(metric.data.value.clone().into())),
                         _ => {
                             return Err(MetricPathError::InvalidPath {
                                 path: &path.to_string(),
-                                expected: VALID_METRIC_PATHS_GET,
+                                expected: &expected_metric_paths(PathOp::Get),
                             }
                             .to_string())
                         }
@@ -198,38 +993,88 @@ impl vrl_core::Target for VrlTarget {
         }
     }
 
+    // Answers like `get`, but without cloning the value, or building one for `.` and `.tags` of a
+    // metric.
+    fn contains(&self, path: &LookupBuf) -> Result<bool, String> {
+        match self {
+            TargetEvent::LogEvent(log, _) => log
+                .get(path)
+                .map(|val| val.is_some())
+                .map_err(|err| log_path_error(path, err)),
+            TargetEvent::Metric(metric) => {
+                if path.is_root() {
+                    return Ok(true);
+                }
+
+                if let Some(value_path) = metric_value_path(path) {
+                    let value = vrl_core::Value::from(metric.data.value.clone());
+                    return Ok(value.get_by_path(&value_path).is_some());
+                }
+
+                for paths in path.to_alternative_components(MAX_METRIC_PATH_DEPTH) {
+                    match paths.as_slice() {
+                        ["name"] | ["kind"] | ["type"] | ["value"] => return Ok(true),
+                        ["namespace"] if metric.series.name.namespace.is_some() => return Ok(true),
+                        ["timestamp"] if metric.data.timestamp.is_some() => return Ok(true),
+                        ["tags"] => return Ok(metric.tags().is_some()),
+                        ["tags", field]
+                            if metric
+                                .tags()
+                                .map_or(false, |tags| tags.contains_key(*field)) =>
+                        {
+                            return Ok(true)
+                        }
+                        ["namespace"] | ["timestamp"] | ["tags", _] => continue,
+                        _ => {
+                            return Err(MetricPathError::InvalidPath {
+                                path: &path.to_string(),
+                                expected: &expected_metric_paths(PathOp::Get),
+                            }
+                            .to_string())
+                        }
+                    }
+                }
+                Ok(false)
+            }
+        }
+    }
+
     fn remove(
         &mut self,
         path: &LookupBuf,
         compact: bool,
     ) -> Result<Option<vrl_core::Value>, String> {
         match self {
-            VrlTarget::LogEvent(ref mut log, _) => {
+            TargetEvent::LogEvent(ref mut log, _) => {
                 if path.is_root() {
-                    Ok(Some({
-                        let mut map = Value::Map(BTreeMap::new());
-                        std::mem::swap(log, &mut map);
-                        map.into()
-                    }))
+                    // The previous contents are returned as the event, or events, they would have
+                    // turned into.
+                    let removed = match std::mem::replace(log, Value::Map(BTreeMap::new())) {
+                        value @ (Value::Map(_) | Value::Array(_)) => value,
+                        value => {
+                            let mut log = LogEvent::default();
+                            log.insert(log_schema().message_key(), value);
+                            Value::Map(log.into_parts().0)
+                        }
+                    };
+                    Ok(Some(removed.into()))
                 } else {
                     log.remove(path, compact)
                         .map(|val| val.map(Into::into))
-                        .map_err(|err| err.to_string())
+                        .map_err(|err| log_path_error(path, err))
                 }
             }
-            VrlTarget::Metric(ref mut metric) => {
+            TargetEvent::Metric(ref mut metric) => {
                 if path.is_root() {
                     return Err(MetricPathError::SetPathError.to_string());
                 }
 
                 if let Some(paths) = path.to_alternative_components(MAX_METRIC_PATH_DEPTH).get(0) {
                     match paths.as_slice() {
-                        ["namespace"] => {
-                            return Ok(metric.series.name.namespace.take().map(Into::into))
-                        }
+                        ["namespace"] => return Ok(metric.take_namespace().map(Into::into)),
                         ["timestamp"] => return Ok(metric.data.timestamp.take().map(Into::into)),
                         ["tags"] => {
-                            return Ok(metric.series.tags.take().map(|map| {
+                            return Ok(metric.take_tags().map(|map| {
                                 map.into_iter()
                                     .map(|(k, v)| (k, v.into()))
                                     .collect::<vrl_core::Value>()
@@ -239,7 +1084,7 @@ impl vrl_core::Target for VrlTarget {
                         _ => {
                             return Err(MetricPathError::InvalidPath {
                                 path: &path.to_string(),
-                                expected: VALID_METRIC_PATHS_SET,
+                                expected: &expected_metric_paths(PathOp::Set),
                             }
                             .to_string())
                         }
@@ -253,8 +1098,8 @@ impl vrl_core::Target for VrlTarget {
 
     fn get_metadata(&self, key: &str) -> Result<Option<vrl_core::Value>, String> {
         let metadata = match self {
-            VrlTarget::LogEvent(_, metadata) => metadata,
-            VrlTarget::Metric(metric) => metric.metadata(),
+            TargetEvent::LogEvent(_, metadata) => metadata,
+            TargetEvent::Metric(metric) => metric.metadata(),
         };
 
         match key {
@@ -266,14 +1111,15 @@ impl vrl_core::Target for VrlTarget {
                 .splunk_hec_token()
                 .as_ref()
                 .map(|token| vrl_core::Value::from(token.to_string()))),
+            "dropped" => Ok(metadata.dropped().map(vrl_core::Value::from)),
             _ => Err(format!("key {} not available", key)),
         }
     }
 
     fn set_metadata(&mut self, key: &str, value: String) -> Result<(), String> {
         let metadata = match self {
-            VrlTarget::LogEvent(_, metadata) => metadata,
-            VrlTarget::Metric(metric) => metric.metadata_mut(),
+            TargetEvent::LogEvent(_, metadata) => metadata,
+            TargetEvent::Metric(metric) => metric.metadata_mut(),
         };
 
         match key {
@@ -285,14 +1131,18 @@ impl vrl_core::Target for VrlTarget {
                 metadata.set_splunk_hec_token(Some(Arc::from(value.as_str())));
                 Ok(())
             }
+            "dropped" => {
+                metadata.set_dropped(value);
+                Ok(())
+            }
             _ => Err(format!("key {} not available", key)),
         }
     }
 
     fn remove_metadata(&mut self, key: &str) -> Result<(), String> {
         let metadata = match self {
-            VrlTarget::LogEvent(_, metadata) => metadata,
-            VrlTarget::Metric(metric) => metric.metadata_mut(),
+            TargetEvent::LogEvent(_, metadata) => metadata,
+            TargetEvent::Metric(metric) => metric.metadata_mut(),
         };
 
         match key {
@@ -304,6 +1154,10 @@ impl vrl_core::Target for VrlTarget {
                 metadata.set_splunk_hec_token(None);
                 Ok(())
             }
+            "dropped" => {
+                metadata.take_dropped();
+                Ok(())
+            }
             _ => Err(format!("key {} not available", key)),
         }
     }
@@ -319,36 +1173,208 @@ impl From<Event> for VrlTarget {
 // * In the common case, where `.` is a map, just create an event using it as the event fields.
 // * If `.` is an array, map over all of the values to create log events:
 //   * If an element is an object, create an event using that as fields.
-//   * If an element is anything else, assign to the `message` key.
-// * If `.` is anything else, assign to the `message` key.
-fn value_into_log_events(value: Value, metadata: EventMetadata) -> impl Iterator<Item = Event> {
+//   * If an element is anything else, assign to the `message_key` path.
+// * If `.` is anything else, assign to the `message_key` path.
+//
+// The `message_key` is inserted as a path, so nested schema keys such as `event.message` work.
+fn value_into_log_events(
+    value: Value,
+    metadata: EventMetadata,
+    message_key: &str,
+) -> impl Iterator<Item = Event> + '_ {
     match value {
-        Value::Map(object) => Box::new(std::iter::once(Event::from(LogEvent::from_parts(
-            object, metadata,
-        )))) as Box<dyn Iterator<Item = Event>>,
-        Value::Array(values) => Box::new(values.into_iter().map(move |v| match v {
-            Value::Map(object) => Event::from(LogEvent::from_parts(object, metadata.clone())),
-            v => {
-                let mut log = LogEvent::new_with_metadata(metadata.clone());
-                log.insert(log_schema().message_key(), v);
-                Event::from(log)
-            }
-        })) as Box<dyn Iterator<Item = Event>>,
-        v => {
-            let mut log = LogEvent::new_with_metadata(metadata);
-            log.insert(log_schema().message_key(), v);
-            Box::new(std::iter::once(Event::from(log))) as Box<dyn Iterator<Item = Event>>
-        }
+        Value::Array(values) => Box::new(SplitLogEvents {
+            values: values.into_iter(),
+            metadata: Some(metadata),
+            message_key,
+        }) as Box<dyn Iterator<Item = Event>>,
+        value => Box::new(std::iter::once(log_event_from_value(
+            value,
+            metadata,
+            message_key,
+        ))) as Box<dyn Iterator<Item = Event>>,
     }
 }
 
-#[derive(Debug, Snafu)]
-enum MetricPathError<'a> {
+/// The log events an array assigned to `.` is split into. Each one is built from its element of
+/// the array as the iterator is polled, so that a large split takes the memory of the array and of
+/// the events not yet dropped, rather than that of all of its events at once.
+struct SplitLogEvents<'a> {
+    values: std::vec::IntoIter<Value>,
+    // Copied into every event but the last one, which takes it.
+    metadata: Option<EventMetadata>,
+    message_key: &'a str,
+}
+
+impl Iterator for SplitLogEvents<'_> {
+    type Item = Event;
+
+    fn next(&mut self) -> Option<Event> {
+        let value = self.values.next()?;
+        let metadata = if self.values.as_slice().is_empty() {
+            self.metadata.take()?
+        } else {
+            self.metadata.as_ref()?.clone()
+        };
+        Some(log_event_from_value(value, metadata, self.message_key))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.values.size_hint()
+    }
+}
+
+impl ExactSizeIterator for SplitLogEvents<'_> {}
+
+/// An object becomes the fields of the event, any other value its message.
+fn log_event_from_value(value: Value, metadata: EventMetadata, message_key: &str) -> Event {
+    match value {
+        Value::Map(object) => Event::from(LogEvent::from_parts(object, metadata)),
+        value => {
+            let mut log = LogEvent::new_with_metadata(metadata);
+            log.insert(message_key, value);
+            Event::from(log)
+        }
+    }
+}
+
+// Build a metric from an object assigned to `.`. The object has the same shape as the one returned
+// when reading `.`, plus a `value` holding the typed metric value in the shape VRL sees it in, e.g.
+// `{ "counter": { "value": 1.0 } }`. The `type` key is optional, but must match the value if
+// it is present.
+fn metric_from_value(
+    value: vrl_core::Value,
+    utf8_handling: Utf8Handling,
+) -> Result<Metric, String> {
+    let mut object = value.try_object().map_err(|e| e.to_string())?;
+
+    if let Some(key) = object
+        .keys()
+        .find(|key| !metric_root_keys().any(|valid| valid == key.as_str()))
+    {
+        return Err(MetricPathError::InvalidRootKey {
+            key,
+            expected: &metric_root_keys().collect::<Vec<_>>().join(", "),
+        }
+        .to_string());
+    }
+
+    let name = utf8_handling.decode_value("name", take_required(&mut object, "name")?)?;
+    let kind = MetricKind::try_from(take_required(&mut object, "kind")?).map_err(|error| {
+        MetricPathError::InvalidField {
+            path: "kind",
+            error: error.to_string(),
+        }
+        .to_string()
+    })?;
+    let value = MetricValue::try_from(take_required(&mut object, "value")?).map_err(|e| {
+        MetricPathError::InvalidValue {
+            reason: e.to_string(),
+        }
+        .to_string()
+    })?;
+
+    if let Some(metric_type) = take_optional(&mut object, "type") {
+        let metric_type = metric_type
+            .try_bytes_utf8_lossy()
+            .map_err(|e| e.to_string())?
+            .into_owned();
+        if metric_type != value.as_name() {
+            return Err(MetricPathError::TypeMismatch {
+                metric_type: &metric_type,
+                value_type: value.as_name(),
+            }
+            .to_string());
+        }
+    }
+
+    let namespace = take_optional(&mut object, "namespace")
+        .map(|namespace| utf8_handling.decode_value("namespace", namespace))
+        .transpose()?;
+    let timestamp = take_optional(&mut object, "timestamp")
+        .map(|timestamp| timestamp.try_timestamp().map_err(|e| e.to_string()))
+        .transpose()?;
+    let tags = take_optional(&mut object, "tags")
+        .map(|tags| {
+            tags.try_object()
+                .map_err(|e| e.to_string())?
+                .into_iter()
+                .map(|(tag, value)| {
+                    utf8_handling
+                        .decode_value(&format!("tags.{}", tag), value)
+                        .map(|value| (tag, value))
+                })
+                .collect::<Result<MetricTags, String>>()
+        })
+        .transpose()?;
+
+    Ok(Metric::new(name, kind, value)
+        .with_namespace(namespace)
+        .with_timestamp(timestamp)
+        .with_tags(tags))
+}
+
+fn take_required(
+    object: &mut BTreeMap<String, vrl_core::Value>,
+    key: &str,
+) -> Result<vrl_core::Value, String> {
+    take_optional(object, key).ok_or_else(|| MetricPathError::MissingRootKey { key }.to_string())
+}
+
+// A key set to `null` is treated the same as a missing key.
+fn take_optional(
+    object: &mut BTreeMap<String, vrl_core::Value>,
+    key: &str,
+) -> Option<vrl_core::Value> {
+    object.remove(key).filter(|value| !value.is_null())
+}
+
+#[derive(Debug, Snafu)]
+enum ProtectedPathError<'a> {
+    #[snafu(display("cannot modify read-only path {}: protected by {}", path, protected))]
+    ReadOnly { path: &'a str, protected: &'a str },
+}
+
+#[derive(Debug, Snafu)]
+enum MetricPathError<'a> {
     #[snafu(display("cannot set root path"))]
     SetPathError,
 
     #[snafu(display("invalid path {}: expected one of {}", path, expected))]
     InvalidPath { path: &'a str, expected: &'a str },
+
+    #[snafu(display("missing required key {} when assigning a metric to root", key))]
+    MissingRootKey { key: &'a str },
+
+    #[snafu(display(
+        "invalid key {} when assigning a metric to root: expected one of {}",
+        key,
+        expected
+    ))]
+    InvalidRootKey { key: &'a str, expected: &'a str },
+
+    #[snafu(display("cannot set {}: {}", path, error))]
+    InvalidField { path: &'a str, error: String },
+
+    #[snafu(display("invalid metric value: {}", reason))]
+    InvalidValue { reason: String },
+
+    #[snafu(display("invalid UTF-8 in {} at byte {}: {}", path, offset, preview))]
+    InvalidUtf8 {
+        path: &'a str,
+        offset: usize,
+        preview: &'a str,
+    },
+
+    #[snafu(display(
+        "metric type {} does not match value of type {}",
+        metric_type,
+        value_type
+    ))]
+    TypeMismatch {
+        metric_type: &'a str,
+        value_type: &'a str,
+    },
 }
 
 #[cfg(test)]
@@ -358,10 +1384,7 @@ mod test {
     use shared::btreemap;
     use vrl_core::{self, Target};
 
-    use super::{
-        super::{metric::MetricTags, MetricValue},
-        *,
-    };
+    use super::*;
 
     #[test]
     fn log_get() {
@@ -414,6 +1437,35 @@ mod test {
         }
     }
 
+    #[test]
+    fn log_get_root_shares_bytes() {
+        let message = bytes::Bytes::from("x".repeat(1024));
+        let mut log = LogEvent::default();
+        log.insert("message", message.clone());
+        log.insert("nested.message", message.clone());
+
+        let root = vrl_core::Value::from(&log);
+        assert_eq!(
+            vrl_core::Target::get(&VrlTarget::new(Event::Log(log)), &LookupBuf::root()),
+            Ok(Some(root.clone()))
+        );
+
+        let shared = |value: &vrl_core::Value| match value {
+            vrl_core::Value::Bytes(bytes) => bytes.as_ptr() == message.as_ptr(),
+            value => panic!("unexpected value {:?}", value),
+        };
+        match &root {
+            vrl_core::Value::Object(fields) => {
+                assert!(shared(&fields["message"]));
+                match &fields["nested"] {
+                    vrl_core::Value::Object(nested) => assert!(shared(&nested["message"])),
+                    value => panic!("unexpected value {:?}", value),
+                }
+            }
+            value => panic!("unexpected value {:?}", value),
+        }
+    }
+
     #[allow(clippy::too_many_lines)]
     #[test]
     fn log_insert() {
@@ -620,6 +1672,182 @@ mod test {
         }
     }
 
+    #[test]
+    fn log_remove_compacts_like_remove_prune() {
+        type Fields = BTreeMap<String, Value>;
+        let cases: Vec<(Fields, &str, Fields)> = vec![
+            // Nested maps.
+            (
+                btreemap! { "a" => btreemap! { "b" => btreemap! { "c" => 1 }, "d" => 2 } },
+                "a.b.c",
+                btreemap! { "a" => btreemap! { "d" => 2 } },
+            ),
+            (
+                btreemap! { "a" => btreemap! { "b" => btreemap! { "c" => 1 } }, "e" => 3 },
+                "a.b.c",
+                btreemap! { "e" => 3 },
+            ),
+            // Arrays of maps.
+            (
+                btreemap! { "a" => vec![Value::from(btreemap! { "x" => 1 }), Value::from(2)] },
+                "a[0].x",
+                btreemap! { "a" => vec![2] },
+            ),
+            (
+                btreemap! { "a" => vec![Value::from(btreemap! { "x" => 1, "y" => 2 })] },
+                "a[0].x",
+                btreemap! { "a" => vec![Value::from(btreemap! { "y" => 2 })] },
+            ),
+            (
+                btreemap! { "a" => vec![Value::from(btreemap! { "x" => 1 })] },
+                "a[0].x",
+                btreemap! {},
+            ),
+            // Arrays of scalars.
+            (
+                btreemap! { "a" => vec![1, 2, 3] },
+                "a[1]",
+                btreemap! { "a" => vec![1, 3] },
+            ),
+            (
+                btreemap! { "a" => vec![1, 2, 3] },
+                "a[2]",
+                btreemap! { "a" => vec![1, 2] },
+            ),
+            (btreemap! { "a" => vec![1] }, "a[0]", btreemap! {}),
+            // Root children.
+            (
+                btreemap! { "a" => btreemap! { "b" => 1 }, "c" => 2 },
+                "a",
+                btreemap! { "c" => 2 },
+            ),
+            (btreemap! { "a" => 1 }, "a", btreemap! {}),
+            // Nothing off the path is compacted, nor anything when there is nothing to remove.
+            (
+                btreemap! {
+                    "a" => btreemap! { "b" => 1, "c" => Value::Array(vec![]) },
+                    "d" => Value::Null,
+                },
+                "a.b",
+                btreemap! { "a" => btreemap! { "c" => Value::Array(vec![]) }, "d" => Value::Null },
+            ),
+            (
+                btreemap! { "a" => btreemap! {} },
+                "a.b",
+                btreemap! { "a" => btreemap! {} },
+            ),
+            (
+                btreemap! { "a" => vec![Value::from(btreemap! {})] },
+                "a[1]",
+                btreemap! { "a" => vec![Value::from(btreemap! {})] },
+            ),
+        ];
+
+        for (fields, path, expect) in cases {
+            let mut log = LogEvent::from(fields.clone());
+            let removed = log.remove_prune(path, true);
+
+            let mut target = VrlTarget::new(Event::Log(LogEvent::from(fields)));
+            let lookup = LookupBuf::from_str(path).unwrap();
+            assert_eq!(
+                vrl_core::Target::remove(&mut target, &lookup, true),
+                Ok(removed.map(Into::into)),
+                "{}",
+                path
+            );
+            assert_eq!(
+                vrl_core::Target::get(&target, &LookupBuf::root()),
+                Ok(Some(vrl_core::Value::from(&log))),
+                "{}",
+                path
+            );
+            assert_eq!(log.as_map(), &expect, "{}", path);
+        }
+    }
+
+    #[test]
+    fn log_remove_from_primitive() {
+        let mut target = VrlTarget::new(Event::Log(LogEvent::from(btreemap! { "foo" => "bar" })));
+
+        let cases = [("foo.bar", false), ("foo.bar.baz", false), ("foo[0]", true)];
+        for (path, index) in cases {
+            let path = LookupBuf::from_str(path).unwrap();
+            let error = if index {
+                PathError::NotAnArray {
+                    path: display_path(&path),
+                    segment: 1,
+                }
+            } else {
+                PathError::NotAMap {
+                    path: display_path(&path),
+                    segment: 1,
+                }
+            };
+
+            assert_eq!(
+                vrl_core::Target::remove(&mut target, &path, false),
+                Err(error.to_string())
+            );
+        }
+    }
+
+    #[test]
+    fn log_remove_root() {
+        let root = LookupBuf::root();
+        let metadata = EventMetadata::default();
+        let log = LogEvent::from_parts(btreemap! { "foo" => "bar" }, metadata.clone());
+
+        let mut target = VrlTarget::new(Event::Log(log));
+        assert_eq!(
+            vrl_core::Target::remove(&mut target, &root, false),
+            Ok(Some(btreemap! { "foo" => "bar" }.into()))
+        );
+        assert_eq!(
+            vrl_core::Target::remove(&mut target, &root, false),
+            Ok(Some(btreemap! {}.into()))
+        );
+        assert_eq!(target.clone().into_events().count(), 0);
+
+        // Writing anything after `del(.)` makes the event come back.
+        let path = LookupBuf::from_str("baz").unwrap();
+        vrl_core::Target::insert(&mut target, &path, "qux".into()).unwrap();
+        assert_eq!(
+            target.into_events().collect::<Vec<_>>(),
+            vec![Event::Log(LogEvent::from_parts(
+                btreemap! { "baz" => "qux" },
+                metadata
+            ))]
+        );
+
+        let cases = vec![
+            (
+                vrl_core::Value::from(vec![
+                    vrl_core::Value::from(1),
+                    vrl_core::Value::from(btreemap! {"foo" => "bar"}),
+                ]),
+                vrl_core::Value::from(vec![
+                    vrl_core::Value::from(1),
+                    vrl_core::Value::from(btreemap! {"foo" => "bar"}),
+                ]),
+            ),
+            (
+                vrl_core::Value::from("foo"),
+                vrl_core::Value::from(btreemap! {"message" => "foo"}),
+            ),
+        ];
+
+        for (value, expect) in cases {
+            let mut target = VrlTarget::new(Event::Log(LogEvent::default()));
+            vrl_core::Target::insert(&mut target, &root, value).unwrap();
+
+            assert_eq!(
+                vrl_core::Target::remove(&mut target, &root, true),
+                Ok(Some(expect))
+            );
+            assert_eq!(target.into_events().count(), 0);
+        }
+    }
+
     #[test]
     fn log_into_events() {
         use shared::btreemap;
@@ -671,6 +1899,132 @@ mod test {
         }
     }
 
+    #[test]
+    fn log_insert_root_map_keeps_metadata() {
+        let mut metadata = EventMetadata::default();
+        metadata.set_datadog_api_key(Some(Arc::from("my_api_key")));
+        let mut target = VrlTarget::new(Event::Log(LogEvent::new_with_metadata(metadata.clone())));
+
+        let value = vrl_core::Value::from(btreemap! {"foo" => "bar"});
+        vrl_core::Target::insert(&mut target, &LookupBuf::root(), value).unwrap();
+
+        let events = target.into_events().collect::<Vec<_>>();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].metadata(), &metadata);
+        assert_eq!(events[0].as_log()["foo"], Value::from("bar"));
+    }
+
+    #[test]
+    fn log_into_events_with_status() {
+        let mut target = VrlTarget::new(Event::Log(LogEvent::default()));
+        let value = vrl_core::Value::from(vec![
+            vrl_core::Value::from(btreemap! {"foo" => "bar"}),
+            vrl_core::Value::from(1),
+        ]);
+        vrl_core::Target::insert(&mut target, &LookupBuf::root(), value).unwrap();
+        let primary = target.clone().into_events_with_status().collect::<Vec<_>>();
+        assert!(primary
+            .iter()
+            .all(|(output, event)| *output == TargetOutput::Primary && event.dropped().is_none()));
+
+        vrl_core::Target::set_metadata(&mut target, "dropped", "invalid record".into()).unwrap();
+        assert_eq!(
+            vrl_core::Target::get_metadata(&target, "dropped"),
+            Ok(Some(vrl_core::Value::from("invalid record")))
+        );
+
+        let events = target.into_events_with_status().collect::<Vec<_>>();
+        assert_eq!(events.len(), 2);
+        for (output, event) in events {
+            assert_eq!(output, TargetOutput::Dropped);
+            assert_eq!(event.dropped(), Some("invalid record"));
+            // The mark stays out of the event fields.
+            assert!(!event.as_log().contains("dropped"));
+        }
+    }
+
+    #[test]
+    fn log_fanout() {
+        let mut target = VrlTarget::new(Event::Log(LogEvent::default()));
+        assert_eq!(target.fanout(), None);
+        assert_eq!(target.truncate_fanout(0), None);
+        assert_eq!(target.clone().into_events().count(), 1);
+
+        let value =
+            vrl_core::Value::from((0_i64..5).map(vrl_core::Value::from).collect::<Vec<_>>());
+        vrl_core::Target::insert(&mut target, &LookupBuf::root(), value).unwrap();
+        assert_eq!(target.fanout(), Some(5));
+        assert_eq!(target.truncate_fanout(5), None);
+        assert_eq!(target.truncate_fanout(3), Some(5));
+        assert_eq!(target.fanout(), Some(3));
+
+        let events = target.into_events().collect::<Vec<_>>();
+        assert_eq!(events.len(), 3);
+        assert_eq!(events[2].as_log()["message"], Value::Integer(2));
+
+        let metric = Metric::new(
+            "name",
+            MetricKind::Absolute,
+            MetricValue::Counter { value: 1.23 },
+        );
+        let mut target = VrlTarget::new(Event::Metric(metric));
+        assert_eq!(target.fanout(), None);
+        assert_eq!(target.truncate_fanout(0), None);
+    }
+
+    #[test]
+    fn log_into_events_builds_split_events_lazily() {
+        let key: Arc<str> = Arc::from("secret");
+        let mut metadata = EventMetadata::default();
+        metadata.set_datadog_api_key(Some(Arc::clone(&key)));
+        let mut target = VrlTarget::new(Event::Log(LogEvent::new_with_metadata(metadata)));
+        let value =
+            vrl_core::Value::from((0_i64..3).map(vrl_core::Value::from).collect::<Vec<_>>());
+        vrl_core::Target::insert(&mut target, &LookupBuf::root(), value).unwrap();
+
+        // Every event holds the key through its metadata, so the references to it tell how many
+        // events exist at once.
+        let mut events = target.into_events();
+        assert_eq!(Arc::strong_count(&key), 2);
+        for i in 0..2 {
+            let event = events.next().unwrap();
+            assert_eq!(event.as_log()["message"], Value::Integer(i));
+            assert_eq!(Arc::strong_count(&key), 3);
+        }
+        // The last event takes the metadata rather than copying it.
+        let last = events.next().unwrap();
+        assert_eq!(last.as_log()["message"], Value::Integer(2));
+        assert_eq!(Arc::strong_count(&key), 2);
+        assert!(events.next().is_none());
+    }
+
+    #[test]
+    fn log_into_events_nested_message_key() {
+        use shared::btreemap;
+
+        let value = vrl_core::Value::from(vec![
+            vrl_core::Value::from(1),
+            vrl_core::Value::from(btreemap! {"foo" => "bar"}),
+            vrl_core::Value::from("2"),
+        ]);
+        let metadata = EventMetadata::default();
+
+        let events =
+            value_into_log_events(value, metadata.clone(), "event.message").collect::<Vec<_>>();
+
+        assert_eq!(
+            events,
+            vec![
+                btreemap! {"event" => btreemap! {"message" => 1}},
+                btreemap! {"foo" => "bar"},
+                btreemap! {"event" => btreemap! {"message" => "2"}},
+            ]
+            .into_iter()
+            .map(|v| Event::Log(LogEvent::from_parts(v, metadata.clone())))
+            .collect::<Vec<_>>()
+        );
+    }
+
     #[test]
     fn metric_all_fields() {
         let metric = Metric::new(
@@ -697,11 +2051,132 @@ mod test {
                     "tags" => btreemap! { "tig" => "tog" },
                     "kind" => "absolute",
                     "type" => "counter",
+                    "value" => btreemap! {
+                        "counter" => btreemap! { "value" => 1.23 },
+                    },
                 }
                 .into()
             )),
             target.get(&LookupBuf::root())
         );
+        assert_eq!(
+            Ok(Some(
+                btreemap! { "counter" => btreemap! { "value" => 1.23 } }.into()
+            )),
+            target.get(&LookupBuf::from_str("value").unwrap())
+        );
+    }
+
+    // The object `.` of a metric reads as is relied on by remap programs, see `TargetEvent::get`.
+    #[test]
+    fn metric_root_schema() {
+        let timestamp = Utc.ymd(2020, 12, 10).and_hms(12, 0, 0);
+        let metric = Metric::new(
+            "requests",
+            MetricKind::Incremental,
+            MetricValue::Gauge { value: 2.5 },
+        )
+        .with_namespace(Some("app"))
+        .with_timestamp(Some(timestamp))
+        .with_tags(Some({
+            let mut map = MetricTags::new();
+            map.insert("host".to_string(), "localhost".to_string());
+            map
+        }));
+        assert_eq!(
+            VrlTarget::new(Event::Metric(metric)).get(&LookupBuf::root()),
+            Ok(Some(
+                btreemap! {
+                    "name" => "requests",
+                    "namespace" => "app",
+                    "timestamp" => timestamp,
+                    "kind" => "incremental",
+                    "tags" => btreemap! { "host" => "localhost" },
+                    "type" => "gauge",
+                    "value" => btreemap! { "gauge" => btreemap! { "value" => 2.5 } },
+                }
+                .into()
+            ))
+        );
+
+        let metric = Metric::new(
+            "requests",
+            MetricKind::Absolute,
+            MetricValue::Counter { value: 1.0 },
+        );
+        assert_eq!(
+            VrlTarget::new(Event::Metric(metric)).get(&LookupBuf::root()),
+            Ok(Some(
+                btreemap! {
+                    "name" => "requests",
+                    "kind" => "absolute",
+                    "type" => "counter",
+                    "value" => btreemap! { "counter" => btreemap! { "value" => 1.0 } },
+                }
+                .into()
+            ))
+        );
+    }
+
+    #[test]
+    fn metric_root_round_trips() {
+        use crate::{event::metric::StatisticKind, metrics::AgentDDSketch};
+
+        let values = vec![
+            MetricValue::Counter { value: 1.5 },
+            MetricValue::Gauge { value: -2.0 },
+            MetricValue::Set {
+                values: vec!["a".to_owned(), "b".to_owned()].into_iter().collect(),
+            },
+            MetricValue::Distribution {
+                samples: crate::samples![1.5 => 2, 3.0 => 1],
+                statistic: StatisticKind::Summary,
+            },
+            MetricValue::AggregatedHistogram {
+                buckets: crate::buckets![1.0 => 3, 2.0 => 5],
+                count: 8,
+                sum: 9.5,
+            },
+            MetricValue::AggregatedSummary {
+                quantiles: crate::quantiles![0.5 => 1.0, 0.99 => 3.0],
+                count: 10,
+                sum: 12.0,
+            },
+            AgentDDSketch::from_raw(3, 1.0, 4.0, 6.0, 2.0, &[-2, 0, 5], &[1, 1, 1])
+                .unwrap()
+                .into(),
+        ];
+
+        for value in values {
+            let name = value.as_name();
+            let metric = Metric::new("requests", MetricKind::Absolute, value.clone())
+                .with_namespace(Some("app"))
+                .with_timestamp(Some(Utc.ymd(2020, 12, 10).and_hms(12, 0, 0)));
+            let root = VrlTarget::new(Event::Metric(metric.clone()))
+                .get(&LookupBuf::root())
+                .unwrap()
+                .unwrap();
+            assert_eq!(
+                root.as_object().unwrap()["value"],
+                vrl_core::Value::from(value),
+                "{}",
+                name
+            );
+
+            // `. = .` leaves the metric as it was.
+            let mut target = VrlTarget::new(Event::Metric(Metric::new(
+                "other",
+                MetricKind::Incremental,
+                MetricValue::Counter { value: 0.0 },
+            )));
+            assert_eq!(target.insert(&LookupBuf::root(), root), Ok(()), "{}", name);
+            assert_eq!(
+                target.into_events().collect::<Vec<_>>(),
+                vec![Event::Metric(metric)],
+                "{}",
+                name
+            );
+        }
     }
 
     #[test]
@@ -771,6 +2246,7 @@ mod test {
             ".kind",
             ".tags",
             ".type",
+            ".value",
         ];
 
         let validpaths_set = vec![".name", ".namespace", ".timestamp", ".kind", ".tags"];
@@ -809,4 +2285,1066 @@ mod test {
             target.get(&LookupBuf::from_str("tags.foo.flork").unwrap())
         );
     }
+
+    #[test]
+    fn expected_metric_paths_keep_their_wording() {
+        assert_eq!(
+            expected_metric_paths(PathOp::Get),
+            ".name, .namespace, .timestamp, .kind, .tags, .type, .value"
+        );
+        assert_eq!(
+            expected_metric_paths(PathOp::Set),
+            ".name, .namespace, .timestamp, .kind, .tags"
+        );
+        assert_eq!(
+            expected_metric_paths(PathOp::Remove),
+            ".name, .namespace, .timestamp, .kind, .tags"
+        );
+    }
+
+    #[test]
+    fn allowed_paths() {
+        let target = VrlTarget::new(Event::Log(LogEvent::default()));
+        for op in [PathOp::Get, PathOp::Set, PathOp::Remove] {
+            assert_eq!(
+                target.allowed_paths(op),
+                vec![PathDescriptor {
+                    pattern: ".**",
+                    kind: None,
+                    writable: true,
+                }]
+            );
+        }
+
+        let metric = Metric::new(
+            "name",
+            MetricKind::Absolute,
+            MetricValue::Counter { value: 1.23 },
+        );
+        let target = VrlTarget::new(Event::Metric(metric));
+        let patterns = |op| {
+            target
+                .allowed_paths(op)
+                .into_iter()
+                .map(|path| path.pattern)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            patterns(PathOp::Get),
+            vec![
+                ".",
+                ".name",
+                ".namespace",
+                ".timestamp",
+                ".kind",
+                ".tags",
+                ".tags.*",
+                ".type",
+                ".value",
+            ]
+        );
+        assert_eq!(
+            patterns(PathOp::Set),
+            vec![
+                ".",
+                ".name",
+                ".namespace",
+                ".timestamp",
+                ".kind",
+                ".tags",
+                ".tags.*"
+            ]
+        );
+        assert_eq!(
+            patterns(PathOp::Remove),
+            vec![".namespace", ".timestamp", ".tags", ".tags.*"]
+        );
+
+        let kind = target
+            .allowed_paths(PathOp::Get)
+            .into_iter()
+            .find(|path| path.pattern == ".type")
+            .unwrap();
+        assert_eq!(kind.kind, Some(ValueKind::Map));
+        assert!(!kind.writable);
+    }
+
+    #[test]
+    fn metric_contains() {
+        let metric = Metric::new(
+            "name",
+            MetricKind::Absolute,
+            MetricValue::Counter { value: 1.23 },
+        );
+        let mut target = VrlTarget::new(Event::Metric(metric));
+
+        let paths = [
+            "name",
+            "namespace",
+            "timestamp",
+            "kind",
+            "type",
+            "value",
+            "value.counter.value",
+            "value.gauge",
+            "tags",
+            "tags.tig",
+            "tags.missing",
+            "(namespace | name)",
+            "(tags.missing | tags.tig)",
+            "zork",
+            "tags.tig.flork",
+        ];
+        let check = |target: &VrlTarget| {
+            assert_eq!(target.contains(&LookupBuf::root()), Ok(true));
+            for path in paths {
+                let path = LookupBuf::from_str(path).unwrap();
+                assert_eq!(
+                    target.contains(&path),
+                    target.get(&path).map(|value| value.is_some()),
+                    "{}",
+                    path
+                );
+            }
+        };
+
+        check(&target);
+        target
+            .insert(&LookupBuf::from_str("tags.tig").unwrap(), "tog".into())
+            .unwrap();
+        target
+            .insert(&LookupBuf::from_str("namespace").unwrap(), "ns".into())
+            .unwrap();
+        check(&target);
+    }
+
+    #[test]
+    fn metric_value_paths() {
+        use crate::event::metric::StatisticKind;
+
+        let metric = Metric::new(
+            "name",
+            MetricKind::Absolute,
+            MetricValue::Distribution {
+                samples: crate::samples![1.5 => 2],
+                statistic: StatisticKind::Histogram,
+            },
+        );
+        let target = VrlTarget::new(Event::Metric(metric));
+        let get = |path: &'static str| target.get(&LookupBuf::from_str(path).unwrap());
+
+        assert_eq!(
+            get("value.distribution.samples[0].value"),
+            Ok(Some(1.5.into()))
+        );
+        assert_eq!(
+            get("value.distribution.statistic"),
+            Ok(Some("histogram".into()))
+        );
+        assert_eq!(
+            get("value.distribution.samples[0].rate"),
+            Ok(Some(2.into()))
+        );
+        assert_eq!(get("value.counter.value"), Ok(None));
+        assert_eq!(get("value.distribution.samples[1]"), Ok(None));
+    }
+
+    #[test]
+    fn log_contains() {
+        let event = Event::try_from(serde_json::json!({
+            "foo": { "bar": [1, 2] },
+            "null": null,
+        }))
+        .unwrap();
+        let mut target = VrlTarget::new(event);
+
+        assert_eq!(target.contains(&LookupBuf::root()), Ok(true));
+        for (path, expect) in [
+            ("foo.bar[1]", Ok(true)),
+            ("foo.bar[2]", Ok(false)),
+            ("null", Ok(true)),
+            ("missing", Ok(false)),
+        ] {
+            let path = LookupBuf::from_str(path).unwrap();
+            assert_eq!(target.contains(&path), expect, "{}", path);
+        }
+
+        let path = LookupBuf::from_str("foo.bar[0].baz").unwrap();
+        assert_eq!(
+            target.contains(&path),
+            target.get(&path).map(|value| value.is_some())
+        );
+        target.remove(&LookupBuf::root(), false).unwrap();
+        assert_eq!(target.contains(&LookupBuf::root()), Ok(true));
+    }
+
+    #[test]
+    fn metric_insert_root() {
+        use super::super::metric::{
+            Bucket, Quantile, Sample, StatisticKind, MAX_EXACT_INTEGER_VALUE,
+        };
+
+        let timestamp = Utc.ymd(2020, 12, 10).and_hms(12, 0, 0);
+        let cases = vec![
+            (
+                btreemap! {
+                    "name" => "requests",
+                    "kind" => "incremental",
+                    "value" => btreemap! { "counter" => btreemap! { "value" => 1 } },
+                },
+                Metric::new(
+                    "requests",
+                    MetricKind::Incremental,
+                    MetricValue::Counter { value: 1.0 },
+                ),
+            ),
+            (
+                // Integers beyond 2^53 are rounded to the nearest value, with ties to even.
+                btreemap! {
+                    "name" => "requests",
+                    "kind" => "absolute",
+                    "value" => btreemap! {
+                        "counter" => btreemap! { "value" => 9_007_199_254_740_993_i64 },
+                    },
+                },
+                Metric::new(
+                    "requests",
+                    MetricKind::Absolute,
+                    MetricValue::Counter {
+                        value: MAX_EXACT_INTEGER_VALUE,
+                    },
+                ),
+            ),
+            (
+                btreemap! {
+                    "name" => "requests",
+                    "namespace" => "app",
+                    "timestamp" => timestamp,
+                    "kind" => "absolute",
+                    "tags" => btreemap! { "host" => "localhost" },
+                    "type" => "gauge",
+                    "value" => btreemap! { "gauge" => btreemap! { "value" => 2.5 } },
+                },
+                Metric::new(
+                    "requests",
+                    MetricKind::Absolute,
+                    MetricValue::Gauge { value: 2.5 },
+                )
+                .with_namespace(Some("app"))
+                .with_timestamp(Some(timestamp))
+                .with_tags(Some({
+                    let mut map = MetricTags::new();
+                    map.insert("host".to_string(), "localhost".to_string());
+                    map
+                })),
+            ),
+            (
+                btreemap! {
+                    "name" => "users",
+                    "namespace" => vrl_core::Value::Null,
+                    "kind" => "incremental",
+                    "value" => btreemap! { "set" => btreemap! { "values" => vec!["a", "b"] } },
+                },
+                Metric::new(
+                    "users",
+                    MetricKind::Incremental,
+                    MetricValue::Set {
+                        values: vec!["a".to_string(), "b".to_string()].into_iter().collect(),
+                    },
+                ),
+            ),
+            (
+                btreemap! {
+                    "name" => "latency",
+                    "kind" => "incremental",
+                    "value" => btreemap! {
+                        "distribution" => btreemap! {
+                            "samples" => vec![vrl_core::Value::from(
+                                btreemap! { "value" => 1.5, "rate" => 2 },
+                            )],
+                            "statistic" => "histogram",
+                        },
+                    },
+                },
+                Metric::new(
+                    "latency",
+                    MetricKind::Incremental,
+                    MetricValue::Distribution {
+                        samples: vec![Sample {
+                            value: 1.5,
+                            rate: 2,
+                        }],
+                        statistic: StatisticKind::Histogram,
+                    },
+                ),
+            ),
+            (
+                btreemap! {
+                    "name" => "latency",
+                    "kind" => "absolute",
+                    "value" => btreemap! {
+                        "aggregated_histogram" => btreemap! {
+                            "buckets" => vec![vrl_core::Value::from(
+                                btreemap! { "upper_limit" => 1.0, "count" => 3 },
+                            )],
+                            "count" => 3,
+                            "sum" => 2.0,
+                        },
+                    },
+                },
+                Metric::new(
+                    "latency",
+                    MetricKind::Absolute,
+                    MetricValue::AggregatedHistogram {
+                        buckets: vec![Bucket {
+                            upper_limit: 1.0,
+                            count: 3,
+                        }],
+                        count: 3,
+                        sum: 2.0,
+                    },
+                ),
+            ),
+            (
+                btreemap! {
+                    "name" => "latency",
+                    "kind" => "absolute",
+                    "value" => btreemap! {
+                        "aggregated_summary" => btreemap! {
+                            "quantiles" => vec![vrl_core::Value::from(
+                                btreemap! { "quantile" => 0.5, "value" => 1.0 },
+                            )],
+                            "count" => 3,
+                            "sum" => 2.0,
+                        },
+                    },
+                },
+                Metric::new(
+                    "latency",
+                    MetricKind::Absolute,
+                    MetricValue::AggregatedSummary {
+                        quantiles: vec![Quantile {
+                            quantile: 0.5,
+                            value: 1.0,
+                        }],
+                        count: 3,
+                        sum: 2.0,
+                    },
+                ),
+            ),
+        ];
+
+        for (value, expected) in cases {
+            let mut metadata = EventMetadata::default();
+            metadata.set_datadog_api_key(Some(Arc::from("my_api_key")));
+            let metric = Metric::new_with_metadata(
+                "zub",
+                MetricKind::Absolute,
+                MetricValue::Counter { value: 1.23 },
+                metadata.clone(),
+            );
+            let mut target = VrlTarget::new(Event::Metric(metric));
+
+            assert_eq!(Ok(()), target.insert(&LookupBuf::root(), value.into()));
+
+            let events = target.into_events().collect::<Vec<_>>();
+            let mut expected = expected;
+            *expected.metadata_mut() = metadata;
+            assert_eq!(events, vec![Event::Metric(expected)]);
+        }
+    }
+
+    #[test]
+    fn metric_insert_root_invalid() {
+        let valid_keys = "name, namespace, timestamp, kind, tags, type, value";
+        let counter =
+            || vrl_core::Value::from(btreemap! { "counter" => btreemap! { "value" => 1 } });
+
+        let cases = vec![
+            (
+                vrl_core::Value::from("requests"),
+                "expected \"object\", got \"string\"".to_owned(),
+            ),
+            (
+                btreemap! { "kind" => "absolute", "value" => counter() }.into(),
+                "missing required key name when assigning a metric to root".to_owned(),
+            ),
+            (
+                btreemap! { "name" => "requests", "value" => counter() }.into(),
+                "missing required key kind when assigning a metric to root".to_owned(),
+            ),
+            (
+                btreemap! { "name" => "requests", "kind" => "absolute" }.into(),
+                "missing required key value when assigning a metric to root".to_owned(),
+            ),
+            (
+                btreemap! {
+                    "name" => "requests",
+                    "kind" => "absolute",
+                    "value" => counter(),
+                    "zork" => true,
+                }
+                .into(),
+                format!(
+                    "invalid key zork when assigning a metric to root: expected one of {}",
+                    valid_keys
+                ),
+            ),
+            (
+                btreemap! { "name" => "requests", "kind" => "sideways", "value" => counter() }
+                    .into(),
+                r#"cannot set kind: invalid metric kind "sideways": expected one of "absolute" or "incremental""#
+                    .to_owned(),
+            ),
+            (
+                btreemap! {
+                    "name" => "requests",
+                    "kind" => "absolute",
+                    "type" => "gauge",
+                    "value" => counter(),
+                }
+                .into(),
+                "metric type gauge does not match value of type counter".to_owned(),
+            ),
+        ];
+
+        for (value, error) in cases {
+            let metric = Metric::new(
+                "zub",
+                MetricKind::Absolute,
+                MetricValue::Counter { value: 1.23 },
+            );
+            let mut target = VrlTarget::new(Event::Metric(metric.clone()));
+
+            assert_eq!(Err(error), target.insert(&LookupBuf::root(), value));
+            // A failed assignment leaves the metric untouched.
+            assert_eq!(
+                target.into_events().collect::<Vec<_>>(),
+                vec![Event::Metric(metric)]
+            );
+        }
+
+        let mut target = VrlTarget::new(Event::Metric(Metric::new(
+            "zub",
+            MetricKind::Absolute,
+            MetricValue::Counter { value: 1.23 },
+        )));
+        let error = target
+            .insert(
+                &LookupBuf::root(),
+                btreemap! {
+                    "name" => "requests",
+                    "kind" => "absolute",
+                    "value" => btreemap! { "counter" => btreemap! { "values" => 1 } },
+                }
+                .into(),
+            )
+            .unwrap_err();
+        assert_eq!(error, "invalid metric value: missing key counter.value");
+    }
+
+    fn protected(paths: &[&'static str]) -> Arc<ProtectedPaths> {
+        Arc::new(ProtectedPaths::new(
+            paths.iter().map(|path| LookupBuf::from_str(path).unwrap()),
+        ))
+    }
+
+    #[test]
+    fn log_protected_paths() {
+        let event = LogEvent::from(btreemap! {
+            "message" => "hello",
+            "tenant" => btreemap! { "id" => "acme", "name" => "Acme" },
+        });
+        let mut target =
+            VrlTarget::new(Event::Log(event)).with_protected_paths(protected(&["tenant.id"]));
+
+        let cases = vec![
+            (LookupBuf::from_str("tenant.id").unwrap(), "tenant.id"),
+            (
+                LookupBuf::from_str("tenant.id.nested").unwrap(),
+                "tenant.id.nested",
+            ),
+            (LookupBuf::from_str("tenant").unwrap(), "tenant"),
+            (LookupBuf::root(), "."),
+        ];
+
+        for (path, display) in cases {
+            let error = format!(
+                "cannot modify read-only path {}: protected by tenant.id",
+                display
+            );
+            assert_eq!(Err(error.clone()), target.insert(&path, "evil".into()));
+            assert_eq!(Err(error), target.remove(&path, false));
+        }
+
+        // Siblings of a protected path are still writable.
+        let name = LookupBuf::from_str("tenant.name").unwrap();
+        assert_eq!(Ok(()), target.insert(&name, "Evil Corp".into()));
+        assert_eq!(Ok(Some("Evil Corp".into())), target.remove(&name, false));
+        assert_eq!(
+            Ok(()),
+            target.insert(&LookupBuf::from_str("message").unwrap(), "bye".into())
+        );
+
+        assert_eq!(
+            target.into_events().collect::<Vec<_>>(),
+            vec![Event::Log(LogEvent::from(btreemap! {
+                "message" => "bye",
+                "tenant" => btreemap! { "id" => "acme" },
+            }))]
+        );
+    }
+
+    #[test]
+    fn log_protected_path_absent_allows_root_write() {
+        let mut target = VrlTarget::new(Event::Log(LogEvent::from(btreemap! { "foo" => "bar" })))
+            .with_protected_paths(protected(&["tenant.id"]));
+
+        assert_eq!(
+            Ok(()),
+            target.insert(&LookupBuf::root(), btreemap! { "foo" => "baz" }.into())
+        );
+        // Creating the protected path from scratch is still rejected.
+        assert!(target
+            .insert(&LookupBuf::from_str("tenant.id").unwrap(), "acme".into())
+            .is_err());
+    }
+
+    #[test]
+    fn path_stats_count_operations_across_events() {
+        let stats = Arc::new(PathStats::new(3));
+        let path = |path: &str| LookupBuf::from_str(path).unwrap();
+        for i in 0..10 {
+            let mut target = VrlTarget::new(Event::Log(LogEvent::from(btreemap! { "foo" => i })))
+                .with_path_stats(Arc::clone(&stats));
+            target.get(&path("foo")).unwrap();
+            target.get(&path("missing")).unwrap();
+            target.contains(&path("foo")).unwrap();
+            target.insert(&path("bar.baz"), "x".into()).unwrap();
+            target.remove(&path("foo"), false).unwrap();
+            // Past `max_paths`, so counted with the other paths.
+            target.get(&path("extra")).unwrap();
+            target.insert(&path("more"), 1.into()).unwrap();
+        }
+
+        let report = stats.take();
+        let counts = |gets, misses, sets, removes| PathCounts {
+            gets,
+            misses,
+            sets,
+            removes,
+        };
+        assert_eq!(
+            report.paths,
+            BTreeMap::from([
+                ("bar.baz".to_owned(), counts(0, 0, 10, 0)),
+                ("foo".to_owned(), counts(20, 0, 0, 10)),
+                ("missing".to_owned(), counts(10, 10, 0, 0)),
+            ])
+        );
+        assert_eq!(report.other, counts(10, 10, 10, 0));
+
+        // Taking the counts starts over.
+        assert_eq!(stats.take(), PathStatsReport::default());
+    }
+
+    #[test]
+    fn metric_protected_tags() {
+        let metric = Metric::new(
+            "zub",
+            MetricKind::Absolute,
+            MetricValue::Counter { value: 1.23 },
+        )
+        .with_tags(Some({
+            let mut map = MetricTags::new();
+            map.insert("tenant".to_string(), "acme".to_string());
+            map.insert("host".to_string(), "localhost".to_string());
+            map
+        }));
+        let mut target = VrlTarget::new(Event::Metric(metric.clone()))
+            .with_protected_paths(protected(&["tags.tenant"]));
+
+        let cases = vec![
+            (LookupBuf::from_str("tags.tenant").unwrap(), "tags.tenant"),
+            (LookupBuf::from_str("tags").unwrap(), "tags"),
+            (LookupBuf::root(), "."),
+        ];
+        for (path, display) in cases {
+            let error = format!(
+                "cannot modify read-only path {}: protected by tags.tenant",
+                display
+            );
+            assert_eq!(Err(error), target.remove(&path, false));
+        }
+        assert!(target
+            .insert(&LookupBuf::from_str("tags.tenant").unwrap(), "evil".into())
+            .is_err());
+
+        assert_eq!(
+            Ok(Some("localhost".into())),
+            target.remove(&LookupBuf::from_str("tags.host").unwrap(), false)
+        );
+        assert_eq!(
+            Ok(()),
+            target.insert(&LookupBuf::from_str("name").unwrap(), "zork".into())
+        );
+
+        let mut expected = metric.with_name("zork");
+        expected.remove_tag("host");
+        assert_eq!(
+            target.into_events().collect::<Vec<_>>(),
+            vec![Event::Metric(expected)]
+        );
+    }
+
+    #[test]
+    fn metric_tags_matching() {
+        let metric = Metric::new(
+            "zub",
+            MetricKind::Absolute,
+            MetricValue::Counter { value: 1.23 },
+        )
+        .with_tags(Some(
+            vec![
+                ("aws", "bare"),
+                ("aws_region", "us-east-1"),
+                ("aws_zone", "us-east-1a"),
+                ("awsx", "other"),
+                ("host", "localhost"),
+            ]
+            .into_iter()
+            .map(|(key, value)| (key.to_owned(), value.to_owned()))
+            .collect(),
+        ));
+        let mut target = VrlTarget::new(Event::Metric(metric.clone()));
+
+        let aws_tags: vrl_core::Value = btreemap! {
+            "aws_region" => "us-east-1",
+            "aws_zone" => "us-east-1a",
+        }
+        .into();
+        assert_eq!(target.tags_matching("aws_"), aws_tags);
+        // `aws` is a prefix of `aws_` and `awsx`, so all of them match it.
+        assert_eq!(
+            target.tags_matching("aws"),
+            btreemap! {
+                "aws" => "bare",
+                "aws_region" => "us-east-1",
+                "aws_zone" => "us-east-1a",
+                "awsx" => "other",
+            }
+            .into()
+        );
+        assert_eq!(target.tags_matching("gcp_"), BTreeMap::new().into());
+
+        assert_eq!(target.delete_tags_matching("aws_"), Ok(aws_tags));
+        assert_eq!(
+            target.delete_tags_matching("aws_"),
+            Ok(BTreeMap::new().into())
+        );
+
+        let mut expected = metric;
+        expected.remove_tag("aws_region");
+        expected.remove_tag("aws_zone");
+        assert_eq!(
+            target.into_events().collect::<Vec<_>>(),
+            vec![Event::Metric(expected)]
+        );
+    }
+
+    #[test]
+    fn metric_tags_matching_target() {
+        let metric = Metric::new(
+            "zub",
+            MetricKind::Absolute,
+            MetricValue::Counter { value: 1.23 },
+        )
+        .with_tags(Some(
+            vec![
+                ("aws_region", "us-east-1"),
+                ("aws_zone", "us-east-1a"),
+                ("gcp_*", "literal"),
+                ("host", "localhost"),
+            ]
+            .into_iter()
+            .map(|(key, value)| (key.to_owned(), value.to_owned()))
+            .collect(),
+        ));
+        let mut target = VrlTarget::new(Event::Metric(metric));
+        let tag = |key: &str| LookupBuf::from_segments(vec!["tags".into(), key.into()]);
+        let aws_tags: vrl_core::Value = btreemap! {
+            "aws_region" => "us-east-1",
+            "aws_zone" => "us-east-1a",
+        }
+        .into();
+
+        assert_eq!(target.get_tags_matching("aws_"), Ok(aws_tags.clone()));
+        assert_eq!(
+            target.get_tags_matching("azure_"),
+            Ok(BTreeMap::new().into())
+        );
+
+        // Paths stay literal: a key ending in `*` is just that tag.
+        assert_eq!(target.get(&tag("aws_*")), Ok(None));
+        assert_eq!(target.contains(&tag("aws_*")), Ok(false));
+        assert_eq!(target.get(&tag("gcp_*")), Ok(Some("literal".into())));
+        assert_eq!(target.remove(&tag("aws_*"), false), Ok(None));
+        assert_eq!(target.get(&tag("aws_region")), Ok(Some("us-east-1".into())));
+
+        assert_eq!(target.remove_tags_matching("aws_"), Ok(aws_tags));
+        assert_eq!(target.get(&tag("aws_region")), Ok(None));
+        assert_eq!(target.get(&tag("host")), Ok(Some("localhost".into())));
+
+        // Log events have no tags.
+        let target = VrlTarget::new(Event::Log(LogEvent::default()));
+        assert_eq!(target.get_tags_matching("aws_"), Ok(BTreeMap::new().into()));
+    }
+
+    #[test]
+    fn metric_delete_tags_matching_protected() {
+        let metric = Metric::new(
+            "zub",
+            MetricKind::Absolute,
+            MetricValue::Counter { value: 1.23 },
+        )
+        .with_tags(Some(
+            vec![("aws_account", "1234"), ("aws_region", "us-east-1")]
+                .into_iter()
+                .map(|(key, value)| (key.to_owned(), value.to_owned()))
+                .collect(),
+        ));
+        let mut target = VrlTarget::new(Event::Metric(metric.clone()))
+            .with_protected_paths(protected(&["tags.aws_account"]));
+
+        assert_eq!(
+            target.delete_tags_matching("aws_"),
+            Err(
+                "cannot modify read-only path tags.aws_account: protected by tags.aws_account"
+                    .to_owned()
+            )
+        );
+        assert_eq!(
+            target.delete_tags_matching("aws_r"),
+            Ok(btreemap! { "aws_region" => "us-east-1" }.into())
+        );
+
+        let mut expected = metric;
+        expected.remove_tag("aws_region");
+        assert_eq!(
+            target.into_events().collect::<Vec<_>>(),
+            vec![Event::Metric(expected)]
+        );
+    }
+
+    #[test]
+    fn log_tags_matching() {
+        let mut target = VrlTarget::new(Event::Log(LogEvent::from(btreemap! {
+            "aws_region" => "us-east-1",
+        })));
+        assert_eq!(target.tags_matching("aws_"), BTreeMap::new().into());
+        assert_eq!(
+            target.delete_tags_matching("aws_"),
+            Ok(BTreeMap::new().into())
+        );
+    }
+
+    #[test]
+    fn metric_tag_limits() {
+        let metric = Metric::new(
+            "zub",
+            MetricKind::Absolute,
+            MetricValue::Counter { value: 1.23 },
+        )
+        .with_tags(Some(
+            vec![("host".to_owned(), "localhost".to_owned())]
+                .into_iter()
+                .collect(),
+        ));
+        let limits = TagLimits {
+            max_tags: Some(2),
+            max_key_length: Some(8),
+            max_value_length: Some(4),
+            action: super::super::TagLimitAction::Truncate,
+        };
+        let mut target = VrlTarget::new(Event::Metric(metric)).with_tag_limits(limits);
+
+        let request_id = LookupBuf::from_str("tags.request_id").unwrap();
+        assert_eq!(Ok(()), target.insert(&request_id, "abcdef".into()));
+        assert_eq!(
+            Ok(Some(
+                btreemap! { "request_" => "abcd", "host" => "localhost" }.into()
+            )),
+            target.get(&LookupBuf::from_str("tags").unwrap())
+        );
+
+        // The metric is full, so new tags are dropped but existing ones can still be written.
+        assert_eq!(
+            Ok(()),
+            target.insert(
+                &LookupBuf::from_str("tags").unwrap(),
+                btreemap! { "user" => "bob", "host" => "a" }.into()
+            )
+        );
+        assert_eq!(
+            Ok(Some(
+                btreemap! { "request_" => "abcd", "host" => "a" }.into()
+            )),
+            target.get(&LookupBuf::from_str("tags").unwrap())
+        );
+
+        // Replacing the whole metric is held to the limits too.
+        assert_eq!(
+            Ok(()),
+            target.insert(
+                &LookupBuf::root(),
+                btreemap! {
+                    "name" => "zub",
+                    "kind" => "absolute",
+                    "tags" => btreemap! { "a" => "1", "b" => "22222", "c" => "3" },
+                    "value" => btreemap! { "counter" => btreemap! { "value" => 1 } },
+                }
+                .into()
+            )
+        );
+        assert_eq!(
+            Ok(Some(btreemap! { "a" => "1", "b" => "2222" }.into())),
+            target.get(&LookupBuf::from_str("tags").unwrap())
+        );
+    }
+
+    #[test]
+    fn metric_utf8_handling() {
+        let invalid = || vrl_core::Value::Bytes(bytes::Bytes::from_static(b"a\xffb\xc3"));
+        let paths = ["name", "namespace", "tags.foo"];
+        let cases = [
+            (Utf8Handling::Lossy, Ok("a\u{fffd}b\u{fffd}")),
+            (Utf8Handling::Base64, Ok("Yf9iww==")),
+            (Utf8Handling::Reject, Err("ff")),
+        ];
+
+        for (utf8_handling, expected) in cases {
+            let metric = Metric::new(
+                "zub",
+                MetricKind::Absolute,
+                MetricValue::Counter { value: 1.23 },
+            );
+            let mut target =
+                VrlTarget::new(Event::Metric(metric)).with_utf8_handling(utf8_handling);
+
+            for path in paths {
+                let path = LookupBuf::from_str(path).unwrap();
+                let result = target.insert(&path, invalid());
+                match expected {
+                    Ok(expected) => {
+                        assert_eq!(result, Ok(()));
+                        assert_eq!(target.get(&path), Ok(Some(expected.into())));
+                    }
+                    Err(preview) => assert_eq!(
+                        result,
+                        Err(format!("invalid UTF-8 in {} at byte 1: {}", path, preview))
+                    ),
+                }
+            }
+
+            // Both ways of writing several tags at once decode each of them the same way.
+            let tags = LookupBuf::from_str("tags").unwrap();
+            let result = target.insert(&tags, btreemap! { "bar" => invalid() }.into());
+            let root = btreemap! {
+                "name" => "zub",
+                "kind" => "absolute",
+                "tags" => btreemap! { "baz" => invalid() },
+                "value" => btreemap! { "counter" => btreemap! { "value" => 1 } },
+            };
+            let root_result = target.insert(&LookupBuf::root(), root.into());
+            match expected {
+                Ok(expected) => {
+                    assert_eq!(result, Ok(()));
+                    assert_eq!(root_result, Ok(()));
+                    assert_eq!(
+                        target.get(&tags),
+                        Ok(Some(btreemap! { "baz" => expected }.into()))
+                    );
+                }
+                Err(preview) => {
+                    assert_eq!(
+                        result,
+                        Err(format!("invalid UTF-8 in tags.bar at byte 1: {}", preview))
+                    );
+                    assert_eq!(
+                        root_result,
+                        Err(format!("invalid UTF-8 in tags.baz at byte 1: {}", preview))
+                    );
+                    // Nothing was written.
+                    assert_eq!(
+                        target.get(&LookupBuf::from_str("name").unwrap()),
+                        Ok(Some("zub".into()))
+                    );
+                    assert_eq!(target.get(&tags), Ok(None));
+                }
+            }
+        }
+
+        // A sequence cut short by the end of the bytes is shown in full.
+        assert_eq!(
+            Utf8Handling::Reject.decode("name", b"ab\xe2\x82"),
+            Err("invalid UTF-8 in name at byte 2: e2 82".to_owned())
+        );
+    }
+
+    fn failure() -> EventFailure {
+        EventFailure::new("function call error")
+            .with_component_id("remap_a")
+            .with_timestamp(Utc.ymd(2021, 1, 1).and_hms(0, 0, 0))
+    }
+
+    #[test]
+    fn log_into_error_event_keeps_mutations() {
+        let metadata = EventMetadata::default();
+        let log = LogEvent::from_parts(btreemap! { "message" => "hello", "keep" => 1 }, metadata);
+        let original = Event::Log(log);
+        let mut target = VrlTarget::new(original.clone());
+        target
+            .insert(&LookupBuf::from_str("added").unwrap(), true.into())
+            .unwrap();
+        target
+            .remove(&LookupBuf::from_str("keep").unwrap(), false)
+            .unwrap();
+
+        let annotation = btreemap! {
+            "message" => "function call error",
+            "component_id" => "remap_a",
+            "timestamp" => Utc.ymd(2021, 1, 1).and_hms(0, 0, 0),
+        };
+        let event = target.clone().into_error_event(&failure());
+        assert_eq!(
+            event.into_log(),
+            LogEvent::from(btreemap! {
+                "message" => "hello",
+                "added" => true,
+                "metadata" => btreemap! { "error" => annotation.clone() },
+            })
+        );
+
+        // The same again gives the same event.
+        assert_eq!(
+            target.clone().into_error_event(&failure()),
+            target.into_error_event(&failure())
+        );
+
+        // The original event can be annotated as is instead.
+        let event = annotate_error(original, &failure().with_namespace("dead_letter"));
+        assert_eq!(
+            event.into_log(),
+            LogEvent::from(btreemap! {
+                "message" => "hello",
+                "keep" => 1,
+                "dead_letter" => annotation,
+            })
+        );
+    }
+
+    #[test]
+    fn log_into_error_event_is_single_event() {
+        let root = LookupBuf::root();
+        let failure = EventFailure::new("failed").with_namespace("error");
+        let annotation = |log: &LogEvent| {
+            (
+                log["error.message"].clone(),
+                log.contains("error.component_id"),
+                log["error.timestamp"].clone(),
+            )
+        };
+
+        let mut target = VrlTarget::new(Event::from("hello"));
+        target.remove(&root, false).unwrap();
+        let event = target.into_error_event(&failure);
+        let log = event.as_log();
+        assert_eq!(log.keys().count(), 2);
+        assert_eq!(
+            annotation(log),
+            ("failed".into(), false, failure.timestamp.into())
+        );
+
+        let mut target = VrlTarget::new(Event::from("hello"));
+        let array = vrl_core::Value::from(vec![
+            vrl_core::Value::from(btreemap! { "a" => 1 }),
+            vrl_core::Value::from(2),
+        ]);
+        target.insert(&root, array).unwrap();
+        assert_eq!(target.fanout(), Some(2));
+        let event = target.into_error_event(&failure);
+        let log = event.as_log();
+        assert_eq!(
+            log["message"],
+            Value::from(vec![Value::from(btreemap! { "a" => 1 }), Value::from(2)])
+        );
+        assert_eq!(annotation(log).0, "failed".into());
+    }
+
+    #[test]
+    fn metric_into_error_event() {
+        let metric = Metric::new(
+            "zub",
+            MetricKind::Absolute,
+            MetricValue::Counter { value: 1.23 },
+        );
+        let mut target = VrlTarget::new(Event::Metric(metric.clone()));
+        target
+            .insert(
+                &LookupBuf::from_str("tags.host").unwrap(),
+                "localhost".into(),
+            )
+            .unwrap();
+
+        let event = target.into_error_event(&failure().with_namespace("dlq"));
+        let mut expected = metric.clone();
+        expected.insert_tag("host".into(), "localhost".into());
+        expected.insert_tag("dlq.message".into(), "function call error".into());
+        expected.insert_tag("dlq.component_id".into(), "remap_a".into());
+        expected.insert_tag("dlq.timestamp".into(), "2021-01-01T00:00:00+00:00".into());
+        assert_eq!(event, Event::Metric(expected));
+
+        // Without a component, there is no tag for it.
+        let event = annotate_error(Event::Metric(metric), &EventFailure::new("failed"));
+        let tags = event.as_metric().tags().unwrap();
+        assert_eq!(tags["metadata.error.message"], "failed");
+        assert!(!tags.contains_key("metadata.error.component_id"));
+        assert!(tags.contains_key("metadata.error.timestamp"));
+    }
+
+    #[test]
+    fn metric_set_kind() {
+        let mut target = VrlTarget::new(Event::Metric(Metric::new(
+            "zub",
+            MetricKind::Absolute,
+            MetricValue::Counter { value: 1.23 },
+        )));
+        let path = LookupBuf::from_str("kind").unwrap();
+
+        assert_eq!(Ok(()), target.insert(&path, "Incremental".into()));
+        assert_eq!(Ok(Some("incremental".into())), target.get(&path));
+        assert_eq!(Ok(()), target.insert(&path, "ABSOLUTE".into()));
+        assert_eq!(Ok(Some("absolute".into())), target.get(&path));
+
+        let cases = vec![
+            (
+                vrl_core::Value::from("increment"),
+                r#""increment""#.to_owned(),
+            ),
+            (vrl_core::Value::from(1), "1".to_owned()),
+            (
+                vrl_core::Value::from("x".repeat(100)),
+                format!(r#""{}..."#, "x".repeat(63)),
+            ),
+        ];
+        for (value, received) in cases {
+            assert_eq!(
+                Err(format!(
+                    r#"cannot set kind: invalid metric kind {}: expected one of "absolute" or "incremental""#,
+                    received
+                )),
+                target.insert(&path, value)
+            );
+        }
+    }
 }
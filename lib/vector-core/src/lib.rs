@@ -30,8 +30,8 @@ pub mod mapping;
 pub mod metrics;
 pub mod sink;
 pub mod source;
-#[cfg(test)]
-mod test_util;
+#[cfg(any(test, feature = "test"))]
+pub mod test_util;
 pub mod transform;
 pub use buffers;
 pub mod partition;
@@ -6,7 +6,7 @@ use std::{
     },
 };
 
-use metrics::GaugeValue;
+use metrics::{GaugeValue, Unit};
 
 #[derive(Debug)]
 struct AtomicF64 {
@@ -72,8 +72,14 @@ impl Handle {
         }
     }
 
-    pub(crate) fn histogram() -> Self {
-        Handle::Histogram(Arc::new(Histogram::new()))
+    /// Histograms registered in bytes measure sizes and get buckets suited to
+    /// that, everything else is assumed to be a duration.
+    pub(crate) fn histogram(unit: Option<Unit>) -> Self {
+        let histogram = match unit {
+            Some(Unit::Bytes) => Histogram::new_with_bounds(&SIZE_BOUNDS),
+            _ => Histogram::new(),
+        };
+        Handle::Histogram(Arc::new(histogram))
     }
 
     pub(crate) fn record_histogram(&self, value: f64) {
@@ -91,38 +97,66 @@ pub struct Histogram {
     sum: AtomicF64,
 }
 
+// The sequence here is based on powers of two. Other sequences are more
+// suitable for different distributions but since our present use case is
+// mostly non-negative and measures smallish latencies we cluster around but
+// never quite get to zero with an increasingly coarse long-tail.
+const LATENCY_BOUNDS: [f64; 20] = [
+    0.015_625,
+    0.03125,
+    0.0625,
+    0.125,
+    0.25,
+    0.5,
+    1.0,
+    2.0,
+    4.0,
+    8.0,
+    16.0,
+    32.0,
+    64.0,
+    128.0,
+    256.0,
+    512.0,
+    1024.0,
+    2048.0,
+    4096.0,
+    f64::INFINITY,
+];
+
+// Powers of two from 64 B to 16 MiB.
+const SIZE_BOUNDS: [f64; 20] = [
+    64.0,
+    128.0,
+    256.0,
+    512.0,
+    1024.0,
+    2048.0,
+    4096.0,
+    8192.0,
+    16_384.0,
+    32_768.0,
+    65_536.0,
+    131_072.0,
+    262_144.0,
+    524_288.0,
+    1_048_576.0,
+    2_097_152.0,
+    4_194_304.0,
+    8_388_608.0,
+    16_777_216.0,
+    f64::INFINITY,
+];
+
 impl Histogram {
     pub(crate) fn new() -> Self {
+        Self::new_with_bounds(&LATENCY_BOUNDS)
+    }
+
+    pub(crate) fn new_with_bounds(bounds: &[f64; 20]) -> Self {
         // Box to avoid having this large array inline to the structure, blowing
         // out cache coherence.
-        //
-        // The sequence here is based on powers of two. Other sequences are more
-        // suitable for different distributions but since our present use case
-        // is mostly non-negative and measures smallish latencies we cluster
-        // around but never quite get to zero with an increasingly coarse
-        // long-tail.
-        let buckets = Box::new([
-            (0.015_625, AtomicU32::new(0)),
-            (0.03125, AtomicU32::new(0)),
-            (0.0625, AtomicU32::new(0)),
-            (0.125, AtomicU32::new(0)),
-            (0.25, AtomicU32::new(0)),
-            (0.5, AtomicU32::new(0)),
-            (1.0, AtomicU32::new(0)),
-            (2.0, AtomicU32::new(0)),
-            (4.0, AtomicU32::new(0)),
-            (8.0, AtomicU32::new(0)),
-            (16.0, AtomicU32::new(0)),
-            (32.0, AtomicU32::new(0)),
-            (64.0, AtomicU32::new(0)),
-            (128.0, AtomicU32::new(0)),
-            (256.0, AtomicU32::new(0)),
-            (512.0, AtomicU32::new(0)),
-            (1024.0, AtomicU32::new(0)),
-            (2048.0, AtomicU32::new(0)),
-            (4096.0, AtomicU32::new(0)),
-            (f64::INFINITY, AtomicU32::new(0)),
-        ]);
+        let buckets = Box::new(bounds.map(|bound| (bound, AtomicU32::new(0))));
         Self {
             buckets,
             count: AtomicU32::new(0),
@@ -235,7 +269,7 @@ impl Gauge {
 mod test {
     use quickcheck::{QuickCheck, TestResult};
 
-    use crate::metrics::handle::{Counter, Histogram};
+    use crate::metrics::handle::{Counter, Histogram, SIZE_BOUNDS};
 
     // Adapted from https://users.rust-lang.org/t/assert-eq-for-float-numbers/7034/4?u=blt
     fn nearly_equal(a: f64, b: f64) -> bool {
@@ -283,6 +317,22 @@ mod test {
             .quickcheck(inner as fn(Vec<f64>) -> TestResult);
     }
 
+    #[test]
+    fn size_histogram_buckets() {
+        let sut = Histogram::new_with_bounds(&SIZE_BOUNDS);
+        sut.record(64.0);
+        sut.record(65.0);
+        sut.record(16_777_216.0);
+        sut.record(16_777_217.0);
+
+        let buckets = sut.buckets().collect::<Vec<_>>();
+        assert_eq!(buckets.len(), 20);
+        assert_eq!(buckets[0], (64.0, 1));
+        assert_eq!(buckets[1], (128.0, 1));
+        assert_eq!(buckets[18], (16_777_216.0, 1));
+        assert_eq!(buckets[19], (f64::INFINITY, 1));
+    }
+
     #[test]
     #[allow(clippy::needless_pass_by_value)] // `&[T]` does not implement `Arbitrary`
     fn count() {
@@ -52,10 +52,17 @@ impl Recorder for VectorRecorder {
     fn register_histogram(
         &self,
         key: &Key,
-        _unit: Option<Unit>,
+        unit: Option<Unit>,
         _description: Option<&'static str>,
     ) {
-        self.with_registry(|r| r.op(MetricKind::Histogram, key, |_| {}, Handle::histogram));
+        self.with_registry(|r| {
+            r.op(
+                MetricKind::Histogram,
+                key,
+                |_| {},
+                || Handle::histogram(unit),
+            );
+        });
     }
 
     fn increment_counter(&self, key: &Key, value: u64) {
@@ -86,7 +93,7 @@ impl Recorder for VectorRecorder {
                 MetricKind::Histogram,
                 key,
                 |handle| handle.record_histogram(value),
-                Handle::histogram,
+                || Handle::histogram(None),
             );
         });
     }
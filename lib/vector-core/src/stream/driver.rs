@@ -1,4 +1,5 @@
 use std::{
+    any::Any,
     collections::{BinaryHeap, VecDeque},
     fmt,
     num::NonZeroUsize,
@@ -139,6 +140,27 @@ pub trait DriverResponse {
     fn events_sent(&self) -> EventsSent;
 }
 
+/// The error a service returns for requests turned away by an open circuit breaker.
+///
+/// The breaker reports when it opens and counts the requests it turns away, so `Driver` doesn't
+/// log each of them as a failed call.
+#[derive(Debug)]
+pub struct CircuitOpen;
+
+impl fmt::Display for CircuitOpen {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Circuit breaker is open; the request was not sent.")
+    }
+}
+
+impl std::error::Error for CircuitOpen {}
+
+fn is_circuit_open<E: 'static>(error: &E) -> bool {
+    (error as &dyn Any)
+        .downcast_ref::<crate::Error>()
+        .map_or(false, |error| error.is::<CircuitOpen>())
+}
+
 /// Drives the interaction between a stream of items and a service which processes them
 /// asynchronously.
 ///
@@ -281,7 +303,11 @@ where
                             .map(move |result: Result<Svc::Response, Svc::Error>| {
                                 match result {
                                     Err(error) => {
-                                        error!(message = "Service call failed.", ?error, request_id);
+                                        if is_circuit_open(&error) {
+                                            debug!(message = "Service call rejected by an open circuit breaker.", request_id);
+                                        } else {
+                                            error!(message = "Service call failed.", ?error, request_id);
+                                        }
                                         finalizers.update_status(EventStatus::Rejected);
                                     },
                                     Ok(response) => {
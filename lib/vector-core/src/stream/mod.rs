@@ -5,6 +5,6 @@ mod futures_unordered_chunked;
 mod partitioned_batcher;
 
 pub use concurrent_map::ConcurrentMap;
-pub use driver::{Driver, DriverResponse};
+pub use driver::{CircuitOpen, Driver, DriverResponse};
 pub use futures_unordered_chunked::FuturesUnorderedChunked;
 pub use partitioned_batcher::{BatcherSettings, ExpirationQueue, PartitionedBatcher};
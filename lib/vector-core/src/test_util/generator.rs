@@ -0,0 +1,372 @@
+//! Randomized, reproducible events for property-style tests.
+//!
+//! Every generator is driven by a single `u64` seed. Tests should include
+//! [`EventGenerator::seed`] in their failure messages so a failing case can be
+//! replayed by setting `VECTOR_TEST_SEED`.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use bytes::Bytes;
+use chrono::{DateTime, NaiveDateTime, Utc};
+use proptest::{
+    arbitrary::{any, Arbitrary},
+    strategy::{BoxedStrategy, Strategy},
+};
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+
+use crate::{
+    event::{
+        metric::{Bucket, MetricSketch, Quantile, Sample},
+        Event, LogEvent, Metric, MetricKind, MetricValue, StatisticKind, Value,
+    },
+    metrics::AgentDDSketch,
+};
+
+/// Environment variable used to replay a specific seed.
+pub const SEED_ENV_VAR: &str = "VECTOR_TEST_SEED";
+
+/// Nesting depth used by the `proptest` strategies.
+const DEFAULT_DEPTH: usize = 3;
+/// Number of entries per map or array used by the `proptest` strategies.
+const DEFAULT_BREADTH: usize = 4;
+
+const MAX_STR_SIZE: usize = 16;
+const MAX_METRIC_VALUES: usize = 8;
+
+const KEY_ALPHABET: &[u8] =
+    b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789_";
+const STRING_CHARS: &[char] = &['a', 'z', 'Z', '0', ' ', '"', '\\', '\n', 'é', 'ß', '日', '🦀'];
+
+const EXTREME_INTEGERS: &[i64] = &[i64::MIN, i64::MIN + 1, -1, 0, 1, i64::MAX - 1, i64::MAX];
+// Non-finite floats are left out on purpose: they can't be represented in
+// JSON and never compare equal to themselves.
+const EXTREME_FLOATS: &[f64] = &[
+    f64::MIN,
+    -1.0,
+    -0.0,
+    0.0,
+    f64::MIN_POSITIVE,
+    f64::EPSILON,
+    1.0,
+    f64::MAX,
+];
+
+// Latest timestamp representable in RFC 3339: 9999-12-31T23:59:59Z.
+const MAX_TIMESTAMP_SECS: i64 = 253_402_300_799;
+
+/// Produces randomized but reproducible events.
+#[derive(Debug)]
+pub struct EventGenerator {
+    seed: u64,
+    rng: StdRng,
+}
+
+impl EventGenerator {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    /// Create a generator seeded from `VECTOR_TEST_SEED` if it is set, or from
+    /// a random seed otherwise.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `VECTOR_TEST_SEED` is set but isn't a valid `u64`.
+    pub fn from_env() -> Self {
+        let seed = match std::env::var(SEED_ENV_VAR) {
+            Ok(seed) => seed
+                .parse()
+                .unwrap_or_else(|_| panic!("{} must be a u64, got {:?}", SEED_ENV_VAR, seed)),
+            Err(_) => rand::random(),
+        };
+        Self::new(seed)
+    }
+
+    /// The seed this generator was created with.
+    pub const fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Generate a log event whose fields nest at most `depth` levels deep,
+    /// with at most `breadth` entries in every map and array.
+    pub fn arbitrary_log_event(&mut self, depth: usize, breadth: usize) -> LogEvent {
+        LogEvent::from(self.arbitrary_map(depth, breadth))
+    }
+
+    /// Generate a value of any variant, nesting at most `depth` levels deep.
+    pub fn arbitrary_value(&mut self, depth: usize, breadth: usize) -> Value {
+        let variants = if depth == 0 { 6 } else { 8 };
+        match self.rng.gen_range(0..variants) {
+            0 => Value::Bytes(self.arbitrary_bytes()),
+            1 => Value::Integer(self.arbitrary_integer()),
+            2 => Value::Float(self.arbitrary_float()),
+            3 => Value::Boolean(self.rng.gen()),
+            4 => Value::Timestamp(self.arbitrary_timestamp()),
+            5 => Value::Null,
+            6 => Value::Map(self.arbitrary_map(depth - 1, breadth)),
+            7 => {
+                let len = self.rng.gen_range(0..=breadth);
+                Value::Array(
+                    (0..len)
+                        .map(|_| self.arbitrary_value(depth - 1, breadth))
+                        .collect(),
+                )
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    /// Generate a metric covering any `MetricKind` and `MetricValue` variant.
+    pub fn arbitrary_metric(&mut self) -> Metric {
+        let kind = if self.rng.gen() {
+            MetricKind::Incremental
+        } else {
+            MetricKind::Absolute
+        };
+        let value = self.arbitrary_metric_value();
+        let name = self.arbitrary_key();
+
+        let namespace = self.rng.gen::<bool>().then(|| self.arbitrary_key());
+        let timestamp = self.rng.gen::<bool>().then(|| self.arbitrary_timestamp());
+        let tags = self.rng.gen::<bool>().then(|| {
+            let len = self.rng.gen_range(1..=DEFAULT_BREADTH);
+            (0..len)
+                .map(|_| (self.arbitrary_key(), self.arbitrary_string()))
+                .collect()
+        });
+
+        Metric::new(name, kind, value)
+            .with_namespace(namespace)
+            .with_timestamp(timestamp)
+            .with_tags(tags)
+    }
+
+    fn arbitrary_metric_value(&mut self) -> MetricValue {
+        let len = self.rng.gen_range(0..=MAX_METRIC_VALUES);
+        match self.rng.gen_range(0..7) {
+            0 => MetricValue::Counter {
+                value: self.arbitrary_float(),
+            },
+            1 => MetricValue::Gauge {
+                value: self.arbitrary_float(),
+            },
+            2 => MetricValue::Set {
+                values: (0..len)
+                    .map(|_| self.arbitrary_string())
+                    .collect::<BTreeSet<_>>(),
+            },
+            3 => MetricValue::Distribution {
+                samples: (0..len)
+                    .map(|_| Sample {
+                        value: self.arbitrary_float(),
+                        rate: self.rng.gen(),
+                    })
+                    .collect(),
+                statistic: if self.rng.gen() {
+                    StatisticKind::Histogram
+                } else {
+                    StatisticKind::Summary
+                },
+            },
+            4 => MetricValue::AggregatedHistogram {
+                buckets: (0..len)
+                    .map(|_| Bucket {
+                        upper_limit: self.arbitrary_float(),
+                        count: self.rng.gen(),
+                    })
+                    .collect(),
+                count: self.rng.gen(),
+                sum: self.arbitrary_float(),
+            },
+            5 => MetricValue::AggregatedSummary {
+                quantiles: (0..len)
+                    .map(|_| Quantile {
+                        quantile: self.rng.gen_range(0.0..=1.0),
+                        value: self.arbitrary_float(),
+                    })
+                    .collect(),
+                count: self.rng.gen(),
+                sum: self.arbitrary_float(),
+            },
+            6 => {
+                let mut sketch = AgentDDSketch::with_agent_defaults();
+                let samples = (0..len)
+                    .map(|_| self.rng.gen_range(-1_000_000.0..1_000_000.0))
+                    .collect::<Vec<f64>>();
+                sketch.insert_many(&samples);
+                MetricValue::Sketch {
+                    sketch: MetricSketch::AgentDDSketch(sketch),
+                }
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    fn arbitrary_map(&mut self, depth: usize, breadth: usize) -> BTreeMap<String, Value> {
+        let len = self.rng.gen_range(0..=breadth);
+        (0..len)
+            .map(|_| (self.arbitrary_key(), self.arbitrary_value(depth, breadth)))
+            .collect()
+    }
+
+    fn arbitrary_key(&mut self) -> String {
+        let len = self.rng.gen_range(1..=MAX_STR_SIZE);
+        (0..len)
+            .map(|_| char::from(*KEY_ALPHABET.choose(&mut self.rng).unwrap()))
+            .collect()
+    }
+
+    fn arbitrary_string(&mut self) -> String {
+        let len = self.rng.gen_range(0..=MAX_STR_SIZE);
+        (0..len)
+            .map(|_| *STRING_CHARS.choose(&mut self.rng).unwrap())
+            .collect()
+    }
+
+    /// Either valid UTF-8 or arbitrary, most likely invalid, raw bytes.
+    fn arbitrary_bytes(&mut self) -> Bytes {
+        if self.rng.gen() {
+            Bytes::from(self.arbitrary_string())
+        } else {
+            let len = self.rng.gen_range(1..=MAX_STR_SIZE);
+            let mut bytes = (0..len).map(|_| self.rng.gen()).collect::<Vec<u8>>();
+            // A lone continuation byte is never valid UTF-8.
+            bytes[0] = 0x80;
+            Bytes::from(bytes)
+        }
+    }
+
+    fn arbitrary_integer(&mut self) -> i64 {
+        if self.rng.gen_bool(0.25) {
+            *EXTREME_INTEGERS.choose(&mut self.rng).unwrap()
+        } else {
+            self.rng.gen()
+        }
+    }
+
+    fn arbitrary_float(&mut self) -> f64 {
+        if self.rng.gen_bool(0.25) {
+            *EXTREME_FLOATS.choose(&mut self.rng).unwrap()
+        } else {
+            self.rng.gen_range(-1_000_000.0..1_000_000.0)
+        }
+    }
+
+    fn arbitrary_timestamp(&mut self) -> DateTime<Utc> {
+        let secs = self.rng.gen_range(0..=MAX_TIMESTAMP_SECS);
+        let nanos = self.rng.gen_range(0..1_000_000_000);
+        DateTime::<Utc>::from_utc(NaiveDateTime::from_timestamp(secs, nanos), Utc)
+    }
+}
+
+impl Arbitrary for LogEvent {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+        any::<u64>()
+            .prop_map(|seed| {
+                EventGenerator::new(seed).arbitrary_log_event(DEFAULT_DEPTH, DEFAULT_BREADTH)
+            })
+            .boxed()
+    }
+}
+
+impl Arbitrary for Metric {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+        any::<u64>()
+            .prop_map(|seed| EventGenerator::new(seed).arbitrary_metric())
+            .boxed()
+    }
+}
+
+impl Arbitrary for Event {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+        proptest::prop_oneof![
+            any::<LogEvent>().prop_map(Event::Log),
+            any::<Metric>().prop_map(Event::Metric),
+        ]
+        .boxed()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashSet;
+
+    use proptest::prelude::*;
+
+    use super::*;
+
+    fn variant(value: &Value) -> &'static str {
+        match value {
+            Value::Bytes(bytes) if std::str::from_utf8(bytes).is_err() => "non-utf8 bytes",
+            Value::Bytes(_) => "bytes",
+            Value::Integer(_) => "integer",
+            Value::Float(_) => "float",
+            Value::Boolean(_) => "boolean",
+            Value::Timestamp(_) => "timestamp",
+            Value::Map(_) => "map",
+            Value::Array(_) => "array",
+            Value::Null => "null",
+        }
+    }
+
+    #[test]
+    fn same_seed_same_events() {
+        let mut first = EventGenerator::new(42);
+        let mut second = EventGenerator::new(42);
+        for _ in 0..10 {
+            assert_eq!(
+                first.arbitrary_log_event(3, 4),
+                second.arbitrary_log_event(3, 4)
+            );
+            assert_eq!(first.arbitrary_metric(), second.arbitrary_metric());
+        }
+    }
+
+    #[test]
+    fn covers_every_value_variant() {
+        let mut generator = EventGenerator::from_env();
+        let mut seen = HashSet::new();
+        for _ in 0..500 {
+            seen.insert(variant(&generator.arbitrary_value(1, 2)));
+        }
+        assert_eq!(seen.len(), 9, "seed {}: saw {:?}", generator.seed(), seen);
+    }
+
+    #[test]
+    fn respects_depth() {
+        fn depth(value: &Value) -> usize {
+            match value {
+                Value::Map(map) => 1 + map.values().map(depth).max().unwrap_or(0),
+                Value::Array(array) => 1 + array.iter().map(depth).max().unwrap_or(0),
+                _ => 0,
+            }
+        }
+
+        let mut generator = EventGenerator::from_env();
+        for _ in 0..100 {
+            let value = generator.arbitrary_value(2, 3);
+            assert!(depth(&value) <= 2, "seed {}", generator.seed());
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn log_events_serialize_to_json_objects(event in any::<LogEvent>()) {
+            let bytes = event.to_json_vec().unwrap();
+            let json: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+            prop_assert!(json.is_object());
+        }
+    }
+}
@@ -0,0 +1,88 @@
+use std::{
+    convert::TryFrom,
+    fs::{self, File},
+    io::{BufRead, BufReader},
+    path::Path,
+    task::{Context, Poll},
+};
+
+use futures::{task::noop_waker_ref, Stream, StreamExt};
+
+use crate::event::LogEvent;
+
+mod generator;
+
+pub use generator::{EventGenerator, SEED_ENV_VAR};
+
+/// Read a single JSON document from a fixture file.
+///
+/// # Errors
+///
+/// Returns an error if the file can't be opened or doesn't contain valid JSON.
+pub fn open_fixture(path: impl AsRef<Path>) -> crate::Result<serde_json::Value> {
+    serde_json::from_reader(File::open(path)?).map_err(Into::into)
+}
+
+/// Read a fixture holding one JSON document per line. Blank lines are skipped.
+///
+/// # Errors
+///
+/// Returns an error if the file can't be read, or naming the offending line
+/// number if any line isn't valid JSON.
+pub fn open_fixture_ndjson(path: impl AsRef<Path>) -> crate::Result<Vec<serde_json::Value>> {
+    let path = path.as_ref();
+    let reader = BufReader::new(File::open(path)?);
+
+    let mut values = Vec::new();
+    for (index, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let value = serde_json::from_str(&line)
+            .map_err(|error| format!("{}:{}: {}", path.display(), index + 1, error))?;
+        values.push(value);
+    }
+    Ok(values)
+}
+
+/// Load every `.json` and `.ndjson` fixture directly inside `dir` as log
+/// events, ordered by file name and then by line. Subdirectories are skipped.
+///
+/// # Errors
+///
+/// Returns an error if any fixture can't be read or holds a JSON document
+/// that isn't an object.
+pub fn fixture_log_events(dir: impl AsRef<Path>) -> crate::Result<Vec<LogEvent>> {
+    let mut paths = fs::read_dir(dir)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<Result<Vec<_>, _>>()?;
+    paths.sort();
+
+    let mut events = Vec::new();
+    for path in paths {
+        let values = match path.extension().and_then(|ext| ext.to_str()) {
+            Some("json") => vec![open_fixture(&path)?],
+            Some("ndjson") => open_fixture_ndjson(&path)?,
+            _ => continue,
+        };
+        for value in values {
+            events.push(LogEvent::try_from(value)?);
+        }
+    }
+    Ok(events)
+}
+
+pub fn collect_ready<S>(mut rx: S) -> Vec<S::Item>
+where
+    S: Stream + Unpin,
+{
+    let waker = noop_waker_ref();
+    let mut cx = Context::from_waker(waker);
+
+    let mut vec = Vec::new();
+    while let Poll::Ready(Some(item)) = rx.poll_next_unpin(&mut cx) {
+        vec.push(item);
+    }
+    vec
+}
@@ -30,7 +30,11 @@ impl Function for GetMetadataField {
         _ctx: &FunctionCompileContext,
         mut arguments: ArgumentList,
     ) -> Compiled {
-        let keys = vec![value!("datadog_api_key"), value!("splunk_hec_token")];
+        let keys = vec![
+            value!("datadog_api_key"),
+            value!("splunk_hec_token"),
+            value!("dropped"),
+        ];
         let key = arguments
             .required_enum("key", &keys)?
             .try_bytes_utf8_lossy()
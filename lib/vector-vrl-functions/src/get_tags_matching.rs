@@ -0,0 +1,63 @@
+use std::collections::BTreeMap;
+
+use vrl_core::prelude::*;
+
+#[derive(Clone, Copy, Debug)]
+pub struct GetTagsMatching;
+
+impl Function for GetTagsMatching {
+    fn identifier(&self) -> &'static str {
+        "get_tags_matching"
+    }
+
+    fn parameters(&self) -> &'static [Parameter] {
+        &[Parameter {
+            keyword: "prefix",
+            kind: kind::BYTES,
+            required: true,
+        }]
+    }
+
+    fn examples(&self) -> &'static [Example] {
+        &[Example {
+            title: "Get the AWS tags",
+            source: r#"get_tags_matching!("aws_")"#,
+            result: Ok("{}"),
+        }]
+    }
+
+    fn compile(
+        &self,
+        _state: &state::Compiler,
+        _ctx: &FunctionCompileContext,
+        mut arguments: ArgumentList,
+    ) -> Compiled {
+        let prefix = arguments.required("prefix");
+
+        Ok(Box::new(GetTagsMatchingFn { prefix }))
+    }
+}
+
+#[derive(Debug, Clone)]
+struct GetTagsMatchingFn {
+    prefix: Box<dyn Expression>,
+}
+
+impl Expression for GetTagsMatchingFn {
+    fn resolve(&self, ctx: &mut Context) -> Resolved {
+        let value = self.prefix.resolve(ctx)?;
+        let prefix = value.try_bytes_utf8_lossy()?;
+
+        ctx.target().get_tags_matching(&prefix).map_err(Into::into)
+    }
+
+    fn type_def(&self, _: &state::Compiler) -> TypeDef {
+        TypeDef::new().fallible().object::<(), Kind>(type_def())
+    }
+}
+
+pub(crate) fn type_def() -> BTreeMap<(), Kind> {
+    map! {
+        (): Kind::Bytes,
+    }
+}
@@ -1,11 +1,15 @@
 pub mod get_metadata_field;
+pub mod get_tags_matching;
 pub mod remove_metadata_field;
+pub mod remove_tags_matching;
 pub mod set_metadata_field;
 
 pub fn vrl_functions() -> Vec<Box<dyn vrl_core::Function>> {
     vec![
         Box::new(get_metadata_field::GetMetadataField) as Box<dyn vrl_core::Function>,
+        Box::new(get_tags_matching::GetTagsMatching) as Box<dyn vrl_core::Function>,
         Box::new(remove_metadata_field::RemoveMetadataField) as Box<dyn vrl_core::Function>,
+        Box::new(remove_tags_matching::RemoveTagsMatching) as Box<dyn vrl_core::Function>,
         Box::new(set_metadata_field::SetMetadataField) as Box<dyn vrl_core::Function>,
     ]
 }
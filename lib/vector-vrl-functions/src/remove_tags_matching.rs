@@ -0,0 +1,59 @@
+use vrl_core::prelude::*;
+
+use crate::get_tags_matching::type_def;
+
+#[derive(Clone, Copy, Debug)]
+pub struct RemoveTagsMatching;
+
+impl Function for RemoveTagsMatching {
+    fn identifier(&self) -> &'static str {
+        "remove_tags_matching"
+    }
+
+    fn parameters(&self) -> &'static [Parameter] {
+        &[Parameter {
+            keyword: "prefix",
+            kind: kind::BYTES,
+            required: true,
+        }]
+    }
+
+    fn examples(&self) -> &'static [Example] {
+        &[Example {
+            title: "Remove the AWS tags",
+            source: r#"remove_tags_matching!("aws_")"#,
+            result: Ok("{}"),
+        }]
+    }
+
+    fn compile(
+        &self,
+        _state: &state::Compiler,
+        _ctx: &FunctionCompileContext,
+        mut arguments: ArgumentList,
+    ) -> Compiled {
+        let prefix = arguments.required("prefix");
+
+        Ok(Box::new(RemoveTagsMatchingFn { prefix }))
+    }
+}
+
+#[derive(Debug, Clone)]
+struct RemoveTagsMatchingFn {
+    prefix: Box<dyn Expression>,
+}
+
+impl Expression for RemoveTagsMatchingFn {
+    fn resolve(&self, ctx: &mut Context) -> Resolved {
+        let value = self.prefix.resolve(ctx)?;
+        let prefix = value.try_bytes_utf8_lossy()?;
+
+        ctx.target_mut()
+            .remove_tags_matching(&prefix)
+            .map_err(Into::into)
+    }
+
+    fn type_def(&self, _: &state::Compiler) -> TypeDef {
+        TypeDef::new().fallible().object::<(), Kind>(type_def())
+    }
+}
@@ -45,6 +45,15 @@ pub trait Target: std::fmt::Debug {
     /// See [`Target::insert`] for more details.
     fn get(&self, path: &LookupBuf) -> Result<Option<Value>, String>;
 
+    /// Check whether a value exists at the given path.
+    ///
+    /// The default implementation gets the value and discards it. Implementors
+    /// that have to clone or build the value in [`Target::get`] should override
+    /// this to answer without doing so.
+    fn contains(&self, path: &LookupBuf) -> Result<bool, String> {
+        self.get(path).map(|value| value.is_some())
+    }
+
     /// Remove the given path from the object.
     ///
     /// Returns the removed object, if any.
@@ -64,4 +73,16 @@ pub trait Target: std::fmt::Debug {
     fn remove_metadata(&mut self, _key: &str) -> Result<(), String> {
         Err("metadata not available".to_string())
     }
+
+    /// Get the tags whose keys start with `prefix` as an object, without
+    /// copying the others.
+    fn get_tags_matching(&self, _prefix: &str) -> Result<Value, String> {
+        Err("tags not available".to_string())
+    }
+
+    /// Remove the tags whose keys start with `prefix`, returning them as an
+    /// object like [`Target::get_tags_matching`] does.
+    fn remove_tags_matching(&mut self, _prefix: &str) -> Result<Value, String> {
+        Err("tags not available".to_string())
+    }
 }
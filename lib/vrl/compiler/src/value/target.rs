@@ -14,6 +14,10 @@ impl Target for Value {
         Ok(self.get_by_path(path).cloned())
     }
 
+    fn contains(&self, path: &LookupBuf) -> Result<bool, String> {
+        Ok(self.get_by_path(path).is_some())
+    }
+
     fn remove(&mut self, path: &LookupBuf, compact: bool) -> Result<Option<Value>, String> {
         let value = self.get(path)?;
         self.remove_by_path(path, compact);
@@ -53,7 +53,7 @@ impl Expression for ExistsFn {
         let path = self.query.path();
 
         if self.query.is_external() {
-            return Ok(ctx.target_mut().get(path).ok().flatten().is_some().into());
+            return Ok(ctx.target().contains(path).unwrap_or(false).into());
         }
 
         if let Some(ident) = self.query.variable_ident() {
@@ -0,0 +1,272 @@
+//! Counting the events a component drops, to log them as one summary per reason and interval
+//! rather than one line each, which floods the logs of a busy component.
+
+use std::{
+    collections::BTreeMap,
+    fmt,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use tokio::{
+    sync::Notify,
+    time::{self, Instant},
+};
+use tracing_futures::Instrument;
+use vector_core::event::EventId;
+
+use crate::internal_events::EventsDroppedSummary;
+
+pub const fn default_interval_secs() -> u64 {
+    10
+}
+
+/// Why a component dropped an event.
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub enum DropReason {
+    /// Larger than the component allows.
+    Oversized,
+    /// It couldn't be encoded.
+    EncodeFailed,
+    /// It couldn't be decoded.
+    DecodeFailed,
+    /// Of a type the component doesn't know.
+    UnknownEventType,
+    /// With more fields than the component allows.
+    TooManyFields,
+}
+
+impl DropReason {
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Oversized => "oversized",
+            Self::EncodeFailed => "encode_failed",
+            Self::DecodeFailed => "decode_failed",
+            Self::UnknownEventType => "unknown_event_type",
+            Self::TooManyFields => "too_many_fields",
+        }
+    }
+}
+
+impl fmt::Display for DropReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// The drops of one reason within an interval.
+#[derive(Debug, PartialEq)]
+pub struct DropSummary {
+    pub reason: DropReason,
+    pub count: usize,
+    /// The id of the first event dropped, if it had one.
+    pub example_event_id: Option<EventId>,
+    /// Why the first event was dropped.
+    pub example_error: String,
+}
+
+/// The counts shared by the clones of a reporter and the task emitting their summaries.
+#[derive(Debug, Default)]
+struct Tallies {
+    summaries: Mutex<BTreeMap<DropReason, DropSummary>>,
+    /// Notified once every clone of the reporter is dropped.
+    closed: Notify,
+}
+
+impl Tallies {
+    fn take(&self) -> BTreeMap<DropReason, DropSummary> {
+        std::mem::take(&mut *self.summaries.lock().expect("mutex poisoned"))
+    }
+}
+
+/// Tells the task emitting the summaries that the reporter is gone when the last clone drops it.
+#[derive(Debug, Default)]
+struct Handle {
+    tallies: Arc<Tallies>,
+}
+
+impl Drop for Handle {
+    fn drop(&mut self) {
+        self.tallies.closed.notify_one();
+    }
+}
+
+/// Where a component reports the events it drops. The drops are summed up by reason, and each
+/// interval a summary of every reason there were drops for is emitted.
+///
+/// Clones share their counts. Once every clone is dropped, the drops of the interval that was
+/// under way are summarized right away and the summaries stop.
+#[derive(Clone, Debug, Default)]
+pub struct DropReporter {
+    handle: Arc<Handle>,
+}
+
+impl DropReporter {
+    /// Count an event dropped for `reason`. The event id and error of the first event of each
+    /// reason are kept as an example.
+    pub fn report(&self, reason: DropReason, event_id: Option<EventId>, error: &str) {
+        self.handle
+            .tallies
+            .summaries
+            .lock()
+            .expect("mutex poisoned")
+            .entry(reason)
+            .or_insert_with(|| DropSummary {
+                reason,
+                count: 0,
+                example_event_id: event_id,
+                example_error: error.to_owned(),
+            })
+            .count += 1;
+    }
+
+    /// Emit the summaries every `interval`, within the current span so that they are logged as
+    /// coming from the component.
+    pub fn spawn(&self, interval: Duration) {
+        self.spawn_with(interval, move |summary| {
+            emit!(&EventsDroppedSummary {
+                reason: summary.reason.as_str(),
+                count: summary.count,
+                interval,
+                example_event_id: summary.example_event_id,
+                example_error: &summary.example_error,
+            })
+        });
+    }
+
+    fn spawn_with(&self, interval: Duration, on_summary: impl FnMut(DropSummary) + Send + 'static) {
+        tokio::spawn(
+            emit_summaries(Arc::clone(&self.handle.tallies), interval, on_summary)
+                .in_current_span(),
+        );
+    }
+}
+
+async fn emit_summaries(
+    tallies: Arc<Tallies>,
+    interval: Duration,
+    mut on_summary: impl FnMut(DropSummary),
+) {
+    let mut ticks = time::interval_at(Instant::now() + interval, interval);
+    loop {
+        tokio::select! {
+            _ = ticks.tick() => tallies.take().into_values().for_each(&mut on_summary),
+            _ = tallies.closed.notified() => {
+                // Flush the drops of the interval cut short, which would be lost otherwise.
+                tallies.take().into_values().for_each(&mut on_summary);
+                break;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn collecting_reporter(interval: Duration) -> (DropReporter, Arc<Mutex<Vec<DropSummary>>>) {
+        let reporter = DropReporter::default();
+        let summaries = Arc::new(Mutex::new(Vec::new()));
+        let collected = Arc::clone(&summaries);
+        reporter.spawn_with(interval, move |summary| {
+            collected.lock().unwrap().push(summary)
+        });
+        (reporter, summaries)
+    }
+
+    async fn advance(duration: Duration) {
+        time::advance(duration).await;
+        // Let the reporting task run.
+        tokio::task::yield_now().await;
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn summarizes_once_per_interval() {
+        let interval = Duration::from_secs(10);
+        let (reporter, summaries) = collecting_reporter(interval);
+        let first = EventId::from_bytes([1; 16]);
+
+        reporter.report(DropReason::Oversized, Some(first), "too large");
+        reporter.report(DropReason::Oversized, None, "also too large");
+        advance(Duration::from_secs(9)).await;
+        assert!(summaries.lock().unwrap().is_empty());
+
+        advance(Duration::from_secs(1)).await;
+        assert_eq!(
+            std::mem::take(&mut *summaries.lock().unwrap()),
+            vec![DropSummary {
+                reason: DropReason::Oversized,
+                count: 2,
+                example_event_id: Some(first),
+                example_error: "too large".to_owned(),
+            }]
+        );
+
+        // A window without drops has nothing to summarize.
+        advance(interval).await;
+        assert!(summaries.lock().unwrap().is_empty());
+
+        // The next window starts counting afresh.
+        reporter.report(DropReason::Oversized, None, "too large again");
+        advance(interval).await;
+        let summaries = summaries.lock().unwrap();
+        assert_eq!(summaries.len(), 1);
+        assert_eq!(summaries[0].count, 1);
+        assert_eq!(summaries[0].example_error, "too large again");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn summarizes_each_reason_apart() {
+        let interval = Duration::from_secs(10);
+        let (reporter, summaries) = collecting_reporter(interval);
+
+        reporter.report(DropReason::TooManyFields, None, "too many fields");
+        reporter.report(DropReason::UnknownEventType, None, "unknown type");
+        reporter.report(DropReason::TooManyFields, None, "too many fields");
+        advance(interval).await;
+
+        let counts = summaries
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|summary| (summary.reason, summary.count))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            counts,
+            vec![
+                (DropReason::UnknownEventType, 1),
+                (DropReason::TooManyFields, 2)
+            ]
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn flushes_and_stops_with_the_reporter() {
+        let interval = Duration::from_secs(10);
+        let (reporter, summaries) = collecting_reporter(interval);
+        let clone = reporter.clone();
+
+        reporter.report(DropReason::EncodeFailed, None, "untruncatable");
+        drop(reporter);
+        advance(Duration::from_secs(1)).await;
+        assert!(summaries.lock().unwrap().is_empty());
+
+        // The last clone going flushes the interval under way without waiting for it to end.
+        clone.report(DropReason::EncodeFailed, None, "untruncatable");
+        drop(clone);
+        advance(Duration::from_secs(1)).await;
+        assert_eq!(
+            std::mem::take(&mut *summaries.lock().unwrap()),
+            vec![DropSummary {
+                reason: DropReason::EncodeFailed,
+                count: 2,
+                example_event_id: None,
+                example_error: "untruncatable".to_owned(),
+            }]
+        );
+
+        // And nothing is summarized after that.
+        advance(interval).await;
+        assert!(summaries.lock().unwrap().is_empty());
+    }
+}
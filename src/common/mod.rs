@@ -1,2 +1,4 @@
 #[cfg(any(feature = "sources-datadog_agent", feature = "sinks-datadog_metrics"))]
 pub(crate) mod datadog;
+#[cfg(any(feature = "sources-vector", feature = "sinks-vector"))]
+pub(crate) mod drop_summary;
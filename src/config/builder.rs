@@ -212,6 +212,9 @@ impl ConfigBuilder {
 
         self.global.proxy = self.global.proxy.merge(&with.global.proxy);
 
+        self.global.telemetry.merge(&with.global.telemetry);
+        self.global.redaction.merge(&with.global.redaction);
+
         if self.global.data_dir.is_none() || self.global.data_dir == default_data_dir() {
             self.global.data_dir = with.global.data_dir;
         } else if with.global.data_dir != default_data_dir()
@@ -20,6 +20,10 @@ pub fn compile(mut builder: ConfigBuilder) -> Result<(Config, Vec<String>), Vec<
         errors.extend(name_errors);
     }
 
+    if let Err(schema_errors) = validation::check_log_schema(&builder) {
+        errors.extend(schema_errors);
+    }
+
     let expansions = expand_macros(&mut builder)?;
 
     expand_globs(&mut builder);
@@ -32,6 +36,10 @@ pub fn compile(mut builder: ConfigBuilder) -> Result<(Config, Vec<String>), Vec<
         errors.extend(type_errors);
     }
 
+    if let Err(buffer_errors) = validation::check_buffers(&builder) {
+        errors.extend(buffer_errors);
+    }
+
     #[cfg(feature = "datadog-pipelines")]
     let version = Some(builder.sha256_hash());
 
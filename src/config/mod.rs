@@ -53,8 +53,8 @@ pub use loading::{
     merge_path_lists, process_paths, CONFIG_PATHS,
 };
 pub use unit_test::build_unit_tests_main as build_unit_tests;
-pub use validation::warnings;
-pub use vector_core::config::{log_schema, proxy::ProxyConfig, LogSchema};
+pub use validation::{check_non_zero, warnings, ConfigError, ConfigErrors, DryRunReport};
+pub use vector_core::config::{log_schema, proxy::ProxyConfig, LogSchema, LogSchemaOverrides};
 
 /// Loads Log Schema from configurations and sets global schema.
 /// Once this is done, configurations can be correctly loaded using
@@ -199,6 +199,21 @@ pub trait SourceConfig: core::fmt::Debug + Send + Sync {
     fn resources(&self) -> Vec<Resource> {
         Vec::new()
     }
+
+    /// The keys this source sets in place of those of the global log schema,
+    /// if any.
+    fn log_schema(&self) -> Option<&LogSchemaOverrides> {
+        None
+    }
+
+    /// Go through the setup that building the source does and that can fail,
+    /// such as loading TLS files, without serving anything. Binding its
+    /// address is only tried with `bind_addresses`, since it fails while
+    /// another process, such as a running Vector, holds the address. `None`
+    /// for sources without a dry run.
+    async fn dry_run(&self, _bind_addresses: bool) -> Option<DryRunReport> {
+        None
+    }
 }
 
 pub struct SourceContext {
@@ -207,9 +222,16 @@ pub struct SourceContext {
     pub shutdown: ShutdownSignal,
     pub out: Pipeline,
     pub proxy: ProxyConfig,
+    pub log_schema: Option<LogSchema>,
 }
 
 impl SourceContext {
+    /// The log schema this source should stamp events with, falling back to
+    /// the global schema when the source doesn't override it.
+    pub fn log_schema(&self) -> &LogSchema {
+        self.log_schema.as_ref().unwrap_or_else(|| log_schema())
+    }
+
     #[cfg(test)]
     pub fn new_shutdown(
         key: &ComponentKey,
@@ -224,6 +246,7 @@ impl SourceContext {
                 shutdown: shutdown_signal,
                 out,
                 proxy: Default::default(),
+                log_schema: None,
             },
             shutdown,
         )
@@ -237,6 +260,7 @@ impl SourceContext {
             shutdown: ShutdownSignal::noop(),
             out,
             proxy: Default::default(),
+            log_schema: None,
         }
     }
 }
@@ -282,6 +306,13 @@ impl<T> SinkOuter<T> {
         }
     }
 
+    fn has_disk_buffer(&self) -> bool {
+        self.buffer
+            .stages()
+            .iter()
+            .any(|stage| matches!(stage, BufferType::DiskV1 { .. } | BufferType::DiskV2 { .. }))
+    }
+
     pub fn resources(&self, id: &ComponentKey) -> Vec<Resource> {
         let mut resources = self.inner.resources();
         for stage in self.buffer.stages() {
@@ -381,6 +412,21 @@ pub trait SinkConfig: core::fmt::Debug + Send + Sync {
     fn resources(&self) -> Vec<Resource> {
         Vec::new()
     }
+
+    /// Whether the sink writes the events it couldn't deliver to a spool of
+    /// its own when it stops. Those events are never acknowledged to its
+    /// buffer, so a disk buffer would send them again as well.
+    fn spools_undelivered(&self) -> bool {
+        false
+    }
+
+    /// Go through the setup that building the sink does and that can fail,
+    /// such as loading TLS files and parsing its endpoint, without sending
+    /// anything. Connecting to the endpoint is only tried with
+    /// `check_connectivity`. `None` for sinks without a dry run.
+    async fn dry_run(&self, _cx: SinkContext, _check_connectivity: bool) -> Option<DryRunReport> {
+        None
+    }
 }
 
 #[derive(Debug, Clone)]
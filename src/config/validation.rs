@@ -1,7 +1,98 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, fmt};
 
 use super::{builder::ConfigBuilder, ComponentKey, Config, OutputId, Resource};
 
+/// A problem with one option of a component.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ConfigError {
+    /// The path of the option, such as `tls.crt_file`.
+    pub field: String,
+    pub message: String,
+}
+
+impl ConfigError {
+    pub fn new(field: impl Into<String>, message: impl ToString) -> Self {
+        Self {
+            field: field.into(),
+            message: message.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.field, self.message)
+    }
+}
+
+/// Every problem found with the options of a component, reported together so
+/// that they can all be fixed at once.
+#[derive(Debug)]
+pub struct ConfigErrors(pub Vec<ConfigError>);
+
+impl fmt::Display for ConfigErrors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Invalid configuration: ")?;
+        for (index, error) in self.0.iter().enumerate() {
+            if index > 0 {
+                f.write_str("; ")?;
+            }
+            write!(f, "{}", error)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ConfigErrors {}
+
+/// Report each of `fields` that is set to zero, for options that must be greater than zero when
+/// they are set.
+pub fn check_non_zero<T: Default + PartialEq>(
+    errors: &mut Vec<ConfigError>,
+    fields: impl IntoIterator<Item = (&'static str, Option<T>)>,
+) {
+    errors.extend(
+        fields
+            .into_iter()
+            .filter(|(_, value)| *value == Some(T::default()))
+            .map(|(field, _)| ConfigError::new(field, "must be greater than zero")),
+    );
+}
+
+/// What a dry run of a component found: the setup steps it got through, and
+/// the problems that stopped it.
+#[derive(Debug, Default)]
+pub struct DryRunReport {
+    /// The steps that succeeded, such as `tls` or `bind`, in the order they ran.
+    pub passed: Vec<&'static str>,
+    pub errors: Vec<ConfigError>,
+}
+
+impl DryRunReport {
+    /// Record how `step` went, blaming `field` if it failed.
+    pub fn check<T, E: fmt::Display>(
+        &mut self,
+        step: &'static str,
+        field: &str,
+        result: Result<T, E>,
+    ) -> Option<T> {
+        match result {
+            Ok(value) => {
+                self.passed.push(step);
+                Some(value)
+            }
+            Err(error) => {
+                self.errors.push(ConfigError::new(field, error));
+                None
+            }
+        }
+    }
+
+    pub fn is_ok(&self) -> bool {
+        self.errors.is_empty()
+    }
+}
+
 /// Check that provide + topology config aren't present in the same builder, which is an error.
 pub fn check_provider(config: &ConfigBuilder) -> Result<(), Vec<String>> {
     if config.provider.is_some()
@@ -15,6 +106,58 @@ pub fn check_provider(config: &ConfigBuilder) -> Result<(), Vec<String>> {
     }
 }
 
+/// Check that the configured log schema keys, both global and per-source, are valid field paths.
+pub fn check_log_schema(config: &ConfigBuilder) -> Result<(), Vec<String>> {
+    let mut errors = config
+        .global
+        .log_schema
+        .validate()
+        .err()
+        .unwrap_or_default();
+
+    for (key, source) in &config.sources {
+        if let Some(Err(source_errors)) = source
+            .inner
+            .log_schema()
+            .map(|overrides| overrides.validate())
+        {
+            errors.extend(
+                source_errors
+                    .into_iter()
+                    .map(|error| format!("Source \"{}\": {}", key, error)),
+            );
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+/// Check that sinks spooling the events they couldn't deliver don't have a disk buffer, which would
+/// send those events again.
+pub fn check_buffers(config: &ConfigBuilder) -> Result<(), Vec<String>> {
+    let errors: Vec<_> = config
+        .sinks
+        .iter()
+        .filter(|(_, sink)| sink.inner.spools_undelivered() && sink.has_disk_buffer())
+        .map(|(key, _)| {
+            format!(
+                "Sink \"{}\": Spooling undelivered events can't be combined with a disk buffer.",
+                key
+            )
+        })
+        .collect();
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
 pub fn check_names<'a, I: Iterator<Item = &'a ComponentKey>>(names: I) -> Result<(), Vec<String>> {
     let errors: Vec<_> = names
         .filter(|component_key| component_key.id().contains('.'))
@@ -1,6 +1,6 @@
 use std::time::Duration;
 
-use metrics::histogram;
+use metrics::{gauge, histogram};
 use vector_core::internal_event::InternalEvent;
 
 #[derive(Debug)]
@@ -31,6 +31,10 @@ impl InternalEvent for AdaptiveConcurrencyLimit {
         // values over each reporting interval, and each of those values
         // is valuable for diagnosis.
         histogram!("adaptive_concurrency_limit", self.concurrency as f64);
+        gauge!(
+            "adaptive_concurrency_current_limit",
+            self.concurrency as f64
+        );
         let reached_limit = self.reached_limit.then(|| 1.0).unwrap_or_default();
         histogram!("adaptive_concurrency_reached_limit", reached_limit);
         let back_pressure = self.had_back_pressure.then(|| 1.0).unwrap_or_default();
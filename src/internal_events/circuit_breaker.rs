@@ -0,0 +1,64 @@
+// ## skip check-events ##
+
+use std::time::Duration;
+
+use metrics::{counter, gauge};
+use vector_core::internal_event::InternalEvent;
+
+#[derive(Debug)]
+pub struct CircuitBreakerOpened {
+    pub failure_rate: f64,
+    pub open_duration: Duration,
+}
+
+impl InternalEvent for CircuitBreakerOpened {
+    fn emit_logs(&self) {
+        warn!(
+            message = "Circuit breaker opened; rejecting requests until it half-opens.",
+            failure_rate = %self.failure_rate,
+            open_duration_secs = %self.open_duration.as_secs(),
+        );
+    }
+
+    fn emit_metrics(&self) {
+        counter!("circuit_breaker_opened_total", 1);
+        gauge!("circuit_breaker_open", 1.0);
+    }
+}
+
+/// Not logged, as the breaker already logged opening.
+#[derive(Debug)]
+pub struct CircuitBreakerRequestRejected;
+
+impl InternalEvent for CircuitBreakerRequestRejected {
+    fn emit_metrics(&self) {
+        counter!("circuit_breaker_rejected_requests_total", 1);
+    }
+}
+
+#[derive(Debug)]
+pub struct CircuitBreakerHalfOpened {
+    pub probes: usize,
+}
+
+impl InternalEvent for CircuitBreakerHalfOpened {
+    fn emit_logs(&self) {
+        info!(
+            message = "Circuit breaker half-open; sending probe requests.",
+            probes = %self.probes,
+        );
+    }
+}
+
+#[derive(Debug)]
+pub struct CircuitBreakerClosed;
+
+impl InternalEvent for CircuitBreakerClosed {
+    fn emit_logs(&self) {
+        info!(message = "Circuit breaker closed; resuming requests.");
+    }
+
+    fn emit_metrics(&self) {
+        gauge!("circuit_breaker_open", 0.0);
+    }
+}
@@ -1,6 +1,8 @@
+use std::time::Duration;
+
 use metrics::counter;
 pub use vector_core::internal_event::EventsReceived;
-use vector_core::internal_event::InternalEvent;
+use vector_core::{event::EventId, internal_event::InternalEvent};
 
 #[derive(Debug)]
 pub struct BytesReceived {
@@ -18,6 +20,30 @@ impl InternalEvent for BytesReceived {
     }
 }
 
+/// The events a component dropped for one reason within an interval, logged in place of a line
+/// for each. Metrics are left to the events emitted for each drop.
+#[derive(Debug)]
+pub struct EventsDroppedSummary<'a> {
+    pub reason: &'static str,
+    pub count: usize,
+    pub interval: Duration,
+    pub example_event_id: Option<EventId>,
+    pub example_error: &'a str,
+}
+
+impl InternalEvent for EventsDroppedSummary<'_> {
+    fn emit_logs(&self) {
+        warn!(
+            message = "Events dropped.",
+            reason = %self.reason,
+            count = %self.count,
+            interval_secs = %self.interval.as_secs(),
+            example_event_id = ?self.example_event_id,
+            example_error = %self.example_error,
+        );
+    }
+}
+
 #[derive(Debug)]
 pub struct HttpClientBytesReceived<'a> {
     pub byte_size: usize,
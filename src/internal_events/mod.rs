@@ -29,6 +29,7 @@ mod aws_sqs;
 pub(crate) mod azure_blob;
 mod batch;
 mod blackhole;
+mod circuit_breaker;
 #[cfg(feature = "transforms-coercer")]
 mod coercer;
 mod common;
@@ -284,9 +285,9 @@ pub(crate) use self::tokenizer::*;
 pub use self::windows::*;
 pub use self::{
     adaptive_concurrency::*, add_fields::*, add_tags::*, aggregate::*, ansi_stripper::*, batch::*,
-    blackhole::*, common::*, conditions::*, elasticsearch::*, encoding_transcode::*, heartbeat::*,
-    logplex::*, open::*, process::*, pulsar::*, remap::*, sample::*, split::*, stdin::*, syslog::*,
-    tcp::*, template::*, udp::*, unix::*, vector::*,
+    blackhole::*, circuit_breaker::*, common::*, conditions::*, elasticsearch::*,
+    encoding_transcode::*, heartbeat::*, logplex::*, open::*, process::*, pulsar::*, remap::*,
+    sample::*, split::*, stdin::*, syslog::*, tcp::*, template::*, udp::*, unix::*, vector::*,
 };
 
 // this version won't be needed once all `InternalEvent`s implement `name()`
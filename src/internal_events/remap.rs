@@ -1,7 +1,7 @@
 // ## skip check-events ##
 
 use metrics::counter;
-use vector_core::internal_event::InternalEvent;
+use vector_core::{event::PathStatsReport, internal_event::InternalEvent};
 
 #[derive(Debug)]
 pub struct RemapMappingError {
@@ -50,3 +50,85 @@ impl InternalEvent for RemapMappingAbort {
         debug!(message, internal_log_rate_secs = 30)
     }
 }
+
+#[derive(Debug)]
+pub struct RemapEventMarkedDropped<'a> {
+    /// The reason the program gave when marking the event as dropped.
+    pub reason: &'a str,
+}
+
+impl<'a> InternalEvent for RemapEventMarkedDropped<'a> {
+    fn emit_logs(&self) {
+        debug!(
+            message = "Event marked as dropped by the program; discarding event.",
+            reason = %self.reason,
+            internal_log_rate_secs = 30
+        )
+    }
+
+    fn emit_metrics(&self) {
+        counter!("events_discarded_total", 1,
+                 "reason" => "marked_dropped");
+    }
+}
+
+#[derive(Debug)]
+pub struct RemapFanoutTruncated {
+    pub original_count: usize,
+    pub resulting_count: usize,
+}
+
+impl InternalEvent for RemapFanoutTruncated {
+    fn emit_logs(&self) {
+        warn!(
+            message = "Event split into more events than allowed by `max_fanout`; discarding the rest.",
+            original_count = %self.original_count,
+            resulting_count = %self.resulting_count,
+            internal_log_rate_secs = 30
+        )
+    }
+
+    fn emit_metrics(&self) {
+        counter!("events_discarded_total", (self.original_count - self.resulting_count) as u64,
+                 "reason" => "max_fanout");
+    }
+}
+
+#[derive(Debug)]
+pub struct RemapPathStatsReported<'a> {
+    pub report: &'a PathStatsReport,
+}
+
+impl<'a> InternalEvent for RemapPathStatsReported<'a> {
+    fn emit_logs(&self) {
+        debug!(
+            message = "Reporting the operations of the program on each path.",
+            paths = %self.report.paths.len(),
+        );
+    }
+
+    fn emit_metrics(&self) {
+        let paths = self
+            .report
+            .paths
+            .iter()
+            .map(|(path, counts)| (path.as_str(), counts));
+        // Not a valid path, so it can't be mistaken for one.
+        for (path, counts) in paths.chain(std::iter::once(("<other>", &self.report.other))) {
+            for (operation, count) in [
+                ("get", counts.gets),
+                ("set", counts.sets),
+                ("remove", counts.removes),
+            ] {
+                if count > 0 {
+                    counter!("remap_path_operations_total", count,
+                             "path" => path.to_owned(), "operation" => operation);
+                }
+            }
+            if counts.misses > 0 {
+                counter!("remap_path_misses_total", counts.misses,
+                         "path" => path.to_owned());
+            }
+        }
+    }
+}
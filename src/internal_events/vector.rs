@@ -1,8 +1,16 @@
 // ## skip check-events ##
 
-use metrics::counter;
+use std::{
+    collections::BTreeMap,
+    io,
+    net::{IpAddr, SocketAddr},
+    path::Path,
+    time::Duration,
+};
+
+use metrics::{counter, gauge, histogram, register_histogram, Unit};
 use prost::DecodeError;
-use vector_core::internal_event::InternalEvent;
+use vector_core::{event::EventId, internal_event::InternalEvent};
 
 #[derive(Debug)]
 pub struct VectorEventReceived {
@@ -21,6 +29,32 @@ impl InternalEvent for VectorEventReceived {
     }
 }
 
+#[derive(Debug)]
+pub struct VectorRequestBytesReceived {
+    pub byte_size: usize,
+    pub peer_addr: Option<IpAddr>,
+}
+
+impl InternalEvent for VectorRequestBytesReceived {
+    fn emit_logs(&self) {
+        trace!(message = "Bytes received.", byte_size = %self.byte_size, peer_addr = ?self.peer_addr);
+    }
+
+    fn emit_metrics(&self) {
+        match self.peer_addr {
+            Some(peer_addr) => counter!(
+                "component_received_bytes_total", self.byte_size as u64,
+                "protocol" => "grpc",
+                "peer_addr" => peer_addr.to_string()
+            ),
+            None => counter!(
+                "component_received_bytes_total", self.byte_size as u64,
+                "protocol" => "grpc"
+            ),
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct VectorProtoDecodeError<'a> {
     pub error: &'a DecodeError,
@@ -35,3 +69,669 @@ impl<'a> InternalEvent for VectorProtoDecodeError<'a> {
         counter!("protobuf_decode_errors_total", 1);
     }
 }
+
+/// Registered in bytes, which gives the histogram buckets sized for events rather than the
+/// default ones meant for durations. The series of a source and a sink of the same type are told
+/// apart by the `component_kind` label of the span it is emitted in.
+#[derive(Debug)]
+pub struct EventSizeObserved {
+    pub byte_size: usize,
+}
+
+impl InternalEvent for EventSizeObserved {
+    fn emit_metrics(&self) {
+        register_histogram!("event_size_bytes", Unit::Bytes);
+        histogram!("event_size_bytes", self.byte_size as f64);
+    }
+}
+
+/// Tagged with the component that delivered the event through the span it is
+/// emitted in, like the other metrics of components.
+#[derive(Debug)]
+pub struct EventAgeObserved {
+    pub age: Duration,
+}
+
+impl InternalEvent for EventAgeObserved {
+    fn emit_metrics(&self) {
+        histogram!("event_age_seconds", self.age);
+    }
+}
+
+#[derive(Debug)]
+pub struct EventAgeClockSkew {
+    pub skew: Duration,
+}
+
+impl InternalEvent for EventAgeClockSkew {
+    fn emit_logs(&self) {
+        warn!(
+            message = "Event was ingested after it was delivered; the clocks involved are skewed.",
+            skew = ?self.skew,
+            internal_log_rate_secs = 30,
+        );
+    }
+
+    fn emit_metrics(&self) {
+        counter!("event_age_clock_skew_total", 1);
+    }
+}
+
+/// Logged at debug level, as the drops are summed up by `EventsDroppedSummary`.
+#[derive(Debug)]
+pub struct VectorEventDiscardedOversized {
+    pub byte_size: usize,
+    pub max_event_bytes: usize,
+    pub event_id: Option<EventId>,
+}
+
+impl InternalEvent for VectorEventDiscardedOversized {
+    fn emit_logs(&self) {
+        debug!(
+            message = "Encoded event is larger than max_event_bytes; dropping event.",
+            byte_size = %self.byte_size,
+            max_event_bytes = %self.max_event_bytes,
+            event_id = ?self.event_id,
+            internal_log_rate_secs = 10,
+        );
+    }
+
+    fn emit_metrics(&self) {
+        counter!("events_discarded_total", 1, "reason" => "oversized");
+    }
+}
+
+/// Logged at debug level, as the drops are summed up by `EventsDroppedSummary`.
+#[derive(Debug)]
+pub struct VectorEventEncodeFailed<'a> {
+    pub error: &'a str,
+    pub reason: &'static str,
+    pub event_id: Option<EventId>,
+}
+
+impl<'a> InternalEvent for VectorEventEncodeFailed<'a> {
+    fn emit_logs(&self) {
+        debug!(
+            message = "Event failed to encode; dropping event.",
+            error = %self.error,
+            reason = %self.reason,
+            event_id = ?self.event_id,
+            internal_log_rate_secs = 10,
+        );
+    }
+
+    fn emit_metrics(&self) {
+        counter!("encode_errors_total", 1, "reason" => self.reason);
+    }
+}
+
+/// Events dropped on purpose by sampling, so they are only logged at trace level.
+#[derive(Debug)]
+pub struct VectorEventSampledOut {
+    pub rate: f64,
+}
+
+impl InternalEvent for VectorEventSampledOut {
+    fn emit_logs(&self) {
+        trace!(message = "Event sampled out; dropping event.", rate = %self.rate);
+    }
+
+    fn emit_metrics(&self) {
+        counter!("events_discarded_total", 1, "reason" => "sampled");
+    }
+}
+
+#[derive(Debug)]
+pub struct VectorChunkedEventDropped<'a> {
+    /// The id the chunks were sent under, which is not the id in the metadata of the event.
+    pub chunk_event_id: &'a str,
+    pub error: &'a str,
+    pub reason: &'static str,
+}
+
+impl<'a> InternalEvent for VectorChunkedEventDropped<'a> {
+    fn emit_logs(&self) {
+        error!(
+            message = "Chunked event could not be reassembled; dropping its chunks.",
+            chunk_event_id = %self.chunk_event_id,
+            error = %self.error,
+            reason = %self.reason,
+            internal_log_rate_secs = 10,
+        );
+    }
+
+    fn emit_metrics(&self) {
+        counter!("events_discarded_total", 1, "reason" => self.reason);
+    }
+}
+
+/// Logged at debug level. With `rejected_events.behavior = "log"` the rejections are summed up by
+/// `EventsDroppedSummary` as well.
+#[derive(Debug)]
+pub struct VectorEventRejected<'a> {
+    pub error: &'a str,
+    pub reason: &'static str,
+    pub remote_addr: Option<SocketAddr>,
+    pub event_id: Option<EventId>,
+}
+
+impl<'a> InternalEvent for VectorEventRejected<'a> {
+    fn emit_logs(&self) {
+        debug!(
+            message = "Rejected event.",
+            error = %self.error,
+            reason = %self.reason,
+            remote_addr = ?self.remote_addr,
+            event_id = ?self.event_id,
+            internal_log_rate_secs = 10,
+        );
+    }
+
+    fn emit_metrics(&self) {
+        counter!("rejected_events_total", 1, "reason" => self.reason);
+    }
+}
+
+#[derive(Debug)]
+pub struct VectorRawPayloadSkipped {
+    pub byte_size: usize,
+    pub max_raw_payload_bytes: usize,
+}
+
+impl InternalEvent for VectorRawPayloadSkipped {
+    fn emit_logs(&self) {
+        warn!(
+            message = "Encoded event is larger than max_raw_payload_bytes; forwarding it without its raw payload.",
+            byte_size = %self.byte_size,
+            max_raw_payload_bytes = %self.max_raw_payload_bytes,
+            internal_log_rate_secs = 10,
+        );
+    }
+
+    fn emit_metrics(&self) {
+        counter!("raw_payloads_skipped_total", 1);
+    }
+}
+
+#[derive(Debug)]
+pub struct VectorSourceStateUpdated {
+    /// Which of the registries of the source, such as `peers`.
+    pub state: &'static str,
+    pub entries: usize,
+    pub byte_size: usize,
+}
+
+impl InternalEvent for VectorSourceStateUpdated {
+    fn emit_metrics(&self) {
+        gauge!("source_state_entries", self.entries as f64, "state" => self.state);
+        gauge!("source_state_bytes", self.byte_size as f64, "state" => self.state);
+    }
+}
+
+#[derive(Debug)]
+pub struct VectorSourceStateEvicted {
+    pub state: &'static str,
+    /// Either `capacity` or `ttl`.
+    pub reason: &'static str,
+    pub count: usize,
+}
+
+impl InternalEvent for VectorSourceStateEvicted {
+    fn emit_logs(&self) {
+        debug!(
+            message = "Evicted state kept by the source.",
+            state = %self.state,
+            reason = %self.reason,
+            count = %self.count,
+        );
+    }
+
+    fn emit_metrics(&self) {
+        counter!(
+            "source_state_evictions_total", self.count as u64,
+            "state" => self.state,
+            "reason" => self.reason,
+        );
+    }
+}
+
+#[derive(Debug)]
+pub struct VectorEventTruncated<'a> {
+    pub field: &'a str,
+    pub byte_size: usize,
+    pub max_event_bytes: usize,
+}
+
+impl<'a> InternalEvent for VectorEventTruncated<'a> {
+    fn emit_logs(&self) {
+        warn!(
+            message = "Encoded event is larger than max_event_bytes; truncating its largest field.",
+            field = %self.field,
+            byte_size = %self.byte_size,
+            max_event_bytes = %self.max_event_bytes,
+            internal_log_rate_secs = 10,
+        );
+    }
+
+    fn emit_metrics(&self) {
+        counter!("events_truncated_total", 1);
+    }
+}
+
+#[derive(Debug)]
+pub struct VectorSinkHealthChecked {
+    pub healthy: bool,
+    pub changed: bool,
+}
+
+impl InternalEvent for VectorSinkHealthChecked {
+    fn emit_logs(&self) {
+        match (self.changed, self.healthy) {
+            (true, true) => info!(message = "Downstream Vector is healthy again."),
+            (true, false) => warn!(message = "Downstream Vector is unhealthy."),
+            (false, healthy) => debug!(message = "Checked downstream Vector health.", healthy),
+        }
+    }
+
+    fn emit_metrics(&self) {
+        gauge!("sink_healthy", if self.healthy { 1.0 } else { 0.0 });
+    }
+}
+
+#[derive(Debug)]
+pub struct VectorSinkGaugesUpdated {
+    pub buffered_events: usize,
+    pub in_flight_requests: usize,
+    pub retrying_events: usize,
+}
+
+impl InternalEvent for VectorSinkGaugesUpdated {
+    fn emit_metrics(&self) {
+        gauge!("sink_buffered_events", self.buffered_events as f64);
+        gauge!("sink_in_flight_requests", self.in_flight_requests as f64);
+        gauge!("sink_retrying_events", self.retrying_events as f64);
+    }
+}
+
+#[derive(Debug)]
+pub struct VectorSinkEventsDroppedAtShutdown {
+    pub count: usize,
+}
+
+impl InternalEvent for VectorSinkEventsDroppedAtShutdown {
+    fn emit_logs(&self) {
+        warn!(
+            message = "Events were dropped as the sink shut down before delivering them.",
+            count = %self.count,
+        );
+    }
+
+    fn emit_metrics(&self) {
+        counter!("sink_shutdown_dropped_events_total", self.count as u64);
+    }
+}
+
+#[derive(Debug)]
+pub struct VectorSinkEventsSpooled<'a> {
+    pub count: usize,
+    pub path: &'a Path,
+}
+
+impl<'a> InternalEvent for VectorSinkEventsSpooled<'a> {
+    fn emit_logs(&self) {
+        info!(
+            message = "Spooled the events the sink stopped before delivering.",
+            count = %self.count,
+            path = ?self.path,
+        );
+    }
+
+    fn emit_metrics(&self) {
+        counter!("sink_spooled_events_total", self.count as u64);
+    }
+}
+
+/// The events that didn't fit in the spool are counted as dropped at shutdown along with those of
+/// a sink without one.
+#[derive(Debug)]
+pub struct VectorSinkSpoolFull {
+    pub count: usize,
+    pub max_bytes: u64,
+}
+
+impl InternalEvent for VectorSinkSpoolFull {
+    fn emit_logs(&self) {
+        error!(
+            message = "Events were dropped as they did not fit in the spool.",
+            count = %self.count,
+            max_bytes = %self.max_bytes,
+        );
+    }
+}
+
+#[derive(Debug)]
+pub struct VectorSinkSpoolRestored<'a> {
+    pub count: usize,
+    pub path: &'a Path,
+}
+
+impl<'a> InternalEvent for VectorSinkSpoolRestored<'a> {
+    fn emit_logs(&self) {
+        info!(
+            message = "Sending spooled events ahead of new ones.",
+            count = %self.count,
+            path = ?self.path,
+        );
+    }
+
+    fn emit_metrics(&self) {
+        counter!("sink_spool_restored_events_total", self.count as u64);
+    }
+}
+
+#[derive(Debug)]
+pub struct VectorSinkSpoolQuarantined<'a> {
+    pub path: &'a Path,
+    pub reason: &'static str,
+    pub error: &'a str,
+}
+
+impl<'a> InternalEvent for VectorSinkSpoolQuarantined<'a> {
+    fn emit_logs(&self) {
+        error!(
+            message = "Spool file can't be sent; moved it to quarantine.",
+            path = ?self.path,
+            reason = %self.reason,
+            error = %self.error,
+        );
+    }
+
+    fn emit_metrics(&self) {
+        counter!("sink_spool_quarantined_files_total", 1, "reason" => self.reason);
+    }
+}
+
+#[derive(Debug)]
+pub struct VectorSinkSpoolFailed<'a> {
+    pub path: &'a Path,
+    pub error: &'a io::Error,
+}
+
+impl<'a> InternalEvent for VectorSinkSpoolFailed<'a> {
+    fn emit_logs(&self) {
+        error!(
+            message = "Failed to access the spool.",
+            path = ?self.path,
+            error = %self.error,
+        );
+    }
+
+    fn emit_metrics(&self) {
+        counter!("sink_spool_errors_total", 1);
+    }
+}
+
+#[derive(Debug)]
+pub struct VectorSinkNotVector<'a> {
+    pub endpoint: &'a str,
+}
+
+impl<'a> InternalEvent for VectorSinkNotVector<'a> {
+    fn emit_logs(&self) {
+        error!(
+            message = "Remote does not implement the Vector service; is the address pointing at the right port? Requests to it are not retried.",
+            endpoint = %self.endpoint,
+        );
+    }
+}
+
+#[derive(Debug)]
+pub struct VectorSinkEndpointHealthChecked<'a> {
+    pub endpoint: &'a str,
+    /// One of `serving`, `not_serving`, `unimplemented` or `error`.
+    pub status: &'static str,
+    /// The gRPC code, when the health check got no answer.
+    pub error_code: Option<&'static str>,
+    pub error: Option<&'a str>,
+}
+
+impl<'a> InternalEvent for VectorSinkEndpointHealthChecked<'a> {
+    fn emit_logs(&self) {
+        if self.status == "serving" {
+            info!(
+                message = "Downstream Vector endpoint is healthy.",
+                endpoint = %self.endpoint,
+            );
+        } else {
+            warn!(
+                message = "Downstream Vector endpoint is unhealthy.",
+                endpoint = %self.endpoint,
+                status = %self.status,
+                error_code = ?self.error_code,
+                error = ?self.error,
+            );
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct VectorSenderIdentified<'a> {
+    pub remote_addr: Option<SocketAddr>,
+    pub hostname: &'a str,
+    pub version: &'a str,
+    pub uuid: &'a str,
+    pub labels: &'a BTreeMap<String, String>,
+}
+
+impl<'a> InternalEvent for VectorSenderIdentified<'a> {
+    fn emit_logs(&self) {
+        debug!(
+            message = "Upstream Vector identified itself.",
+            remote_addr = ?self.remote_addr,
+            hostname = %self.hostname,
+            version = %self.version,
+            uuid = %self.uuid,
+            labels = ?self.labels,
+        );
+    }
+}
+
+#[derive(Debug)]
+pub struct VectorMetricValueImprecise<'a> {
+    pub name: &'a str,
+}
+
+impl<'a> InternalEvent for VectorMetricValueImprecise<'a> {
+    fn emit_logs(&self) {
+        warn!(
+            message = "Metric value is too large to be an exact integer; it may have been rounded.",
+            name = %self.name,
+            internal_log_rate_secs = 10,
+        );
+    }
+}
+
+#[derive(Debug)]
+pub struct VectorDeltaBaselineMissing {
+    pub remote_addr: Option<SocketAddr>,
+}
+
+impl InternalEvent for VectorDeltaBaselineMissing {
+    fn emit_logs(&self) {
+        debug!(
+            message = "Received a delta based on an event the source doesn't have; asking for the events whole.",
+            remote_addr = ?self.remote_addr,
+            internal_log_rate_secs = 10,
+        );
+    }
+
+    fn emit_metrics(&self) {
+        counter!("delta_baseline_misses_total", 1);
+    }
+}
+
+#[derive(Debug)]
+pub struct VectorDeltaEventsReceived {
+    pub count: usize,
+}
+
+impl InternalEvent for VectorDeltaEventsReceived {
+    fn emit_logs(&self) {
+        trace!(message = "Received events as deltas.", count = %self.count);
+    }
+
+    fn emit_metrics(&self) {
+        counter!("delta_events_received_total", self.count as u64);
+    }
+}
+
+#[derive(Debug)]
+pub struct VectorSinkDeltaResent {
+    pub count: usize,
+}
+
+impl InternalEvent for VectorSinkDeltaResent {
+    fn emit_logs(&self) {
+        debug!(
+            message = "Downstream Vector is missing the events deltas were based on; sending them whole.",
+            count = %self.count,
+            internal_log_rate_secs = 10,
+        );
+    }
+
+    fn emit_metrics(&self) {
+        counter!("sink_delta_resent_events_total", self.count as u64);
+    }
+}
+
+#[derive(Debug)]
+pub struct VectorSinkDeltaUnsupported {
+    pub endpoint: String,
+}
+
+impl InternalEvent for VectorSinkDeltaUnsupported {
+    fn emit_logs(&self) {
+        warn!(
+            message = "Downstream Vector doesn't accept deltas; sending events whole.",
+            endpoint = %self.endpoint,
+        );
+    }
+}
+
+/// Events of a request the downstream Vector accepted that it rejected one by one, such as those
+/// that failed to decode there. It handled them as its `rejected_events` option says.
+#[derive(Debug)]
+pub struct VectorSinkEventsRejected<'a> {
+    pub count: usize,
+    pub endpoint: &'a str,
+}
+
+impl<'a> InternalEvent for VectorSinkEventsRejected<'a> {
+    fn emit_logs(&self) {
+        warn!(
+            message = "Downstream Vector rejected events.",
+            count = %self.count,
+            endpoint = %self.endpoint,
+            internal_log_rate_secs = 30,
+        );
+    }
+
+    fn emit_metrics(&self) {
+        counter!(
+            "events_discarded_total", self.count as u64,
+            "reason" => "rejected_downstream",
+        );
+    }
+}
+
+#[derive(Debug)]
+pub struct VectorSinkDnsRefreshFailed<'a> {
+    pub host: &'a str,
+    pub error: &'a str,
+}
+
+impl<'a> InternalEvent for VectorSinkDnsRefreshFailed<'a> {
+    fn emit_logs(&self) {
+        warn!(
+            message = "Failed to refresh the addresses of the downstream Vector; keeping the current connections.",
+            host = %self.host,
+            error = %self.error,
+            internal_log_rate_secs = 30,
+        );
+    }
+}
+
+#[derive(Debug)]
+pub struct VectorSinkAddressesChanged<'a> {
+    pub host: &'a str,
+    pub added: usize,
+    pub removed: usize,
+}
+
+impl<'a> InternalEvent for VectorSinkAddressesChanged<'a> {
+    fn emit_logs(&self) {
+        info!(
+            message = "Addresses of the downstream Vector changed; reconnecting.",
+            host = %self.host,
+            added = %self.added,
+            removed = %self.removed,
+        );
+    }
+}
+
+#[derive(Debug)]
+pub struct VectorSenderRateLimited<'a> {
+    /// The sender, or `other` once enough senders were tagged already.
+    pub sender: &'a str,
+    pub events: usize,
+    pub bytes: usize,
+}
+
+impl<'a> InternalEvent for VectorSenderRateLimited<'a> {
+    fn emit_logs(&self) {
+        warn!(
+            message = "Refused a request of a sender over its rate limit.",
+            sender = %self.sender,
+            events = %self.events,
+            bytes = %self.bytes,
+            internal_log_rate_secs = 10,
+        );
+    }
+
+    fn emit_metrics(&self) {
+        counter!("source_rate_limited_requests_total", 1, "sender" => self.sender.to_owned());
+        counter!(
+            "source_rate_limited_events_total", self.events as u64,
+            "sender" => self.sender.to_owned(),
+        );
+    }
+}
+
+#[derive(Debug)]
+pub struct VectorExtraValueOmitted<'a> {
+    /// `field` for the `extra_fields` of log events, `tag` for the `extra_tags` of metrics.
+    pub kind: &'static str,
+    pub name: &'a str,
+    /// The events of the request the value was left out of.
+    pub count: usize,
+}
+
+impl<'a> InternalEvent for VectorExtraValueOmitted<'a> {
+    fn emit_logs(&self) {
+        debug!(
+            message = "Omitted an extra value whose request metadata is missing.",
+            kind = %self.kind,
+            name = %self.name,
+            count = %self.count,
+            internal_log_rate_secs = 10,
+        );
+    }
+
+    fn emit_metrics(&self) {
+        counter!(
+            "source_extra_values_omitted_total", self.count as u64,
+            "kind" => self.kind,
+            "name" => self.name.to_owned(),
+        );
+    }
+}
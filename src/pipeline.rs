@@ -1,11 +1,15 @@
 use std::{collections::VecDeque, fmt, pin::Pin, task::Context};
 
-use futures::{channel::mpsc, task::Poll, Sink};
-#[cfg(test)]
-use futures::{Stream, StreamExt};
+use chrono::{DateTime, Utc};
+use futures::{channel::mpsc, future, task::Poll, Sink, Stream, StreamExt};
 #[cfg(test)]
 use vector_core::event::EventStatus;
-use vector_core::{event::Event, internal_event::EventsSent, ByteSizeOf};
+use vector_core::{
+    config::{LogSchema, TimestampTimezone},
+    event::Event,
+    internal_event::EventsSent,
+    ByteSizeOf,
+};
 
 use crate::transforms::FunctionTransform;
 
@@ -20,6 +24,31 @@ impl fmt::Display for ClosedError {
 
 impl std::error::Error for ClosedError {}
 
+/// Returned when the pipeline closes partway through sending a batch of events.
+#[derive(Debug)]
+pub struct BatchClosedError {
+    /// The number of events accepted into the channel before it was closed.
+    pub accepted: usize,
+}
+
+impl fmt::Display for BatchClosedError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Pipeline is closed after accepting {} events.",
+            self.accepted
+        )
+    }
+}
+
+impl std::error::Error for BatchClosedError {}
+
+impl From<BatchClosedError> for ClosedError {
+    fn from(_: BatchClosedError) -> Self {
+        ClosedError
+    }
+}
+
 const MAX_ENQUEUED: usize = 1000;
 
 #[derive(Derivative, Clone)]
@@ -30,6 +59,8 @@ pub struct Pipeline {
     #[derivative(Debug = "ignore")]
     inlines: Vec<Box<dyn FunctionTransform>>,
     enqueued: VecDeque<Event>,
+    // The schema of the source, if its `timestamp_timezone` isn't UTC.
+    timestamp_schema: Option<LogSchema>,
 }
 
 impl Pipeline {
@@ -101,23 +132,28 @@ impl Pipeline {
         }
         Poll::Ready(Ok(()))
     }
-}
 
-impl Sink<Event> for Pipeline {
-    type Error = ClosedError;
+    /// Record the offset of the `timestamp_timezone` of `log_schema` on the log events sent, as
+    /// [`LogEvent::insert_timestamp_offset`](vector_core::event::LogEvent::insert_timestamp_offset)
+    /// does. Nothing is recorded if it is UTC.
+    pub fn with_timestamp_timezone(mut self, log_schema: &LogSchema) -> Self {
+        self.timestamp_schema =
+            (log_schema.timestamp_timezone() != TimestampTimezone::Utc).then(|| log_schema.clone());
+        self
+    }
 
-    fn poll_ready(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
-        if self.enqueued.len() < MAX_ENQUEUED {
-            Poll::Ready(Ok(()))
-        } else {
-            self.try_flush(cx)
+    /// Stamp what the pipeline records on the events entering the topology.
+    fn stamp(&self, event: Event, now: DateTime<Utc>) -> Event {
+        let mut event = stamp_ingest_timestamp(event, now);
+        if let (Some(log_schema), Event::Log(log)) = (&self.timestamp_schema, &mut event) {
+            log.insert_timestamp_offset(log_schema);
         }
+        event
     }
 
-    fn start_send(mut self: Pin<&mut Self>, item: Event) -> Result<(), Self::Error> {
+    fn run_inlines(&mut self, mut working_set: Vec<Event>) -> Vec<Event> {
         // Note how this gets **swapped** with `new_working_set` in the loop.
         // At the end of the loop, it will only contain finalized events.
-        let mut working_set = vec![item];
         for inline in self.inlines.iter_mut() {
             let mut new_working_set = Vec::with_capacity(working_set.len());
             for event in working_set.drain(..) {
@@ -125,6 +161,111 @@ impl Sink<Event> for Pipeline {
             }
             core::mem::swap(&mut new_working_set, &mut working_set);
         }
+        working_set
+    }
+
+    /// Send a batch of events, preserving their order.
+    ///
+    /// The events are fed directly into the underlying channel and a single
+    /// `EventsSent` is emitted for the whole batch. Returns the number of events
+    /// accepted into the channel, which can differ from the input when inline
+    /// transforms filter or expand events.
+    pub async fn send_batch(
+        &mut self,
+        events: impl IntoIterator<Item = Event>,
+    ) -> Result<usize, BatchClosedError> {
+        // Anything still enqueued by the `Sink` implementation must go first to
+        // preserve ordering.
+        if future::poll_fn(|cx| self.try_flush(cx)).await.is_err() {
+            return Err(BatchClosedError { accepted: 0 });
+        }
+
+        let now = Utc::now();
+        let events = events
+            .into_iter()
+            .map(|event| self.stamp(event, now))
+            .collect();
+        let events = self.run_inlines(events);
+
+        let mut accepted = 0;
+        let mut byte_size = 0;
+        let mut result = Ok(());
+        for event in events {
+            if future::poll_fn(|cx| self.inner.poll_ready(cx))
+                .await
+                .is_err()
+            {
+                result = Err(BatchClosedError { accepted });
+                break;
+            }
+
+            let event_bytes = event.size_of();
+            if self.inner.start_send(event).is_err() {
+                result = Err(BatchClosedError { accepted });
+                break;
+            }
+            accepted += 1;
+            byte_size += event_bytes;
+        }
+
+        if accepted > 0 {
+            emit!(&EventsSent {
+                count: accepted,
+                byte_size,
+            });
+        }
+
+        result.map(|()| accepted)
+    }
+
+    /// Send all events from a stream, batching whatever is ready at once.
+    ///
+    /// Returns the total number of events accepted into the channel.
+    ///
+    /// This isn't named `send_all`: sources call `SinkExt::send_all` on their
+    /// `Pipeline` with streams of results, and an inherent method of that name
+    /// would shadow it.
+    pub async fn send_stream(
+        &mut self,
+        events: impl Stream<Item = Event> + Unpin,
+    ) -> Result<usize, BatchClosedError> {
+        let mut chunks = events.ready_chunks(MAX_ENQUEUED);
+        let mut total = 0;
+        while let Some(chunk) = chunks.next().await {
+            total += self
+                .send_batch(chunk)
+                .await
+                .map_err(|error| BatchClosedError {
+                    accepted: total + error.accepted,
+                })?;
+        }
+        Ok(total)
+    }
+}
+
+/// Record when the event entered the topology, unless that was already done.
+fn stamp_ingest_timestamp(mut event: Event, now: DateTime<Utc>) -> Event {
+    let metadata = event.metadata_mut();
+    if metadata.ingest_timestamp().is_none() {
+        metadata.set_ingest_timestamp(now);
+    }
+    event
+}
+
+impl Sink<Event> for Pipeline {
+    type Error = ClosedError;
+
+    fn poll_ready(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        if self.enqueued.len() < MAX_ENQUEUED {
+            Poll::Ready(Ok(()))
+        } else {
+            self.try_flush(cx)
+        }
+    }
+
+    fn start_send(mut self: Pin<&mut Self>, item: Event) -> Result<(), Self::Error> {
+        let event = self.stamp(item, Utc::now());
+        let working_set = self.run_inlines(vec![event]);
         self.enqueued.extend(working_set);
         Ok(())
     }
@@ -177,6 +318,7 @@ impl Pipeline {
             // We ensure the buffer is sufficient that it is unlikely to require reallocations.
             // There is a possibility a component might blow this queue size.
             enqueued: VecDeque::with_capacity(10),
+            timestamp_schema: None,
         }
     }
 }
@@ -185,12 +327,15 @@ impl Pipeline {
 mod test {
     use std::convert::TryFrom;
 
+    use chrono::{TimeZone as _, Utc};
     use futures::SinkExt;
     use serde_json::json;
+    use shared::TimeZone;
+    use vector_core::config::{LogSchema, TimestampTimezone};
 
     use super::Pipeline;
     use crate::{
-        event::{Event, Value},
+        event::{Event, LogEvent, Value},
         test_util::collect_ready,
         transforms::{add_fields::AddFields, filter::Filter},
     };
@@ -254,4 +399,104 @@ mod test {
 
         Ok(())
     }
+
+    #[tokio::test]
+    async fn send_batch_preserves_order() {
+        let (mut pipeline, receiver) = Pipeline::new_test();
+        let events = (0..10)
+            .map(|i| Event::from(i.to_string()))
+            .collect::<Vec<_>>();
+
+        pipeline.send(Event::from("first")).await.unwrap();
+        let accepted = pipeline.send_batch(events.clone()).await.unwrap();
+        assert_eq!(accepted, 10);
+
+        let out = collect_ready(receiver).await;
+        assert_eq!(out.len(), 11);
+        assert_eq!(out[0].as_log()["message"], Value::from("first"));
+        assert_eq!(&out[1..], &events[..]);
+    }
+
+    #[tokio::test]
+    async fn send_batch_reports_accepted_on_close() {
+        let (mut pipeline, receiver) = Pipeline::new_with_buffer(0, vec![]);
+        drop(receiver);
+
+        let events = (0..3).map(|i| Event::from(i.to_string()));
+        let error = pipeline.send_batch(events).await.unwrap_err();
+        assert_eq!(error.accepted, 0);
+    }
+
+    #[tokio::test]
+    async fn send_stream_sends_everything() {
+        let (mut pipeline, receiver) = Pipeline::new_test();
+        let events = (0..50)
+            .map(|i| Event::from(i.to_string()))
+            .collect::<Vec<_>>();
+
+        let accepted = pipeline
+            .send_stream(futures::stream::iter(events.clone()))
+            .await
+            .unwrap();
+        assert_eq!(accepted, 50);
+
+        let out = collect_ready(receiver).await;
+        assert_eq!(out, events);
+    }
+
+    #[tokio::test]
+    async fn records_the_timestamp_offset_of_the_source_timezone() {
+        let mut schema = LogSchema::default();
+        schema.set_timestamp_timezone(TimestampTimezone::Zone(
+            TimeZone::parse("Europe/Berlin").unwrap(),
+        ));
+        let (pipeline, receiver) = Pipeline::new_test();
+        let mut pipeline = pipeline.with_timestamp_timezone(&schema);
+
+        let event = |timestamp: Option<_>, offset: Option<&str>| {
+            let mut log = LogEvent::default();
+            log.insert("message", "hello");
+            if let Some(timestamp) = timestamp {
+                log.insert("timestamp", timestamp);
+            }
+            if let Some(offset) = offset {
+                log.insert("timestamp_offset", offset);
+            }
+            Event::from(log)
+        };
+        let summer = Utc.ymd(2021, 7, 1).and_hms(12, 0, 0);
+        let winter = Utc.ymd(2021, 12, 1).and_hms(12, 0, 0);
+        pipeline.send(event(Some(summer), None)).await.unwrap();
+        pipeline
+            .send_batch(vec![
+                event(Some(winter), None),
+                // An offset that is already there, such as one from an upstream Vector, is kept.
+                event(Some(winter), Some("-05:00")),
+                event(None, None),
+            ])
+            .await
+            .unwrap();
+
+        let offsets = collect_ready(receiver)
+            .await
+            .iter()
+            .map(|event| event.as_log().get("timestamp_offset").cloned())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            offsets,
+            vec![
+                Some("+02:00".into()),
+                Some("+01:00".into()),
+                Some("-05:00".into()),
+                None
+            ]
+        );
+
+        // Nothing is recorded in UTC.
+        let (pipeline, receiver) = Pipeline::new_test();
+        let mut pipeline = pipeline.with_timestamp_timezone(&LogSchema::default());
+        pipeline.send(event(Some(summer), None)).await.unwrap();
+        let out = collect_ready(receiver).await;
+        assert_eq!(out[0].as_log().get("timestamp_offset"), None);
+    }
 }
@@ -0,0 +1,263 @@
+//! Pins the bytes events are sent as between Vectors, so that a change to them can't slip in
+//! unnoticed: a `vector` sink has to keep talking to the `vector` sources of older and newer
+//! Vectors, and the other way around.
+//!
+//! Each fixture below is checked in encoded, as a `PushEventsRequest` holding it, under
+//! `tests/data/proto-golden`. Changing the files takes running these tests with
+//! `VECTOR_REGENERATE_PROTO_GOLDEN=1` set, and reviewing their diff as a change of the wire
+//! format.
+
+use std::{fs, path::PathBuf};
+
+use chrono::{DateTime, TimeZone, Utc};
+use prost::Message;
+use shared::btreemap;
+use vector_core::{
+    event::metric::{Bucket, MetricSketch, Quantile, Sample},
+    metrics::AgentDDSketch,
+};
+
+use super::vector::PushEventsRequest;
+use crate::event::{
+    proto::EventWrapper, Event, EventId, LogEvent, Metric, MetricKind, MetricValue, StatisticKind,
+    Value,
+};
+
+const GOLDEN_DIR: &str = "tests/data/proto-golden";
+
+const REGENERATE_ENV_VAR: &str = "VECTOR_REGENERATE_PROTO_GOLDEN";
+
+fn regenerating() -> bool {
+    std::env::var_os(REGENERATE_ENV_VAR).is_some()
+}
+
+fn timestamp() -> DateTime<Utc> {
+    Utc.timestamp(1_638_316_800, 123_456_789)
+}
+
+/// The events pinned, covering every kind of `Value` and every `MetricValue`.
+fn fixtures() -> Vec<(&'static str, Event)> {
+    let scalars = LogEvent::from(btreemap! {
+        "bytes" => "hello",
+        "integer" => -42,
+        "float" => 1.5,
+        "boolean" => true,
+        "timestamp" => timestamp(),
+        "null" => Value::Null,
+    });
+
+    let mut nested = Event::from(LogEvent::from(btreemap! {
+        "map" => Value::from(btreemap! {
+            "count" => 7,
+            "inner" => "value",
+        }),
+        "array" => Value::Array(vec![
+            Value::from(1),
+            Value::from("two"),
+            Value::from(false),
+            Value::Array(vec![Value::Null]),
+        ]),
+    }));
+    nested
+        .metadata_mut()
+        .set_event_id(Some(EventId::from_bytes([
+            0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d, 0x0e,
+            0x0f, 0x10,
+        ])));
+
+    let counter = Metric::new(
+        "requests",
+        MetricKind::Incremental,
+        MetricValue::Counter { value: 3.0 },
+    )
+    .with_namespace(Some("vector"))
+    .with_tags(Some(btreemap! {
+        "host" => "a",
+        "region" => "b",
+    }))
+    .with_timestamp(Some(timestamp()));
+
+    let gauge = Metric::new(
+        "temperature",
+        MetricKind::Absolute,
+        MetricValue::Gauge { value: -2.5 },
+    );
+
+    let set = Metric::new(
+        "users",
+        MetricKind::Incremental,
+        MetricValue::Set {
+            values: vec!["alice".to_owned(), "bob".to_owned()]
+                .into_iter()
+                .collect(),
+        },
+    );
+
+    let distribution = Metric::new(
+        "latency",
+        MetricKind::Incremental,
+        MetricValue::Distribution {
+            samples: vec![
+                Sample {
+                    value: 1.0,
+                    rate: 2,
+                },
+                Sample {
+                    value: 2.5,
+                    rate: 1,
+                },
+            ],
+            statistic: StatisticKind::Summary,
+        },
+    );
+
+    let histogram = Metric::new(
+        "sizes",
+        MetricKind::Absolute,
+        MetricValue::AggregatedHistogram {
+            buckets: vec![
+                Bucket {
+                    upper_limit: 1.0,
+                    count: 3,
+                },
+                Bucket {
+                    upper_limit: 2.0,
+                    count: 5,
+                },
+            ],
+            count: 8,
+            sum: 9.5,
+        },
+    );
+
+    let summary = Metric::new(
+        "durations",
+        MetricKind::Absolute,
+        MetricValue::AggregatedSummary {
+            quantiles: vec![
+                Quantile {
+                    quantile: 0.5,
+                    value: 1.0,
+                },
+                Quantile {
+                    quantile: 0.99,
+                    value: 3.0,
+                },
+            ],
+            count: 10,
+            sum: 12.0,
+        },
+    );
+
+    let sketch = Metric::new(
+        "payloads",
+        MetricKind::Incremental,
+        MetricValue::Sketch {
+            sketch: MetricSketch::AgentDDSketch(
+                AgentDDSketch::from_raw(3, 1.0, 4.0, 6.0, 2.0, &[-2, 0, 5], &[1, 1, 1]).unwrap(),
+            ),
+        },
+    );
+
+    vec![
+        ("log_scalars", scalars.into()),
+        ("log_nested", nested),
+        ("metric_counter", counter.into()),
+        ("metric_gauge", gauge.into()),
+        ("metric_set", set.into()),
+        ("metric_distribution", distribution.into()),
+        ("metric_aggregated_histogram", histogram.into()),
+        ("metric_aggregated_summary", summary.into()),
+        ("metric_sketch", sketch.into()),
+    ]
+}
+
+fn golden_path(name: &str) -> PathBuf {
+    PathBuf::from(GOLDEN_DIR).join(format!("{}.pb", name))
+}
+
+fn read_golden(name: &str) -> Vec<u8> {
+    let path = golden_path(name);
+    fs::read(&path).unwrap_or_else(|error| {
+        panic!(
+            "Can't read {:?}, run with {}=1 to write it: {}",
+            path, REGENERATE_ENV_VAR, error
+        )
+    })
+}
+
+fn encode(event: Event) -> Vec<u8> {
+    PushEventsRequest {
+        events: vec![EventWrapper::from(event)],
+        ..PushEventsRequest::default()
+    }
+    .encode_to_vec()
+}
+
+/// Writes the golden files anew. Without the env var set it does nothing, so that a change of the
+/// encoding fails the tests below rather than being written over.
+#[test]
+fn regenerate_goldens() {
+    if !regenerating() {
+        return;
+    }
+    fs::create_dir_all(GOLDEN_DIR).unwrap();
+    for (name, event) in fixtures() {
+        fs::write(golden_path(name), encode(event)).unwrap();
+    }
+}
+
+#[test]
+fn encoding_matches_goldens() {
+    if regenerating() {
+        return;
+    }
+    for (name, event) in fixtures() {
+        let encoded = encode(event);
+        assert!(
+            encoded == read_golden(name),
+            "The encoding of `{}` changed, which older Vectors may not decode. Run with {}=1 if \
+             the change is intended.",
+            name,
+            REGENERATE_ENV_VAR
+        );
+    }
+}
+
+#[test]
+fn decoding_goldens_gives_fixtures() {
+    if regenerating() {
+        return;
+    }
+    for (name, event) in fixtures() {
+        let request = PushEventsRequest::decode(read_golden(name).as_slice())
+            .unwrap_or_else(|error| panic!("Can't decode `{}`: {}", name, error));
+        assert_eq!(request.events.len(), 1, "{}", name);
+
+        let decoded = Event::from(request.events.into_iter().next().unwrap());
+        assert_eq!(
+            decoded.metadata().event_id(),
+            event.metadata().event_id(),
+            "{}",
+            name
+        );
+        shared::assert_event_data_eq!(decoded, event, name);
+    }
+}
+
+#[test]
+fn every_golden_has_a_fixture() {
+    let names = fixtures()
+        .into_iter()
+        .map(|(name, _)| format!("{}.pb", name))
+        .collect::<Vec<_>>();
+    for entry in fs::read_dir(GOLDEN_DIR).unwrap() {
+        let file_name = entry.unwrap().file_name();
+        let file_name = file_name.to_string_lossy();
+        assert!(
+            names.iter().any(|name| *name == file_name),
+            "{} has no fixture, remove it or add one",
+            file_name
+        );
+    }
+}
@@ -1,6 +1,58 @@
 #![allow(clippy::clone_on_ref_ptr)]
 
+use prost::Message;
+
 tonic::include_proto!("vector");
 
 pub use vector_client::VectorClient as Client;
 pub use vector_server::{Vector as Service, VectorServer as Server};
+
+/// The message of the `Unavailable` status a `vector` source refuses requests with once it is
+/// shutting down. The sender should rather send them elsewhere, or wait for as long as the
+/// [`RETRY_PUSHBACK_KEY`] metadata of the status says.
+pub const SHUTTING_DOWN_MESSAGE: &str = "shutting down";
+
+/// The metadata key with which a server tells the client how many milliseconds to wait before
+/// retrying, as defined by gRPC.
+pub const RETRY_PUSHBACK_KEY: &str = "grpc-retry-pushback-ms";
+
+/// The capability a Vector advertises in its health check response when it accepts the
+/// `keyed_events` of a request as deltas.
+pub const DELTA_CAPABILITY: &str = "delta";
+
+/// The capability a Vector advertises in its health check response when it keeps the fields
+/// listed as compressed in a log event compressed, rather than taking their compressed bytes as
+/// the values. Vectors that don't advertise it are sent the fields decompressed.
+pub const COMPRESSED_FIELDS_CAPABILITY: &str = "compressed_fields";
+
+/// The metadata key of the `FailedPrecondition` status a `vector` source refuses requests with
+/// when a delta in them is based on an event it doesn't have, such as one it evicted. Its value is
+/// the sequence number of that event. The sender should send the events of the request again,
+/// whole.
+pub const DELTA_BASELINE_MISSING_KEY: &str = "vector-delta-baseline-missing";
+
+/// The prefix gRPC puts in front of each message in the body of a request: a compression flag
+/// and the length of the message.
+const MESSAGE_PREFIX_BYTES: usize = 5;
+
+impl PushEventsRequest {
+    /// The bytes the request takes in the body of its HTTP/2 stream: its protobuf encoding and
+    /// the gRPC message prefix. This is what the `vector` sink and source both count as bytes
+    /// sent and received, so that they agree. HTTP/2 frames and headers are not included.
+    ///
+    /// Requests aren't compressed. Once they can be, this has to count the compressed encoding.
+    pub fn wire_size(&self) -> usize {
+        MESSAGE_PREFIX_BYTES + self.encoded_len()
+    }
+
+    /// The events in the request: whole, keyed, and those its chunks complete. The chunks of an
+    /// event count once, with the last of them.
+    pub fn events_count(&self) -> usize {
+        let completed = self
+            .chunks
+            .iter()
+            .filter(|chunk| chunk.index.checked_add(1) == Some(chunk.total))
+            .count();
+        self.events.len() + self.keyed_events.len() + completed
+    }
+}
@@ -2,8 +2,12 @@ use std::{
     collections::HashMap,
     future::Future,
     pin::Pin,
-    sync::Arc,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
     task::{Context, Poll},
+    time::Duration,
 };
 
 use futures::{future, ready, FutureExt};
@@ -19,14 +23,27 @@ use crate::{config::ComponentKey, stream::tripwire_handler, trigger::DisabledTri
 #[derive(Clone, Debug)]
 pub struct ShutdownSignalToken {
     _shutdown_complete: Arc<Trigger>,
+    deadline_exceeded: Arc<AtomicBool>,
 }
 
 impl ShutdownSignalToken {
     fn new(shutdown_complete: Trigger) -> Self {
         Self {
             _shutdown_complete: Arc::new(shutdown_complete),
+            deadline_exceeded: Arc::new(AtomicBool::new(false)),
         }
     }
+
+    /// Record that the Source could not finish draining before its own deadline and abandoned
+    /// whatever work was still in flight.
+    pub fn mark_deadline_exceeded(&self) {
+        self.deadline_exceeded.store(true, Ordering::Relaxed);
+    }
+
+    /// Whether the Source reported that it was force-stopped by its own deadline.
+    pub fn deadline_exceeded(&self) -> bool {
+        self.deadline_exceeded.load(Ordering::Relaxed)
+    }
 }
 
 /// Passed to each Source to coordinate the global shutdown process.
@@ -72,6 +89,21 @@ impl ShutdownSignal {
         }
     }
 
+    /// Convert this signal into a two-phase shutdown future. It resolves once shutdown has begun,
+    /// yielding a [`ShutdownDrain`] that allows the Source to drain for at most `grace_period`.
+    pub const fn with_deadline(self, grace_period: Duration) -> ShutdownDeadline {
+        ShutdownDeadline {
+            signal: self,
+            grace_period,
+        }
+    }
+
+    fn deadline_exceeded_flag(&self) -> Option<Arc<AtomicBool>> {
+        self.shutdown_complete
+            .as_ref()
+            .map(|token| Arc::clone(&token.deadline_exceeded))
+    }
+
     #[cfg(test)]
     pub fn noop() -> Self {
         let (trigger, tripwire) = Tripwire::new();
@@ -91,11 +123,67 @@ impl ShutdownSignal {
     }
 }
 
+/// The graceful phase of a deadline-aware shutdown, created by [`ShutdownSignal::with_deadline`].
+#[pin_project::pin_project]
+#[derive(Debug)]
+pub struct ShutdownDeadline {
+    #[pin]
+    signal: ShutdownSignal,
+    grace_period: Duration,
+}
+
+impl Future for ShutdownDeadline {
+    type Output = ShutdownDrain;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        let token = ready!(this.signal.poll(cx));
+        Poll::Ready(ShutdownDrain {
+            token,
+            deadline: Instant::now() + *this.grace_period,
+        })
+    }
+}
+
+/// The drain phase of a deadline-aware shutdown. Holds the `ShutdownSignalToken` until the drain
+/// is finished or the hard deadline passes.
+#[derive(Debug)]
+pub struct ShutdownDrain {
+    token: ShutdownSignalToken,
+    deadline: Instant,
+}
+
+impl ShutdownDrain {
+    /// The instant at which draining will be cut short.
+    pub const fn deadline(&self) -> Instant {
+        self.deadline
+    }
+
+    /// Drive `drain` until it completes or the deadline passes, whichever comes first. Returns
+    /// `None` if the deadline passed, in which case the token records that the Source was
+    /// force-stopped. The token is released when this returns.
+    pub async fn run<F: Future>(self, drain: F) -> Option<F::Output> {
+        match timeout_at(self.deadline, drain).await {
+            Ok(output) => Some(output),
+            Err(_) => {
+                self.token.mark_deadline_exceeded();
+                None
+            }
+        }
+    }
+
+    /// Give up the drain phase, returning the underlying token.
+    pub const fn into_token(self) -> ShutdownSignalToken {
+        self.token
+    }
+}
+
 #[derive(Debug, Default)]
 pub struct SourceShutdownCoordinator {
     shutdown_begun_triggers: HashMap<ComponentKey, Trigger>,
     shutdown_force_triggers: HashMap<ComponentKey, Trigger>,
     shutdown_complete_tripwires: HashMap<ComponentKey, Tripwire>,
+    shutdown_deadline_flags: HashMap<ComponentKey, Arc<AtomicBool>>,
 }
 
 impl SourceShutdownCoordinator {
@@ -119,6 +207,9 @@ impl SourceShutdownCoordinator {
 
         let shutdown_signal =
             ShutdownSignal::new(shutdown_begun_tripwire, shutdown_complete_trigger);
+        if let Some(flag) = shutdown_signal.deadline_exceeded_flag() {
+            self.shutdown_deadline_flags.insert(id.clone(), flag);
+        }
 
         // `force_shutdown_tripwire` resolves even if canceled when we should *not* be shutting down.
         // `tripwire_handler` handles cancel by never resolving.
@@ -178,6 +269,10 @@ impl SourceShutdownCoordinator {
                 id
             );
         }
+
+        if let Some(flag) = other.shutdown_deadline_flags.remove(id) {
+            self.shutdown_deadline_flags.insert(id.clone(), flag);
+        }
     }
 
     /// Sends a signal to begin shutting down to all sources, and returns a future that
@@ -190,6 +285,7 @@ impl SourceShutdownCoordinator {
         let shutdown_begun_triggers = self.shutdown_begun_triggers;
         let mut shutdown_complete_tripwires = self.shutdown_complete_tripwires;
         let mut shutdown_force_triggers = self.shutdown_force_triggers;
+        let mut shutdown_deadline_flags = self.shutdown_deadline_flags;
 
         for (id, trigger) in shutdown_begun_triggers {
             trigger.cancel();
@@ -211,6 +307,7 @@ impl SourceShutdownCoordinator {
             let source_complete = SourceShutdownCoordinator::shutdown_source_complete(
                 shutdown_complete_tripwire,
                 shutdown_force_trigger,
+                shutdown_deadline_flags.remove(&id).unwrap_or_default(),
                 id.clone(),
                 deadline,
             );
@@ -255,9 +352,11 @@ impl SourceShutdownCoordinator {
                 id
             )
         });
+        let deadline_exceeded = self.shutdown_deadline_flags.remove(id).unwrap_or_default();
         SourceShutdownCoordinator::shutdown_source_complete(
             shutdown_complete_tripwire,
             shutdown_force_trigger,
+            deadline_exceeded,
             id.clone(),
             deadline,
         )
@@ -279,6 +378,7 @@ impl SourceShutdownCoordinator {
     fn shutdown_source_complete(
         shutdown_complete_tripwire: Tripwire,
         shutdown_force_trigger: Trigger,
+        deadline_exceeded: Arc<AtomicBool>,
         id: ComponentKey,
         deadline: Instant,
     ) -> impl Future<Output = bool> {
@@ -289,6 +389,12 @@ impl SourceShutdownCoordinator {
             let fut = shutdown_complete_tripwire.then(tripwire_handler);
             if timeout_at(deadline, fut).await.is_ok() {
                 shutdown_force_trigger.into_inner().disable();
+                if deadline_exceeded.load(Ordering::Relaxed) {
+                    warn!(
+                        "Source '{}' did not finish draining before its own deadline. In-flight work was abandoned.",
+                        id,
+                    );
+                }
                 true
             } else {
                 error!(
@@ -344,4 +450,56 @@ mod test {
         let finished = futures::poll!(force_shutdown_tripwire.boxed());
         assert_eq!(finished, Poll::Ready(()));
     }
+
+    #[tokio::test(start_paused = true)]
+    async fn shutdown_deadline_drain_completes() {
+        let (trigger_shutdown, shutdown, shutdown_done) = ShutdownSignal::new_wired();
+        let shutdown = shutdown.with_deadline(Duration::from_secs(10));
+
+        drop(trigger_shutdown);
+        let drain = shutdown.await;
+        let start = Instant::now();
+        assert_eq!(drain.deadline(), start + Duration::from_secs(10));
+
+        let output = drain.run(tokio::time::sleep(Duration::from_secs(5))).await;
+        assert_eq!(output, Some(()));
+        assert_eq!(Instant::now(), start + Duration::from_secs(5));
+
+        // The token is released once draining is done.
+        shutdown_done.await;
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn shutdown_deadline_drain_exceeded() {
+        let (trigger_shutdown, shutdown, shutdown_done) = ShutdownSignal::new_wired();
+        let shutdown = shutdown.with_deadline(Duration::from_secs(10));
+
+        drop(trigger_shutdown);
+        let drain = shutdown.await;
+        let start = Instant::now();
+
+        let output = drain.run(futures::future::pending::<()>()).await;
+        assert_eq!(output, None);
+        assert_eq!(Instant::now(), start + Duration::from_secs(10));
+
+        shutdown_done.await;
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn shutdown_coordinator_reports_deadline_exceeded() {
+        let mut shutdown = SourceShutdownCoordinator::default();
+        let id = ComponentKey::from("test");
+
+        let (shutdown_signal, _) = shutdown.register_source(&id);
+        let flag = shutdown.shutdown_deadline_flags.get(&id).cloned().unwrap();
+
+        let deadline = Instant::now() + Duration::from_secs(60);
+        let shutdown_complete = shutdown.shutdown_source(&id, deadline);
+
+        let drain = shutdown_signal.with_deadline(Duration::from_secs(1)).await;
+        assert_eq!(drain.run(futures::future::pending::<()>()).await, None);
+
+        assert!(shutdown_complete.await);
+        assert!(flag.load(Ordering::Relaxed));
+    }
 }
@@ -61,9 +61,9 @@ impl<L> Controller<L> {
     ) -> Self {
         // If a `concurrency` is specified, it becomes both the
         // current limit and the maximum, effectively bypassing all the
-        // mechanisms. Otherwise, the current limit is set to 1 and the
-        // maximum to MAX_CONCURRENCY.
-        let current_limit = concurrency.unwrap_or(1);
+        // mechanisms. Otherwise, the current limit starts at the minimum
+        // of the settings.
+        let current_limit = concurrency.unwrap_or_else(|| settings.min_concurrency.max(1));
         Self {
             semaphore: Arc::new(ShrinkableSemaphore::new(current_limit)),
             concurrency,
@@ -210,7 +210,7 @@ impl<L> Controller<L> {
         // concurrency limit. Note that we only check this if we had
         // requests to go beyond the current limit to prevent
         // increasing the limit beyond what we have evidence for.
-        if inner.current_limit < super::MAX_CONCURRENCY
+        if inner.current_limit < self.settings.max_concurrency
             && inner.reached_limit
             && !inner.had_back_pressure
             && current_rtt.is_some()
@@ -223,12 +223,13 @@ impl<L> Controller<L> {
         // Back pressure responses, either explicit or implicit due
         // to increasing response times, trigger a decrease in the
         // concurrency limit.
-        else if inner.current_limit > 1
+        else if inner.current_limit > self.settings.min_concurrency.max(1)
             && (inner.had_back_pressure || current_rtt.unwrap_or(0.0) >= past_rtt.mean + threshold)
         {
             // Decrease (multiplicative) the current concurrency limit
-            let to_forget = inner.current_limit
-                - (inner.current_limit as f64 * self.settings.decrease_ratio) as usize;
+            let decreased = (inner.current_limit as f64 * self.settings.decrease_ratio) as usize;
+            let to_forget =
+                inner.current_limit - decreased.max(self.settings.min_concurrency.max(1));
             self.semaphore.forget_permits(to_forget);
             inner.current_limit -= to_forget;
         }
@@ -262,7 +263,7 @@ where
             Ok(action) => matches!(action, RetryAction::Retry(_)),
             Err(error) => {
                 if let Some(error) = error.downcast_ref::<L::Error>() {
-                    self.logic.is_retriable_error(error)
+                    self.logic.is_back_pressure(error)
                 } else if error.downcast_ref::<Elapsed>().is_some() {
                     true
                 } else if error.downcast_ref::<HttpError>().is_some() {
@@ -283,3 +284,75 @@ where
         self.adjust_to_response_inner(start, is_back_pressure, use_rtt)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use tokio::time::advance;
+
+    use super::*;
+
+    /// Send rounds of as many requests as the limit allows, each answered
+    /// after `rtt`, and give the limit after each round.
+    async fn rounds(
+        controller: &Controller<()>,
+        rtt: Duration,
+        is_back_pressure: bool,
+        count: usize,
+    ) -> Vec<usize> {
+        let mut limits = Vec::new();
+        for _ in 0..count {
+            let limit = controller.inner.lock().unwrap().current_limit;
+            let start = instant_now();
+            for _ in 0..limit {
+                controller.start_request();
+            }
+            advance(rtt).await;
+            for _ in 0..limit {
+                controller.adjust_to_response_inner(start, is_back_pressure, !is_back_pressure);
+            }
+            limits.push(controller.inner.lock().unwrap().current_limit);
+        }
+        limits
+    }
+
+    fn controller(min_concurrency: usize, max_concurrency: usize) -> Controller<()> {
+        let settings = AdaptiveConcurrencySettings {
+            decrease_ratio: 0.5,
+            min_concurrency,
+            max_concurrency,
+            ..AdaptiveConcurrencySettings::const_default()
+        };
+        Controller::new(None, settings, ())
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn steady_latency_grows_the_limit_up_to_the_maximum() {
+        let controller = controller(2, 5);
+        let rtt = Duration::from_millis(50);
+        assert_eq!(controller.inner.lock().unwrap().current_limit, 2);
+
+        // The first round only measures the RTT.
+        assert_eq!(
+            rounds(&controller, rtt, false, 6).await,
+            vec![2, 3, 4, 5, 5, 5]
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn back_pressure_cuts_the_limit_down_to_the_minimum() {
+        let controller = controller(2, 8);
+        let rtt = Duration::from_millis(50);
+        assert_eq!(rounds(&controller, rtt, false, 7).await.last(), Some(&8));
+
+        assert_eq!(rounds(&controller, rtt, true, 3).await, vec![4, 2, 2]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn rising_latency_cuts_the_limit() {
+        let controller = controller(1, 8);
+        let rtt = Duration::from_millis(50);
+        assert_eq!(rounds(&controller, rtt, false, 5).await.last(), Some(&5));
+
+        assert_eq!(rounds(&controller, rtt * 4, false, 1).await, vec![2]);
+    }
+}
@@ -1,6 +1,9 @@
 //! Limit the max number of requests being concurrently processed.
 
+use std::convert::TryFrom;
+
 use serde::{Deserialize, Serialize};
+use snafu::Snafu;
 
 mod controller;
 mod future;
@@ -21,8 +24,11 @@ pub(self) fn instant_now() -> std::time::Instant {
 // The defaults for these values were chosen after running several
 // simulations on a test service that had various responses to load. The
 // values are the best balances found between competing outcomes.
+//
+// The bounds are checked as the settings are read, so that every sink
+// taking them refuses invalid ones.
 #[derive(Clone, Copy, Debug, Deserialize, Serialize)]
-#[serde(default, deny_unknown_fields)]
+#[serde(try_from = "UncheckedSettings")]
 pub struct AdaptiveConcurrencySettings {
     // This value maintained high concurrency without holding it too
     // high under adverse conditions.
@@ -35,6 +41,12 @@ pub struct AdaptiveConcurrencySettings {
     // This value avoided changing concurrency too aggressively when
     // there is fluctuation in the RTT measurements.
     pub(super) rtt_deviation_scale: f64,
+
+    // The bounds the limit is kept within. The limit starts at the
+    // minimum, and the maximum keeps a sink from growing it past what
+    // the service is known to handle.
+    pub(super) min_concurrency: usize,
+    pub(super) max_concurrency: usize,
 }
 
 impl AdaptiveConcurrencySettings {
@@ -43,6 +55,8 @@ impl AdaptiveConcurrencySettings {
             decrease_ratio: 0.9,
             ewma_alpha: 0.4,
             rtt_deviation_scale: 2.5,
+            min_concurrency: 1,
+            max_concurrency: MAX_CONCURRENCY,
         }
     }
 }
@@ -52,3 +66,55 @@ impl Default for AdaptiveConcurrencySettings {
         AdaptiveConcurrencySettings::const_default()
     }
 }
+
+#[derive(Debug, PartialEq, Snafu)]
+pub enum BoundsError {
+    #[snafu(display("`min_concurrency` must be greater than zero"))]
+    MinConcurrencyZero,
+    #[snafu(display("`max_concurrency` must be at least `min_concurrency`"))]
+    MaxBelowMin,
+}
+
+/// The settings as they are read, before their bounds are checked.
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct UncheckedSettings {
+    decrease_ratio: f64,
+    ewma_alpha: f64,
+    rtt_deviation_scale: f64,
+    min_concurrency: usize,
+    max_concurrency: usize,
+}
+
+impl Default for UncheckedSettings {
+    fn default() -> Self {
+        let settings = AdaptiveConcurrencySettings::const_default();
+        Self {
+            decrease_ratio: settings.decrease_ratio,
+            ewma_alpha: settings.ewma_alpha,
+            rtt_deviation_scale: settings.rtt_deviation_scale,
+            min_concurrency: settings.min_concurrency,
+            max_concurrency: settings.max_concurrency,
+        }
+    }
+}
+
+impl TryFrom<UncheckedSettings> for AdaptiveConcurrencySettings {
+    type Error = BoundsError;
+
+    fn try_from(settings: UncheckedSettings) -> Result<Self, Self::Error> {
+        if settings.min_concurrency == 0 {
+            return Err(BoundsError::MinConcurrencyZero);
+        }
+        if settings.max_concurrency < settings.min_concurrency {
+            return Err(BoundsError::MaxBelowMin);
+        }
+        Ok(Self {
+            decrease_ratio: settings.decrease_ratio,
+            ewma_alpha: settings.ewma_alpha,
+            rtt_deviation_scale: settings.rtt_deviation_scale,
+            min_concurrency: settings.min_concurrency,
+            max_concurrency: settings.max_concurrency,
+        })
+    }
+}
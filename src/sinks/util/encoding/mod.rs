@@ -155,21 +155,18 @@ pub trait EncodingConfiguration {
     }
     fn apply_timestamp_format(&self, log: &mut LogEvent) {
         if let Some(timestamp_format) = &self.timestamp_format() {
-            match timestamp_format {
-                TimestampFormat::Unix => {
-                    let mut unix_timestamps = Vec::new();
-                    for (k, v) in log.all_fields() {
-                        if let Value::Timestamp(ts) = v {
-                            unix_timestamps.push((k.clone(), Value::Integer(ts.timestamp())));
-                        }
-                    }
-                    for (k, v) in unix_timestamps {
-                        log.insert(k, v);
-                    }
-                }
-                // RFC3339 is the default serialization of a timestamp.
-                TimestampFormat::Rfc3339 => (),
-            }
+            timestamp_format.apply(log);
+        }
+    }
+    /// Restore the fields compressed in place, so that sinks write the values they were compressed
+    /// from. A field that can't be decompressed is written as its compressed bytes.
+    fn apply_decompression(&self, log: &mut LogEvent) {
+        if let Err(error) = log.decompress_fields() {
+            error!(
+                message = "Failed to decompress field, writing it compressed.",
+                %error,
+                internal_log_rate_secs = 10
+            );
         }
     }
 
@@ -204,6 +201,7 @@ pub trait EncodingConfiguration {
         // No rules are currently applied to metrics
         if let Some(log) = event.maybe_as_log_mut() {
             // Ordering in here should not matter.
+            self.apply_decompression(log);
             self.apply_except_fields(log);
             self.apply_only_fields(log);
             self.apply_timestamp_format(log);
@@ -274,13 +272,34 @@ pub enum TimestampFormat {
     Rfc3339,
 }
 
+impl TimestampFormat {
+    /// Convert the timestamps of `log` to this format.
+    pub fn apply(self, log: &mut LogEvent) {
+        match self {
+            Self::Unix => {
+                let mut unix_timestamps = Vec::new();
+                for (k, v) in log.all_fields() {
+                    if let Value::Timestamp(ts) = v {
+                        unix_timestamps.push((k.clone(), Value::Integer(ts.timestamp())));
+                    }
+                }
+                for (k, v) in unix_timestamps {
+                    log.insert(k, v);
+                }
+            }
+            // RFC3339 is the default serialization of a timestamp.
+            Self::Rfc3339 => (),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use indoc::indoc;
     use shared::btreemap;
 
     use super::*;
-    use crate::config::log_schema;
+    use crate::{config::log_schema, event::FieldCodec};
 
     #[derive(Deserialize, Serialize, Debug, Eq, PartialEq, Clone)]
     enum TestEncoding {
@@ -424,6 +443,21 @@ mod tests {
         encoding.timestamp_format = "unix"
     "#};
 
+    #[test]
+    fn test_decompression() {
+        let config: TestConfig = toml::from_str(TOML_SIMPLE_STRING).unwrap();
+        let mut event = Event::from("Demo");
+        event
+            .as_mut_log()
+            .compress_field(log_schema().message_key(), FieldCodec::Gzip)
+            .unwrap();
+
+        config.encoding.apply_rules(&mut event);
+
+        assert_eq!(event.as_log()[log_schema().message_key()], "Demo".into());
+        assert_eq!(event.as_log().compressed_fields().count(), 0);
+    }
+
     #[test]
     fn test_timestamp() {
         let config: TestConfig = toml::from_str(TOML_TIMESTAMP_FORMAT).unwrap();
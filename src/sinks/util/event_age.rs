@@ -0,0 +1,149 @@
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+
+use crate::{
+    event::EventMetadata,
+    internal_events::{EventAgeClockSkew, EventAgeObserved},
+};
+
+/// Record how long each of a batch of events about to be acknowledged took to get from the source
+/// that ingested it to the sink. Events without an ingest timestamp are skipped. Call this within
+/// the span of the sink, so that the metrics are tagged with its component.
+pub fn emit_event_ages<'a>(
+    metadata: impl IntoIterator<Item = &'a EventMetadata>,
+    now: DateTime<Utc>,
+) {
+    for age in metadata
+        .into_iter()
+        .filter_map(|metadata| metadata.age(now))
+    {
+        emit_event_age(age);
+    }
+}
+
+/// The ingest timestamps of a batch of events, for sinks that have encoded the events, and so no
+/// longer hold their metadata, by the time the batch is delivered.
+#[derive(Clone, Debug, Default)]
+pub struct EventAges {
+    ingest_timestamps: Vec<DateTime<Utc>>,
+}
+
+impl EventAges {
+    /// Add the ingest timestamp of an event in the batch, if it has one.
+    pub fn push(&mut self, metadata: &EventMetadata) {
+        self.ingest_timestamps.extend(metadata.ingest_timestamp());
+    }
+
+    /// Add the ingest timestamps of another batch.
+    pub fn merge(&mut self, other: Self) {
+        self.ingest_timestamps.extend(other.ingest_timestamps);
+    }
+
+    /// Record the age of each event in the batch as of `now`, like [`emit_event_ages`].
+    pub fn emit(&self, now: DateTime<Utc>) {
+        for timestamp in &self.ingest_timestamps {
+            emit_event_age(now - *timestamp);
+        }
+    }
+}
+
+fn emit_event_age(age: chrono::Duration) {
+    let (age, skew) = clamp_age(age);
+    if let Some(skew) = skew {
+        emit!(&EventAgeClockSkew { skew });
+    }
+    emit!(&EventAgeObserved { age });
+}
+
+/// Split a signed age into the age to record, and how far ahead of `now` the ingest timestamp
+/// was if it was. Such an age is recorded as zero.
+fn clamp_age(age: chrono::Duration) -> (Duration, Option<Duration>) {
+    match age.to_std() {
+        Ok(age) => (age, None),
+        Err(_) => (
+            Duration::ZERO,
+            Some((-age).to_std().unwrap_or(Duration::MAX)),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{event::metric::MetricValue, metrics::Controller};
+
+    use super::*;
+
+    fn metadata(ingest_timestamp: Option<DateTime<Utc>>) -> EventMetadata {
+        let mut metadata = EventMetadata::default();
+        if let Some(ingest_timestamp) = ingest_timestamp {
+            metadata.set_ingest_timestamp(ingest_timestamp);
+        }
+        metadata
+    }
+
+    fn metric_value(name: &str) -> Option<MetricValue> {
+        Controller::get()
+            .expect("failed to init metric container")
+            .capture_metrics()
+            .find(|metric| metric.name() == name)
+            .map(|metric| metric.value().clone())
+    }
+
+    #[test]
+    fn clamps_negative_ages() {
+        let second = chrono::Duration::seconds(1);
+
+        assert_eq!(clamp_age(second), (Duration::from_secs(1), None));
+        assert_eq!(clamp_age(chrono::Duration::zero()), (Duration::ZERO, None));
+        assert_eq!(
+            clamp_age(-second),
+            (Duration::ZERO, Some(Duration::from_secs(1)))
+        );
+    }
+
+    #[test]
+    fn skewed_and_missing_ingest_timestamps() {
+        let _ = crate::metrics::init_test();
+        let now = Utc::now();
+        let batch = vec![
+            metadata(Some(now - chrono::Duration::seconds(2))),
+            metadata(None),
+            metadata(Some(now + chrono::Duration::seconds(5))),
+        ];
+
+        assert_eq!(batch[1].age(now), None);
+        assert_eq!(batch[2].age(now), Some(chrono::Duration::seconds(-5)));
+
+        emit_event_ages(&batch, now);
+
+        match metric_value("event_age_seconds") {
+            Some(MetricValue::AggregatedHistogram { count, sum, .. }) => {
+                assert_eq!(count, 2);
+                assert!((sum - 2.0).abs() < 1e-6, "{}", sum);
+            }
+            value => panic!("unexpected event_age_seconds {:?}", value),
+        }
+        assert_eq!(
+            metric_value("event_age_clock_skew_total"),
+            Some(MetricValue::Counter { value: 1.0 })
+        );
+    }
+
+    #[test]
+    fn event_ages_collects_ingest_timestamps() {
+        let now = Utc::now();
+        let mut ages = EventAges::default();
+        ages.push(&metadata(Some(now)));
+        ages.push(&metadata(None));
+
+        let mut other = EventAges::default();
+        other.push(&metadata(Some(now - chrono::Duration::seconds(1))));
+        ages.merge(other);
+
+        assert_eq!(
+            ages.ingest_timestamps,
+            vec![now, now - chrono::Duration::seconds(1)]
+        );
+    }
+}
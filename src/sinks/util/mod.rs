@@ -4,12 +4,14 @@ pub mod buffer;
 pub mod builder;
 pub mod compressor;
 pub mod encoding;
+pub mod event_age;
 pub mod http;
 pub mod normalizer;
 pub mod partitioner;
 pub mod processed_event;
 pub mod request_builder;
 pub mod retries;
+pub mod sampler;
 pub mod service;
 pub mod sink;
 pub mod socket_bytes_sink;
@@ -1,17 +1,27 @@
 use std::{
     borrow::Cow,
     cmp,
+    collections::VecDeque,
     future::Future,
     pin::Pin,
+    sync::{Arc, Mutex},
     task::{Context, Poll},
     time::Duration,
 };
 
-use futures::FutureExt;
-use tokio::time::{sleep, Sleep};
-use tower::{retry::Policy, timeout::error::Elapsed};
-
-use crate::Error;
+use futures::{future::BoxFuture, FutureExt};
+use serde::{Deserialize, Serialize};
+use tokio::time::{sleep, Instant, Sleep};
+use tower::{retry::Policy, timeout::error::Elapsed, Layer, Service};
+pub use vector_core::stream::CircuitOpen;
+
+use crate::{
+    internal_events::{
+        CircuitBreakerClosed, CircuitBreakerHalfOpened, CircuitBreakerOpened,
+        CircuitBreakerRequestRejected,
+    },
+    Error,
+};
 
 pub enum RetryAction {
     /// Indicate that this request should be retried with a reason
@@ -28,6 +38,19 @@ pub trait RetryLogic: Clone + Send + Sync + 'static {
 
     fn is_retriable_error(&self, error: &Self::Error) -> bool;
 
+    /// Whether `error` is a sign of the destination being overloaded, for
+    /// adaptive concurrency to lower the limit. Only some retriable errors
+    /// may be, but by default all of them are taken to be.
+    fn is_back_pressure(&self, error: &Self::Error) -> bool {
+        self.is_retriable_error(error)
+    }
+
+    /// How long the destination asked to wait before retrying after `error`, if it did. The retry
+    /// waits for the longer of this and the backoff.
+    fn retry_after(&self, _error: &Self::Error) -> Option<Duration> {
+        None
+    }
+
     fn should_retry_response(&self, _response: &Self::Response) -> RetryAction {
         // Treat the default as the request is successful
         RetryAction::Successful
@@ -80,12 +103,15 @@ impl<L: RetryLogic> FixedRetryPolicy<L> {
         self.current_duration
     }
 
-    fn build_retry(&self) -> RetryPolicyFuture<L> {
+    fn build_retry(&self, retry_after: Option<Duration>) -> RetryPolicyFuture<L> {
         let policy = self.advance();
-        let delay = Box::pin(sleep(self.backoff()));
+        let delay = cmp::max(self.backoff(), retry_after.unwrap_or_default());
 
-        debug!(message = "Retrying request.", delay_ms = %self.backoff().as_millis());
-        RetryPolicyFuture { delay, policy }
+        debug!(message = "Retrying request.", delay_ms = %delay.as_millis());
+        RetryPolicyFuture {
+            delay: Box::pin(sleep(delay)),
+            policy,
+        }
     }
 }
 
@@ -109,7 +135,7 @@ where
                     }
 
                     warn!(message = "Retrying after response.", reason = %reason);
-                    Some(self.build_retry())
+                    Some(self.build_retry(None))
                 }
 
                 RetryAction::DontRetry(reason) => {
@@ -120,6 +146,12 @@ where
                 RetryAction::Successful => None,
             },
             Err(error) => {
+                if error.is::<CircuitOpen>() {
+                    // Retrying would only be rejected again, and the breaker
+                    // already reported opening.
+                    return None;
+                }
+
                 if self.remaining_attempts == 0 {
                     error!(message = "Retries exhausted; dropping the request.", %error);
                     return None;
@@ -128,7 +160,7 @@ where
                 if let Some(expected) = error.downcast_ref::<L::Error>() {
                     if self.logic.is_retriable_error(expected) {
                         warn!(message = "Retrying after error.", error = %expected);
-                        Some(self.build_retry())
+                        Some(self.build_retry(self.logic.retry_after(expected)))
                     } else {
                         error!(
                             message = "Non-retriable error; dropping the request.",
@@ -138,7 +170,7 @@ where
                     }
                 } else if error.downcast_ref::<Elapsed>().is_some() {
                     warn!("Request timed out. If this happens often while the events are actually reaching their destination, try decreasing `batch.max_bytes` and/or using `compression` if applicable. Alternatively `request.timeout_secs` can be increased.");
-                    Some(self.build_retry())
+                    Some(self.build_retry(None))
                 } else {
                     error!(
                         message = "Unexpected error type; dropping the request.",
@@ -182,6 +214,259 @@ impl RetryAction {
     }
 }
 
+pub const CIRCUIT_BREAKER_FAILURE_RATE_THRESHOLD_DEFAULT: f64 = 0.5;
+pub const CIRCUIT_BREAKER_MINIMUM_REQUESTS_DEFAULT: usize = 20;
+pub const CIRCUIT_BREAKER_OPEN_DURATION_SECONDS_DEFAULT: u64 = 30;
+pub const CIRCUIT_BREAKER_HALF_OPEN_PROBES_DEFAULT: usize = 1;
+
+const fn default_failure_rate_threshold() -> f64 {
+    CIRCUIT_BREAKER_FAILURE_RATE_THRESHOLD_DEFAULT
+}
+
+const fn default_minimum_requests() -> usize {
+    CIRCUIT_BREAKER_MINIMUM_REQUESTS_DEFAULT
+}
+
+const fn default_open_duration_secs() -> u64 {
+    CIRCUIT_BREAKER_OPEN_DURATION_SECONDS_DEFAULT
+}
+
+const fn default_half_open_probes() -> usize {
+    CIRCUIT_BREAKER_HALF_OPEN_PROBES_DEFAULT
+}
+
+/// Circuit breaker configuration
+///
+/// Once enough of the recent requests have failed, the breaker opens and
+/// rejects every request without sending it. After `open_duration_secs` it
+/// lets `half_open_probes` requests through, closing again if they all
+/// succeed and reopening as soon as one of them fails.
+#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct CircuitBreakerConfig {
+    /// The fraction of failed requests, between 0 and 1, at which the breaker opens.
+    #[serde(default = "default_failure_rate_threshold")]
+    pub failure_rate_threshold: f64,
+    /// The number of most recent requests the failure rate is computed over.
+    /// The breaker never opens before this many requests have completed.
+    #[serde(default = "default_minimum_requests")]
+    pub minimum_requests: usize,
+    #[serde(default = "default_open_duration_secs")]
+    pub open_duration_secs: u64,
+    #[serde(default = "default_half_open_probes")]
+    pub half_open_probes: usize,
+}
+
+impl Default for CircuitBreakerConfig {
+    fn default() -> Self {
+        Self {
+            failure_rate_threshold: CIRCUIT_BREAKER_FAILURE_RATE_THRESHOLD_DEFAULT,
+            minimum_requests: CIRCUIT_BREAKER_MINIMUM_REQUESTS_DEFAULT,
+            open_duration_secs: CIRCUIT_BREAKER_OPEN_DURATION_SECONDS_DEFAULT,
+            half_open_probes: CIRCUIT_BREAKER_HALF_OPEN_PROBES_DEFAULT,
+        }
+    }
+}
+
+#[derive(Debug)]
+enum BreakerState {
+    /// Outcomes of the most recent requests, `true` for failures.
+    Closed {
+        outcomes: VecDeque<bool>,
+    },
+    Open {
+        until: Instant,
+    },
+    HalfOpen {
+        sent: usize,
+        succeeded: usize,
+    },
+}
+
+#[derive(Debug)]
+struct Breaker {
+    config: CircuitBreakerConfig,
+    state: BreakerState,
+    /// Bumped on every state change so that responses to requests sent
+    /// before the change are not attributed to the new state.
+    generation: u64,
+}
+
+impl Breaker {
+    fn new(config: CircuitBreakerConfig) -> Self {
+        Self {
+            config: CircuitBreakerConfig {
+                minimum_requests: config.minimum_requests.max(1),
+                half_open_probes: config.half_open_probes.max(1),
+                ..config
+            },
+            state: BreakerState::Closed {
+                outcomes: VecDeque::new(),
+            },
+            generation: 0,
+        }
+    }
+
+    fn transition(&mut self, state: BreakerState) {
+        self.state = state;
+        self.generation += 1;
+    }
+
+    /// Returns the generation to record the outcome against if a request may
+    /// be sent now, or `None` if it must be rejected.
+    fn try_acquire(&mut self) -> Option<u64> {
+        if let BreakerState::Open { until } = self.state {
+            if Instant::now() < until {
+                return None;
+            }
+            self.transition(BreakerState::HalfOpen {
+                sent: 0,
+                succeeded: 0,
+            });
+            emit!(&CircuitBreakerHalfOpened {
+                probes: self.config.half_open_probes
+            });
+        }
+
+        match &mut self.state {
+            BreakerState::Closed { .. } => Some(self.generation),
+            BreakerState::HalfOpen { sent, .. } if *sent < self.config.half_open_probes => {
+                *sent += 1;
+                Some(self.generation)
+            }
+            BreakerState::HalfOpen { .. } | BreakerState::Open { .. } => None,
+        }
+    }
+
+    fn record(&mut self, generation: u64, failed: bool) {
+        if generation != self.generation {
+            return;
+        }
+
+        match &mut self.state {
+            BreakerState::Closed { outcomes } => {
+                outcomes.push_back(failed);
+                if outcomes.len() > self.config.minimum_requests {
+                    outcomes.pop_front();
+                }
+                if outcomes.len() == self.config.minimum_requests {
+                    let failures = outcomes.iter().filter(|failed| **failed).count();
+                    let failure_rate = failures as f64 / outcomes.len() as f64;
+                    if failure_rate >= self.config.failure_rate_threshold {
+                        self.open(failure_rate);
+                    }
+                }
+            }
+            BreakerState::HalfOpen { .. } if failed => self.open(1.0),
+            BreakerState::HalfOpen { succeeded, .. } => {
+                *succeeded += 1;
+                if *succeeded >= self.config.half_open_probes {
+                    self.transition(BreakerState::Closed {
+                        outcomes: VecDeque::new(),
+                    });
+                    emit!(&CircuitBreakerClosed);
+                }
+            }
+            BreakerState::Open { .. } => {}
+        }
+    }
+
+    fn open(&mut self, failure_rate: f64) {
+        let open_duration = Duration::from_secs(self.config.open_duration_secs);
+        self.transition(BreakerState::Open {
+            until: Instant::now() + open_duration,
+        });
+        emit!(&CircuitBreakerOpened {
+            failure_rate,
+            open_duration,
+        });
+    }
+}
+
+/// A circuit breaker in front of each request attempt.
+///
+/// Failures are classified with the sink's `RetryLogic`: retriable errors,
+/// timeouts and responses that would be retried count against the
+/// downstream, while non-retriable errors do not. The breaker state is
+/// shared between all clones of the service.
+#[derive(Clone, Debug)]
+pub struct CircuitBreaker<S, L> {
+    inner: S,
+    logic: L,
+    breaker: Arc<Mutex<Breaker>>,
+}
+
+impl<S, L> CircuitBreaker<S, L> {
+    pub fn new(inner: S, config: CircuitBreakerConfig, logic: L) -> Self {
+        Self {
+            inner,
+            logic,
+            breaker: Arc::new(Mutex::new(Breaker::new(config))),
+        }
+    }
+}
+
+impl<S, L, Request> Service<Request> for CircuitBreaker<S, L>
+where
+    S: Service<Request, Error = Error>,
+    S::Response: Send + 'static,
+    S::Future: Send + 'static,
+    L: RetryLogic<Response = S::Response>,
+{
+    type Response = S::Response;
+    type Error = Error;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request) -> Self::Future {
+        let generation = match self.breaker.lock().unwrap().try_acquire() {
+            Some(generation) => generation,
+            None => {
+                emit!(&CircuitBreakerRequestRejected);
+                return Box::pin(futures::future::err(Box::new(CircuitOpen) as Error));
+            }
+        };
+
+        let logic = self.logic.clone();
+        let breaker = Arc::clone(&self.breaker);
+        let future = self.inner.call(request);
+        Box::pin(async move {
+            let result = future.await;
+            let failed = match &result {
+                Ok(response) => logic.should_retry_response(response).is_retryable(),
+                Err(error) => error
+                    .downcast_ref::<L::Error>()
+                    .map_or(true, |error| logic.is_retriable_error(error)),
+            };
+            breaker.lock().unwrap().record(generation, failed);
+            result
+        })
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct CircuitBreakerLayer<L> {
+    config: CircuitBreakerConfig,
+    logic: L,
+}
+
+impl<L> CircuitBreakerLayer<L> {
+    pub const fn new(config: CircuitBreakerConfig, logic: L) -> Self {
+        Self { config, logic }
+    }
+}
+
+impl<S, L: Clone> Layer<S> for CircuitBreakerLayer<L> {
+    type Service = CircuitBreaker<S, L>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        CircuitBreaker::new(inner, self.config, self.logic.clone())
+    }
+}
+
 // `tokio-retry` crate
 // MIT License
 // Copyright (c) 2017 Sam Rijs
@@ -259,12 +544,17 @@ impl Iterator for ExponentialBackoff {
 
 #[cfg(test)]
 mod tests {
-    use std::{fmt, time::Duration};
+    use std::{
+        fmt,
+        sync::atomic::{AtomicBool, AtomicUsize, Ordering},
+        time::Duration,
+    };
 
     use tokio::time;
     use tokio_test::{assert_pending, assert_ready_err, assert_ready_ok, task};
-    use tower::retry::RetryLayer;
+    use tower::{retry::RetryLayer, ServiceExt};
     use tower_test::{assert_request_eq, mock};
+    use vector_core::{event::MetricValue, metrics::Controller};
 
     use super::*;
     use crate::test_util::trace_init;
@@ -348,6 +638,38 @@ mod tests {
         assert_eq!(fut.await.unwrap(), "world");
     }
 
+    #[tokio::test]
+    async fn retry_waits_for_requested_delay() {
+        trace_init();
+
+        time::pause();
+
+        let policy = FixedRetryPolicy::new(
+            5,
+            Duration::from_secs(1),
+            Duration::from_secs(10),
+            PushbackRetryLogic,
+        );
+
+        let (mut svc, mut handle) = mock::spawn_layer(RetryLayer::new(policy));
+
+        assert_ready_ok!(svc.poll_ready());
+
+        let mut fut = task::spawn(svc.call("hello"));
+        assert_request_eq!(handle, "hello").send_error(Error(true));
+        assert_pending!(fut.poll());
+
+        // Past the backoff, but not the five seconds asked for.
+        time::advance(Duration::from_secs(4)).await;
+        assert_pending!(fut.poll());
+        assert_pending!(handle.poll_request());
+
+        time::advance(Duration::from_secs(2)).await;
+        assert_pending!(fut.poll());
+        assert_request_eq!(handle, "hello").send_response("world");
+        assert_eq!(fut.await.unwrap(), "world");
+    }
+
     #[test]
     fn backoff_grows_to_max() {
         let mut policy = FixedRetryPolicy::new(
@@ -380,6 +702,194 @@ mod tests {
         assert_eq!(Duration::from_secs(10), policy.backoff());
     }
 
+    /// A service that fails while `failing` is set, counting how many
+    /// requests actually reached it.
+    #[derive(Clone)]
+    struct ScriptedService {
+        failing: Arc<AtomicBool>,
+        retriable: bool,
+        calls: Arc<AtomicUsize>,
+    }
+
+    impl ScriptedService {
+        fn new(retriable: bool) -> Self {
+            Self {
+                failing: Arc::new(AtomicBool::new(true)),
+                retriable,
+                calls: Arc::new(AtomicUsize::new(0)),
+            }
+        }
+
+        fn set_failing(&self, failing: bool) {
+            self.failing.store(failing, Ordering::SeqCst);
+        }
+
+        fn calls(&self) -> usize {
+            self.calls.load(Ordering::SeqCst)
+        }
+    }
+
+    impl Service<&'static str> for ScriptedService {
+        type Response = &'static str;
+        type Error = crate::Error;
+        type Future = futures::future::Ready<Result<Self::Response, Self::Error>>;
+
+        fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn call(&mut self, _request: &'static str) -> Self::Future {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            futures::future::ready(if self.failing.load(Ordering::SeqCst) {
+                Err(Box::new(Error(self.retriable)) as crate::Error)
+            } else {
+                Ok("world")
+            })
+        }
+    }
+
+    const BREAKER_CONFIG: CircuitBreakerConfig = CircuitBreakerConfig {
+        failure_rate_threshold: 0.5,
+        minimum_requests: 4,
+        open_duration_secs: 10,
+        half_open_probes: 2,
+    };
+
+    fn rejected_requests() -> Option<MetricValue> {
+        Controller::get()
+            .unwrap()
+            .capture_metrics()
+            .find(|metric| metric.name() == "circuit_breaker_rejected_requests_total")
+            .map(|metric| metric.value().clone())
+    }
+
+    #[tokio::test]
+    async fn circuit_breaker_state_machine() {
+        trace_init();
+        let _ = crate::metrics::init_test();
+
+        time::pause();
+
+        let inner = ScriptedService::new(true);
+        let svc = CircuitBreaker::new(inner.clone(), BREAKER_CONFIG, SvcRetryLogic);
+
+        // Closed: failures pass through until enough requests have been seen
+        // to compute the failure rate.
+        for _ in 0..4 {
+            let error = svc.clone().oneshot("hello").await.unwrap_err();
+            assert!(error.is::<Error>());
+        }
+        assert_eq!(inner.calls(), 4);
+
+        // Open: rejected without reaching the service.
+        let error = svc.clone().oneshot("hello").await.unwrap_err();
+        assert!(error.is::<CircuitOpen>());
+        time::advance(Duration::from_secs(5)).await;
+        let error = svc.clone().oneshot("hello").await.unwrap_err();
+        assert!(error.is::<CircuitOpen>());
+        assert_eq!(inner.calls(), 4);
+        assert_eq!(
+            rejected_requests(),
+            Some(MetricValue::Counter { value: 2.0 })
+        );
+
+        // Half-open: a failing probe reopens the breaker straight away.
+        time::advance(Duration::from_secs(5)).await;
+        let error = svc.clone().oneshot("hello").await.unwrap_err();
+        assert!(error.is::<Error>());
+        let error = svc.clone().oneshot("hello").await.unwrap_err();
+        assert!(error.is::<CircuitOpen>());
+        assert_eq!(inner.calls(), 5);
+
+        // Half-open again: enough successful probes close it.
+        time::advance(Duration::from_secs(10)).await;
+        inner.set_failing(false);
+        assert_eq!(svc.clone().oneshot("hello").await.unwrap(), "world");
+        assert_eq!(svc.clone().oneshot("hello").await.unwrap(), "world");
+
+        // Closed: the failure rate starts over, so the occasional failure
+        // among successes is tolerated.
+        for failing in [true, false, false, false, true, false] {
+            inner.set_failing(failing);
+            let _ = svc.clone().oneshot("hello").await;
+        }
+        assert_eq!(inner.calls(), 13);
+    }
+
+    #[tokio::test]
+    async fn circuit_breaker_ignores_non_retriable_errors() {
+        trace_init();
+
+        let inner = ScriptedService::new(false);
+        let svc = CircuitBreaker::new(inner.clone(), BREAKER_CONFIG, SvcRetryLogic);
+
+        for _ in 0..10 {
+            let error = svc.clone().oneshot("hello").await.unwrap_err();
+            assert!(error.is::<Error>());
+        }
+        assert_eq!(inner.calls(), 10);
+    }
+
+    #[tokio::test]
+    async fn circuit_breaker_limits_probes() {
+        trace_init();
+
+        time::pause();
+
+        let (mock, mut handle) = mock::pair::<&'static str, &'static str>();
+        let svc = CircuitBreaker::new(mock, BREAKER_CONFIG, SvcRetryLogic);
+
+        for _ in 0..4 {
+            let mut fut = task::spawn(svc.clone().oneshot("hello"));
+            assert_pending!(fut.poll());
+            assert_request_eq!(handle, "hello").send_error(Error(true));
+            assert_ready_err!(fut.poll());
+        }
+
+        time::advance(Duration::from_secs(10)).await;
+
+        // Only two probes may be in flight while half-open.
+        let mut first = task::spawn(svc.clone().oneshot("hello"));
+        let mut second = task::spawn(svc.clone().oneshot("hello"));
+        let mut third = task::spawn(svc.clone().oneshot("hello"));
+        assert_pending!(first.poll());
+        assert_pending!(second.poll());
+        let error = assert_ready_err!(third.poll());
+        assert!(error.is::<CircuitOpen>());
+
+        assert_request_eq!(handle, "hello").send_response("world");
+        assert_request_eq!(handle, "hello").send_response("world");
+        assert_eq!(assert_ready_ok!(first.poll()), "world");
+        assert_eq!(assert_ready_ok!(second.poll()), "world");
+
+        // Both probes succeeded, so requests flow again.
+        let mut fut = task::spawn(svc.clone().oneshot("hello"));
+        assert_pending!(fut.poll());
+        assert_request_eq!(handle, "hello").send_response("world");
+        assert_eq!(assert_ready_ok!(fut.poll()), "world");
+    }
+
+    #[tokio::test]
+    async fn circuit_open_is_not_retried() {
+        trace_init();
+
+        let policy = FixedRetryPolicy::new(
+            5,
+            Duration::from_secs(1),
+            Duration::from_secs(10),
+            SvcRetryLogic,
+        );
+
+        let (mut svc, mut handle) = mock::spawn_layer(RetryLayer::new(policy));
+
+        assert_ready_ok!(svc.poll_ready());
+
+        let mut fut = task::spawn(svc.call("hello"));
+        assert_request_eq!(handle, "hello").send_error(CircuitOpen);
+        let error = assert_ready_err!(fut.poll());
+        assert!(error.is::<CircuitOpen>());
+    }
+
     #[derive(Debug, Clone)]
     struct SvcRetryLogic;
 
@@ -392,6 +902,22 @@ mod tests {
         }
     }
 
+    #[derive(Debug, Clone)]
+    struct PushbackRetryLogic;
+
+    impl RetryLogic for PushbackRetryLogic {
+        type Error = Error;
+        type Response = &'static str;
+
+        fn is_retriable_error(&self, error: &Self::Error) -> bool {
+            error.0
+        }
+
+        fn retry_after(&self, _error: &Self::Error) -> Option<Duration> {
+            Some(Duration::from_secs(5))
+        }
+    }
+
     #[derive(Debug)]
     struct Error(bool);
 
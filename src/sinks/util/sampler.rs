@@ -0,0 +1,289 @@
+//! Sampling log events deterministically, so that a sink under overload sheds a share of them
+//! rather than running out of memory, and the downstream can scale what it counts back up.
+
+use std::{hash::Hasher, time::Duration};
+
+use serde::{Deserialize, Serialize};
+use tokio::time::Instant;
+use twox_hash::XxHash64;
+use vector_core::{
+    event::{Event, LogEvent},
+    ByteSizeOf,
+};
+
+const fn default_interval_secs() -> u64 {
+    1
+}
+
+fn default_rate_field() -> String {
+    "sample_rate".to_owned()
+}
+
+/// Which log events to keep. With both `rate` and `max_bytes` set, the lower of the two shares
+/// of events is kept.
+#[derive(Clone, Debug, Derivative, Deserialize, PartialEq, Serialize)]
+#[derivative(Default)]
+#[serde(deny_unknown_fields)]
+pub struct SamplerConfig {
+    /// Keep one in `rate` events.
+    #[serde(default)]
+    pub rate: Option<u64>,
+    /// Keep about this many bytes of events per interval, sampling at the share of the bytes
+    /// that came in over the last interval that this makes up.
+    #[serde(default)]
+    pub max_bytes: Option<usize>,
+    #[derivative(Default(value = "default_interval_secs()"))]
+    #[serde(default = "default_interval_secs")]
+    pub interval_secs: u64,
+    /// The field whose value decides whether an event is kept, so that all the events with the
+    /// same value, such as those of one request, are kept or dropped together. Events without
+    /// it are decided on all of their fields.
+    #[serde(default)]
+    pub key_field: Option<String>,
+    /// Seeds the hash of events. The same events are kept for the same seed, across restarts and
+    /// on every Vector.
+    #[serde(default)]
+    pub seed: u64,
+    /// The field of the kept events that the rate they were sampled at is recorded in.
+    #[derivative(Default(value = "default_rate_field()"))]
+    #[serde(default = "default_rate_field")]
+    pub rate_field: String,
+}
+
+impl SamplerConfig {
+    pub fn build(&self) -> Sampler {
+        Sampler {
+            rate: self.rate,
+            max_bytes: self.max_bytes,
+            interval: Duration::from_secs(self.interval_secs),
+            key_field: self.key_field.clone(),
+            seed: self.seed,
+            rate_field: self.rate_field.clone(),
+            window: None,
+        }
+    }
+}
+
+/// Whether to keep an event, and at what rate events are sampled.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SampleDecision {
+    pub keep: bool,
+    /// How many events each kept event stands for, the inverse of the share of events kept.
+    /// Counts of the kept events are scaled by it to estimate those of all events.
+    pub rate: f64,
+}
+
+/// The bytes of events seen over an interval.
+#[derive(Debug)]
+struct Window {
+    start: Instant,
+    bytes: usize,
+    /// The bytes seen over the interval before, if it immediately preceded this one.
+    previous_bytes: usize,
+}
+
+/// Decides which log events to keep. An event is hashed to a point between 0 and 1 and kept if
+/// that is below the share of events kept, so the same events are kept for the same seed, and
+/// an event kept at some share is kept at any higher one. Metrics are always kept.
+#[derive(Debug)]
+pub struct Sampler {
+    rate: Option<u64>,
+    max_bytes: Option<usize>,
+    interval: Duration,
+    key_field: Option<String>,
+    seed: u64,
+    rate_field: String,
+    window: Option<Window>,
+}
+
+impl Sampler {
+    pub fn should_keep(&mut self, event: &Event) -> SampleDecision {
+        let log = match event {
+            Event::Log(log) => log,
+            Event::Metric(_) => {
+                return SampleDecision {
+                    keep: true,
+                    rate: 1.0,
+                }
+            }
+        };
+        let share = self.share(event.size_of());
+        SampleDecision {
+            keep: self.point(log) < share,
+            rate: 1.0 / share,
+        }
+    }
+
+    pub fn rate_field(&self) -> &str {
+        &self.rate_field
+    }
+
+    /// The share of events to keep, after counting the `byte_size` of the current one.
+    fn share(&mut self, byte_size: usize) -> f64 {
+        let mut share = self.rate.map_or(1.0, |rate| 1.0 / rate as f64);
+        if let Some(max_bytes) = self.max_bytes {
+            let bytes = self.count_bytes(byte_size);
+            if bytes > max_bytes {
+                share = share.min(max_bytes as f64 / bytes as f64);
+            }
+        }
+        share
+    }
+
+    /// Count `byte_size` in the current window, and return the most bytes seen over it or the
+    /// one before. The bytes seen so far are counted as well, so that a burst is sampled before
+    /// its first interval is over.
+    fn count_bytes(&mut self, byte_size: usize) -> usize {
+        let now = Instant::now();
+        let interval = self.interval;
+        let window = self.window.get_or_insert(Window {
+            start: now,
+            bytes: 0,
+            previous_bytes: 0,
+        });
+        let elapsed = now.duration_since(window.start);
+        if elapsed >= interval {
+            *window = Window {
+                start: now,
+                bytes: 0,
+                // After an interval without events, the bytes before it say nothing of now.
+                previous_bytes: if elapsed < interval * 2 {
+                    window.bytes
+                } else {
+                    0
+                },
+            };
+        }
+        window.bytes += byte_size;
+        window.bytes.max(window.previous_bytes)
+    }
+
+    /// Hash the event to a point in `[0, 1)`, from the canonical form of its key or its fields.
+    fn point(&self, log: &LogEvent) -> f64 {
+        let mut hasher = XxHash64::with_seed(self.seed);
+        match self.key_field.as_ref().and_then(|field| log.get(field)) {
+            Some(key) => hasher.write(&key.to_canonical_json()),
+            None => hasher.write(&log.to_canonical_json()),
+        }
+        // The top 53 bits are as many as an `f64` holds exactly.
+        (hasher.finish() >> 11) as f64 / (1_u64 << 53) as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use vector_core::event::{Metric, MetricKind, MetricValue};
+
+    use super::*;
+
+    fn events(count: usize) -> Vec<Event> {
+        (0..count)
+            .map(|i| Event::from(format!("event {}", i)))
+            .collect()
+    }
+
+    fn decisions(sampler: &mut Sampler, events: &[Event]) -> Vec<bool> {
+        events
+            .iter()
+            .map(|event| sampler.should_keep(event).keep)
+            .collect()
+    }
+
+    fn config(rate: u64, seed: u64) -> SamplerConfig {
+        SamplerConfig {
+            rate: Some(rate),
+            seed,
+            ..SamplerConfig::default()
+        }
+    }
+
+    #[test]
+    fn same_seed_keeps_same_events() {
+        let events = events(1_000);
+        let kept = decisions(&mut config(4, 7).build(), &events);
+
+        // As a restarted sink would.
+        assert_eq!(decisions(&mut config(4, 7).build(), &events), kept);
+        assert_ne!(decisions(&mut config(4, 8).build(), &events), kept);
+
+        let count = kept.iter().filter(|keep| **keep).count();
+        assert!((200..300).contains(&count), "{}", count);
+        assert_eq!(config(4, 7).build().should_keep(&events[0]).rate, 4.0);
+    }
+
+    #[test]
+    fn events_with_same_key_share_fate() {
+        let mut sampler = SamplerConfig {
+            key_field: Some("request_id".to_owned()),
+            ..config(2, 0)
+        }
+        .build();
+
+        let mut kept_requests = 0;
+        for request in 0..100 {
+            let fates = (0..5)
+                .map(|i| {
+                    let mut log = LogEvent::from(format!("event {} of request {}", i, request));
+                    log.insert("request_id", request);
+                    sampler.should_keep(&log.into()).keep
+                })
+                .collect::<Vec<_>>();
+            assert!(fates.iter().all(|keep| *keep == fates[0]), "{}", request);
+            kept_requests += fates[0] as usize;
+        }
+        assert!((30..70).contains(&kept_requests), "{}", kept_requests);
+    }
+
+    #[test]
+    fn metrics_are_kept() {
+        let mut sampler = config(1_000, 0).build();
+        let metric = Event::Metric(Metric::new(
+            "requests",
+            MetricKind::Incremental,
+            MetricValue::Counter { value: 1.0 },
+        ));
+        for _ in 0..10 {
+            assert_eq!(
+                sampler.should_keep(&metric),
+                SampleDecision {
+                    keep: true,
+                    rate: 1.0
+                }
+            );
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn byte_budget_follows_traffic() {
+        let events = events(1_000);
+        let total_bytes = events.iter().map(ByteSizeOf::size_of).sum::<usize>();
+        let mut sampler = SamplerConfig {
+            max_bytes: Some(total_bytes / 10),
+            ..SamplerConfig::default()
+        }
+        .build();
+
+        // Everything is kept until the budget is reached, then less and less of the burst.
+        let first = events
+            .iter()
+            .map(|event| sampler.should_keep(event))
+            .collect::<Vec<_>>();
+        assert!(first[..50].iter().all(|decision| decision.keep));
+        let last_rate = first.last().unwrap().rate;
+        assert!((9.0..11.0).contains(&last_rate), "{}", last_rate);
+
+        // The next interval is sampled at the rate of the one before from its start.
+        tokio::time::advance(Duration::from_secs(1)).await;
+        let second = events
+            .iter()
+            .map(|event| sampler.should_keep(event))
+            .collect::<Vec<_>>();
+        assert!((9.0..11.0).contains(&second[0].rate), "{}", second[0].rate);
+        let kept = second.iter().filter(|decision| decision.keep).count();
+        assert!((50..150).contains(&kept), "{}", kept);
+
+        // After an idle interval, everything is kept again.
+        tokio::time::advance(Duration::from_secs(2)).await;
+        assert_eq!(sampler.should_keep(&events[0]).rate, 1.0);
+    }
+}
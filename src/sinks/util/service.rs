@@ -19,7 +19,7 @@ use crate::sinks::util::{
     adaptive_concurrency::{
         AdaptiveConcurrencyLimit, AdaptiveConcurrencyLimitLayer, AdaptiveConcurrencySettings,
     },
-    retries::{FixedRetryPolicy, RetryLogic},
+    retries::{CircuitBreakerConfig, CircuitBreakerLayer, FixedRetryPolicy, RetryLogic},
     service::map::MapLayer,
     sink::{Response, ServiceLogic},
     Batch, BatchSink, Partition, PartitionBatchSink,
@@ -79,6 +79,8 @@ pub struct TowerRequestConfig {
     pub retry_initial_backoff_secs: Option<u64>, // 1
     #[serde(default)]
     pub adaptive_concurrency: AdaptiveConcurrencySettings,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub circuit_breaker: Option<CircuitBreakerConfig>,
 }
 
 pub const CONCURRENCY_DEFAULT: Concurrency = Concurrency::None;
@@ -106,6 +108,7 @@ impl TowerRequestConfig {
             retry_max_duration_secs: Some(RETRY_MAX_DURATION_SECONDS_DEFAULT),
             retry_initial_backoff_secs: Some(RETRY_INITIAL_BACKOFF_SECONDS_DEFAULT),
             adaptive_concurrency: AdaptiveConcurrencySettings::const_default(),
+            circuit_breaker: None,
         }
     }
 
@@ -175,6 +178,7 @@ impl TowerRequestConfig {
                     .unwrap_or(RETRY_INITIAL_BACKOFF_SECONDS_DEFAULT),
             ),
             adaptive_concurrency: self.adaptive_concurrency,
+            circuit_breaker: self.circuit_breaker.or(defaults.circuit_breaker),
         }
     }
 }
@@ -189,6 +193,7 @@ pub struct TowerRequestSettings {
     pub retry_max_duration_secs: Duration,
     pub retry_initial_backoff_secs: Duration,
     pub adaptive_concurrency: AdaptiveConcurrencySettings,
+    pub circuit_breaker: Option<CircuitBreakerConfig>,
 }
 
 impl TowerRequestSettings {
@@ -310,6 +315,11 @@ where
                 self.settings.rate_limit_duration,
             )
             .retry(policy)
+            .option_layer(
+                self.settings
+                    .circuit_breaker
+                    .map(|config| CircuitBreakerLayer::new(config, self.retry_logic.clone())),
+            )
             .timeout(self.settings.timeout)
             .service(inner);
 
@@ -370,6 +380,29 @@ mod tests {
         assert_eq!(cfg.concurrency, None);
     }
 
+    #[test]
+    fn adaptive_concurrency_bounds_are_checked() {
+        toml::from_str::<TowerRequestConfig>(
+            "adaptive_concurrency.min_concurrency = 2\nadaptive_concurrency.max_concurrency = 2",
+        )
+        .expect("Equal bounds failed");
+
+        let error =
+            toml::from_str::<TowerRequestConfig>("adaptive_concurrency.min_concurrency = 0")
+                .expect_err("Zero minimum concurrency didn't fail");
+        assert!(error
+            .to_string()
+            .contains("`min_concurrency` must be greater than zero"));
+
+        let error = toml::from_str::<TowerRequestConfig>(
+            "adaptive_concurrency.min_concurrency = 4\nadaptive_concurrency.max_concurrency = 2",
+        )
+        .expect_err("Maximum below minimum concurrency didn't fail");
+        assert!(error
+            .to_string()
+            .contains("`max_concurrency` must be at least `min_concurrency`"));
+    }
+
     #[tokio::test]
     async fn partition_sink_retry_concurrency() {
         let cfg = TowerRequestConfig {
@@ -3,7 +3,9 @@ pub mod v2;
 
 use serde::{Deserialize, Serialize};
 
-use crate::config::{DataType, GenerateConfig, SinkConfig, SinkContext, SinkDescription};
+use crate::config::{
+    DataType, DryRunReport, GenerateConfig, SinkConfig, SinkContext, SinkDescription,
+};
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 enum V1 {
@@ -46,11 +48,12 @@ inventory::submit! {
 
 impl GenerateConfig for VectorConfig {
     fn generate_config() -> toml::Value {
-        toml::from_str(
-            r#"version = "2"
-            address = "127.0.0.1:6000"
-            "#,
-        )
+        let config =
+            toml::Value::try_into::<v2::VectorConfig>(v2::VectorConfig::generate_config()).unwrap();
+        toml::Value::try_from(VectorConfigV2 {
+            version: V2::V2,
+            config,
+        })
         .unwrap()
     }
 }
@@ -75,12 +78,49 @@ impl SinkConfig for VectorConfig {
     fn sink_type(&self) -> &'static str {
         "vector"
     }
+
+    fn spools_undelivered(&self) -> bool {
+        match self {
+            VectorConfig::V1(_) => false,
+            VectorConfig::V2(v2) => v2.config.spool.is_some(),
+        }
+    }
+
+    async fn dry_run(&self, cx: SinkContext, check_connectivity: bool) -> Option<DryRunReport> {
+        match self {
+            VectorConfig::V1(_) => None,
+            VectorConfig::V2(v2) => Some(v2.config.dry_run(cx, check_connectivity).await),
+        }
+    }
 }
 
 #[cfg(test)]
 mod test {
+    use super::*;
+
     #[test]
     fn generate_config() {
         crate::test_util::test_generate_config::<super::VectorConfig>();
     }
+
+    #[test]
+    fn generated_config_round_trips() {
+        let generated = VectorConfig::generate_config().to_string();
+        let config = match toml::from_str::<VectorConfig>(&generated).unwrap() {
+            VectorConfig::V2(v2) => v2.config,
+            VectorConfig::V1(_) => panic!("Generated config is not version 2."),
+        };
+        assert_eq!(
+            toml::Value::try_from(config).unwrap().to_string(),
+            v2::VectorConfig::generate_config().to_string()
+        );
+
+        let generated: toml::Value = generated.parse().unwrap();
+        assert_eq!(generated["address"].as_str(), Some("127.0.0.1:6000"));
+        assert_eq!(generated["tls"]["enabled"].as_bool(), Some(false));
+        assert!(generated["tls"]["crt_file"].is_str());
+        assert_eq!(generated["batch"]["max_events"].as_integer(), Some(1000));
+        assert_eq!(generated["batch"]["timeout_secs"].as_integer(), Some(1));
+        assert_eq!(generated["request"]["timeout_secs"].as_integer(), Some(60));
+    }
 }
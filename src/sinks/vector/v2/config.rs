@@ -2,21 +2,44 @@ use http::Uri;
 use hyper::client::HttpConnector;
 use hyper_openssl::HttpsConnector;
 use hyper_proxy::ProxyConnector;
+use std::{
+    collections::BTreeMap,
+    sync::{atomic::AtomicBool, Arc},
+    time::Duration,
+};
+
+use once_cell::sync::Lazy;
 use serde::{Deserialize, Serialize};
-use tonic::body::BoxBody;
-use tower::ServiceBuilder;
+use tower::{util::BoxService, ServiceBuilder};
+use uuid::Uuid;
 
 use crate::{
-    config::{GenerateConfig, ProxyConfig, SinkContext, SinkHealthcheckOptions},
-    proto::vector as proto,
+    common::drop_summary,
+    config::{
+        check_non_zero, ConfigError, ConfigErrors, DryRunReport, GenerateConfig, ProxyConfig,
+        SinkContext, SinkHealthcheckOptions,
+    },
+    dns::Resolver,
+    event::CompiledLookup,
+    proto::vector as proto_vector,
     sinks::{
         util::{
-            retries::RetryLogic, BatchConfig, RealtimeEventBasedDefaultBatchSettings,
-            ServiceBuilderExt, TowerRequestConfig,
+            encoding::TimestampFormat, retries::RetryLogic, sampler::SamplerConfig, BatchConfig,
+            Concurrency, RealtimeEventBasedDefaultBatchSettings, ServiceBuilderExt,
+            SinkBatchSettings, TowerRequestConfig,
         },
         vector::v2::{
-            service::{VectorResponse, VectorService},
+            delta::DeltaEncoder,
+            fields::{FieldFilter, FieldSelection},
+            gauges::SinkGauges,
+            health::{
+                check_endpoints, spawn_capability_probe, spawn_health_monitor, HealthGate,
+                HealthcheckChannelConfig, PeriodicHealthcheckConfig,
+            },
+            resolve::{resolvable_host, spawn_dns_refresh, ClientHandle},
+            service::{DeltaStream, HyperClient, VectorResponse, VectorService},
             sink::VectorSink,
+            spool::SpoolConfig,
             VectorSinkError,
         },
         Healthcheck, VectorSink as VectorSinkType,
@@ -34,20 +57,248 @@ pub struct VectorConfig {
     pub request: TowerRequestConfig,
     #[serde(default)]
     tls: Option<TlsConfig>,
+    /// The largest encoded size of a single event. Larger events are never
+    /// sent, since the downstream Vector would reject every request holding them.
+    #[serde(default)]
+    pub max_event_bytes: Option<usize>,
+    #[serde(default)]
+    pub oversized_event_action: OversizedEventAction,
+    #[serde(default)]
+    pub periodic_healthcheck: PeriodicHealthcheckConfig,
+    #[serde(default)]
+    pub healthcheck_channel: HealthcheckChannelConfig,
+    /// Record how long delivered events took to get here since they were ingested.
+    #[serde(default)]
+    pub event_age_histograms: bool,
+    #[serde(default)]
+    pub sender: SenderConfig,
+    #[serde(default)]
+    pub chunking: ChunkingConfig,
+    #[serde(default)]
+    pub delta: DeltaConfig,
+    #[serde(default)]
+    pub encoding: FieldsConfig,
+    /// How often to look up the addresses of the downstream Vector again,
+    /// reconnecting when they change. Disabled when unset.
+    #[serde(default)]
+    pub dns_refresh_interval_secs: Option<u64>,
+    /// Deliver events in the order they came in, by sending a request only
+    /// once the one before it was answered, retries included.
+    #[serde(default)]
+    pub ordered: bool,
+    /// How often the events dropped, such as oversized ones, are logged, as one line for each
+    /// reason.
+    #[serde(default = "drop_summary::default_interval_secs")]
+    pub drop_summary_interval_secs: u64,
+    /// Send only a sample of the log events, to shed load rather than run out of memory when the
+    /// downstream Vector can't keep up. Every event is sent when unset.
+    #[serde(default)]
+    pub sample: Option<SamplerConfig>,
+    /// Spool the events left undelivered when the sink stops to disk, and send them first when
+    /// it starts again. They are lost when unset.
+    #[serde(default)]
+    pub spool: Option<SpoolConfig>,
 }
 
-impl GenerateConfig for VectorConfig {
-    fn generate_config() -> toml::Value {
-        toml::Value::try_from(default_config("127.0.0.1:6000")).unwrap()
+/// Identifies the sending Vector process on every request, across config reloads.
+static SENDER_UUID: Lazy<Uuid> = Lazy::new(Uuid::new_v4);
+
+/// How this Vector identifies itself on the requests it sends, so that the
+/// downstream Vector can tell which agent sent what.
+#[derive(Clone, Debug, Derivative, Deserialize, Serialize)]
+#[derivative(Default)]
+#[serde(deny_unknown_fields)]
+pub struct SenderConfig {
+    /// Send the hostname, version and process id of this Vector along with
+    /// `labels`.
+    #[derivative(Default(value = "true"))]
+    #[serde(default = "crate::serde::default_true")]
+    pub enabled: bool,
+    /// Custom labels to identify this Vector by, such as its cluster or region.
+    #[serde(default)]
+    pub labels: BTreeMap<String, String>,
+}
+
+impl SenderConfig {
+    fn build(&self) -> Option<proto_vector::Sender> {
+        self.enabled.then(|| proto_vector::Sender {
+            hostname: crate::get_hostname().unwrap_or_default(),
+            version: crate::vector_version().to_string(),
+            uuid: SENDER_UUID.to_string(),
+            labels: self.labels.clone(),
+        })
     }
 }
 
-fn default_config(address: &str) -> VectorConfig {
-    VectorConfig {
-        address: address.to_owned(),
-        batch: BatchConfig::default(),
-        request: TowerRequestConfig::default(),
-        tls: None,
+/// Sending events too large for a single request split into chunks, each in a
+/// request of its own, for the downstream Vector to put back together. The
+/// downstream Vector must have chunking enabled as well.
+#[derive(Clone, Debug, Derivative, Deserialize, Serialize)]
+#[derivative(Default)]
+#[serde(deny_unknown_fields)]
+pub struct ChunkingConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// The most bytes of an encoded event sent in a single request. Larger
+    /// events are split into chunks of this size.
+    #[derivative(Default(value = "default_chunk_bytes()"))]
+    #[serde(default = "default_chunk_bytes")]
+    pub chunk_bytes: usize,
+}
+
+const fn default_chunk_bytes() -> usize {
+    512 * 1024
+}
+
+impl ChunkingConfig {
+    /// The size of the chunks events are split into, if they are.
+    const fn chunk_bytes(&self) -> Option<usize> {
+        if self.enabled {
+            Some(self.chunk_bytes)
+        } else {
+            None
+        }
+    }
+}
+
+/// Sending log events that share a key with the previous one as the fields
+/// that changed since then, for the downstream Vector to apply to the event it
+/// already has. Only used with a downstream Vector that accepts deltas.
+/// Experimental.
+#[derive(Clone, Debug, Derivative, Deserialize, Serialize)]
+#[derivative(Default)]
+#[serde(deny_unknown_fields)]
+pub struct DeltaConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// The field holding the key of each event. Events without it are sent
+    /// whole.
+    #[serde(default)]
+    pub key_field: Option<String>,
+    /// The most keys whose last event is remembered. The least recently seen
+    /// keys are forgotten to make room, and their next event sent whole.
+    #[derivative(Default(value = "default_delta_max_keys()"))]
+    #[serde(default = "default_delta_max_keys")]
+    pub max_keys: usize,
+}
+
+const fn default_delta_max_keys() -> usize {
+    10_000
+}
+
+impl DeltaConfig {
+    /// The encoder of the sink, which only sends deltas once `active` is set.
+    fn build(&self, active: &Arc<AtomicBool>) -> Option<DeltaEncoder> {
+        if !self.enabled {
+            return None;
+        }
+        let key_field = self.key_field.clone()?;
+        Some(DeltaEncoder::new(
+            key_field,
+            self.max_keys,
+            Arc::clone(active),
+        ))
+    }
+}
+
+/// Which fields of log events are sent, to save bandwidth without a remap
+/// transform in front of the sink. Metrics are sent whole.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct FieldsConfig {
+    /// Only send these fields, and the fields nested below them.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub only_fields: Option<Vec<String>>,
+    /// Send every field except these.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub except_fields: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timestamp_format: Option<TimestampFormat>,
+}
+
+impl FieldsConfig {
+    /// The filter of the sink, if it changes anything. Invalid paths are left
+    /// out, as `VectorConfig::validate` reports them.
+    fn build(&self) -> Option<FieldFilter> {
+        let compile = |paths: &[String]| {
+            paths
+                .iter()
+                .filter_map(|path| CompiledLookup::new(path).ok())
+                .collect()
+        };
+        let selection = match (&self.only_fields, &self.except_fields) {
+            (Some(paths), _) => Some(FieldSelection::Only(compile(paths))),
+            (None, Some(paths)) => Some(FieldSelection::Except(compile(paths))),
+            (None, None) => None,
+        };
+        (selection.is_some() || self.timestamp_format.is_some()).then(|| FieldFilter {
+            selection,
+            timestamp_format: self.timestamp_format,
+        })
+    }
+
+    fn validate(&self, errors: &mut Vec<ConfigError>) {
+        if self.only_fields.is_some() && self.except_fields.is_some() {
+            errors.push(ConfigError::new(
+                "encoding.except_fields",
+                "can't be set along with `encoding.only_fields`",
+            ));
+        }
+        let fields = [
+            ("encoding.only_fields", &self.only_fields),
+            ("encoding.except_fields", &self.except_fields),
+        ];
+        for (field, paths) in fields {
+            for path in paths.iter().flatten() {
+                if let Err(error) = CompiledLookup::new(path) {
+                    errors.push(ConfigError::new(field, error));
+                }
+            }
+        }
+    }
+}
+
+/// What to do with an event whose encoded size is over `max_event_bytes`.
+#[derive(Clone, Copy, Debug, Derivative, Deserialize, PartialEq, Serialize)]
+#[derivative(Default)]
+#[serde(rename_all = "snake_case")]
+pub enum OversizedEventAction {
+    /// Drop the event.
+    #[derivative(Default)]
+    Drop,
+    /// Shorten the event's largest bytes field so that the event fits, or
+    /// drop it if that isn't enough.
+    Truncate,
+}
+
+impl GenerateConfig for VectorConfig {
+    fn generate_config() -> toml::Value {
+        let mut batch = BatchConfig::default();
+        batch.max_bytes = RealtimeEventBasedDefaultBatchSettings::MAX_BYTES;
+        batch.max_events = RealtimeEventBasedDefaultBatchSettings::MAX_EVENTS;
+        batch.timeout_secs = Some(RealtimeEventBasedDefaultBatchSettings::TIMEOUT_SECS.get());
+
+        toml::Value::try_from(VectorConfig {
+            address: "127.0.0.1:6000".to_owned(),
+            batch,
+            request: TowerRequestConfig::default(),
+            tls: Some(TlsConfig::example()),
+            max_event_bytes: None,
+            oversized_event_action: OversizedEventAction::default(),
+            periodic_healthcheck: PeriodicHealthcheckConfig::default(),
+            healthcheck_channel: HealthcheckChannelConfig::default(),
+            event_age_histograms: false,
+            sender: SenderConfig::default(),
+            chunking: ChunkingConfig::default(),
+            delta: DeltaConfig::default(),
+            encoding: FieldsConfig::default(),
+            dns_refresh_interval_secs: None,
+            ordered: false,
+            drop_summary_interval_secs: drop_summary::default_interval_secs(),
+            sample: None,
+            spool: None,
+        })
+        .unwrap()
     }
 }
 
@@ -56,10 +307,89 @@ impl VectorConfig {
         &self,
         cx: SinkContext,
     ) -> crate::Result<(VectorSinkType, Healthcheck)> {
+        self.build_with_gauges(cx, SinkGauges::default()).await
+    }
+
+    /// Do what building the sink would that can fail, short of sending: check
+    /// the options, load the TLS files, and parse the endpoint and build the
+    /// client for it. With `check_connectivity` the endpoint is also health
+    /// checked, as the sink's health check would.
+    pub(crate) async fn dry_run(&self, cx: SinkContext, check_connectivity: bool) -> DryRunReport {
+        let mut report = DryRunReport::default();
+        if let Err(errors) = self.validate() {
+            report.errors = errors;
+            return report;
+        }
+        report.passed.push("options");
+
+        let tls = match report.check(
+            "tls",
+            "tls",
+            MaybeTlsSettings::from_config(&self.tls, false),
+        ) {
+            Some(tls) => tls,
+            None => return report,
+        };
+        let uri = match report.check(
+            "endpoint",
+            "address",
+            with_default_scheme(&self.address, tls.is_tls()),
+        ) {
+            Some(uri) => uri,
+            None => return report,
+        };
+        let client = match report.check("client", "tls", new_client(&tls, cx.proxy())) {
+            Some(client) => client,
+            None => return report,
+        };
+
+        if check_connectivity {
+            let uri = cx.healthcheck.uri.map_or(uri, |uri| uri.uri);
+            let service = VectorService::new(ClientHandle::from(client), uri);
+            report.check(
+                "connectivity",
+                "address",
+                check_endpoints(vec![service], self.startup_healthcheck_timeout()).await,
+            );
+        }
+        report
+    }
+
+    pub(super) async fn build_with_gauges(
+        &self,
+        cx: SinkContext,
+        gauges: SinkGauges,
+    ) -> crate::Result<(VectorSinkType, Healthcheck)> {
+        self.validate().map_err(ConfigErrors)?;
+
         let tls = MaybeTlsSettings::from_config(&self.tls, false)?;
         let uri = with_default_scheme(&self.address, tls.is_tls())?;
 
-        let client = new_client(&tls, cx.proxy())?;
+        let client = ClientHandle::from(new_client(&tls, cx.proxy())?);
+        // The health checks get a client, and so a connection, of their own so
+        // that they aren't queued behind the events of a busy sink.
+        let healthcheck_client = if self.healthcheck_channel.dedicated {
+            ClientHandle::from(new_client(&tls, cx.proxy())?)
+        } else {
+            client.clone()
+        };
+        if let Some(secs) = self.dns_refresh_interval_secs {
+            if let Some(host) = uri.host().and_then(resolvable_host) {
+                let proxy = cx.proxy().clone();
+                let handles = if self.healthcheck_channel.dedicated {
+                    vec![&client, &healthcheck_client]
+                } else {
+                    vec![&client]
+                };
+                spawn_dns_refresh(
+                    Resolver,
+                    host.to_owned(),
+                    Duration::from_secs(secs),
+                    &handles,
+                    move || new_client(&tls, &proxy),
+                );
+            }
+        }
 
         let healthcheck_uri = cx
             .healthcheck
@@ -67,20 +397,82 @@ impl VectorConfig {
             .clone()
             .map(|uri| uri.uri)
             .unwrap_or_else(|| uri.clone());
-        let healthcheck_client = VectorService::new(client.clone(), healthcheck_uri);
-        let healthcheck = healthcheck(healthcheck_client, cx.healthcheck.clone());
-        let service = VectorService::new(client, uri);
-        let request_settings = self.request.unwrap_with(&TowerRequestConfig::default());
+        let healthcheck_service = VectorService::new(healthcheck_client, healthcheck_uri);
+        let health = self.periodic_healthcheck.interval_secs.map(|secs| {
+            spawn_health_monitor(
+                healthcheck_service.clone(),
+                Duration::from_secs(secs),
+                self.healthcheck_channel.timeout(),
+            )
+            .0
+        });
+        let mut request_settings = self.request.unwrap_with(&TowerRequestConfig::default());
+        if self.ordered {
+            // The retries happen below the concurrency limit, so a request
+            // being retried holds the only slot until it is done with.
+            request_settings.concurrency = Some(1);
+        }
+        let healthcheck = healthcheck(
+            vec![healthcheck_service],
+            cx.healthcheck.clone(),
+            self.startup_healthcheck_timeout(),
+        );
+        let delta_active = Arc::new(AtomicBool::new(false));
+        let delta_encoder = self.delta.build(&delta_active);
+        let compressed_fields_accepted = Arc::new(AtomicBool::new(false));
+        let mut capabilities = vec![(
+            proto_vector::COMPRESSED_FIELDS_CAPABILITY,
+            Arc::downgrade(&compressed_fields_accepted),
+        )];
+        if delta_encoder.is_some() {
+            capabilities.push((
+                proto_vector::DELTA_CAPABILITY,
+                Arc::downgrade(&delta_active),
+            ));
+        }
+        spawn_capability_probe(
+            VectorService::new(client.clone(), uri.clone()),
+            capabilities,
+        );
+        let service = VectorService {
+            timeout: Some(request_settings.timeout),
+            sender: self.sender.build(),
+            gauges: gauges.clone(),
+            delta: delta_encoder
+                .is_some()
+                .then(|| DeltaStream::new(delta_active)),
+            ..VectorService::new(client, uri)
+        };
         let batch_settings = self.batch.into_batcher_settings()?;
         //
         let service = ServiceBuilder::new()
             .settings(request_settings, VectorGrpcRetryLogic)
             .service(service);
+        let service = match health {
+            Some(health) => BoxService::new(HealthGate::new(
+                service,
+                health,
+                self.periodic_healthcheck.pause_when_unhealthy,
+            )),
+            None => service,
+        };
 
         let sink = VectorSink {
             batch_settings,
             service,
             acker: cx.acker(),
+            max_event_bytes: self.max_event_bytes,
+            oversized_event_action: self.oversized_event_action,
+            event_age_histograms: self.event_age_histograms,
+            event_size_histograms: cx.globals.telemetry.event_size_histograms,
+            compressed_fields_accepted,
+            chunk_bytes: self.chunking.chunk_bytes(),
+            delta_encoder,
+            field_filter: self.encoding.build(),
+            sampler: self.sample.as_ref().map(SamplerConfig::build),
+            gauges,
+            drop_summary_interval: Duration::from_secs(self.drop_summary_interval_secs),
+            spool: self.spool.as_ref().map(SpoolConfig::build),
         };
 
         Ok((
@@ -90,26 +482,157 @@ impl VectorConfig {
     }
 }
 
-/// Check to see if the remote service accepts new events.
-async fn healthcheck(
-    mut service: VectorService,
-    options: SinkHealthcheckOptions,
-) -> crate::Result<()> {
-    if !options.enabled {
-        return Ok(());
+impl VectorConfig {
+    /// How long the startup health check waits for an answer. Sharing the
+    /// connection of the events, it is given as long as a request is.
+    fn startup_healthcheck_timeout(&self) -> Duration {
+        if self.healthcheck_channel.dedicated {
+            self.healthcheck_channel.timeout()
+        } else {
+            self.request
+                .unwrap_with(&TowerRequestConfig::default())
+                .timeout
+        }
     }
 
-    let request = service.client.health_check(proto::HealthCheckRequest {});
+    /// Check all the options at once, so that every problem is reported
+    /// together with the option it is about rather than building failing on
+    /// the first one.
+    pub(super) fn validate(&self) -> Result<(), Vec<ConfigError>> {
+        let mut errors = Vec::new();
+
+        match with_default_scheme(&self.address, false) {
+            Ok(uri) if uri.host().is_none() => {
+                errors.push(ConfigError::new("address", VectorSinkError::NoHost));
+            }
+            Ok(_) => (),
+            Err(error) => errors.push(ConfigError::new("address", error)),
+        }
+
+        errors.extend(TlsConfig::validate(&self.tls, false));
+
+        check_non_zero(
+            &mut errors,
+            [
+                ("batch.max_bytes", self.batch.max_bytes),
+                ("batch.max_events", self.batch.max_events),
+                ("max_event_bytes", self.max_event_bytes),
+                ("chunking.chunk_bytes", Some(self.chunking.chunk_bytes)),
+                ("delta.max_keys", Some(self.delta.max_keys)),
+                (
+                    "sample.max_bytes",
+                    self.sample.as_ref().and_then(|sample| sample.max_bytes),
+                ),
+            ],
+        );
+        check_non_zero(
+            &mut errors,
+            [
+                ("batch.timeout_secs", self.batch.timeout_secs),
+                ("request.timeout_secs", self.request.timeout_secs),
+                ("request.rate_limit_num", self.request.rate_limit_num),
+                (
+                    "request.rate_limit_duration_secs",
+                    self.request.rate_limit_duration_secs,
+                ),
+                (
+                    "periodic_healthcheck.interval_secs",
+                    self.periodic_healthcheck.interval_secs,
+                ),
+                (
+                    "healthcheck_channel.timeout_secs",
+                    Some(self.healthcheck_channel.timeout_secs),
+                ),
+                ("dns_refresh_interval_secs", self.dns_refresh_interval_secs),
+                (
+                    "drop_summary_interval_secs",
+                    Some(self.drop_summary_interval_secs),
+                ),
+                (
+                    "sample.rate",
+                    self.sample.as_ref().and_then(|sample| sample.rate),
+                ),
+                (
+                    "sample.interval_secs",
+                    self.sample.as_ref().map(|sample| sample.interval_secs),
+                ),
+            ],
+        );
 
-    if let Ok(response) = request.await {
-        let status = proto::ServingStatus::from_i32(response.into_inner().status);
+        if self.oversized_event_action == OversizedEventAction::Truncate
+            && self.max_event_bytes.is_none()
+        {
+            errors.push(ConfigError::new(
+                "oversized_event_action",
+                "`truncate` requires `max_event_bytes` to be set",
+            ));
+        }
+        if self.periodic_healthcheck.pause_when_unhealthy
+            && self.periodic_healthcheck.interval_secs.is_none()
+        {
+            errors.push(ConfigError::new(
+                "periodic_healthcheck.pause_when_unhealthy",
+                "requires `periodic_healthcheck.interval_secs` to be set",
+            ));
+        }
 
-        if let Some(proto::ServingStatus::Serving) = status {
-            return Ok(());
+        if self.ordered
+            && !matches!(
+                self.request.concurrency,
+                Concurrency::None | Concurrency::Fixed(1)
+            )
+        {
+            errors.push(ConfigError::new(
+                "request.concurrency",
+                "can't be more than 1 when `ordered` is set",
+            ));
         }
+
+        self.encoding.validate(&mut errors);
+
+        if self.delta.enabled && self.delta.key_field.is_none() {
+            errors.push(ConfigError::new(
+                "delta.key_field",
+                "required when `delta.enabled` is set",
+            ));
+        }
+
+        if let Some(sample) = &self.sample {
+            if sample.rate.is_none() && sample.max_bytes.is_none() {
+                errors.push(ConfigError::new(
+                    "sample",
+                    "requires `sample.rate` or `sample.max_bytes` to be set",
+                ));
+            }
+        }
+
+        if let Some(spool) = &self.spool {
+            if spool.directory.as_os_str().is_empty() {
+                errors.push(ConfigError::new("spool.directory", "must not be empty"));
+            }
+            check_non_zero(&mut errors, [("spool.max_bytes", Some(spool.max_bytes))]);
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+/// Check to see if every remote service accepts new events, waiting at most
+/// `timeout` for each.
+async fn healthcheck(
+    services: Vec<VectorService>,
+    options: SinkHealthcheckOptions,
+    timeout: Duration,
+) -> crate::Result<()> {
+    if !options.enabled {
+        return Ok(());
     }
 
-    Err(Box::new(VectorSinkError::Health))
+    check_endpoints(services, timeout).await.map_err(Into::into)
 }
 
 /// grpc doesn't like an address without a scheme, so we default to http or https if one isn't
@@ -146,10 +669,10 @@ pub fn with_default_scheme(address: &str, tls: bool) -> crate::Result<Uri> {
     }
 }
 
-fn new_client(
+pub(super) fn new_client(
     tls_settings: &MaybeTlsSettings,
     proxy_config: &ProxyConfig,
-) -> crate::Result<hyper::Client<ProxyConnector<HttpsConnector<HttpConnector>>, BoxBody>> {
+) -> crate::Result<HyperClient> {
     let mut http = HttpConnector::new();
     http.enforce_http(false);
 
@@ -171,6 +694,13 @@ fn new_client(
     Ok(hyper::Client::builder().http2_only(true).build(proxy))
 }
 
+/// Retries requests that may go through on another attempt.
+///
+/// That includes `DeadlineExceeded`. The downstream Vector only returns it when
+/// the request timeout elapsed before it forwarded anything, and answers with
+/// success once it has. The client also returns it when no answer came in
+/// time, in which case the events may already be on their way downstream and
+/// retrying delivers them twice, the same as after a lost connection.
 #[derive(Debug, Clone)]
 struct VectorGrpcRetryLogic;
 
@@ -198,4 +728,363 @@ impl RetryLogic for VectorGrpcRetryLogic {
             _ => true,
         }
     }
+
+    /// Only a downstream Vector saying it is overloaded, or not answering in
+    /// time, lowers the concurrency. Other retriable errors, such as a lost
+    /// connection, say nothing about its load.
+    fn is_back_pressure(&self, err: &Self::Error) -> bool {
+        use tonic::Code::*;
+
+        match err {
+            VectorSinkError::Request { source } => {
+                matches!(
+                    source.code(),
+                    ResourceExhausted | DeadlineExceeded | Unavailable
+                )
+            }
+            _ => false,
+        }
+    }
+
+    /// A downstream Vector shutting down refuses requests with the delay to
+    /// wait before retrying in their metadata. Since there is no other
+    /// endpoint to send them to, they are retried once it has passed.
+    fn retry_after(&self, err: &Self::Error) -> Option<Duration> {
+        match err {
+            VectorSinkError::Request { source } => source
+                .metadata()
+                .get(proto_vector::RETRY_PUSHBACK_KEY)
+                .and_then(|value| value.to_str().ok()?.parse().ok())
+                .map(Duration::from_millis),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        config::ConfigBuilder,
+        tls::{TEST_PEM_CRT_PATH, TEST_PEM_KEY_PATH},
+    };
+
+    fn invalid_fields(config: &str) -> Vec<String> {
+        let config: VectorConfig = toml::from_str(config).unwrap();
+        match config.validate() {
+            Ok(()) => vec![],
+            Err(errors) => errors.into_iter().map(|error| error.field).collect(),
+        }
+    }
+
+    #[test]
+    fn validate_accepts_valid_config() {
+        let config = format!(
+            r#"
+            address = "127.0.0.1:6000"
+            batch.max_events = 100
+            max_event_bytes = 1024
+            oversized_event_action = "truncate"
+            chunking.enabled = true
+            delta.enabled = true
+            delta.key_field = "resource"
+            periodic_healthcheck.interval_secs = 10
+            periodic_healthcheck.pause_when_unhealthy = true
+            ordered = true
+            request.concurrency = 1
+            tls.enabled = true
+            tls.crt_file = "{}"
+            tls.key_file = "{}"
+            "#,
+            TEST_PEM_CRT_PATH, TEST_PEM_KEY_PATH
+        );
+        assert_eq!(invalid_fields(&config), Vec::<String>::new());
+    }
+
+    #[test]
+    fn validate_reports_every_invalid_field() {
+        let config = format!(
+            r#"
+            address = "http://bad host:6000/"
+            batch.max_events = 0
+            batch.timeout_secs = 0
+            request.timeout_secs = 0
+            healthcheck_channel.timeout_secs = 0
+            dns_refresh_interval_secs = 0
+            drop_summary_interval_secs = 0
+            oversized_event_action = "truncate"
+            periodic_healthcheck.pause_when_unhealthy = true
+            delta.enabled = true
+            delta.max_keys = 0
+            ordered = true
+            request.concurrency = "adaptive"
+            tls.enabled = true
+            tls.ca_file = "/nonexistent/ca.crt"
+            tls.crt_file = "{}"
+            "#,
+            TEST_PEM_CRT_PATH
+        );
+        assert_eq!(
+            invalid_fields(&config),
+            vec![
+                "address",
+                "tls.ca_file",
+                "tls.key_file",
+                "batch.max_events",
+                "delta.max_keys",
+                "batch.timeout_secs",
+                "request.timeout_secs",
+                "healthcheck_channel.timeout_secs",
+                "dns_refresh_interval_secs",
+                "drop_summary_interval_secs",
+                "oversized_event_action",
+                "periodic_healthcheck.pause_when_unhealthy",
+                "request.concurrency",
+                "delta.key_field",
+            ]
+        );
+    }
+
+    #[test]
+    fn validate_sample() {
+        let config = r#"
+            address = "127.0.0.1:6000"
+            sample.rate = 10
+            sample.max_bytes = 1048576
+            sample.key_field = "request_id"
+            sample.seed = 7
+            "#;
+        assert_eq!(invalid_fields(config), Vec::<String>::new());
+
+        let config = r#"
+            address = "127.0.0.1:6000"
+            sample.max_bytes = 0
+            sample.rate = 0
+            sample.interval_secs = 0
+            "#;
+        assert_eq!(
+            invalid_fields(config),
+            vec!["sample.max_bytes", "sample.rate", "sample.interval_secs"]
+        );
+
+        let config = r#"
+            address = "127.0.0.1:6000"
+            sample.key_field = "request_id"
+            "#;
+        assert_eq!(invalid_fields(config), vec!["sample"]);
+    }
+
+    #[test]
+    fn validate_spool() {
+        let config = r#"
+            address = "127.0.0.1:6000"
+            spool.directory = "/var/lib/vector/spool"
+            "#;
+        assert_eq!(invalid_fields(config), Vec::<String>::new());
+
+        let config = r#"
+            address = "127.0.0.1:6000"
+            spool.directory = ""
+            spool.max_bytes = 0
+            "#;
+        assert_eq!(
+            invalid_fields(config),
+            vec!["spool.directory", "spool.max_bytes"]
+        );
+    }
+
+    #[test]
+    fn spool_rejects_disk_buffer() {
+        // The other errors, such as for the missing input, don't matter here.
+        let errors = |buffer: &str| -> Vec<String> {
+            let config = format!(
+                r#"
+                [sinks.out]
+                type = "vector"
+                version = "2"
+                inputs = ["in"]
+                address = "127.0.0.1:6000"
+                spool.directory = "/var/lib/vector/spool"
+                {}
+                "#,
+                buffer
+            );
+            let config: ConfigBuilder = toml::from_str(&config).unwrap();
+            config.build().err().unwrap_or_default()
+        };
+        let error =
+            "Sink \"out\": Spooling undelivered events can't be combined with a disk buffer."
+                .to_owned();
+
+        assert!(!errors(r#"buffer.type = "memory""#).contains(&error));
+        assert!(errors(r#"buffer = { type = "disk", max_size = 1048576 }"#).contains(&error));
+    }
+
+    #[test]
+    fn validate_field_selection() {
+        let config = r#"
+            address = "127.0.0.1:6000"
+            encoding.only_fields = ["message", "http.request"]
+            encoding.except_fields = ["http.request.headers", "a.b..c"]
+            "#;
+        assert_eq!(
+            invalid_fields(config),
+            vec!["encoding.except_fields", "encoding.except_fields"]
+        );
+
+        let config = r#"
+            address = "127.0.0.1:6000"
+            encoding.only_fields = ["message", "http.request"]
+            encoding.timestamp_format = "unix"
+            "#;
+        assert_eq!(invalid_fields(config), Vec::<String>::new());
+    }
+
+    #[test]
+    fn validate_tls_files() {
+        let config = r#"
+            address = "127.0.0.1:6000"
+            tls.enabled = true
+            tls.key_file = "/nonexistent/key.pem"
+        "#;
+        assert_eq!(invalid_fields(config), vec!["tls.key_file", "tls.crt_file"]);
+
+        // Options of disabled TLS aren't used, so they aren't checked either.
+        let config = r#"
+            address = "127.0.0.1:6000"
+            tls.enabled = false
+            tls.key_file = "/nonexistent/key.pem"
+        "#;
+        assert_eq!(invalid_fields(config), Vec::<String>::new());
+    }
+
+    #[tokio::test]
+    async fn build_reports_all_errors() {
+        let config: VectorConfig = toml::from_str(
+            r#"
+            address = "127.0.0.1:6000"
+            batch.max_bytes = 0
+            max_event_bytes = 0
+            chunking.chunk_bytes = 0
+            "#,
+        )
+        .unwrap();
+        let error = config.build(SinkContext::new_test()).await.err().unwrap();
+        assert_eq!(
+            error.to_string(),
+            "Invalid configuration: batch.max_bytes: must be greater than zero; \
+             max_event_bytes: must be greater than zero; \
+             chunking.chunk_bytes: must be greater than zero"
+        );
+    }
+
+    fn report_fields(report: &DryRunReport) -> Vec<&str> {
+        report
+            .errors
+            .iter()
+            .map(|error| error.field.as_str())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn dry_run_checks_connectivity_only_when_asked() {
+        // Nothing listens on the address.
+        let config: VectorConfig =
+            toml::from_str(&format!(r#"address = "{}""#, crate::test_util::next_addr())).unwrap();
+
+        let report = config.dry_run(SinkContext::new_test(), false).await;
+        assert_eq!(report_fields(&report), Vec::<&str>::new());
+        assert_eq!(report.passed, vec!["options", "tls", "endpoint", "client"]);
+
+        let report = config.dry_run(SinkContext::new_test(), true).await;
+        assert_eq!(report_fields(&report), vec!["address"]);
+        assert_eq!(report.passed, vec!["options", "tls", "endpoint", "client"]);
+    }
+
+    #[tokio::test]
+    async fn dry_run_reports_a_malformed_address() {
+        let config: VectorConfig = toml::from_str(r#"address = "http://bad host:6000/""#).unwrap();
+        let report = config.dry_run(SinkContext::new_test(), false).await;
+        assert_eq!(report_fields(&report), vec!["address"]);
+        assert!(report.passed.is_empty());
+    }
+
+    #[tokio::test]
+    async fn dry_run_reports_a_bad_pem() {
+        let ca_file = crate::test_util::temp_file();
+        std::fs::write(
+            &ca_file,
+            "-----BEGIN CERTIFICATE-----\nnot a certificate\n-----END CERTIFICATE-----\n",
+        )
+        .unwrap();
+        let config: VectorConfig = toml::from_str(&format!(
+            r#"
+            address = "127.0.0.1:6000"
+            tls.enabled = true
+            tls.ca_file = "{}"
+            "#,
+            ca_file.display()
+        ))
+        .unwrap();
+        let report = config.dry_run(SinkContext::new_test(), true).await;
+        assert_eq!(report_fields(&report), vec!["tls.ca_file"]);
+        assert!(report.passed.is_empty());
+    }
+
+    #[test]
+    fn startup_healthcheck_timeout_follows_the_channel() {
+        let timeout = |options: &str| {
+            let config = format!("address = \"127.0.0.1:6000\"\n{}", options);
+            toml::from_str::<VectorConfig>(&config)
+                .unwrap()
+                .startup_healthcheck_timeout()
+        };
+        assert_eq!(
+            timeout("request.timeout_secs = 30"),
+            Duration::from_secs(10)
+        );
+        assert_eq!(
+            timeout("healthcheck_channel.dedicated = false\nrequest.timeout_secs = 30"),
+            Duration::from_secs(30)
+        );
+    }
+
+    #[test]
+    fn retries_wait_for_pushback() {
+        let mut metadata = tonic::metadata::MetadataMap::new();
+        metadata.insert(proto_vector::RETRY_PUSHBACK_KEY, "5000".parse().unwrap());
+        let shutting_down = VectorSinkError::Request {
+            source: tonic::Status::with_metadata(
+                tonic::Code::Unavailable,
+                proto_vector::SHUTTING_DOWN_MESSAGE,
+                metadata,
+            ),
+        };
+        assert!(VectorGrpcRetryLogic.is_retriable_error(&shutting_down));
+        assert_eq!(
+            VectorGrpcRetryLogic.retry_after(&shutting_down),
+            Some(Duration::from_secs(5))
+        );
+
+        let unavailable = VectorSinkError::Request {
+            source: tonic::Status::unavailable("busy"),
+        };
+        assert_eq!(VectorGrpcRetryLogic.retry_after(&unavailable), None);
+    }
+
+    #[test]
+    fn only_overload_is_back_pressure() {
+        let error = |status| VectorSinkError::Request { source: status };
+        for status in [
+            tonic::Status::resource_exhausted("full"),
+            tonic::Status::deadline_exceeded("slow"),
+            tonic::Status::unavailable("busy"),
+        ] {
+            assert!(VectorGrpcRetryLogic.is_back_pressure(&error(status)));
+        }
+        let reset = error(tonic::Status::unknown("connection reset"));
+        assert!(VectorGrpcRetryLogic.is_retriable_error(&reset));
+        assert!(!VectorGrpcRetryLogic.is_back_pressure(&reset));
+        assert!(!VectorGrpcRetryLogic.is_back_pressure(&VectorSinkError::Health));
+    }
 }
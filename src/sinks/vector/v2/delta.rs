@@ -0,0 +1,249 @@
+use std::{
+    collections::BTreeMap,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+use lru::LruCache;
+use prost::Message;
+use vector_core::event::{proto as proto_event, Event};
+
+use crate::proto::vector as proto_vector;
+
+/// A log event sent under its key, as a delta when the downstream Vector was
+/// sent the previous event of the key.
+#[derive(Clone, Debug)]
+pub struct KeyedPayload {
+    pub key: String,
+    pub sequence: u64,
+    /// Unset when the event is sent whole.
+    pub delta: Option<proto_vector::EventDelta>,
+    /// The event whole, for when the downstream Vector can't apply the delta.
+    pub whole: proto_event::EventWrapper,
+}
+
+impl KeyedPayload {
+    /// The keyed event to send, as a delta unless `whole` is set or there is
+    /// none.
+    pub fn to_proto(&self, whole: bool) -> proto_vector::KeyedEvent {
+        let payload = match &self.delta {
+            Some(delta) if !whole => proto_vector::keyed_event::Payload::Delta(delta.clone()),
+            _ => proto_vector::keyed_event::Payload::Full(self.whole.clone()),
+        };
+        proto_vector::KeyedEvent {
+            key: self.key.clone(),
+            sequence: self.sequence,
+            payload: Some(payload),
+        }
+    }
+
+    /// About the size of the keyed event as it is sent, for batching.
+    pub fn encoded_len(&self) -> usize {
+        let payload = match &self.delta {
+            Some(delta) => delta.encoded_len(),
+            None => self.whole.encoded_len(),
+        };
+        self.key.len() + payload + 16
+    }
+}
+
+/// The last event sent of a key.
+#[derive(Debug)]
+struct Baseline {
+    sequence: u64,
+    fields: BTreeMap<String, proto_event::Value>,
+}
+
+/// Turns the log events that share a key with the previous one into the
+/// changes from it, remembering the last event of up to `max_keys` keys.
+pub struct DeltaEncoder {
+    key_field: String,
+    baselines: LruCache<String, Baseline>,
+    next_sequence: u64,
+    /// Whether the downstream Vector accepts deltas, which is unknown until
+    /// it has answered a health check.
+    active: Arc<AtomicBool>,
+}
+
+impl DeltaEncoder {
+    pub fn new(key_field: String, max_keys: usize, active: Arc<AtomicBool>) -> Self {
+        Self {
+            key_field,
+            baselines: LruCache::new(max_keys),
+            // `0` is left for the downstream Vector to tell there is no baseline.
+            next_sequence: 1,
+            active,
+        }
+    }
+
+    /// The key of `event`, if it is a log event with the key field.
+    pub fn key_of(&self, event: &Event) -> Option<String> {
+        match event {
+            Event::Log(log) => log.get(&self.key_field).map(|key| key.to_string_lossy()),
+            Event::Metric(_) => None,
+        }
+    }
+
+    /// Send `wrapper` under `key`, as a delta if the previous event of the key
+    /// is known. It is given back to be sent as usual if deltas aren't
+    /// accepted downstream, or it has fields this Vector doesn't know, which
+    /// a delta would lose.
+    pub fn encode(
+        &mut self,
+        key: String,
+        wrapper: proto_event::EventWrapper,
+    ) -> Result<KeyedPayload, proto_event::EventWrapper> {
+        if !self.active.load(Ordering::Relaxed) {
+            self.baselines.clear();
+            return Err(wrapper);
+        }
+        let fields = match &wrapper.event {
+            Some(proto_event::Event::Log(log))
+                if log.unknown_fields.is_empty() && wrapper.unknown_fields.is_empty() =>
+            {
+                log.fields.clone()
+            }
+            _ => return Err(wrapper),
+        };
+
+        let sequence = self.next_sequence;
+        self.next_sequence += 1;
+        let delta = self.baselines.get(&key).map(|baseline| {
+            let changed = fields
+                .iter()
+                .filter(|(field, value)| baseline.fields.get(*field) != Some(value))
+                .map(|(field, value)| (field.clone(), value.clone()))
+                .collect();
+            let removed = baseline
+                .fields
+                .keys()
+                .filter(|field| !fields.contains_key(*field))
+                .cloned()
+                .collect();
+            proto_vector::EventDelta {
+                baseline: baseline.sequence,
+                changed,
+                removed,
+                event_id: wrapper.event_id.clone(),
+            }
+        });
+        self.baselines
+            .put(key.clone(), Baseline { sequence, fields });
+
+        Ok(KeyedPayload {
+            key,
+            sequence,
+            delta,
+            whole: wrapper,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use vector_core::event::LogEvent;
+
+    use super::*;
+
+    fn encoder(max_keys: usize) -> DeltaEncoder {
+        DeltaEncoder::new(
+            "resource".to_owned(),
+            max_keys,
+            Arc::new(AtomicBool::new(true)),
+        )
+    }
+
+    fn wrapper(fields: &[(&str, &str)]) -> proto_event::EventWrapper {
+        let mut log = LogEvent::default();
+        for (field, value) in fields {
+            log.insert(*field, *value);
+        }
+        Event::from(log).into()
+    }
+
+    fn value(value: &str) -> proto_event::Value {
+        match proto_event::EventWrapper::from(Event::from(value)).event {
+            Some(proto_event::Event::Log(mut log)) => log.fields.remove("message").unwrap(),
+            _ => unreachable!("log events are encoded as logs"),
+        }
+    }
+
+    #[test]
+    fn sends_the_changes_from_the_previous_event_of_the_key() {
+        let mut encoder = encoder(10);
+        let first = encoder
+            .encode(
+                "a".to_owned(),
+                wrapper(&[("resource", "a"), ("status", "up"), ("zone", "1")]),
+            )
+            .unwrap();
+        assert!(first.delta.is_none());
+        assert_eq!(first.sequence, 1);
+
+        let other = encoder
+            .encode("b".to_owned(), wrapper(&[("resource", "b")]))
+            .unwrap();
+        assert!(other.delta.is_none());
+
+        let second = encoder
+            .encode(
+                "a".to_owned(),
+                wrapper(&[("resource", "a"), ("status", "down"), ("region", "eu")]),
+            )
+            .unwrap();
+        let delta = second.delta.unwrap();
+        assert_eq!(delta.baseline, 1);
+        assert_eq!(
+            delta.changed,
+            BTreeMap::from([
+                ("region".to_owned(), value("eu")),
+                ("status".to_owned(), value("down")),
+            ])
+        );
+        assert_eq!(delta.removed, vec!["zone".to_owned()]);
+        assert_eq!(second.sequence, 3);
+    }
+
+    #[test]
+    fn evicted_keys_are_sent_whole() {
+        let mut encoder = encoder(1);
+        for key in ["a", "b", "a"] {
+            let keyed = encoder
+                .encode(key.to_owned(), wrapper(&[("resource", key)]))
+                .unwrap();
+            assert!(keyed.delta.is_none());
+        }
+        assert!(encoder
+            .encode("a".to_owned(), wrapper(&[("resource", "a")]))
+            .unwrap()
+            .delta
+            .is_some());
+    }
+
+    #[test]
+    fn events_are_given_back_unless_deltas_are_accepted() {
+        let mut encoder = encoder(10);
+        encoder
+            .encode("a".to_owned(), wrapper(&[("resource", "a")]))
+            .unwrap();
+
+        encoder.active.store(false, Ordering::Relaxed);
+        assert!(encoder
+            .encode("a".to_owned(), wrapper(&[("resource", "a")]))
+            .is_err());
+
+        // Deltas are never based on events from before they were turned off.
+        encoder.active.store(true, Ordering::Relaxed);
+        assert!(encoder
+            .encode("a".to_owned(), wrapper(&[("resource", "a")]))
+            .unwrap()
+            .delta
+            .is_none());
+
+        let mut unknown = wrapper(&[("resource", "a")]);
+        unknown.unknown_fields = vec![0x88, 0x01, 0x01];
+        assert!(encoder.encode("a".to_owned(), unknown).is_err());
+    }
+}
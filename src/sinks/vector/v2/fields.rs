@@ -0,0 +1,139 @@
+use vector_core::event::{CompiledLookup, Event, ImmutableEvent};
+
+use crate::sinks::util::encoding::TimestampFormat;
+
+/// Which fields of log events are sent.
+#[derive(Debug)]
+pub enum FieldSelection {
+    /// Only these fields, and the fields nested below them.
+    Only(Vec<CompiledLookup>),
+    /// Every field except these.
+    Except(Vec<CompiledLookup>),
+}
+
+/// Prunes the fields of log events before they are sent, and formats their
+/// timestamps. Metrics are sent as they are.
+#[derive(Debug)]
+pub struct FieldFilter {
+    pub selection: Option<FieldSelection>,
+    pub timestamp_format: Option<TimestampFormat>,
+}
+
+impl FieldFilter {
+    /// The log event to send in place of `event`, or `None` if it is sent as
+    /// it is. The event itself is left untouched, since it may be shared with
+    /// other sinks.
+    ///
+    /// Only the kept fields are copied with `only_fields`, while the event is
+    /// copied whole with `except_fields`. Either way, its fields are walked a
+    /// single time for all of the paths.
+    pub fn apply(&self, event: &ImmutableEvent) -> Option<Event> {
+        let log = match event.as_event() {
+            Event::Log(log) => log,
+            Event::Metric(_) => return None,
+        };
+        let mut log = match &self.selection {
+            Some(FieldSelection::Only(paths)) => log.clone_compiled_subset(paths),
+            Some(FieldSelection::Except(paths)) => log.clone().extract_owned(paths).1,
+            None if self.timestamp_format == Some(TimestampFormat::Unix) => log.clone(),
+            None => return None,
+        };
+        if let Some(timestamp_format) = self.timestamp_format {
+            timestamp_format.apply(&mut log);
+        }
+        Some(Event::Log(log))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+    use serde_json::json;
+    use vector_core::event::{LogEvent, Value};
+
+    use super::*;
+
+    fn paths(paths: &[&str]) -> Vec<CompiledLookup> {
+        paths
+            .iter()
+            .map(|path| CompiledLookup::new(path).unwrap())
+            .collect()
+    }
+
+    fn event() -> ImmutableEvent {
+        let mut log = LogEvent::from("hello");
+        log.insert("host", "edge-1");
+        log.insert("http.request.method", "GET");
+        log.insert("http.request.headers.authorization", "secret");
+        log.insert("http.response.status", 200);
+        log.insert("at", Utc.ymd(2022, 1, 2).and_hms(3, 4, 5));
+        Event::from(log).into()
+    }
+
+    fn to_json(event: Event) -> serde_json::Value {
+        serde_json::to_value(event.as_log().as_map()).unwrap()
+    }
+
+    #[test]
+    fn only_fields_keeps_nested_paths() {
+        let filter = FieldFilter {
+            selection: Some(FieldSelection::Only(paths(&[
+                "message",
+                "http.request.method",
+                "http.response",
+                "missing.field",
+            ]))),
+            timestamp_format: None,
+        };
+        let event = event();
+        let pruned = filter.apply(&event).unwrap();
+        assert_eq!(
+            to_json(pruned),
+            json!({
+                "message": "hello",
+                "http": {
+                    "request": { "method": "GET" },
+                    "response": { "status": 200 },
+                },
+            })
+        );
+        assert_eq!(event.as_map().len(), 4);
+        assert!(event.contains("http.request.headers.authorization"));
+        assert!(event.is_unique());
+    }
+
+    #[test]
+    fn except_fields_drops_nested_paths() {
+        let filter = FieldFilter {
+            selection: Some(FieldSelection::Except(paths(&[
+                "host",
+                "http.request.headers",
+            ]))),
+            timestamp_format: Some(TimestampFormat::Unix),
+        };
+        let event = event();
+        let pruned = filter.apply(&event).unwrap();
+        assert_eq!(
+            to_json(pruned),
+            json!({
+                "message": "hello",
+                "at": 1_641_092_645,
+                "http": {
+                    "request": { "method": "GET" },
+                    "response": { "status": 200 },
+                },
+            })
+        );
+        assert_eq!(event.get("host"), Some(&Value::from("edge-1")));
+        assert!(matches!(event.get("at"), Some(Value::Timestamp(_))));
+    }
+
+    #[test]
+    fn events_without_changes_are_sent_as_they_are() {
+        let filter = FieldFilter {
+            selection: None,
+            timestamp_format: Some(TimestampFormat::Rfc3339),
+        };
+        assert!(filter.apply(&event()).is_none());
+    }
+}
@@ -0,0 +1,338 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc,
+    },
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use futures::{future::BoxFuture, FutureExt};
+use tokio::{
+    sync::Notify,
+    time::{self, Instant},
+};
+use tower::Service;
+use tracing_futures::Instrument;
+
+use crate::{
+    internal_events::{VectorSinkEventsDroppedAtShutdown, VectorSinkGaugesUpdated},
+    sinks::vector::v2::service::VectorRequest,
+};
+
+/// How often the gauges are published while the sink runs.
+const PUBLISH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Where the events of one sink are between being accepted and delivered.
+/// Events and requests moving along only update counters, which are
+/// published as gauges every [`PUBLISH_INTERVAL`] and as the sink stops.
+///
+/// The gauges are emitted from within the sink, so they are tagged with its
+/// component like any other metric of the sink.
+#[derive(Clone, Debug, Default)]
+pub struct SinkGauges {
+    counts: Arc<Counts>,
+}
+
+#[derive(Debug, Default)]
+struct Counts {
+    /// Events waiting in a batch to be sent.
+    buffered_events: AtomicUsize,
+    in_flight_requests: AtomicUsize,
+    /// Events of the in-flight requests, including those awaiting retry.
+    in_flight_events: AtomicUsize,
+    /// Events of in-flight requests whose last attempt failed.
+    retrying_events: AtomicUsize,
+    /// Events that were buffered or in flight when the sink stopped, but spooled rather than
+    /// dropped.
+    spooled_events: AtomicUsize,
+    /// Notified once the sink stopped, to stop publishing.
+    stopped: Notify,
+}
+
+impl SinkGauges {
+    pub fn buffered_events(&self) -> usize {
+        self.counts.buffered_events.load(Ordering::Relaxed)
+    }
+
+    pub fn in_flight_requests(&self) -> usize {
+        self.counts.in_flight_requests.load(Ordering::Relaxed)
+    }
+
+    pub fn retrying_events(&self) -> usize {
+        self.counts.retrying_events.load(Ordering::Relaxed)
+    }
+
+    /// Publish the gauges every [`PUBLISH_INTERVAL`] until the sink stops,
+    /// within the current span so that they are tagged with the component.
+    pub fn spawn_publisher(&self) {
+        let gauges = self.clone();
+        tokio::spawn(
+            async move {
+                let mut ticks =
+                    time::interval_at(Instant::now() + PUBLISH_INTERVAL, PUBLISH_INTERVAL);
+                loop {
+                    tokio::select! {
+                        _ = ticks.tick() => gauges.emit(),
+                        _ = gauges.counts.stopped.notified() => break,
+                    }
+                }
+            }
+            .in_current_span(),
+        );
+    }
+
+    /// An event went into a batch.
+    pub fn event_buffered(&self) {
+        self.counts.buffered_events.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// A request holding `events` buffered events was sent.
+    fn request_started(&self, events: usize) {
+        self.counts
+            .buffered_events
+            .fetch_sub(events, Ordering::Relaxed);
+        self.counts
+            .in_flight_requests
+            .fetch_add(1, Ordering::Relaxed);
+        self.counts
+            .in_flight_events
+            .fetch_add(events, Ordering::Relaxed);
+    }
+
+    /// A request holding `events` events was delivered, or failed for good.
+    fn request_finished(&self, events: usize, awaiting_retry: &AwaitingRetry) {
+        if awaiting_retry.0.swap(false, Ordering::Relaxed) {
+            self.counts
+                .retrying_events
+                .fetch_sub(events, Ordering::Relaxed);
+        }
+        self.counts
+            .in_flight_requests
+            .fetch_sub(1, Ordering::Relaxed);
+        self.counts
+            .in_flight_events
+            .fetch_sub(events, Ordering::Relaxed);
+    }
+
+    /// Start one attempt at sending a request holding `events` events. The
+    /// events await retry from when the attempt is dropped without having
+    /// succeeded, whether it failed or timed out, until the next attempt.
+    pub fn start_attempt(&self, events: usize, awaiting_retry: &AwaitingRetry) -> Attempt {
+        if awaiting_retry.0.swap(false, Ordering::Relaxed) {
+            self.counts
+                .retrying_events
+                .fetch_sub(events, Ordering::Relaxed);
+        }
+        Attempt {
+            gauges: self.clone(),
+            awaiting_retry: awaiting_retry.clone(),
+            events,
+            succeeded: false,
+        }
+    }
+
+    /// `count` of the events buffered or in flight were spooled as the sink stopped.
+    pub fn events_spooled(&self, count: usize) {
+        self.counts
+            .spooled_events
+            .fetch_add(count, Ordering::Relaxed);
+    }
+
+    /// Count the events that were buffered or in flight when the sink stopped
+    /// without delivering or spooling them, reset the gauges, and publish them
+    /// a last time.
+    fn shut_down(&self) {
+        let count = (self.counts.buffered_events.swap(0, Ordering::Relaxed)
+            + self.counts.in_flight_events.swap(0, Ordering::Relaxed))
+        .saturating_sub(self.counts.spooled_events.swap(0, Ordering::Relaxed));
+        self.counts.in_flight_requests.store(0, Ordering::Relaxed);
+        self.counts.retrying_events.store(0, Ordering::Relaxed);
+        if count > 0 {
+            emit!(&VectorSinkEventsDroppedAtShutdown { count });
+        }
+        self.emit();
+        self.counts.stopped.notify_one();
+    }
+
+    fn emit(&self) {
+        emit!(&VectorSinkGaugesUpdated {
+            buffered_events: self.buffered_events(),
+            in_flight_requests: self.in_flight_requests(),
+            retrying_events: self.retrying_events(),
+        });
+    }
+}
+
+/// Whether the events of a request await retry. Shared by the copies of the
+/// request the retry layer makes.
+#[derive(Clone, Debug, Default)]
+pub struct AwaitingRetry(Arc<AtomicBool>);
+
+/// One attempt at sending a request, from [`SinkGauges::start_attempt`].
+pub struct Attempt {
+    gauges: SinkGauges,
+    awaiting_retry: AwaitingRetry,
+    events: usize,
+    succeeded: bool,
+}
+
+impl Attempt {
+    pub fn succeeded(mut self) {
+        self.succeeded = true;
+    }
+}
+
+impl Drop for Attempt {
+    fn drop(&mut self) {
+        if !self.succeeded && !self.awaiting_retry.0.swap(true, Ordering::Relaxed) {
+            self.gauges
+                .counts
+                .retrying_events
+                .fetch_add(self.events, Ordering::Relaxed);
+        }
+    }
+}
+
+/// Counts the events the sink didn't deliver when it is dropped, such as
+/// when it is stopped before it could flush.
+pub struct ShutdownGuard {
+    gauges: SinkGauges,
+    span: tracing::Span,
+}
+
+impl ShutdownGuard {
+    /// Must be created within the span of the sink, so the count is tagged
+    /// with its component.
+    pub fn new(gauges: SinkGauges) -> Self {
+        Self {
+            gauges,
+            span: tracing::Span::current(),
+        }
+    }
+}
+
+impl Drop for ShutdownGuard {
+    fn drop(&mut self) {
+        let _enter = self.span.enter();
+        self.gauges.shut_down();
+    }
+}
+
+/// Moves the events of each request from buffered to in flight while the
+/// request is sent, including its retries.
+pub struct GaugedService<S> {
+    inner: S,
+    gauges: SinkGauges,
+}
+
+impl<S> GaugedService<S> {
+    pub const fn new(inner: S, gauges: SinkGauges) -> Self {
+        Self { inner, gauges }
+    }
+}
+
+impl<S> Service<VectorRequest> for GaugedService<S>
+where
+    S: Service<VectorRequest>,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: VectorRequest) -> Self::Future {
+        let events = request.events_count();
+        let awaiting_retry = request.awaiting_retry.clone();
+        let gauges = self.gauges.clone();
+
+        gauges.request_started(events);
+        // If the sink is dropped before the request finishes, its events are
+        // counted as dropped at shutdown instead.
+        self.inner
+            .call(request)
+            .map(move |result| {
+                gauges.request_finished(events, &awaiting_retry);
+                result
+            })
+            .boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use vector_core::event_test_util;
+
+    use super::*;
+
+    #[tokio::test(start_paused = true)]
+    async fn gauges_are_published_on_a_tick() {
+        event_test_util::clear_recorded_events();
+        let gauges = SinkGauges::default();
+        gauges.spawn_publisher();
+        for _ in 0..3 {
+            gauges.event_buffered();
+        }
+        gauges.request_started(3);
+        drop(gauges.start_attempt(3, &AwaitingRetry::default()));
+        assert!(!event_test_util::contains_name("VectorSinkGaugesUpdated"));
+
+        time::sleep(PUBLISH_INTERVAL + Duration::from_millis(1)).await;
+        assert!(event_test_util::contains_name("VectorSinkGaugesUpdated"));
+    }
+
+    #[test]
+    fn attempts_track_retrying_events() {
+        let gauges = SinkGauges::default();
+        let awaiting_retry = AwaitingRetry::default();
+        for _ in 0..3 {
+            gauges.event_buffered();
+        }
+        gauges.request_started(3);
+        assert_eq!(gauges.buffered_events(), 0);
+        assert_eq!(gauges.in_flight_requests(), 1);
+
+        drop(gauges.start_attempt(3, &awaiting_retry));
+        assert_eq!(gauges.retrying_events(), 3);
+
+        let attempt = gauges.start_attempt(3, &awaiting_retry);
+        assert_eq!(gauges.retrying_events(), 0);
+        drop(attempt);
+        assert_eq!(gauges.retrying_events(), 3);
+
+        // Retries ran out.
+        gauges.request_finished(3, &awaiting_retry);
+        assert_eq!(gauges.retrying_events(), 0);
+        assert_eq!(gauges.in_flight_requests(), 0);
+
+        let awaiting_retry = AwaitingRetry::default();
+        gauges.event_buffered();
+        gauges.request_started(1);
+        gauges.start_attempt(1, &awaiting_retry).succeeded();
+        assert_eq!(gauges.retrying_events(), 0);
+        gauges.request_finished(1, &awaiting_retry);
+    }
+
+    #[test]
+    fn shutdown_counts_undelivered_events() {
+        let gauges = SinkGauges::default();
+        for _ in 0..5 {
+            gauges.event_buffered();
+        }
+        gauges.request_started(2);
+        drop(gauges.start_attempt(2, &AwaitingRetry::default()));
+
+        gauges.events_spooled(4);
+
+        drop(ShutdownGuard::new(gauges.clone()));
+        assert_eq!(gauges.buffered_events(), 0);
+        assert_eq!(gauges.in_flight_requests(), 0);
+        assert_eq!(gauges.retrying_events(), 0);
+        assert_eq!(gauges.counts.in_flight_events.load(Ordering::Relaxed), 0);
+        assert_eq!(gauges.counts.spooled_events.load(Ordering::Relaxed), 0);
+    }
+}
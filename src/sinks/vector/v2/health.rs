@@ -0,0 +1,538 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Weak,
+    },
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use futures::{
+    future::{join_all, BoxFuture},
+    FutureExt,
+};
+use serde::{Deserialize, Serialize};
+use tokio::{sync::watch, task::JoinHandle, time};
+use tower::Service;
+
+use crate::{
+    internal_events::{
+        VectorSinkDeltaUnsupported, VectorSinkEndpointHealthChecked, VectorSinkHealthChecked,
+    },
+    proto::vector as proto,
+    sinks::vector::v2::{service::VectorService, VectorSinkError},
+};
+
+/// Repeats the health check RPC in the background while the sink runs.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct PeriodicHealthcheckConfig {
+    /// How often to check the downstream Vector. Disabled when unset.
+    pub interval_secs: Option<u64>,
+    /// Hold back requests while the downstream Vector is unhealthy instead of
+    /// sending and retrying them.
+    #[serde(default)]
+    pub pause_when_unhealthy: bool,
+}
+
+/// The connection the health checks are sent over.
+#[derive(Clone, Copy, Debug, Derivative, Deserialize, Serialize)]
+#[derivative(Default)]
+#[serde(deny_unknown_fields)]
+pub struct HealthcheckChannelConfig {
+    /// Send the health checks over a connection of their own, so that they
+    /// don't wait behind the events when the downstream Vector is busy. When
+    /// disabled, they share the connection of the events.
+    #[derivative(Default(value = "true"))]
+    #[serde(default = "crate::serde::default_true")]
+    pub dedicated: bool,
+    /// How long to wait for the answer to a health check. The startup health
+    /// check waits for `request.timeout_secs` instead when not `dedicated`.
+    #[derivative(Default(value = "default_timeout_secs()"))]
+    #[serde(default = "default_timeout_secs")]
+    pub timeout_secs: u64,
+}
+
+const fn default_timeout_secs() -> u64 {
+    10
+}
+
+impl HealthcheckChannelConfig {
+    pub const fn timeout(&self) -> Duration {
+        Duration::from_secs(self.timeout_secs)
+    }
+}
+
+/// The answer of a downstream Vector to the health check RPC.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(super) enum EndpointHealth {
+    Serving,
+    /// Answered with any status other than serving.
+    NotServing,
+    /// Doesn't implement the health check RPC, so it isn't a Vector at all.
+    Unimplemented,
+}
+
+impl EndpointHealth {
+    const fn as_str(self) -> &'static str {
+        match self {
+            Self::Serving => "serving",
+            Self::NotServing => "not_serving",
+            Self::Unimplemented => "unimplemented",
+        }
+    }
+}
+
+/// Ask the downstream Vector behind `service` whether it is accepting events.
+///
+/// Fails with the status of the RPC if it got no answer, such as when the
+/// connection couldn't be made.
+pub(super) async fn check_endpoint(
+    service: &mut VectorService,
+) -> Result<EndpointHealth, tonic::Status> {
+    let request = service.client.health_check(proto::HealthCheckRequest {});
+
+    match request.await {
+        Ok(response) => match proto::ServingStatus::from_i32(response.into_inner().status) {
+            Some(proto::ServingStatus::Serving) => Ok(EndpointHealth::Serving),
+            _ => Ok(EndpointHealth::NotServing),
+        },
+        Err(status) if status.code() == tonic::Code::Unimplemented => {
+            Ok(EndpointHealth::Unimplemented)
+        }
+        Err(status) => Err(status),
+    }
+}
+
+/// Ask the downstream Vector whether it is accepting events.
+pub(super) async fn check_health(service: &mut VectorService) -> bool {
+    matches!(check_endpoint(service).await, Ok(EndpointHealth::Serving))
+}
+
+/// Check all of `services` concurrently, giving each `timeout` to answer,
+/// and log the result for each.
+///
+/// Fails if any of them isn't serving, naming the first one that turned out
+/// not to be a Vector at all, if any.
+pub(super) async fn check_endpoints(
+    services: Vec<VectorService>,
+    timeout: Duration,
+) -> Result<(), VectorSinkError> {
+    let checks = services.into_iter().map(|mut service| async move {
+        let health = time::timeout(timeout, check_endpoint(&mut service))
+            .await
+            .unwrap_or_else(|_| {
+                Err(tonic::Status::deadline_exceeded(
+                    "Timed out waiting for the health check.",
+                ))
+            });
+        emit!(&VectorSinkEndpointHealthChecked {
+            endpoint: &service.endpoint,
+            status: health.as_ref().map_or("error", |health| health.as_str()),
+            error_code: health
+                .as_ref()
+                .err()
+                .map(|error| error.code().description()),
+            error: health.as_ref().err().map(tonic::Status::message),
+        });
+        (service.endpoint, health)
+    });
+
+    let healths = join_all(checks).await;
+    if let Some((endpoint, _)) = healths
+        .iter()
+        .find(|(_, health)| matches!(health, Ok(EndpointHealth::Unimplemented)))
+    {
+        return Err(VectorSinkError::NotVector {
+            endpoint: endpoint.clone(),
+        });
+    }
+    if healths
+        .iter()
+        .all(|(_, health)| matches!(health, Ok(EndpointHealth::Serving)))
+    {
+        Ok(())
+    } else {
+        Err(VectorSinkError::Health)
+    }
+}
+
+/// Start checking the health of the downstream Vector every `interval`,
+/// taking it to be unhealthy if it doesn't answer within `timeout`.
+///
+/// The downstream is assumed to be healthy until the first check completes,
+/// since the startup healthcheck has already run by then. The task stops once
+/// every receiver of the health status has been dropped, which happens when
+/// the sink shuts down.
+pub(super) fn spawn_health_monitor(
+    mut service: VectorService,
+    interval: Duration,
+    timeout: Duration,
+) -> (watch::Receiver<bool>, JoinHandle<()>) {
+    let (tx, rx) = watch::channel(true);
+    let task = tokio::spawn(async move {
+        let mut interval = time::interval_at(time::Instant::now() + interval, interval);
+        loop {
+            tokio::select! {
+                _ = tx.closed() => break,
+                _ = interval.tick() => {
+                    let healthy = time::timeout(timeout, check_health(&mut service))
+                        .await
+                        .unwrap_or(false);
+                    let changed = healthy != *tx.borrow();
+                    emit!(&VectorSinkHealthChecked { healthy, changed });
+                    if changed && tx.send(healthy).is_err() {
+                        break;
+                    }
+                }
+            }
+        }
+    });
+    (rx, task)
+}
+
+/// How long to wait before asking again a downstream Vector that didn't answer
+/// which capabilities it has.
+const CAPABILITY_PROBE_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Find out which of the `capabilities` the downstream Vector has, setting the
+/// flag of each one it advertises. It is asked until it answers, or the sink
+/// has shut down.
+pub(super) fn spawn_capability_probe(
+    mut service: VectorService,
+    capabilities: Vec<(&'static str, Weak<AtomicBool>)>,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        loop {
+            let response = service
+                .client
+                .health_check(proto::HealthCheckRequest {})
+                .await;
+            let flags = capabilities
+                .iter()
+                .filter_map(|(name, flag)| Some((*name, flag.upgrade()?)))
+                .collect::<Vec<_>>();
+            if flags.is_empty() {
+                break;
+            }
+            match response {
+                Ok(response) => {
+                    let advertised = response.into_inner().capabilities;
+                    for (name, flag) in flags {
+                        if advertised.iter().any(|advertised| advertised == name) {
+                            flag.store(true, Ordering::Relaxed);
+                        } else if name == proto::DELTA_CAPABILITY {
+                            emit!(&VectorSinkDeltaUnsupported {
+                                endpoint: service.endpoint.clone(),
+                            });
+                        }
+                    }
+                    break;
+                }
+                // Not a Vector at all, which the health checks report.
+                Err(status) if status.code() == tonic::Code::Unimplemented => break,
+                Err(_) => time::sleep(CAPABILITY_PROBE_INTERVAL).await,
+            }
+        }
+    })
+}
+
+/// Holds back requests while the downstream Vector is unhealthy.
+///
+/// Readiness is withheld rather than requests failed, so batches wait in the
+/// sink until the health monitor reports the downstream as healthy again. If
+/// the monitor goes away, requests are let through.
+///
+/// The gate also owns the receiver that keeps the health monitor running, so
+/// it is used even when requests shouldn't be paused.
+pub struct HealthGate<S> {
+    inner: S,
+    health: watch::Receiver<bool>,
+    pause: bool,
+    waiting: Option<BoxFuture<'static, bool>>,
+    monitor_gone: bool,
+}
+
+impl<S> HealthGate<S> {
+    pub const fn new(inner: S, health: watch::Receiver<bool>, pause: bool) -> Self {
+        Self {
+            inner,
+            health,
+            pause,
+            waiting: None,
+            monitor_gone: false,
+        }
+    }
+}
+
+impl<S, Request> Service<Request> for HealthGate<S>
+where
+    S: Service<Request>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = S::Future;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        loop {
+            if let Some(waiting) = &mut self.waiting {
+                self.monitor_gone = !futures::ready!(waiting.poll_unpin(cx));
+                self.waiting = None;
+            }
+
+            if !self.pause || self.monitor_gone || *self.health.borrow() {
+                return self.inner.poll_ready(cx);
+            }
+
+            let mut health = self.health.clone();
+            self.waiting = Some(
+                async move {
+                    while health.changed().await.is_ok() {
+                        if *health.borrow() {
+                            return true;
+                        }
+                    }
+                    false
+                }
+                .boxed(),
+            );
+        }
+    }
+
+    fn call(&mut self, request: Request) -> Self::Future {
+        self.inner.call(request)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        net::SocketAddr,
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            Arc,
+        },
+        time::Instant,
+    };
+
+    use tokio_test::{assert_pending, assert_ready_ok, task};
+    use tower_test::{assert_request_eq, mock};
+
+    use super::*;
+    use crate::{
+        sinks::{
+            util::test::build_test_server_generic,
+            vector::v2::{
+                config::{new_client, with_default_scheme},
+                tests::encode_body,
+            },
+        },
+        test_util::{next_addr, vector_grpc::OtherGrpcServer},
+        tls::MaybeTlsSettings,
+    };
+
+    fn service(addr: SocketAddr) -> VectorService {
+        let tls = MaybeTlsSettings::from_config(&None, false).unwrap();
+        let uri = with_default_scheme(&format!("http://{}/", addr), false).unwrap();
+        let client = new_client(&tls, &Default::default()).unwrap();
+        VectorService::new(client, uri)
+    }
+
+    /// Start a server answering every request with `respond`. It runs until
+    /// the returned guard is dropped.
+    fn stub_server(
+        respond: impl Fn() -> hyper::Response<hyper::Body> + Clone + Send + Sync + 'static,
+    ) -> (VectorService, impl Sized) {
+        let addr = next_addr();
+        let (rx, trigger, server) = build_test_server_generic(addr, respond);
+        tokio::spawn(server);
+        (service(addr), (rx, trigger))
+    }
+
+    fn health_response(status: proto::ServingStatus) -> hyper::Response<hyper::Body> {
+        hyper::Response::builder()
+            .header("grpc-status", "0") // OK
+            .header("content-type", "application/grpc")
+            .body(hyper::Body::from(encode_body(proto::HealthCheckResponse {
+                status: status.into(),
+                capabilities: Vec::new(),
+            })))
+            .unwrap()
+    }
+
+    fn unimplemented_response() -> hyper::Response<hyper::Body> {
+        hyper::Response::builder()
+            .header("grpc-status", "12") // UNIMPLEMENTED
+            .header("content-type", "application/grpc")
+            .body(hyper::Body::empty())
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn check_endpoint_reports_each_state() {
+        let (mut serving, _serving) =
+            stub_server(|| health_response(proto::ServingStatus::Serving));
+        let (mut not_serving, _not_serving) =
+            stub_server(|| health_response(proto::ServingStatus::NotServing));
+        let (mut unimplemented, _unimplemented) = stub_server(unimplemented_response);
+        // Nothing listens on a fresh address.
+        let mut unreachable = service(next_addr());
+
+        assert_eq!(
+            check_endpoint(&mut serving).await.unwrap(),
+            EndpointHealth::Serving
+        );
+        assert_eq!(
+            check_endpoint(&mut not_serving).await.unwrap(),
+            EndpointHealth::NotServing
+        );
+        assert_eq!(
+            check_endpoint(&mut unimplemented).await.unwrap(),
+            EndpointHealth::Unimplemented
+        );
+        assert!(check_endpoint(&mut unreachable).await.is_err());
+
+        assert!(check_health(&mut serving).await);
+        assert!(!check_health(&mut unimplemented).await);
+    }
+
+    #[tokio::test]
+    async fn check_endpoints_requires_every_endpoint_serving() {
+        let (serving, _serving) = stub_server(|| health_response(proto::ServingStatus::Serving));
+        let (not_serving, _not_serving) =
+            stub_server(|| health_response(proto::ServingStatus::NotServing));
+        // Accepts connections but never answers.
+        let silent_addr = next_addr();
+        let _silent = tokio::net::TcpListener::bind(silent_addr).await.unwrap();
+        let timeout = Duration::from_millis(200);
+
+        assert!(
+            check_endpoints(vec![serving.clone(), serving.clone()], timeout)
+                .await
+                .is_ok()
+        );
+        assert!(matches!(
+            check_endpoints(vec![serving.clone(), not_serving], timeout).await,
+            Err(VectorSinkError::Health)
+        ));
+
+        let started = Instant::now();
+        assert!(
+            check_endpoints(vec![serving, service(silent_addr)], timeout)
+                .await
+                .is_err()
+        );
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+
+    #[tokio::test]
+    async fn check_endpoints_names_other_grpc_services() {
+        let other = OtherGrpcServer::start().await;
+        let (serving, _serving) = stub_server(|| health_response(proto::ServingStatus::Serving));
+        let other_service = service(other.addr());
+        let endpoint = other_service.endpoint.clone();
+
+        let error = check_endpoints(vec![serving, other_service], Duration::from_secs(5))
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(&error, VectorSinkError::NotVector { endpoint: name } if *name == endpoint)
+        );
+        assert_eq!(
+            error.to_string(),
+            format!(
+                "Remote {} does not implement the Vector service; \
+                 is the address pointing at the right port?",
+                endpoint
+            )
+        );
+    }
+
+    #[tokio::test]
+    async fn health_gate_pauses_until_healthy() {
+        let (tx, rx) = watch::channel(false);
+        let (mock, mut handle) = mock::pair::<&'static str, &'static str>();
+        let mut gate = mock::Spawn::new(HealthGate::new(mock, rx, true));
+
+        assert_pending!(gate.poll_ready());
+
+        tx.send(true).unwrap();
+        assert!(gate.is_woken());
+        assert_ready_ok!(gate.poll_ready());
+
+        let mut response = task::spawn(gate.call("hello"));
+        assert_request_eq!(handle, "hello").send_response("world");
+        assert_eq!(assert_ready_ok!(response.poll()), "world");
+
+        // Once the monitor is gone requests are no longer held back.
+        tx.send(false).unwrap();
+        assert_pending!(gate.poll_ready());
+        drop(tx);
+        assert!(gate.is_woken());
+        assert_ready_ok!(gate.poll_ready());
+    }
+
+    #[tokio::test]
+    async fn health_gate_without_pause_is_always_ready() {
+        let (_tx, rx) = watch::channel(false);
+        let (mock, _handle) = mock::pair::<&'static str, &'static str>();
+        let mut gate = mock::Spawn::new(HealthGate::new(mock, rx, false));
+
+        assert_ready_ok!(gate.poll_ready());
+    }
+
+    #[tokio::test]
+    async fn health_monitor_follows_flaps() {
+        let in_addr = next_addr();
+        let serving = Arc::new(AtomicBool::new(true));
+        let (_rx, trigger, server) = build_test_server_generic(in_addr, {
+            let serving = Arc::clone(&serving);
+            move || {
+                let status = if serving.load(Ordering::SeqCst) {
+                    proto::ServingStatus::Serving
+                } else {
+                    proto::ServingStatus::NotServing
+                };
+                hyper::Response::builder()
+                    .header("grpc-status", "0") // OK
+                    .header("content-type", "application/grpc")
+                    .body(hyper::Body::from(encode_body(proto::HealthCheckResponse {
+                        status: status.into(),
+                        capabilities: Vec::new(),
+                    })))
+                    .unwrap()
+            }
+        });
+        tokio::spawn(server);
+
+        let tls = MaybeTlsSettings::from_config(&None, false).unwrap();
+        let uri = with_default_scheme(&format!("http://{}/", in_addr), false).unwrap();
+        let client = new_client(&tls, &Default::default()).unwrap();
+        let service = VectorService::new(client, uri);
+
+        let (mut health, monitor) =
+            spawn_health_monitor(service, Duration::from_millis(50), Duration::from_secs(1));
+        assert!(*health.borrow());
+
+        let timeout = Duration::from_secs(5);
+        serving.store(false, Ordering::SeqCst);
+        time::timeout(timeout, health.changed())
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(!*health.borrow());
+
+        serving.store(true, Ordering::SeqCst);
+        time::timeout(timeout, health.changed())
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(*health.borrow());
+
+        // Dropping the last receiver, as the sink does when it shuts down,
+        // stops the monitor.
+        drop(health);
+        time::timeout(timeout, monitor).await.unwrap().unwrap();
+        drop(trigger);
+    }
+}
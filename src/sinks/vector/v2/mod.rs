@@ -1,8 +1,14 @@
 use snafu::Snafu;
 
 mod config;
+mod delta;
+mod fields;
+mod gauges;
+mod health;
+mod resolve;
 mod service;
 mod sink;
+mod spool;
 
 pub use config::VectorConfig;
 
@@ -15,17 +21,26 @@ pub enum VectorSinkError {
     #[snafu(display("Vector source unhealthy"))]
     Health,
 
+    #[snafu(display(
+        "Remote {} does not implement the Vector service; is the address pointing at the right port?",
+        endpoint
+    ))]
+    NotVector { endpoint: String },
+
     #[snafu(display("URL has no host."))]
     NoHost,
 }
 
 #[cfg(test)]
 mod tests {
+    use std::time::Duration;
+
     use bytes::{BufMut, Bytes, BytesMut};
     use futures::{channel::mpsc, StreamExt};
     use http::request::Parts;
     use hyper::Method;
     use prost::Message;
+    use tonic::Code;
     use vector_core::event::{BatchNotifier, BatchStatus};
 
     use super::*;
@@ -33,8 +48,15 @@ mod tests {
         config::SinkContext,
         event::Event,
         proto::vector as proto,
-        sinks::{util::test::build_test_server_generic, vector::v2::config::with_default_scheme},
-        test_util::{components, next_addr, random_lines_with_stream},
+        sinks::{
+            util::test::build_test_server_generic,
+            vector::v2::{config::with_default_scheme, gauges::SinkGauges},
+            VectorSink,
+        },
+        test_util::{
+            components, next_addr, random_lines_with_stream,
+            vector_grpc::{Faults, FaultyVector, OtherGrpcServer, Outcome},
+        },
     };
 
     // one byte for the compression flag plus four bytes for the length
@@ -62,7 +84,9 @@ mod tests {
             hyper::Response::builder()
                 .header("grpc-status", "0") // OK
                 .header("content-type", "application/grpc")
-                .body(hyper::Body::from(encode_body(proto::PushEventsResponse {})))
+                .body(hyper::Body::from(encode_body(
+                    proto::PushEventsResponse::default(),
+                )))
                 .unwrap()
         });
 
@@ -84,6 +108,8 @@ mod tests {
                 "application/grpc",
                 parts.headers.get("content-type").unwrap().to_str().unwrap()
             );
+            // The request timeout is passed on as the gRPC deadline.
+            assert!(parts.headers.contains_key("grpc-timeout"));
         })
         .await;
 
@@ -91,6 +117,98 @@ mod tests {
         assert_eq!(input_lines, output_lines);
     }
 
+    #[tokio::test]
+    async fn oversized_event_does_not_wedge_batches() {
+        let in_addr = next_addr();
+
+        let config = format!(
+            r#"
+            address = "http://{}/"
+            batch.max_events = 1
+            max_event_bytes = 1024
+            "#,
+            in_addr
+        );
+        let config: VectorConfig = toml::from_str(&config).unwrap();
+
+        let cx = SinkContext::new_test();
+
+        let (sink, _) = config.build(cx).await.unwrap();
+        let (rx, trigger, server) = build_test_server_generic(in_addr, move || {
+            hyper::Response::builder()
+                .header("grpc-status", "0") // OK
+                .header("content-type", "application/grpc")
+                .body(hyper::Body::from(encode_body(
+                    proto::PushEventsResponse::default(),
+                )))
+                .unwrap()
+        });
+
+        tokio::spawn(server);
+
+        let lines = vec!["first".to_owned(), "x".repeat(2048), "last".to_owned()];
+        let events = lines.iter().map(|line| Event::from(line.as_str()));
+
+        sink.run(futures::stream::iter(events)).await.unwrap();
+        drop(trigger);
+
+        let output_lines = get_received(rx, |_| {}).await;
+        assert_eq!(output_lines, vec![lines[0].clone(), lines[2].clone()]);
+    }
+
+    #[tokio::test]
+    async fn encode_failures_skip_only_failed_events() {
+        let in_addr = next_addr();
+
+        let config = format!(
+            r#"
+            address = "http://{}/"
+            max_event_bytes = 1024
+            oversized_event_action = "truncate"
+            "#,
+            in_addr
+        );
+        let config: VectorConfig = toml::from_str(&config).unwrap();
+
+        let (sink, _) = config.build(SinkContext::new_test()).await.unwrap();
+        let (rx, trigger, server) = build_test_server_generic(in_addr, move || {
+            hyper::Response::builder()
+                .header("grpc-status", "0") // OK
+                .header("content-type", "application/grpc")
+                .body(hyper::Body::from(encode_body(
+                    proto::PushEventsResponse::default(),
+                )))
+                .unwrap()
+        });
+
+        tokio::spawn(server);
+
+        // No single field is long enough to truncate these below the limit.
+        let unencodable = || {
+            let mut event = Event::from("unencodable");
+            for index in 0..100 {
+                event
+                    .as_mut_log()
+                    .insert(format!("field_{}", index), "x".repeat(20));
+            }
+            event
+        };
+        let events = vec![
+            Event::from("first"),
+            unencodable(),
+            Event::from("middle"),
+            unencodable(),
+            unencodable(),
+            Event::from("last"),
+        ];
+
+        sink.run(futures::stream::iter(events)).await.unwrap();
+        drop(trigger);
+
+        let output_lines = get_received(rx, |_| {}).await;
+        assert_eq!(output_lines, vec!["first", "middle", "last"]);
+    }
+
     #[tokio::test]
     async fn acknowledges_error() {
         let num_lines = 10;
@@ -121,6 +239,533 @@ mod tests {
         assert_eq!(receiver.try_recv(), Ok(BatchStatus::Rejected));
     }
 
+    #[tokio::test]
+    async fn circuit_breaker_stops_requests() {
+        let in_addr = next_addr();
+
+        let config = format!(
+            r#"
+            address = "http://{}/"
+            batch.max_events = 1
+            request.concurrency = 1
+            request.retry_attempts = 2
+            request.retry_initial_backoff_secs = 1
+            request.circuit_breaker.failure_rate_threshold = 1.0
+            request.circuit_breaker.minimum_requests = 1
+            request.circuit_breaker.open_duration_secs = 60
+            "#,
+            in_addr
+        );
+        let config: VectorConfig = toml::from_str(&config).unwrap();
+
+        let cx = SinkContext::new_test();
+
+        let (sink, _) = config.build(cx).await.unwrap();
+        let (rx, trigger, server) = build_test_server_generic(in_addr, move || {
+            hyper::Response::builder()
+                .header("grpc-status", "14") // unavailable
+                .header("content-type", "application/grpc")
+                .body(tonic::body::empty_body())
+                .unwrap()
+        });
+
+        tokio::spawn(server);
+
+        let (batch, mut receiver) = BatchNotifier::new_with_receiver();
+        let (_, events) = random_lines_with_stream(8, 3, Some(batch));
+
+        sink.run(events).await.unwrap();
+        drop(trigger);
+        assert_eq!(receiver.try_recv(), Ok(BatchStatus::Rejected));
+
+        // The first failure opens the breaker, so its retry and the two
+        // following requests are rejected without reaching the server.
+        assert_eq!(rx.count().await, 1);
+    }
+
+    #[tokio::test]
+    async fn gauges_track_unanswered_requests() {
+        let downstream = FaultyVector::start(Faults::default().withholding_responses()).await;
+
+        let config = format!(
+            r#"
+            address = "http://{}/"
+            batch.max_events = 1
+            request.concurrency = 2
+            "#,
+            downstream.addr()
+        );
+        let config: VectorConfig = toml::from_str(&config).unwrap();
+
+        let cx = SinkContext::new_test();
+
+        let gauges = SinkGauges::default();
+        let (sink, _) = config.build_with_gauges(cx, gauges.clone()).await.unwrap();
+        let events = (0..10).map(|i| Event::from(format!("line {}", i)));
+        let sink = tokio::spawn(sink.run(futures::stream::iter(events)));
+
+        let settled = async {
+            while gauges.buffered_events() < 8 || gauges.in_flight_requests() < 2 {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        };
+        tokio::time::timeout(Duration::from_secs(5), settled)
+            .await
+            .unwrap();
+
+        // Nothing more is sent while the two requests hang.
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        assert_eq!(gauges.buffered_events(), 8);
+        assert_eq!(gauges.in_flight_requests(), 2);
+        assert_eq!(gauges.retrying_events(), 0);
+        assert_eq!(downstream.requests(), 2);
+        assert_eq!(downstream.outcomes(), vec![Outcome::Withheld; 2]);
+
+        // Stopping the sink counts what it didn't deliver as dropped.
+        sink.abort();
+        assert!(sink.await.unwrap_err().is_cancelled());
+        assert_eq!(gauges.buffered_events(), 0);
+        assert_eq!(gauges.in_flight_requests(), 0);
+    }
+
+    #[tokio::test]
+    async fn healthcheck_is_not_queued_behind_events() {
+        // The downstream Vector never answers the pushes, and only handles one
+        // request of each connection at a time, so the requests sent over the
+        // connection of the events after the first one wait forever.
+        let downstream = FaultyVector::start(
+            Faults::default()
+                .withholding_responses()
+                .with_concurrency_per_connection(1),
+        )
+        .await;
+
+        for (dedicated, healthy) in [(true, true), (false, false)] {
+            let config = format!(
+                r#"
+                address = "http://{}/"
+                batch.max_events = 1
+                healthcheck_channel.dedicated = {}
+                healthcheck_channel.timeout_secs = 1
+                request.timeout_secs = 1
+                "#,
+                downstream.addr(),
+                dedicated
+            );
+            let config: VectorConfig = toml::from_str(&config).unwrap();
+            let (sink, healthcheck) = config.build(SinkContext::new_test()).await.unwrap();
+
+            let pushed = downstream.requests();
+            let events = (0..10).map(|i| Event::from(format!("line {}", i)));
+            let sink = tokio::spawn(sink.run(futures::stream::iter(events)));
+            let saturated = async {
+                while downstream.requests() == pushed {
+                    tokio::time::sleep(Duration::from_millis(10)).await;
+                }
+            };
+            tokio::time::timeout(Duration::from_secs(5), saturated)
+                .await
+                .unwrap();
+
+            assert_eq!(
+                healthcheck.await.is_ok(),
+                healthy,
+                "dedicated = {}",
+                dedicated
+            );
+            sink.abort();
+        }
+    }
+
+    /// Send `count` events to `downstream`, one per request, through a sink
+    /// with the extra `options`, returning the status the events ended up with.
+    async fn send_to(downstream: &FaultyVector, options: &str, count: usize) -> BatchStatus {
+        let config = format!(
+            "address = \"http://{}/\"\nbatch.max_events = 1\n{}",
+            downstream.addr(),
+            options
+        );
+        let config: VectorConfig = toml::from_str(&config).unwrap();
+        let (sink, _) = config.build(SinkContext::new_test()).await.unwrap();
+
+        let (batch, mut receiver) = BatchNotifier::new_with_receiver();
+        let (_, events) = random_lines_with_stream(8, count, Some(batch));
+        sink.run(events).await.unwrap();
+        receiver.try_recv().unwrap()
+    }
+
+    fn count(outcomes: &[Outcome], outcome: Outcome) -> usize {
+        outcomes.iter().filter(|&&other| other == outcome).count()
+    }
+
+    #[tokio::test]
+    async fn non_retriable_status_codes_are_not_retried() {
+        for code in [
+            Code::InvalidArgument,
+            Code::PermissionDenied,
+            Code::Unimplemented,
+            Code::Unauthenticated,
+        ] {
+            let faults = Faults::default().with_failure_rate(code, 1.0);
+            let downstream = FaultyVector::start(faults).await;
+
+            let status = send_to(&downstream, "request.retry_attempts = 2", 1).await;
+            assert_eq!(status, BatchStatus::Rejected);
+            assert_eq!(downstream.outcomes(), vec![Outcome::Failed(code)]);
+        }
+    }
+
+    #[tokio::test]
+    async fn retriable_status_codes_are_retried() {
+        for code in [Code::Unavailable, Code::ResourceExhausted] {
+            let faults = Faults::default().with_failure_rate(code, 1.0);
+            let downstream = FaultyVector::start(faults).await;
+
+            let options = r#"
+                request.retry_attempts = 1
+                request.retry_initial_backoff_secs = 1
+            "#;
+            let status = send_to(&downstream, options, 1).await;
+            assert_eq!(status, BatchStatus::Rejected);
+            assert_eq!(downstream.outcomes(), vec![Outcome::Failed(code); 2]);
+        }
+    }
+
+    #[tokio::test]
+    async fn other_grpc_service_fails_without_retries() {
+        let other = OtherGrpcServer::start().await;
+
+        let config = format!(
+            r#"
+            address = "http://{}/"
+            request.retry_attempts = 2
+            request.retry_initial_backoff_secs = 1
+            "#,
+            other.addr()
+        );
+        let config: VectorConfig = toml::from_str(&config).unwrap();
+        let (sink, healthcheck) = config.build(SinkContext::new_test()).await.unwrap();
+
+        let error = healthcheck.await.unwrap_err().to_string();
+        assert!(error.contains("does not implement the Vector service"));
+
+        let (batch, mut receiver) = BatchNotifier::new_with_receiver();
+        let (_, events) = random_lines_with_stream(8, 1, Some(batch));
+        sink.run(events).await.unwrap();
+        assert_eq!(receiver.try_recv(), Ok(BatchStatus::Rejected));
+        // The health check, then the one push that wasn't retried.
+        assert_eq!(other.requests(), 2);
+    }
+
+    #[tokio::test]
+    async fn flaky_downstream_gets_every_event_once() {
+        let faults = Faults::default()
+            .with_failure_rate(Code::Unavailable, 0.2)
+            .with_failure_rate(Code::Internal, 0.1)
+            .with_seed(1);
+        let downstream = FaultyVector::start(faults).await;
+
+        let options = r#"
+            request.concurrency = 1
+            request.retry_initial_backoff_secs = 1
+        "#;
+        let status = send_to(&downstream, options, 5).await;
+        assert_eq!(status, BatchStatus::Delivered);
+        assert_eq!(count(&downstream.outcomes(), Outcome::Accepted), 5);
+        assert_eq!(downstream.events().len(), 5);
+    }
+
+    #[tokio::test]
+    async fn accepted_chunks_are_not_sent_again() {
+        let downstream = FaultyVector::start(Faults::default().with_reset_every(3)).await;
+
+        let options = r#"
+            chunking.enabled = true
+            chunking.chunk_bytes = 4
+            request.retry_initial_backoff_secs = 1
+        "#;
+        let status = send_to(&downstream, options, 1).await;
+        assert_eq!(status, BatchStatus::Delivered);
+        assert!(downstream.outcomes().contains(&Outcome::Reset));
+
+        // Each chunk got through once, in order, though some were retried.
+        let chunks = downstream.chunks();
+        assert!(chunks.len() > 1);
+        let indexes = chunks.iter().map(|chunk| chunk.index).collect::<Vec<_>>();
+        assert_eq!(indexes, (0..chunks[0].total).collect::<Vec<_>>());
+    }
+
+    /// A sink sending the events of each `resource` as deltas to `downstream`, once it found out
+    /// that it takes them.
+    async fn delta_sink(downstream: &FaultyVector) -> VectorSink {
+        let config = format!(
+            r#"
+            address = "http://{}/"
+            batch.max_events = 1
+            request.concurrency = 1
+            request.retry_initial_backoff_secs = 1
+            delta.enabled = true
+            delta.key_field = "resource"
+            "#,
+            downstream.addr()
+        );
+        let config: VectorConfig = toml::from_str(&config).unwrap();
+        let (sink, _) = config.build(SinkContext::new_test()).await.unwrap();
+        let probed = async {
+            while downstream.health_checks() == 0 {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        };
+        tokio::time::timeout(Duration::from_secs(5), probed)
+            .await
+            .unwrap();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        sink
+    }
+
+    #[tokio::test]
+    async fn dropped_keyed_events_are_sent_again_whole() {
+        let downstream = FaultyVector::start(Faults::default().advertising_deltas()).await;
+        let sink = delta_sink(&downstream).await;
+
+        let (batch, mut receiver) = BatchNotifier::new_with_receiver();
+        let events = (0..5)
+            .map(|i| {
+                let mut event = Event::from(format!("line {}", i));
+                event.as_mut_log().insert("resource", "a");
+                event.with_batch_notifier(&batch)
+            })
+            .collect::<Vec<_>>();
+        drop(batch);
+        sink.run(futures::stream::iter(events)).await.unwrap();
+
+        assert_eq!(receiver.try_recv(), Ok(BatchStatus::Delivered));
+        let messages = downstream
+            .events()
+            .iter()
+            .map(|event| event.as_log()["message"].to_string_lossy())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            messages,
+            (0..5).map(|i| format!("line {}", i)).collect::<Vec<_>>()
+        );
+        // Only the first event was sent keyed, then again whole.
+        assert_eq!(downstream.requests(), 6);
+    }
+
+    #[tokio::test]
+    async fn keyed_events_sent_again_are_retried_on_their_own() {
+        // The keyed event is dropped by the first push, and the push sending it again whole fails.
+        let faults = Faults::default().advertising_deltas().with_reset_every(2);
+        let downstream = FaultyVector::start(faults).await;
+        let sink = delta_sink(&downstream).await;
+
+        let (batch, mut receiver) = BatchNotifier::new_with_receiver();
+        let mut event = Event::from("line");
+        event.as_mut_log().insert("resource", "a");
+        let event = event.with_batch_notifier(&batch);
+        drop(batch);
+        sink.run(futures::stream::iter(vec![event])).await.unwrap();
+
+        assert_eq!(receiver.try_recv(), Ok(BatchStatus::Delivered));
+        assert_eq!(downstream.events().len(), 1);
+        // The retry only sent the event again whole, not keyed first.
+        assert_eq!(
+            downstream.outcomes(),
+            vec![Outcome::Accepted, Outcome::Reset, Outcome::Accepted]
+        );
+    }
+
+    #[tokio::test]
+    async fn jittered_latency_within_timeout_delivers_everything() {
+        let faults = Faults::default()
+            .with_latency(Duration::from_millis(50), Duration::from_millis(200))
+            .with_seed(1);
+        let downstream = FaultyVector::start(faults).await;
+
+        let status = send_to(&downstream, "request.concurrency = 4", 10).await;
+        assert_eq!(status, BatchStatus::Delivered);
+        assert_eq!(downstream.outcomes(), vec![Outcome::Accepted; 10]);
+    }
+
+    #[tokio::test]
+    async fn ordered_delivery_survives_jitter_and_retries() {
+        // The jitter would let later requests overtake earlier ones if they
+        // were in flight together, and the failures make some be retried.
+        let faults = Faults::default()
+            .with_latency(Duration::from_millis(10), Duration::from_millis(100))
+            .with_failure_rate(Code::Unavailable, 0.3)
+            .with_seed(3);
+        let downstream = FaultyVector::start(faults).await;
+
+        let config = format!(
+            r#"
+            address = "http://{}/"
+            batch.max_events = 1
+            ordered = true
+            request.retry_initial_backoff_secs = 1
+            "#,
+            downstream.addr()
+        );
+        let config: VectorConfig = toml::from_str(&config).unwrap();
+        let (sink, _) = config.build(SinkContext::new_test()).await.unwrap();
+
+        let (batch, mut receiver) = BatchNotifier::new_with_receiver();
+        let (lines, events) = random_lines_with_stream(8, 10, Some(batch));
+        sink.run(events).await.unwrap();
+        assert_eq!(receiver.try_recv(), Ok(BatchStatus::Delivered));
+
+        let received = downstream
+            .events()
+            .into_iter()
+            .map(|event| event.as_log()["message"].to_string_lossy())
+            .collect::<Vec<_>>();
+        assert_eq!(received, lines);
+    }
+
+    #[cfg(feature = "sources-demo_logs")]
+    fn spool_files(directory: &std::path::Path) -> usize {
+        std::fs::read_dir(directory).map_or(0, |entries| {
+            entries
+                .filter(|entry| {
+                    let path = entry.as_ref().unwrap().path();
+                    path.extension().and_then(|extension| extension.to_str()) == Some("spool")
+                })
+                .count()
+        })
+    }
+
+    /// A `demo_logs` source sending `count` copies of `line` to a `vector` sink that spools to
+    /// `directory`.
+    #[cfg(feature = "sources-demo_logs")]
+    fn spooling_topology(
+        address: std::net::SocketAddr,
+        directory: &std::path::Path,
+        line: &str,
+        count: usize,
+    ) -> crate::config::Config {
+        let mut config = crate::config::Config::builder();
+        config.add_source(
+            "in",
+            crate::sources::demo_logs::DemoLogsConfig::repeat(vec![line.to_owned()], count, 0.0),
+        );
+        let sink = format!(
+            r#"
+            version = "2"
+            address = "http://{}/"
+            batch.max_events = 100
+            batch.timeout_secs = 1
+            ordered = true
+            request.retry_initial_backoff_secs = 1
+            spool.directory = "{}"
+            "#,
+            address,
+            directory.display()
+        );
+        config.add_sink(
+            "out",
+            &["in"],
+            toml::from_str::<crate::sinks::vector::VectorConfig>(&sink).unwrap(),
+        );
+        config.build().unwrap()
+    }
+
+    /// Kill the topology the way Vector does once the shutdown timeout is up, by shutting the
+    /// runtime down with the sink still retrying.
+    #[cfg(feature = "sources-demo_logs")]
+    fn run_until_killed(config: crate::config::Config) {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        runtime.block_on(async {
+            let (topology, _crash) = crate::test_util::start_topology(config, false).await;
+            // Long enough for the events to be sent and retried.
+            tokio::time::sleep(Duration::from_secs(2)).await;
+            let stop = tokio::time::timeout(Duration::from_secs(1), topology.stop()).await;
+            assert!(stop.is_err());
+        });
+        drop(runtime);
+    }
+
+    #[cfg(feature = "sources-demo_logs")]
+    #[test]
+    fn spooled_events_survive_killing_the_topology() {
+        let directory = crate::test_util::temp_dir();
+
+        // Nothing listens on the address, so the requests are retried until Vector is killed.
+        run_until_killed(spooling_topology(next_addr(), &directory, "first", 8));
+        assert_eq!(spool_files(&directory), 1);
+
+        // Killed again while sending the restored events along with new ones. The file they were
+        // restored from is kept as it is, and only the new ones are spooled.
+        run_until_killed(spooling_topology(next_addr(), &directory, "second", 8));
+        assert_eq!(spool_files(&directory), 2);
+
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let received = runtime.block_on(async {
+            let downstream = FaultyVector::start(Faults::default()).await;
+            let config = spooling_topology(downstream.addr(), &directory, "third", 1);
+            let (topology, _crash) = crate::test_util::start_topology(config, false).await;
+            tokio::time::timeout(Duration::from_secs(10), async {
+                while downstream.events().len() < 17 {
+                    tokio::time::sleep(Duration::from_millis(10)).await;
+                }
+            })
+            .await
+            .unwrap();
+            topology.stop().await;
+
+            // The files are removed once their events are delivered.
+            tokio::time::timeout(Duration::from_secs(5), async {
+                while spool_files(&directory) > 0 {
+                    tokio::time::sleep(Duration::from_millis(10)).await;
+                }
+            })
+            .await
+            .unwrap();
+            downstream.events()
+        });
+
+        // The spooled events are sent once each, oldest first and ahead of the new ones.
+        let received = received
+            .into_iter()
+            .map(|event| event.as_log()["message"].to_string_lossy())
+            .collect::<Vec<_>>();
+        let expected = [vec!["first"; 8], vec!["second"; 8], vec!["third"]].concat();
+        assert_eq!(received, expected);
+    }
+
+    #[tokio::test]
+    async fn slow_downstream_times_out_and_is_retried() {
+        let faults = Faults::default().with_latency(Duration::from_secs(30), Duration::ZERO);
+        let downstream = FaultyVector::start(faults).await;
+
+        let options = r#"
+            request.timeout_secs = 1
+            request.retry_attempts = 1
+            request.retry_initial_backoff_secs = 1
+        "#;
+        let status = send_to(&downstream, options, 1).await;
+        assert_eq!(status, BatchStatus::Rejected);
+        // Both attempts were given up on before they were handled.
+        assert_eq!(downstream.requests(), 2);
+        assert!(downstream.events().is_empty());
+    }
+
+    #[tokio::test]
+    async fn connection_resets_are_retried() {
+        let downstream = FaultyVector::start(Faults::default().with_reset_every(2)).await;
+
+        let options = r#"
+            request.concurrency = 1
+            request.retry_initial_backoff_secs = 1
+        "#;
+        let status = send_to(&downstream, options, 3).await;
+        assert_eq!(status, BatchStatus::Delivered);
+        let outcomes = downstream.outcomes();
+        assert_eq!(count(&outcomes, Outcome::Accepted), 3);
+        assert_eq!(count(&outcomes, Outcome::Reset), 2);
+        assert_eq!(downstream.events().len(), 3);
+    }
+
     #[test]
     fn test_with_default_scheme() {
         assert_eq!(
@@ -161,7 +806,7 @@ mod tests {
     }
 
     // taken from <https://github.com/hyperium/tonic/blob/5aa8ae1fec27377cd4c2a41d309945d7e38087d0/examples/src/grpc-web/client.rs#L45-L75>
-    fn encode_body<T>(msg: T) -> Bytes
+    pub(super) fn encode_body<T>(msg: T) -> Bytes
     where
         T: prost::Message,
     {
@@ -0,0 +1,231 @@
+use std::{
+    collections::BTreeSet,
+    net::IpAddr,
+    sync::{Arc, RwLock, Weak},
+    time::Duration,
+};
+
+use futures::{future::BoxFuture, FutureExt};
+use tokio::{task::JoinHandle, time};
+
+use crate::{
+    dns::{DnsError, Resolver},
+    internal_events::{VectorSinkAddressesChanged, VectorSinkDnsRefreshFailed},
+    sinks::vector::v2::service::HyperClient,
+};
+
+/// Looks up the addresses of a host.
+pub trait Resolve: Send + Sync + 'static {
+    fn resolve(&self, host: String) -> BoxFuture<'static, Result<BTreeSet<IpAddr>, DnsError>>;
+}
+
+impl Resolve for Resolver {
+    fn resolve(&self, host: String) -> BoxFuture<'static, Result<BTreeSet<IpAddr>, DnsError>> {
+        self.lookup_ip(host)
+            .map(|result| result.map(Iterator::collect))
+            .boxed()
+    }
+}
+
+/// The HTTP client shared by the services of the sink. It is replaced with a
+/// new one when the addresses of the downstream Vector change, so that new
+/// requests are sent over new connections, while requests in flight finish on
+/// the connections of the client they were sent with.
+#[derive(Clone, Debug)]
+pub struct ClientHandle<C = HyperClient>(Arc<RwLock<C>>);
+
+impl<C: Clone> ClientHandle<C> {
+    pub fn get(&self) -> C {
+        self.0.read().expect("client lock poisoned").clone()
+    }
+}
+
+impl<C> ClientHandle<C> {
+    fn downgrade(&self) -> Weak<RwLock<C>> {
+        Arc::downgrade(&self.0)
+    }
+}
+
+impl<C> From<C> for ClientHandle<C> {
+    fn from(client: C) -> Self {
+        Self(Arc::new(RwLock::new(client)))
+    }
+}
+
+/// The host of the downstream Vector, unless it is an IP address, which there
+/// is nothing to resolve for.
+pub(super) fn resolvable_host(host: &str) -> Option<&str> {
+    let address = host.trim_start_matches('[').trim_end_matches(']');
+    address.parse::<IpAddr>().is_err().then(|| host)
+}
+
+/// Resolve `host` every `interval`, and replace the client of each of
+/// `handles` with one from `build` when its addresses change. Failures to
+/// resolve or build, and answers without addresses, keep the current clients
+/// and log a warning. The task stops once the sink has shut down.
+pub(super) fn spawn_dns_refresh<R, B, C>(
+    resolver: R,
+    host: String,
+    interval: Duration,
+    handles: &[&ClientHandle<C>],
+    build: B,
+) -> JoinHandle<()>
+where
+    R: Resolve,
+    B: Fn() -> crate::Result<C> + Send + 'static,
+    C: Send + Sync + 'static,
+{
+    let handles = handles
+        .iter()
+        .map(|handle| handle.downgrade())
+        .collect::<Vec<_>>();
+    tokio::spawn(async move {
+        // The addresses the client connected to are unknown, so those of the
+        // first answer are taken to be them.
+        let mut addresses = None;
+        loop {
+            let resolved = resolver.resolve(host.clone()).await;
+            let handles = handles.iter().filter_map(Weak::upgrade).collect::<Vec<_>>();
+            if handles.is_empty() {
+                break;
+            }
+            match resolved {
+                Err(error) => emit!(&VectorSinkDnsRefreshFailed {
+                    host: &host,
+                    error: &error.to_string(),
+                }),
+                // An empty answer says nothing about where the downstream Vector is.
+                Ok(resolved) if resolved.is_empty() => emit!(&VectorSinkDnsRefreshFailed {
+                    host: &host,
+                    error: "No addresses were found.",
+                }),
+                Ok(resolved) => match &addresses {
+                    None => addresses = Some(resolved),
+                    Some(current) if *current == resolved => (),
+                    Some(current) => match handles
+                        .iter()
+                        .map(|_| build())
+                        .collect::<crate::Result<Vec<_>>>()
+                    {
+                        Ok(clients) => {
+                            emit!(&VectorSinkAddressesChanged {
+                                host: &host,
+                                added: resolved.difference(current).count(),
+                                removed: current.difference(&resolved).count(),
+                            });
+                            for (handle, client) in handles.iter().zip(clients) {
+                                *handle.write().expect("client lock poisoned") = client;
+                            }
+                            addresses = Some(resolved);
+                        }
+                        Err(error) => emit!(&VectorSinkDnsRefreshFailed {
+                            host: &host,
+                            error: &error.to_string(),
+                        }),
+                    },
+                },
+            }
+            drop(handles);
+            time::sleep(interval).await;
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::VecDeque,
+        io,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Mutex,
+        },
+    };
+
+    use super::*;
+
+    /// Answers with the given results in turn, then with the last one.
+    struct StubResolver(Mutex<VecDeque<Result<Vec<&'static str>, ()>>>);
+
+    impl Resolve for StubResolver {
+        fn resolve(&self, _host: String) -> BoxFuture<'static, Result<BTreeSet<IpAddr>, DnsError>> {
+            let mut answers = self.0.lock().unwrap();
+            let answer = if answers.len() > 1 {
+                answers.pop_front().unwrap()
+            } else {
+                answers[0].clone()
+            };
+            let result = match answer {
+                Ok(addresses) => Ok(addresses.iter().map(|a| a.parse().unwrap()).collect()),
+                Err(()) => Err(DnsError::UnableLookup {
+                    source: io::Error::new(io::ErrorKind::Other, "no answer"),
+                }),
+            };
+            futures::future::ready(result).boxed()
+        }
+    }
+
+    /// Run the refresh over the given answers, with clients numbered in the
+    /// order they are built, `0` being the one the sink started with. Returns
+    /// the client in use once the answers ran out, and how many were built.
+    async fn refresh(answers: Vec<Result<Vec<&'static str>, ()>>) -> (usize, usize) {
+        let rounds = answers.len();
+        let builds = Arc::new(AtomicUsize::new(0));
+        let handle = ClientHandle::from(0);
+        let counted = Arc::clone(&builds);
+        let interval = Duration::from_secs(30);
+        spawn_dns_refresh(
+            StubResolver(Mutex::new(answers.into())),
+            "vector.example".to_owned(),
+            interval,
+            &[&handle],
+            move || Ok(counted.fetch_add(1, Ordering::Relaxed) + 1),
+        );
+        // The answers after the last repeat it, which changes nothing.
+        time::sleep(interval * rounds as u32).await;
+        (handle.get(), builds.load(Ordering::Relaxed))
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn rotated_addresses_rebuild_the_client() {
+        let answers = vec![
+            Ok(vec!["10.0.0.1", "10.0.0.2"]),
+            Ok(vec!["10.0.0.2", "10.0.0.1"]),
+            Ok(vec!["10.0.0.2", "10.0.0.3"]),
+            Ok(vec!["10.0.0.2", "10.0.0.3"]),
+        ];
+        assert_eq!(refresh(answers).await, (1, 1));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn failures_keep_the_client() {
+        let answers = vec![
+            Ok(vec!["10.0.0.1"]),
+            Err(()),
+            Ok(vec![]),
+            Ok(vec!["10.0.0.1"]),
+        ];
+        assert_eq!(refresh(answers).await, (0, 0));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn stops_once_the_sink_is_gone() {
+        let handle = ClientHandle::from(0);
+        let task = spawn_dns_refresh(
+            StubResolver(Mutex::new(vec![Ok(vec!["10.0.0.1"])].into())),
+            "vector.example".to_owned(),
+            Duration::from_secs(30),
+            &[&handle],
+            || Ok(1),
+        );
+        drop(handle);
+        task.await.unwrap();
+    }
+
+    #[test]
+    fn addresses_are_not_resolved() {
+        assert_eq!(resolvable_host("vector.example"), Some("vector.example"));
+        assert_eq!(resolvable_host("10.0.0.1"), None);
+        assert_eq!(resolvable_host("[::1]"), None);
+    }
+}
@@ -1,13 +1,21 @@
-use std::task::{Context, Poll};
+use std::{
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    task::{Context, Poll},
+    time::Duration,
+};
 
-use futures::{future::BoxFuture, TryFutureExt};
+use chrono::Utc;
+use futures::future::BoxFuture;
 use http::Uri;
 use hyper::client::HttpConnector;
 use hyper_openssl::HttpsConnector;
 use hyper_proxy::ProxyConnector;
-use prost::Message;
 use proto_event::EventWrapper;
 use tonic::{body::BoxBody, IntoRequest};
+use uuid::Uuid;
 use vector_core::{
     buffers::Ackable, event::proto as proto_event, internal_event::EventsSent,
     stream::DriverResponse,
@@ -15,9 +23,20 @@ use vector_core::{
 
 use crate::{
     event::{EventFinalizers, EventStatus, Finalizable},
-    internal_events::EndpointBytesSent,
+    internal_events::{
+        EndpointBytesSent, VectorSinkDeltaResent, VectorSinkDeltaUnsupported,
+        VectorSinkEventsRejected, VectorSinkNotVector,
+    },
     proto::vector as proto_vector,
-    sinks::{util::uri, vector::v2::VectorSinkError},
+    sinks::{
+        util::{event_age::EventAges, uri},
+        vector::v2::{
+            delta::KeyedPayload,
+            gauges::{AwaitingRetry, SinkGauges},
+            resolve::ClientHandle,
+            VectorSinkError,
+        },
+    },
     Error,
 };
 
@@ -26,6 +45,35 @@ pub struct VectorService {
     pub client: proto_vector::Client<HyperSvc>,
     pub protocol: String,
     pub endpoint: String,
+    /// Sent along with each request as its gRPC deadline, so the downstream
+    /// Vector can stop working on requests this sink has given up on.
+    pub timeout: Option<Duration>,
+    /// Sent along with each request to identify this Vector.
+    pub sender: Option<proto_vector::Sender>,
+    pub gauges: SinkGauges,
+    /// Whether the remote answered a push with `Unimplemented`. It is only
+    /// logged the first time, since every other push fails the same way.
+    pub not_vector_reported: Arc<AtomicBool>,
+    /// Only set when the sink sends deltas.
+    pub delta: Option<DeltaStream>,
+}
+
+/// The deltas sent by a sink, which the downstream Vector keeps the baselines
+/// of apart from those of other sinks.
+#[derive(Clone, Debug)]
+pub struct DeltaStream {
+    id: String,
+    /// Cleared once the downstream Vector turns out not to take deltas.
+    active: Arc<AtomicBool>,
+}
+
+impl DeltaStream {
+    pub fn new(active: Arc<AtomicBool>) -> Self {
+        Self {
+            id: Uuid::new_v4().to_string(),
+            active,
+        }
+    }
 }
 
 pub struct VectorResponse {
@@ -49,13 +97,92 @@ impl DriverResponse for VectorResponse {
 #[derive(Clone, Default)]
 pub struct VectorRequest {
     pub events: Vec<EventWrapper>,
+    /// The chunks of events too large to send whole, each sent in a request
+    /// of its own after `events`.
+    pub chunks: Vec<proto_vector::EventChunk>,
+    /// How many events `chunks` are split from.
+    pub chunked_events: usize,
+    /// Log events sent under their key, in the request holding `events`.
+    pub keyed: Vec<KeyedPayload>,
     pub finalizers: EventFinalizers,
     pub events_byte_size: usize,
+    /// Only filled in when the sink reports the age of the events it delivers.
+    pub event_ages: EventAges,
+    pub awaiting_retry: AwaitingRetry,
+    /// How many of the events were restored from the spool rather than read from the input of
+    /// the sink, and so aren't acknowledged to it.
+    pub restored_events: usize,
+    /// The ids the spool keeps copies of the events under, only set when the sink has one.
+    pub spool_ids: Vec<u64>,
+    /// How many of the pushes of the request got through. It is shared with the copies the
+    /// request is retried with, so that a retry only sends the pushes that didn't.
+    pub pushed: Arc<AtomicUsize>,
+    /// How far the push of `keyed` got, shared like `pushed`, so that a retry
+    /// picks up at the push that failed.
+    pub keyed_stage: Arc<Mutex<KeyedStage>>,
+}
+
+/// The push of the keyed events of a request to send next. A push that gets
+/// through moves on to the next stage if the downstream Vector didn't take
+/// all of the keyed events.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum KeyedStage {
+    /// The keyed events as the sink encoded them, deltas included.
+    Encoded,
+    /// The keyed events whole, along with the rest of the request, after the
+    /// downstream Vector was missing the events their deltas are based on.
+    Whole,
+    /// The keyed events as plain events, after the downstream Vector dropped
+    /// them as keyed events.
+    Plain,
+}
+
+impl Default for KeyedStage {
+    fn default() -> Self {
+        Self::Encoded
+    }
+}
+
+impl VectorRequest {
+    /// The number of events in the request, whole, keyed or chunked.
+    pub fn events_count(&self) -> usize {
+        self.events.len() + self.keyed.len() + self.chunked_events
+    }
+
+    /// The requests to push, one for the whole events and `keyed`, taken out
+    /// of the request to be sent again if need be, and one for each chunk.
+    fn into_push_requests(
+        self,
+        sender: Option<proto_vector::Sender>,
+        delta_stream: &str,
+        keyed: &[KeyedPayload],
+    ) -> Vec<proto_vector::PushEventsRequest> {
+        let mut requests = Vec::with_capacity(1 + self.chunks.len());
+        if !self.events.is_empty() || !keyed.is_empty() || self.chunks.is_empty() {
+            requests.push(proto_vector::PushEventsRequest {
+                events: self.events,
+                sender: sender.clone(),
+                keyed_events: keyed.iter().map(|keyed| keyed.to_proto(false)).collect(),
+                delta_stream: delta_stream.to_owned(),
+                ..Default::default()
+            });
+        }
+        requests.extend(
+            self.chunks
+                .into_iter()
+                .map(|chunk| proto_vector::PushEventsRequest {
+                    sender: sender.clone(),
+                    chunks: vec![chunk],
+                    ..Default::default()
+                }),
+        );
+        requests
+    }
 }
 
 impl Ackable for VectorRequest {
     fn ack_size(&self) -> usize {
-        self.events.len()
+        self.events_count() - self.restored_events
     }
 }
 
@@ -66,19 +193,119 @@ impl Finalizable for VectorRequest {
 }
 
 impl VectorService {
-    pub fn new(
-        hyper_client: hyper::Client<ProxyConnector<HttpsConnector<HttpConnector>>, BoxBody>,
-        uri: Uri,
-    ) -> Self {
+    pub fn new(hyper_client: impl Into<ClientHandle>, uri: Uri) -> Self {
         let (protocol, endpoint) = uri::protocol_endpoint(uri.clone());
         let proto_client = proto_vector::Client::new(HyperSvc {
             uri,
-            client: hyper_client,
+            client: hyper_client.into(),
         });
         Self {
             client: proto_client,
             protocol,
             endpoint,
+            timeout: None,
+            sender: None,
+            gauges: SinkGauges::default(),
+            not_vector_reported: Arc::default(),
+            delta: None,
+        }
+    }
+
+    /// Push a single request, giving it the deadline of the sink.
+    async fn push(
+        &mut self,
+        request: proto_vector::PushEventsRequest,
+    ) -> Result<proto_vector::PushEventsResponse, VectorSinkError> {
+        let byte_size = request.wire_size();
+        let mut request = request.into_request();
+        if let Some(timeout) = self.timeout {
+            request.set_timeout(timeout);
+        }
+        let response = self.client.push_events(request).await.map_err(|source| {
+            if source.code() == tonic::Code::Unimplemented
+                && !self.not_vector_reported.swap(true, Ordering::Relaxed)
+            {
+                emit!(&VectorSinkNotVector {
+                    endpoint: &self.endpoint,
+                });
+            }
+            VectorSinkError::Request { source }
+        })?;
+        emit!(&EndpointBytesSent {
+            byte_size,
+            protocol: &self.protocol,
+            endpoint: &self.endpoint,
+        });
+        let response = response.into_inner();
+        if response.rejected_events > 0 {
+            emit!(&VectorSinkEventsRejected {
+                count: response.rejected_events as usize,
+                endpoint: &self.endpoint,
+            });
+        }
+        Ok(response)
+    }
+
+    /// Push the request holding `keyed`, sending the keyed events whole if
+    /// the downstream Vector is missing the events their deltas are based on,
+    /// and as plain events if it turns out not to know about keyed events at
+    /// all, in which case no more deltas are sent.
+    ///
+    /// Each of these pushes is retried on its own: `stage` records the one
+    /// to send, and only moves on once it got through.
+    async fn push_keyed(
+        &mut self,
+        request: proto_vector::PushEventsRequest,
+        keyed: Vec<KeyedPayload>,
+        stage: &Mutex<KeyedStage>,
+    ) -> Result<(), VectorSinkError> {
+        let set_stage = |next| *stage.lock().expect("mutex poisoned") = next;
+        loop {
+            let current = *stage.lock().expect("mutex poisoned");
+            let response = match current {
+                KeyedStage::Encoded => match self.push(request.clone()).await {
+                    Err(VectorSinkError::Request { source })
+                        if source.code() == tonic::Code::FailedPrecondition
+                            && source
+                                .metadata()
+                                .contains_key(proto_vector::DELTA_BASELINE_MISSING_KEY) =>
+                    {
+                        emit!(&VectorSinkDeltaResent { count: keyed.len() });
+                        set_stage(KeyedStage::Whole);
+                        continue;
+                    }
+                    result => result?,
+                },
+                KeyedStage::Whole => {
+                    self.push(proto_vector::PushEventsRequest {
+                        keyed_events: keyed.iter().map(|keyed| keyed.to_proto(true)).collect(),
+                        ..request.clone()
+                    })
+                    .await?
+                }
+                KeyedStage::Plain => {
+                    self.push(proto_vector::PushEventsRequest {
+                        events: keyed.into_iter().map(|keyed| keyed.whole).collect(),
+                        sender: self.sender.clone(),
+                        ..Default::default()
+                    })
+                    .await?;
+                    return Ok(());
+                }
+            };
+
+            if (response.keyed_events as usize) >= keyed.len() {
+                return Ok(());
+            }
+            // Only the keyed events were dropped, the rest of the request got through.
+            if let Some(delta) = &self.delta {
+                if delta.active.swap(false, Ordering::Relaxed) {
+                    emit!(&VectorSinkDeltaUnsupported {
+                        endpoint: self.endpoint.clone(),
+                    });
+                }
+            }
+            set_stage(KeyedStage::Plain);
         }
     }
 }
@@ -97,41 +324,52 @@ impl tower::Service<VectorRequest> for VectorService {
         Poll::Ready(Ok(()))
     }
 
-    fn call(&mut self, list: VectorRequest) -> Self::Future {
+    fn call(&mut self, mut list: VectorRequest) -> Self::Future {
         let mut service = self.clone();
-        let events_count = list.events.len();
+        let events_count = list.events_count();
+        let event_ages = std::mem::take(&mut list.event_ages);
+        let attempt = service
+            .gauges
+            .start_attempt(events_count, &list.awaiting_retry);
 
-        let request = proto_vector::PushEventsRequest {
-            events: list.events,
-        };
-        let byte_size = request.encoded_len();
+        let mut keyed = std::mem::take(&mut list.keyed);
+        let pushed = Arc::clone(&list.pushed);
+        let keyed_stage = Arc::clone(&list.keyed_stage);
+        let delta_stream = service.delta.as_ref().map_or("", |delta| delta.id.as_str());
+        let requests = list.into_push_requests(service.sender.clone(), delta_stream, &keyed);
         let future = async move {
-            service
-                .client
-                .push_events(request.into_request())
-                .map_ok(|_response| {
-                    emit!(&EndpointBytesSent {
-                        byte_size,
-                        protocol: &service.protocol,
-                        endpoint: &service.endpoint,
-                    });
-                    VectorResponse {
-                        events_count,
-                        events_byte_size: 0,
-                    }
-                })
-                .map_err(|source| VectorSinkError::Request { source }.into())
-                .await
+            // The chunks of an event are sent one after the other, so the
+            // request holding the last one is only sent once the others got
+            // through. Those that got through in an earlier attempt aren't
+            // sent again.
+            for request in requests.into_iter().skip(pushed.load(Ordering::Acquire)) {
+                if request.keyed_events.is_empty() {
+                    service.push(request).await?;
+                } else {
+                    service
+                        .push_keyed(request, std::mem::take(&mut keyed), &keyed_stage)
+                        .await?;
+                }
+                pushed.fetch_add(1, Ordering::AcqRel);
+            }
+            event_ages.emit(Utc::now());
+            attempt.succeeded();
+            Ok::<_, Error>(VectorResponse {
+                events_count,
+                events_byte_size: 0,
+            })
         };
 
         Box::pin(future)
     }
 }
 
+pub type HyperClient = hyper::Client<ProxyConnector<HttpsConnector<HttpConnector>>, BoxBody>;
+
 #[derive(Clone, Debug)]
 pub struct HyperSvc {
     uri: Uri,
-    client: hyper::Client<ProxyConnector<HttpsConnector<HttpConnector>>, BoxBody>,
+    client: ClientHandle,
 }
 
 impl tower::Service<hyper::Request<BoxBody>> for HyperSvc {
@@ -153,6 +391,6 @@ impl tower::Service<hyper::Request<BoxBody>> for HyperSvc {
 
         *req.uri_mut() = uri;
 
-        Box::pin(self.client.request(req))
+        Box::pin(self.client.get().request(req))
     }
 }
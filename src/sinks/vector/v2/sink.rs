@@ -1,55 +1,767 @@
+use std::{
+    collections::HashSet,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
 use async_trait::async_trait;
-use futures::{stream::BoxStream, StreamExt};
+use futures::{
+    future,
+    stream::{self, BoxStream},
+    StreamExt,
+};
 use prost::Message;
+use snafu::Snafu;
 use tower::util::BoxService;
+use uuid::Uuid;
 use vector_core::{buffers::Acker, stream::BatcherSettings, ByteSizeOf};
 
 use crate::{
-    event::{proto::EventWrapper, Event, EventFinalizers, Finalizable},
+    common::drop_summary::{DropReason, DropReporter},
+    event::{
+        error::CompressionError, metric::MetricSeries, proto::EventWrapper, Event, EventFinalizers,
+        EventStatus, ImmutableEvent, Value,
+    },
+    internal_events::{
+        EventSizeObserved, VectorEventDiscardedOversized, VectorEventEncodeFailed,
+        VectorEventSampledOut, VectorEventTruncated, VectorMetricValueImprecise,
+    },
+    proto::vector::EventChunk,
     sinks::{
-        util::{SinkBuilderExt, StreamSink},
-        vector::v2::service::{VectorRequest, VectorResponse},
+        util::{event_age::EventAges, sampler::Sampler, SinkBuilderExt, StreamSink},
+        vector::v2::{
+            config::OversizedEventAction,
+            delta::{DeltaEncoder, KeyedPayload},
+            fields::FieldFilter,
+            gauges::{GaugedService, ShutdownGuard, SinkGauges},
+            service::{VectorRequest, VectorResponse},
+            spool::{Spool, SpoolGuard, SpoolService},
+        },
     },
     Error,
 };
 
+/// The most series remembered as having been warned about by `ImpreciseSeries`. Series beyond this
+/// are warned about every time, subject to the usual rate limiting of internal logs.
+const MAX_IMPRECISE_SERIES: usize = 10_000;
+
+/// Warns once per series about counter and gauge values that may not be the exact integers they
+/// were created from, as the downstream Vector can't tell either.
+#[derive(Default)]
+struct ImpreciseSeries {
+    warned: HashSet<MetricSeries>,
+}
+
+impl ImpreciseSeries {
+    fn check(&mut self, event: &Event) {
+        let metric = match event {
+            Event::Metric(metric) if metric.value().exceeds_exact_integer_range() => metric,
+            _ => return,
+        };
+        if self.warned.contains(metric.series()) {
+            return;
+        }
+
+        emit!(&VectorMetricValueImprecise {
+            name: metric.name(),
+        });
+        if self.warned.len() < MAX_IMPRECISE_SERIES {
+            self.warned.insert(metric.series().clone());
+        }
+    }
+}
+
+/// Why an event couldn't be encoded. Events that are dropped on purpose, such as oversized events
+/// with `oversized_event_action = "drop"`, are filtered out instead.
+#[derive(Debug, Snafu)]
+enum EncodeError {
+    #[snafu(display(
+        "Encoded event of {} bytes could not be truncated to max_event_bytes ({}).",
+        byte_size,
+        max_event_bytes
+    ))]
+    Untruncatable {
+        byte_size: usize,
+        max_event_bytes: usize,
+    },
+    #[snafu(display(
+        "Compressed field could not be restored for a downstream Vector that doesn't keep it compressed: {}",
+        source
+    ))]
+    Decompress { source: CompressionError },
+}
+
+impl EncodeError {
+    const fn reason(&self) -> &'static str {
+        match self {
+            Self::Untruncatable { .. } => "untruncatable",
+            Self::Decompress { .. } => "decompress_failed",
+        }
+    }
+}
+
 struct EventData {
     byte_size: usize,
+    encoded_size: usize,
     finalizers: EventFinalizers,
-    wrapper: EventWrapper,
+    payload: Payload,
+    event_ages: EventAges,
+    /// Whether the event was restored from the spool rather than read from the input of the sink.
+    restored: bool,
+    /// The id the spool keeps a copy of the event under, if it does.
+    spool_id: Option<u64>,
+}
+
+/// An encoded event, whole, split into chunks or sent under its key.
+enum Payload {
+    Whole(EventWrapper),
+    Chunked(Vec<EventChunk>),
+    Keyed(KeyedPayload),
+}
+
+impl EventData {
+    /// Split the event into chunks of `chunk_bytes` if its encoded form is
+    /// larger than that.
+    fn chunked(mut self, chunk_bytes: usize) -> Self {
+        if let Payload::Whole(wrapper) = &self.payload {
+            if self.encoded_size > chunk_bytes {
+                self.payload = Payload::Chunked(chunk_event(wrapper, chunk_bytes));
+            }
+        }
+        self
+    }
+
+    /// Send the event under `key`, as a delta if `encoder` allows. Chunked
+    /// events are left as they are.
+    fn keyed(mut self, key: String, encoder: &mut DeltaEncoder) -> Self {
+        self.payload = match self.payload {
+            Payload::Whole(wrapper) => match encoder.encode(key, wrapper) {
+                Ok(keyed) => {
+                    self.encoded_size = keyed.encoded_len();
+                    Payload::Keyed(keyed)
+                }
+                Err(wrapper) => Payload::Whole(wrapper),
+            },
+            payload => payload,
+        };
+        self
+    }
+}
+
+/// Split the encoded form of an event into chunks of at most `chunk_bytes`,
+/// under an id of its own.
+fn chunk_event(wrapper: &EventWrapper, chunk_bytes: usize) -> Vec<EventChunk> {
+    let encoded = wrapper.encode_to_vec();
+    let event_id = Uuid::new_v4().to_string();
+    let total = encoded.chunks(chunk_bytes).len() as u32;
+    encoded
+        .chunks(chunk_bytes)
+        .enumerate()
+        .map(|(index, data)| EventChunk {
+            event_id: event_id.clone(),
+            index: index as u32,
+            total,
+            data: data.to_vec(),
+        })
+        .collect()
 }
 
 pub struct VectorSink {
     pub batch_settings: BatcherSettings,
     pub service: BoxService<VectorRequest, VectorResponse, Error>,
     pub acker: Acker,
+    pub max_event_bytes: Option<usize>,
+    pub oversized_event_action: OversizedEventAction,
+    pub event_age_histograms: bool,
+    /// Taken from `telemetry.event_size_histograms` of the global options.
+    pub event_size_histograms: bool,
+    /// Set once the downstream Vector said it keeps fields compressed. Until then, compressed
+    /// fields are restored before they are sent.
+    pub compressed_fields_accepted: Arc<AtomicBool>,
+    /// Set when events larger than this are sent in chunks.
+    pub chunk_bytes: Option<usize>,
+    /// Only set when log events are sent as deltas.
+    pub delta_encoder: Option<DeltaEncoder>,
+    /// Only set when fields of log events are left out or changed.
+    pub field_filter: Option<FieldFilter>,
+    /// Only set when log events are sampled.
+    pub sampler: Option<Sampler>,
+    pub gauges: SinkGauges,
+    /// How often the events dropped are logged.
+    pub drop_summary_interval: Duration,
+    /// Only set when the events left undelivered when the sink stops are spooled.
+    pub spool: Option<Spool>,
 }
 
 impl VectorSink {
-    async fn run_inner(self: Box<Self>, input: BoxStream<'_, Event>) -> Result<(), ()> {
-        input
-            .map(|mut event| EventData {
-                byte_size: event.size_of(),
-                finalizers: event.take_finalizers(),
-                wrapper: EventWrapper::from(event),
+    /// The events are only read, so they are encoded by reference rather than taken apart.
+    async fn run_inner(self: Box<Self>, input: BoxStream<'_, ImmutableEvent>) -> Result<(), ()> {
+        let _shutdown_guard = ShutdownGuard::new(self.gauges.clone());
+        let gauges = self.gauges.clone();
+        gauges.spawn_publisher();
+        let max_event_bytes = self.max_event_bytes;
+        let oversized_event_action = self.oversized_event_action;
+        let event_age_histograms = self.event_age_histograms;
+        let event_size_histograms = self.event_size_histograms;
+        let compressed_fields_accepted = self.compressed_fields_accepted;
+        let chunk_bytes = self.chunk_bytes;
+        let mut delta_encoder = self.delta_encoder;
+        let field_filter = self.field_filter;
+        let mut sampler = self.sampler;
+        let mut imprecise_series = ImpreciseSeries::default();
+        let drops = DropReporter::default();
+        drops.spawn(self.drop_summary_interval);
+        let spool = self.spool.clone();
+        // Dropped before the shutdown guard, so that the events it spools aren't counted as
+        // dropped.
+        let _spool_guard = spool
+            .clone()
+            .map(|spool| SpoolGuard::new(spool, gauges.clone()));
+        let restored = match &spool {
+            Some(spool) => spool.restore().await,
+            None => Vec::new(),
+        };
+        // The spooled events are sent ahead of new ones, under the ids the spool restored them
+        // with.
+        stream::iter(restored)
+            .map(|(id, event)| (ImmutableEvent::from(event), Some(id)))
+            .chain(input.map(|event| (event, None)))
+            .filter_map(move |(event, restored_id)| {
+                let restored = restored_id.is_some();
+                imprecise_series.check(event.as_event());
+                // Spooled events were sampled and had their fields picked before they were spooled.
+                let sample_rate = match &mut sampler {
+                    Some(sampler) if !restored => {
+                        let decision = sampler.should_keep(event.as_event());
+                        if !decision.keep {
+                            // Dropped on purpose, so the event counts as delivered.
+                            emit!(&VectorEventSampledOut {
+                                rate: decision.rate
+                            });
+                            return future::ready(None);
+                        }
+                        Some((sampler.rate_field(), decision.rate))
+                    }
+                    _ => None,
+                };
+                let mut event_ages = EventAges::default();
+                if event_age_histograms {
+                    event_ages.push(event.metadata());
+                }
+                let event_id = event.metadata().event_id();
+                let key = delta_encoder
+                    .as_ref()
+                    .and_then(|encoder| encoder.key_of(event.as_event()));
+                // The pruned copy shares the finalizers of the event.
+                let event = match field_filter
+                    .as_ref()
+                    .filter(|_| !restored)
+                    .and_then(|filter| filter.apply(&event))
+                {
+                    Some(pruned) => ImmutableEvent::from(pruned),
+                    None => event,
+                };
+                let event = match sample_rate {
+                    Some((field, rate)) => with_sample_rate(event, field, rate),
+                    None => event,
+                };
+                let decompress_fields = !compressed_fields_accepted.load(Ordering::Relaxed);
+                let data = match encode_event(
+                    event,
+                    max_event_bytes,
+                    oversized_event_action,
+                    decompress_fields,
+                ) {
+                    Ok(Some(data)) => {
+                        if event_size_histograms {
+                            emit!(&EventSizeObserved {
+                                byte_size: data.encoded_size
+                            });
+                        }
+                        let spool_id = restored_id.or_else(|| match (&spool, &data.payload) {
+                            (Some(spool), Payload::Whole(wrapper)) => {
+                                Some(spool.track(wrapper.clone()))
+                            }
+                            _ => None,
+                        });
+                        let data = EventData {
+                            event_ages,
+                            restored,
+                            spool_id,
+                            ..data
+                        };
+                        let data = match chunk_bytes {
+                            Some(chunk_bytes) => data.chunked(chunk_bytes),
+                            None => data,
+                        };
+                        Some(match (key, &mut delta_encoder) {
+                            (Some(key), Some(encoder)) => data.keyed(key, encoder),
+                            _ => data,
+                        })
+                    }
+                    Ok(None) => {
+                        drops.report(
+                            DropReason::Oversized,
+                            event_id,
+                            "Encoded event is larger than max_event_bytes.",
+                        );
+                        None
+                    }
+                    Err(error) => {
+                        // Only this event is lost, the rest of the stream is batched as usual.
+                        let message = error.to_string();
+                        emit!(&VectorEventEncodeFailed {
+                            error: &message,
+                            reason: error.reason(),
+                            event_id,
+                        });
+                        drops.report(DropReason::EncodeFailed, event_id, &message);
+                        None
+                    }
+                };
+                if data.is_some() {
+                    gauges.event_buffered();
+                } else if let (Some(spool), Some(id)) = (&spool, restored_id) {
+                    // Its file is quarantined, as it would be if the downstream Vector rejected
+                    // the event.
+                    spool.finish(&[id], EventStatus::Rejected);
+                }
+                future::ready(data)
             })
             .batched(self.batch_settings.into_reducer_config(
-                |data: &EventData| data.wrapper.encoded_len(),
+                |data: &EventData| data.encoded_size,
                 |req: &mut VectorRequest, item: EventData| {
                     req.events_byte_size += item.byte_size;
                     req.finalizers.merge(item.finalizers);
-                    req.events.push(item.wrapper);
+                    match item.payload {
+                        Payload::Whole(wrapper) => req.events.push(wrapper),
+                        Payload::Chunked(chunks) => {
+                            req.chunks.extend(chunks);
+                            req.chunked_events += 1;
+                        }
+                        Payload::Keyed(keyed) => req.keyed.push(keyed),
+                    }
+                    req.event_ages.merge(item.event_ages);
+                    req.restored_events += usize::from(item.restored);
+                    req.spool_ids.extend(item.spool_id);
                 },
             ))
-            .into_driver(self.service, self.acker)
+            .into_driver(
+                SpoolService::new(GaugedService::new(self.service, self.gauges), self.spool),
+                self.acker,
+            )
             .run()
             .await
     }
 }
 
+/// Record the rate a log event was sampled at in `field`, so that the downstream can scale what it
+/// counts back up. The event is copied first if it is shared with other sinks.
+fn with_sample_rate(event: ImmutableEvent, field: &str, rate: f64) -> ImmutableEvent {
+    if !matches!(event.as_event(), Event::Log(_)) {
+        return event;
+    }
+    let mut event = event.into_owned();
+    event.as_mut_log().insert(field, rate);
+    event.into()
+}
+
+/// The event with its compressed fields restored, for a downstream Vector that
+/// would take their compressed bytes as the values.
+fn with_fields_decompressed(event: ImmutableEvent) -> Result<ImmutableEvent, CompressionError> {
+    let compressed = matches!(
+        event.as_event(),
+        Event::Log(log) if log.compressed_fields().next().is_some()
+    );
+    if !compressed {
+        return Ok(event);
+    }
+    let mut event = event.into_owned();
+    event.as_mut_log().decompress_fields()?;
+    Ok(event.into())
+}
+
+/// Convert an event to its protobuf form, or `None` if it is filtered out.
+///
+/// When `max_event_bytes` is set, events whose encoded form is larger are
+/// truncated or rejected here, as a request holding them could never succeed.
+/// Events that are filtered out or fail to encode are marked as rejected.
+///
+/// The event is encoded without being copied first, even if it is shared with
+/// other sinks, and the request shares its finalizers with them. Only events
+/// with compressed fields are copied when `decompress_fields` is set, to
+/// restore those fields.
+fn encode_event(
+    event: ImmutableEvent,
+    max_event_bytes: Option<usize>,
+    oversized_event_action: OversizedEventAction,
+    decompress_fields: bool,
+) -> Result<Option<EventData>, EncodeError> {
+    let byte_size = event.size_of();
+    let event_id = event.metadata().event_id();
+    let finalizers = event.finalizers();
+    let event = if decompress_fields {
+        match with_fields_decompressed(event) {
+            Ok(event) => event,
+            Err(source) => {
+                finalizers.update_status(EventStatus::Rejected);
+                return Err(EncodeError::Decompress { source });
+            }
+        }
+    } else {
+        event
+    };
+    let mut wrapper = EventWrapper::from(event.as_event());
+    let mut encoded_size = wrapper.encoded_len();
+
+    if let Some(max_event_bytes) = max_event_bytes {
+        if encoded_size > max_event_bytes {
+            if oversized_event_action == OversizedEventAction::Truncate {
+                let mut event = Event::from(wrapper);
+                let truncated = truncate_largest_field(&mut event, encoded_size - max_event_bytes);
+                wrapper = EventWrapper::from(event);
+                if let Some(field) = truncated {
+                    emit!(&VectorEventTruncated {
+                        field: &field,
+                        byte_size: encoded_size,
+                        max_event_bytes,
+                    });
+                }
+                encoded_size = wrapper.encoded_len();
+            }
+
+            if encoded_size > max_event_bytes {
+                finalizers.update_status(EventStatus::Rejected);
+                if oversized_event_action == OversizedEventAction::Truncate {
+                    return Err(EncodeError::Untruncatable {
+                        byte_size: encoded_size,
+                        max_event_bytes,
+                    });
+                }
+                emit!(&VectorEventDiscardedOversized {
+                    byte_size: encoded_size,
+                    max_event_bytes,
+                    event_id,
+                });
+                return Ok(None);
+            }
+        }
+    }
+
+    Ok(Some(EventData {
+        byte_size,
+        encoded_size,
+        finalizers,
+        payload: Payload::Whole(wrapper),
+        event_ages: EventAges::default(),
+        restored: false,
+        spool_id: None,
+    }))
+}
+
+/// Shorten the largest bytes field of a log event by at least `excess` bytes,
+/// returning the path of the field. Text is cut at a character boundary so
+/// that it stays valid UTF-8. Nothing is changed if that field isn't long
+/// enough, or for metrics, which have no free-form bytes fields.
+fn truncate_largest_field(event: &mut Event, excess: usize) -> Option<String> {
+    let log = match event {
+        Event::Log(log) => log,
+        Event::Metric(_) => return None,
+    };
+
+    // The path is kept as its components, so that fields whose names hold
+    // `.` or `[` are found again as they are.
+    let (path, bytes) = log
+        .paths_with_values()
+        .filter_map(|(path, value)| match value {
+            Value::Bytes(bytes) => Some((path, bytes.clone())),
+            _ => None,
+        })
+        .max_by_key(|(_, bytes)| bytes.len())?;
+    if bytes.len() < excess {
+        return None;
+    }
+
+    let max_len = bytes.len() - excess;
+    let len = match std::str::from_utf8(&bytes) {
+        Ok(string) => (0..=max_len)
+            .rev()
+            .find(|len| string.is_char_boundary(*len))
+            .unwrap_or(0),
+        Err(_) => max_len,
+    };
+    let field = path.to_string();
+    log.insert_checked(&path, Value::Bytes(bytes.slice(..len)))
+        .ok()?;
+    Some(field)
+}
+
 #[async_trait]
 impl StreamSink for VectorSink {
     async fn run(self: Box<Self>, input: BoxStream<'_, Event>) -> Result<(), ()> {
-        self.run_inner(input).await
+        self.run_inner(input.map(ImmutableEvent::from).boxed())
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use vector_core::event::{
+        BatchNotifier, BatchStatus, FieldCodec, LogEvent, Metric, MetricKind, MetricValue,
+    };
+
+    use super::*;
+
+    fn log_event(fields: &[(&str, usize)]) -> Event {
+        let mut log = LogEvent::default();
+        for (field, len) in fields {
+            log.insert(*field, "x".repeat(*len));
+        }
+        log.into()
+    }
+
+    fn encoded_len(event: Event) -> usize {
+        EventWrapper::from(event).encoded_len()
+    }
+
+    #[test]
+    fn imprecise_series_warned_once() {
+        let counter = |name: &str, value| {
+            Event::Metric(Metric::new(
+                name,
+                MetricKind::Absolute,
+                MetricValue::Counter { value },
+            ))
+        };
+        let mut imprecise = ImpreciseSeries::default();
+
+        imprecise.check(&counter("exact", 1.0));
+        imprecise.check(&log_event(&[("message", 10)]));
+        assert!(imprecise.warned.is_empty());
+
+        let value = vector_core::event::metric::MAX_EXACT_INTEGER_VALUE;
+        imprecise.check(&counter("large", value));
+        imprecise.check(&counter("large", value * 2.0));
+        imprecise.check(&counter("larger", value * 4.0));
+        assert_eq!(imprecise.warned.len(), 2);
+    }
+
+    #[test]
+    fn encode_event_without_limit() {
+        let event = log_event(&[("message", 10_000)]);
+        let data = encode_event(
+            event.clone().into(),
+            None,
+            OversizedEventAction::Drop,
+            false,
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(data.encoded_size, encoded_len(event));
+    }
+
+    #[test]
+    fn encode_event_restores_compressed_fields_unless_accepted() {
+        let mut event = log_event(&[("message", 1_000)]);
+        let original = event.as_log()["message"].clone();
+        event
+            .as_mut_log()
+            .compress_field("message", FieldCodec::Zstd)
+            .unwrap();
+
+        for decompress_fields in [true, false] {
+            let data = encode_event(
+                event.clone().into(),
+                None,
+                OversizedEventAction::Drop,
+                decompress_fields,
+            )
+            .unwrap()
+            .unwrap();
+            let sent = match data.payload {
+                Payload::Whole(wrapper) => Event::from(wrapper),
+                _ => panic!("event was not sent whole"),
+            };
+            let compressed = sent.as_log().compressed_fields().count() == 1;
+            assert_eq!(compressed, !decompress_fields);
+            assert_eq!(sent.as_log()["message"] == original, decompress_fields);
+        }
+    }
+
+    #[test]
+    fn encode_event_drops_oversized() {
+        let (batch, mut receiver) = BatchNotifier::new_with_receiver();
+        let event = log_event(&[("message", 1_000)]).with_batch_notifier(&batch);
+        drop(batch);
+
+        assert!(
+            encode_event(event.into(), Some(500), OversizedEventAction::Drop, false)
+                .unwrap()
+                .is_none()
+        );
+        assert_eq!(receiver.try_recv(), Ok(BatchStatus::Rejected));
+    }
+
+    #[test]
+    fn encode_event_keeps_events_that_fit() {
+        let event = log_event(&[("message", 100)]);
+        let max = encoded_len(event.clone());
+
+        let data = encode_event(event.into(), Some(max), OversizedEventAction::Drop, false)
+            .unwrap()
+            .unwrap();
+        assert_eq!(data.encoded_size, max);
+    }
+
+    #[test]
+    fn encode_event_truncates_largest_field() {
+        let event = log_event(&[("message", 1_000), ("host", 100)]);
+        let max = encoded_len(event.clone()) - 300;
+
+        let data = encode_event(
+            event.into(),
+            Some(max),
+            OversizedEventAction::Truncate,
+            false,
+        )
+        .unwrap()
+        .unwrap();
+        assert!(data.encoded_size <= max);
+
+        let event = match data.payload {
+            Payload::Whole(wrapper) => Event::from(wrapper),
+            _ => panic!("event should be whole"),
+        };
+        let log = event.as_log();
+        assert_eq!(log["message"].as_bytes().len(), 700);
+        assert_eq!(log["host"].as_bytes().len(), 100);
+    }
+
+    #[test]
+    fn truncate_largest_field_keeps_text_valid() {
+        let mut log = LogEvent::default();
+        log.insert_flat("a.b", "é".repeat(10));
+        log.insert("host", "x");
+        let mut event = Event::Log(log);
+
+        // Cutting 3 bytes off the 20 of the accents lands within one of them.
+        let field = truncate_largest_field(&mut event, 3).unwrap();
+        let value = event.as_log().get_flat("a.b").unwrap().as_bytes();
+        assert_eq!(std::str::from_utf8(&value).unwrap(), "é".repeat(8));
+        assert_eq!(event.as_log().get("a.b"), None);
+        assert_eq!(field, r#""a.b""#);
+    }
+
+    #[test]
+    fn encode_event_truncate_fails_what_cannot_fit() {
+        let event = log_event(&[("message", 200), ("host", 200)]);
+        let max = encoded_len(event.clone()) - 300;
+        let (batch, mut receiver) = BatchNotifier::new_with_receiver();
+        let event = event.with_batch_notifier(&batch);
+        drop(batch);
+        let error = encode_event(
+            event.into(),
+            Some(max),
+            OversizedEventAction::Truncate,
+            false,
+        )
+        .err()
+        .unwrap();
+        assert_eq!(error.reason(), "untruncatable");
+        assert_eq!(receiver.try_recv(), Ok(BatchStatus::Rejected));
+
+        let metric = Event::Metric(Metric::new(
+            "requests",
+            MetricKind::Incremental,
+            MetricValue::Counter { value: 1.0 },
+        ));
+        let max = encoded_len(metric.clone()) - 1;
+        assert!(encode_event(
+            metric.into(),
+            Some(max),
+            OversizedEventAction::Truncate,
+            false
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn encode_event_leaves_shared_events_as_they_are() {
+        let (batch, mut receiver) = BatchNotifier::new_with_receiver();
+        let event =
+            ImmutableEvent::from(log_event(&[("message", 1_000)]).with_batch_notifier(&batch));
+        drop(batch);
+        let other = event.clone();
+        let max = encoded_len(other.as_event().clone()) - 300;
+
+        let data = encode_event(event, Some(max), OversizedEventAction::Truncate, false)
+            .unwrap()
+            .unwrap();
+        assert!(data.encoded_size <= max);
+        assert_eq!(other.get("message").unwrap().as_bytes().len(), 1_000);
+        assert!(other.is_unique());
+
+        // The batch is only notified once the other sink is done with the event as well.
+        data.finalizers.update_status(EventStatus::Delivered);
+        drop(data);
+        assert!(receiver.try_recv().is_err());
+        drop(other);
+        assert_eq!(receiver.try_recv(), Ok(BatchStatus::Delivered));
+    }
+
+    #[test]
+    fn sample_rate_recorded_on_copies_of_shared_logs() {
+        let event = ImmutableEvent::from(log_event(&[("message", 10)]));
+        let other = event.clone();
+        let sampled = with_sample_rate(event, "sampling.rate", 4.0);
+        assert_eq!(sampled.get("sampling.rate"), Some(&Value::from(4.0)));
+        assert!(!other.contains("sampling"));
+
+        let metric = ImmutableEvent::from(Event::Metric(Metric::new(
+            "requests",
+            MetricKind::Incremental,
+            MetricValue::Counter { value: 1.0 },
+        )));
+        let sampled = with_sample_rate(metric.clone(), "sampling.rate", 4.0);
+        assert_eq!(sampled.as_event(), metric.as_event());
+    }
+
+    #[test]
+    fn large_events_are_chunked() {
+        let event = log_event(&[("message", 10_000)]);
+        let wrapper = EventWrapper::from(event.clone());
+        let data = encode_event(
+            event.clone().into(),
+            None,
+            OversizedEventAction::Drop,
+            false,
+        )
+        .unwrap()
+        .unwrap();
+
+        let chunks = match data.chunked(4096).payload {
+            Payload::Chunked(chunks) => chunks,
+            _ => panic!("event should be chunked"),
+        };
+        assert_eq!(chunks.len(), 3);
+        let mut encoded = Vec::new();
+        for (index, chunk) in chunks.iter().enumerate() {
+            assert_eq!(chunk.event_id, chunks[0].event_id);
+            assert_eq!(chunk.index, index as u32);
+            assert_eq!(chunk.total, 3);
+            assert!(chunk.data.len() <= 4096);
+            encoded.extend_from_slice(&chunk.data);
+        }
+        assert_eq!(EventWrapper::decode(encoded.as_slice()).unwrap(), wrapper);
+
+        // Events that fit are left whole.
+        let data = encode_event(event.into(), None, OversizedEventAction::Drop, false)
+            .unwrap()
+            .unwrap();
+        let max = data.encoded_size;
+        assert!(matches!(data.chunked(max).payload, Payload::Whole(_)));
     }
 }
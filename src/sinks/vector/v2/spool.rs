@@ -0,0 +1,652 @@
+//! Writing the events the sink couldn't deliver before it stopped to disk, and sending them again
+//! ahead of new events once it starts back up.
+//!
+//! Events are spooled in their protobuf encoding, as the downstream Vector would have received
+//! them, so that their event id and the fields added by newer Vectors are kept along with their
+//! fields.
+
+use std::{
+    collections::BTreeMap,
+    fs::{self, File},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use bytes::Buf;
+use futures::{future::BoxFuture, FutureExt};
+use prost::Message;
+use serde::{Deserialize, Serialize};
+use tower::Service;
+use uuid::Uuid;
+use vector_core::stream::DriverResponse;
+
+use crate::{
+    event::{proto::EventWrapper, Event, EventStatus},
+    internal_events::{
+        VectorSinkEventsSpooled, VectorSinkSpoolFailed, VectorSinkSpoolFull,
+        VectorSinkSpoolQuarantined, VectorSinkSpoolRestored,
+    },
+    sinks::vector::v2::{gauges::SinkGauges, service::VectorRequest},
+};
+
+/// Written at the start of every spool file, to tell them from other files.
+const MAGIC: &[u8] = b"vector-spool-1\n";
+const EXTENSION: &str = "spool";
+/// Where the files that can't be sent are moved to, within the spool directory, for an operator to
+/// look into.
+const QUARANTINE_DIR: &str = "quarantine";
+
+const fn default_max_bytes() -> u64 {
+    100 * 1024 * 1024
+}
+
+/// Spooling the events the sink couldn't deliver before it stopped, such as those held up by an
+/// unreachable downstream Vector when it is restarted, to send them once it starts again.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct SpoolConfig {
+    /// The directory the events are spooled to. It must not be shared with other sinks.
+    pub directory: PathBuf,
+    /// The most bytes the spool files may take up together. The events that don't fit are dropped
+    /// when the sink stops, and the files that don't are quarantined when it starts.
+    #[serde(default = "default_max_bytes")]
+    pub max_bytes: u64,
+}
+
+impl SpoolConfig {
+    pub fn build(&self) -> Spool {
+        Spool {
+            directory: self.directory.clone(),
+            max_bytes: self.max_bytes,
+            pending: Arc::default(),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct Pending {
+    next_id: u64,
+    /// Copies of the events read from the input of the sink.
+    events: BTreeMap<u64, EventWrapper>,
+    /// The file each restored event that isn't done with yet was read from.
+    restored: BTreeMap<u64, PathBuf>,
+    /// The files events were restored from, until each of their events is done with.
+    files: BTreeMap<PathBuf, RestoredFile>,
+}
+
+/// How the events restored from a file have fared so far.
+#[derive(Debug)]
+struct RestoredFile {
+    remaining: usize,
+    status: EventStatus,
+}
+
+/// The spool of a sink. A copy of each event is kept from when it is encoded until the request
+/// holding it is done with, and the copies still kept when the sink stops are written to a file of
+/// their own. Restored events are tracked against the file they were read from instead, which is
+/// only settled once each of them is done with.
+#[derive(Clone, Debug)]
+pub struct Spool {
+    directory: PathBuf,
+    max_bytes: u64,
+    pending: Arc<Mutex<Pending>>,
+}
+
+impl Spool {
+    /// Keep a copy of an encoded event until the request holding it, which is given the returned id,
+    /// is done with.
+    pub fn track(&self, wrapper: EventWrapper) -> u64 {
+        let mut pending = self.pending.lock().expect("mutex poisoned");
+        let id = pending.next_id;
+        pending.next_id += 1;
+        pending.events.insert(id, wrapper);
+        id
+    }
+
+    /// Forget the events of a request that was delivered or failed for good. Once each event of a
+    /// restored file is done with, the file is settled by how they fared.
+    pub fn finish(&self, ids: &[u64], status: EventStatus) {
+        let mut settled = Vec::new();
+        {
+            let mut pending = self.pending.lock().expect("mutex poisoned");
+            for id in ids {
+                if pending.events.remove(id).is_some() {
+                    continue;
+                }
+                let path = match pending.restored.remove(id) {
+                    Some(path) => path,
+                    None => continue,
+                };
+                let file = pending
+                    .files
+                    .get_mut(&path)
+                    .expect("restored events are tracked along with their file");
+                file.status = file.status.update(status);
+                file.remaining -= 1;
+                if file.remaining == 0 {
+                    let status = file.status;
+                    pending.files.remove(&path);
+                    settled.push((path, status));
+                }
+            }
+        }
+
+        if !settled.is_empty() {
+            let spool = self.clone();
+            let span = tracing::Span::current();
+            tokio::task::spawn_blocking(move || {
+                let _enter = span.enter();
+                for (path, status) in settled {
+                    spool.settle(&path, status);
+                }
+            });
+        }
+    }
+
+    /// Read the spooled events back, oldest first, along with the ids to finish them under. Each
+    /// file is removed once all of its events are delivered, kept to be sent again on the next
+    /// start if some of them failed or weren't done with before the sink stopped, and quarantined
+    /// if some were rejected. Files that can't be read, or don't fit in `max_bytes` along with
+    /// those before them, are quarantined right away.
+    ///
+    /// The files are only ever removed whole, so the events of a file that was partly delivered
+    /// when the sink stopped, or when the process died, are sent again along with the others.
+    pub async fn restore(&self) -> Vec<(u64, Event)> {
+        let spool = self.clone();
+        let span = tracing::Span::current();
+        let restored = tokio::task::spawn_blocking(move || {
+            let _enter = span.enter();
+            spool.read_files()
+        })
+        .await;
+        match restored {
+            Ok(events) => events,
+            Err(error) => match error.try_into_panic() {
+                Ok(panic) => std::panic::resume_unwind(panic),
+                // Cancelled as the runtime shuts down, leaving the files for the next start.
+                Err(_) => Vec::new(),
+            },
+        }
+    }
+
+    fn read_files(&self) -> Vec<(u64, Event)> {
+        let files = match self.files() {
+            Ok(files) => files,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => return Vec::new(),
+            Err(error) => {
+                emit!(&VectorSinkSpoolFailed {
+                    path: &self.directory,
+                    error: &error,
+                });
+                return Vec::new();
+            }
+        };
+
+        let mut events = Vec::new();
+        let mut total_bytes = 0;
+        for (path, len) in files {
+            if total_bytes + len > self.max_bytes {
+                let error = format!(
+                    "File of {} bytes does not fit in max_bytes ({}).",
+                    len, self.max_bytes
+                );
+                self.quarantine(&path, "over_cap", &error);
+                continue;
+            }
+            let wrappers = match read_file(&path) {
+                Ok(wrappers) => wrappers,
+                Err(error) => {
+                    self.quarantine(&path, "corrupt", &error.to_string());
+                    continue;
+                }
+            };
+            total_bytes += len;
+
+            emit!(&VectorSinkSpoolRestored {
+                count: wrappers.len(),
+                path: &path,
+            });
+            if wrappers.is_empty() {
+                self.settle(&path, EventStatus::Delivered);
+                continue;
+            }
+
+            let mut pending = self.pending.lock().expect("mutex poisoned");
+            pending.files.insert(
+                path.clone(),
+                RestoredFile {
+                    remaining: wrappers.len(),
+                    status: EventStatus::Dropped,
+                },
+            );
+            for wrapper in wrappers {
+                let id = pending.next_id;
+                pending.next_id += 1;
+                pending.restored.insert(id, path.clone());
+                events.push((id, Event::from(wrapper)));
+            }
+        }
+        events
+    }
+
+    /// Write the copies still kept of the events read from the input to a new file, as many as fit
+    /// in `max_bytes` along with the files already there, oldest first, returning how many were
+    /// written. Restored events are left in the files they were read from.
+    fn spill(&self) -> usize {
+        let pending = std::mem::take(&mut self.pending.lock().expect("mutex poisoned").events);
+        if pending.is_empty() {
+            return 0;
+        }
+
+        let used = self
+            .files()
+            .map_or(0, |files| files.iter().map(|(_, len)| len).sum::<u64>());
+        let mut budget = self
+            .max_bytes
+            .saturating_sub(used)
+            .saturating_sub(MAGIC.len() as u64);
+        let mut bytes = MAGIC.to_vec();
+        let mut count = 0;
+        for wrapper in pending.values() {
+            let len = wrapper.encoded_len();
+            let len = (prost::length_delimiter_len(len) + len) as u64;
+            if len > budget {
+                break;
+            }
+            budget -= len;
+            wrapper
+                .encode_length_delimited(&mut bytes)
+                .expect("a Vec has room for any message");
+            count += 1;
+        }
+        if count < pending.len() {
+            emit!(&VectorSinkSpoolFull {
+                count: pending.len() - count,
+                max_bytes: self.max_bytes,
+            });
+        }
+        if count == 0 {
+            return 0;
+        }
+
+        match self.write_file(&bytes) {
+            Ok(path) => {
+                emit!(&VectorSinkEventsSpooled { count, path: &path });
+                count
+            }
+            Err(error) => {
+                emit!(&VectorSinkSpoolFailed {
+                    path: &self.directory,
+                    error: &error,
+                });
+                0
+            }
+        }
+    }
+
+    /// Write a new spool file, named after the current time so that the files sort oldest first.
+    fn write_file(&self, bytes: &[u8]) -> io::Result<PathBuf> {
+        fs::create_dir_all(&self.directory)?;
+        let millis = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis();
+        let path = self
+            .directory
+            .join(format!("{:020}-{}.{}", millis, Uuid::new_v4(), EXTENSION));
+        // Written under another name first, so that a file cut short by a crash isn't read back
+        // as a spool file.
+        let partial = path.with_extension("partial");
+        let mut file = File::create(&partial)?;
+        file.write_all(bytes)?;
+        file.sync_all()?;
+        fs::rename(&partial, &path)?;
+        Ok(path)
+    }
+
+    /// The files in the spool directory, other than the quarantined ones, with their sizes, oldest
+    /// first.
+    fn files(&self) -> io::Result<Vec<(PathBuf, u64)>> {
+        let mut files = Vec::new();
+        for entry in fs::read_dir(&self.directory)? {
+            let entry = entry?;
+            let metadata = entry.metadata()?;
+            if metadata.is_file() {
+                files.push((entry.path(), metadata.len()));
+            }
+        }
+        files.sort();
+        Ok(files)
+    }
+
+    fn settle(&self, path: &Path, status: EventStatus) {
+        match status {
+            EventStatus::Dropped | EventStatus::Delivered | EventStatus::Recorded => {
+                if let Err(error) = fs::remove_file(path) {
+                    emit!(&VectorSinkSpoolFailed {
+                        path,
+                        error: &error
+                    });
+                }
+            }
+            EventStatus::Rejected => {
+                self.quarantine(path, "rejected", "Events of the file were rejected.")
+            }
+            // Sent again on the next start.
+            EventStatus::Errored => (),
+        }
+    }
+
+    fn quarantine(&self, path: &Path, reason: &'static str, error: &str) {
+        let directory = self.directory.join(QUARANTINE_DIR);
+        let result = fs::create_dir_all(&directory)
+            .and_then(|()| fs::rename(path, directory.join(path.file_name().unwrap_or_default())));
+        match result {
+            Ok(()) => emit!(&VectorSinkSpoolQuarantined {
+                path,
+                reason,
+                error,
+            }),
+            Err(error) => emit!(&VectorSinkSpoolFailed {
+                path,
+                error: &error
+            }),
+        }
+    }
+}
+
+/// Read the events of a spool file, failing if it is anything but a whole spool file of events
+/// this Vector knows.
+fn read_file(path: &Path) -> io::Result<Vec<EventWrapper>> {
+    let invalid = |error: String| io::Error::new(io::ErrorKind::InvalidData, error);
+    if path.extension().and_then(|extension| extension.to_str()) != Some(EXTENSION) {
+        return Err(invalid("Not a complete spool file.".into()));
+    }
+    let bytes = fs::read(path)?;
+    let mut bytes = bytes
+        .strip_prefix(MAGIC)
+        .ok_or_else(|| invalid("Not a spool file.".into()))?;
+
+    let mut wrappers = Vec::new();
+    while bytes.has_remaining() {
+        let wrapper = EventWrapper::decode_length_delimited(&mut bytes)
+            .map_err(|error| invalid(error.to_string()))?;
+        if wrapper.event.is_none() {
+            return Err(invalid("Event of an unknown type.".into()));
+        }
+        wrappers.push(wrapper);
+    }
+    Ok(wrappers)
+}
+
+/// Spools the events the sink didn't deliver when it is dropped, whether it stopped once it was
+/// done with every event or was killed while still retrying some, as happens at the shutdown
+/// timeout once the runtime shuts down.
+pub struct SpoolGuard {
+    spill: Option<Spill>,
+}
+
+impl SpoolGuard {
+    /// Must be created within the span of the sink, so what it emits is tagged with its component.
+    pub fn new(spool: Spool, gauges: SinkGauges) -> Self {
+        Self {
+            spill: Some(Spill {
+                spool,
+                gauges,
+                span: tracing::Span::current(),
+            }),
+        }
+    }
+}
+
+impl Drop for SpoolGuard {
+    fn drop(&mut self) {
+        let spill = self.spill.take();
+        // The file is written on a blocking thread, so as not to hold up the runtime. A runtime
+        // that is shutting down drops the task without running it, which writes the file right
+        // away instead.
+        match tokio::runtime::Handle::try_current() {
+            Ok(handle) => {
+                handle.spawn_blocking(move || drop(spill));
+            }
+            Err(_) => drop(spill),
+        }
+    }
+}
+
+/// Writes the events the spool still keeps when dropped.
+struct Spill {
+    spool: Spool,
+    gauges: SinkGauges,
+    span: tracing::Span,
+}
+
+impl Drop for Spill {
+    fn drop(&mut self) {
+        let _enter = self.span.enter();
+        let count = self.spool.spill();
+        self.gauges.events_spooled(count);
+    }
+}
+
+/// Forgets the events of each request once it is done with, delivered or failed for good, so that
+/// only those still pending are spooled.
+pub struct SpoolService<S> {
+    inner: S,
+    spool: Option<Spool>,
+}
+
+impl<S> SpoolService<S> {
+    pub const fn new(inner: S, spool: Option<Spool>) -> Self {
+        Self { inner, spool }
+    }
+}
+
+impl<S> Service<VectorRequest> for SpoolService<S>
+where
+    S: Service<VectorRequest>,
+    S::Response: DriverResponse,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut request: VectorRequest) -> Self::Future {
+        let ids = std::mem::take(&mut request.spool_ids);
+        let spool = self.spool.clone();
+        self.inner
+            .call(request)
+            .map(move |result| {
+                if let Some(spool) = spool {
+                    // Failed requests count as rejected, as they do for the finalizers of their
+                    // events.
+                    let status = result
+                        .as_ref()
+                        .map_or(EventStatus::Rejected, DriverResponse::event_status);
+                    spool.finish(&ids, status);
+                }
+                result
+            })
+            .boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use vector_core::event::LogEvent;
+
+    use super::*;
+
+    fn spool(max_bytes: u64) -> Spool {
+        SpoolConfig {
+            directory: crate::test_util::temp_dir(),
+            max_bytes,
+        }
+        .build()
+    }
+
+    /// The spool of the sink started again.
+    fn restart(spool: &Spool) -> Spool {
+        SpoolConfig {
+            directory: spool.directory.clone(),
+            max_bytes: spool.max_bytes,
+        }
+        .build()
+    }
+
+    fn messages(events: &[(u64, Event)]) -> Vec<String> {
+        events
+            .iter()
+            .map(|(_, event)| event.as_log()["message"].to_string_lossy())
+            .collect()
+    }
+
+    async fn wait_for_files(directory: &Path, names: Vec<String>) {
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while file_names(directory) != names {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .unwrap();
+    }
+
+    fn wrapper(message: &str) -> EventWrapper {
+        EventWrapper::from(Event::from(message))
+    }
+
+    fn file_names(directory: &Path) -> Vec<String> {
+        let mut names = fs::read_dir(directory)
+            .map(|entries| {
+                entries
+                    .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+        names.sort();
+        names
+    }
+
+    #[tokio::test]
+    async fn spills_pending_events_and_restores_them() {
+        let spool = spool(1024 * 1024);
+        let mut event = LogEvent::from("first");
+        let event_id = event.metadata_mut().ensure_event_id();
+        spool.track(EventWrapper::from(Event::from(event)));
+        let delivered = spool.track(wrapper("delivered"));
+        spool.track(wrapper("second"));
+        spool.finish(&[delivered], EventStatus::Delivered);
+
+        assert_eq!(spool.spill(), 2);
+        // Nothing is left to spool again.
+        assert_eq!(spool.spill(), 0);
+        assert_eq!(file_names(&spool.directory).len(), 1);
+
+        let spool = restart(&spool);
+        let events = spool.restore().await;
+        assert_eq!(messages(&events), vec!["first", "second"]);
+        assert_eq!(events[0].1.metadata().event_id(), Some(event_id));
+
+        // The file is removed once every event in it is delivered.
+        spool.finish(&[events[0].0], EventStatus::Delivered);
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert_eq!(file_names(&spool.directory).len(), 1);
+        spool.finish(&[events[1].0], EventStatus::Delivered);
+        wait_for_files(&spool.directory, Vec::new()).await;
+    }
+
+    #[tokio::test]
+    async fn restored_files_are_kept_until_each_event_is_done_with() {
+        let spool = spool(1024 * 1024);
+        spool.track(wrapper("first"));
+        spool.track(wrapper("second"));
+        assert_eq!(spool.spill(), 2);
+        let files = file_names(&spool.directory);
+
+        // Restarted twice against a dead downstream, delivering nothing the first time and only
+        // some of the events the second. Restored events aren't spooled again, the file they were
+        // read from is kept instead.
+        let spool = restart(&spool);
+        assert_eq!(messages(&spool.restore().await), vec!["first", "second"]);
+        assert_eq!(spool.spill(), 0);
+        assert_eq!(file_names(&spool.directory), files);
+
+        let spool = restart(&spool);
+        let events = spool.restore().await;
+        assert_eq!(messages(&events), vec!["first", "second"]);
+        spool.finish(&[events[0].0], EventStatus::Delivered);
+        assert_eq!(spool.spill(), 0);
+        assert_eq!(file_names(&spool.directory), files);
+
+        // Files whose events failed are sent again on the next start.
+        let spool = restart(&spool);
+        let events = spool.restore().await;
+        assert_eq!(messages(&events), vec!["first", "second"]);
+        spool.finish(&[events[0].0], EventStatus::Delivered);
+        spool.finish(&[events[1].0], EventStatus::Errored);
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert_eq!(file_names(&spool.directory), files);
+
+        // And quarantined if some were rejected.
+        let spool = restart(&spool);
+        let events = spool.restore().await;
+        let ids = events.iter().map(|(id, _)| *id).collect::<Vec<_>>();
+        spool.finish(&ids[..1], EventStatus::Rejected);
+        spool.finish(&ids[1..], EventStatus::Delivered);
+        wait_for_files(&spool.directory, vec![QUARANTINE_DIR.to_owned()]).await;
+        assert_eq!(file_names(&spool.directory.join(QUARANTINE_DIR)), files);
+    }
+
+    #[tokio::test]
+    async fn spill_keeps_to_max_bytes() {
+        let event_len = wrapper(&"x".repeat(100)).encoded_len();
+        let event_len = (prost::length_delimiter_len(event_len) + event_len) as u64;
+        let spool = spool(MAGIC.len() as u64 + event_len * 2);
+        for _ in 0..5 {
+            spool.track(wrapper(&"x".repeat(100)));
+        }
+        assert_eq!(spool.spill(), 2);
+        assert_eq!(spool.restore().await.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn unreadable_and_oversized_files_are_quarantined() {
+        let spool = spool(1024);
+        fs::create_dir_all(&spool.directory).unwrap();
+        fs::write(spool.directory.join("1-corrupt.spool"), b"garbage").unwrap();
+        fs::write(spool.directory.join("2-partial.partial"), MAGIC).unwrap();
+        let mut large = MAGIC.to_vec();
+        wrapper(&"x".repeat(2048))
+            .encode_length_delimited(&mut large)
+            .unwrap();
+        fs::write(spool.directory.join("3-large.spool"), large).unwrap();
+        let mut truncated = MAGIC.to_vec();
+        wrapper("cut short")
+            .encode_length_delimited(&mut truncated)
+            .unwrap();
+        truncated.pop();
+        fs::write(spool.directory.join("4-truncated.spool"), truncated).unwrap();
+
+        assert!(spool.restore().await.is_empty());
+        assert_eq!(file_names(&spool.directory), vec![QUARANTINE_DIR]);
+        assert_eq!(
+            file_names(&spool.directory.join(QUARANTINE_DIR)),
+            vec![
+                "1-corrupt.spool",
+                "2-partial.partial",
+                "3-large.spool",
+                "4-truncated.spool"
+            ]
+        );
+    }
+}
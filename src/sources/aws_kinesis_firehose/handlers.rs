@@ -17,7 +17,7 @@ use super::{
 use crate::{
     codecs,
     config::log_schema,
-    event::{BatchStatus, Event},
+    event::{BatchStatus, Event, InsertMode},
     internal_events::{
         AwsKinesisFirehoseAutomaticRecordDecodeError, AwsKinesisFirehoseEventsReceived,
     },
@@ -67,7 +67,11 @@ pub async fn firehose(
                                 log_schema().source_type_key(),
                                 Bytes::from("aws_kinesis_firehose"),
                             );
-                            log.try_insert(log_schema().timestamp_key(), request.timestamp);
+                            log.try_insert_mode(
+                                log_schema().timestamp_key(),
+                                request.timestamp,
+                                InsertMode::IfAbsentOrNull,
+                            );
                             log.try_insert_flat("request_id", request_id.to_string());
                             log.try_insert_flat("source_arn", source_arn.to_string());
                         }
@@ -134,6 +134,8 @@ mod tests {
 
     use super::*;
     use crate::{
+        codecs::JsonDeserializerConfig,
+        config::log_schema,
         event::{Event, EventStatus},
         log_event,
         test_util::{collect_ready, next_addr, wait_for_tcp},
@@ -174,6 +176,21 @@ mod tests {
         access_key: Option<String>,
         record_compression: Option<Compression>,
         delivered: bool,
+    ) -> (impl Stream<Item = Event>, SocketAddr) {
+        source_with_decoding(
+            access_key,
+            record_compression,
+            delivered,
+            default_decoding(),
+        )
+        .await
+    }
+
+    async fn source_with_decoding(
+        access_key: Option<String>,
+        record_compression: Option<Compression>,
+        delivered: bool,
+        decoding: Box<dyn DeserializerConfig>,
     ) -> (impl Stream<Item = Event>, SocketAddr) {
         use EventStatus::*;
         let status = if delivered { Delivered } else { Rejected };
@@ -187,7 +204,7 @@ mod tests {
                 access_key,
                 record_compression,
                 framing: default_framing_message_based(),
-                decoding: default_decoding(),
+                decoding,
                 acknowledgements: true.into(),
             }
             .build(cx)
@@ -393,6 +410,46 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn aws_kinesis_firehose_fills_in_null_timestamp() {
+        let (rx, addr) = source_with_decoding(
+            None,
+            Some(Compression::None),
+            true,
+            Box::new(JsonDeserializerConfig::new()),
+        )
+        .await;
+
+        let timestamp: DateTime<Utc> = Utc::now();
+
+        let res = spawn_send(
+            addr,
+            timestamp,
+            vec![
+                r#"{"timestamp":null}"#.as_bytes(),
+                r#"{"timestamp":"then"}"#.as_bytes(),
+            ],
+            None,
+            false,
+            Compression::None,
+        )
+        .await;
+
+        let events = collect_ready(rx).await;
+        let res = res.await.unwrap().unwrap();
+        assert_eq!(200, res.status().as_u16());
+
+        assert_eq!(events.len(), 2);
+        assert_eq!(
+            events[0].as_log()[log_schema().timestamp_key()],
+            timestamp.trunc_subsecs(3).into()
+        );
+        assert_eq!(
+            events[1].as_log()[log_schema().timestamp_key()],
+            "then".into()
+        );
+    }
+
     #[tokio::test]
     async fn aws_kinesis_firehose_forwards_events_gzip_request() {
         let (rx, addr) = source(None, None, true).await;
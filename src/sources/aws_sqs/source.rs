@@ -16,7 +16,7 @@ use super::events::*;
 use crate::{
     codecs::Decoder,
     config::log_schema,
-    event::{BatchNotifier, BatchStatus, Event},
+    event::{BatchNotifier, BatchStatus, Event, InsertMode},
     shutdown::ShutdownSignal,
     sources::util::StreamDecodingError,
     vector_core::ByteSizeOf,
@@ -184,7 +184,7 @@ fn decode_message<E>(
                         if let Event::Log(ref mut log) = event {
                             log.try_insert(schema.source_type_key(), Bytes::from("aws_sqs"));
                             if let Some(sent_time) = sent_time {
-                                log.try_insert(schema.timestamp_key(), sent_time);
+                                log.try_insert_mode(schema.timestamp_key(), sent_time, InsertMode::IfAbsentOrNull);
                             }
                         }
                         total_events_size += event.size_of();
@@ -214,6 +214,10 @@ mod tests {
     use chrono::SecondsFormat;
 
     use super::*;
+    use crate::{
+        codecs::{decoding::DecodingConfig, JsonDeserializerConfig},
+        serde::default_framing_message_based,
+    };
 
     #[tokio::test]
     async fn test_decode() {
@@ -244,6 +248,26 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn test_decode_fills_in_null_timestamp() {
+        let decoder = DecodingConfig::new(
+            default_framing_message_based(),
+            Box::new(JsonDeserializerConfig::new()),
+        )
+        .build()
+        .unwrap();
+        let now = Utc::now();
+        let message = r#"[{"timestamp":null},{"timestamp":"then"}]"#;
+        let stream = decode_message::<()>(decoder, message, Some(now));
+        let events: Vec<_> = stream.map(Result::unwrap).collect().await;
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].as_log()[log_schema().timestamp_key()], now.into());
+        assert_eq!(
+            events[1].as_log()[log_schema().timestamp_key()],
+            "then".into()
+        );
+    }
+
     #[test]
     fn test_get_timestamp() {
         let attributes = HashMap::from([(
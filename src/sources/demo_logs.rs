@@ -16,6 +16,7 @@ use crate::{
         decoding::{DecodingConfig, DeserializerConfig, FramingConfig},
     },
     config::{log_schema, DataType, SourceConfig, SourceContext, SourceDescription},
+    event::InsertMode,
     internal_events::DemoLogsEventProcessed,
     serde::{default_decoding, default_framing_message_based},
     shutdown::ShutdownSignal,
@@ -169,7 +170,11 @@ async fn demo_logs_source(
                         let log = event.as_mut_log();
 
                         log.try_insert(log_schema().source_type_key(), Bytes::from("demo_logs"));
-                        log.try_insert(log_schema().timestamp_key(), now);
+                        log.try_insert_mode(
+                            log_schema().timestamp_key(),
+                            now,
+                            InsertMode::IfAbsentOrNull,
+                        );
 
                         out.send(event)
                             .await
@@ -254,7 +259,10 @@ mod tests {
     use futures::{channel::mpsc, poll, StreamExt};
 
     use super::*;
-    use crate::{config::log_schema, event::Event, shutdown::ShutdownSignal, Pipeline};
+    use crate::{
+        codecs::JsonDeserializerConfig, config::log_schema, event::Event, shutdown::ShutdownSignal,
+        Pipeline,
+    };
 
     #[test]
     fn generate_config() {
@@ -262,9 +270,16 @@ mod tests {
     }
 
     async fn runit(config: &str) -> mpsc::Receiver<Event> {
+        runit_with_decoding(config, default_decoding()).await
+    }
+
+    async fn runit_with_decoding(
+        config: &str,
+        decoding: Box<dyn DeserializerConfig>,
+    ) -> mpsc::Receiver<Event> {
         let (tx, rx) = Pipeline::new_test();
         let config: DemoLogsConfig = toml::from_str(config).unwrap();
-        let decoder = DecodingConfig::new(default_framing_message_based(), default_decoding())
+        let decoder = DecodingConfig::new(default_framing_message_based(), decoding)
             .build()
             .unwrap();
         demo_logs_source(
@@ -362,6 +377,30 @@ mod tests {
         assert_eq!(poll!(rx.next()), Poll::Ready(None));
     }
 
+    #[tokio::test]
+    async fn shuffle_demo_logs_fills_in_null_timestamp() {
+        let timestamp_key = log_schema().timestamp_key();
+        let mut rx = runit_with_decoding(
+            r#"format = "shuffle"
+               lines = ['{"timestamp":null}']
+               count = 1"#,
+            Box::new(JsonDeserializerConfig::new()),
+        )
+        .await;
+        let event = rx.next().await.unwrap();
+        assert!(event.as_log()[timestamp_key].as_timestamp().is_some());
+
+        let mut rx = runit_with_decoding(
+            r#"format = "shuffle"
+               lines = ['{"timestamp":"then"}']
+               count = 1"#,
+            Box::new(JsonDeserializerConfig::new()),
+        )
+        .await;
+        let event = rx.next().await.unwrap();
+        assert_eq!(event.as_log()[timestamp_key], "then".into());
+    }
+
     #[tokio::test]
     async fn shuffle_demo_logs_obeys_interval() {
         let start = Instant::now();
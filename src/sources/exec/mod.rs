@@ -26,7 +26,7 @@ use crate::{
         decoding::{DecodingConfig, DeserializerConfig, FramingConfig},
     },
     config::{log_schema, DataType, SourceConfig, SourceContext, SourceDescription},
-    event::Event,
+    event::{Event, InsertMode},
     internal_events::{ExecCommandExecuted, ExecEventsReceived, ExecFailed, ExecTimeout},
     serde::{default_decoding, default_framing_stream_based},
     shutdown::ShutdownSignal,
@@ -475,7 +475,11 @@ fn handle_event(
 ) {
     if let Event::Log(log) = event {
         // Add timestamp
-        log.try_insert(log_schema().timestamp_key(), Utc::now());
+        log.try_insert_mode(
+            log_schema().timestamp_key(),
+            Utc::now(),
+            InsertMode::IfAbsentOrNull,
+        );
 
         // Add source type
         log.try_insert(log_schema().source_type_key(), Bytes::from(EXEC));
@@ -492,7 +496,11 @@ fn handle_event(
 
         // Add hostname (if needed)
         if let Some(hostname) = hostname {
-            log.try_insert(log_schema().host_key(), hostname.clone());
+            log.try_insert_mode(
+                log_schema().host_key(),
+                hostname.clone(),
+                InsertMode::IfAbsentOrNull,
+            );
         }
 
         // Add command
@@ -540,7 +548,7 @@ mod tests {
     use std::io::Cursor;
 
     use super::*;
-    use crate::test_util::trace_init;
+    use crate::{event::Value, test_util::trace_init};
 
     #[test]
     fn test_generate_config() {
@@ -587,6 +595,38 @@ mod tests {
         assert!(log.get(log_schema().timestamp_key()).is_some());
     }
 
+    #[test]
+    fn test_handle_event_replaces_null_timestamp_and_host() {
+        let config = standard_scheduled_test_config();
+        let hostname = Some("Some.Machine".to_string());
+
+        let mut event: Event = Bytes::from("hello world").into();
+        let log = event.as_mut_log();
+        log.insert(log_schema().timestamp_key(), Value::Null);
+        log.insert(log_schema().host_key(), Value::Null);
+        log.insert(log_schema().source_type_key(), Value::Null);
+        handle_event(&config, &hostname, &None, None, &mut event);
+        let log = event.as_log();
+
+        assert!(matches!(
+            log[log_schema().timestamp_key()],
+            Value::Timestamp(_)
+        ));
+        assert_eq!(log[log_schema().host_key()], "Some.Machine".into());
+        // Only the timestamp and host are filled in when null.
+        assert_eq!(log[log_schema().source_type_key()], Value::Null);
+
+        let mut event: Event = Bytes::from("hello world").into();
+        let log = event.as_mut_log();
+        log.insert(log_schema().timestamp_key(), "then");
+        log.insert(log_schema().host_key(), "elsewhere");
+        handle_event(&config, &hostname, &None, None, &mut event);
+        let log = event.as_log();
+
+        assert_eq!(log[log_schema().timestamp_key()], "then".into());
+        assert_eq!(log[log_schema().host_key()], "elsewhere".into());
+    }
+
     #[test]
     fn test_build_command() {
         let config = ExecConfig {
@@ -21,7 +21,7 @@ use crate::{
         log_schema, AcknowledgementsConfig, DataType, GenerateConfig, Resource, SourceConfig,
         SourceContext, SourceDescription,
     },
-    event::Event,
+    event::{Event, InsertMode},
     internal_events::{HerokuLogplexRequestReadError, HerokuLogplexRequestReceived},
     serde::{bool_or_struct, default_decoding, default_framing_message_based},
     sources::util::{
@@ -233,10 +233,18 @@ fn line_to_events(mut decoder: codecs::Decoder, line: String) -> SmallVec<[Event
                     for mut event in decoded {
                         if let Event::Log(ref mut log) = event {
                             if let Ok(ts) = timestamp.parse::<DateTime<Utc>>() {
-                                log.try_insert(log_schema().timestamp_key(), ts);
+                                log.try_insert_mode(
+                                    log_schema().timestamp_key(),
+                                    ts,
+                                    InsertMode::IfAbsentOrNull,
+                                );
                             }
 
-                            log.try_insert(log_schema().host_key(), hostname.to_owned());
+                            log.try_insert_mode(
+                                log_schema().host_key(),
+                                hostname.to_owned(),
+                                InsertMode::IfAbsentOrNull,
+                            );
 
                             log.try_insert_flat("app_name", app_name.to_owned());
                             log.try_insert_flat("proc_id", proc_id.to_owned());
@@ -268,7 +276,11 @@ fn line_to_events(mut decoder: codecs::Decoder, line: String) -> SmallVec<[Event
     for event in &mut events {
         if let Event::Log(log) = event {
             log.try_insert(log_schema().source_type_key(), Bytes::from("heroku_logs"));
-            log.try_insert(log_schema().timestamp_key(), now);
+            log.try_insert_mode(
+                log_schema().timestamp_key(),
+                now,
+                InsertMode::IfAbsentOrNull,
+            );
         }
     }
 
@@ -286,6 +298,7 @@ mod tests {
 
     use super::{HttpSourceAuthConfig, LogplexConfig};
     use crate::{
+        codecs::{decoding::DecodingConfig, JsonDeserializerConfig},
         config::{log_schema, SourceConfig, SourceContext},
         serde::{default_decoding, default_framing_message_based},
         test_util::{components, next_addr, random_string, spawn_collect_n, wait_for_tcp},
@@ -480,6 +493,42 @@ mod tests {
         assert_eq!(log[log_schema().source_type_key()], "heroku_logs".into());
     }
 
+    #[test]
+    fn logplex_fills_in_null_timestamp_and_host() {
+        let decoder = || {
+            DecodingConfig::new(
+                default_framing_message_based(),
+                Box::new(JsonDeserializerConfig::new()),
+            )
+            .build()
+            .unwrap()
+        };
+
+        let body = r#"267 <158>1 2020-01-08T22:33:57.353034+00:00 host heroku router - {"timestamp":null,"host":null}"#;
+        let events = super::line_to_events(decoder(), body.into());
+        let log = events[0].as_log();
+        assert_eq!(
+            log[log_schema().timestamp_key()],
+            "2020-01-08T22:33:57.353034+00:00"
+                .parse::<DateTime<Utc>>()
+                .unwrap()
+                .into()
+        );
+        assert_eq!(log[log_schema().host_key()], "host".into());
+
+        let body = r#"267 <158>1 2020-01-08T22:33:57.353034+00:00 host heroku router - {"timestamp":"then","host":"elsewhere"}"#;
+        let events = super::line_to_events(decoder(), body.into());
+        let log = events[0].as_log();
+        assert_eq!(log[log_schema().timestamp_key()], "then".into());
+        assert_eq!(log[log_schema().host_key()], "elsewhere".into());
+
+        // Without a usable syslog timestamp the receive time fills in the null.
+        let body = r#"267 <158>1 - host heroku router - {"timestamp":null}"#;
+        let events = super::line_to_events(decoder(), body.into());
+        let log = events[0].as_log();
+        assert!(log[log_schema().timestamp_key()].as_timestamp().is_some());
+    }
+
     #[test]
     fn logplex_handles_malformed_lines() {
         let body = "what am i doing here";
@@ -18,7 +18,7 @@ use crate::{
         log_schema, AcknowledgementsConfig, DataType, GenerateConfig, Resource, SourceConfig,
         SourceContext, SourceDescription,
     },
-    event::{Event, Value},
+    event::{Event, InsertMode, Value},
     serde::{bool_or_struct, default_decoding, default_framing_stream_based},
     sources::util::{
         add_query_parameters, Encoding, ErrorMessage, HttpSource, HttpSourceAuthConfig,
@@ -126,7 +126,11 @@ impl HttpSource for SimpleHttpSource {
             let log = event.as_mut_log();
 
             log.try_insert(log_schema().source_type_key(), Bytes::from("http"));
-            log.try_insert(log_schema().timestamp_key(), now);
+            log.try_insert_mode(
+                log_schema().timestamp_key(),
+                now,
+                InsertMode::IfAbsentOrNull,
+            );
         }
 
         Ok(events)
@@ -512,6 +516,35 @@ mod tests {
             .is_some());
     }
 
+    #[tokio::test]
+    async fn http_json_fills_in_null_timestamp() {
+        let (rx, addr) = source(
+            vec![],
+            vec![],
+            "http_path",
+            "/",
+            true,
+            EventStatus::Delivered,
+            true,
+            None,
+            Some(Box::new(JsonDeserializerConfig::new())),
+        )
+        .await;
+
+        let mut events = spawn_ok_collect_n(
+            send(addr, r#"[{"timestamp":null},{"timestamp":"then"}]"#),
+            rx,
+            2,
+        )
+        .await;
+
+        let timestamp_key = log_schema().timestamp_key();
+        assert_eq!(events.remove(1).as_log()[timestamp_key], "then".into());
+        assert!(events.remove(0).as_log()[timestamp_key]
+            .as_timestamp()
+            .is_some());
+    }
+
     #[tokio::test]
     async fn http_json_values() {
         let (rx, addr) = source(
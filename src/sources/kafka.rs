@@ -27,7 +27,7 @@ use crate::{
         log_schema, AcknowledgementsConfig, DataType, SourceConfig, SourceContext,
         SourceDescription,
     },
-    event::{BatchNotifier, Event, Value},
+    event::{BatchNotifier, Event, InsertMode, Value},
     internal_events::{KafkaEventFailed, KafkaEventReceived, KafkaOffsetUpdateFailed},
     kafka::{KafkaAuthConfig, KafkaStatisticsContext},
     serde::{bool_or_struct, default_decoding, default_framing_message_based},
@@ -238,7 +238,11 @@ async fn kafka_source(
                             let mut event = events.pop().expect("event must exist");
                             if let Event::Log(ref mut log) = event {
                                 log.try_insert(schema.source_type_key(), Bytes::from("kafka"));
-                                log.try_insert(schema.timestamp_key(), timestamp);
+                                log.try_insert_mode(
+                                    schema.timestamp_key(),
+                                    timestamp,
+                                    InsertMode::IfAbsentOrNull,
+                                );
                                 log.try_insert(key_field, msg_key.clone());
                                 log.try_insert(topic_key, Value::from(msg_topic.clone()));
                                 log.try_insert(partition_key, Value::from(msg_partition));
@@ -420,6 +424,7 @@ mod integration_test {
 
     use super::{test::*, *};
     use crate::{
+        codecs::JsonDeserializerConfig,
         shutdown::ShutdownSignal,
         test_util::{collect_n, random_string},
         Pipeline,
@@ -546,4 +551,56 @@ mod integration_test {
             assert_eq!(event.as_log()["headers"], Value::from(expected_headers));
         }
     }
+
+    #[tokio::test]
+    async fn fills_in_null_timestamp() {
+        let topic = format!("test-topic-{}", random_string(10));
+        let group_id = format!("test-group-{}", random_string(10));
+        let now = Utc::now();
+
+        let config = make_config(&topic, &group_id);
+
+        let producer: FutureProducer = client_config(None);
+        for payload in [r#"{"timestamp":null}"#, r#"{"timestamp":"then"}"#] {
+            let record = FutureRecord::<(), _>::to(&topic)
+                .payload(payload)
+                .timestamp(now.timestamp_millis());
+            if let Err(error) = producer.send(record, Timeout::Never).await {
+                panic!("Cannot send event to Kafka: {:?}", error);
+            }
+        }
+
+        let decoder = DecodingConfig::new(
+            default_framing_message_based(),
+            Box::new(JsonDeserializerConfig::new()),
+        )
+        .build()
+        .unwrap();
+        let (trigger_shutdown, shutdown, shutdown_done) = ShutdownSignal::new_wired();
+        let (tx, rx) = Pipeline::new_test();
+        tokio::spawn(kafka_source(
+            create_consumer(&config).unwrap(),
+            config.key_field,
+            config.topic_key,
+            config.partition_key,
+            config.offset_key,
+            config.headers_key,
+            decoder,
+            shutdown,
+            tx,
+            false,
+        ));
+        let events = collect_n(rx, 2).await;
+        drop(trigger_shutdown);
+        shutdown_done.await;
+
+        assert_eq!(
+            events[0].as_log()[log_schema().timestamp_key()],
+            now.trunc_subsecs(3).into()
+        );
+        assert_eq!(
+            events[1].as_log()[log_schema().timestamp_key()],
+            "then".into()
+        );
+    }
 }
@@ -17,7 +17,7 @@ use crate::{
         log_schema, AcknowledgementsConfig, DataType, GenerateConfig, Resource, SourceConfig,
         SourceContext, SourceDescription,
     },
-    event::{Event, Value},
+    event::{Event, InsertMode, Value},
     serde::bool_or_struct,
     tcp::TcpKeepaliveConfig,
     tls::{MaybeTlsSettings, TlsConfig},
@@ -116,7 +116,11 @@ impl TcpSource for LogstashSource {
                     .unwrap_or_else(|| now.clone());
                 log.insert(log_schema().timestamp_key(), timestamp);
             }
-            log.try_insert(log_schema().host_key(), host.clone());
+            log.try_insert_mode(
+                log_schema().host_key(),
+                host.clone(),
+                InsertMode::IfAbsentOrNull,
+            );
         }
     }
 
@@ -582,6 +586,31 @@ mod test {
         test_protocol(EventStatus::Rejected, false).await;
     }
 
+    #[test]
+    fn handle_events_fills_in_null_host() {
+        let source = LogstashSource {
+            timestamp_converter: types::Conversion::Timestamp(Default::default()),
+        };
+        let mut events = vec![Event::new_empty_log(), Event::new_empty_log()];
+        events[0]
+            .as_mut_log()
+            .insert(log_schema().host_key(), Value::Null);
+        events[1]
+            .as_mut_log()
+            .insert(log_schema().host_key(), "elsewhere");
+
+        source.handle_events(&mut events, Bytes::from("127.0.0.1"), 0);
+
+        assert_eq!(
+            events[0].as_log()[log_schema().host_key()],
+            "127.0.0.1".into()
+        );
+        assert_eq!(
+            events[1].as_log()[log_schema().host_key()],
+            "elsewhere".into()
+        );
+    }
+
     async fn test_protocol(status: EventStatus, sends_ack: bool) {
         let (sender, recv) = Pipeline::new_test_finalize(status);
         let address = next_addr();
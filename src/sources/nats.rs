@@ -13,7 +13,7 @@ use crate::{
     config::{
         log_schema, DataType, GenerateConfig, SourceConfig, SourceContext, SourceDescription,
     },
-    event::Event,
+    event::{Event, InsertMode},
     internal_events::NatsEventsReceived,
     serde::{default_decoding, default_framing_message_based},
     shutdown::ShutdownSignal,
@@ -145,7 +145,11 @@ async fn nats_source(
                     for mut event in events {
                         if let Event::Log(ref mut log) = event {
                             log.try_insert(log_schema().source_type_key(), Bytes::from("nats"));
-                            log.try_insert(log_schema().timestamp_key(), now);
+                            log.try_insert_mode(
+                                log_schema().timestamp_key(),
+                                now,
+                                InsertMode::IfAbsentOrNull,
+                            );
                         }
 
                         out.send(event)
@@ -201,7 +205,10 @@ mod integration_tests {
     #![allow(clippy::print_stdout)] //tests
 
     use super::*;
-    use crate::test_util::{collect_n, random_string};
+    use crate::{
+        codecs::JsonDeserializerConfig,
+        test_util::{collect_n, random_string},
+    };
 
     #[tokio::test]
     async fn nats_happy() {
@@ -231,4 +238,44 @@ mod integration_tests {
         println!("Received event  {:?}", events[0].as_log());
         assert_eq!(events[0].as_log()[log_schema().message_key()], msg.into());
     }
+
+    #[tokio::test]
+    async fn nats_fills_in_null_timestamp() {
+        let subject = format!("test-{}", random_string(10));
+
+        let conf = NatsSourceConfig {
+            connection_name: "".to_owned(),
+            subject: subject.clone(),
+            url: "nats://127.0.0.1:4222".to_owned(),
+            queue: None,
+            framing: default_framing_message_based(),
+            decoding: Box::new(JsonDeserializerConfig::new()),
+        };
+
+        let (nc, sub) = create_subscription(&conf).await.unwrap();
+        let nc_pub = nc.clone();
+
+        let (tx, rx) = Pipeline::new_test();
+        let decoder = DecodingConfig::new(conf.framing.clone(), conf.decoding.clone())
+            .build()
+            .unwrap();
+        tokio::spawn(nats_source(nc, sub, decoder, ShutdownSignal::noop(), tx));
+        nc_pub
+            .publish(&subject, r#"{"timestamp":null}"#)
+            .await
+            .unwrap();
+        nc_pub
+            .publish(&subject, r#"{"timestamp":"then"}"#)
+            .await
+            .unwrap();
+
+        let events = collect_n(rx, 2).await;
+        assert!(events[0].as_log()[log_schema().timestamp_key()]
+            .as_timestamp()
+            .is_some());
+        assert_eq!(
+            events[1].as_log()[log_schema().timestamp_key()],
+            "then".into()
+        );
+    }
 }
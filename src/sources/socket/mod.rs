@@ -230,6 +230,7 @@ mod test {
     #[cfg(unix)]
     use {
         super::{unix::UnixConfig, Mode},
+        crate::codecs::decoding::DeserializerConfig,
         futures::SinkExt,
         std::path::PathBuf,
         tokio::{
@@ -241,7 +242,7 @@ mod test {
 
     use super::{tcp::TcpConfig, udp::UdpConfig, SocketConfig};
     use crate::{
-        codecs::NewlineDelimitedDecoderConfig,
+        codecs::{JsonDeserializerConfig, NewlineDelimitedDecoderConfig},
         config::{
             log_schema, ComponentKey, GlobalOptions, SinkContext, SourceConfig, SourceContext,
         },
@@ -374,6 +375,39 @@ mod test {
         SOURCE_TESTS.assert(&TCP_SOURCE_TAGS);
     }
 
+    #[tokio::test]
+    async fn tcp_fills_in_null_timestamp_and_host() {
+        let (tx, rx) = Pipeline::new_test();
+        let addr = next_addr();
+
+        let mut config = TcpConfig::from_address(addr.into());
+        config.set_decoding(Box::new(JsonDeserializerConfig::new()));
+
+        let server = SocketConfig::from(config)
+            .build(SourceContext::new_test(tx))
+            .await
+            .unwrap();
+        tokio::spawn(server);
+
+        let lines = vec![
+            r#"{"timestamp":null,"host":null}"#.to_owned(),
+            r#"{"timestamp":"then","host":"elsewhere"}"#.to_owned(),
+        ];
+
+        wait_for_tcp(addr).await;
+        send_lines(addr, lines.into_iter()).await.unwrap();
+
+        let events = collect_n(rx, 2).await;
+
+        let log = events[0].as_log();
+        assert!(log[log_schema().timestamp_key()].as_timestamp().is_some());
+        assert_eq!(log[log_schema().host_key()], "127.0.0.1".into());
+
+        let log = events[1].as_log();
+        assert_eq!(log[log_schema().timestamp_key()], "then".into());
+        assert_eq!(log[log_schema().host_key()], "elsewhere".into());
+    }
+
     #[tokio::test]
     async fn tcp_with_tls() {
         components::init_test();
@@ -619,6 +653,7 @@ mod test {
                 shutdown: shutdown_signal,
                 out: sender,
                 proxy: Default::default(),
+                log_schema: None,
             })
             .await
             .unwrap();
@@ -704,6 +739,44 @@ mod test {
         );
     }
 
+    #[tokio::test]
+    async fn udp_fills_in_null_timestamp_and_host() {
+        let (tx, rx) = Pipeline::new_test();
+        let address = next_addr();
+
+        let config = toml::from_str::<SocketConfig>(&format!(
+            r#"
+               mode = "udp"
+               address = "{}"
+               decoding.codec = "json"
+            "#,
+            address
+        ))
+        .unwrap();
+        let server = config.build(SourceContext::new_test(tx)).await.unwrap();
+        tokio::spawn(server);
+
+        // Wait for UDP to start listening
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+
+        let from = send_lines_udp(
+            address,
+            vec![
+                r#"{"timestamp":null,"host":null}"#.to_string(),
+                r#"{"timestamp":"then","host":"elsewhere"}"#.to_string(),
+            ],
+        );
+        let events = collect_n(rx, 2).await;
+
+        let log = events[0].as_log();
+        assert!(log[log_schema().timestamp_key()].as_timestamp().is_some());
+        assert_eq!(log[log_schema().host_key()], from.to_string().into());
+
+        let log = events[1].as_log();
+        assert_eq!(log[log_schema().timestamp_key()], "then".into());
+        assert_eq!(log[log_schema().host_key()], "elsewhere".into());
+    }
+
     #[tokio::test]
     async fn udp_shutdown_simple() {
         let (tx, rx) = Pipeline::new_test();
@@ -772,9 +845,21 @@ mod test {
     ////////////// UNIX TEST LIBS //////////////
     #[cfg(unix)]
     async fn init_unix(sender: Pipeline, stream: bool) -> PathBuf {
+        init_unix_with_decoding(sender, stream, crate::serde::default_decoding()).await
+    }
+
+    #[cfg(unix)]
+    async fn init_unix_with_decoding(
+        sender: Pipeline,
+        stream: bool,
+        decoding: Box<dyn DeserializerConfig>,
+    ) -> PathBuf {
         let in_path = tempfile::tempdir().unwrap().into_path().join("unix_test");
 
-        let config = UnixConfig::new(in_path.clone());
+        let config = UnixConfig {
+            decoding,
+            ..UnixConfig::new(in_path.clone())
+        };
         let mode = if stream {
             Mode::UnixStream(config)
         } else {
@@ -900,6 +985,41 @@ mod test {
         unix_multiple_packets(false).await
     }
 
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn unix_datagram_fills_in_null_timestamp_and_host() {
+        let (tx, rx) = Pipeline::new_test();
+        let path =
+            init_unix_with_decoding(tx, false, Box::new(JsonDeserializerConfig::new())).await;
+
+        let from = tempfile::tempdir()
+            .unwrap()
+            .into_path()
+            .join("unix_test_client");
+        let socket = UnixDatagram::bind(&from).unwrap();
+        socket.connect(path).unwrap();
+        socket
+            .send(br#"{"timestamp":null,"host":null}"#)
+            .await
+            .unwrap();
+        socket
+            .send(br#"{"timestamp":"then","host":"elsewhere"}"#)
+            .await
+            .unwrap();
+        let events = collect_n(rx, 2).await;
+
+        let log = events[0].as_log();
+        assert!(log[log_schema().timestamp_key()].as_timestamp().is_some());
+        assert_eq!(
+            log[log_schema().host_key()],
+            from.to_string_lossy().into_owned().into()
+        );
+
+        let log = events[1].as_log();
+        assert_eq!(log[log_schema().timestamp_key()], "then".into());
+        assert_eq!(log[log_schema().host_key()], "elsewhere".into());
+    }
+
     #[cfg(unix)]
     #[test]
     fn parses_unix_datagram_config() {
@@ -10,7 +10,7 @@ use crate::{
         decoding::{DeserializerConfig, FramingConfig},
     },
     config::log_schema,
-    event::Event,
+    event::{Event, InsertMode},
     internal_events::{SocketEventsReceived, SocketMode},
     serde::default_decoding,
     sources::util::{SocketListenAddr, TcpNullAcker, TcpSource},
@@ -120,12 +120,16 @@ impl TcpSource for RawTcpSource {
         for event in events {
             if let Event::Log(ref mut log) = event {
                 log.try_insert(log_schema().source_type_key(), Bytes::from("socket"));
-                log.try_insert(log_schema().timestamp_key(), now);
+                log.try_insert_mode(
+                    log_schema().timestamp_key(),
+                    now,
+                    InsertMode::IfAbsentOrNull,
+                );
 
                 let host_key = (self.config.host_key.clone())
                     .unwrap_or_else(|| log_schema().host_key().to_string());
 
-                log.try_insert(host_key, host.clone());
+                log.try_insert_mode(host_key, host.clone(), InsertMode::IfAbsentOrNull);
             }
         }
     }
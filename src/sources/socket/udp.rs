@@ -15,7 +15,7 @@ use crate::{
         Decoder,
     },
     config::log_schema,
-    event::Event,
+    event::{Event, InsertMode},
     internal_events::{SocketEventsReceived, SocketMode, SocketReceiveError},
     serde::{default_decoding, default_framing_message_based},
     shutdown::ShutdownSignal,
@@ -118,8 +118,8 @@ pub fn udp(
                                 for mut event in events {
                                     if let Event::Log(ref mut log) = event {
                                         log.try_insert(log_schema().source_type_key(), Bytes::from("socket"));
-                                        log.try_insert(log_schema().timestamp_key(), now);
-                                        log.try_insert(host_key.clone(), address.to_string());
+                                        log.try_insert_mode(log_schema().timestamp_key(), now, InsertMode::IfAbsentOrNull);
+                                        log.try_insert_mode(host_key.clone(), address.to_string(), InsertMode::IfAbsentOrNull);
                                     }
 
                                     tokio::select!{
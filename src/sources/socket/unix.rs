@@ -10,7 +10,7 @@ use crate::{
         Decoder,
     },
     config::log_schema,
-    event::Event,
+    event::{Event, InsertMode},
     internal_events::{SocketEventsReceived, SocketMode},
     serde::default_decoding,
     shutdown::ShutdownSignal,
@@ -65,10 +65,14 @@ fn handle_events(
         let log = event.as_mut_log();
 
         log.try_insert(log_schema().source_type_key(), Bytes::from("socket"));
-        log.try_insert(log_schema().timestamp_key(), now);
+        log.try_insert_mode(
+            log_schema().timestamp_key(),
+            now,
+            InsertMode::IfAbsentOrNull,
+        );
 
         if let Some(ref host) = received_from {
-            log.try_insert(host_key, host.clone());
+            log.try_insert_mode(host_key, host.clone(), InsertMode::IfAbsentOrNull);
         }
     }
 }
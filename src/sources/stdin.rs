@@ -10,6 +10,7 @@ use tokio_util::{codec::FramedRead, io::StreamReader};
 use crate::{
     codecs::decoding::{DecodingConfig, DeserializerConfig, FramingConfig},
     config::{log_schema, DataType, Resource, SourceConfig, SourceContext, SourceDescription},
+    event::InsertMode,
     internal_events::StdinEventsReceived,
     serde::{default_decoding, default_framing_stream_based},
     shutdown::ShutdownSignal,
@@ -134,10 +135,10 @@ where
                             let log = event.as_mut_log();
 
                             log.try_insert(log_schema().source_type_key(), Bytes::from("stdin"));
-                            log.try_insert(log_schema().timestamp_key(), now);
+                            log.try_insert_mode(log_schema().timestamp_key(), now, InsertMode::IfAbsentOrNull);
 
                             if let Some(hostname) = &hostname {
-                                log.try_insert(&host_key, hostname.clone());
+                                log.try_insert_mode(&host_key, hostname.clone(), InsertMode::IfAbsentOrNull);
                             }
 
                             yield event;
@@ -171,7 +172,7 @@ mod tests {
     use std::io::Cursor;
 
     use super::*;
-    use crate::{test_util::trace_init, Pipeline};
+    use crate::{codecs::JsonDeserializerConfig, test_util::trace_init, Pipeline};
 
     #[test]
     fn generate_config() {
@@ -208,4 +209,39 @@ mod tests {
         let event = stream.next().await;
         assert!(event.is_none());
     }
+
+    #[tokio::test]
+    async fn stdin_fills_in_null_timestamp_and_host() {
+        trace_init();
+
+        let (tx, rx) = Pipeline::new_test();
+        let config = StdinConfig {
+            decoding: Box::new(JsonDeserializerConfig::new()),
+            ..Default::default()
+        };
+        let buf = Cursor::new(concat!(
+            r#"{"message":"null","timestamp":null,"host":null}"#,
+            "\n",
+            r#"{"message":"set","timestamp":"then","host":"elsewhere"}"#,
+        ));
+
+        stdin_source(buf, config, ShutdownSignal::noop(), tx)
+            .unwrap()
+            .await
+            .unwrap();
+
+        let events = rx.collect::<Vec<_>>().await;
+        assert_eq!(events.len(), 2);
+
+        let log = events[0].as_log();
+        assert!(log[log_schema().timestamp_key()].as_timestamp().is_some());
+        assert_eq!(
+            log[log_schema().host_key()],
+            crate::get_hostname().unwrap().into()
+        );
+
+        let log = events[1].as_log();
+        assert_eq!(log[log_schema().timestamp_key()], "then".into());
+        assert_eq!(log[log_schema().host_key()], "elsewhere".into());
+    }
 }
@@ -0,0 +1,438 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    time::Duration,
+};
+
+use prost::Message;
+use snafu::{ResultExt, Snafu};
+use tokio::time::Instant;
+use vector_core::event::proto::EventWrapper;
+
+use crate::proto::vector::EventChunk;
+
+/// Why the chunks of an event were dropped without it being reassembled.
+#[derive(Debug, Snafu)]
+pub enum ChunkError {
+    #[snafu(display(
+        "Chunk {} of event {} is out of range for {} chunks.",
+        index,
+        event_id,
+        total
+    ))]
+    OutOfRange {
+        event_id: String,
+        index: u32,
+        total: u32,
+    },
+    #[snafu(display(
+        "Chunk of event {} has a total of {} chunks, earlier ones had {}.",
+        event_id,
+        total,
+        expected
+    ))]
+    TotalMismatch {
+        event_id: String,
+        total: u32,
+        expected: u32,
+    },
+    #[snafu(display(
+        "Chunk of event {} doesn't fit in max_buffer_bytes ({}).",
+        event_id,
+        max_buffer_bytes
+    ))]
+    BufferFull {
+        event_id: String,
+        max_buffer_bytes: usize,
+    },
+    #[snafu(display(
+        "Event {} of {} chunks could never fit in max_buffer_bytes ({}).",
+        event_id,
+        total,
+        max_buffer_bytes
+    ))]
+    TooLarge {
+        event_id: String,
+        total: u32,
+        max_buffer_bytes: usize,
+    },
+    #[snafu(display(
+        "Chunk of event {} would be over max_open_events ({}).",
+        event_id,
+        max_open_events
+    ))]
+    TooManyEvents {
+        event_id: String,
+        max_open_events: usize,
+    },
+    #[snafu(display(
+        "Event {} timed out with {} of its {} chunks received.",
+        event_id,
+        received,
+        total
+    ))]
+    TimedOut {
+        event_id: String,
+        received: usize,
+        total: u32,
+    },
+    #[snafu(display("Reassembled event {} failed to decode: {}", event_id, source))]
+    Decode {
+        event_id: String,
+        /// The reassembled bytes that failed to decode.
+        data: Vec<u8>,
+        source: prost::DecodeError,
+    },
+}
+
+impl ChunkError {
+    /// The id the chunks of the event were sent under.
+    pub fn chunk_event_id(&self) -> &str {
+        match self {
+            Self::OutOfRange { event_id, .. }
+            | Self::TotalMismatch { event_id, .. }
+            | Self::BufferFull { event_id, .. }
+            | Self::TooLarge { event_id, .. }
+            | Self::TooManyEvents { event_id, .. }
+            | Self::TimedOut { event_id, .. }
+            | Self::Decode { event_id, .. } => event_id,
+        }
+    }
+
+    pub const fn reason(&self) -> &'static str {
+        match self {
+            Self::OutOfRange { .. } | Self::TotalMismatch { .. } => "invalid_chunk",
+            Self::BufferFull { .. } | Self::TooManyEvents { .. } => "buffer_full",
+            Self::TooLarge { .. } => "too_large",
+            Self::TimedOut { .. } => "timeout",
+            Self::Decode { .. } => "decode_failed",
+        }
+    }
+}
+
+/// The chunks received so far of an event.
+#[derive(Debug)]
+struct Partial {
+    total: u32,
+    chunks: BTreeMap<u32, Vec<u8>>,
+    byte_size: usize,
+    deadline: Instant,
+}
+
+/// What holding an incomplete event costs on top of the data of its chunks,
+/// charged against `max_buffer_bytes` along with its id, so that many events
+/// of tiny chunks can't take up far more memory than the buffer allows.
+const PARTIAL_OVERHEAD_BYTES: usize = 128;
+
+/// What holding each chunk costs on top of its data.
+const CHUNK_OVERHEAD_BYTES: usize = 64;
+
+/// Puts back together the events an upstream Vector split into chunks. The
+/// chunks of incomplete events are held up to `max_buffer_bytes` in total, for
+/// at most `max_open_events` events at once, and for at most `timeout` per
+/// event.
+#[derive(Debug)]
+pub struct Reassembler {
+    partials: HashMap<String, Partial>,
+    byte_size: usize,
+    max_buffer_bytes: usize,
+    max_open_events: usize,
+    timeout: Duration,
+}
+
+impl Reassembler {
+    pub fn new(max_buffer_bytes: usize, max_open_events: usize, timeout: Duration) -> Self {
+        Self {
+            partials: HashMap::new(),
+            byte_size: 0,
+            max_buffer_bytes,
+            max_open_events,
+            timeout,
+        }
+    }
+
+    /// Add a chunk, returning its event if this was the last chunk missing.
+    /// The chunks of the event received so far are dropped if this one is
+    /// invalid or doesn't fit in the buffer.
+    pub fn insert(
+        &mut self,
+        chunk: EventChunk,
+        now: Instant,
+    ) -> Result<Option<EventWrapper>, ChunkError> {
+        let EventChunk {
+            event_id,
+            index,
+            total,
+            data,
+        } = chunk;
+        if index >= total {
+            self.remove(&event_id);
+            return Err(ChunkError::OutOfRange {
+                event_id,
+                index,
+                total,
+            });
+        }
+
+        if !self.partials.contains_key(&event_id) {
+            if min_event_bytes(&event_id, index, total, data.len()) > self.max_buffer_bytes {
+                return Err(ChunkError::TooLarge {
+                    event_id,
+                    total,
+                    max_buffer_bytes: self.max_buffer_bytes,
+                });
+            }
+            if self.partials.len() >= self.max_open_events {
+                return Err(ChunkError::TooManyEvents {
+                    event_id,
+                    max_open_events: self.max_open_events,
+                });
+            }
+            let byte_size = PARTIAL_OVERHEAD_BYTES + event_id.len();
+            if self.byte_size + byte_size > self.max_buffer_bytes {
+                return Err(ChunkError::BufferFull {
+                    event_id,
+                    max_buffer_bytes: self.max_buffer_bytes,
+                });
+            }
+            self.byte_size += byte_size;
+            self.partials.insert(
+                event_id.clone(),
+                Partial {
+                    total,
+                    chunks: BTreeMap::new(),
+                    byte_size,
+                    deadline: now + self.timeout,
+                },
+            );
+        }
+        let partial = self
+            .partials
+            .get_mut(&event_id)
+            .expect("partial event should exist");
+        if partial.total != total {
+            let expected = partial.total;
+            self.remove(&event_id);
+            return Err(ChunkError::TotalMismatch {
+                event_id,
+                total,
+                expected,
+            });
+        }
+
+        // A chunk received again, as when a request is retried, replaces the
+        // one received before.
+        let replaced = partial
+            .chunks
+            .get(&index)
+            .map_or(0, |data| data.len() + CHUNK_OVERHEAD_BYTES);
+        let added = data.len() + CHUNK_OVERHEAD_BYTES;
+        if self.byte_size - replaced + added > self.max_buffer_bytes {
+            self.remove(&event_id);
+            return Err(ChunkError::BufferFull {
+                event_id,
+                max_buffer_bytes: self.max_buffer_bytes,
+            });
+        }
+        self.byte_size = self.byte_size - replaced + added;
+        partial.byte_size = partial.byte_size - replaced + added;
+        partial.chunks.insert(index, data);
+        if partial.chunks.len() < total as usize {
+            return Ok(None);
+        }
+
+        let partial = self.remove(&event_id).expect("partial event should exist");
+        let mut encoded = Vec::with_capacity(partial.chunks.values().map(Vec::len).sum());
+        for data in partial.chunks.into_values() {
+            encoded.extend_from_slice(&data);
+        }
+        let wrapper = EventWrapper::decode(encoded.as_slice()).context(Decode {
+            event_id,
+            data: encoded,
+        })?;
+        Ok(Some(wrapper))
+    }
+
+    /// Drop the events that weren't completed in time.
+    pub fn expire(&mut self, now: Instant) -> Vec<ChunkError> {
+        let expired = self
+            .partials
+            .iter()
+            .filter(|(_, partial)| partial.deadline <= now)
+            .map(|(event_id, _)| event_id.clone())
+            .collect::<Vec<_>>();
+        expired
+            .into_iter()
+            .filter_map(|event_id| {
+                let partial = self.remove(&event_id)?;
+                Some(ChunkError::TimedOut {
+                    event_id,
+                    received: partial.chunks.len(),
+                    total: partial.total,
+                })
+            })
+            .collect()
+    }
+
+    fn remove(&mut self, event_id: &str) -> Option<Partial> {
+        let partial = self.partials.remove(event_id)?;
+        self.byte_size -= partial.byte_size;
+        Some(partial)
+    }
+}
+
+/// The least the buffer must hold for an event of `total` chunks, going by the
+/// size of its chunk at `index`. The chunks are all the same size but for the
+/// last one, which may be smaller.
+fn min_event_bytes(event_id: &str, index: u32, total: u32, chunk_bytes: usize) -> usize {
+    let total = total as usize;
+    let data_bytes = if index as usize + 1 < total {
+        chunk_bytes.saturating_mul(total - 1).saturating_add(1)
+    } else {
+        chunk_bytes
+    };
+    CHUNK_OVERHEAD_BYTES
+        .saturating_mul(total)
+        .saturating_add(data_bytes)
+        .saturating_add(PARTIAL_OVERHEAD_BYTES + event_id.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use vector_core::event::Event;
+
+    use super::*;
+
+    fn chunks(event_id: &str, message: &str, chunk_bytes: usize) -> Vec<EventChunk> {
+        let encoded = EventWrapper::from(Event::from(message)).encode_to_vec();
+        let total = encoded.chunks(chunk_bytes).len() as u32;
+        encoded
+            .chunks(chunk_bytes)
+            .enumerate()
+            .map(|(index, data)| EventChunk {
+                event_id: event_id.to_owned(),
+                index: index as u32,
+                total,
+                data: data.to_vec(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn reassembles_chunks_in_any_order() {
+        let mut reassembler = Reassembler::new(1024 * 1024, 100, Duration::from_secs(60));
+        let now = Instant::now();
+        let message = "x".repeat(1000);
+        let mut chunks = chunks("a", &message, 100);
+        let last = chunks.remove(0);
+        chunks.reverse();
+
+        for chunk in chunks.iter().cloned().chain(chunks.iter().take(2).cloned()) {
+            assert!(reassembler.insert(chunk, now).unwrap().is_none());
+        }
+        let event = Event::from(reassembler.insert(last, now).unwrap().unwrap());
+        assert_eq!(event.as_log()["message"], message.into());
+        assert_eq!(reassembler.byte_size, 0);
+        assert!(reassembler.partials.is_empty());
+    }
+
+    #[test]
+    fn expires_incomplete_events() {
+        let timeout = Duration::from_secs(10);
+        let mut reassembler = Reassembler::new(1024 * 1024, 100, timeout);
+        let now = Instant::now();
+        let mut chunks = chunks("a", &"x".repeat(1000), 100);
+        chunks.pop();
+        for chunk in chunks {
+            reassembler.insert(chunk, now).unwrap();
+        }
+
+        assert!(reassembler.expire(now + timeout / 2).is_empty());
+        let expired = reassembler.expire(now + timeout);
+        assert_eq!(expired.len(), 1);
+        assert_eq!(expired[0].reason(), "timeout");
+        assert_eq!(reassembler.byte_size, 0);
+    }
+
+    #[test]
+    fn buffer_is_bounded() {
+        let mut reassembler = Reassembler::new(2000, 100, Duration::from_secs(60));
+        let now = Instant::now();
+        let mut first = chunks("a", &"x".repeat(1000), 100).into_iter();
+        let mut second = chunks("b", &"y".repeat(1000), 100).into_iter();
+
+        for _ in 0..5 {
+            reassembler.insert(first.next().unwrap(), now).unwrap();
+            reassembler.insert(second.next().unwrap(), now).unwrap();
+        }
+        let error = reassembler.insert(first.next().unwrap(), now).unwrap_err();
+        assert_eq!(error.reason(), "buffer_full");
+        // Only the event that didn't fit is dropped, and the bookkeeping of
+        // the other one is charged along with its data.
+        assert_eq!(
+            reassembler.byte_size,
+            PARTIAL_OVERHEAD_BYTES + 1 + 5 * (100 + CHUNK_OVERHEAD_BYTES)
+        );
+        assert!(reassembler.partials.contains_key("b"));
+    }
+
+    #[test]
+    fn open_events_are_bounded() {
+        let mut reassembler = Reassembler::new(1024 * 1024, 2, Duration::from_secs(60));
+        let now = Instant::now();
+        let mut first = chunks("a", &"x".repeat(1000), 100);
+        let last = first.pop().unwrap();
+        for chunk in first {
+            reassembler.insert(chunk, now).unwrap();
+        }
+        reassembler
+            .insert(chunks("b", &"y".repeat(1000), 100).remove(0), now)
+            .unwrap();
+
+        let third = chunks("c", &"z".repeat(1000), 100).remove(0);
+        let error = reassembler.insert(third.clone(), now).unwrap_err();
+        assert_eq!(error.reason(), "buffer_full");
+        assert_eq!(reassembler.partials.len(), 2);
+
+        // Completing an event makes room for another one.
+        assert!(reassembler.insert(last, now).unwrap().is_some());
+        reassembler.insert(third, now).unwrap();
+        assert!(reassembler.partials.contains_key("c"));
+    }
+
+    #[test]
+    fn totals_that_could_never_fit_are_refused() {
+        let mut reassembler = Reassembler::new(1024 * 1024, 100, Duration::from_secs(60));
+        let now = Instant::now();
+        for (total, data) in [(100_000, vec![0; 100]), (u32::MAX, Vec::new())] {
+            let chunk = EventChunk {
+                event_id: "a".to_owned(),
+                index: 0,
+                total,
+                data,
+            };
+            let error = reassembler.insert(chunk, now).unwrap_err();
+            assert_eq!(error.reason(), "too_large");
+        }
+        assert!(reassembler.partials.is_empty());
+        assert_eq!(reassembler.byte_size, 0);
+    }
+
+    #[test]
+    fn invalid_chunks_drop_their_event() {
+        let mut reassembler = Reassembler::new(1024 * 1024, 100, Duration::from_secs(60));
+        let now = Instant::now();
+        let mut chunks = chunks("a", &"x".repeat(1000), 100).into_iter();
+        reassembler.insert(chunks.next().unwrap(), now).unwrap();
+
+        let mut chunk = chunks.next().unwrap();
+        chunk.total += 1;
+        let error = reassembler.insert(chunk.clone(), now).unwrap_err();
+        assert_eq!(error.reason(), "invalid_chunk");
+        assert!(reassembler.partials.is_empty());
+
+        chunk.index = chunk.total;
+        let error = reassembler.insert(chunk, now).unwrap_err();
+        assert_eq!(error.reason(), "invalid_chunk");
+        assert_eq!(reassembler.byte_size, 0);
+    }
+}
@@ -0,0 +1,248 @@
+use std::{collections::BTreeMap, convert::TryFrom};
+
+use serde::{Deserialize, Serialize};
+use snafu::Snafu;
+use tonic::metadata::{Ascii, Binary, MetadataKey, MetadataMap};
+use vector_core::event::Event;
+
+use crate::internal_events::VectorExtraValueOmitted;
+
+/// The text of an `extra_fields` or `extra_tags` value, where each `{{ header.<key> }}` is
+/// replaced by the request metadata of that key.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct MetadataTemplate {
+    template: String,
+    parts: Vec<Part>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Part {
+    Literal(String),
+    Header(String),
+}
+
+#[derive(Debug, PartialEq, Snafu)]
+pub enum TemplateError {
+    #[snafu(display("a `{{{{` is never closed by `}}}}`"))]
+    Unterminated,
+    #[snafu(display(
+        "unknown placeholder `{}`, only `header.<key>` is supported",
+        placeholder
+    ))]
+    UnknownPlaceholder { placeholder: String },
+    #[snafu(display("`{}` is not a valid gRPC metadata key", key))]
+    InvalidKey { key: String },
+}
+
+impl TryFrom<String> for MetadataTemplate {
+    type Error = TemplateError;
+
+    fn try_from(template: String) -> Result<Self, Self::Error> {
+        let mut parts = Vec::new();
+        let mut rest = template.as_str();
+        while let Some(start) = rest.find("{{") {
+            if start > 0 {
+                parts.push(Part::Literal(rest[..start].to_owned()));
+            }
+            let placeholder = &rest[start + 2..];
+            let end = placeholder.find("}}").ok_or(TemplateError::Unterminated)?;
+            let key = placeholder[..end].trim();
+            let key = key
+                .strip_prefix("header.")
+                .ok_or_else(|| TemplateError::UnknownPlaceholder {
+                    placeholder: key.to_owned(),
+                })?
+                .to_ascii_lowercase();
+            let valid = if key.ends_with("-bin") {
+                MetadataKey::<Binary>::from_bytes(key.as_bytes()).is_ok()
+            } else {
+                MetadataKey::<Ascii>::from_bytes(key.as_bytes()).is_ok()
+            };
+            if !valid {
+                return Err(TemplateError::InvalidKey { key });
+            }
+            parts.push(Part::Header(key));
+            rest = &placeholder[end + 2..];
+        }
+        if !rest.is_empty() {
+            parts.push(Part::Literal(rest.to_owned()));
+        }
+        Ok(Self { template, parts })
+    }
+}
+
+impl From<MetadataTemplate> for String {
+    fn from(template: MetadataTemplate) -> Self {
+        template.template
+    }
+}
+
+impl MetadataTemplate {
+    /// The value for a request with `metadata`, or `None` if metadata it refers to is missing or
+    /// isn't valid UTF-8. Binary (`-bin`) metadata is decoded from base64 first.
+    pub fn render(&self, metadata: &MetadataMap) -> Option<String> {
+        let mut rendered = String::new();
+        for part in &self.parts {
+            match part {
+                Part::Literal(text) => rendered.push_str(text),
+                Part::Header(key) if key.ends_with("-bin") => {
+                    let bytes = metadata.get_bin(key.as_str())?.to_bytes().ok()?;
+                    rendered.push_str(std::str::from_utf8(&bytes).ok()?);
+                }
+                Part::Header(key) => {
+                    rendered.push_str(metadata.get(key.as_str())?.to_str().ok()?);
+                }
+            }
+        }
+        Some(rendered)
+    }
+}
+
+/// The fields added to the log events of every request, and the tags added to its metrics.
+#[derive(Clone, Debug, Default)]
+pub struct ExtraValues {
+    pub fields: BTreeMap<String, MetadataTemplate>,
+    pub tags: BTreeMap<String, MetadataTemplate>,
+    /// Replace the fields and tags the events already have.
+    pub overwrite: bool,
+}
+
+impl ExtraValues {
+    /// The values for a request with `metadata`, rendered once for all of its events.
+    pub fn render(&self, metadata: &MetadataMap) -> RenderedExtraValues<'_> {
+        RenderedExtraValues {
+            fields: render_all(&self.fields, metadata),
+            tags: render_all(&self.tags, metadata),
+            overwrite: self.overwrite,
+            logs: 0,
+            metrics: 0,
+        }
+    }
+}
+
+fn render_all<'a>(
+    templates: &'a BTreeMap<String, MetadataTemplate>,
+    metadata: &MetadataMap,
+) -> Vec<(&'a str, Option<String>)> {
+    templates
+        .iter()
+        .map(|(name, template)| (name.as_str(), template.render(metadata)))
+        .collect()
+}
+
+/// The extra values of a request. Those that couldn't be rendered are `None`, and left out of
+/// every event.
+pub struct RenderedExtraValues<'a> {
+    fields: Vec<(&'a str, Option<String>)>,
+    tags: Vec<(&'a str, Option<String>)>,
+    overwrite: bool,
+    logs: usize,
+    metrics: usize,
+}
+
+impl<'a> RenderedExtraValues<'a> {
+    pub fn apply(&mut self, event: &mut Event) {
+        match event {
+            Event::Log(log) => {
+                self.logs += 1;
+                for (field, value) in &self.fields {
+                    if let Some(value) = value {
+                        if self.overwrite || !log.contains(*field) {
+                            log.insert(*field, value.as_str());
+                        }
+                    }
+                }
+            }
+            Event::Metric(metric) => {
+                self.metrics += 1;
+                for (tag, value) in &self.tags {
+                    if let Some(value) = value {
+                        let exists = metric.tags().map_or(false, |tags| tags.contains_key(*tag));
+                        if self.overwrite || !exists {
+                            metric.insert_tag((*tag).to_owned(), value.clone());
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Count the values left out of the events they were applied to.
+    pub fn emit_omitted(&self) {
+        let omitted = [
+            ("field", &self.fields, self.logs),
+            ("tag", &self.tags, self.metrics),
+        ];
+        for (kind, values, count) in omitted {
+            if count == 0 {
+                continue;
+            }
+            for (name, _) in values.iter().filter(|(_, value)| value.is_none()) {
+                emit!(&VectorExtraValueOmitted {
+                    kind,
+                    name: *name,
+                    count,
+                });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tonic::metadata::MetadataValue;
+
+    use super::*;
+
+    fn template(template: &str) -> Result<MetadataTemplate, TemplateError> {
+        MetadataTemplate::try_from(template.to_owned())
+    }
+
+    #[test]
+    fn parses_templates() {
+        let parts = |text: &str| template(text).map(|template| template.parts);
+        assert_eq!(parts("prod"), Ok(vec![Part::Literal("prod".to_owned())]));
+        assert_eq!(
+            parts("{{header.X-Region}}-{{ header.x-zone }}"),
+            Ok(vec![
+                Part::Header("x-region".to_owned()),
+                Part::Literal("-".to_owned()),
+                Part::Header("x-zone".to_owned()),
+            ])
+        );
+        assert_eq!(
+            parts("{{ header.x-region"),
+            Err(TemplateError::Unterminated)
+        );
+        assert_eq!(
+            parts("{{ event.message }}"),
+            Err(TemplateError::UnknownPlaceholder {
+                placeholder: "event.message".to_owned()
+            })
+        );
+        assert_eq!(
+            parts("{{ header.x region }}"),
+            Err(TemplateError::InvalidKey {
+                key: "x region".to_owned()
+            })
+        );
+    }
+
+    #[test]
+    fn renders_metadata() {
+        let mut metadata = MetadataMap::new();
+        metadata.insert("x-region", "eu-west-1".parse().unwrap());
+        metadata.insert_bin("x-zone-bin", MetadataValue::from_bytes(b"eu-west-1a"));
+
+        let region = template("region={{ header.x-region }}").unwrap();
+        assert_eq!(
+            region.render(&metadata),
+            Some("region=eu-west-1".to_owned())
+        );
+        let zone = template("{{ header.x-zone-bin }}").unwrap();
+        assert_eq!(zone.render(&metadata), Some("eu-west-1a".to_owned()));
+        let missing = template("{{ header.x-region }}/{{ header.x-cell }}").unwrap();
+        assert_eq!(missing.render(&metadata), None);
+    }
+}
@@ -1,10 +1,15 @@
+mod chunks;
+mod extra;
+mod rate_limit;
+mod state;
 pub mod v1;
 pub mod v2;
 
 use serde::{Deserialize, Serialize};
 
 use crate::config::{
-    DataType, GenerateConfig, Resource, SourceConfig, SourceContext, SourceDescription,
+    DataType, DryRunReport, GenerateConfig, LogSchemaOverrides, Resource, SourceConfig,
+    SourceContext, SourceDescription,
 };
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -87,12 +92,46 @@ impl SourceConfig for VectorConfig {
             VectorConfig::V2(v2) => v2.config.resources(),
         }
     }
+
+    fn log_schema(&self) -> Option<&LogSchemaOverrides> {
+        match self {
+            VectorConfig::V1(_) => None,
+            VectorConfig::V2(v2) => v2.config.log_schema(),
+        }
+    }
+
+    async fn dry_run(&self, bind_addresses: bool) -> Option<DryRunReport> {
+        match self {
+            VectorConfig::V1(_) => None,
+            VectorConfig::V2(v2) => Some(v2.config.dry_run(bind_addresses).await),
+        }
+    }
 }
 
 #[cfg(test)]
 mod test {
+    use super::*;
+
     #[test]
     fn generate_config() {
         crate::test_util::test_generate_config::<super::VectorConfig>();
     }
+
+    #[test]
+    fn generated_config_round_trips() {
+        let generated = VectorConfig::generate_config().to_string();
+        let config = match toml::from_str::<VectorConfig>(&generated).unwrap() {
+            VectorConfig::V2(v2) => v2.config,
+            VectorConfig::V1(_) => panic!("Generated config is not version 2."),
+        };
+        assert_eq!(
+            toml::Value::try_from(config).unwrap().to_string(),
+            v2::VectorConfig::generate_config().to_string()
+        );
+
+        let generated: toml::Value = generated.parse().unwrap();
+        assert_eq!(generated["address"].as_str(), Some("0.0.0.0:6000"));
+        assert_eq!(generated["tls"]["enabled"].as_bool(), Some(false));
+        assert!(generated["tls"]["key_file"].is_str());
+    }
 }
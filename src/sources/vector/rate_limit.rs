@@ -0,0 +1,325 @@
+use std::{
+    collections::HashSet,
+    fmt::Write,
+    net::IpAddr,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use openssl::{nid::Nid, sha::sha256, x509::X509};
+use serde::{Deserialize, Serialize};
+use tokio::time::Instant;
+use tonic::{metadata::MetadataMap, transport::Certificate};
+use vector_core::ByteSizeOf;
+
+use crate::{internal_events::VectorSenderRateLimited, sources::vector::state::SharedState};
+
+/// What a sender is told apart by, in the order they are tried.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SenderIdentity {
+    /// The common name of the client certificate.
+    Certificate,
+    /// The value of the token metadata, which only a hash of is kept.
+    Token,
+    /// The IP address the request came from.
+    PeerIp,
+}
+
+/// How fast a bucket refills, and how many tokens it holds at most.
+#[derive(Clone, Copy, Debug)]
+pub struct Rate {
+    pub per_sec: f64,
+    pub burst: f64,
+}
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    updated_at: Instant,
+}
+
+impl Bucket {
+    const fn full(rate: Rate, now: Instant) -> Self {
+        Self {
+            tokens: rate.burst,
+            updated_at: now,
+        }
+    }
+
+    fn refill(&mut self, rate: Rate, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.updated_at).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * rate.per_sec).min(rate.burst);
+        self.updated_at = now;
+    }
+
+    /// How long until `cost` tokens are available, if they aren't yet. A
+    /// cost larger than the burst only needs a full bucket, so that it isn't
+    /// refused forever.
+    fn wait(&self, rate: Rate, cost: f64) -> Option<Duration> {
+        let cost = cost.min(rate.burst);
+        (self.tokens < cost).then(|| Duration::from_secs_f64((cost - self.tokens) / rate.per_sec))
+    }
+
+    /// Take the whole `cost`, even if it is larger than the burst. The bucket
+    /// is then left in debt, which the sender waits out before its next
+    /// request, so that large requests don't get through faster than the rate.
+    fn take(&mut self, cost: f64) {
+        self.tokens -= cost;
+    }
+}
+
+/// The buckets of a sender, filled up when it is first heard from.
+#[derive(Debug, Default)]
+pub struct SenderLimits {
+    events: Option<Bucket>,
+    bytes: Option<Bucket>,
+}
+
+impl ByteSizeOf for SenderLimits {
+    fn allocated_bytes(&self) -> usize {
+        0
+    }
+}
+
+/// Limits how fast each sender may send events and bytes, with a token
+/// bucket of each per sender. The buckets are kept in a [`SharedState`], so
+/// those of senders not heard from for a while are dropped.
+#[derive(Debug)]
+pub struct RateLimiter {
+    pub events: Option<Rate>,
+    pub bytes: Option<Rate>,
+    pub identify_by: Vec<SenderIdentity>,
+    pub token_metadata_key: String,
+    pub senders: Arc<SharedState<String, SenderLimits>>,
+    /// The senders the metrics are tagged with. Senders limited once this
+    /// holds `max_metric_senders` are counted together as `other`.
+    pub metric_senders: Mutex<HashSet<String>>,
+    pub max_metric_senders: usize,
+}
+
+impl RateLimiter {
+    /// The sender of a request, by the first of `identify_by` the request
+    /// has.
+    pub fn identify(
+        &self,
+        peer_ip: Option<IpAddr>,
+        peer_certs: Option<&[Certificate]>,
+        metadata: &MetadataMap,
+    ) -> Option<String> {
+        self.identify_by.iter().find_map(|identity| match identity {
+            SenderIdentity::Certificate => {
+                let certificate = X509::from_pem(peer_certs?.first()?.get_ref()).ok()?;
+                let common_name = certificate
+                    .subject_name()
+                    .entries_by_nid(Nid::COMMONNAME)
+                    .next()?
+                    .data()
+                    .as_utf8()
+                    .ok()?;
+                Some(format!("certificate:{}", common_name))
+            }
+            SenderIdentity::Token => {
+                let token = metadata.get(self.token_metadata_key.as_str())?;
+                let hash = sha256(token.as_encoded_bytes());
+                let mut identity = "token:".to_owned();
+                for byte in &hash[..8] {
+                    write!(identity, "{:02x}", byte).expect("writing to a string can't fail");
+                }
+                Some(identity)
+            }
+            SenderIdentity::PeerIp => peer_ip.map(|ip| format!("peer_ip:{}", ip)),
+        })
+    }
+
+    /// Take the tokens for a request of `events` and `bytes` from the buckets
+    /// of `sender`, or say how long it has to wait until there are enough. No
+    /// tokens are taken from a refused request.
+    pub fn check(
+        &self,
+        sender: &str,
+        events: usize,
+        bytes: usize,
+        now: Instant,
+    ) -> Result<(), Duration> {
+        let result = self
+            .senders
+            .lock()
+            .update(sender.to_owned(), now, |limits| {
+                let mut buckets = [
+                    (self.events, &mut limits.events, events as f64),
+                    (self.bytes, &mut limits.bytes, bytes as f64),
+                ];
+                let mut wait = None;
+                for (rate, bucket, cost) in &mut buckets {
+                    if let Some(rate) = rate {
+                        let bucket = bucket.get_or_insert_with(|| Bucket::full(*rate, now));
+                        bucket.refill(*rate, now);
+                        wait = wait.max(bucket.wait(*rate, *cost));
+                    }
+                }
+                match wait {
+                    Some(wait) => Err(wait),
+                    None => {
+                        for (_, bucket, cost) in buckets {
+                            if let Some(bucket) = bucket {
+                                bucket.take(cost);
+                            }
+                        }
+                        Ok(())
+                    }
+                }
+            });
+        if result.is_err() {
+            emit!(&VectorSenderRateLimited {
+                sender: &self.metric_sender(sender),
+                events,
+                bytes,
+            });
+        }
+        result
+    }
+
+    fn metric_sender(&self, sender: &str) -> String {
+        let mut metric_senders = self.metric_senders.lock().expect("mutex poisoned");
+        if metric_senders.contains(sender) || metric_senders.len() < self.max_metric_senders {
+            metric_senders.insert(sender.to_owned());
+            sender.to_owned()
+        } else {
+            "other".to_owned()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sources::vector::state::StateRegistry;
+
+    fn limiter(events: Option<Rate>, bytes: Option<Rate>) -> RateLimiter {
+        RateLimiter {
+            events,
+            bytes,
+            identify_by: vec![
+                SenderIdentity::Certificate,
+                SenderIdentity::Token,
+                SenderIdentity::PeerIp,
+            ],
+            token_metadata_key: "authorization".to_owned(),
+            senders: Arc::new(SharedState::new(StateRegistry::new(
+                "rate_limits",
+                1024 * 1024,
+                Duration::from_secs(60),
+            ))),
+            metric_senders: Mutex::default(),
+            max_metric_senders: 1,
+        }
+    }
+
+    const fn rate(per_sec: f64, burst: f64) -> Option<Rate> {
+        Some(Rate { per_sec, burst })
+    }
+
+    #[test]
+    fn buckets_refill_over_time() {
+        let limiter = limiter(rate(10.0, 20.0), None);
+        let now = Instant::now();
+        assert_eq!(limiter.check("a", 15, 0, now), Ok(()));
+        assert_eq!(
+            limiter.check("a", 10, 0, now),
+            Err(Duration::from_millis(500))
+        );
+        // The refused request took nothing.
+        assert_eq!(limiter.check("a", 5, 0, now), Ok(()));
+        assert_eq!(
+            limiter.check("a", 10, 0, now + Duration::from_secs(1)),
+            Ok(())
+        );
+        // Larger than the burst, so it only needs a full bucket.
+        assert!(limiter
+            .check("a", 100, 0, now + Duration::from_secs(2))
+            .is_err());
+        assert_eq!(
+            limiter.check("a", 100, 0, now + Duration::from_secs(4)),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn oversized_requests_leave_the_bucket_in_debt() {
+        let limiter = limiter(rate(10.0, 20.0), None);
+        let now = Instant::now();
+        assert_eq!(limiter.check("a", 100, 0, now), Ok(()));
+
+        // The 80 events over the burst are paid back before anything else
+        // gets through, so the sender is held to the rate on average.
+        assert!(limiter
+            .check("a", 1, 0, now + Duration::from_secs(8))
+            .is_err());
+        assert_eq!(
+            limiter.check("a", 1, 0, now + Duration::from_secs(9)),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn requests_need_room_in_every_bucket() {
+        let limiter = limiter(rate(100.0, 100.0), rate(1000.0, 1000.0));
+        let now = Instant::now();
+        assert_eq!(
+            limiter.check("a", 1, 1500, now),
+            Ok(()),
+            "a full bucket lets any request through"
+        );
+        assert_eq!(
+            limiter.check("a", 1, 500, now),
+            Err(Duration::from_millis(500))
+        );
+        assert_eq!(limiter.check("a", 99, 0, now), Ok(()));
+    }
+
+    #[test]
+    fn senders_are_limited_apart() {
+        let limiter = limiter(rate(1.0, 1.0), None);
+        let now = Instant::now();
+        assert_eq!(limiter.check("a", 1, 0, now), Ok(()));
+        assert!(limiter.check("a", 1, 0, now).is_err());
+        assert_eq!(limiter.check("b", 1, 0, now), Ok(()));
+        assert_eq!(limiter.senders.lock().len(), 2);
+
+        // Only the first limited sender gets metrics of its own.
+        assert!(limiter.check("b", 1, 0, now).is_err());
+        assert_eq!(limiter.metric_sender("a"), "a");
+        assert_eq!(limiter.metric_sender("b"), "other");
+    }
+
+    #[test]
+    fn identity_follows_precedence() {
+        let limiter = limiter(None, None);
+        let ip = "10.0.0.1".parse().ok();
+        let mut metadata = MetadataMap::new();
+        assert_eq!(
+            limiter.identify(ip, None, &metadata),
+            Some("peer_ip:10.0.0.1".to_owned())
+        );
+
+        metadata.insert("authorization", "Bearer secret".parse().unwrap());
+        let token = limiter.identify(ip, None, &metadata).unwrap();
+        assert!(token.starts_with("token:"));
+        assert!(!token.contains("secret"));
+        assert_eq!(token.len(), "token:".len() + 16);
+
+        let certificate =
+            Certificate::from_pem(std::fs::read(crate::tls::TEST_PEM_CRT_PATH).unwrap());
+        assert_eq!(
+            limiter.identify(ip, Some(&[certificate]), &metadata),
+            Some("certificate:localhost".to_owned())
+        );
+
+        let limiter = RateLimiter {
+            identify_by: vec![SenderIdentity::Token],
+            ..limiter
+        };
+        assert_eq!(limiter.identify(ip, None, &MetadataMap::new()), None);
+    }
+}
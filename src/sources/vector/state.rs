@@ -0,0 +1,401 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    hash::Hash,
+    ops::{Deref, DerefMut},
+    sync::{Mutex, MutexGuard},
+    time::Duration,
+};
+
+use tokio::time::Instant;
+use vector_core::ByteSizeOf;
+
+use crate::internal_events::{VectorSourceStateEvicted, VectorSourceStateUpdated};
+
+/// Why entries were dropped from a [`StateRegistry`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum EvictionReason {
+    /// The least recently used entries made room for others.
+    Capacity,
+    /// The entries weren't used for longer than the TTL.
+    Ttl,
+}
+
+impl EvictionReason {
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Capacity => "capacity",
+            Self::Ttl => "ttl",
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Entry<V> {
+    value: V,
+    /// The position of the entry in the order of use.
+    used: u64,
+    last_used_at: Instant,
+    byte_size: usize,
+}
+
+/// The state the source keeps about each connection or sender, such as
+/// whether a sender was already identified, so that features needing it
+/// don't each keep their own. The state is held up to `max_bytes` in total,
+/// evicting the least recently used entries to make room, and entries not
+/// used for `ttl` are dropped by [`StateRegistry::expire`].
+///
+/// The metrics of a registry are tagged with its `name`, for those of a
+/// feature needing limits of its own to be told apart. They are emitted by
+/// [`SharedState`] once its lock is released.
+#[derive(Debug)]
+pub struct StateRegistry<K, V> {
+    name: &'static str,
+    entries: HashMap<K, Entry<V>>,
+    /// The keys from the least to the most recently used.
+    order: BTreeMap<u64, K>,
+    next_use: u64,
+    byte_size: usize,
+    max_bytes: usize,
+    ttl: Duration,
+    changes: Changes,
+}
+
+/// What happened to a registry since its metrics were last emitted.
+#[derive(Debug, Default, Eq, PartialEq)]
+struct Changes {
+    updated: bool,
+    evicted_for_capacity: usize,
+    expired: usize,
+}
+
+/// The metrics of a registry as of when its lock was released.
+#[derive(Debug)]
+struct Report {
+    state: &'static str,
+    changes: Changes,
+    entries: usize,
+    byte_size: usize,
+}
+
+impl Report {
+    fn emit(self) {
+        for (reason, count) in [
+            (EvictionReason::Capacity, self.changes.evicted_for_capacity),
+            (EvictionReason::Ttl, self.changes.expired),
+        ] {
+            if count > 0 {
+                emit!(&VectorSourceStateEvicted {
+                    state: self.state,
+                    reason: reason.as_str(),
+                    count,
+                });
+            }
+        }
+        if self.changes.updated {
+            emit!(&VectorSourceStateUpdated {
+                state: self.state,
+                entries: self.entries,
+                byte_size: self.byte_size,
+            });
+        }
+    }
+}
+
+impl<K, V> StateRegistry<K, V> {
+    fn take_report(&mut self) -> Report {
+        Report {
+            state: self.name,
+            changes: std::mem::take(&mut self.changes),
+            entries: self.entries.len(),
+            byte_size: self.byte_size,
+        }
+    }
+}
+
+/// A [`StateRegistry`] shared by the tasks of the source. The metrics of the
+/// changes made while it is locked are emitted once the lock is released, so
+/// that other requests don't wait on them.
+#[derive(Debug)]
+pub struct SharedState<K, V>(Mutex<StateRegistry<K, V>>);
+
+impl<K, V> SharedState<K, V> {
+    pub fn new(registry: StateRegistry<K, V>) -> Self {
+        Self(Mutex::new(registry))
+    }
+
+    pub fn lock(&self) -> StateGuard<'_, K, V> {
+        StateGuard(Some(self.0.lock().expect("mutex poisoned")))
+    }
+}
+
+/// The lock of a [`SharedState`], emitting the metrics of the registry once
+/// it is released.
+pub struct StateGuard<'a, K, V>(Option<MutexGuard<'a, StateRegistry<K, V>>>);
+
+impl<'a, K, V> Deref for StateGuard<'a, K, V> {
+    type Target = StateRegistry<K, V>;
+
+    fn deref(&self) -> &Self::Target {
+        self.0.as_ref().expect("guard is only taken when dropped")
+    }
+}
+
+impl<'a, K, V> DerefMut for StateGuard<'a, K, V> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.0.as_mut().expect("guard is only taken when dropped")
+    }
+}
+
+impl<'a, K, V> Drop for StateGuard<'a, K, V> {
+    fn drop(&mut self) {
+        if let Some(mut guard) = self.0.take() {
+            let report = guard.take_report();
+            drop(guard);
+            report.emit();
+        }
+    }
+}
+
+impl<K, V> StateRegistry<K, V>
+where
+    K: Clone + Eq + Hash + ByteSizeOf,
+    V: Default + ByteSizeOf,
+{
+    pub fn new(name: &'static str, max_bytes: usize, ttl: Duration) -> Self {
+        Self {
+            name,
+            entries: HashMap::new(),
+            order: BTreeMap::new(),
+            next_use: 0,
+            byte_size: 0,
+            max_bytes,
+            ttl,
+            changes: Changes::default(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub const fn byte_size(&self) -> usize {
+        self.byte_size
+    }
+
+    /// Call `f` with the state of `key`, starting from its default if there
+    /// is none, and mark it as the most recently used. Other entries are
+    /// evicted if it grew past what fits, and it is dropped as well if it
+    /// doesn't fit on its own.
+    ///
+    /// `now` must not go backwards from one call to the next.
+    pub fn update<R>(&mut self, key: K, now: Instant, f: impl FnOnce(&mut V) -> R) -> R {
+        let used = self.next_use;
+        self.next_use += 1;
+
+        let (result, byte_size) = match self.entries.get_mut(&key) {
+            Some(entry) => {
+                self.order.remove(&entry.used);
+                let result = f(&mut entry.value);
+                let byte_size = key.size_of() + entry.value.size_of();
+                self.byte_size = self.byte_size - entry.byte_size + byte_size;
+                entry.used = used;
+                entry.last_used_at = now;
+                entry.byte_size = byte_size;
+                (result, byte_size)
+            }
+            None => {
+                let mut value = V::default();
+                let result = f(&mut value);
+                let byte_size = key.size_of() + value.size_of();
+                self.byte_size += byte_size;
+                self.entries.insert(
+                    key.clone(),
+                    Entry {
+                        value,
+                        used,
+                        last_used_at: now,
+                        byte_size,
+                    },
+                );
+                (result, byte_size)
+            }
+        };
+        self.order.insert(used, key);
+
+        if byte_size > self.max_bytes {
+            // Evicting others wouldn't make room for it.
+            self.remove(used);
+            self.changes.evicted_for_capacity += 1;
+        } else {
+            while self.byte_size > self.max_bytes {
+                let least_recently_used = *self.order.keys().next().expect("order is not empty");
+                self.remove(least_recently_used);
+                self.changes.evicted_for_capacity += 1;
+            }
+        }
+        self.changes.updated = true;
+        result
+    }
+
+    /// Like [`update`](Self::update), but only for a key that has a state
+    /// already, leaving the registry as it is otherwise.
+    pub fn update_existing<R>(
+        &mut self,
+        key: K,
+        now: Instant,
+        f: impl FnOnce(&mut V) -> R,
+    ) -> Option<R> {
+        if self.entries.contains_key(&key) {
+            Some(self.update(key, now, f))
+        } else {
+            None
+        }
+    }
+
+    /// Drop the entries that weren't used for longer than the TTL.
+    pub fn expire(&mut self, now: Instant) {
+        while let Some((&used, key)) = self.order.iter().next() {
+            if self.entries[key].last_used_at + self.ttl > now {
+                break;
+            }
+            self.remove(used);
+            self.changes.expired += 1;
+            self.changes.updated = true;
+        }
+    }
+
+    fn remove(&mut self, used: u64) {
+        let key = self.order.remove(&used).expect("key should be in order");
+        let entry = self.entries.remove(&key).expect("entry should exist");
+        self.byte_size -= entry.byte_size;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// State holding the given number of bytes.
+    #[derive(Debug, Default)]
+    struct Held(usize);
+
+    impl ByteSizeOf for Held {
+        fn allocated_bytes(&self) -> usize {
+            self.0
+        }
+    }
+
+    fn entry_size(key: &str, held: usize) -> usize {
+        key.to_owned().size_of() + Held(held).size_of()
+    }
+
+    fn hold(bytes: usize) -> impl FnOnce(&mut Held) {
+        move |held| held.0 = bytes
+    }
+
+    #[test]
+    fn evicts_least_recently_used_under_capacity_pressure() {
+        let size = entry_size("a", 100);
+        let mut registry =
+            StateRegistry::<String, Held>::new("test", size * 3, Duration::from_secs(60));
+        let now = Instant::now();
+        for key in ["a", "b", "c"] {
+            registry.update(key.to_owned(), now, hold(100));
+        }
+        assert_eq!(registry.len(), 3);
+        assert_eq!(registry.byte_size(), size * 3);
+
+        // Using `a` makes `b` the least recently used.
+        registry.update("a".to_owned(), now, |_| ());
+        registry.update("d".to_owned(), now, hold(100));
+        assert_eq!(registry.len(), 3);
+        assert!(!registry.entries.contains_key("b"));
+        assert_eq!(registry.byte_size(), size * 3);
+
+        // An entry growing makes room for itself.
+        registry.update("a".to_owned(), now, hold(100 + size));
+        assert_eq!(registry.len(), 2);
+        assert!(!registry.entries.contains_key("c"));
+        assert_eq!(registry.byte_size(), size * 3);
+    }
+
+    #[test]
+    fn drops_entries_too_large_to_fit() {
+        let size = entry_size("a", 100);
+        let mut registry =
+            StateRegistry::<String, Held>::new("test", size * 2, Duration::from_secs(60));
+        let now = Instant::now();
+        registry.update("a".to_owned(), now, hold(100));
+        registry.update("b".to_owned(), now, hold(size * 2));
+        assert_eq!(registry.len(), 1);
+        assert!(registry.entries.contains_key("a"));
+        assert_eq!(registry.byte_size(), size);
+        assert_eq!(registry.order.len(), 1);
+    }
+
+    #[test]
+    fn updates_existing_entries_only() {
+        let mut registry =
+            StateRegistry::<String, Held>::new("test", 1024 * 1024, Duration::from_secs(60));
+        let now = Instant::now();
+        assert_eq!(
+            registry.update_existing("a".to_owned(), now, hold(100)),
+            None
+        );
+        assert_eq!(registry.len(), 0);
+        assert_eq!(registry.byte_size(), 0);
+
+        registry.update("a".to_owned(), now, hold(100));
+        assert_eq!(
+            registry.update_existing("a".to_owned(), now, |held| held.0),
+            Some(100)
+        );
+        assert_eq!(registry.len(), 1);
+    }
+
+    #[test]
+    fn expires_entries_not_used_within_ttl() {
+        let ttl = Duration::from_secs(10);
+        let mut registry = StateRegistry::<String, Held>::new("test", 1024 * 1024, ttl);
+        let now = Instant::now();
+        registry.update("a".to_owned(), now, hold(100));
+        registry.update("b".to_owned(), now, hold(100));
+        registry.update("a".to_owned(), now + ttl / 2, |_| ());
+
+        registry.expire(now + ttl / 2);
+        assert_eq!(registry.len(), 2);
+        registry.expire(now + ttl);
+        assert_eq!(registry.len(), 1);
+        assert!(registry.entries.contains_key("a"));
+        assert_eq!(registry.byte_size(), entry_size("a", 100));
+        registry.expire(now + ttl + ttl / 2);
+        assert_eq!(registry.len(), 0);
+        assert_eq!(registry.byte_size(), 0);
+        assert!(registry.order.is_empty());
+    }
+
+    #[test]
+    fn changes_are_reported_once_the_lock_is_released() {
+        let size = entry_size("a", 100);
+        let state = SharedState::new(StateRegistry::<String, Held>::new(
+            "test",
+            size,
+            Duration::from_secs(60),
+        ));
+        let now = Instant::now();
+        {
+            let mut registry = state.lock();
+            registry.update("a".to_owned(), now, hold(100));
+            registry.update("b".to_owned(), now, hold(100));
+            assert_eq!(
+                registry.changes,
+                Changes {
+                    updated: true,
+                    evicted_for_capacity: 1,
+                    expired: 0,
+                }
+            );
+        }
+        assert_eq!(state.lock().changes, Changes::default());
+    }
+}
@@ -249,6 +249,7 @@ mod test {
                 shutdown,
                 out: tx,
                 proxy: Default::default(),
+                log_schema: None,
             })
             .await
             .unwrap();
@@ -286,6 +287,7 @@ mod test {
                 shutdown,
                 out: tx,
                 proxy: Default::default(),
+                log_schema: None,
             })
             .await
             .unwrap();
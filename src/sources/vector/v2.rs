@@ -1,24 +1,60 @@
-use std::net::SocketAddr;
+use std::{
+    collections::BTreeMap,
+    net::SocketAddr,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex, Weak,
+    },
+    time::Duration,
+};
 
-use futures::{FutureExt, SinkExt, StreamExt, TryFutureExt};
+use bytes::Bytes;
+use chrono::{DateTime, Utc};
+use futures::{FutureExt, TryFutureExt};
+use prost::Message;
 use serde::{Deserialize, Serialize};
-use tokio::net::TcpStream;
+use tokio::{
+    net::TcpStream,
+    sync::{oneshot, watch},
+    time::{self, Instant},
+};
 use tonic::{
+    metadata::{Ascii, Binary, MetadataKey, MetadataMap, MetadataValue},
     transport::{server::Connected, Certificate, Server},
-    Request, Response, Status,
+    Code, Request, Response, Status,
 };
+use tracing_futures::Instrument;
 use vector_core::{
-    event::{BatchNotifier, BatchStatus, BatchStatusReceiver, Event},
+    event::{
+        proto as proto_event, BatchNotifier, BatchStatus, BatchStatusReceiver, Event, EventId,
+        LogEvent, Value,
+    },
     ByteSizeOf,
 };
 
 use crate::{
-    config::{AcknowledgementsConfig, DataType, GenerateConfig, Resource, SourceContext},
-    internal_events::{EventsReceived, TcpBytesReceived},
+    common::drop_summary::{self, DropReason, DropReporter},
+    config::{
+        check_non_zero, AcknowledgementsConfig, ConfigError, ConfigErrors, DataType, DryRunReport,
+        GenerateConfig, LogSchema, LogSchemaOverrides, Resource, SourceContext,
+    },
+    internal_events::{
+        EventSizeObserved, EventsReceived, VectorChunkedEventDropped, VectorDeltaBaselineMissing,
+        VectorDeltaEventsReceived, VectorEventRejected, VectorRawPayloadSkipped,
+        VectorRequestBytesReceived, VectorSenderIdentified,
+    },
     proto::vector as proto,
     serde::bool_or_struct,
-    shutdown::ShutdownSignalToken,
-    sources::{util::AfterReadExt as _, Source},
+    shutdown::{ShutdownDrain, ShutdownSignal},
+    sources::{
+        vector::{
+            chunks::{ChunkError, Reassembler},
+            extra::{ExtraValues, MetadataTemplate},
+            rate_limit::{Rate, RateLimiter, SenderIdentity},
+            state::{SharedState, StateRegistry},
+        },
+        Source,
+    },
     tls::{MaybeTlsIncomingStream, MaybeTlsSettings, TlsConfig},
     Pipeline,
 };
@@ -27,6 +63,41 @@ use crate::{
 pub struct Service {
     pipeline: Pipeline,
     acknowledgements: bool,
+    log_schema: LogSchema,
+    timestamp_policy: TimestampPolicy,
+    host_key_policy: HostKeyPolicy,
+    sender: SenderConfig,
+    /// What is kept about each sender on each connection.
+    peers: Arc<SharedState<PeerKey, PeerState>>,
+    metadata_fields: BTreeMap<String, String>,
+    metadata_fields_overwrite: bool,
+    extra: ExtraValues,
+    max_request_bytes: Option<usize>,
+    max_fields_per_event: Option<usize>,
+    /// Only set when chunked events are accepted.
+    reassembler: Option<Arc<Mutex<Reassembler>>>,
+    /// Set once the source starts shutting down.
+    shutting_down: watch::Receiver<bool>,
+    /// Only set when the raw payloads of log events are stored.
+    raw_payload_field: Option<String>,
+    max_raw_payload_bytes: usize,
+    rejected_events: RejectedEventsConfig,
+    /// What was received since the heartbeat task last looked.
+    activity: Arc<Activity>,
+    /// Only set when heartbeats are sent.
+    heartbeat_interval: Option<Duration>,
+    mint_event_ids: bool,
+    /// Taken from `telemetry.event_size_histograms` of the global options.
+    event_size_histograms: bool,
+    /// The events deltas are based on. Only set when deltas are accepted.
+    deltas: Option<Arc<SharedState<DeltaKey, DeltaBaseline>>>,
+    /// Only set when senders are rate limited.
+    rate_limiter: Option<Arc<RateLimiter>>,
+    /// Where rejected events are reported, to be logged as one summary for
+    /// each reason every `drop_summary_interval`, when `rejected_events.behavior`
+    /// is `log`.
+    drops: DropReporter,
+    drop_summary_interval: Duration,
 }
 
 #[tonic::async_trait]
@@ -35,28 +106,175 @@ impl proto::Service for Service {
         &self,
         request: Request<proto::PushEventsRequest>,
     ) -> Result<Response<proto::PushEventsResponse>, Status> {
-        let mut events: Vec<Event> = request
-            .into_inner()
-            .events
-            .into_iter()
-            .map(Event::from)
-            .collect();
+        let remote_addr = request
+            .extensions()
+            .get::<MaybeTlsConnectInfo>()
+            .map(|info| info.remote_addr);
+        // tonic doesn't tell how large the message it decoded was, so it is encoded again. prost
+        // encodes a message the same way every time, so this is the size the sink counted.
+        emit!(&VectorRequestBytesReceived {
+            byte_size: request.get_ref().wire_size(),
+            peer_addr: remote_addr.map(|addr| addr.ip()),
+        });
+        self.activity.requests.fetch_add(1, Ordering::Relaxed);
+        if *self.shutting_down.borrow() {
+            return Err(shutting_down());
+        }
+
+        if let Some(max_request_bytes) = self.max_request_bytes {
+            let byte_size = request.get_ref().encoded_len();
+            if byte_size > max_request_bytes {
+                return Err(Status::invalid_argument(format!(
+                    "Request of {} bytes is larger than max_request_bytes ({}).",
+                    byte_size, max_request_bytes
+                )));
+            }
+        }
+
+        if let Some(rate_limiter) = &self.rate_limiter {
+            let info = request.extensions().get::<MaybeTlsConnectInfo>();
+            let sender = rate_limiter.identify(
+                info.map(|info| info.remote_addr.ip()),
+                info.and_then(|info| info.peer_certs.as_deref()),
+                request.metadata(),
+            );
+            if let Some(sender) = sender {
+                let message = request.get_ref();
+                rate_limiter
+                    .check(
+                        &sender,
+                        message.events_count(),
+                        message.wire_size(),
+                        Instant::now(),
+                    )
+                    .map_err(rate_limited)?;
+            }
+        }
+
+        let deadline = request_deadline(request.metadata());
+        let metadata_values = metadata_values(request.metadata(), &self.metadata_fields);
+        let mut extra = self.extra.render(request.metadata());
+        let proto::PushEventsRequest {
+            events,
+            sender,
+            chunks,
+            keyed_events,
+            delta_stream,
+        } = request.into_inner();
+        let keyed_count = keyed_events.len();
+        let mut rejected = Vec::new();
+        let decoded = self
+            .reassemble(chunks, &mut rejected)
+            .and_then(|reassembled| {
+                let keyed = self.resolve_keyed(&delta_stream, keyed_events, remote_addr)?;
+                let mut wrappers = events;
+                wrappers.extend(reassembled);
+                wrappers.extend(keyed);
+                Ok(wrappers
+                    .into_iter()
+                    .filter_map(|wrapper| self.decode_event(wrapper, &mut rejected))
+                    .collect::<Vec<_>>())
+            });
+        let rejected_count = rejected.len();
+        let dead_letters = self.handle_rejected(rejected, remote_addr);
+        let mut events = match decoded {
+            Ok(events) => events,
+            Err(status) => {
+                if !dead_letters.is_empty() {
+                    // The request fails either way, a closed pipeline only loses the record of
+                    // why.
+                    let _ = self.pipeline.clone().send_batch(dead_letters).await;
+                }
+                return Err(status);
+            }
+        };
+
+        if let Some(sender) = &sender {
+            self.log_sender(remote_addr, sender);
+        }
+        let sender_field = self
+            .sender
+            .field
+            .as_deref()
+            .zip(sender.as_ref().map(sender_value));
+
+        let peer = remote_addr.map(|addr| addr.ip().to_string());
+        let now = Utc::now();
+        for event in &mut events {
+            if self.mint_event_ids {
+                event.metadata_mut().ensure_event_id();
+            }
+            self.timestamp_policy
+                .apply(event, self.log_schema.timestamp_key(), now);
+            if let Some(peer) = &peer {
+                self.host_key_policy
+                    .apply(event, self.log_schema.host_key(), peer);
+            }
+            if let Event::Log(log) = event {
+                if let Some((field, sender)) = &sender_field {
+                    log.insert(*field, sender.clone());
+                }
+                for (field, value) in &metadata_values {
+                    if self.metadata_fields_overwrite || !log.contains(field) {
+                        log.insert(*field, value.clone());
+                    }
+                }
+            }
+            extra.apply(event);
+            if self.event_size_histograms {
+                emit!(&EventSizeObserved {
+                    byte_size: event.size_of()
+                });
+            }
+        }
+        extra.emit_omitted();
 
         emit!(&EventsReceived {
             count: events.len(),
             byte_size: events.size_of(),
         });
+        self.activity
+            .events
+            .fetch_add(events.len(), Ordering::Relaxed);
+        // Sent along with the accepted events, so that they are acknowledged with them.
+        events.extend(dead_letters);
+
+        // The client has already given up, don't push events into the pipeline
+        // for nothing.
+        check_deadline(deadline)?;
 
         let receiver = BatchNotifier::maybe_apply_to_events(self.acknowledgements, &mut events);
 
-        self.pipeline
-            .clone()
-            .send_all(&mut futures::stream::iter(events).map(Ok))
-            .map_err(|err| Status::unavailable(err.to_string()))
-            .and_then(|_| handle_batch_status(receiver))
-            .await?;
+        // Once started, the batch is sent in full even if this request is
+        // cancelled, so that a deadline elapsing halfway through never leaves it
+        // partially forwarded.
+        let mut pipeline = self.pipeline.clone();
+        tokio::spawn(async move { pipeline.send_batch(events).await })
+            .await
+            .map_err(|err| Status::internal(err.to_string()))?
+            .map_err(|err| {
+                // The pipeline is closed as the source shuts down.
+                if *self.shutting_down.borrow() {
+                    shutting_down()
+                } else {
+                    Status::unavailable(err.to_string())
+                }
+            })?;
 
-        Ok(Response::new(proto::PushEventsResponse {}))
+        // The events are forwarded by now, so the deadline elapsing from here on
+        // is answered with success rather than an error the client would retry,
+        // delivering them twice.
+        match deadline {
+            Some(deadline) => time::timeout_at(deadline, handle_batch_status(receiver))
+                .await
+                .unwrap_or(Ok(()))?,
+            None => handle_batch_status(receiver).await?,
+        }
+
+        Ok(Response::new(proto::PushEventsResponse {
+            keyed_events: keyed_count as u32,
+            rejected_events: rejected_count as u32,
+        }))
     }
 
     // TODO: figure out a way to determine if the current Vector instance is "healthy".
@@ -64,14 +282,628 @@ impl proto::Service for Service {
         &self,
         _: Request<proto::HealthCheckRequest>,
     ) -> Result<Response<proto::HealthCheckResponse>, Status> {
+        let mut capabilities = vec![proto::COMPRESSED_FIELDS_CAPABILITY.to_owned()];
+        if self.deltas.is_some() {
+            capabilities.push(proto::DELTA_CAPABILITY.to_owned());
+        }
         let message = proto::HealthCheckResponse {
             status: proto::ServingStatus::Serving.into(),
+            capabilities,
         };
 
         Ok(Response::new(message))
     }
 }
 
+impl Service {
+    fn log_sender(&self, remote_addr: Option<SocketAddr>, sender: &proto::Sender) {
+        if self.sender.log && self.first_contact(remote_addr, &sender.uuid) {
+            emit!(&VectorSenderIdentified {
+                remote_addr,
+                hostname: &sender.hostname,
+                version: &sender.version,
+                uuid: &sender.uuid,
+                labels: &sender.labels,
+            });
+        }
+    }
+
+    /// Whether this is the first request from the sender `uuid` on the
+    /// connection from `remote_addr`, since its state was last evicted.
+    fn first_contact(&self, remote_addr: Option<SocketAddr>, uuid: &str) -> bool {
+        let key = PeerKey {
+            remote_addr,
+            uuid: uuid.to_owned(),
+        };
+        self.peers.lock().update(key, Instant::now(), |peer| {
+            !std::mem::replace(&mut peer.identified, true)
+        })
+    }
+
+    /// Decode an event of a request, storing its encoded form in
+    /// `raw_payload_field` if it is a log event and that is enabled. Events of
+    /// a type this Vector doesn't know, as a newer one may send, and log
+    /// events of more than `max_fields_per_event` fields are added to
+    /// `rejected` instead.
+    ///
+    /// tonic doesn't keep the bytes it decoded, so the event is encoded again.
+    /// prost encodes a message the same way every time, so these are the bytes
+    /// the sender sent, apart from fields unknown to this Vector within
+    /// metrics, which prost drops.
+    fn decode_event(
+        &self,
+        wrapper: proto_event::EventWrapper,
+        rejected: &mut Vec<RejectedEvent>,
+    ) -> Option<Event> {
+        if wrapper.event.is_none() {
+            // The type of the event is in the unknown fields of the wrapper, so they are all
+            // there is of it.
+            rejected.push(RejectedEvent {
+                reason: DropReason::UnknownEventType,
+                error: "Event is of a type unknown to this Vector.".to_owned(),
+                event_id: EventId::from_slice(&wrapper.event_id).ok(),
+                payload: Bytes::from(wrapper.encode_to_vec()),
+            });
+            return None;
+        }
+
+        let raw_payload = match (&self.raw_payload_field, &wrapper.event) {
+            (Some(field), Some(proto_event::Event::Log(_))) => {
+                let byte_size = wrapper.encoded_len();
+                if byte_size > self.max_raw_payload_bytes {
+                    emit!(&VectorRawPayloadSkipped {
+                        byte_size,
+                        max_raw_payload_bytes: self.max_raw_payload_bytes,
+                    });
+                    None
+                } else {
+                    Some((field, Bytes::from(wrapper.encode_to_vec())))
+                }
+            }
+            _ => None,
+        };
+
+        let event_id = EventId::from_slice(&wrapper.event_id).ok();
+        let max_fields = self.max_fields_per_event.unwrap_or(usize::MAX);
+        let mut event = match wrapper.try_into_event(max_fields) {
+            Ok(event) => event,
+            Err(error) => {
+                // The payload is left out, holding on to it is what the limit is there to avoid.
+                rejected.push(RejectedEvent {
+                    reason: DropReason::TooManyFields,
+                    error: error.to_string(),
+                    event_id,
+                    payload: Bytes::new(),
+                });
+                return None;
+            }
+        };
+        if let Some((field, raw_payload)) = raw_payload {
+            event.as_mut_log().insert(field.as_str(), raw_payload);
+        }
+        Some(event)
+    }
+
+    /// The events completed by `chunks`. A chunk that can't be reassembled
+    /// fails the request, so that the sender can try again later when the
+    /// buffer is full. An event whose chunks were reassembled but don't decode
+    /// is added to `rejected` instead, and the others are still returned.
+    fn reassemble(
+        &self,
+        chunks: Vec<proto::EventChunk>,
+        rejected: &mut Vec<RejectedEvent>,
+    ) -> Result<Vec<proto_event::EventWrapper>, Status> {
+        if chunks.is_empty() {
+            return Ok(Vec::new());
+        }
+        let reassembler = self.reassembler.as_ref().ok_or_else(|| {
+            Status::invalid_argument("Chunked events are not accepted, see `chunking.enabled`.")
+        })?;
+
+        let mut reassembler = reassembler.lock().expect("mutex poisoned");
+        let now = Instant::now();
+        let mut events = Vec::new();
+        for chunk in chunks {
+            match reassembler.insert(chunk, now) {
+                Ok(event) => events.extend(event),
+                Err(error) => {
+                    let message = error.to_string();
+                    emit!(&VectorChunkedEventDropped {
+                        chunk_event_id: error.chunk_event_id(),
+                        error: &message,
+                        reason: error.reason(),
+                    });
+                    match error {
+                        ChunkError::Decode { data, .. } => rejected.push(RejectedEvent {
+                            reason: DropReason::DecodeFailed,
+                            error: message,
+                            event_id: None,
+                            payload: Bytes::from(data),
+                        }),
+                        ChunkError::BufferFull { .. } | ChunkError::TooManyEvents { .. } => {
+                            return Err(Status::resource_exhausted(message))
+                        }
+                        _ => return Err(Status::invalid_argument(message)),
+                    }
+                }
+            }
+        }
+        Ok(events)
+    }
+
+    /// The events of `keyed_events`, with their deltas applied to the events
+    /// of the same key they are based on, which are then replaced as the
+    /// baselines of the key. A keyed event of a kind unknown to this Vector is
+    /// returned as a wrapper without an event, to be rejected as such.
+    ///
+    /// Fails the request if a delta is based on an event the source doesn't
+    /// have, such as one that was evicted, for the sender to send the events
+    /// of the request again whole. Full events that were taken by then are
+    /// taken again from the new request.
+    fn resolve_keyed(
+        &self,
+        stream: &str,
+        keyed_events: Vec<proto::KeyedEvent>,
+        remote_addr: Option<SocketAddr>,
+    ) -> Result<Vec<proto_event::EventWrapper>, Status> {
+        if keyed_events.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut deltas = self.deltas.as_ref().map(|deltas| deltas.lock());
+        let now = Instant::now();
+        let mut wrappers = Vec::with_capacity(keyed_events.len());
+        let mut applied_deltas = 0;
+        for keyed in keyed_events {
+            let delta_key = DeltaKey {
+                stream: stream.to_owned(),
+                key: keyed.key,
+            };
+            let wrapper = match keyed.payload {
+                Some(proto::keyed_event::Payload::Full(wrapper)) => {
+                    if let (Some(deltas), Some(proto_event::Event::Log(log))) =
+                        (&mut deltas, &wrapper.event)
+                    {
+                        let fields = log.fields.clone();
+                        deltas.update(delta_key, now, |baseline| {
+                            *baseline = DeltaBaseline {
+                                sequence: keyed.sequence,
+                                fields,
+                            };
+                        });
+                    }
+                    wrapper
+                }
+                Some(proto::keyed_event::Payload::Delta(delta)) => {
+                    let baseline = delta.baseline;
+                    // A key without a baseline gets none, the sender has to send its event whole.
+                    let fields = deltas.as_mut().and_then(|deltas| {
+                        deltas
+                            .update_existing(delta_key, now, |baseline| {
+                                baseline.apply(keyed.sequence, &delta)
+                            })
+                            .flatten()
+                    });
+                    let fields = match fields {
+                        Some(fields) => fields,
+                        None => {
+                            emit!(&VectorDeltaBaselineMissing { remote_addr });
+                            return Err(delta_baseline_missing(baseline));
+                        }
+                    };
+                    applied_deltas += 1;
+                    proto_event::EventWrapper {
+                        event: Some(proto_event::Event::Log(proto_event::Log {
+                            fields,
+                            unknown_fields: Vec::new(),
+                        })),
+                        event_id: delta.event_id,
+                        unknown_fields: Vec::new(),
+                    }
+                }
+                None => proto_event::EventWrapper::default(),
+            };
+            wrappers.push(wrapper);
+        }
+        if applied_deltas > 0 {
+            emit!(&VectorDeltaEventsReceived {
+                count: applied_deltas
+            });
+        }
+        Ok(wrappers)
+    }
+
+    /// Record the events refused in a request as `rejected_events` says,
+    /// returning the dead letters to forward in their place if it says so.
+    fn handle_rejected(
+        &self,
+        rejected: Vec<RejectedEvent>,
+        remote_addr: Option<SocketAddr>,
+    ) -> Vec<Event> {
+        if rejected.is_empty() {
+            return Vec::new();
+        }
+        for event in &rejected {
+            emit!(&VectorEventRejected {
+                error: &event.error,
+                reason: event.reason.as_str(),
+                remote_addr,
+                event_id: event.event_id,
+            });
+            if self.rejected_events.behavior == RejectedEventsBehavior::Log {
+                self.drops
+                    .report(event.reason, event.event_id, &event.error);
+            }
+        }
+        if self.rejected_events.behavior != RejectedEventsBehavior::ForwardToField {
+            return Vec::new();
+        }
+
+        let now = Utc::now();
+        rejected
+            .into_iter()
+            .map(|event| Event::Log(self.dead_letter(event, remote_addr, now)))
+            .collect()
+    }
+
+    /// A log event recording a refused event: its encoded form as the
+    /// message, with why it was refused and whom from in
+    /// `rejected_events.field`.
+    fn dead_letter(
+        &self,
+        event: RejectedEvent,
+        remote_addr: Option<SocketAddr>,
+        now: DateTime<Utc>,
+    ) -> LogEvent {
+        let mut rejection = BTreeMap::new();
+        rejection.insert("reason".to_owned(), Value::from(event.reason.as_str()));
+        rejection.insert("error".to_owned(), Value::from(event.error));
+        if let Some(remote_addr) = remote_addr {
+            rejection.insert("peer_addr".to_owned(), Value::from(remote_addr.to_string()));
+        }
+        if let Some(event_id) = event.event_id {
+            rejection.insert("event_id".to_owned(), Value::from(event_id.to_string()));
+        }
+
+        let mut log = LogEvent::default();
+        log.insert(self.log_schema.message_key(), event.payload);
+        log.insert(self.log_schema.timestamp_key(), now);
+        log.insert(self.rejected_events.field.as_str(), Value::Map(rejection));
+        log
+    }
+}
+
+/// An event the source refused, in the form it was received in.
+#[derive(Debug)]
+struct RejectedEvent {
+    reason: DropReason,
+    error: String,
+    /// The id the sender gave the event, if it could be read.
+    event_id: Option<EventId>,
+    payload: Bytes,
+}
+
+/// How often partial chunked events are checked for having timed out.
+const CHUNK_EXPIRY_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Drop the chunked events that timed out, for as long as the source is
+/// running.
+async fn expire_chunked_events(reassembler: Weak<Mutex<Reassembler>>) {
+    let mut interval = time::interval(CHUNK_EXPIRY_INTERVAL);
+    loop {
+        interval.tick().await;
+        let reassembler = match reassembler.upgrade() {
+            Some(reassembler) => reassembler,
+            None => break,
+        };
+        let expired = reassembler
+            .lock()
+            .expect("mutex poisoned")
+            .expire(Instant::now());
+        for error in expired {
+            emit!(&VectorChunkedEventDropped {
+                chunk_event_id: error.chunk_event_id(),
+                error: &error.to_string(),
+                reason: error.reason(),
+            });
+        }
+    }
+}
+
+/// What the source received since the heartbeat task last looked.
+#[derive(Debug, Default)]
+struct Activity {
+    requests: AtomicUsize,
+    events: AtomicUsize,
+}
+
+/// Send a heartbeat into `pipeline` at the end of each `interval` in which no
+/// events arrived, until the source starts shutting down or has stopped. Each
+/// heartbeat counts what was received since the previous one.
+async fn send_heartbeats(
+    activity: Weak<Activity>,
+    mut pipeline: Pipeline,
+    log_schema: LogSchema,
+    address: SocketAddr,
+    interval: Duration,
+    mut shutting_down: watch::Receiver<bool>,
+) {
+    let mut ticks = time::interval_at(Instant::now() + interval, interval);
+    let (mut requests, mut events) = (0, 0);
+    loop {
+        tokio::select! {
+            _ = ticks.tick() => {}
+            changed = shutting_down.changed() => {
+                if changed.is_err() {
+                    break;
+                }
+            }
+        }
+        // No heartbeats while draining, the source isn't idle but going away.
+        if *shutting_down.borrow() {
+            break;
+        }
+        let events_in_interval = match activity.upgrade() {
+            Some(activity) => {
+                requests += activity.requests.swap(0, Ordering::Relaxed);
+                activity.events.swap(0, Ordering::Relaxed)
+            }
+            None => break,
+        };
+        events += events_in_interval;
+        if events_in_interval > 0 {
+            continue;
+        }
+
+        let heartbeat =
+            heartbeat_event(&log_schema, address, interval, events, requests, Utc::now());
+        if pipeline.send_batch(Some(heartbeat)).await.is_err() {
+            break;
+        }
+        requests = 0;
+        events = 0;
+    }
+}
+
+/// The event sent at the end of an `interval` in which no events arrived.
+/// `events` and `requests` were received since the previous heartbeat.
+fn heartbeat_event(
+    log_schema: &LogSchema,
+    address: SocketAddr,
+    interval: Duration,
+    events: usize,
+    requests: usize,
+    now: DateTime<Utc>,
+) -> Event {
+    let mut log = LogEvent::default();
+    log.insert(log_schema.message_key(), "Heartbeat");
+    log.insert(log_schema.timestamp_key(), now);
+    log.insert(log_schema.source_type_key(), "vector");
+    log.insert("heartbeat", true);
+    log.insert("address", address.to_string());
+    log.insert("interval_secs", interval.as_secs() as i64);
+    log.insert("events_received", events as i64);
+    log.insert("requests_received", requests as i64);
+    Event::Log(log)
+}
+
+/// A key of the events a sink sends as deltas.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+struct DeltaKey {
+    /// The `delta_stream` of the requests of the sink.
+    stream: String,
+    key: String,
+}
+
+impl ByteSizeOf for DeltaKey {
+    fn allocated_bytes(&self) -> usize {
+        self.stream.allocated_bytes() + self.key.allocated_bytes()
+    }
+}
+
+/// The last event of a key, which the next delta of the key is based on.
+#[derive(Debug, Default)]
+struct DeltaBaseline {
+    /// `0`, which no event has, until the first event of the key arrives.
+    sequence: u64,
+    fields: BTreeMap<String, proto_event::Value>,
+}
+
+impl DeltaBaseline {
+    /// Apply `delta` if it is based on this event, making the result the
+    /// event of `sequence` and returning its fields.
+    fn apply(
+        &mut self,
+        sequence: u64,
+        delta: &proto::EventDelta,
+    ) -> Option<BTreeMap<String, proto_event::Value>> {
+        if self.sequence == 0 || self.sequence != delta.baseline {
+            return None;
+        }
+        for field in &delta.removed {
+            self.fields.remove(field);
+        }
+        self.fields.extend(
+            delta
+                .changed
+                .iter()
+                .map(|(field, value)| (field.clone(), value.clone())),
+        );
+        self.sequence = sequence;
+        Some(self.fields.clone())
+    }
+}
+
+impl ByteSizeOf for DeltaBaseline {
+    // The generated messages can't tell the size they take in memory, so their encoded size
+    // stands in for it.
+    fn allocated_bytes(&self) -> usize {
+        self.fields
+            .iter()
+            .map(|(field, value)| field.allocated_bytes() + value.encoded_len())
+            .sum()
+    }
+}
+
+/// A sender on a connection, which the source keeps [`PeerState`] about.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+struct PeerKey {
+    remote_addr: Option<SocketAddr>,
+    uuid: String,
+}
+
+impl ByteSizeOf for PeerKey {
+    fn allocated_bytes(&self) -> usize {
+        self.uuid.allocated_bytes()
+    }
+}
+
+/// What the source keeps about a sender on a connection, for as long as
+/// `state` allows.
+#[derive(Debug, Default)]
+struct PeerState {
+    /// Whether the sender was identified already, and so logged if enabled.
+    identified: bool,
+}
+
+impl ByteSizeOf for PeerState {
+    fn allocated_bytes(&self) -> usize {
+        0
+    }
+}
+
+/// How often the state kept about connections and senders is checked for
+/// having expired.
+const STATE_EXPIRY_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Drop the entries of `state` not used within its TTL, such as the state of
+/// senders not heard from within `state.ttl_secs`, for as long as the source
+/// is running.
+async fn expire_state<K, V>(state: Weak<SharedState<K, V>>)
+where
+    K: Clone + Eq + std::hash::Hash + ByteSizeOf,
+    V: Default + ByteSizeOf,
+{
+    let mut interval = time::interval(STATE_EXPIRY_INTERVAL);
+    loop {
+        interval.tick().await;
+        let state = match state.upgrade() {
+            Some(state) => state,
+            None => break,
+        };
+        state.lock().expire(Instant::now());
+    }
+}
+
+/// The sender as the object added to events by `sender.field`.
+fn sender_value(sender: &proto::Sender) -> Value {
+    let labels = sender
+        .labels
+        .iter()
+        .map(|(label, value)| (label.clone(), Value::from(value.as_str())))
+        .collect::<BTreeMap<_, _>>();
+
+    let mut object = BTreeMap::new();
+    object.insert("hostname".to_owned(), Value::from(sender.hostname.as_str()));
+    object.insert("version".to_owned(), Value::from(sender.version.as_str()));
+    object.insert("uuid".to_owned(), Value::from(sender.uuid.as_str()));
+    object.insert("labels".to_owned(), Value::Map(labels));
+    Value::Map(object)
+}
+
+/// The values of the request metadata named by `metadata_fields`, with the
+/// field each goes into. Binary (`-bin`) values are decoded from base64.
+/// Missing metadata, and binary values that aren't valid base64, are skipped.
+fn metadata_values<'a>(
+    metadata: &MetadataMap,
+    metadata_fields: &'a BTreeMap<String, String>,
+) -> Vec<(&'a str, Value)> {
+    metadata_fields
+        .iter()
+        .filter_map(|(key, field)| {
+            let value = if key.ends_with("-bin") {
+                metadata.get_bin(key.as_str())?.to_bytes().ok()?
+            } else {
+                Bytes::copy_from_slice(metadata.get(key.as_str())?.as_encoded_bytes())
+            };
+            Some((field.as_str(), Value::Bytes(value)))
+        })
+        .collect()
+}
+
+/// Get the deadline set by the client through the `grpc-timeout` header.
+fn request_deadline(metadata: &MetadataMap) -> Option<Instant> {
+    let value = metadata.get("grpc-timeout")?.to_str().ok()?;
+    if value.len() < 2 {
+        return None;
+    }
+
+    let (amount, unit) = value.split_at(value.len() - 1);
+    let amount = amount.parse::<u64>().ok()?;
+    let timeout = match unit {
+        "H" => Duration::from_secs(amount.checked_mul(60 * 60)?),
+        "M" => Duration::from_secs(amount.checked_mul(60)?),
+        "S" => Duration::from_secs(amount),
+        "m" => Duration::from_millis(amount),
+        "u" => Duration::from_micros(amount),
+        "n" => Duration::from_nanos(amount),
+        _ => return None,
+    };
+    Some(Instant::now() + timeout)
+}
+
+fn check_deadline(deadline: Option<Instant>) -> Result<(), Status> {
+    match deadline {
+        Some(deadline) if Instant::now() >= deadline => {
+            Err(Status::deadline_exceeded("Request deadline elapsed"))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// How long senders are asked to wait before retrying a request refused because the source is
+/// shutting down, by when it has stopped or been started again.
+const SHUTTING_DOWN_PUSHBACK: Duration = Duration::from_secs(5);
+
+fn shutting_down() -> Status {
+    let mut metadata = MetadataMap::new();
+    metadata.insert(
+        proto::RETRY_PUSHBACK_KEY,
+        MetadataValue::from(SHUTTING_DOWN_PUSHBACK.as_millis() as u64),
+    );
+    Status::with_metadata(Code::Unavailable, proto::SHUTTING_DOWN_MESSAGE, metadata)
+}
+
+/// Refuse a request holding a delta based on the event with sequence number `baseline`, which the
+/// source doesn't have.
+fn delta_baseline_missing(baseline: u64) -> Status {
+    let mut metadata = MetadataMap::new();
+    metadata.insert(
+        proto::DELTA_BASELINE_MISSING_KEY,
+        MetadataValue::from(baseline),
+    );
+    Status::with_metadata(
+        Code::FailedPrecondition,
+        "Delta is based on an event the source doesn't have.",
+        metadata,
+    )
+}
+
+/// Refuse a request of a sender over its rate limit, telling it to wait
+/// `wait` before retrying.
+fn rate_limited(wait: Duration) -> Status {
+    let mut metadata = MetadataMap::new();
+    metadata.insert(
+        proto::RETRY_PUSHBACK_KEY,
+        MetadataValue::from(wait.as_millis().max(1) as u64),
+    );
+    Status::with_metadata(
+        Code::ResourceExhausted,
+        "Sender is over its rate limit.",
+        metadata,
+    )
+}
+
 async fn handle_batch_status(receiver: Option<BatchStatusReceiver>) -> Result<(), Status> {
     let status = match receiver {
         Some(receiver) => receiver.await,
@@ -85,132 +917,2426 @@ async fn handle_batch_status(receiver: Option<BatchStatusReceiver>) -> Result<()
     }
 }
 
-#[derive(Deserialize, Serialize, Debug, Clone)]
+/// How the time events are received at is recorded on them.
+#[derive(Clone, Debug, Derivative, Deserialize, PartialEq, Serialize)]
+#[derivative(Default)]
+#[serde(rename_all = "snake_case")]
+pub enum TimestampPolicy {
+    /// Keep events as the sender sent them, without or with a timestamp.
+    #[derivative(Default)]
+    Preserve,
+    /// Replace the timestamp of logs with the receive time. Metrics without a
+    /// timestamp get the receive time.
+    Overwrite,
+    /// Keep the timestamp set by the sender, if any, and store the receive
+    /// time of logs in the given field.
+    AddReceivedAt(String),
+}
+
+impl TimestampPolicy {
+    fn apply(&self, event: &mut Event, timestamp_key: &str, now: DateTime<Utc>) {
+        match event {
+            Event::Log(log) => match self {
+                Self::Preserve => {}
+                Self::Overwrite => {
+                    log.insert(timestamp_key, now);
+                }
+                Self::AddReceivedAt(field) => {
+                    log.insert(field.as_str(), now);
+                }
+            },
+            Event::Metric(metric) => {
+                if *self == Self::Overwrite && metric.timestamp().is_none() {
+                    metric.data_mut().timestamp = Some(now);
+                }
+            }
+        }
+    }
+}
+
+/// What is done with the host of events, so that the hosts they came through
+/// can be told apart from the one they were created on.
+#[derive(Clone, Debug, Derivative, Deserialize, PartialEq, Serialize)]
+#[derivative(Default)]
+#[serde(rename_all = "snake_case")]
+pub enum HostKeyPolicy {
+    /// Keep the host set by the sender.
+    #[derivative(Default)]
+    Preserve,
+    /// Replace the host of logs, and the host tag of metrics, with the address
+    /// of the peer that sent them.
+    OverwriteWithPeer,
+    /// Keep the host set by the sender, and append the address of the peer
+    /// that sent the events to the given field: an array of the hops of logs,
+    /// and a comma separated list in a tag of metrics.
+    AppendHop(String),
+}
+
+impl HostKeyPolicy {
+    fn apply(&self, event: &mut Event, host_key: &str, peer: &str) {
+        match (self, event) {
+            (Self::Preserve, _) => {}
+            (Self::OverwriteWithPeer, Event::Log(log)) => {
+                log.insert(host_key, peer);
+            }
+            (Self::OverwriteWithPeer, Event::Metric(metric)) => {
+                metric.insert_tag(host_key.to_owned(), peer.to_owned());
+            }
+            (Self::AppendHop(field), Event::Log(log)) => match log.get_mut(field.as_str()) {
+                Some(Value::Array(hops)) => hops.push(peer.into()),
+                Some(Value::Null) | None => {
+                    log.insert(field.as_str(), Value::Array(vec![peer.into()]));
+                }
+                // A single hop set by something else is kept as the first one.
+                Some(value) => {
+                    let first = std::mem::replace(value, Value::Null);
+                    *value = Value::Array(vec![first, peer.into()]);
+                }
+            },
+            (Self::AppendHop(field), Event::Metric(metric)) => {
+                let hops = match metric.tag_value(field) {
+                    Some(hops) => format!("{},{}", hops, peer),
+                    None => peer.to_owned(),
+                };
+                metric.insert_tag(field.clone(), hops);
+            }
+        }
+    }
+}
+
+/// Putting back together the events an upstream Vector split into chunks, see
+/// the `chunking` option of the `vector` sink.
+#[derive(Clone, Debug, Derivative, Deserialize, PartialEq, Serialize)]
+#[derivative(Default)]
 #[serde(deny_unknown_fields)]
-pub struct VectorConfig {
-    pub address: SocketAddr,
-    #[serde(default = "default_shutdown_timeout_secs")]
-    pub shutdown_timeout_secs: u64,
+pub struct ChunkingConfig {
     #[serde(default)]
-    tls: Option<TlsConfig>,
-    #[serde(default, deserialize_with = "bool_or_struct")]
-    acknowledgements: AcknowledgementsConfig,
+    enabled: bool,
+    /// How long the chunks of an event are held waiting for the rest of them.
+    #[derivative(Default(value = "default_chunk_timeout_secs()"))]
+    #[serde(default = "default_chunk_timeout_secs")]
+    timeout_secs: u64,
+    /// The most bytes of chunks held for events that aren't complete yet.
+    #[derivative(Default(value = "default_chunk_max_buffer_bytes()"))]
+    #[serde(default = "default_chunk_max_buffer_bytes")]
+    max_buffer_bytes: usize,
+    /// The most events that are held incomplete at once.
+    #[derivative(Default(value = "default_chunk_max_open_events()"))]
+    #[serde(default = "default_chunk_max_open_events")]
+    max_open_events: usize,
 }
 
-const fn default_shutdown_timeout_secs() -> u64 {
-    30
+const fn default_chunk_timeout_secs() -> u64 {
+    60
 }
 
-impl GenerateConfig for VectorConfig {
-    fn generate_config() -> toml::Value {
-        toml::Value::try_from(Self {
-            address: "0.0.0.0:6000".parse().unwrap(),
-            shutdown_timeout_secs: default_shutdown_timeout_secs(),
-            tls: None,
-            acknowledgements: AcknowledgementsConfig::default(),
+const fn default_chunk_max_buffer_bytes() -> usize {
+    100 * 1024 * 1024
+}
+
+const fn default_chunk_max_open_events() -> usize {
+    1_000
+}
+
+impl ChunkingConfig {
+    fn build(&self) -> Option<Arc<Mutex<Reassembler>>> {
+        self.enabled.then(|| {
+            Arc::new(Mutex::new(Reassembler::new(
+                self.max_buffer_bytes,
+                self.max_open_events,
+                Duration::from_secs(self.timeout_secs),
+            )))
         })
-        .unwrap()
     }
 }
 
-impl VectorConfig {
-    pub(super) async fn build(&self, cx: SourceContext) -> crate::Result<Source> {
-        let tls_settings = MaybeTlsSettings::from_config(&self.tls, true)?;
+/// What is done with the events the source refuses, such as those that fail
+/// to decode. The other events of the request they were in are accepted
+/// either way, and the response tells the sender how many were refused.
+#[derive(Clone, Debug, Derivative, Deserialize, PartialEq, Serialize)]
+#[derivative(Default)]
+#[serde(deny_unknown_fields)]
+pub struct RejectedEventsConfig {
+    #[serde(default)]
+    behavior: RejectedEventsBehavior,
+    /// The field of the forwarded log events that records why the event was
+    /// refused, and whom from.
+    #[derivative(Default(value = "default_rejected_events_field()"))]
+    #[serde(default = "default_rejected_events_field")]
+    field: String,
+}
 
-        let source = run(self.address, tls_settings, cx, self.acknowledgements).map_err(|error| {
-            error!(message = "Source future failed.", %error);
-        });
+fn default_rejected_events_field() -> String {
+    "rejected".to_owned()
+}
 
-        Ok(Box::pin(source))
-    }
+#[derive(Clone, Copy, Debug, Derivative, Deserialize, PartialEq, Serialize)]
+#[derivative(Default)]
+#[serde(rename_all = "snake_case")]
+pub enum RejectedEventsBehavior {
+    /// Only count them, logging them at debug level.
+    #[derivative(Default)]
+    Drop,
+    /// Log them as warnings, summed up as one line for each reason every
+    /// `drop_summary_interval_secs`.
+    Log,
+    /// Forward a log event into the pipeline for each of them, holding its
+    /// encoded form as the message and the rejection in `field`.
+    ForwardToField,
+}
 
-    pub(super) const fn output_type(&self) -> DataType {
-        DataType::Any
-    }
+/// Events sent into the pipeline while the source is idle, so that one that
+/// gets no traffic can be told from one that is broken downstream.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct HeartbeatConfig {
+    /// How long the source waits for events before sending a heartbeat, and
+    /// then between heartbeats. Nothing is sent if unset.
+    #[serde(default)]
+    interval_secs: Option<u64>,
+}
 
-    pub(super) const fn source_type(&self) -> &'static str {
-        "vector"
-    }
+/// Bounds on the state the source keeps about each connection and sender,
+/// such as whether the sender was already logged.
+#[derive(Clone, Debug, Derivative, Deserialize, PartialEq, Serialize)]
+#[derivative(Default)]
+#[serde(deny_unknown_fields)]
+pub struct StateConfig {
+    /// The most bytes of state kept in total. The state of the least recently
+    /// heard from senders is dropped to make room.
+    #[derivative(Default(value = "default_state_max_bytes()"))]
+    #[serde(default = "default_state_max_bytes")]
+    max_bytes: usize,
+    /// How long the state of a sender that isn't heard from is kept.
+    #[derivative(Default(value = "default_state_ttl_secs()"))]
+    #[serde(default = "default_state_ttl_secs")]
+    ttl_secs: u64,
+}
 
-    pub(super) fn resources(&self) -> Vec<Resource> {
-        vec![Resource::tcp(self.address)]
-    }
+const fn default_state_max_bytes() -> usize {
+    10 * 1024 * 1024
 }
 
-async fn run(
-    address: SocketAddr,
-    tls_settings: MaybeTlsSettings,
-    cx: SourceContext,
-    acknowledgements: AcknowledgementsConfig,
-) -> crate::Result<()> {
-    let _span = crate::trace::current_span();
+const fn default_state_ttl_secs() -> u64 {
+    60 * 60
+}
+
+impl StateConfig {
+    fn build<K, V>(&self, name: &'static str) -> Arc<SharedState<K, V>>
+    where
+        K: Clone + Eq + std::hash::Hash + ByteSizeOf,
+        V: Default + ByteSizeOf,
+    {
+        Arc::new(SharedState::new(StateRegistry::new(
+            name,
+            self.max_bytes,
+            Duration::from_secs(self.ttl_secs),
+        )))
+    }
+}
 
-    let service = proto::Server::new(Service {
-        pipeline: cx.out,
-        acknowledgements: acknowledgements.enabled,
-    });
-    let (tx, rx) = tokio::sync::oneshot::channel::<ShutdownSignalToken>();
+/// Accepting log events that upstream Vectors send as the changes from the
+/// previous event of the same key, see [`proto::KeyedEvent`]. Experimental.
+#[derive(Clone, Debug, Derivative, Deserialize, PartialEq, Serialize)]
+#[derivative(Default)]
+#[serde(deny_unknown_fields)]
+pub struct DeltaConfig {
+    #[serde(default)]
+    enabled: bool,
+    /// The most bytes of events kept as baselines in total. The baselines of
+    /// the least recently used keys are dropped to make room, and the events
+    /// based on them sent again whole.
+    #[derivative(Default(value = "default_state_max_bytes()"))]
+    #[serde(default = "default_state_max_bytes")]
+    max_bytes: usize,
+    /// How long the baseline of a key that isn't used is kept.
+    #[derivative(Default(value = "default_state_ttl_secs()"))]
+    #[serde(default = "default_state_ttl_secs")]
+    ttl_secs: u64,
+}
 
-    let listener = tls_settings.bind(&address).await?;
-    let stream = listener.accept_stream().map(|result| {
-        result.map(|socket| {
-            let peer_addr = socket.connect_info().remote_addr.ip();
-            socket.after_read(move |byte_size| {
-                emit!(&TcpBytesReceived {
-                    byte_size,
-                    peer_addr
-                })
-            })
+impl DeltaConfig {
+    fn build(&self) -> Option<Arc<SharedState<DeltaKey, DeltaBaseline>>> {
+        self.enabled.then(|| {
+            StateConfig {
+                max_bytes: self.max_bytes,
+                ttl_secs: self.ttl_secs,
+            }
+            .build("delta_baselines")
         })
-    });
+    }
+}
 
-    Server::builder()
-        .add_service(service)
-        .serve_with_incoming_shutdown(stream, cx.shutdown.map(|token| tx.send(token).unwrap()))
-        .await?;
+/// Limiting how fast each upstream Vector may send, so that one sending too
+/// much can't starve the others. Requests over the limit are refused with
+/// `ResourceExhausted`, and the time to wait before retrying them.
+#[derive(Clone, Debug, Derivative, Deserialize, PartialEq, Serialize)]
+#[derivative(Default)]
+#[serde(deny_unknown_fields)]
+pub struct RateLimitConfig {
+    #[serde(default)]
+    enabled: bool,
+    /// The events each sender may send per second, on average.
+    #[serde(default)]
+    events_per_sec: Option<u64>,
+    /// The most events a sender may send at once after being idle. Defaults
+    /// to `events_per_sec`.
+    #[serde(default)]
+    events_burst: Option<u64>,
+    /// The bytes of requests each sender may send per second, on average.
+    #[serde(default)]
+    bytes_per_sec: Option<u64>,
+    /// The most bytes a sender may send at once after being idle. Defaults to
+    /// `bytes_per_sec`.
+    #[serde(default)]
+    bytes_burst: Option<u64>,
+    /// What senders are told apart by, in order of precedence. Requests with
+    /// none of them aren't limited.
+    #[derivative(Default(value = "default_identify_by()"))]
+    #[serde(default = "default_identify_by")]
+    identify_by: Vec<SenderIdentity>,
+    /// The request metadata holding the token of the sender.
+    #[derivative(Default(value = "default_token_metadata_key()"))]
+    #[serde(default = "default_token_metadata_key")]
+    token_metadata_key: String,
+    /// The most senders the metrics of limited requests are tagged with.
+    /// Senders beyond these are tagged `other`.
+    #[derivative(Default(value = "default_max_metric_senders()"))]
+    #[serde(default = "default_max_metric_senders")]
+    max_metric_senders: usize,
+}
 
-    drop(rx.await);
+fn default_identify_by() -> Vec<SenderIdentity> {
+    vec![
+        SenderIdentity::Certificate,
+        SenderIdentity::Token,
+        SenderIdentity::PeerIp,
+    ]
+}
 
-    Ok(())
+fn default_token_metadata_key() -> String {
+    "authorization".to_owned()
 }
 
-#[derive(Clone)]
-pub struct MaybeTlsConnectInfo {
-    pub remote_addr: SocketAddr,
-    pub peer_certs: Option<Vec<Certificate>>,
+const fn default_max_metric_senders() -> usize {
+    100
 }
 
-impl Connected for MaybeTlsIncomingStream<TcpStream> {
-    type ConnectInfo = MaybeTlsConnectInfo;
+impl RateLimitConfig {
+    /// The limiter of the source, whose buckets are kept within the bounds
+    /// of `state`.
+    fn build(&self, state: &StateConfig) -> Option<Arc<RateLimiter>> {
+        let rate = |per_sec: Option<u64>, burst: Option<u64>| {
+            per_sec.map(|per_sec| Rate {
+                per_sec: per_sec as f64,
+                burst: burst.unwrap_or(per_sec) as f64,
+            })
+        };
+        self.enabled.then(|| {
+            Arc::new(RateLimiter {
+                events: rate(self.events_per_sec, self.events_burst),
+                bytes: rate(self.bytes_per_sec, self.bytes_burst),
+                identify_by: self.identify_by.clone(),
+                token_metadata_key: self.token_metadata_key.clone(),
+                senders: state.build("rate_limits"),
+                metric_senders: Mutex::default(),
+                max_metric_senders: self.max_metric_senders,
+            })
+        })
+    }
 
-    fn connect_info(&self) -> Self::ConnectInfo {
-        MaybeTlsConnectInfo {
-            remote_addr: self.peer_addr(),
-            peer_certs: self
-                .ssl_stream()
-                .and_then(|s| s.ssl().peer_cert_chain())
-                .map(|s| {
-                    s.into_iter()
-                        .filter_map(|c| c.to_pem().ok())
-                        .map(Certificate::from_pem)
-                        .collect()
-                }),
+    fn validate(&self, errors: &mut Vec<ConfigError>) {
+        if !self.enabled {
+            return;
+        }
+        if self.events_per_sec.is_none() && self.bytes_per_sec.is_none() {
+            errors.push(ConfigError::new(
+                "rate_limit.enabled",
+                "requires `rate_limit.events_per_sec` or `rate_limit.bytes_per_sec` to be set",
+            ));
+        }
+        check_non_zero(
+            errors,
+            [
+                ("rate_limit.events_per_sec", self.events_per_sec),
+                ("rate_limit.events_burst", self.events_burst),
+                ("rate_limit.bytes_per_sec", self.bytes_per_sec),
+                ("rate_limit.bytes_burst", self.bytes_burst),
+            ],
+        );
+        if self.identify_by.is_empty() {
+            errors.push(ConfigError::new(
+                "rate_limit.identify_by",
+                "must not be empty",
+            ));
+        }
+        if self.identify_by.contains(&SenderIdentity::Token)
+            && MetadataKey::<Ascii>::from_bytes(self.token_metadata_key.as_bytes()).is_err()
+        {
+            errors.push(ConfigError::new(
+                "rate_limit.token_metadata_key",
+                "is not a valid gRPC metadata key",
+            ));
         }
     }
 }
 
-#[cfg(feature = "sinks-vector")]
-#[cfg(test)]
+/// What is done with the identity an upstream Vector attaches to its requests.
+#[derive(Clone, Debug, Derivative, Deserialize, PartialEq, Serialize)]
+#[derivative(Default)]
+#[serde(deny_unknown_fields)]
+pub struct SenderConfig {
+    /// Log the sender at debug level the first time it is seen on a
+    /// connection.
+    #[derivative(Default(value = "true"))]
+    #[serde(default = "crate::serde::default_true")]
+    log: bool,
+    /// Add the sender to each log event it sent, as an object in this field.
+    #[serde(default)]
+    field: Option<String>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct VectorConfig {
+    pub address: SocketAddr,
+    #[serde(default = "default_shutdown_timeout_secs")]
+    pub shutdown_timeout_secs: u64,
+    #[serde(default)]
+    tls: Option<TlsConfig>,
+    #[serde(default, deserialize_with = "bool_or_struct")]
+    acknowledgements: AcknowledgementsConfig,
+    #[serde(default)]
+    log_schema: Option<LogSchemaOverrides>,
+    #[serde(default)]
+    timestamp_policy: TimestampPolicy,
+    #[serde(default)]
+    host_key_policy: HostKeyPolicy,
+    #[serde(default)]
+    sender: SenderConfig,
+    /// Request metadata copied into each log event of the request, from the
+    /// metadata key to the field.
+    #[serde(default)]
+    metadata_fields: BTreeMap<String, String>,
+    /// Replace fields the log events already have with the metadata values.
+    #[serde(default)]
+    metadata_fields_overwrite: bool,
+    /// Fields added to each log event, from the field, which may be a nested
+    /// path, to its value. `{{ header.<key> }}` in a value is replaced by the
+    /// request metadata of that key.
+    #[serde(default)]
+    extra_fields: BTreeMap<String, MetadataTemplate>,
+    /// Tags added to each metric, with values like those of `extra_fields`.
+    #[serde(default)]
+    extra_tags: BTreeMap<String, MetadataTemplate>,
+    /// Replace the fields and tags the events already have with those of
+    /// `extra_fields` and `extra_tags`.
+    #[serde(default)]
+    overwrite: bool,
+    /// The largest encoded request accepted. Larger requests are rejected
+    /// without any of their events being forwarded.
+    #[serde(default)]
+    max_request_bytes: Option<usize>,
+    /// The most fields of a log event accepted, counting those of nested
+    /// maps. Log events with more are rejected, see `rejected_events`.
+    #[serde(default)]
+    max_fields_per_event: Option<usize>,
+    #[serde(default)]
+    chunking: ChunkingConfig,
+    #[serde(default)]
+    state: StateConfig,
+    /// Store the encoded form of each log event, as it was received, in
+    /// `raw_payload_field`.
+    #[serde(default)]
+    store_raw_payload: bool,
+    #[serde(default = "default_raw_payload_field")]
+    raw_payload_field: String,
+    /// The largest encoded log event whose raw payload is stored. Larger ones
+    /// are forwarded without it.
+    #[serde(default = "default_max_raw_payload_bytes")]
+    max_raw_payload_bytes: usize,
+    #[serde(default)]
+    rejected_events: RejectedEventsConfig,
+    #[serde(default)]
+    heartbeat: HeartbeatConfig,
+    /// Give each event received without an id one, so that it can be traced
+    /// through the components it goes through from here on.
+    #[serde(default)]
+    mint_event_ids: bool,
+    #[serde(default)]
+    delta: DeltaConfig,
+    #[serde(default)]
+    rate_limit: RateLimitConfig,
+    /// How often the rejected events are logged, as one line for each
+    /// reason, when `rejected_events.behavior` is `log`.
+    #[serde(default = "drop_summary::default_interval_secs")]
+    drop_summary_interval_secs: u64,
+}
+
+const fn default_shutdown_timeout_secs() -> u64 {
+    30
+}
+
+fn default_raw_payload_field() -> String {
+    "raw_payload".to_owned()
+}
+
+const fn default_max_raw_payload_bytes() -> usize {
+    1024 * 1024
+}
+
+impl GenerateConfig for VectorConfig {
+    fn generate_config() -> toml::Value {
+        toml::Value::try_from(Self {
+            address: "0.0.0.0:6000".parse().unwrap(),
+            shutdown_timeout_secs: default_shutdown_timeout_secs(),
+            tls: Some(TlsConfig::example()),
+            acknowledgements: AcknowledgementsConfig::default(),
+            log_schema: None,
+            timestamp_policy: TimestampPolicy::default(),
+            host_key_policy: HostKeyPolicy::default(),
+            sender: SenderConfig::default(),
+            metadata_fields: BTreeMap::new(),
+            metadata_fields_overwrite: false,
+            extra_fields: BTreeMap::new(),
+            extra_tags: BTreeMap::new(),
+            overwrite: false,
+            max_request_bytes: None,
+            max_fields_per_event: None,
+            chunking: ChunkingConfig::default(),
+            state: StateConfig::default(),
+            store_raw_payload: false,
+            raw_payload_field: default_raw_payload_field(),
+            max_raw_payload_bytes: default_max_raw_payload_bytes(),
+            rejected_events: RejectedEventsConfig::default(),
+            heartbeat: HeartbeatConfig::default(),
+            mint_event_ids: false,
+            delta: DeltaConfig::default(),
+            rate_limit: RateLimitConfig::default(),
+            drop_summary_interval_secs: drop_summary::default_interval_secs(),
+        })
+        .unwrap()
+    }
+}
+
+impl VectorConfig {
+    pub(super) async fn build(&self, cx: SourceContext) -> crate::Result<Source> {
+        self.build_with_address(cx).await.map(|(source, _)| source)
+    }
+
+    /// Build the source, also returning a channel that receives the address
+    /// the source is listening on once it has bound its listener. This is the
+    /// way to find out which port was picked when `address` uses port `0`.
+    pub async fn build_with_address(
+        &self,
+        cx: SourceContext,
+    ) -> crate::Result<(Source, oneshot::Receiver<SocketAddr>)> {
+        self.validate().map_err(ConfigErrors)?;
+
+        let tls_settings = MaybeTlsSettings::from_config(&self.tls, true)?;
+
+        let (shutting_down_tx, shutting_down) = watch::channel(false);
+        let service = Service {
+            log_schema: cx.log_schema().clone(),
+            pipeline: cx.out,
+            acknowledgements: self.acknowledgements.enabled,
+            timestamp_policy: self.timestamp_policy.clone(),
+            host_key_policy: self.host_key_policy.clone(),
+            sender: self.sender.clone(),
+            peers: self.state.build("peers"),
+            metadata_fields: self.metadata_fields.clone(),
+            metadata_fields_overwrite: self.metadata_fields_overwrite,
+            extra: ExtraValues {
+                fields: self.extra_fields.clone(),
+                tags: self.extra_tags.clone(),
+                overwrite: self.overwrite,
+            },
+            max_request_bytes: self.max_request_bytes,
+            max_fields_per_event: self.max_fields_per_event,
+            reassembler: self.chunking.build(),
+            shutting_down,
+            raw_payload_field: self
+                .store_raw_payload
+                .then(|| self.raw_payload_field.clone()),
+            max_raw_payload_bytes: self.max_raw_payload_bytes,
+            rejected_events: self.rejected_events.clone(),
+            activity: Arc::default(),
+            heartbeat_interval: self.heartbeat.interval_secs.map(Duration::from_secs),
+            mint_event_ids: self.mint_event_ids,
+            event_size_histograms: cx.globals.telemetry.event_size_histograms,
+            deltas: self.delta.build(),
+            rate_limiter: self.rate_limit.build(&self.state),
+            drops: DropReporter::default(),
+            drop_summary_interval: Duration::from_secs(self.drop_summary_interval_secs),
+        };
+        let shutdown_timeout = Duration::from_secs(self.shutdown_timeout_secs);
+        let (bound_tx, bound_rx) = oneshot::channel();
+        let source = run(
+            self.address,
+            tls_settings,
+            service,
+            cx.shutdown,
+            shutting_down_tx,
+            shutdown_timeout,
+            bound_tx,
+        )
+        .map_err(|error| {
+            error!(message = "Source future failed.", %error);
+        });
+
+        Ok((Box::pin(source), bound_rx))
+    }
+
+    /// Do what building and starting the source would that can fail, short of
+    /// serving: check the options and load the TLS files. With
+    /// `bind_addresses` `address` is also bound, and let go of right away.
+    /// There is nothing for a source to connect to, so there is no
+    /// connectivity check.
+    pub(super) async fn dry_run(&self, bind_addresses: bool) -> DryRunReport {
+        let mut report = DryRunReport::default();
+        if let Err(errors) = self.validate() {
+            report.errors = errors;
+            return report;
+        }
+        report.passed.push("options");
+
+        let tls_settings =
+            match report.check("tls", "tls", MaybeTlsSettings::from_config(&self.tls, true)) {
+                Some(tls_settings) => tls_settings,
+                None => return report,
+            };
+        if bind_addresses {
+            // The listener is dropped as soon as it is bound, releasing the address.
+            report.check("bind", "address", tls_settings.bind(&self.address).await);
+        }
+        report
+    }
+
+    /// Check all the options at once, so that every problem is reported
+    /// together with the option it is about rather than building failing on
+    /// the first one.
+    fn validate(&self) -> Result<(), Vec<ConfigError>> {
+        let mut errors = TlsConfig::validate(&self.tls, true);
+
+        if self.sender.field.as_deref() == Some("") {
+            errors.push(ConfigError::new("sender.field", "must not be empty"));
+        }
+        if let TimestampPolicy::AddReceivedAt(field) = &self.timestamp_policy {
+            if field.is_empty() {
+                errors.push(ConfigError::new(
+                    "timestamp_policy.add_received_at",
+                    "must not be empty",
+                ));
+            } else if self.sender.field.as_ref() == Some(field) {
+                errors.push(ConfigError::new(
+                    "timestamp_policy.add_received_at",
+                    "must not be the same field as `sender.field`",
+                ));
+            }
+        }
+
+        if self.host_key_policy == HostKeyPolicy::AppendHop(String::new()) {
+            errors.push(ConfigError::new(
+                "host_key_policy.append_hop",
+                "must not be empty",
+            ));
+        }
+
+        if self.store_raw_payload && self.raw_payload_field.is_empty() {
+            errors.push(ConfigError::new("raw_payload_field", "must not be empty"));
+        }
+        if self.rejected_events.behavior == RejectedEventsBehavior::ForwardToField
+            && self.rejected_events.field.is_empty()
+        {
+            errors.push(ConfigError::new(
+                "rejected_events.field",
+                "must not be empty",
+            ));
+        }
+
+        check_non_zero(
+            &mut errors,
+            [
+                ("max_request_bytes", self.max_request_bytes),
+                ("max_fields_per_event", self.max_fields_per_event),
+                (
+                    "chunking.max_buffer_bytes",
+                    Some(self.chunking.max_buffer_bytes),
+                ),
+                (
+                    "chunking.max_open_events",
+                    Some(self.chunking.max_open_events),
+                ),
+                ("max_raw_payload_bytes", Some(self.max_raw_payload_bytes)),
+                ("state.max_bytes", Some(self.state.max_bytes)),
+            ],
+        );
+        check_non_zero(
+            &mut errors,
+            [
+                ("chunking.timeout_secs", Some(self.chunking.timeout_secs)),
+                ("state.ttl_secs", Some(self.state.ttl_secs)),
+                (
+                    "drop_summary_interval_secs",
+                    Some(self.drop_summary_interval_secs),
+                ),
+                ("heartbeat.interval_secs", self.heartbeat.interval_secs),
+            ],
+        );
+        self.rate_limit.validate(&mut errors);
+
+        for (key, field) in &self.metadata_fields {
+            let valid_key = if key.ends_with("-bin") {
+                MetadataKey::<Binary>::from_bytes(key.as_bytes()).is_ok()
+            } else {
+                MetadataKey::<Ascii>::from_bytes(key.as_bytes()).is_ok()
+            };
+            if !valid_key {
+                errors.push(ConfigError::new(
+                    format!("metadata_fields.{}", key),
+                    "is not a valid gRPC metadata key",
+                ));
+            }
+            if field.is_empty() {
+                errors.push(ConfigError::new(
+                    format!("metadata_fields.{}", key),
+                    "must not map to an empty field",
+                ));
+            }
+        }
+
+        let extra_names = [
+            ("extra_fields", &self.extra_fields),
+            ("extra_tags", &self.extra_tags),
+        ];
+        for (option, values) in extra_names {
+            if values.contains_key("") {
+                errors.push(ConfigError::new(option, "must not have an empty name"));
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    pub(super) const fn output_type(&self) -> DataType {
+        DataType::Any
+    }
+
+    pub(super) const fn source_type(&self) -> &'static str {
+        "vector"
+    }
+
+    pub(super) fn resources(&self) -> Vec<Resource> {
+        vec![Resource::tcp(self.address)]
+    }
+
+    pub(super) const fn log_schema(&self) -> Option<&LogSchemaOverrides> {
+        self.log_schema.as_ref()
+    }
+}
+
+async fn run(
+    address: SocketAddr,
+    tls_settings: MaybeTlsSettings,
+    service: Service,
+    shutdown: ShutdownSignal,
+    shutting_down: watch::Sender<bool>,
+    shutdown_timeout: Duration,
+    bound: oneshot::Sender<SocketAddr>,
+) -> crate::Result<()> {
+    let _span = crate::trace::current_span();
+
+    if let Some(reassembler) = &service.reassembler {
+        tokio::spawn(expire_chunked_events(Arc::downgrade(reassembler)).in_current_span());
+    }
+    tokio::spawn(expire_state(Arc::downgrade(&service.peers)).in_current_span());
+    service.drops.spawn(service.drop_summary_interval);
+    if let Some(deltas) = &service.deltas {
+        tokio::spawn(expire_state(Arc::downgrade(deltas)).in_current_span());
+    }
+    if let Some(rate_limiter) = &service.rate_limiter {
+        tokio::spawn(expire_state(Arc::downgrade(&rate_limiter.senders)).in_current_span());
+    }
+    let listener = tls_settings.bind(&address).await?;
+    let local_addr = listener.local_addr()?;
+    if let Some(interval) = service.heartbeat_interval {
+        tokio::spawn(
+            send_heartbeats(
+                Arc::downgrade(&service.activity),
+                service.pipeline.clone(),
+                service.log_schema.clone(),
+                local_addr,
+                interval,
+                service.shutting_down.clone(),
+            )
+            .in_current_span(),
+        );
+    }
+    let service = proto::Server::new(service);
+    let shutdown = shutdown.with_deadline(shutdown_timeout);
+    let (tx, rx) = oneshot::channel::<ShutdownDrain>();
+
+    // Nobody waiting for the address is fine.
+    let _ = bound.send(local_addr);
+    let server = Server::builder()
+        .add_service(service)
+        .serve_with_incoming_shutdown(
+            listener.accept_stream(),
+            shutdown.map(move |drain| {
+                // Refuse new requests from now on, rather than accepting ones whose events
+                // might not make it through the pipeline anymore.
+                let _ = shutting_down.send(true);
+                tx.send(drain).unwrap()
+            }),
+        );
+    tokio::pin!(server);
+
+    tokio::select! {
+        result = &mut server => result?,
+        Ok(drain) = rx => {
+            // Shutdown has begun, give in-flight requests until the deadline to complete.
+            match drain.run(&mut server).await {
+                Some(result) => result?,
+                None => warn!(
+                    message = "Source did not finish draining before shutdown timeout.",
+                    timeout_secs = shutdown_timeout.as_secs(),
+                ),
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[derive(Clone)]
+pub struct MaybeTlsConnectInfo {
+    pub remote_addr: SocketAddr,
+    pub peer_certs: Option<Vec<Certificate>>,
+}
+
+impl Connected for MaybeTlsIncomingStream<TcpStream> {
+    type ConnectInfo = MaybeTlsConnectInfo;
+
+    fn connect_info(&self) -> Self::ConnectInfo {
+        MaybeTlsConnectInfo {
+            remote_addr: self.peer_addr(),
+            peer_certs: self
+                .ssl_stream()
+                .and_then(|s| s.ssl().peer_cert_chain())
+                .map(|s| {
+                    s.into_iter()
+                        .filter_map(|c| c.to_pem().ok())
+                        .map(Certificate::from_pem)
+                        .collect()
+                }),
+        }
+    }
+}
+
+#[cfg(feature = "sinks-vector")]
+#[cfg(test)]
 mod tests {
+    use chrono::TimeZone;
+    use futures::StreamExt;
     use shared::assert_event_data_eq;
+    use vector_core::{
+        event::{LogEvent, Metric, MetricKind, MetricValue},
+        metrics::Controller,
+    };
+
+    use super::*;
+    use crate::{
+        config::{log_schema, SinkContext},
+        sinks::vector::v2::VectorConfig as SinkConfig,
+        test_util::{self, components},
+        tls::TEST_PEM_KEY_PATH,
+        Pipeline,
+    };
+
+    fn invalid_fields(config: &str) -> Vec<String> {
+        let config: VectorConfig = toml::from_str(config).unwrap();
+        match config.validate() {
+            Ok(()) => vec![],
+            Err(errors) => errors.into_iter().map(|error| error.field).collect(),
+        }
+    }
+
+    #[test]
+    fn validate_rate_limit() {
+        assert_eq!(
+            invalid_fields(
+                r#"
+                address = "127.0.0.1:0"
+                rate_limit.enabled = true
+                rate_limit.events_burst = 0
+                rate_limit.identify_by = []
+                "#
+            ),
+            vec![
+                "rate_limit.enabled",
+                "rate_limit.events_burst",
+                "rate_limit.identify_by",
+            ]
+        );
+        assert_eq!(
+            invalid_fields(
+                r#"
+                address = "127.0.0.1:0"
+                rate_limit.enabled = true
+                rate_limit.bytes_per_sec = 1000000
+                rate_limit.token_metadata_key = "X Token"
+                "#
+            ),
+            vec!["rate_limit.token_metadata_key"]
+        );
+    }
+
+    #[tokio::test]
+    async fn throttled_senders_leave_others_unaffected() {
+        let (pipeline, rx) = Pipeline::new_test();
+        let config = RateLimitConfig {
+            enabled: true,
+            events_per_sec: Some(1),
+            events_burst: Some(3),
+            ..RateLimitConfig::default()
+        };
+        let service = Service {
+            rate_limiter: config.build(&StateConfig::default()),
+            ..test_service(pipeline)
+        };
+        let from = |peer: &str, lines: &[&str]| {
+            let mut request = push_request(lines, "10S");
+            request.extensions_mut().insert(MaybeTlsConnectInfo {
+                remote_addr: peer.parse().unwrap(),
+                peer_certs: None,
+            });
+            request
+        };
+
+        proto::Service::push_events(&service, from("10.0.0.1:5000", &["a", "b", "c"]))
+            .await
+            .unwrap();
+        let status = proto::Service::push_events(&service, from("10.0.0.1:5001", &["d", "e"]))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), Code::ResourceExhausted);
+        let wait = status
+            .metadata()
+            .get(proto::RETRY_PUSHBACK_KEY)
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .parse::<u64>()
+            .unwrap();
+        assert!(wait > 1000 && wait <= 2000, "{}", wait);
+
+        // Another sender still has its whole burst.
+        proto::Service::push_events(&service, from("10.0.0.2:5000", &["f", "g", "h"]))
+            .await
+            .unwrap();
+
+        let output = test_util::collect_ready(rx).await;
+        let messages = output
+            .iter()
+            .map(|event| event.as_log()["message"].to_string_lossy())
+            .collect::<Vec<_>>();
+        assert_eq!(messages, ["a", "b", "c", "f", "g", "h"]);
+    }
+
+    #[test]
+    fn validate_reports_every_invalid_field() {
+        assert_eq!(
+            invalid_fields(
+                r#"
+                address = "127.0.0.1:0"
+                tls.enabled = true
+                tls.key_file = "/nonexistent/key.pem"
+                sender.field = ""
+                timestamp_policy.add_received_at = ""
+                host_key_policy.append_hop = ""
+                max_request_bytes = 0
+                max_fields_per_event = 0
+                chunking.timeout_secs = 0
+                metadata_fields = { "x-tenant-id" = "", "X Region" = "region", "x-id-bin" = "id" }
+                store_raw_payload = true
+                raw_payload_field = ""
+                max_raw_payload_bytes = 0
+                rejected_events.behavior = "forward_to_field"
+                rejected_events.field = ""
+                state.max_bytes = 0
+                state.ttl_secs = 0
+                drop_summary_interval_secs = 0
+                heartbeat.interval_secs = 0
+                extra_fields = { "" = "prod" }
+                extra_tags = { "" = "prod" }
+                "#
+            ),
+            vec![
+                "tls.key_file",
+                "tls.crt_file",
+                "sender.field",
+                "timestamp_policy.add_received_at",
+                "host_key_policy.append_hop",
+                "raw_payload_field",
+                "rejected_events.field",
+                "max_request_bytes",
+                "max_fields_per_event",
+                "max_raw_payload_bytes",
+                "state.max_bytes",
+                "chunking.timeout_secs",
+                "state.ttl_secs",
+                "drop_summary_interval_secs",
+                "heartbeat.interval_secs",
+                "metadata_fields.X Region",
+                "metadata_fields.x-tenant-id",
+                "extra_fields",
+                "extra_tags",
+            ]
+        );
+
+        // A server needs a certificate.
+        assert_eq!(
+            invalid_fields(
+                r#"
+                address = "127.0.0.1:0"
+                tls.enabled = true
+                sender.field = "sender"
+                timestamp_policy.add_received_at = "sender"
+                "#
+            ),
+            vec!["tls.crt_file", "timestamp_policy.add_received_at"]
+        );
+
+        assert_eq!(
+            invalid_fields(
+                r#"
+                address = "127.0.0.1:0"
+                sender.field = "sender"
+                timestamp_policy.add_received_at = "received_at"
+                "#
+            ),
+            Vec::<String>::new()
+        );
+    }
+
+    fn report_fields(report: &DryRunReport) -> Vec<&str> {
+        report
+            .errors
+            .iter()
+            .map(|error| error.field.as_str())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn dry_run_binds_and_releases_the_address() {
+        let config: VectorConfig =
+            toml::from_str(&format!(r#"address = "{}""#, test_util::next_addr())).unwrap();
+        let report = config.dry_run(true).await;
+        assert_eq!(report_fields(&report), Vec::<&str>::new());
+        assert_eq!(report.passed, vec!["options", "tls", "bind"]);
+
+        // The address was let go of, so binding it again works.
+        assert!(config.dry_run(true).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn dry_run_reports_an_occupied_port() {
+        let address = test_util::next_addr();
+        let _listener = tokio::net::TcpListener::bind(address).await.unwrap();
+        let config: VectorConfig = toml::from_str(&format!(r#"address = "{}""#, address)).unwrap();
+        let report = config.dry_run(true).await;
+        assert_eq!(report_fields(&report), vec!["address"]);
+        assert_eq!(report.passed, vec!["options", "tls"]);
+
+        // Without binding, such as when validating the config of a running Vector, the port
+        // being in use doesn't matter.
+        let report = config.dry_run(false).await;
+        assert!(report.is_ok());
+        assert_eq!(report.passed, vec!["options", "tls"]);
+    }
+
+    #[tokio::test]
+    async fn dry_run_reports_a_bad_pem() {
+        let crt_file = test_util::temp_file();
+        std::fs::write(
+            &crt_file,
+            "-----BEGIN CERTIFICATE-----\nnot a certificate\n-----END CERTIFICATE-----\n",
+        )
+        .unwrap();
+        let config: VectorConfig = toml::from_str(&format!(
+            r#"
+            address = "{}"
+            tls.enabled = true
+            tls.crt_file = "{}"
+            tls.key_file = "{}"
+            "#,
+            test_util::next_addr(),
+            crt_file.display(),
+            TEST_PEM_KEY_PATH
+        ))
+        .unwrap();
+        let report = config.dry_run(true).await;
+        assert_eq!(report_fields(&report), vec!["tls.crt_file"]);
+        assert!(report.passed.is_empty());
+    }
+
+    #[test]
+    fn timestamp_policy_config() {
+        let parse = |policy: &str| {
+            toml::from_str::<VectorConfig>(&format!("address = \"127.0.0.1:0\"\n{}", policy))
+                .unwrap()
+                .timestamp_policy
+        };
+
+        assert_eq!(parse(""), TimestampPolicy::Preserve);
+        assert_eq!(
+            parse(r#"timestamp_policy = "overwrite""#),
+            TimestampPolicy::Overwrite
+        );
+        assert_eq!(
+            parse(r#"timestamp_policy.add_received_at = "received.at""#),
+            TimestampPolicy::AddReceivedAt("received.at".into())
+        );
+    }
+
+    #[test]
+    fn timestamp_policy_logs() {
+        let sent = Utc.ymd(2021, 1, 1).and_hms(0, 0, 0);
+        let now = Utc.ymd(2021, 1, 1).and_hms(0, 0, 5);
+        let apply = |policy: TimestampPolicy, timestamp: Option<DateTime<Utc>>| {
+            let mut log = LogEvent::default();
+            log.insert("message", "hello");
+            if let Some(timestamp) = timestamp {
+                log.insert("timestamp", timestamp);
+            }
+            let mut event = Event::Log(log);
+            policy.apply(&mut event, "timestamp", now);
+            event.into_log()
+        };
+
+        let log = apply(TimestampPolicy::Preserve, Some(sent));
+        assert_eq!(log["timestamp"], sent.into());
+        let log = apply(TimestampPolicy::Preserve, None);
+        assert!(!log.contains("timestamp"));
+        let mut log = LogEvent::default();
+        log.insert("timestamp", Value::Null);
+        let mut event = Event::Log(log.clone());
+        TimestampPolicy::Preserve.apply(&mut event, "timestamp", now);
+        assert_eq!(event.into_log(), log);
+
+        let log = apply(TimestampPolicy::Overwrite, Some(sent));
+        assert_eq!(log["timestamp"], now.into());
+        let log = apply(TimestampPolicy::Overwrite, None);
+        assert_eq!(log["timestamp"], now.into());
+
+        let policy = TimestampPolicy::AddReceivedAt("received.at".into());
+        let log = apply(policy.clone(), Some(sent));
+        assert_eq!(log["timestamp"], sent.into());
+        assert_eq!(log["received.at"], now.into());
+        let log = apply(policy, None);
+        assert!(!log.contains("timestamp"));
+        assert_eq!(log["received.at"], now.into());
+    }
+
+    #[test]
+    fn timestamp_policy_metrics() {
+        let sent = Utc.ymd(2021, 1, 1).and_hms(0, 0, 0);
+        let now = Utc.ymd(2021, 1, 1).and_hms(0, 0, 5);
+        let apply = |policy: TimestampPolicy, timestamp: Option<DateTime<Utc>>| {
+            let mut event = Event::Metric(
+                Metric::new(
+                    "requests",
+                    MetricKind::Incremental,
+                    MetricValue::Counter { value: 1.0 },
+                )
+                .with_timestamp(timestamp),
+            );
+            policy.apply(&mut event, "timestamp", now);
+            event.into_metric().timestamp()
+        };
+
+        assert_eq!(apply(TimestampPolicy::Preserve, Some(sent)), Some(sent));
+        assert_eq!(apply(TimestampPolicy::Preserve, None), None);
+
+        assert_eq!(apply(TimestampPolicy::Overwrite, Some(sent)), Some(sent));
+        assert_eq!(apply(TimestampPolicy::Overwrite, None), Some(now));
+
+        let policy = TimestampPolicy::AddReceivedAt("received_at".into());
+        assert_eq!(apply(policy.clone(), Some(sent)), Some(sent));
+        assert_eq!(apply(policy, None), None);
+    }
+
+    #[test]
+    fn host_key_policy_logs() {
+        let apply = |policy: HostKeyPolicy, log: &LogEvent| {
+            let mut event = Event::Log(log.clone());
+            policy.apply(&mut event, "source.host", "10.0.0.1");
+            event.into_log()
+        };
+        let mut log = LogEvent::from("hello");
+        log.insert("source.host", "origin");
+
+        assert_eq!(apply(HostKeyPolicy::Preserve, &log), log);
+
+        let overwritten = apply(HostKeyPolicy::OverwriteWithPeer, &log);
+        assert_eq!(overwritten["source.host"], "10.0.0.1".into());
+
+        // The array of hops is created on the first hop, nested fields included.
+        let appended = apply(
+            HostKeyPolicy::AppendHop("hops.forwarded_by".to_owned()),
+            &log,
+        );
+        assert_eq!(appended["source.host"], "origin".into());
+        assert_eq!(
+            appended["hops.forwarded_by"],
+            Value::Array(vec!["10.0.0.1".into()])
+        );
+        let appended = apply(
+            HostKeyPolicy::AppendHop("hops.forwarded_by".to_owned()),
+            &appended,
+        );
+        assert_eq!(
+            appended["hops.forwarded_by"],
+            Value::Array(vec!["10.0.0.1".into(), "10.0.0.1".into()])
+        );
+
+        // A single value already in the field becomes the first hop.
+        let mut log = LogEvent::from("hello");
+        log.insert("forwarded_by", "10.0.0.2");
+        let appended = apply(HostKeyPolicy::AppendHop("forwarded_by".to_owned()), &log);
+        assert_eq!(
+            appended["forwarded_by"],
+            Value::Array(vec!["10.0.0.2".into(), "10.0.0.1".into()])
+        );
+    }
+
+    #[test]
+    fn host_key_policy_metrics() {
+        let apply = |policy: HostKeyPolicy, metric: &Metric| {
+            let mut event = Event::Metric(metric.clone());
+            policy.apply(&mut event, "host", "10.0.0.1");
+            event.into_metric()
+        };
+        let metric = Metric::new(
+            "requests",
+            MetricKind::Incremental,
+            MetricValue::Counter { value: 1.0 },
+        )
+        .with_tags(Some(
+            vec![("host".to_owned(), "origin".to_owned())]
+                .into_iter()
+                .collect(),
+        ));
+
+        assert_eq!(apply(HostKeyPolicy::Preserve, &metric), metric);
+        assert_eq!(
+            apply(HostKeyPolicy::OverwriteWithPeer, &metric).tag_value("host"),
+            Some("10.0.0.1".to_owned())
+        );
+
+        let policy = HostKeyPolicy::AppendHop("forwarded_by".to_owned());
+        let appended = apply(policy.clone(), &metric);
+        assert_eq!(appended.tag_value("host"), Some("origin".to_owned()));
+        assert_eq!(
+            appended.tag_value("forwarded_by"),
+            Some("10.0.0.1".to_owned())
+        );
+        assert_eq!(
+            apply(policy, &appended).tag_value("forwarded_by"),
+            Some("10.0.0.1,10.0.0.1".to_owned())
+        );
+    }
+
+    fn push_request(lines: &[&str], grpc_timeout: &str) -> Request<proto::PushEventsRequest> {
+        let mut request = Request::new(proto::PushEventsRequest {
+            events: lines.iter().map(|line| Event::from(*line).into()).collect(),
+            sender: None,
+            ..Default::default()
+        });
+        request
+            .metadata_mut()
+            .insert("grpc-timeout", grpc_timeout.parse().unwrap());
+        request
+    }
+
+    fn test_service(pipeline: Pipeline) -> Service {
+        Service {
+            pipeline,
+            acknowledgements: false,
+            log_schema: LogSchema::default(),
+            timestamp_policy: TimestampPolicy::default(),
+            host_key_policy: HostKeyPolicy::default(),
+            sender: SenderConfig::default(),
+            peers: StateConfig::default().build("peers"),
+            metadata_fields: BTreeMap::new(),
+            metadata_fields_overwrite: false,
+            extra: ExtraValues::default(),
+            max_request_bytes: None,
+            max_fields_per_event: None,
+            reassembler: None,
+            shutting_down: watch::channel(false).1,
+            raw_payload_field: None,
+            max_raw_payload_bytes: default_max_raw_payload_bytes(),
+            rejected_events: RejectedEventsConfig::default(),
+            activity: Arc::default(),
+            heartbeat_interval: None,
+            mint_event_ids: false,
+            event_size_histograms: false,
+            deltas: None,
+            rate_limiter: None,
+            drops: DropReporter::default(),
+            drop_summary_interval: Duration::from_secs(drop_summary::default_interval_secs()),
+        }
+    }
+
+    #[test]
+    fn parses_grpc_timeout() {
+        let timeout = |value: &str| {
+            let mut metadata = MetadataMap::new();
+            metadata.insert("grpc-timeout", value.parse().unwrap());
+            let now = Instant::now();
+            request_deadline(&metadata).map(|deadline| deadline - now)
+        };
+
+        let close_to = |actual: Option<Duration>, expected: Duration| {
+            let actual = actual.unwrap();
+            assert!(actual >= expected && actual < expected + Duration::from_secs(1));
+        };
+        close_to(timeout("2H"), Duration::from_secs(2 * 60 * 60));
+        close_to(timeout("3M"), Duration::from_secs(3 * 60));
+        close_to(timeout("10S"), Duration::from_secs(10));
+        close_to(timeout("250m"), Duration::from_millis(250));
+        close_to(timeout("100u"), Duration::from_micros(100));
+        close_to(timeout("0n"), Duration::from_nanos(0));
+
+        assert_eq!(timeout("S"), None);
+        assert_eq!(timeout("10"), None);
+        assert_eq!(timeout("10x"), None);
+        assert_eq!(timeout("-1S"), None);
+        assert_eq!(request_deadline(&MetadataMap::new()), None);
+    }
+
+    #[tokio::test]
+    async fn elapsed_deadline_forwards_nothing() {
+        let (pipeline, mut rx) = Pipeline::new_test();
+        let service = test_service(pipeline);
+
+        let status = proto::Service::push_events(&service, push_request(&["a", "b"], "0n"))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), tonic::Code::DeadlineExceeded);
+        assert!(rx.try_next().is_err());
+    }
+
+    #[tokio::test]
+    async fn deadline_elapsing_in_slow_pipeline_forwards_whole_batch_once() {
+        // Room for a single event, so the batch can only be sent as the
+        // pipeline is read.
+        let (pipeline, mut rx) = Pipeline::new_with_buffer(1, vec![]);
+        let service = test_service(pipeline);
+
+        let lines = ["a", "b", "c", "d", "e"];
+        let reader = tokio::spawn(async move {
+            let mut received = Vec::new();
+            time::sleep(Duration::from_millis(300)).await;
+            while let Some(event) = rx.next().await {
+                received.push(event.as_log()["message"].to_string_lossy());
+                if received.len() == lines.len() {
+                    break;
+                }
+            }
+            (received, rx)
+        });
+
+        // Answered with success, so the sink has no reason to send the batch
+        // again.
+        proto::Service::push_events(&service, push_request(&lines, "100m"))
+            .await
+            .unwrap();
+
+        let (received, mut rx) = time::timeout(Duration::from_secs(5), reader)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(received, lines);
+        assert!(rx.try_next().is_err());
+    }
+
+    #[tokio::test]
+    async fn deadline_elapsing_before_acknowledgement_answers_success() {
+        let (pipeline, rx) = Pipeline::new_test();
+        let service = Service {
+            acknowledgements: true,
+            ..test_service(pipeline)
+        };
+
+        // The events are never acknowledged while `rx` holds them.
+        proto::Service::push_events(&service, push_request(&["a", "b"], "100m"))
+            .await
+            .unwrap();
+        assert_eq!(test_util::collect_ready(rx).await.len(), 2);
+    }
+
+    fn assert_shutting_down(status: &Status) {
+        assert_eq!(status.code(), Code::Unavailable);
+        assert_eq!(status.message(), proto::SHUTTING_DOWN_MESSAGE);
+        assert_eq!(
+            status.metadata().get(proto::RETRY_PUSHBACK_KEY).unwrap(),
+            "5000"
+        );
+    }
+
+    #[tokio::test]
+    async fn shutdown_refuses_requests() {
+        // Room for a single event, so that a request stays in flight until
+        // the pipeline is read or closed.
+        let (pipeline, rx) = Pipeline::new_with_buffer(1, vec![]);
+        let (shutting_down_tx, shutting_down) = watch::channel(false);
+        let service = Service {
+            shutting_down,
+            ..test_service(pipeline)
+        };
+
+        let in_flight = tokio::spawn({
+            let service = service.clone();
+            async move {
+                proto::Service::push_events(&service, push_request(&["a", "b", "c"], "10S")).await
+            }
+        });
+        time::sleep(Duration::from_millis(100)).await;
+
+        shutting_down_tx.send(true).unwrap();
+        let status = proto::Service::push_events(&service, push_request(&["d"], "10S"))
+            .await
+            .unwrap_err();
+        assert_shutting_down(&status);
+
+        // The pipeline closing fails the request in flight the same way.
+        drop(rx);
+        let status = in_flight.await.unwrap().unwrap_err();
+        assert_shutting_down(&status);
+    }
+
+    fn test_sender() -> proto::Sender {
+        proto::Sender {
+            hostname: "agent-1".to_owned(),
+            version: "0.20.0".to_owned(),
+            uuid: "2f1a".to_owned(),
+            labels: vec![("region".to_owned(), "eu".to_owned())]
+                .into_iter()
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn senders_are_identified_once_per_connection() {
+        let first: SocketAddr = "127.0.0.1:1000".parse().unwrap();
+        let second: SocketAddr = "127.0.0.1:1001".parse().unwrap();
+        let service = test_service(Pipeline::new_test().0);
+
+        assert!(service.first_contact(Some(first), "a"));
+        assert!(!service.first_contact(Some(first), "a"));
+        assert!(service.first_contact(Some(first), "b"));
+        assert!(service.first_contact(Some(second), "a"));
+        assert!(!service.first_contact(Some(second), "a"));
+        assert_eq!(service.peers.lock().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn sender_field_enriches_logs() {
+        let (pipeline, rx) = Pipeline::new_test();
+        let service = Service {
+            sender: SenderConfig {
+                log: false,
+                field: Some("sender".to_owned()),
+            },
+            ..test_service(pipeline)
+        };
+
+        let metric = Event::Metric(Metric::new(
+            "requests",
+            MetricKind::Incremental,
+            MetricValue::Counter { value: 1.0 },
+        ));
+        let request = Request::new(proto::PushEventsRequest {
+            events: vec![Event::from("hello").into(), metric.clone().into()],
+            sender: Some(test_sender()),
+            ..Default::default()
+        });
+        proto::Service::push_events(&service, request)
+            .await
+            .unwrap();
+        // Requests from senders that don't identify themselves are left alone.
+        proto::Service::push_events(&service, push_request(&["anonymous"], "10S"))
+            .await
+            .unwrap();
+
+        let output = test_util::collect_ready(rx).await;
+        assert_eq!(output.len(), 3);
+        let log = output[0].as_log();
+        assert_eq!(log["sender.hostname"], "agent-1".into());
+        assert_eq!(log["sender.version"], "0.20.0".into());
+        assert_eq!(log["sender.uuid"], "2f1a".into());
+        assert_eq!(log["sender.labels.region"], "eu".into());
+        assert_eq!(output[1], metric);
+        assert!(!output[2].as_log().contains("sender"));
+    }
+
+    fn metadata_request(metadata: &[(&'static str, &str)]) -> Request<proto::PushEventsRequest> {
+        let mut log = LogEvent::from("hello");
+        log.insert("tenant", "from event");
+        let mut request = push_request(&[], "10S");
+        request.get_mut().events.push(Event::Log(log).into());
+        for (key, value) in metadata {
+            if key.ends_with("-bin") {
+                request
+                    .metadata_mut()
+                    .insert_bin(*key, MetadataValue::from_bytes(value.as_bytes()));
+            } else {
+                request.metadata_mut().insert(*key, value.parse().unwrap());
+            }
+        }
+        request
+    }
+
+    fn metadata_service(pipeline: Pipeline, overwrite: bool) -> Service {
+        Service {
+            metadata_fields: vec![
+                ("x-tenant-id", "tenant"),
+                ("x-region", "cloud.region"),
+                ("x-trace-bin", "trace"),
+            ]
+            .into_iter()
+            .map(|(key, field)| (key.to_owned(), field.to_owned()))
+            .collect(),
+            metadata_fields_overwrite: overwrite,
+            ..test_service(pipeline)
+        }
+    }
+
+    #[tokio::test]
+    async fn metadata_fields_keep_existing_fields() {
+        let (pipeline, rx) = Pipeline::new_test();
+        let service = metadata_service(pipeline, false);
+
+        let request = metadata_request(&[("x-tenant-id", "acme"), ("x-region", "eu-west-1")]);
+        proto::Service::push_events(&service, request)
+            .await
+            .unwrap();
+
+        let output = test_util::collect_ready(rx).await;
+        assert_eq!(output.len(), 1);
+        let log = output[0].as_log();
+        assert_eq!(log["tenant"], "from event".into());
+        assert_eq!(log["cloud.region"], "eu-west-1".into());
+        // Absent metadata adds nothing.
+        assert!(!log.contains("trace"));
+    }
+
+    #[tokio::test]
+    async fn metadata_fields_overwrite_existing_fields() {
+        let (pipeline, rx) = Pipeline::new_test();
+        let service = metadata_service(pipeline, true);
+
+        let request = metadata_request(&[("x-tenant-id", "acme")]);
+        proto::Service::push_events(&service, request)
+            .await
+            .unwrap();
+
+        let output = test_util::collect_ready(rx).await;
+        assert_eq!(output.len(), 1);
+        let log = output[0].as_log();
+        assert_eq!(log["tenant"], "acme".into());
+        assert!(!log.contains("cloud"));
+    }
+
+    fn extra_service(pipeline: Pipeline, overwrite: bool) -> Service {
+        let templates = |values: &[(&str, &str)]| {
+            values
+                .iter()
+                .map(|(name, template)| {
+                    let template = MetadataTemplate::try_from(template.to_string()).unwrap();
+                    (name.to_string(), template)
+                })
+                .collect()
+        };
+        Service {
+            extra: ExtraValues {
+                fields: templates(&[
+                    ("cluster", "prod"),
+                    ("cloud.region", "{{ header.x-region }}"),
+                    ("tenant", "team-{{ header.x-team }}"),
+                ]),
+                tags: templates(&[("cluster", "prod"), ("region", "{{ header.x-region }}")]),
+                overwrite,
+            },
+            ..test_service(pipeline)
+        }
+    }
+
+    /// A request of a log with a `tenant` field, and a metric with a `region` tag.
+    fn extra_request(metadata: &[(&'static str, &str)]) -> Request<proto::PushEventsRequest> {
+        let mut request = metadata_request(metadata);
+        let metric = Metric::new(
+            "requests",
+            MetricKind::Incremental,
+            MetricValue::Counter { value: 1.0 },
+        )
+        .with_tags(Some(
+            vec![("region".to_owned(), "from metric".to_owned())]
+                .into_iter()
+                .collect(),
+        ));
+        request.get_mut().events.push(Event::Metric(metric).into());
+        request
+    }
+
+    #[tokio::test]
+    async fn extra_values_keep_existing_values() {
+        let (pipeline, rx) = Pipeline::new_test();
+        let service = extra_service(pipeline, false);
+
+        let request = extra_request(&[("x-region", "eu-west-1"), ("x-team", "blue")]);
+        proto::Service::push_events(&service, request)
+            .await
+            .unwrap();
+
+        let output = test_util::collect_ready(rx).await;
+        assert_eq!(output.len(), 2);
+        let log = output[0].as_log();
+        assert_eq!(log["cluster"], "prod".into());
+        assert_eq!(log["cloud.region"], "eu-west-1".into());
+        assert_eq!(log["tenant"], "from event".into());
+        let metric = output[1].as_metric();
+        assert_eq!(metric.tag_value("cluster"), Some("prod".to_owned()));
+        assert_eq!(metric.tag_value("region"), Some("from metric".to_owned()));
+    }
+
+    #[tokio::test]
+    async fn extra_values_overwrite_existing_values() {
+        let (pipeline, rx) = Pipeline::new_test();
+        let service = extra_service(pipeline, true);
+
+        let request = extra_request(&[("x-region", "eu-west-1"), ("x-team", "blue")]);
+        proto::Service::push_events(&service, request)
+            .await
+            .unwrap();
+
+        let output = test_util::collect_ready(rx).await;
+        assert_eq!(output.len(), 2);
+        assert_eq!(output[0].as_log()["tenant"], "team-blue".into());
+        assert_eq!(
+            output[1].as_metric().tag_value("region"),
+            Some("eu-west-1".to_owned())
+        );
+    }
+
+    #[tokio::test]
+    async fn extra_values_without_metadata_are_omitted() {
+        components::init_test();
+        let (pipeline, rx) = Pipeline::new_test();
+        let service = extra_service(pipeline, true);
+
+        let request = extra_request(&[("x-team", "blue")]);
+        proto::Service::push_events(&service, request)
+            .await
+            .unwrap();
+
+        let output = test_util::collect_ready(rx).await;
+        assert_eq!(output.len(), 2);
+        let log = output[0].as_log();
+        assert_eq!(log["tenant"], "team-blue".into());
+        assert!(!log.contains("cloud"));
+        // The tag the metric had is kept, even though `overwrite` is set.
+        assert_eq!(
+            output[1].as_metric().tag_value("region"),
+            Some("from metric".to_owned())
+        );
+        // `cloud.region` of the log, and the `region` tag of the metric.
+        assert_eq!(counter_total("source_extra_values_omitted_total"), 2.0);
+    }
+
+    #[tokio::test]
+    async fn unknown_proto_fields_are_passed_on() {
+        let (pipeline, rx) = Pipeline::new_test();
+        let service = test_service(pipeline);
+
+        // Fields a newer Vector added: a string as field 10 of `EventWrapper` and an integer as
+        // field 2 of `Log`.
+        let mut wrapper = proto_event::EventWrapper::from(Event::from("hello"));
+        wrapper.unknown_fields = vec![0x52, 4, b'e', b'd', b'g', b'e'];
+        if let Some(proto_event::Event::Log(log)) = &mut wrapper.event {
+            log.unknown_fields = vec![0x10, 42];
+        }
+        let mut request = push_request(&[], "10S");
+        request.get_mut().events.push(wrapper.clone());
+        proto::Service::push_events(&service, request)
+            .await
+            .unwrap();
+
+        let output = test_util::collect_ready(rx).await;
+        assert_eq!(output.len(), 1);
+        let forwarded = proto_event::EventWrapper::from(output[0].clone());
+        assert_eq!(forwarded.unknown_fields, wrapper.unknown_fields);
+        match forwarded.event {
+            Some(proto_event::Event::Log(log)) => assert_eq!(log.unknown_fields, vec![0x10, 42]),
+            event => panic!("unexpected event {:?}", event),
+        }
+    }
+
+    fn dead_letter_service(pipeline: Pipeline) -> Service {
+        Service {
+            reassembler: ChunkingConfig {
+                enabled: true,
+                ..ChunkingConfig::default()
+            }
+            .build(),
+            rejected_events: RejectedEventsConfig {
+                behavior: RejectedEventsBehavior::ForwardToField,
+                field: "rejected".to_owned(),
+            },
+            ..test_service(pipeline)
+        }
+    }
 
-    use super::*;
-    use crate::{
-        config::SinkContext,
-        sinks::vector::v2::VectorConfig as SinkConfig,
-        test_util::{self, components},
-        Pipeline,
-    };
+    fn from_peer(
+        mut request: Request<proto::PushEventsRequest>,
+    ) -> Request<proto::PushEventsRequest> {
+        request.extensions_mut().insert(MaybeTlsConnectInfo {
+            remote_addr: "10.0.0.1:5000".parse().unwrap(),
+            peer_certs: None,
+        });
+        request
+    }
+
+    #[tokio::test]
+    async fn undecodable_event_is_forwarded_as_dead_letter() {
+        let (pipeline, rx) = Pipeline::new_test();
+        let service = dead_letter_service(pipeline);
+
+        let data = vec![0xff, 0xff];
+        let mut request = push_request(&["hello"], "10S");
+        request.get_mut().chunks.push(proto::EventChunk {
+            event_id: "a".to_owned(),
+            index: 0,
+            total: 1,
+            data: data.clone(),
+        });
+        let response = proto::Service::push_events(&service, from_peer(request))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(response.rejected_events, 1);
+
+        // The valid event is forwarded, followed by the dead letter of the other.
+        let output = test_util::collect_ready(rx).await;
+        assert_eq!(output.len(), 2);
+        assert_eq!(output[0].as_log()["message"], "hello".into());
+        let log = output[1].as_log();
+        assert_eq!(log["message"], Value::from(Bytes::from(data)));
+        assert!(log.contains("timestamp"));
+        assert_eq!(log["rejected.reason"], "decode_failed".into());
+        assert!(log["rejected.error"]
+            .to_string_lossy()
+            .contains("Reassembled event a failed to decode"));
+        assert_eq!(log["rejected.peer_addr"], "10.0.0.1:5000".into());
+    }
+
+    #[tokio::test]
+    async fn unknown_event_type_is_forwarded_as_dead_letter() {
+        let (pipeline, rx) = Pipeline::new_test();
+        let service = dead_letter_service(pipeline);
+
+        // An event of a type a newer Vector added, as field 3 of the `event` oneof.
+        let event_id = EventId::new_v4();
+        let wrapper = proto_event::EventWrapper {
+            event: None,
+            event_id: event_id.as_bytes().to_vec(),
+            unknown_fields: vec![0x1a, 2, 0x08, 1],
+        };
+        let mut request = push_request(&["hello"], "10S");
+        request.get_mut().events.push(wrapper.clone());
+        let response = proto::Service::push_events(&service, from_peer(request))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(response.rejected_events, 1);
+
+        let output = test_util::collect_ready(rx).await;
+        assert_eq!(output.len(), 2);
+        assert_eq!(output[0].as_log()["message"], "hello".into());
+        let log = output[1].as_log();
+        let payload = match &log["message"] {
+            Value::Bytes(payload) => payload.clone(),
+            value => panic!("unexpected message {:?}", value),
+        };
+        assert_eq!(proto_event::EventWrapper::decode(payload).unwrap(), wrapper);
+        assert_eq!(log["rejected.reason"], "unknown_event_type".into());
+        assert_eq!(log["rejected.peer_addr"], "10.0.0.1:5000".into());
+        assert_eq!(log["rejected.event_id"], event_id.to_string().into());
+    }
+
+    #[tokio::test]
+    async fn log_with_too_many_fields_is_forwarded_as_dead_letter() {
+        let (pipeline, rx) = Pipeline::new_test();
+        let service = Service {
+            max_fields_per_event: Some(2),
+            ..dead_letter_service(pipeline)
+        };
+
+        let log = |count: usize| {
+            let log = (0..count)
+                .map(|i| (format!("field{}", i), Value::from("value")))
+                .collect::<BTreeMap<_, _>>();
+            proto_event::EventWrapper::from(Event::from(LogEvent::from(log)))
+        };
+        let mut request = push_request(&[], "10S");
+        request.get_mut().events.push(log(2));
+        proto::Service::push_events(&service, from_peer(request))
+            .await
+            .unwrap();
+
+        let event_id = EventId::new_v4();
+        let mut request = push_request(&[], "10S");
+        request.get_mut().events.push(proto_event::EventWrapper {
+            event_id: event_id.as_bytes().to_vec(),
+            ..log(3)
+        });
+        let response = proto::Service::push_events(&service, from_peer(request))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(response.rejected_events, 1);
+
+        // The fields of nested maps count too.
+        let mut nested = LogEvent::default();
+        nested.insert("nested.a", 1);
+        nested.insert("nested.b", 2);
+        let mut request = push_request(&[], "10S");
+        request
+            .get_mut()
+            .events
+            .push(proto_event::EventWrapper::from(Event::from(nested)));
+        let response = proto::Service::push_events(&service, from_peer(request))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(response.rejected_events, 1);
+
+        let output = test_util::collect_ready(rx).await;
+        assert_eq!(output.len(), 3);
+        assert!(output[0].as_log().contains("field1"));
+        let log = output[1].as_log();
+        assert_eq!(log["message"], Value::from(Bytes::new()));
+        assert_eq!(log["rejected.reason"], "too_many_fields".into());
+        assert_eq!(
+            log["rejected.error"],
+            "Event has more than 2 fields.".into()
+        );
+        assert_eq!(log["rejected.event_id"], event_id.to_string().into());
+        assert_eq!(
+            output[2].as_log()["rejected.reason"],
+            "too_many_fields".into()
+        );
+    }
+
+    #[tokio::test]
+    async fn rejected_events_are_dropped_by_default() {
+        let (pipeline, rx) = Pipeline::new_test();
+        let service = test_service(pipeline);
+
+        let mut request = push_request(&["hello"], "10S");
+        request
+            .get_mut()
+            .events
+            .push(proto_event::EventWrapper::default());
+        let response = proto::Service::push_events(&service, request)
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(response.rejected_events, 1);
+        let output = test_util::collect_ready(rx).await;
+        assert_eq!(output.len(), 1);
+        assert_eq!(output[0].as_log()["message"], "hello".into());
+    }
+
+    #[tokio::test]
+    async fn event_ids_are_kept_or_minted() {
+        let (pipeline, rx) = Pipeline::new_test();
+        let service = Service {
+            mint_event_ids: true,
+            ..test_service(pipeline)
+        };
+
+        let mut sent = Event::from("traced");
+        let event_id = sent.metadata_mut().ensure_event_id();
+        let mut request = push_request(&["untraced"], "10S");
+        request.get_mut().events.push(sent.into());
+        proto::Service::push_events(&service, request)
+            .await
+            .unwrap();
+
+        let output = test_util::collect_ready(rx).await;
+        assert_eq!(output.len(), 2);
+        let minted = output[0]
+            .metadata()
+            .event_id()
+            .expect("event id was minted");
+        assert_ne!(minted, event_id);
+        assert_eq!(output[1].metadata().event_id(), Some(event_id));
+    }
+
+    #[tokio::test]
+    async fn event_ids_are_not_minted_by_default() {
+        let (pipeline, rx) = Pipeline::new_test();
+        let service = test_service(pipeline);
+
+        proto::Service::push_events(&service, push_request(&["hello"], "10S"))
+            .await
+            .unwrap();
+        let output = test_util::collect_ready(rx).await;
+        assert_eq!(output[0].metadata().event_id(), None);
+    }
+
+    fn delta_service(pipeline: Pipeline, max_bytes: usize) -> Service {
+        Service {
+            deltas: DeltaConfig {
+                enabled: true,
+                max_bytes,
+                ..DeltaConfig::default()
+            }
+            .build(),
+            ..test_service(pipeline)
+        }
+    }
+
+    fn proto_fields(fields: &[(&str, &str)]) -> BTreeMap<String, proto_event::Value> {
+        let mut log = LogEvent::default();
+        for (field, value) in fields {
+            log.insert(*field, *value);
+        }
+        match proto_event::EventWrapper::from(Event::from(log)).event {
+            Some(proto_event::Event::Log(log)) => log.fields,
+            _ => unreachable!("log events are encoded as logs"),
+        }
+    }
+
+    fn keyed_request(
+        stream: &str,
+        sequence: u64,
+        payload: proto::keyed_event::Payload,
+    ) -> Request<proto::PushEventsRequest> {
+        Request::new(proto::PushEventsRequest {
+            keyed_events: vec![proto::KeyedEvent {
+                key: "a".to_owned(),
+                sequence,
+                payload: Some(payload),
+            }],
+            delta_stream: stream.to_owned(),
+            ..Default::default()
+        })
+    }
+
+    fn full(fields: &[(&str, &str)]) -> proto::keyed_event::Payload {
+        let mut log = LogEvent::default();
+        for (field, value) in fields {
+            log.insert(*field, *value);
+        }
+        proto::keyed_event::Payload::Full(Event::from(log).into())
+    }
+
+    fn delta(
+        baseline: u64,
+        changed: &[(&str, &str)],
+        removed: &[&str],
+    ) -> proto::keyed_event::Payload {
+        proto::keyed_event::Payload::Delta(proto::EventDelta {
+            baseline,
+            changed: proto_fields(changed),
+            removed: removed.iter().map(|field| (*field).to_owned()).collect(),
+            event_id: Vec::new(),
+        })
+    }
+
+    #[tokio::test]
+    async fn deltas_are_applied_to_their_baseline() {
+        let (pipeline, rx) = Pipeline::new_test();
+        let service = delta_service(pipeline, default_state_max_bytes());
+
+        let response = proto::Service::push_events(
+            &service,
+            keyed_request(
+                "s",
+                1,
+                full(&[("message", "one"), ("status", "up"), ("zone", "1")]),
+            ),
+        )
+        .await
+        .unwrap();
+        assert_eq!(response.get_ref().keyed_events, 1);
+        proto::Service::push_events(
+            &service,
+            keyed_request("s", 2, delta(1, &[("status", "down")], &["zone"])),
+        )
+        .await
+        .unwrap();
+        // The delta replaced the baseline of the key.
+        proto::Service::push_events(
+            &service,
+            keyed_request("s", 3, delta(2, &[("message", "three")], &[])),
+        )
+        .await
+        .unwrap();
+
+        let output = test_util::collect_ready(rx).await;
+        let fields = output
+            .iter()
+            .map(|event| {
+                let log = event.as_log();
+                (
+                    log["message"].to_string_lossy(),
+                    log["status"].to_string_lossy(),
+                    log.contains("zone"),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            fields,
+            vec![
+                ("one".to_owned(), "up".to_owned(), true),
+                ("one".to_owned(), "down".to_owned(), false),
+                ("three".to_owned(), "down".to_owned(), false),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn deltas_without_baseline_fail_the_request() {
+        let (pipeline, rx) = Pipeline::new_test();
+        let service = delta_service(pipeline, default_state_max_bytes());
+        proto::Service::push_events(&service, keyed_request("s", 1, full(&[("message", "one")])))
+            .await
+            .unwrap();
+
+        // Neither another stream nor another baseline of the key can be used.
+        for (stream, baseline) in [("other", 1), ("s", 2)] {
+            let status = proto::Service::push_events(
+                &service,
+                keyed_request(stream, 3, delta(baseline, &[("message", "two")], &[])),
+            )
+            .await
+            .unwrap_err();
+            assert_eq!(status.code(), Code::FailedPrecondition);
+            assert_eq!(
+                status.metadata().get(proto::DELTA_BASELINE_MISSING_KEY),
+                Some(&MetadataValue::from(baseline))
+            );
+        }
+        assert_eq!(test_util::collect_ready(rx).await.len(), 1);
+        // The delta of the other stream didn't leave an empty baseline behind.
+        assert_eq!(service.deltas.as_ref().unwrap().lock().len(), 1);
+
+        // Nor can a baseline that didn't fit.
+        let (pipeline, _rx) = Pipeline::new_test();
+        let service = delta_service(pipeline, 1);
+        proto::Service::push_events(&service, keyed_request("s", 1, full(&[("message", "one")])))
+            .await
+            .unwrap();
+        let status = proto::Service::push_events(
+            &service,
+            keyed_request("s", 2, delta(1, &[("message", "two")], &[])),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(status.code(), Code::FailedPrecondition);
+    }
+
+    async fn capabilities(service: Service) -> Vec<String> {
+        proto::Service::health_check(&service, Request::new(proto::HealthCheckRequest {}))
+            .await
+            .unwrap()
+            .into_inner()
+            .capabilities
+    }
+
+    #[tokio::test]
+    async fn deltas_are_advertised_when_accepted() {
+        let (pipeline, _rx) = Pipeline::new_test();
+        assert_eq!(
+            capabilities(test_service(pipeline)).await,
+            vec![proto::COMPRESSED_FIELDS_CAPABILITY.to_owned()]
+        );
+        let (pipeline, _rx) = Pipeline::new_test();
+        assert_eq!(
+            capabilities(delta_service(pipeline, default_state_max_bytes())).await,
+            vec![
+                proto::COMPRESSED_FIELDS_CAPABILITY.to_owned(),
+                proto::DELTA_CAPABILITY.to_owned()
+            ]
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn heartbeats_are_sent_while_idle() {
+        let (pipeline, mut rx) = Pipeline::new_test();
+        let service = test_service(pipeline.clone());
+        let (shutting_down_tx, shutting_down) = watch::channel(false);
+        let interval = Duration::from_secs(10);
+        let heartbeats = tokio::spawn(send_heartbeats(
+            Arc::downgrade(&service.activity),
+            pipeline,
+            LogSchema::default(),
+            "127.0.0.1:6000".parse().unwrap(),
+            interval,
+            shutting_down,
+        ));
+
+        // One heartbeat per idle interval.
+        time::sleep(interval * 2 + Duration::from_secs(1)).await;
+        let sent = test_util::collect_ready(&mut rx).await;
+        assert_eq!(sent.len(), 2);
+        let log = sent[0].as_log();
+        assert_eq!(log["heartbeat"], true.into());
+        assert_eq!(log["source_type"], "vector".into());
+        assert_eq!(log["address"], "127.0.0.1:6000".into());
+        assert_eq!(log["interval_secs"], 10.into());
+        assert_eq!(log["events_received"], 0.into());
+        assert_eq!(log["requests_received"], 0.into());
+
+        // Events arriving within an interval suppress its heartbeat, and are
+        // counted by the next one.
+        proto::Service::push_events(&service, push_request(&["a", "b"], "10S"))
+            .await
+            .unwrap();
+        assert_eq!(test_util::collect_ready(&mut rx).await.len(), 2);
+        time::sleep(interval).await;
+        assert!(test_util::collect_ready(&mut rx).await.is_empty());
+        time::sleep(interval).await;
+        let sent = test_util::collect_ready(&mut rx).await;
+        assert_eq!(sent.len(), 1);
+        assert_eq!(sent[0].as_log()["events_received"], 2.into());
+        assert_eq!(sent[0].as_log()["requests_received"], 1.into());
+
+        // Requests without events don't.
+        proto::Service::push_events(&service, push_request(&[], "10S"))
+            .await
+            .unwrap();
+        time::sleep(interval).await;
+        let sent = test_util::collect_ready(&mut rx).await;
+        assert_eq!(sent.len(), 1);
+        assert_eq!(sent[0].as_log()["events_received"], 0.into());
+        assert_eq!(sent[0].as_log()["requests_received"], 1.into());
+
+        // Nothing is sent once the source starts shutting down.
+        shutting_down_tx.send(true).unwrap();
+        heartbeats.await.unwrap();
+        time::sleep(interval * 2).await;
+        assert!(test_util::collect_ready(&mut rx).await.is_empty());
+    }
+
+    fn raw_payload_service(pipeline: Pipeline, max_raw_payload_bytes: usize) -> Service {
+        Service {
+            raw_payload_field: Some("raw".to_owned()),
+            max_raw_payload_bytes,
+            ..test_service(pipeline)
+        }
+    }
+
+    fn raw_payload_request(events: &[Event]) -> Request<proto::PushEventsRequest> {
+        let mut request = push_request(&[], "10S");
+        request
+            .get_mut()
+            .events
+            .extend(events.iter().cloned().map(Into::into));
+        request
+    }
+
+    #[tokio::test]
+    async fn raw_payload_decodes_to_received_event() {
+        let (pipeline, rx) = Pipeline::new_test();
+        let service = raw_payload_service(pipeline, default_max_raw_payload_bytes());
+
+        let mut log = LogEvent::from("hello");
+        log.insert("nested.field", vec![1, 2, 3]);
+        let metric = Event::Metric(Metric::new(
+            "requests",
+            MetricKind::Absolute,
+            MetricValue::Counter { value: 1.0 },
+        ));
+        let sent = vec![Event::Log(log), metric.clone()];
+        proto::Service::push_events(&service, raw_payload_request(&sent))
+            .await
+            .unwrap();
+
+        let output = test_util::collect_ready(rx).await;
+        assert_eq!(output.len(), 2);
+        let raw = match &output[0].as_log()["raw"] {
+            Value::Bytes(raw) => raw.clone(),
+            value => panic!("unexpected raw payload {:?}", value),
+        };
+        let decoded = Event::from(proto_event::EventWrapper::decode(raw.clone()).unwrap());
+        assert_event_data_eq!(decoded, sent[0].clone());
+        // The raw payload counts towards the size of the event.
+        assert!(output[0].size_of() > sent[0].size_of() + raw.len());
+        // Metrics have no fields to store it in.
+        assert_event_data_eq!(output[1].clone(), metric);
+    }
+
+    #[tokio::test]
+    async fn oversized_raw_payload_is_skipped() {
+        let (pipeline, rx) = Pipeline::new_test();
+        let service = raw_payload_service(pipeline, 128);
+
+        components::init_test();
+        let sent = vec![Event::from("short"), Event::from("x".repeat(200))];
+        proto::Service::push_events(&service, raw_payload_request(&sent))
+            .await
+            .unwrap();
+
+        let output = test_util::collect_ready(rx).await;
+        assert_eq!(output.len(), 2);
+        assert!(output[0].as_log().contains("raw"));
+        assert!(!output[1].as_log().contains("raw"));
+        assert_eq!(counter_total("raw_payloads_skipped_total"), 1.0);
+    }
+
+    #[test]
+    fn binary_metadata_is_decoded() {
+        let fields = vec![("x-trace-bin".to_owned(), "trace".to_owned())]
+            .into_iter()
+            .collect();
+        let values = |encoded: &'static str| {
+            let mut headers = http::HeaderMap::new();
+            headers.insert("x-trace-bin", http::HeaderValue::from_static(encoded));
+            metadata_values(&MetadataMap::from_headers(headers), &fields)
+        };
+
+        assert_eq!(
+            values("AAH/"),
+            vec![("trace", Value::Bytes(Bytes::from_static(b"\x00\x01\xff")))]
+        );
+        assert_eq!(values("not base64!"), vec![]);
+
+        let mut request = metadata_request(&[("x-trace-bin", "\u{0}raw")]);
+        let metadata = std::mem::take(request.metadata_mut());
+        assert_eq!(
+            metadata_values(&metadata, &fields),
+            vec![("trace", Value::Bytes(Bytes::from_static(b"\x00raw")))]
+        );
+    }
+
+    #[tokio::test]
+    async fn sender_round_trip() {
+        let addr = test_util::next_addr();
+        let config = format!(
+            r#"
+            address = "{}"
+            sender.field = "sender"
+            "#,
+            addr
+        );
+        let source: VectorConfig = toml::from_str(&config).unwrap();
+
+        let (tx, rx) = Pipeline::new_test();
+        tokio::spawn(source.build(SourceContext::new_test(tx)).await.unwrap());
+        test_util::wait_for_tcp(addr).await;
+
+        let config = format!(
+            r#"
+            address = "{}"
+            sender.labels.region = "eu"
+            "#,
+            addr
+        );
+        let sink: SinkConfig = toml::from_str(&config).unwrap();
+        let (sink, _) = sink.build(SinkContext::new_test()).await.unwrap();
+        sink.run(futures::stream::iter(vec![
+            Event::from("a"),
+            Event::from("b"),
+        ]))
+        .await
+        .unwrap();
+
+        let output = test_util::collect_ready(rx).await;
+        assert_eq!(output.len(), 2);
+        let sender = &output[0].as_log()["sender"];
+        assert_eq!(sender, &output[1].as_log()["sender"]);
+        let sender = match sender {
+            Value::Map(sender) => sender,
+            value => panic!("unexpected sender {:?}", value),
+        };
+        assert_eq!(
+            sender["hostname"],
+            crate::get_hostname().unwrap().as_str().into()
+        );
+        assert_eq!(
+            sender["version"],
+            crate::vector_version().to_string().into()
+        );
+        assert!(!sender["uuid"].to_string_lossy().is_empty());
+        assert_eq!(
+            sender["labels"],
+            Value::Map(
+                vec![("region".to_owned(), "eu".into())]
+                    .into_iter()
+                    .collect()
+            )
+        );
+    }
+
+    async fn send_with_chunking(addr: SocketAddr, chunking: bool, events: Vec<Event>) {
+        let config = format!(
+            r#"
+            address = "{}"
+            batch.max_bytes = 1048576
+            chunking.enabled = {}
+            chunking.chunk_bytes = 524288
+            "#,
+            addr, chunking
+        );
+        let sink: SinkConfig = toml::from_str(&config).unwrap();
+        let (sink, _) = sink.build(SinkContext::new_test()).await.unwrap();
+        sink.run(futures::stream::iter(events)).await.unwrap();
+    }
+
+    fn message_lengths(events: &[Event]) -> Vec<usize> {
+        let mut lengths = events
+            .iter()
+            .map(|event| event.as_log()["message"].as_bytes().len())
+            .collect::<Vec<_>>();
+        lengths.sort_unstable();
+        lengths
+    }
+
+    #[tokio::test]
+    async fn chunked_event_round_trip() {
+        let addr = test_util::next_addr();
+        let config = format!(
+            r#"
+            address = "{}"
+            max_request_bytes = 1048576
+            chunking.enabled = true
+            "#,
+            addr
+        );
+        let source: VectorConfig = toml::from_str(&config).unwrap();
+
+        let (tx, mut rx) = Pipeline::new_test();
+        tokio::spawn(source.build(SourceContext::new_test(tx)).await.unwrap());
+        test_util::wait_for_tcp(addr).await;
+
+        let message = "x".repeat(10 * 1024 * 1024);
+        let events = || {
+            vec![
+                Event::from("before"),
+                Event::from(message.as_str()),
+                Event::from("after"),
+            ]
+        };
+
+        // Without chunking, the request holding the large event is over the
+        // limit, and only the others get through.
+        send_with_chunking(addr, false, events()).await;
+        let output = test_util::collect_ready(&mut rx).await;
+        assert_eq!(message_lengths(&output), vec![5, 6]);
+
+        send_with_chunking(addr, true, events()).await;
+        let output = test_util::collect_ready(&mut rx).await;
+        assert_eq!(message_lengths(&output), vec![5, 6, message.len()]);
+        assert!(output
+            .iter()
+            .any(|event| event.as_log()["message"] == message.as_str().into()));
+    }
+
+    /// Send events of two keys, each changing a field of the previous event of
+    /// its key, with deltas enabled, and return the events the source got.
+    async fn delta_round_trip(source_options: &str, sink_options: &str) -> Vec<Event> {
+        let addr = test_util::next_addr();
+        let config = format!("address = \"{}\"\n{}", addr, source_options);
+        let source: VectorConfig = toml::from_str(&config).unwrap();
+
+        let (tx, rx) = Pipeline::new_test();
+        tokio::spawn(source.build(SourceContext::new_test(tx)).await.unwrap());
+        test_util::wait_for_tcp(addr).await;
+
+        let config = format!(
+            r#"
+            address = "{}"
+            batch.max_events = 2
+            delta.enabled = true
+            delta.key_field = "resource"
+            {}
+            "#,
+            addr, sink_options
+        );
+        let sink: SinkConfig = toml::from_str(&config).unwrap();
+        let (sink, _) = sink.build(SinkContext::new_test()).await.unwrap();
+        // Give the sink time to find out that the source takes deltas.
+        tokio::time::sleep(Duration::from_millis(500)).await;
+
+        let events = (0..20).map(|i| {
+            let mut log = LogEvent::from(format!("event {}", i));
+            log.insert("resource", if i % 2 == 0 { "a" } else { "b" });
+            log.insert("zone", "eu");
+            log.insert("status", if i % 4 < 2 { "up" } else { "down" });
+            Event::from(log)
+        });
+        sink.run(futures::stream::iter(events)).await.unwrap();
+        test_util::collect_ready(rx).await
+    }
+
+    fn assert_delta_events(output: &[Event]) {
+        assert_eq!(output.len(), 20);
+        let mut logs = output.iter().map(Event::as_log).collect::<Vec<_>>();
+        // Requests retried after a miss may arrive after the ones sent next.
+        logs.sort_by_key(|log| {
+            log["message"].to_string_lossy()[6..]
+                .parse::<usize>()
+                .unwrap()
+        });
+        for (i, log) in logs.into_iter().enumerate() {
+            assert_eq!(log["message"], format!("event {}", i).into());
+            assert_eq!(log["resource"], (if i % 2 == 0 { "a" } else { "b" }).into());
+            assert_eq!(log["zone"], "eu".into());
+            assert_eq!(
+                log["status"],
+                (if i % 4 < 2 { "up" } else { "down" }).into()
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn delta_events_round_trip() {
+        components::init_test();
+        let output = delta_round_trip("delta.enabled = true", "").await;
+        assert_delta_events(&output);
+        // The events were sent as deltas, not only whole.
+        assert!(counter_total("delta_events_received_total") > 0.0);
+        assert_eq!(counter_total("delta_baseline_misses_total"), 0.0);
+    }
+
+    #[tokio::test]
+    async fn delta_events_recover_from_missing_baselines() {
+        components::init_test();
+        // No baseline fits, so every delta is sent again whole.
+        let output = delta_round_trip("delta.enabled = true\ndelta.max_bytes = 1", "").await;
+        assert_delta_events(&output);
+        assert!(counter_total("delta_baseline_misses_total") > 0.0);
+
+        // The sink forgetting the other key each time sends every event whole.
+        let output = delta_round_trip("delta.enabled = true", "delta.max_keys = 1").await;
+        assert_delta_events(&output);
+    }
+
+    #[tokio::test]
+    async fn delta_events_are_sent_whole_to_sources_without_deltas() {
+        let output = delta_round_trip("", "").await;
+        assert_delta_events(&output);
+    }
 
     #[tokio::test]
     async fn receive_message() {
@@ -239,4 +3365,237 @@ mod tests {
         let output = test_util::collect_ready(rx).await;
         assert_event_data_eq!(events, output);
     }
+
+    #[tokio::test]
+    async fn reports_bound_address() {
+        let config = r#"address = "127.0.0.1:0""#;
+        let source: VectorConfig = toml::from_str(config).unwrap();
+
+        let (tx, rx) = Pipeline::new_test();
+        let (server, bound) = source
+            .build_with_address(SourceContext::new_test(tx))
+            .await
+            .unwrap();
+        tokio::spawn(server);
+
+        let addr = bound.await.unwrap();
+        assert_ne!(addr.port(), 0);
+        assert_eq!(
+            source.resources(),
+            vec![Resource::tcp("127.0.0.1:0".parse().unwrap())]
+        );
+
+        send_untimestamped(addr, "hello").await;
+
+        let output = test_util::collect_ready(rx).await;
+        assert_eq!(output.len(), 1);
+        assert_eq!(output[0].as_log()["message"], "hello".into());
+    }
+
+    async fn send_untimestamped(addr: SocketAddr, message: &str) {
+        send_untimestamped_with(addr, message, SinkContext::new_test()).await;
+    }
+
+    async fn send_untimestamped_with(addr: SocketAddr, message: &str, cx: SinkContext) {
+        let config = format!(r#"address = "{}""#, addr);
+        let sink: SinkConfig = toml::from_str(&config).unwrap();
+        let (sink, _) = sink.build(cx).await.unwrap();
+
+        let mut log = LogEvent::default();
+        log.insert("message", message);
+        sink.run(futures::stream::once(async move { Event::Log(log) }))
+            .await
+            .unwrap();
+    }
+
+    /// Send `events` through a `vector` sink to a `vector` source with
+    /// `source_options`, returning the events the source forwards.
+    async fn hop(events: Vec<Event>, source_options: &str) -> Vec<Event> {
+        let addr = test_util::next_addr();
+        let config = format!("address = \"{}\"\n{}", addr, source_options);
+        let source: VectorConfig = toml::from_str(&config).unwrap();
+        let (tx, rx) = Pipeline::new_test();
+        tokio::spawn(source.build(SourceContext::new_test(tx)).await.unwrap());
+        test_util::wait_for_tcp(addr).await;
+
+        let config = format!(r#"address = "{}""#, addr);
+        let sink: SinkConfig = toml::from_str(&config).unwrap();
+        let (sink, _) = sink.build(SinkContext::new_test()).await.unwrap();
+        sink.run(futures::stream::iter(events)).await.unwrap();
+        test_util::collect_ready(rx).await
+    }
+
+    #[tokio::test]
+    async fn hops_are_appended_across_vectors() {
+        let mut log = LogEvent::from("hello");
+        log.insert(log_schema().host_key(), "origin");
+        let metric = Metric::new(
+            "requests",
+            MetricKind::Incremental,
+            MetricValue::Counter { value: 1.0 },
+        );
+        let options = r#"host_key_policy.append_hop = "forwarded_by""#;
+
+        let events = hop(vec![log.into(), metric.into()], options).await;
+        let events = hop(events, options).await;
+
+        assert_eq!(events.len(), 2);
+        let log = events[0].as_log();
+        assert_eq!(log[log_schema().host_key()], "origin".into());
+        assert_eq!(
+            log["forwarded_by"],
+            Value::Array(vec!["127.0.0.1".into(), "127.0.0.1".into()])
+        );
+        assert_eq!(
+            events[1].as_metric().tag_value("forwarded_by"),
+            Some("127.0.0.1,127.0.0.1".to_owned())
+        );
+    }
+
+    #[tokio::test]
+    async fn per_source_log_schema() {
+        let (tx, rx) = Pipeline::new_test();
+
+        // Stands in for the global schema that sources without overrides use.
+        let mut global = LogSchema::default();
+        global.set_host_key("origin".to_string());
+
+        let default_addr = test_util::next_addr();
+        let config = format!(
+            r#"
+            address = "{}"
+            timestamp_policy = "overwrite"
+            host_key_policy = "overwrite_with_peer"
+            "#,
+            default_addr
+        );
+        let source: VectorConfig = toml::from_str(&config).unwrap();
+        let mut cx = SourceContext::new_test(tx.clone());
+        cx.log_schema = Some(global.clone());
+        tokio::spawn(source.build(cx).await.unwrap());
+
+        let custom_addr = test_util::next_addr();
+        let config = format!(
+            r#"
+            address = "{}"
+            timestamp_policy = "overwrite"
+            host_key_policy = "overwrite_with_peer"
+            log_schema.timestamp_key = "ts"
+            "#,
+            custom_addr
+        );
+        let source: VectorConfig = toml::from_str(&config).unwrap();
+        let mut cx = SourceContext::new_test(tx);
+        cx.log_schema = source
+            .log_schema()
+            .map(|overrides| overrides.apply(&global));
+        tokio::spawn(source.build(cx).await.unwrap());
+
+        test_util::wait_for_tcp(default_addr).await;
+        test_util::wait_for_tcp(custom_addr).await;
+
+        send_untimestamped(default_addr, "default").await;
+        send_untimestamped(custom_addr, "custom").await;
+
+        let output = test_util::collect_ready(rx).await;
+        assert_eq!(output.len(), 2);
+
+        let default = output[0].as_log();
+        assert_eq!(default["message"], "default".into());
+        assert!(default.contains("timestamp"));
+        assert!(!default.contains("ts"));
+        assert_eq!(default["origin"], "127.0.0.1".into());
+
+        // Only the timestamp key is overridden, the host key is the global one.
+        let custom = output[1].as_log();
+        assert_eq!(custom["message"], "custom".into());
+        assert!(custom.contains("ts"));
+        assert!(!custom.contains("timestamp"));
+        assert_eq!(custom["origin"], "127.0.0.1".into());
+    }
+
+    fn counter_total(name: &str) -> f64 {
+        Controller::get()
+            .unwrap()
+            .capture_metrics()
+            .filter(|metric| metric.name() == name)
+            .map(|metric| match metric.value() {
+                MetricValue::Counter { value } => *value,
+                value => panic!("unexpected metric value {:?}", value),
+            })
+            .sum()
+    }
+
+    #[tokio::test]
+    async fn sink_and_source_count_the_same_bytes() {
+        let addr = test_util::next_addr();
+        let config = format!(r#"address = "{}""#, addr);
+        let source: VectorConfig = toml::from_str(&config).unwrap();
+
+        components::init_test();
+        let (tx, rx) = Pipeline::new_test();
+        tokio::spawn(source.build(SourceContext::new_test(tx)).await.unwrap());
+        test_util::wait_for_tcp(addr).await;
+
+        let config = format!(
+            r#"
+            address = "{}"
+            batch.max_events = 10
+            "#,
+            addr
+        );
+        let sink: SinkConfig = toml::from_str(&config).unwrap();
+        let (sink, _) = sink.build(SinkContext::new_test()).await.unwrap();
+        let (_, events) = test_util::random_events_with_stream(100, 25, None);
+        sink.run(events).await.unwrap();
+        assert_eq!(test_util::collect_ready(rx).await.len(), 25);
+
+        // The metrics are local to the thread of the test, which the sink and
+        // source both run on.
+        let sent = counter_total("component_sent_bytes_total");
+        assert!(sent > 0.0);
+        assert_eq!(sent, counter_total("component_received_bytes_total"));
+    }
+
+    /// How many sizes were observed, checking that they were put in the buckets
+    /// meant for sizes.
+    fn event_size_observations() -> u32 {
+        Controller::get()
+            .unwrap()
+            .capture_metrics()
+            .filter(|metric| metric.name() == "event_size_bytes")
+            .map(|metric| match metric.value() {
+                MetricValue::AggregatedHistogram { buckets, count, .. } => {
+                    assert_eq!(buckets[0].upper_limit, 64.0);
+                    *count
+                }
+                value => panic!("unexpected metric value {:?}", value),
+            })
+            .sum()
+    }
+
+    #[tokio::test]
+    async fn event_size_histograms() {
+        let addr = test_util::next_addr();
+        let config = format!(r#"address = "{}""#, addr);
+        let source: VectorConfig = toml::from_str(&config).unwrap();
+
+        components::init_test();
+        let (tx, rx) = Pipeline::new_test();
+        let mut cx = SourceContext::new_test(tx);
+        cx.globals.telemetry.event_size_histograms = true;
+        tokio::spawn(source.build(cx).await.unwrap());
+        test_util::wait_for_tcp(addr).await;
+
+        // Without the spans of a topology, the sink and the source share a
+        // series, so only one of them is switched on at first.
+        send_untimestamped(addr, "sink disabled").await;
+        assert_eq!(event_size_observations(), 1);
+
+        let mut cx = SinkContext::new_test();
+        cx.globals.telemetry.event_size_histograms = true;
+        send_untimestamped_with(addr, "sink enabled", cx).await;
+        assert_eq!(event_size_observations(), 3);
+        assert_eq!(test_util::collect_ready(rx).await.len(), 2);
+    }
 }
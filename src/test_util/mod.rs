@@ -50,6 +50,8 @@ const WAIT_FOR_MAX_MILLIS: u64 = 500; // The maximum time to pause before retryi
 #[cfg(test)]
 pub mod components;
 pub mod stats;
+#[cfg(all(test, feature = "sinks-vector"))]
+pub mod vector_grpc;
 
 #[macro_export]
 macro_rules! assert_downcast_matches {
@@ -0,0 +1,448 @@
+//! A stand-in for the downstream Vector of a `vector` sink that misbehaves on purpose. It answers
+//! slowly, fails requests with chosen status codes, resets connections or never answers, so the
+//! sink can be tested against a source that is slow, flaky or going away.
+
+use std::{
+    convert::Infallible,
+    io,
+    net::SocketAddr,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use futures::{future, StreamExt};
+use rand::{rngs::SmallRng, Rng, SeedableRng};
+use tokio::{
+    io::{AsyncRead, AsyncWrite, ReadBuf},
+    net::{TcpListener, TcpStream},
+    task::JoinHandle,
+};
+use tokio_stream::wrappers::TcpListenerStream;
+use tonic::{
+    body::BoxBody,
+    transport::{server::Connected, Body, NamedService, Server},
+    Code, Request, Response, Status,
+};
+use vector_core::event::Event;
+
+use crate::proto::vector as proto;
+
+/// How a [`FaultyVector`] misbehaves. By default it answers every request right away.
+#[derive(Clone, Debug, Default)]
+pub struct Faults {
+    latency: Duration,
+    jitter: Duration,
+    failure_rates: Vec<(Code, f64)>,
+    reset_every: Option<usize>,
+    withhold_responses: bool,
+    advertise_deltas: bool,
+    concurrency_per_connection: Option<usize>,
+    seed: u64,
+}
+
+impl Faults {
+    /// Wait `latency`, plus a random extra of up to `jitter`, before handling each request.
+    pub const fn with_latency(mut self, latency: Duration, jitter: Duration) -> Self {
+        self.latency = latency;
+        self.jitter = jitter;
+        self
+    }
+
+    /// Fail the given fraction of requests with `code`. The rates of all codes together must
+    /// not be over `1.0`.
+    pub fn with_failure_rate(mut self, code: Code, rate: f64) -> Self {
+        self.failure_rates.push((code, rate));
+        self
+    }
+
+    /// Reset the connection of every `requests`th request instead of answering it.
+    pub const fn with_reset_every(mut self, requests: usize) -> Self {
+        self.reset_every = Some(requests);
+        self
+    }
+
+    /// Never answer requests. Their events are still recorded.
+    pub const fn withholding_responses(mut self) -> Self {
+        self.withhold_responses = true;
+        self
+    }
+
+    /// Handle at most `requests` requests of a connection at once, the others waiting their turn
+    /// as they do at a busy downstream Vector.
+    pub const fn with_concurrency_per_connection(mut self, requests: usize) -> Self {
+        self.concurrency_per_connection = Some(requests);
+        self
+    }
+
+    /// Claim to accept deltas in health checks, while dropping the keyed events of requests as a
+    /// Vector that doesn't know about them does.
+    pub const fn advertising_deltas(mut self) -> Self {
+        self.advertise_deltas = true;
+        self
+    }
+
+    /// Seed the latency jitter and failures are drawn from, so that a test gets the same ones on
+    /// every run.
+    pub const fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+}
+
+/// What a [`FaultyVector`] did with a request.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Outcome {
+    Accepted,
+    Failed(Code),
+    Reset,
+    Withheld,
+}
+
+#[derive(Debug, Default)]
+struct Log {
+    requests: AtomicUsize,
+    health_checks: AtomicUsize,
+    outcomes: Mutex<Vec<Outcome>>,
+    events: Mutex<Vec<Event>>,
+    chunks: Mutex<Vec<proto::EventChunk>>,
+}
+
+#[derive(Clone)]
+struct FaultyService {
+    faults: Arc<Faults>,
+    rng: Arc<Mutex<SmallRng>>,
+    log: Arc<Log>,
+}
+
+impl FaultyService {
+    /// The latency and failure, if any, of the next request.
+    fn draw(&self) -> (Duration, Option<Code>) {
+        let mut rng = self.rng.lock().expect("mutex poisoned");
+        let jitter = self.faults.jitter.mul_f64(rng.gen());
+        let mut roll: f64 = rng.gen();
+        let failure = self.faults.failure_rates.iter().find_map(|(code, rate)| {
+            if roll < *rate {
+                Some(*code)
+            } else {
+                roll -= rate;
+                None
+            }
+        });
+        (self.faults.latency + jitter, failure)
+    }
+}
+
+#[tonic::async_trait]
+impl proto::Service for FaultyService {
+    async fn push_events(
+        &self,
+        request: Request<proto::PushEventsRequest>,
+    ) -> Result<Response<proto::PushEventsResponse>, Status> {
+        let count = self.log.requests.fetch_add(1, Ordering::Relaxed) + 1;
+        let connection = request.extensions().get::<Connection>().cloned();
+        let (latency, failure) = self.draw();
+        tokio::time::sleep(latency).await;
+
+        let outcome = if self
+            .faults
+            .reset_every
+            .map_or(false, |every| count % every == 0)
+        {
+            Outcome::Reset
+        } else if let Some(code) = failure {
+            Outcome::Failed(code)
+        } else if self.faults.withhold_responses {
+            Outcome::Withheld
+        } else {
+            Outcome::Accepted
+        };
+        if matches!(outcome, Outcome::Accepted | Outcome::Withheld) {
+            let request = request.into_inner();
+            let events = request.events.into_iter().map(Event::from);
+            self.log
+                .events
+                .lock()
+                .expect("mutex poisoned")
+                .extend(events);
+            self.log
+                .chunks
+                .lock()
+                .expect("mutex poisoned")
+                .extend(request.chunks);
+        }
+        self.log
+            .outcomes
+            .lock()
+            .expect("mutex poisoned")
+            .push(outcome);
+
+        match outcome {
+            Outcome::Accepted => Ok(Response::new(proto::PushEventsResponse::default())),
+            Outcome::Failed(code) => Err(Status::new(code, "Injected failure")),
+            Outcome::Reset => {
+                if let Some(connection) = connection {
+                    connection.reset();
+                }
+                Err(Status::unavailable("Connection reset"))
+            }
+            Outcome::Withheld => futures::future::pending().await,
+        }
+    }
+
+    async fn health_check(
+        &self,
+        _: Request<proto::HealthCheckRequest>,
+    ) -> Result<Response<proto::HealthCheckResponse>, Status> {
+        self.log.health_checks.fetch_add(1, Ordering::Relaxed);
+        let capabilities = if self.faults.advertise_deltas {
+            vec![proto::DELTA_CAPABILITY.to_owned()]
+        } else {
+            Vec::new()
+        };
+        Ok(Response::new(proto::HealthCheckResponse {
+            status: proto::ServingStatus::Serving.into(),
+            capabilities,
+        }))
+    }
+}
+
+/// Handed to each request of a connection, so that it can reset the connection.
+#[derive(Clone, Debug, Default)]
+struct Connection {
+    reset: Arc<AtomicBool>,
+}
+
+impl Connection {
+    fn reset(&self) {
+        self.reset.store(true, Ordering::Relaxed);
+    }
+
+    fn check(&self) -> io::Result<()> {
+        if self.reset.load(Ordering::Relaxed) {
+            Err(io::ErrorKind::ConnectionReset.into())
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// A connection that fails all reads and writes once it is reset, which makes the server drop
+/// it without answering the requests on it.
+struct ResettableStream {
+    inner: TcpStream,
+    connection: Connection,
+}
+
+impl Connected for ResettableStream {
+    type ConnectInfo = Connection;
+
+    fn connect_info(&self) -> Self::ConnectInfo {
+        self.connection.clone()
+    }
+}
+
+impl AsyncRead for ResettableStream {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        if let Err(error) = self.connection.check() {
+            return Poll::Ready(Err(error));
+        }
+        Pin::new(&mut self.inner).poll_read(cx, buf)
+    }
+}
+
+impl AsyncWrite for ResettableStream {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        if let Err(error) = self.connection.check() {
+            return Poll::Ready(Err(error));
+        }
+        Pin::new(&mut self.inner).poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        if let Err(error) = self.connection.check() {
+            return Poll::Ready(Err(error));
+        }
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_shutdown(cx)
+    }
+}
+
+/// A misbehaving downstream Vector serving on an ephemeral port of localhost, stopped when
+/// dropped.
+pub struct FaultyVector {
+    addr: SocketAddr,
+    log: Arc<Log>,
+    server: JoinHandle<()>,
+}
+
+impl FaultyVector {
+    pub async fn start(faults: Faults) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("failed to bind listener");
+        let addr = listener.local_addr().expect("listener has no address");
+
+        let log = Arc::new(Log::default());
+        let mut builder = Server::builder();
+        if let Some(requests) = faults.concurrency_per_connection {
+            builder = builder.concurrency_limit_per_connection(requests);
+        }
+        let service = FaultyService {
+            rng: Arc::new(Mutex::new(SmallRng::seed_from_u64(faults.seed))),
+            faults: Arc::new(faults),
+            log: Arc::clone(&log),
+        };
+        let incoming = TcpListenerStream::new(listener).map(|stream| {
+            stream.map(|inner| ResettableStream {
+                inner,
+                connection: Connection::default(),
+            })
+        });
+        let server = tokio::spawn(async move {
+            let result = builder
+                .add_service(proto::Server::new(service))
+                .serve_with_incoming(incoming)
+                .await;
+            if let Err(error) = result {
+                error!(message = "Faulty Vector failed.", %error);
+            }
+        });
+
+        Self { addr, log, server }
+    }
+
+    pub const fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// The number of requests received so far, including those not handled yet.
+    pub fn requests(&self) -> usize {
+        self.log.requests.load(Ordering::Relaxed)
+    }
+
+    /// The number of health checks answered so far.
+    pub fn health_checks(&self) -> usize {
+        self.log.health_checks.load(Ordering::Relaxed)
+    }
+
+    /// What was done with each request handled so far, in the order they were handled.
+    pub fn outcomes(&self) -> Vec<Outcome> {
+        self.log.outcomes.lock().expect("mutex poisoned").clone()
+    }
+
+    /// The events of the accepted and withheld requests.
+    pub fn events(&self) -> Vec<Event> {
+        self.log.events.lock().expect("mutex poisoned").clone()
+    }
+
+    /// The event chunks of the accepted and withheld requests.
+    pub fn chunks(&self) -> Vec<proto::EventChunk> {
+        self.log.chunks.lock().expect("mutex poisoned").clone()
+    }
+}
+
+impl Drop for FaultyVector {
+    fn drop(&mut self) {
+        self.server.abort();
+    }
+}
+
+/// Some gRPC service other than Vector's, answering every call to it with an empty message.
+#[derive(Clone)]
+struct OtherService;
+
+impl NamedService for OtherService {
+    const NAME: &'static str = "test.Other";
+}
+
+impl tower::Service<http::Request<Body>> for OtherService {
+    type Response = http::Response<BoxBody>;
+    type Error = Infallible;
+    type Future = future::Ready<Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, _request: http::Request<Body>) -> Self::Future {
+        let response = http::Response::builder()
+            .header("grpc-status", "0") // OK
+            .header("content-type", "application/grpc")
+            .body(tonic::body::empty_body())
+            .expect("response should be valid");
+        future::ready(Ok(response))
+    }
+}
+
+/// A gRPC server of some other service than Vector's, as a sink pointed at the wrong port might
+/// find, serving on an ephemeral port of localhost and stopped when dropped. It answers the calls
+/// of the Vector service with `Unimplemented`, as any gRPC server does for services it doesn't
+/// have.
+pub struct OtherGrpcServer {
+    addr: SocketAddr,
+    requests: Arc<AtomicUsize>,
+    server: JoinHandle<()>,
+}
+
+impl OtherGrpcServer {
+    pub async fn start() -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("failed to bind listener");
+        let addr = listener.local_addr().expect("listener has no address");
+
+        let requests = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&requests);
+        let server = tokio::spawn(async move {
+            let result = Server::builder()
+                // Called for every request, including those to services the server doesn't have.
+                .trace_fn(move |_| {
+                    counter.fetch_add(1, Ordering::Relaxed);
+                    tracing::Span::none()
+                })
+                .add_service(OtherService)
+                .serve_with_incoming(TcpListenerStream::new(listener))
+                .await;
+            if let Err(error) = result {
+                error!(message = "Other gRPC server failed.", %error);
+            }
+        });
+
+        Self {
+            addr,
+            requests,
+            server,
+        }
+    }
+
+    pub const fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// The number of requests received so far, to any service.
+    pub fn requests(&self) -> usize {
+        self.requests.load(Ordering::Relaxed)
+    }
+}
+
+impl Drop for OtherGrpcServer {
+    fn drop(&mut self) {
+        self.server.abort();
+    }
+}
@@ -20,6 +20,7 @@ use super::{
     MaybeTls, NewCaStack, NewStoreBuilder, ParsePkcs12, Pkcs12Error, PrivateKeyParseError, Result,
     SetCertificate, SetPrivateKey, SetVerifyCert, TlsError, TlsIdentityError, X509ParseError,
 };
+use crate::config::ConfigError;
 
 const PEM_START_MARKER: &str = "-----BEGIN ";
 
@@ -45,6 +46,20 @@ impl TlsConfig {
         }
     }
 
+    /// A disabled configuration with placeholder file paths, to show the
+    /// available settings in generated configurations.
+    pub fn example() -> Self {
+        Self {
+            enabled: Some(false),
+            options: TlsOptions {
+                ca_file: Some("/path/to/ca.crt".into()),
+                crt_file: Some("/path/to/certificate.crt".into()),
+                key_file: Some("/path/to/certificate.key".into()),
+                ..TlsOptions::default()
+            },
+        }
+    }
+
     #[cfg(test)]
     pub fn test_config() -> Self {
         Self {
@@ -52,6 +67,21 @@ impl TlsConfig {
             options: TlsOptions::test_options(),
         }
     }
+
+    /// The problems with the files of `config`, if it enables TLS, with each
+    /// option named as a field of `tls`. See [`MaybeTlsSettings::from_config`]
+    /// for `for_server`.
+    pub fn validate(config: &Option<Self>, for_server: bool) -> Vec<ConfigError> {
+        match config {
+            Some(config) if config.enabled.unwrap_or(false) => config
+                .options
+                .validate_files(for_server)
+                .into_iter()
+                .map(|(option, error)| ConfigError::new(format!("tls.{}", option), error))
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
 }
 
 /// Standard TLS options
@@ -187,6 +217,55 @@ impl TlsSettings {
 }
 
 impl TlsOptions {
+    /// Check that each configured file can be read and parsed, returning every
+    /// problem found along with the option naming the file.
+    pub fn validate_files(&self, for_server: bool) -> Vec<(&'static str, TlsError)> {
+        let mut errors = Vec::new();
+        if let Err(error) = self.load_authorities() {
+            errors.push(("ca_file", error));
+        }
+        if let Some(key_file) = &self.key_file {
+            if let Err(error) = load_key(key_file, &self.key_pass) {
+                errors.push(("key_file", error));
+            }
+        }
+        match &self.crt_file {
+            Some(crt_file) => match self.check_certificate(crt_file) {
+                Ok(true) if self.key_file.is_none() => {
+                    errors.push(("key_file", TlsError::MissingKey))
+                }
+                Ok(_) => (),
+                Err(error) => errors.push(("crt_file", error)),
+            },
+            None if self.key_file.is_some() => {
+                errors.push(("crt_file", TlsError::MissingCrtKeyFile))
+            }
+            None if for_server => errors.push(("crt_file", TlsError::MissingRequiredIdentity)),
+            None => (),
+        }
+        errors
+    }
+
+    /// Check that `crt_file` holds a PKCS#12 archive or PEM certificates,
+    /// returning whether it needs a separate key file, as PEM certificates do.
+    fn check_certificate(&self, crt_file: &Path) -> Result<bool> {
+        let (data, filename) = open_read(crt_file, "certificate")?;
+        der_or_pem(
+            data,
+            |der| self.parse_pkcs12_identity(der).map(|_| false),
+            |pem| {
+                let certificates =
+                    X509::stack_from_pem(pem.as_bytes()).with_context(|| X509ParseError {
+                        filename: &filename,
+                    })?;
+                if certificates.is_empty() {
+                    return Err(TlsError::MissingCertificate);
+                }
+                Ok(true)
+            },
+        )
+    }
+
     fn load_authorities(&self) -> Result<Vec<X509>> {
         match &self.ca_file {
             None => Ok(vec![]),
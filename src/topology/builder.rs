@@ -135,6 +135,9 @@ pub async fn build_pieces(
 
     let mut errors = vec![];
 
+    config.global.telemetry.apply();
+    config.global.redaction.apply();
+
     let (enrichment_tables, enrichment_errors) = load_enrichment_tables(config, diff).await;
     errors.extend(enrichment_errors);
 
@@ -145,7 +148,12 @@ pub async fn build_pieces(
         .filter(|(key, _)| diff.sources.contains_new(key))
     {
         let (tx, rx) = futures::channel::mpsc::channel(1000);
-        let pipeline = Pipeline::from_sender(tx, vec![]);
+        let log_schema = source
+            .inner
+            .log_schema()
+            .map(|overrides| overrides.apply(&config.global.log_schema));
+        let pipeline = Pipeline::from_sender(tx, vec![])
+            .with_timestamp_timezone(log_schema.as_ref().unwrap_or(&config.global.log_schema));
 
         let typetag = source.inner.source_type();
 
@@ -157,6 +165,7 @@ pub async fn build_pieces(
             shutdown: shutdown_signal,
             out: pipeline,
             proxy: ProxyConfig::merge_with_env(&config.global.proxy, &source.proxy),
+            log_schema,
         };
         let server = match source.inner.build(context).await {
             Err(error) => {
@@ -74,18 +74,18 @@ impl FunctionTransform for Coercer {
             // below, as it will be fewer steps to fully recreate the
             // event than to scan the event for extraneous fields after
             // conversion.
-            let mut new_log = LogEvent::new_with_metadata(log.metadata().clone());
+            let mut old_fields = LogEvent::from(log.take_fields());
             for (field, conv) in &self.types {
-                if let Some(value) = log.remove(field) {
+                if let Some(value) = old_fields.remove(field) {
                     match conv.convert::<Value>(value.into_bytes()) {
                         Ok(converted) => {
-                            new_log.insert(field, converted);
+                            log.insert(field, converted);
                         }
                         Err(error) => emit!(&CoercerConversionFailed { field, error }),
                     }
                 }
             }
-            output.push(new_log.into());
+            output.push(log.into());
             return;
         } else {
             for (field, conv) in &self.types {
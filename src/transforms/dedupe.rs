@@ -2,6 +2,7 @@ use std::{future::ready, pin::Pin};
 
 use bytes::Bytes;
 use futures::{Stream, StreamExt};
+use lookup::{LookupBuf, SegmentBuf};
 use lru::LruCache;
 use serde::{Deserialize, Serialize};
 
@@ -10,7 +11,7 @@ use crate::{
         log_schema, DataType, GenerateConfig, TransformConfig, TransformContext,
         TransformDescription,
     },
-    event::{Event, Value},
+    event::{Event, PathComponent, PathIter, Value},
     internal_events::DedupeEventDiscarded,
     transforms::{TaskTransform, Transform},
 };
@@ -61,7 +62,7 @@ impl DedupeConfig {
 }
 
 pub struct Dedupe {
-    fields: FieldMatchConfig,
+    fields: FieldMatch,
     cache: LruCache<CacheEntry, bool>,
 }
 
@@ -116,7 +117,7 @@ type TypeId = u8;
 ///
 /// When ignoring fields, a CacheEntry contains a vector of 3-tuples. Each
 /// element in the vector represents one field in the corresponding LogEvent.
-/// The tuples will each contain the field name, TypeId, and data as Bytes for
+/// The tuples will each contain the field path, TypeId, and data as Bytes for
 /// the corresponding field (in that order). Since the set of fields that might
 /// go into CacheEntries is not known at startup, we must store the field names
 /// as part of CacheEntries. Since Event objects store their field in alphabetic
@@ -127,7 +128,47 @@ type TypeId = u8;
 #[derive(PartialEq, Eq, Hash)]
 enum CacheEntry {
     Match(Vec<Option<(TypeId, Bytes)>>),
-    Ignore(Vec<(String, TypeId, Bytes)>),
+    Ignore(Vec<(LookupBuf, TypeId, Bytes)>),
+}
+
+/// The fields of a `FieldMatchConfig`, with those ignored parsed into their path components
+/// once rather than for every event.
+enum FieldMatch {
+    Match(Vec<String>),
+    Ignore(Vec<Vec<PathComponent<'static>>>),
+}
+
+impl From<FieldMatchConfig> for FieldMatch {
+    fn from(config: FieldMatchConfig) -> Self {
+        match config {
+            FieldMatchConfig::MatchFields(fields) => Self::Match(fields),
+            FieldMatchConfig::IgnoreFields(fields) => Self::Ignore(
+                fields
+                    .iter()
+                    .map(|field| {
+                        PathIter::new(field)
+                            .map(PathComponent::into_static)
+                            .collect()
+                    })
+                    .collect(),
+            ),
+        }
+    }
+}
+
+/// Whether `path` is the one made of `components`.
+fn path_is(path: &LookupBuf, components: &[PathComponent<'static>]) -> bool {
+    path.len() == components.len()
+        && path
+            .iter()
+            .zip(components)
+            .all(|(segment, component)| match (segment, component) {
+                (SegmentBuf::Field(field), PathComponent::Key(key)) => field.name == *key,
+                (SegmentBuf::Index(index), PathComponent::Index(expected)) => {
+                    *index >= 0 && *index as usize == *expected
+                }
+                _ => false,
+            })
 }
 
 /// Assigns a unique number to each of the types supported by Event::Value.
@@ -147,7 +188,7 @@ const fn type_id_for_value(val: &Value) -> TypeId {
 impl Dedupe {
     pub fn new(config: DedupeConfig) -> Self {
         let num_entries = config.cache.num_events;
-        let fields = config.fill_default_fields_match();
+        let fields = config.fill_default_fields_match().into();
         Self {
             fields,
             cache: LruCache::new(num_entries),
@@ -168,9 +209,9 @@ impl Dedupe {
 /// Takes in an Event and returns a CacheEntry to place into the LRU cache
 /// containing all relevant information for the fields that need matching
 /// against according to the specified FieldMatchConfig.
-fn build_cache_entry(event: &Event, fields: &FieldMatchConfig) -> CacheEntry {
+fn build_cache_entry(event: &Event, fields: &FieldMatch) -> CacheEntry {
     match &fields {
-        FieldMatchConfig::MatchFields(fields) => {
+        FieldMatch::Match(fields) => {
             let mut entry = Vec::new();
             for field_name in fields.iter() {
                 if let Some(value) = event.as_log().get(&field_name) {
@@ -181,12 +222,12 @@ fn build_cache_entry(event: &Event, fields: &FieldMatchConfig) -> CacheEntry {
             }
             CacheEntry::Match(entry)
         }
-        FieldMatchConfig::IgnoreFields(fields) => {
+        FieldMatch::Ignore(fields) => {
             let mut entry = Vec::new();
 
-            for (field_name, value) in event.as_log().all_fields() {
-                if !fields.contains(&field_name) {
-                    entry.push((field_name, type_id_for_value(value), value.as_bytes()));
+            for (path, value) in event.as_log().paths_with_values() {
+                if !fields.iter().any(|field| path_is(&path, field)) {
+                    entry.push((path, type_id_for_value(value), value.as_bytes()));
                 }
             }
 
@@ -477,4 +518,30 @@ mod tests {
         let new_event = transform.transform_one(event2.clone()).unwrap();
         assert_eq!(new_event, event2);
     }
+
+    #[test]
+    fn dedupe_ignore_nested_fields() {
+        let mut transform = make_ignore_transform(5, vec!["request.id".into(), "tags[0]".into()]);
+
+        let mut event1 = Event::from("message");
+        event1.as_mut_log().insert("request.id", 1);
+        event1.as_mut_log().insert("request.path", "/");
+        event1.as_mut_log().insert("tags[0]", "first");
+        event1.as_mut_log().insert("tags[1]", "second");
+
+        // Only the ignored fields differ, so this is a duplicate.
+        let mut event2 = event1.clone();
+        event2.as_mut_log().insert("request.id", 2);
+        event2.as_mut_log().insert("tags[0]", "other");
+
+        // A field next to an ignored one still counts.
+        let mut event3 = event1.clone();
+        event3.as_mut_log().insert("tags[1]", "other");
+
+        let new_event = transform.transform_one(event1.clone()).unwrap();
+        assert_eq!(new_event, event1);
+        assert!(transform.transform_one(event2).is_none());
+        let new_event = transform.transform_one(event3.clone()).unwrap();
+        assert_eq!(new_event, event3);
+    }
 }
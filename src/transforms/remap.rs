@@ -2,8 +2,11 @@ use std::{
     fs::File,
     io::{self, Read},
     path::PathBuf,
+    sync::Arc,
+    time::{Duration, Instant},
 };
 
+use lookup::{LookupBuf, LookupError};
 use serde::{Deserialize, Serialize};
 use shared::TimeZone;
 use snafu::{ResultExt, Snafu};
@@ -11,10 +14,14 @@ use vrl::{diagnostic::Formatter, prelude::ExpressionError, Program, Runtime, Ter
 
 use crate::{
     config::{
-        log_schema, ComponentKey, DataType, TransformConfig, TransformContext, TransformDescription,
+        check_non_zero, log_schema, ComponentKey, ConfigErrors, DataType, TransformConfig,
+        TransformContext, TransformDescription,
+    },
+    event::{Event, PathStats, ProtectedPaths, TagLimits, TargetOutput, Utf8Handling, VrlTarget},
+    internal_events::{
+        RemapEventMarkedDropped, RemapFanoutTruncated, RemapMappingAbort, RemapMappingError,
+        RemapPathStatsReported,
     },
-    event::{Event, VrlTarget},
-    internal_events::{RemapMappingAbort, RemapMappingError},
     transforms::{SyncTransform, Transform, TransformOutputsBuf},
     Result,
 };
@@ -33,6 +40,57 @@ pub struct RemapConfig {
     #[serde(default = "crate::serde::default_true")]
     pub drop_on_abort: bool,
     pub reroute_dropped: bool,
+    /// Paths the program may read but not modify or delete, such as `.timestamp` or `.tags.tenant`.
+    pub protected_paths: Vec<String>,
+    /// The most events a single event may be split into by assigning an array to `.`.
+    #[derivative(Default(value = "default_max_fanout()"))]
+    pub max_fanout: usize,
+    pub fanout_exceeded_action: FanoutExceededAction,
+    /// Limits on the tags the program writes to metrics.
+    pub tag_limits: Option<TagLimits>,
+    /// How bytes that aren't valid UTF-8 are written to metric names, namespaces and tags.
+    pub metric_utf8_handling: Utf8Handling,
+    pub path_stats: PathStatsConfig,
+}
+
+/// Counting how often the program reads, writes and removes each path of the events, reported
+/// as internal metrics, for finding the paths an expensive program uses most and the reads that
+/// never find anything.
+#[derive(Deserialize, Serialize, Debug, Clone, Derivative)]
+#[serde(deny_unknown_fields, default)]
+#[derivative(Default)]
+pub struct PathStatsConfig {
+    pub enabled: bool,
+    /// The most paths counted apart. The operations on any other path are counted together.
+    #[derivative(Default(value = "default_path_stats_max_paths()"))]
+    pub max_paths: usize,
+    /// How often the counts are reported. They are only reported as events go through.
+    #[derivative(Default(value = "default_path_stats_interval_secs()"))]
+    pub interval_secs: u64,
+}
+
+const fn default_path_stats_max_paths() -> usize {
+    100
+}
+
+const fn default_path_stats_interval_secs() -> u64 {
+    60
+}
+
+/// What to do with an event whose program splits it into more than `max_fanout` events.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, Derivative, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+#[derivative(Default)]
+pub enum FanoutExceededAction {
+    /// Keep the first `max_fanout` events and discard the rest.
+    #[derivative(Default)]
+    Truncate,
+    /// Handle the event as if the program had failed.
+    Fail,
+}
+
+const fn default_max_fanout() -> usize {
+    1000
 }
 
 inventory::submit! {
@@ -83,6 +141,15 @@ pub struct Remap {
     drop_on_error: bool,
     drop_on_abort: bool,
     reroute_dropped: bool,
+    protected_paths: Option<Arc<ProtectedPaths>>,
+    max_fanout: usize,
+    fanout_exceeded_action: FanoutExceededAction,
+    tag_limits: Option<TagLimits>,
+    metric_utf8_handling: Utf8Handling,
+    /// Shared by the clones of the transform, so that the counts cover all of its events.
+    path_stats: Option<Arc<PathStats>>,
+    path_stats_interval: Duration,
+    path_stats_reported_at: Instant,
 }
 
 impl Remap {
@@ -102,6 +169,24 @@ impl Remap {
             _ => return Err(Box::new(BuildError::SourceAndOrFile)),
         };
 
+        if config.path_stats.enabled {
+            let mut errors = Vec::new();
+            check_non_zero(
+                &mut errors,
+                [("path_stats.max_paths", Some(config.path_stats.max_paths))],
+            );
+            check_non_zero(
+                &mut errors,
+                [(
+                    "path_stats.interval_secs",
+                    Some(config.path_stats.interval_secs),
+                )],
+            );
+            if !errors.is_empty() {
+                return Err(Box::new(ConfigErrors(errors)));
+            }
+        }
+
         let mut functions = vrl_stdlib::all();
         functions.append(&mut enrichment::vrl_functions());
         functions.append(&mut vector_vrl_functions::vrl_functions());
@@ -113,6 +198,17 @@ impl Remap {
         )
         .map_err(|diagnostics| Formatter::new(&source, diagnostics).colored().to_string())?;
 
+        let protected_paths = config
+            .protected_paths
+            .iter()
+            .map(|path| {
+                path.parse::<LookupBuf>()
+                    .with_context(|| InvalidProtectedPath { path })
+            })
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        let protected_paths =
+            (!protected_paths.is_empty()).then(|| Arc::new(ProtectedPaths::new(protected_paths)));
+
         Ok(Remap {
             component_key: context.key.clone(),
             program,
@@ -121,6 +217,17 @@ impl Remap {
             drop_on_error: config.drop_on_error,
             drop_on_abort: config.drop_on_abort,
             reroute_dropped: config.reroute_dropped,
+            protected_paths,
+            max_fanout: config.max_fanout,
+            fanout_exceeded_action: config.fanout_exceeded_action,
+            tag_limits: config.tag_limits,
+            metric_utf8_handling: config.metric_utf8_handling,
+            path_stats: config
+                .path_stats
+                .enabled
+                .then(|| Arc::new(PathStats::new(config.path_stats.max_paths))),
+            path_stats_interval: Duration::from_secs(config.path_stats.interval_secs),
+            path_stats_reported_at: Instant::now(),
         })
     }
 
@@ -129,6 +236,46 @@ impl Remap {
         &self.runtime
     }
 
+    /// Enforce `max_fanout` on the events created by splitting an array assigned to `.`.
+    fn limit_fanout(&self, target: &mut VrlTarget) -> std::result::Result<(), ExpressionError> {
+        let fanout = match target.fanout() {
+            Some(fanout) if fanout > self.max_fanout => fanout,
+            _ => return Ok(()),
+        };
+
+        match self.fanout_exceeded_action {
+            FanoutExceededAction::Truncate => {
+                target.truncate_fanout(self.max_fanout);
+                emit!(&RemapFanoutTruncated {
+                    original_count: fanout,
+                    resulting_count: self.max_fanout,
+                });
+                Ok(())
+            }
+            FanoutExceededAction::Fail => Err(format!(
+                "event split into {} events, more than the max_fanout of {}",
+                fanout, self.max_fanout
+            )
+            .into()),
+        }
+    }
+
+    /// Report the path counts if `path_stats.interval_secs` has passed since they last were.
+    fn report_path_stats(&mut self) {
+        let path_stats = match &self.path_stats {
+            Some(path_stats) => path_stats,
+            None => return,
+        };
+        let now = Instant::now();
+        if now.duration_since(self.path_stats_reported_at) < self.path_stats_interval {
+            return;
+        }
+        self.path_stats_reported_at = now;
+        emit!(&RemapPathStatsReported {
+            report: &path_stats.take(),
+        });
+    }
+
     fn annotate_dropped(&self, event: &mut Event, reason: &str, error: ExpressionError) {
         match event {
             Event::Log(ref mut log) => {
@@ -172,12 +319,23 @@ impl Clone for Remap {
             drop_on_error: self.drop_on_error,
             drop_on_abort: self.drop_on_abort,
             reroute_dropped: self.reroute_dropped,
+            protected_paths: self.protected_paths.as_ref().map(Arc::clone),
+            max_fanout: self.max_fanout,
+            fanout_exceeded_action: self.fanout_exceeded_action,
+            tag_limits: self.tag_limits,
+            metric_utf8_handling: self.metric_utf8_handling,
+            path_stats: self.path_stats.as_ref().map(Arc::clone),
+            path_stats_interval: self.path_stats_interval,
+            path_stats_reported_at: self.path_stats_reported_at,
         }
     }
 }
 
 impl SyncTransform for Remap {
-    fn transform(&mut self, event: Event, output: &mut TransformOutputsBuf) {
+    fn transform(&mut self, mut event: Event, output: &mut TransformOutputsBuf) {
+        // A `dropped` mark left by an upstream component doesn't apply to this program.
+        event.metadata_mut().take_dropped();
+
         // If a program can fail or abort at runtime and we know that we will still need to forward
         // the event in that case (either to the main output or `dropped`, depending on the
         // config), we need to clone the original event and keep it around, to allow us to discard
@@ -188,27 +346,60 @@ impl SyncTransform for Remap {
         // any mutations made by VRL will be ignored regardless. If they hav configured
         // `reroute_dropped`, however, we still need to do the clone to ensure that we can forward
         // the event to the `dropped` output.
+        //
+        // Writes to protected paths fail at runtime even if the program itself is infallible, and so
+        // do exceeding `max_fanout` and writing invalid UTF-8 to a metric when configured to.
         let forward_on_error = !self.drop_on_error || self.reroute_dropped;
         let forward_on_abort = !self.drop_on_abort || self.reroute_dropped;
-        let original_event = if (self.program.can_fail() && forward_on_error)
-            || (self.program.can_abort() && forward_on_abort)
-        {
-            Some(event.clone())
-        } else {
-            None
-        };
-
-        let mut target: VrlTarget = event.into();
+        let can_fail = self.program.can_fail()
+            || self.protected_paths.is_some()
+            || self.fanout_exceeded_action == FanoutExceededAction::Fail
+            || self.metric_utf8_handling == Utf8Handling::Reject;
+        let original_event =
+            if (can_fail && forward_on_error) || (self.program.can_abort() && forward_on_abort) {
+                Some(event.clone())
+            } else {
+                None
+            };
+
+        let mut target = VrlTarget::new(event);
+        if let Some(protected_paths) = &self.protected_paths {
+            target = target.with_protected_paths(Arc::clone(protected_paths));
+        }
+        if let Some(tag_limits) = self.tag_limits {
+            target = target.with_tag_limits(tag_limits);
+        }
+        target = target.with_utf8_handling(self.metric_utf8_handling);
+        if let Some(path_stats) = &self.path_stats {
+            target = target.with_path_stats(Arc::clone(path_stats));
+        }
 
-        let result = self
+        let mut result = self
             .runtime
             .resolve(&mut target, &self.program, &self.timezone);
         self.runtime.clear();
+        self.report_path_stats();
+
+        if result.is_ok() {
+            if let Err(error) = self.limit_fanout(&mut target) {
+                result = Err(Terminate::Error(error));
+            }
+        }
 
         match result {
             Ok(_) => {
-                for event in target.into_events() {
-                    output.push(event)
+                // Events the program marked as dropped are discarded, unless they can be sent to
+                // the `dropped` output.
+                for (target_output, event) in target.into_events_with_status() {
+                    match target_output {
+                        TargetOutput::Primary => output.push(event),
+                        TargetOutput::Dropped if self.reroute_dropped => {
+                            output.push_named(DROPPED, event)
+                        }
+                        TargetOutput::Dropped => emit!(&RemapEventMarkedDropped {
+                            reason: event.dropped().unwrap_or_default(),
+                        }),
+                    }
                 }
             }
             Err(Terminate::Abort(error)) => {
@@ -221,6 +412,7 @@ impl SyncTransform for Remap {
                 } else if self.reroute_dropped {
                     let mut event = original_event.expect("event will be set");
                     self.annotate_dropped(&mut event, "abort", error);
+                    event.set_dropped("abort");
                     output.push_named(DROPPED, event)
                 }
             }
@@ -235,6 +427,7 @@ impl SyncTransform for Remap {
                 } else if self.reroute_dropped {
                     let mut event = original_event.expect("event will be set");
                     self.annotate_dropped(&mut event, "error", error);
+                    event.set_dropped("error");
                     output.push_named(DROPPED, event)
                 }
             }
@@ -251,6 +444,8 @@ pub enum BuildError {
     FileOpenFailed { path: PathBuf, source: io::Error },
     #[snafu(display("Could not read vrl program {:?}: {}", path, source))]
     FileReadFailed { path: PathBuf, source: io::Error },
+    #[snafu(display("Invalid protected path {:?}: {}", path, source))]
+    InvalidProtectedPath { path: String, source: LookupError },
 }
 
 #[cfg(test)]
@@ -263,7 +458,7 @@ mod tests {
     use super::*;
     use crate::event::{
         metric::{MetricKind, MetricValue},
-        LogEvent, Metric, Value,
+        LogEvent, Metric, TagLimitAction, Value,
     };
 
     #[test]
@@ -346,6 +541,38 @@ mod tests {
         assert!(tform.runtime().is_empty());
     }
 
+    #[test]
+    fn check_remap_removes_root() {
+        let transform = |source: &str| {
+            let conf = RemapConfig {
+                source: Some(source.to_owned()),
+                ..Default::default()
+            };
+            let mut tform = Remap::new(conf, &Default::default()).unwrap();
+            collect_outputs(&mut tform, Event::from(LogEvent::from("hello")))
+        };
+
+        // An emptied event isn't forwarded at all.
+        let out = transform("del(.)");
+        assert!(out.primary.is_empty());
+        assert!(out.named[DROPPED].is_empty());
+
+        let out = transform(indoc! {r#"
+            removed = del(.)
+            .message = removed.message
+        "#});
+        assert_eq!(out.primary.len(), 1);
+        let keys = out.primary[0].as_log().keys().collect::<Vec<_>>();
+        assert_eq!(keys, vec!["message"]);
+        assert_eq!(get_field_string(&out.primary[0], "message"), "hello");
+
+        let out = transform(indoc! {r#"
+            . = [{"a": 1}, {"b": 2}]
+            del(.)
+        "#});
+        assert!(out.primary.is_empty());
+    }
+
     #[test]
     fn check_remap_adds() {
         let event = {
@@ -380,6 +607,106 @@ mod tests {
         assert_eq!(result.metadata(), &metadata);
     }
 
+    #[test]
+    fn check_remap_protected_paths() {
+        let event = {
+            let mut event = LogEvent::from("protect me");
+            event.insert("tenant.id", "acme");
+            Event::from(event)
+        };
+
+        let conf = RemapConfig {
+            source: Some(
+                indoc! {r#"
+                    .message = "changed"
+                    .tenant.id = "evil"
+                "#}
+                .to_string(),
+            ),
+            protected_paths: vec!["tenant".to_string()],
+            ..Default::default()
+        };
+        let mut tform = Remap::new(conf, &Default::default()).unwrap();
+
+        let result = transform_one(&mut tform, event.clone()).unwrap();
+        assert_eq!(result, event);
+    }
+
+    #[test]
+    fn check_remap_path_stats() {
+        let conf = RemapConfig {
+            source: Some(
+                indoc! {r#"
+                    .copy = .message
+                    .found = exists(.missing)
+                    del(.drop_me)
+                "#}
+                .to_string(),
+            ),
+            path_stats: PathStatsConfig {
+                enabled: true,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let mut tform = Remap::new(conf, &Default::default()).unwrap();
+        for i in 0..100 {
+            let mut event = LogEvent::from(format!("event {}", i));
+            event.insert("drop_me", i);
+            transform_one(&mut tform, Event::from(event)).unwrap();
+        }
+
+        // Nothing was reported yet, so the counts cover every event.
+        let report = tform.path_stats.as_ref().unwrap().take();
+        let message = report.paths["message"];
+        assert_eq!((message.gets, message.misses), (100, 0));
+        let missing = report.paths["missing"];
+        assert_eq!((missing.gets, missing.misses), (100, 100));
+        assert_eq!(report.paths["copy"].sets, 100);
+        assert_eq!(report.paths["found"].sets, 100);
+        assert_eq!(report.paths["drop_me"].removes, 100);
+        assert_eq!(report.other, Default::default());
+    }
+
+    #[test]
+    fn config_zero_path_stats() {
+        let conf = RemapConfig {
+            source: Some(".foo = 1".to_string()),
+            path_stats: PathStatsConfig {
+                enabled: true,
+                max_paths: 0,
+                interval_secs: 0,
+            },
+            ..Default::default()
+        };
+        let err = Remap::new(conf, &Default::default())
+            .unwrap_err()
+            .to_string();
+        assert_eq!(
+            err,
+            "Invalid configuration: path_stats.max_paths: must be greater than zero; \
+             path_stats.interval_secs: must be greater than zero"
+        );
+    }
+
+    #[test]
+    fn config_invalid_protected_path() {
+        let conf = RemapConfig {
+            source: Some(".foo = 1".to_string()),
+            protected_paths: vec!["tenant[".to_string()],
+            ..Default::default()
+        };
+
+        let err = Remap::new(conf, &Default::default())
+            .unwrap_err()
+            .to_string();
+        assert!(
+            err.starts_with(r#"Invalid protected path "tenant[""#),
+            "{}",
+            err
+        );
+    }
+
     #[test]
     fn check_remap_emits_multiple() {
         let event = {
@@ -598,6 +925,98 @@ mod tests {
         );
     }
 
+    #[test]
+    fn check_remap_metric_tags_matching() {
+        let metric = Event::Metric(
+            Metric::new(
+                "counter",
+                MetricKind::Absolute,
+                MetricValue::Counter { value: 1.0 },
+            )
+            .with_tags(Some(btreemap! {
+                "aws_region" => "us-east-1",
+                "aws_zone" => "us-east-1a",
+                "host" => "localhost",
+            })),
+        );
+
+        let conf = RemapConfig {
+            source: Some(
+                indoc! {r#"
+                    .tags.removed = encode_json(remove_tags_matching!("aws_"))
+                    .tags.left = encode_json(get_tags_matching!("aws_"))
+                "#}
+                .to_string(),
+            ),
+            ..Default::default()
+        };
+        let mut tform = Remap::new(conf, &Default::default()).unwrap();
+
+        let result = transform_one(&mut tform, metric).unwrap();
+        assert_eq!(
+            result.as_metric().tags(),
+            Some(&btreemap! {
+                "host" => "localhost",
+                "left" => "{}",
+                "removed" => r#"{"aws_region":"us-east-1","aws_zone":"us-east-1a"}"#,
+            })
+        );
+    }
+
+    #[test]
+    fn check_remap_metric_tag_limits() {
+        let metric = Event::Metric(Metric::new(
+            "counter",
+            MetricKind::Absolute,
+            MetricValue::Counter { value: 1.0 },
+        ));
+
+        let conf = RemapConfig {
+            source: Some(
+                indoc! {r#"
+                    .tags.host = "localhost"
+                    .tags.request_id = "1234"
+                "#}
+                .to_string(),
+            ),
+            tag_limits: Some(TagLimits {
+                max_value_length: Some(5),
+                action: TagLimitAction::Drop,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let mut tform = Remap::new(conf, &Default::default()).unwrap();
+
+        let result = transform_one(&mut tform, metric).unwrap();
+        assert_eq!(
+            result.as_metric().tags(),
+            Some(&btreemap! { "request_id" => "1234" })
+        );
+    }
+
+    #[test]
+    fn check_remap_metric_utf8_handling() {
+        let metric = Event::Metric(Metric::new(
+            "counter",
+            MetricKind::Absolute,
+            MetricValue::Counter { value: 1.0 },
+        ));
+
+        let conf = RemapConfig {
+            source: Some(r#".tags.raw = decode_base64!("/w==")"#.to_string()),
+            metric_utf8_handling: Utf8Handling::Base64,
+            ..Default::default()
+        };
+        let mut tform = Remap::new(conf, &Default::default()).unwrap();
+
+        let result = transform_one(&mut tform, metric).unwrap();
+        assert_eq!(
+            result.as_metric().tags(),
+            Some(&btreemap! { "raw" => "/w==" })
+        );
+    }
+
     #[test]
     fn check_remap_branching() {
         let happy = Event::try_from(serde_json::json!({"hello": "world"})).unwrap();
@@ -817,6 +1236,130 @@ mod tests {
         assert!(out.named[DROPPED].is_empty());
     }
 
+    #[test]
+    fn check_remap_marked_dropped() {
+        let conf = |reroute_dropped| RemapConfig {
+            source: Some(
+                indoc! {r#"
+                if .hello == "goodbye" {
+                    set_metadata_field("dropped", "said goodbye")
+                }
+                . = [., {"split": true}]
+            "#}
+                .to_owned(),
+            ),
+            reroute_dropped,
+            ..Default::default()
+        };
+        let mut tform = Remap::new(conf(true), &Default::default()).unwrap();
+
+        let happy = Event::try_from(serde_json::json!({"hello": "world"})).unwrap();
+        let out = collect_outputs(&mut tform, happy);
+        assert_eq!(out.primary.len(), 2);
+        assert!(out.named[DROPPED].is_empty());
+
+        // A mark from upstream doesn't carry over into this program.
+        let mut marked = Event::try_from(serde_json::json!({"hello": "world"})).unwrap();
+        marked.set_dropped("upstream");
+        let out = collect_outputs(&mut tform, marked);
+        assert_eq!(out.primary.len(), 2);
+        assert!(out.primary.iter().all(|event| event.dropped().is_none()));
+
+        let goodbye = Event::try_from(serde_json::json!({"hello": "goodbye"})).unwrap();
+        let out = collect_outputs(&mut tform, goodbye.clone());
+        assert!(out.primary.is_empty());
+        let dropped = &out.named[DROPPED];
+        assert_eq!(dropped.len(), 2);
+        for event in dropped {
+            assert_eq!(event.dropped(), Some("said goodbye"));
+            assert!(!event.as_log().contains("metadata"));
+        }
+        assert_eq!(dropped[1].as_log()["split"], true.into());
+
+        let mut tform = Remap::new(conf(false), &Default::default()).unwrap();
+        let out = collect_outputs(&mut tform, goodbye);
+        assert!(out.primary.is_empty());
+        assert!(out.named[DROPPED].is_empty());
+    }
+
+    fn fanout_config(
+        max_fanout: usize,
+        fanout_exceeded_action: FanoutExceededAction,
+    ) -> RemapConfig {
+        RemapConfig {
+            source: Some(
+                indoc! {r#"
+                    if .split == true {
+                        . = [{"n": 1}, {"n": 2}, {"n": 3}, {"n": 4}]
+                    }
+                "#}
+                .to_owned(),
+            ),
+            drop_on_error: true,
+            reroute_dropped: true,
+            max_fanout,
+            fanout_exceeded_action,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn check_remap_fanout_truncate() {
+        let conf = fanout_config(2, FanoutExceededAction::Truncate);
+        let mut tform = Remap::new(conf, &Default::default()).unwrap();
+
+        let event = Event::try_from(serde_json::json!({"split": true})).unwrap();
+        let out = collect_outputs(&mut tform, event);
+        assert!(out.named[DROPPED].is_empty());
+        assert_eq!(
+            out.primary
+                .iter()
+                .map(|event| event.as_log()["n"].clone())
+                .collect::<Vec<_>>(),
+            vec![Value::Integer(1), Value::Integer(2)]
+        );
+
+        // Only splitting counts towards the limit.
+        let conf = fanout_config(0, FanoutExceededAction::Truncate);
+        let mut tform = Remap::new(conf, &Default::default()).unwrap();
+        let event = Event::try_from(serde_json::json!({"split": false})).unwrap();
+        let out = collect_outputs(&mut tform, event);
+        assert_eq!(out.primary.len(), 1);
+    }
+
+    #[test]
+    fn check_remap_fanout_fail() {
+        let conf = fanout_config(2, FanoutExceededAction::Fail);
+        let mut tform = Remap::new(conf, &Default::default()).unwrap();
+
+        let event = Event::try_from(serde_json::json!({"split": true})).unwrap();
+        let out = collect_outputs(&mut tform, event);
+        assert!(out.primary.is_empty());
+        let dropped = &out.named[DROPPED];
+        assert_eq!(dropped.len(), 1);
+        assert_eq!(dropped[0].as_log()["split"], true.into());
+        assert_eq!(dropped[0].dropped(), Some("error"));
+        assert_eq!(
+            dropped[0].as_log()["metadata.dropped.message"],
+            "event split into 4 events, more than the max_fanout of 2".into()
+        );
+
+        let event = Event::try_from(serde_json::json!({"split": false})).unwrap();
+        let out = collect_outputs(&mut tform, event);
+        assert_eq!(out.primary.len(), 1);
+
+        let conf = RemapConfig {
+            drop_on_error: false,
+            reroute_dropped: false,
+            ..fanout_config(2, FanoutExceededAction::Fail)
+        };
+        let mut tform = Remap::new(conf, &Default::default()).unwrap();
+        let event = Event::try_from(serde_json::json!({"split": true})).unwrap();
+        let result = transform_one(&mut tform, event).unwrap();
+        assert_eq!(result.as_log()["split"], true.into());
+        assert!(!result.as_log().contains("n"));
+    }
+
     struct CollectedOuput {
         primary: Vec<Event>,
         named: HashMap<String, Vec<Event>>,
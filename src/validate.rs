@@ -3,9 +3,10 @@ use std::{collections::HashMap, fmt, fs::remove_dir_all, path::PathBuf};
 use colored::*;
 use exitcode::ExitCode;
 use structopt::StructOpt;
+use vector_core::buffers::Acker;
 
 use crate::{
-    config::{self, Config, ConfigDiff},
+    config::{self, Config, ConfigDiff, ProxyConfig, SinkContext},
     topology::{self, builder::Pieces},
 };
 
@@ -14,7 +15,7 @@ const TEMPORARY_DIRECTORY: &str = "validate_tmp";
 #[derive(StructOpt, Debug)]
 #[structopt(rename_all = "kebab-case")]
 pub struct Opts {
-    /// Disables environment checks. That includes component checks and health checks.
+    /// Disables environment checks. That includes component checks, dry runs and health checks.
     #[structopt(long)]
     no_environment: bool,
 
@@ -23,6 +24,11 @@ pub struct Opts {
     #[structopt(short, long)]
     deny_warnings: bool,
 
+    /// Bind the addresses of sources during dry runs, releasing them right away. This fails
+    /// while the addresses are in use, such as by a Vector running this config.
+    #[structopt(long)]
+    bind_addresses: bool,
+
     /// Vector config files in TOML format to validate.
     #[structopt(
         name = "config-toml",
@@ -89,7 +95,7 @@ impl Opts {
     }
 }
 
-/// Performs topology, component, and health checks.
+/// Performs topology, component, dry run, and health checks.
 pub async fn validate(opts: &Opts, color: bool) -> ExitCode {
     let mut fmt = Formatter::new(color);
 
@@ -176,6 +182,10 @@ async fn validate_environment(opts: &Opts, config: &Config, fmt: &mut Formatter)
         return false;
     };
 
+    if !validate_dry_runs(opts, config, fmt).await {
+        return false;
+    }
+
     validate_healthchecks(opts, config, &diff, &mut pieces, fmt).await
 }
 
@@ -197,6 +207,42 @@ async fn validate_components(
     }
 }
 
+/// Runs the dry runs of the components that have one, which go further than
+/// building them, such as loading TLS files. The addresses of sources are only
+/// bound with `--bind-addresses`.
+async fn validate_dry_runs(opts: &Opts, config: &Config, fmt: &mut Formatter) -> bool {
+    let mut reports = Vec::new();
+    for (key, source) in &config.sources {
+        if let Some(report) = source.inner.dry_run(opts.bind_addresses).await {
+            reports.push((format!("source \"{}\"", key), report));
+        }
+    }
+    for (key, sink) in &config.sinks {
+        let cx = SinkContext {
+            acker: Acker::passthrough(),
+            healthcheck: sink.healthcheck(),
+            globals: config.global.clone(),
+            proxy: ProxyConfig::merge_with_env(&config.global.proxy, sink.proxy()),
+        };
+        // Connecting to the endpoints is left to the health checks.
+        if let Some(report) = sink.inner.dry_run(cx, false).await {
+            reports.push((format!("sink \"{}\"", key), report));
+        }
+    }
+
+    let mut validated = true;
+    for (component, report) in reports {
+        if report.is_ok() {
+            fmt.success(format!("Dry run of {}", component));
+        } else {
+            validated = false;
+            fmt.title(format!("Dry run of {} failed", component));
+            fmt.sub_error(report.errors);
+        }
+    }
+    validated
+}
+
 async fn validate_healthchecks(
     opts: &Opts,
     config: &Config,
@@ -0,0 +1,5 @@
+
+
+users:
+alice
+bob
\ No newline at end of file
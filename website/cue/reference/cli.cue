@@ -346,13 +346,22 @@ cli: {
 					_short: "ne"
 					description: """
 						Disables environment checks. That includes component
-						checks and health checks
+						checks, dry runs (such as loading the TLS files of
+						`vector` sources) and health checks
 						"""
 				}
 				"deny-warnings": {
 					_short:      "d"
 					description: "Fail validation on warnings"
 				}
+				"bind-addresses": {
+					description: """
+						Bind the addresses of `vector` sources during dry runs,
+						releasing them right away. This fails while the
+						addresses are in use, such as by a Vector running the
+						config being validated
+						"""
+				}
 			}
 
 			options: {
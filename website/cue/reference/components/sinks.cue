@@ -303,6 +303,24 @@ components: sinks: [Name=string]: {
 											required:    false
 											type: float: default: 0.7
 										}
+										max_concurrency: {
+											common:      false
+											description: "The highest the concurrency limit is raised to, such as the number of requests the service is known to handle at once. Must be at least `min_concurrency`."
+											required:    false
+											type: uint: {
+												default: 200
+												unit:    "requests"
+											}
+										}
+										min_concurrency: {
+											common:      false
+											description: "The concurrency limit to start from, and the lowest it is lowered to when the service pushes back. Must be greater than zero."
+											required:    false
+											type: uint: {
+												default: 1
+												unit:    "requests"
+											}
+										}
 										rtt_deviation_scale: {
 											common: false
 											description: """
@@ -60,7 +60,13 @@ components: sinks: vector: {
 
 	support: {
 		requirements: []
-		warnings: []
+		warnings: [
+			"""
+				Requests whose answer from the downstream Vector is lost, or doesn't arrive within
+				`request.timeout_secs` because of the network, are retried. When it had already
+				forwarded their events, those events are delivered twice.
+				""",
+		]
 		notices: []
 	}
 
@@ -97,16 +103,425 @@ components: sinks: vector: {
 				default: "1"
 			}
 		}
+		max_event_bytes: {
+			common:        false
+			description:   "The largest encoded size of a single event. Larger events are handled according to `oversized_event_action` instead of being sent, since every request holding them would be rejected."
+			required:      false
+			relevant_when: "version = \"2\""
+			type: uint: {
+				default: null
+				examples: [4_194_304]
+				unit: "bytes"
+			}
+		}
+		oversized_event_action: {
+			common:        false
+			description:   "What to do with events larger than `max_event_bytes`."
+			required:      false
+			relevant_when: "version = \"2\""
+			type: string: {
+				default: "drop"
+				enum: {
+					drop:     "Drop the event."
+					truncate: "Shorten the largest string field of a log event so that it fits. Events that still do not fit are dropped and counted in `encode_errors_total`."
+				}
+			}
+		}
+		ordered: {
+			common:        false
+			description:   "Deliver events in the order they came in. Only one request is in flight at a time: the next batch is sent once the previous one was acknowledged, and a request being retried holds back every request after it. This caps throughput at one batch per round trip, so raise `batch.max_events` and `batch.max_bytes` to make up for it. `request.concurrency` can't be set over 1 along with it."
+			required:      false
+			relevant_when: "version = \"2\""
+			type: bool: default: false
+		}
+		chunking: {
+			common:        false
+			description:   "Send events too large for a single request split into chunks, each in a request of its own, for the downstream Vector to put back together. The downstream `vector` source must have `chunking.enabled` set as well. Keep `batch.max_bytes` and `chunking.chunk_bytes` under the largest request the downstream Vector accepts."
+			required:      false
+			relevant_when: "version = \"2\""
+			type: object: {
+				examples: []
+				options: {
+					enabled: {
+						common:      false
+						description: "Split events larger than `chunk_bytes` into chunks."
+						required:    false
+						type: bool: default: false
+					}
+					chunk_bytes: {
+						common:      false
+						description: "The most bytes of an encoded event sent in a single request."
+						required:    false
+						type: uint: {
+							default: 524_288
+							unit:    "bytes"
+						}
+					}
+				}
+			}
+		}
+		delta: {
+			common:        false
+			description:   "Send log events that share a key with the previous one as the fields that changed since then, for the downstream Vector to apply to the event it already has. Only used once the downstream `vector` source has answered that it has `delta.enabled` set; events are sent whole otherwise. Events whose baseline the source no longer has are sent again whole, and counted in `sink_delta_resent_events_total`. Experimental."
+			required:      false
+			relevant_when: "version = \"2\""
+			type: object: {
+				examples: []
+				options: {
+					enabled: {
+						common:      false
+						description: "Send deltas. Requires `key_field`."
+						required:    false
+						type: bool: default: false
+					}
+					key_field: {
+						common:      false
+						description: "The field holding the key of each event. Events without it are sent whole."
+						required:    false
+						type: string: {
+							default: null
+							examples: ["resource_id"]
+						}
+					}
+					max_keys: {
+						common:      false
+						description: "The most keys whose last event is remembered. The least recently seen keys are forgotten to make room, and their next event sent whole."
+						required:    false
+						type: uint: {
+							default: 10_000
+							unit:    null
+						}
+					}
+				}
+			}
+		}
+		dns_refresh_interval_secs: {
+			common:        false
+			description:   "How often to look up the addresses of the downstream Vector again. When they change, new requests are sent over new connections, while requests in flight finish on the old ones. Failed lookups keep the current connections. Disabled when unset, in which case connections are only made again once they fail."
+			required:      false
+			relevant_when: "version = \"2\""
+			type: uint: {
+				default: null
+				unit:    "seconds"
+			}
+		}
+		drop_summary_interval_secs: {
+			common:        false
+			description:   "How often the events the sink drops, such as those larger than `max_event_bytes` or that fail to encode, are logged. Each interval one warning is logged for each reason there were drops for, with how many there were and the error and event id of the first of them. The drops are counted in `events_discarded_total` and `encode_errors_total` either way."
+			required:      false
+			relevant_when: "version = \"2\""
+			type: uint: {
+				default: 10
+				unit:    "seconds"
+			}
+		}
+		sample: {
+			common:        false
+			description:   "Send only a sample of the log events, to shed load rather than run out of memory when the downstream Vector can't keep up. Events are hashed with `seed`, so the same events are kept across restarts and on every Vector sharing the seed. Kept events record the rate they were sampled at in `rate_field`, for the downstream to scale counts back up by; the others are counted in `events_discarded_total` and reported delivered. Metrics are always sent. Requires `rate` or `max_bytes`; with both set, the lower share of events is kept."
+			required:      false
+			relevant_when: "version = \"2\""
+			type: object: {
+				examples: []
+				options: {
+					rate: {
+						common:      false
+						description: "Keep one in `rate` events."
+						required:    false
+						type: uint: {
+							default: null
+							examples: [10]
+							unit: null
+						}
+					}
+					max_bytes: {
+						common:      false
+						description: "Keep about this many bytes of events each `interval_secs`. Events are sampled at the share of the bytes that came in over the last interval, or so far in the current one if more, that this makes up."
+						required:    false
+						type: uint: {
+							default: null
+							examples: [10_485_760]
+							unit: "bytes"
+						}
+					}
+					interval_secs: {
+						common:      false
+						description: "The interval `max_bytes` applies to."
+						required:    false
+						type: uint: {
+							default: 1
+							unit:    "seconds"
+						}
+					}
+					key_field: {
+						common:      false
+						description: "The field whose value decides whether an event is kept, so that all the events with the same value, such as those of one request, are kept or dropped together. Events without it are decided on all of their fields."
+						required:    false
+						type: string: {
+							default: null
+							examples: ["request_id"]
+							syntax: "field_path"
+						}
+					}
+					seed: {
+						common:      false
+						description: "Seeds the hash that decides which events are kept."
+						required:    false
+						type: uint: {
+							default: 0
+							unit:    null
+						}
+					}
+					rate_field: {
+						common:      false
+						description: "The field of the kept events that the rate they were sampled at is recorded in, as how many events each stands for."
+						required:    false
+						type: string: {
+							default: "sample_rate"
+							syntax:  "field_path"
+						}
+					}
+				}
+			}
+		}
+		spool: {
+			common:        false
+			description:   "Write the events left undelivered when the sink stops, such as those still being retried against an unreachable downstream Vector at the shutdown timeout, to disk, and send them ahead of new events when it starts again. Events are spooled whole, with their ids. Each file is removed once all of its events are delivered, kept to be sent again if some of them failed or were still being sent when the sink stopped, and moved to the `quarantine` directory of the spool if some are rejected, or if it can't be read back or doesn't fit in `max_bytes`. Files are only ever sent again whole, so some events may be delivered twice. If the process dies before the sink stops, such as on a crash, nothing is spooled. It can't be used along with a disk buffer, which would send the spooled events again as well."
+			required:      false
+			relevant_when: "version = \"2\""
+			type: object: {
+				examples: []
+				options: {
+					directory: {
+						description: "The directory the events are spooled to. It must not be shared with other sinks."
+						required:    true
+						type: string: {
+							examples: ["/var/lib/vector/vector_sink_spool"]
+							syntax: "literal"
+						}
+					}
+					max_bytes: {
+						common:      false
+						description: "The most bytes the spool files may take up together. The events that don't fit are dropped when the sink stops."
+						required:    false
+						type: uint: {
+							default: 104_857_600
+							unit:    "bytes"
+						}
+					}
+				}
+			}
+		}
+		encoding: {
+			common:        false
+			description:   "Which fields of log events are sent, to save bandwidth without a `remap` transform in front of the sink. Events shared with other sinks are left as they are. Metrics are sent whole."
+			required:      false
+			relevant_when: "version = \"2\""
+			type: object: {
+				examples: []
+				options: {
+					except_fields: {
+						common:      false
+						description: "Send every field except these. Array elements left out are sent as `null`. Can't be set along with `only_fields`."
+						required:    false
+						type: array: {
+							default: null
+							items: type: string: {
+								examples: ["host", "http.request.headers"]
+								syntax: "field_path"
+							}
+						}
+					}
+					only_fields: {
+						common:      false
+						description: "Only send these fields, and the fields nested below them. Can't be set along with `except_fields`."
+						required:    false
+						type: array: {
+							default: null
+							items: type: string: {
+								examples: ["message", "http.request.method"]
+								syntax: "field_path"
+							}
+						}
+					}
+					timestamp_format: {
+						common:      false
+						description: "How to send the timestamps of log events."
+						required:    false
+						type: string: {
+							default: "rfc3339"
+							enum: {
+								rfc3339: "As timestamps."
+								unix:    "As integers of seconds since the Unix epoch."
+							}
+						}
+					}
+				}
+			}
+		}
+		event_age_histograms: {
+			common:        false
+			description:   "Record an `event_age_seconds` histogram of how long each delivered event took to get here since a source ingested it."
+			required:      false
+			relevant_when: "version = \"2\""
+			type: bool: default: false
+		}
+		sender: {
+			common:        false
+			description:   "Identifies this Vector to the downstream one on every request, so that it can tell which agent sent what."
+			required:      false
+			relevant_when: "version = \"2\""
+			type: object: {
+				examples: []
+				options: {
+					enabled: {
+						common:      false
+						description: "Send the hostname, version, and a process id that stays the same across config reloads along with each request."
+						required:    false
+						type: bool: default: true
+					}
+					labels: {
+						common:      false
+						description: "Custom labels sent along with the sender, such as the cluster or region of this Vector."
+						required:    false
+						type: object: {
+							examples: [{"region": "eu-west-1"}]
+							options: {
+								"*": {
+									common:      false
+									description: "Any label."
+									required:    false
+									type: string: {
+										default: null
+										examples: ["eu-west-1"]
+									}
+								}
+							}
+						}
+					}
+				}
+			}
+		}
+		healthcheck_channel: {
+			common:        false
+			description:   "The connection the startup and periodic health checks are sent to the downstream Vector over."
+			required:      false
+			relevant_when: "version = \"2\""
+			type: object: {
+				examples: []
+				options: {
+					dedicated: {
+						common:      false
+						description: "Send the health checks over a connection of their own, so that they aren't held up behind events while the downstream Vector is busy and report it unhealthy. Disable to keep to a single connection to the downstream Vector."
+						required:    false
+						type: bool: default: true
+					}
+					timeout_secs: {
+						common:      false
+						description: "How long to wait for the answer to a health check before taking the downstream Vector to be unhealthy. When `dedicated` is disabled, the startup health check waits for `request.timeout_secs` instead."
+						required:    false
+						type: uint: {
+							default: 10
+							unit:    "seconds"
+						}
+					}
+				}
+			}
+		}
+		periodic_healthcheck: {
+			common:        false
+			description:   "Keeps checking the health of the downstream Vector after startup."
+			required:      false
+			relevant_when: "version = \"2\""
+			type: object: {
+				examples: []
+				options: {
+					interval_secs: {
+						common:      false
+						description: "How often to check the health of the downstream Vector. Periodic checks are disabled when unset."
+						required:    false
+						type: uint: {
+							default: null
+							examples: [10]
+							unit: "seconds"
+						}
+					}
+					pause_when_unhealthy: {
+						common:      false
+						description: "Hold back requests while the downstream Vector is unhealthy instead of sending and retrying them. Events are sent again once it is healthy."
+						required:    false
+						type: bool: default: false
+					}
+				}
+			}
+		}
+		request: type: object: options: circuit_breaker: {
+			common:        false
+			description:   "Stops sending requests for a while when most recent requests to the downstream Vector have failed, rejecting them immediately instead of retrying."
+			required:      false
+			relevant_when: "version = \"2\""
+			type: object: {
+				examples: []
+				options: {
+					failure_rate_threshold: {
+						common:      false
+						description: "The fraction of failed requests, between 0 and 1, at which the breaker opens."
+						required:    false
+						type: float: default: 0.5
+					}
+					minimum_requests: {
+						common:      false
+						description: "The number of most recent requests the failure rate is computed over. The breaker never opens before this many requests have completed."
+						required:    false
+						type: uint: {
+							default: 20
+							unit:    "requests"
+						}
+					}
+					open_duration_secs: {
+						common:      false
+						description: "How long the breaker stays open, rejecting all requests, before sending probe requests."
+						required:    false
+						type: uint: {
+							default: 30
+							unit:    "seconds"
+						}
+					}
+					half_open_probes: {
+						common:      false
+						description: "The number of probe requests that must succeed to close the breaker. A single failed probe opens it again."
+						required:    false
+						type: uint: {
+							default: 1
+							unit:    "requests"
+						}
+					}
+				}
+			}
+		}
 	}
 
 	how_it_works: components.sources.vector.how_it_works
 
 	telemetry: metrics: {
-		component_sent_bytes_total:       components.sources.internal_metrics.output.metrics.component_sent_bytes_total
-		component_sent_events_total:      components.sources.internal_metrics.output.metrics.component_sent_events_total
-		component_sent_event_bytes_total: components.sources.internal_metrics.output.metrics.component_sent_event_bytes_total
-		processed_bytes_total:            components.sources.internal_metrics.output.metrics.processed_bytes_total
-		processed_events_total:           components.sources.internal_metrics.output.metrics.processed_events_total
-		protobuf_decode_errors_total:     components.sources.internal_metrics.output.metrics.protobuf_decode_errors_total
+		adaptive_concurrency_current_limit: components.sources.internal_metrics.output.metrics.adaptive_concurrency_current_limit
+		component_sent_bytes_total:         components.sources.internal_metrics.output.metrics.component_sent_bytes_total
+		component_sent_events_total:        components.sources.internal_metrics.output.metrics.component_sent_events_total
+		component_sent_event_bytes_total:   components.sources.internal_metrics.output.metrics.component_sent_event_bytes_total
+		encode_errors_total:                components.sources.internal_metrics.output.metrics.encode_errors_total
+		event_age_clock_skew_total:         components.sources.internal_metrics.output.metrics.event_age_clock_skew_total
+		event_age_seconds:                  components.sources.internal_metrics.output.metrics.event_age_seconds
+		events_discarded_total:             components.sources.internal_metrics.output.metrics.events_discarded_total
+		processed_bytes_total:              components.sources.internal_metrics.output.metrics.processed_bytes_total
+		processed_events_total:             components.sources.internal_metrics.output.metrics.processed_events_total
+		protobuf_decode_errors_total:       components.sources.internal_metrics.output.metrics.protobuf_decode_errors_total
+		sink_buffered_events:               components.sources.internal_metrics.output.metrics.sink_buffered_events
+		sink_delta_resent_events_total:     components.sources.internal_metrics.output.metrics.sink_delta_resent_events_total
+		sink_in_flight_requests:            components.sources.internal_metrics.output.metrics.sink_in_flight_requests
+		sink_retrying_events:               components.sources.internal_metrics.output.metrics.sink_retrying_events
+		sink_shutdown_dropped_events_total: components.sources.internal_metrics.output.metrics.sink_shutdown_dropped_events_total
+		sink_spool_errors_total:            components.sources.internal_metrics.output.metrics.sink_spool_errors_total
+		sink_spool_quarantined_files_total: components.sources.internal_metrics.output.metrics.sink_spool_quarantined_files_total
+		sink_spool_restored_events_total:   components.sources.internal_metrics.output.metrics.sink_spool_restored_events_total
+		sink_spooled_events_total:          components.sources.internal_metrics.output.metrics.sink_spooled_events_total
 	}
 }
@@ -232,6 +232,12 @@ components: sources: internal_metrics: {
 			default_namespace: "vector"
 			tags:              _internal_metrics_tags
 		}
+		adaptive_concurrency_current_limit: {
+			description:       "The concurrency limit that the adaptive concurrency feature last decided on, within the `min_concurrency` and `max_concurrency` bounds."
+			type:              "gauge"
+			default_namespace: "vector"
+			tags:              _internal_metrics_tags
+		}
 		adaptive_concurrency_observed_rtt: {
 			description:       "The observed round-trip time (RTT) for requests."
 			type:              "histogram"
@@ -421,6 +427,18 @@ components: sources: internal_metrics: {
 			default_namespace: "vector"
 			tags:              _internal_metrics_tags
 		}
+		event_age_clock_skew_total: {
+			description:       "The total number of delivered events whose ingest timestamp was later than their delivery, because the clocks involved disagree. Their age is recorded as zero."
+			type:              "counter"
+			default_namespace: "vector"
+			tags:              _component_tags
+		}
+		event_age_seconds: {
+			description:       "The time from when an event was ingested by a source to when it was delivered by a sink."
+			type:              "histogram"
+			default_namespace: "vector"
+			tags:              _component_tags
+		}
 		events_discarded_total: {
 			description:       "The total number of events discarded by this component."
 			type:              "counter"
@@ -612,6 +630,18 @@ components: sources: internal_metrics: {
 			default_namespace: "vector"
 			tags:              _component_tags
 		}
+		delta_baseline_misses_total: {
+			description:       "The total number of requests the `vector` source refused because a delta in them was based on an event it doesn't have, for the sender to send their events whole."
+			type:              "counter"
+			default_namespace: "vector"
+			tags:              _component_tags
+		}
+		delta_events_received_total: {
+			description:       "The total number of events the `vector` source received as deltas from the previous event of their key."
+			type:              "counter"
+			default_namespace: "vector"
+			tags:              _component_tags
+		}
 		kafka_queue_messages: {
 			description:       "Current number of messages in producer queues."
 			type:              "gauge"
@@ -821,6 +851,18 @@ components: sources: internal_metrics: {
 			default_namespace: "vector"
 			tags:              _component_tags
 		}
+		metric_tags_dropped_total: {
+			description:       "The total number of metric tags dropped for exceeding the configured tag limits."
+			type:              "counter"
+			default_namespace: "vector"
+			tags:              _component_tags
+		}
+		metric_tags_truncated_total: {
+			description:       "The total number of metric tags truncated to fit the configured tag limits."
+			type:              "counter"
+			default_namespace: "vector"
+			tags:              _component_tags
+		}
 		open_connections: {
 			description:       "The number of current open connections to Vector."
 			type:              "gauge"
@@ -888,6 +930,53 @@ components: sources: internal_metrics: {
 			default_namespace: "vector"
 			tags:              _component_tags
 		}
+		raw_payloads_skipped_total: {
+			description:       "The total number of log events forwarded without their raw payload because they were larger than `max_raw_payload_bytes`."
+			type:              "counter"
+			default_namespace: "vector"
+			tags:              _component_tags
+		}
+		remap_path_misses_total: {
+			description:       "The total number of reads of a path by a `remap` program that found no value there, see its `path_stats` option."
+			type:              "counter"
+			default_namespace: "vector"
+			tags:              _component_tags & {
+				path: _remap_path
+			}
+		}
+		remap_path_operations_total: {
+			description:       "The total number of operations of a `remap` program on a path, see its `path_stats` option."
+			type:              "counter"
+			default_namespace: "vector"
+			tags:              _component_tags & {
+				path: _remap_path
+				operation: {
+					description: "The operation on the path."
+					required:    true
+					enum: {
+						"get":    "The path was read, or checked for a value."
+						"set":    "The path was written."
+						"remove": "The path was removed."
+					}
+				}
+			}
+		}
+		rejected_events_total: {
+			description:       "The total number of events the `vector` source rejected, see its `rejected_events` option."
+			type:              "counter"
+			default_namespace: "vector"
+			tags:              _component_tags & {
+				reason: {
+					description: "Why the event was rejected."
+					required:    true
+					enum: {
+						"decode_failed":      "The reassembled chunks of the event failed to decode."
+						"too_many_fields":    "The log event has more fields than `max_fields_per_event`."
+						"unknown_event_type": "The event is of a type unknown to this Vector."
+					}
+				}
+			}
+		}
 		request_errors_total: {
 			description:       "The total number of requests errors for this component."
 			type:              "counter"
@@ -930,6 +1019,116 @@ components: sources: internal_metrics: {
 			default_namespace: "vector"
 			tags:              _component_tags
 		}
+		sink_buffered_events: {
+			description:       "The number of events waiting in batches of this sink to be sent."
+			type:              "gauge"
+			default_namespace: "vector"
+			tags:              _component_tags
+		}
+		sink_delta_resent_events_total: {
+			description:       "The total number of keyed events the `vector` sink sent again whole because the downstream Vector was missing the events their deltas were based on."
+			type:              "counter"
+			default_namespace: "vector"
+			tags:              _component_tags
+		}
+		sink_in_flight_requests: {
+			description:       "The number of requests this sink has sent, including those being retried, that haven't completed yet."
+			type:              "gauge"
+			default_namespace: "vector"
+			tags:              _component_tags
+		}
+		sink_retrying_events: {
+			description:       "The number of events in requests of this sink whose last attempt failed and that are waiting to be retried."
+			type:              "gauge"
+			default_namespace: "vector"
+			tags:              _component_tags
+		}
+		sink_shutdown_dropped_events_total: {
+			description:       "The total number of events this sink dropped because it was stopped before delivering them."
+			type:              "counter"
+			default_namespace: "vector"
+			tags:              _component_tags
+		}
+		sink_spool_errors_total: {
+			description:       "The total number of times the `vector` sink failed to read, write or remove a file of its spool."
+			type:              "counter"
+			default_namespace: "vector"
+			tags:              _component_tags
+		}
+		sink_spool_quarantined_files_total: {
+			description:       "The total number of spool files the `vector` sink moved to the `quarantine` directory of its spool instead of sending their events."
+			type:              "counter"
+			default_namespace: "vector"
+			tags:              _component_tags & {
+				reason: {
+					description: "Why the file was quarantined."
+					required:    true
+					enum: {
+						"corrupt":  "The file couldn't be read back as events."
+						"over_cap": "The file didn't fit in `spool.max_bytes` along with the older ones."
+						"rejected": "Some of the events of the file were rejected by the downstream Vector, or could no longer be encoded within `max_event_bytes`."
+					}
+				}
+			}
+		}
+		sink_spool_restored_events_total: {
+			description:       "The total number of events the `vector` sink read back from its spool when it started."
+			type:              "counter"
+			default_namespace: "vector"
+			tags:              _component_tags
+		}
+		sink_spooled_events_total: {
+			description:       "The total number of undelivered events the `vector` sink wrote to its spool when it stopped."
+			type:              "counter"
+			default_namespace: "vector"
+			tags:              _component_tags
+		}
+		source_extra_values_omitted_total: {
+			description:       "The total number of events the `vector` source left a value of `extra_fields` or `extra_tags` out of, because the request metadata it refers to was missing."
+			type:              "counter"
+			default_namespace: "vector"
+			tags:              _component_tags & {kind: _extra_value_kind, name: _extra_value_name}
+		}
+		source_rate_limited_events_total: {
+			description:       "The total number of events in the requests the `vector` source refused for being over the rate limit of their sender."
+			type:              "counter"
+			default_namespace: "vector"
+			tags:              _component_tags & {sender: _rate_limited_sender}
+		}
+		source_rate_limited_requests_total: {
+			description:       "The total number of requests the `vector` source refused for being over the rate limit of their sender."
+			type:              "counter"
+			default_namespace: "vector"
+			tags:              _component_tags & {sender: _rate_limited_sender}
+		}
+		source_state_bytes: {
+			description:       "The number of bytes of state the `vector` source keeps."
+			type:              "gauge"
+			default_namespace: "vector"
+			tags:              _component_tags & {state: _source_state}
+		}
+		source_state_entries: {
+			description:       "The number of entries of state the `vector` source keeps, such as one per sender on a connection."
+			type:              "gauge"
+			default_namespace: "vector"
+			tags:              _component_tags & {state: _source_state}
+		}
+		source_state_evictions_total: {
+			description:       "The total number of entries of state the `vector` source dropped."
+			type:              "counter"
+			default_namespace: "vector"
+			tags:              _component_tags & {
+				state: _source_state
+				reason: {
+					description: "Why the state was dropped."
+					required:    true
+					enum: {
+						"capacity": "Other entries needed the room, see `state.max_bytes` and `delta.max_bytes`."
+						"ttl":      "The entry wasn't used within `state.ttl_secs` or `delta.ttl_secs`."
+					}
+				}
+			}
+		}
 		splunk_pending_acks: {
 			description:       "The number of outstanding Splunk HEC indexer acknowledgement acks."
 			type:              "gauge"
@@ -1216,12 +1415,43 @@ components: sources: internal_metrics: {
 			description: "The path that produced the error."
 			required:    true
 		}
+		_remap_path: {
+			description: "The path, as written in VRL without the leading dot, or `<other>` for the paths past `path_stats.max_paths`."
+			required:    true
+		}
+		_source_state: {
+			description: "The state of the `vector` source the metric is about."
+			required:    true
+			enum: {
+				"peers":           "What is kept about each sender on each connection, see `state`."
+				"delta_baselines": "The last event of each key of the senders sending deltas, see `delta`."
+				"rate_limits":     "The token buckets of each sender, see `rate_limit`."
+			}
+		}
+		_extra_value_kind: {
+			description: "Whether the value is one of the fields added to log events, or one of the tags added to metrics."
+			required:    true
+			enum: {
+				"field": "A value of `extra_fields`."
+				"tag":   "A value of `extra_tags`."
+			}
+		}
+		_extra_value_name: {
+			description: "The field or tag the value is added as."
+			required:    true
+		}
+		_rate_limited_sender: {
+			description: "The sender, such as `peer_ip:10.0.0.1`, or `other` once `rate_limit.max_metric_senders` senders were tagged."
+			required:    true
+		}
 		_reason: {
 			description: "The type of the error"
 			required:    true
 			enum: {
-				"out_of_order": "The event was out of order."
-				"oversized":    "The event was too large."
+				"marked_dropped":      "A `remap` program marked the event as dropped, and `reroute_dropped` is off."
+				"out_of_order":        "The event was out of order."
+				"oversized":           "The event was too large."
+				"rejected_downstream": "The downstream Vector rejected the event, such as because it failed to decode there."
 			}
 		}
 	}
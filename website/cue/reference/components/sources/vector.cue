@@ -65,13 +65,423 @@ components: sources: vector: {
 		}
 		shutdown_timeout_secs: {
 			common:      false
-			description: "The timeout before a connection is forcefully closed during shutdown."
+			description: """
+				The timeout before a connection is forcefully closed during shutdown. Once shutdown
+				starts, new requests are refused with an `Unavailable` status with the message
+				`shutting down`, asking the sender to wait 5 seconds before retrying through the
+				`grpc-retry-pushback-ms` metadata.
+				"""
 			required:    false
 			type: uint: {
 				default: 30
 				unit:    "seconds"
 			}
 		}
+		timestamp_policy: {
+			common:        false
+			description:   "How the timestamps of received events are set. To keep the sender's timestamp and also record when the event arrived, set `timestamp_policy.add_received_at` to the field that should hold the arrival time."
+			required:      false
+			relevant_when: "version = \"2\""
+			type: string: {
+				default: "preserve"
+				enum: {
+					preserve:  "Keep events as the sender sent them. Logs without a timestamp are left without one."
+					overwrite: "Replace the timestamp of logs with the arrival time. Metrics without a timestamp get the arrival time."
+				}
+			}
+		}
+		host_key_policy: {
+			common:        false
+			description:   "What is done with the host of received events, at the `host_key` of the log schema for logs and in the tag of that name for metrics, so that the Vectors events came through can be told apart from the host they were created on. To keep the sender's host and also record each hop, set `host_key_policy.append_hop` to the field that should hold the hops: an array of peer addresses for logs, and a comma separated list in a tag of that name for metrics."
+			required:      false
+			relevant_when: "version = \"2\""
+			type: string: {
+				default: "preserve"
+				enum: {
+					preserve:            "Keep the host set by the sender."
+					overwrite_with_peer: "Replace the host with the address of the peer that sent the events."
+				}
+			}
+		}
+		sender: {
+			common:        false
+			description:   "What is done with the identity an upstream Vector attaches to its requests: its hostname, version, process id, and custom labels."
+			required:      false
+			relevant_when: "version = \"2\""
+			type: object: {
+				examples: []
+				options: {
+					log: {
+						common:      false
+						description: "Log the sender at debug level the first time it is seen on a connection."
+						required:    false
+						type: bool: default: true
+					}
+					field: {
+						common:      false
+						description: "Add the sender to each log event it sent, as an object with `hostname`, `version`, `uuid`, and `labels` in this field. Metrics are left as they are."
+						required:    false
+						type: string: {
+							default: null
+							examples: ["sender"]
+						}
+					}
+				}
+			}
+		}
+		metadata_fields: {
+			common:        false
+			description:   "Request metadata to copy into each log event of the request, from the metadata key to the field it goes into. Metadata the request doesn't have is skipped. Binary metadata, whose key ends in `-bin`, is decoded from base64. Metrics are left as they are."
+			required:      false
+			relevant_when: "version = \"2\""
+			type: object: {
+				examples: [{"x-tenant-id": "tenant", "x-region": "cloud.region"}]
+				options: {}
+			}
+		}
+		metadata_fields_overwrite: {
+			common:        false
+			description:   "Replace fields the log events already have with the values of `metadata_fields`. By default, such fields are kept."
+			required:      false
+			relevant_when: "version = \"2\""
+			type: bool: default: false
+		}
+		extra_fields: {
+			common:        false
+			description:   "Fields to add to each log event, from the field, which may be a nested path, to its value. `{{ header.<key> }}` in a value is replaced by the request metadata of that key, decoded from base64 for binary metadata. A value whose metadata the request doesn't have is left out, and counted in `source_extra_values_omitted_total`. Fields the log events already have are kept, unless `overwrite` is set."
+			required:      false
+			relevant_when: "version = \"2\""
+			type: object: {
+				examples: [{"cluster": "prod-eu", "cloud.region": "{{ header.x-region }}"}]
+				options: {}
+			}
+		}
+		extra_tags: {
+			common:        false
+			description:   "Tags to add to each metric, from the tag to its value. Values are templated like those of `extra_fields`."
+			required:      false
+			relevant_when: "version = \"2\""
+			type: object: {
+				examples: [{"cluster": "prod-eu", "region": "{{ header.x-region }}"}]
+				options: {}
+			}
+		}
+		overwrite: {
+			common:        false
+			description:   "Replace the fields and tags the events already have with those of `extra_fields` and `extra_tags`. By default, they are kept."
+			required:      false
+			relevant_when: "version = \"2\""
+			type: bool: default: false
+		}
+		max_request_bytes: {
+			common:        false
+			description:   "The largest encoded request accepted. Larger requests are rejected without any of their events being forwarded."
+			required:      false
+			relevant_when: "version = \"2\""
+			type: uint: {
+				default: null
+				examples: [4_194_304]
+				unit: "bytes"
+			}
+		}
+		max_fields_per_event: {
+			common:        false
+			description:   "The most fields of a log event accepted, counting the fields of maps nested in it at every depth. A log event with more is rejected as `too_many_fields` without its fields past the limit being decoded, see `rejected_events`, while the other events of its request are accepted. Its payload is left out of the dead letter. Requests are decoded whole before this is checked, so bound their size with `max_request_bytes` as well."
+			required:      false
+			relevant_when: "version = \"2\""
+			type: uint: {
+				default: null
+				examples: [10_000]
+				unit: null
+			}
+		}
+		store_raw_payload: {
+			common:        false
+			description:   "Store the encoded form of each log event, as it was received, in `raw_payload_field`, so that a downstream sink can archive the original. It counts towards the size of the event. Metrics are left as they are."
+			required:      false
+			relevant_when: "version = \"2\""
+			type: bool: default: false
+		}
+		raw_payload_field: {
+			common:        false
+			description:   "The field the raw payload of each log event is stored in, as bytes, when `store_raw_payload` is set."
+			required:      false
+			relevant_when: "version = \"2\""
+			type: string: {
+				default: "raw_payload"
+				examples: ["raw_payload", "original.payload"]
+			}
+		}
+		max_raw_payload_bytes: {
+			common:        false
+			description:   "The largest encoded log event whose raw payload is stored. Larger ones are forwarded without it, and counted in `raw_payloads_skipped_total`."
+			required:      false
+			relevant_when: "version = \"2\""
+			type: uint: {
+				default: 1_048_576
+				unit:    "bytes"
+			}
+		}
+		chunking: {
+			common:        false
+			description:   "Put back together the events an upstream `vector` sink split into chunks because they were too large for a single request. An event is forwarded once all of its chunks have arrived. The chunks of events that time out or don't fit in the buffer are dropped and counted in `events_discarded_total`."
+			required:      false
+			relevant_when: "version = \"2\""
+			type: object: {
+				examples: []
+				options: {
+					enabled: {
+						common:      false
+						description: "Accept chunked events. Requests holding chunks are rejected otherwise."
+						required:    false
+						type: bool: default: false
+					}
+					timeout_secs: {
+						common:      false
+						description: "How long the chunks of an event are held waiting for the rest of them."
+						required:    false
+						type: uint: {
+							default: 60
+							unit:    "seconds"
+						}
+					}
+					max_buffer_bytes: {
+						common:      false
+						description: "The most bytes of chunks held for events that aren't complete yet, including a fixed overhead for each event and each chunk. Requests whose chunks don't fit are rejected, so the sender retries them later. Events with more chunks than could ever fit are dropped."
+						required:    false
+						type: uint: {
+							default: 104_857_600
+							unit:    "bytes"
+						}
+					}
+					max_open_events: {
+						common:      false
+						description: "The most events whose chunks are held while they aren't complete yet. Requests holding chunks of another event are rejected, so the sender retries them later."
+						required:    false
+						type: uint: default: 1_000
+					}
+				}
+			}
+		}
+		heartbeat: {
+			common:        false
+			description:   "Send a heartbeat log event into the pipeline at the end of each interval in which no events arrived, so that a source getting no traffic can be told from a broken one downstream. It has `heartbeat` set to `true`, the `source_type`, the `address` the source is listening on, the `interval_secs`, and the `events_received` and `requests_received` since the previous heartbeat, or since the source started. No heartbeats are sent once the source starts shutting down."
+			required:      false
+			relevant_when: "version = \"2\""
+			type: object: {
+				examples: []
+				options: {
+					interval_secs: {
+						common:      false
+						description: "How long the source waits for events before sending a heartbeat, and then between heartbeats. No heartbeats are sent if unset."
+						required:    false
+						type: uint: {
+							default: null
+							examples: [60]
+							unit: "seconds"
+						}
+					}
+				}
+			}
+		}
+		mint_event_ids: {
+			common:        false
+			description:   "Give each event received without an id a new one, as `telemetry.event_ids` does for the sources that create events. The ids events were sent with by the upstream Vector are always kept."
+			required:      false
+			relevant_when: "version = \"2\""
+			type: bool: default: false
+		}
+		rate_limit: {
+			common:        false
+			description:   "Limit how fast each upstream Vector may send, so that one sending too much can't starve the others. Each sender gets a token bucket of events and one of bytes. Requests over the limit are refused with `ResourceExhausted` and the time to wait before retrying them, which `vector` sinks honor. They are counted in `source_rate_limited_requests_total`. The buckets are kept within the bounds of `state`."
+			required:      false
+			relevant_when: "version = \"2\""
+			type: object: {
+				examples: []
+				options: {
+					enabled: {
+						common:      false
+						description: "Limit senders. Requires `events_per_sec` or `bytes_per_sec`."
+						required:    false
+						type: bool: default: false
+					}
+					events_per_sec: {
+						common:      false
+						description: "The events each sender may send per second, on average."
+						required:    false
+						type: uint: {
+							default: null
+							unit:    "events"
+						}
+					}
+					events_burst: {
+						common:      false
+						description: "The most events a sender may send at once after being idle. Defaults to `events_per_sec`. A larger request goes through once the bucket is full, and the sender then waits until the events over the burst are paid back."
+						required:    false
+						type: uint: {
+							default: null
+							unit:    "events"
+						}
+					}
+					bytes_per_sec: {
+						common:      false
+						description: "The bytes of requests each sender may send per second, on average."
+						required:    false
+						type: uint: {
+							default: null
+							unit:    "bytes"
+						}
+					}
+					bytes_burst: {
+						common:      false
+						description: "The most bytes a sender may send at once after being idle. Defaults to `bytes_per_sec`. A larger request goes through once the bucket is full, and the sender then waits until the bytes over the burst are paid back."
+						required:    false
+						type: uint: {
+							default: null
+							unit:    "bytes"
+						}
+					}
+					identify_by: {
+						common:      false
+						description: "What senders are told apart by, in order of precedence. Requests with none of them aren't limited."
+						required:    false
+						type: array: {
+							default: ["certificate", "token", "peer_ip"]
+							items: type: string: enum: {
+								certificate: "The common name of the client certificate."
+								token:       "The value of the `token_metadata_key` metadata. Only a hash of it is kept, and shown in metrics and logs."
+								peer_ip:     "The IP address the request came from."
+							}
+						}
+					}
+					token_metadata_key: {
+						common:      false
+						description: "The request metadata holding the token of the sender."
+						required:    false
+						type: string: {
+							default: "authorization"
+							examples: ["authorization", "x-agent-token"]
+						}
+					}
+					max_metric_senders: {
+						common:      false
+						description: "The most senders the metrics of refused requests are tagged with. Senders beyond these are tagged `other`."
+						required:    false
+						type: uint: {
+							default: 100
+							unit:    null
+						}
+					}
+				}
+			}
+		}
+		rejected_events: {
+			common:        false
+			description:   "What is done with the events the source rejects, such as those of a type unknown to this Vector or whose reassembled chunks fail to decode. The other events of the request holding them are accepted either way, and the response tells the sender how many were rejected. Rejections are counted in `rejected_events_total`."
+			required:      false
+			relevant_when: "version = \"2\""
+			type: object: {
+				examples: []
+				options: {
+					behavior: {
+						common:      false
+						description: "How rejected events are handled."
+						required:    false
+						type: string: {
+							default: "drop"
+							enum: {
+								drop:             "Only count them, logging them at debug level."
+								log:              "Log them as warnings, summed up as one line for each reason every `drop_summary_interval_secs`, with how many were rejected and the error and event id of the first of them."
+								forward_to_field: "Forward a log event for each of them into the pipeline. Its message is the event in the encoded form it was received in, its timestamp the time it was rejected, and `field` holds the `reason`, `error`, `peer_addr` and, when the sender gave the event one, `event_id` of the rejection, so that routing can send it to an audit sink."
+							}
+						}
+					}
+					field: {
+						common:      false
+						description: "The field of the forwarded log events that holds the rejection, when `behavior` is `forward_to_field`."
+						required:    false
+						type: string: {
+							default: "rejected"
+							examples: ["rejected", "dead_letter.rejection"]
+						}
+					}
+				}
+			}
+		}
+		drop_summary_interval_secs: {
+			common:        false
+			description:   "How often the rejected events are logged when `rejected_events.behavior` is `log`, as one warning for each reason there were rejections for."
+			required:      false
+			relevant_when: "version = \"2\""
+			type: uint: {
+				default: 10
+				unit:    "seconds"
+			}
+		}
+		delta: {
+			common:        false
+			description:   "Accept log events that upstream `vector` sinks send as the fields that changed since the previous event of the same key, applying them to that event. The source keeps the last event of each key to do so, and asks for the events of a request whole when it doesn't have the one a delta is based on. Experimental."
+			required:      false
+			relevant_when: "version = \"2\""
+			type: object: {
+				examples: []
+				options: {
+					enabled: {
+						common:      false
+						description: "Accept deltas, advertising it to the sinks that ask."
+						required:    false
+						type: bool: default: false
+					}
+					max_bytes: {
+						common:      false
+						description: "The most bytes of events kept as the baselines of deltas. The baselines of the least recently used keys are dropped to make room."
+						required:    false
+						type: uint: {
+							default: 10_485_760
+							unit:    "bytes"
+						}
+					}
+					ttl_secs: {
+						common:      false
+						description: "How long the baseline of a key that isn't used is kept."
+						required:    false
+						type: uint: {
+							default: 3600
+							unit:    "seconds"
+						}
+					}
+				}
+			}
+		}
+		state: {
+			common:        false
+			description:   "Bounds on the state the source keeps about each sender on each connection, such as whether it was already logged by `sender.log`. Evictions are counted in `source_state_evictions_total`."
+			required:      false
+			relevant_when: "version = \"2\""
+			type: object: {
+				examples: []
+				options: {
+					max_bytes: {
+						common:      false
+						description: "The most bytes of state kept in total. The state of the least recently heard from senders is dropped to make room."
+						required:    false
+						type: uint: {
+							default: 10_485_760
+							unit:    "bytes"
+						}
+					}
+					ttl_secs: {
+						common:      false
+						description: "How long the state of a sender that isn't heard from is kept."
+						required:    false
+						type: uint: {
+							default: 3600
+							unit:    "seconds"
+						}
+					}
+				}
+			}
+		}
 		version: {
 			description: "Source API version. Specifying this version ensures that Vector does not break backward compatibility."
 			common:      true
@@ -111,7 +521,18 @@ components: sources: vector: {
 		component_received_bytes_total:       components.sources.internal_metrics.output.metrics.component_received_bytes_total
 		component_received_events_total:      components.sources.internal_metrics.output.metrics.component_received_events_total
 		component_received_event_bytes_total: components.sources.internal_metrics.output.metrics.component_received_event_bytes_total
+		delta_baseline_misses_total:          components.sources.internal_metrics.output.metrics.delta_baseline_misses_total
+		delta_events_received_total:          components.sources.internal_metrics.output.metrics.delta_events_received_total
+		events_discarded_total:               components.sources.internal_metrics.output.metrics.events_discarded_total
 		events_in_total:                      components.sources.internal_metrics.output.metrics.events_in_total
 		protobuf_decode_errors_total:         components.sources.internal_metrics.output.metrics.protobuf_decode_errors_total
+		raw_payloads_skipped_total:           components.sources.internal_metrics.output.metrics.raw_payloads_skipped_total
+		rejected_events_total:                components.sources.internal_metrics.output.metrics.rejected_events_total
+		source_extra_values_omitted_total:    components.sources.internal_metrics.output.metrics.source_extra_values_omitted_total
+		source_rate_limited_events_total:     components.sources.internal_metrics.output.metrics.source_rate_limited_events_total
+		source_rate_limited_requests_total:   components.sources.internal_metrics.output.metrics.source_rate_limited_requests_total
+		source_state_bytes:                   components.sources.internal_metrics.output.metrics.source_state_bytes
+		source_state_entries:                 components.sources.internal_metrics.output.metrics.source_state_entries
+		source_state_evictions_total:         components.sources.internal_metrics.output.metrics.source_state_evictions_total
 	}
 }
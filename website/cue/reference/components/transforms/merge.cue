@@ -40,6 +40,7 @@ components: transforms: merge: {
 				Fields not specified here will be ignored.
 				Merging process takes the first partial event and the base, then it merges in the fields from each successive partial event, until a non-partial event arrives.
 				Finally, the non-partial event fields are merged in, producing the resulting merged event.
+				String values are concatenated, and arrays of objects appended to, with the elements of earlier events first. Values of any other type are overridden.
 				"""
 			required: false
 			type: array: {
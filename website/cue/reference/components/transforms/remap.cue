@@ -16,6 +16,11 @@ components: transforms: "remap": {
 		output can be accessed by specifying `foo.dropped` as the input to another
 		component. Events sent to this output will be in their original form,
 		omitting any partial modification that took place before the error or abort.
+		A program can also mark an event as dropped itself with
+		`set_metadata_field("dropped", reason)`, in which case the events it produces
+		are sent to the `dropped` output as they are when the program finishes. Without
+		`reroute_dropped`, those events are discarded and counted in
+		`events_discarded_total`.
 
 		Please refer to the [VRL reference](\(urls.vrl_reference)) when writing VRL scripts.
 		"""
@@ -113,6 +118,151 @@ components: transforms: "remap": {
 				"""
 			type: bool: default: false
 		}
+		max_fanout: {
+			common:   false
+			required: false
+			description: """
+				The most events a single event may be split into when the program assigns an
+				array to `.`. Events that are not split are not affected.
+				"""
+			type: uint: {
+				default: 1000
+				unit:    "events"
+			}
+		}
+		fanout_exceeded_action: {
+			common:   false
+			required: false
+			description: """
+				What to do when the program splits an event into more than `max_fanout` events.
+				"""
+			type: string: {
+				default: "truncate"
+				enum: {
+					truncate: "Keep the first `max_fanout` events and discard the rest."
+					fail:     "Handle the event as if the program had returned a runtime error."
+				}
+			}
+		}
+		protected_paths: {
+			common:   false
+			required: false
+			description: """
+				Paths the program may read but not modify or delete. A protected path also
+				covers every path nested below it. Events whose program attempts to write to
+				a protected path are treated as having a runtime error.
+				"""
+			type: array: {
+				default: []
+				items: type: string: {
+					examples: ["timestamp", "tenant.id", "tags.tenant"]
+				}
+			}
+		}
+		tag_limits: {
+			common:   false
+			required: false
+			description: """
+				Limits on the tags the program writes to metrics, to keep tag cardinality in check.
+				Tags set by an upstream component are left as they are.
+				"""
+			type: object: options: {
+				max_tags: {
+					common:      true
+					required:    false
+					description: "The most tags a metric may have. New tags beyond this are dropped."
+					type: uint: {
+						default: null
+						unit:    null
+					}
+				}
+				max_key_length: {
+					common:      false
+					required:    false
+					description: "The longest tag key allowed."
+					type: uint: {
+						default: null
+						unit:    "bytes"
+					}
+				}
+				max_value_length: {
+					common:      true
+					required:    false
+					description: "The longest tag value allowed."
+					type: uint: {
+						default: null
+						unit:    "bytes"
+					}
+				}
+				action: {
+					common:      false
+					required:    false
+					description: "What to do with a tag whose key or value is longer than allowed."
+					type: string: {
+						default: "truncate"
+						enum: {
+							truncate: "Shorten the key or value to the limit. A tag whose shortened key matches another tag is dropped."
+							drop:     "Drop the tag."
+						}
+					}
+				}
+			}
+		}
+		metric_utf8_handling: {
+			common:   false
+			required: false
+			description: """
+				How bytes that aren't valid UTF-8 are written to the name, namespace, or tags of a
+				metric. Valid UTF-8 is always written as is.
+				"""
+			type: string: {
+				default: "lossy"
+				enum: {
+					lossy:  "Replace each invalid sequence with the `U+FFFD` replacement character."
+					reject: "Fail the write, which is handled like any other runtime error of the program."
+					base64: "Write the standard base64 encoding of the bytes instead."
+				}
+			}
+		}
+		path_stats: {
+			common:   false
+			required: false
+			description: """
+				Count how often the program reads, writes, and removes each path of the events, and
+				how many of its reads find nothing, reported in `remap_path_operations_total` and
+				`remap_path_misses_total`. Useful for finding the paths an expensive program uses
+				most. Counting adds a lock per path operation, so leave it disabled otherwise.
+				"""
+			type: object: {
+				examples: []
+				options: {
+					enabled: {
+						common:      false
+						description: "Count the operations on each path."
+						required:    false
+						type: bool: default: false
+					}
+					max_paths: {
+						common:      false
+						description: "The most paths counted apart. The operations on any other path are reported under the `<other>` path. Must be greater than zero."
+						required:    false
+						type: uint: {
+							default: 100
+							unit:    null
+						}
+					}
+					interval_secs: {
+						common:      false
+						description: "How often the counts are reported. They are only reported as events go through the transform. Must be greater than zero."
+						required:    false
+						type: uint: {
+							default: 60
+							unit:    "seconds"
+						}
+					}
+				}
+			}
+		}
 	}
 
 	input: {
@@ -174,6 +324,35 @@ components: transforms: "remap": {
 				`timestamp` | Read/write/delete | You assign only a valid [VRL timestamp](\(urls.vrl_expressions)/#timestamp) value, not a [VRL string](\(urls.vrl_expressions)/#string).
 				`namespace` | Read/write/delete |
 				`tags` | Read/write/delete | The `tags` field must be a [VRL object](\(urls.vrl_expressions)/#object) in which all keys and values are strings.
+				`value` | Read only | An object with the type of the metric as its only key, such as `{"counter": {"value": 1.5}}`. Paths inside it, such as `.value.counter.value`, read the value directly. It can only be changed by assigning the whole metric to `.`.
+
+				The tags starting with a prefix, such as every `aws_` tag, are read as an object with
+				`get_tags_matching` and removed with `remove_tags_matching`, without copying the other tags.
+
+				Reading `.` gives an object with the fields above, leaving out `namespace`, `timestamp`, and `tags`
+				when the metric has none. This shape is stable, so `encode_json(.)` of a metric keeps its value, and
+				assigning the object back to `.` gives the same metric:
+
+				```json
+				{
+				  "name": "requests",
+				  "namespace": "app",
+				  "timestamp": "2020-12-10T12:00:00Z",
+				  "kind": "absolute",
+				  "tags": {"host": "localhost"},
+				  "type": "counter",
+				  "value": {"counter": {"value": 1.5}}
+				}
+				```
+
+				The `value` of the other types is, by type:
+
+				* `gauge`: `{"gauge": {"value": 1.5}}`
+				* `set`: `{"set": {"values": ["a", "b"]}}`
+				* `distribution`: `{"distribution": {"samples": [{"value": 1.5, "rate": 2}], "statistic": "histogram"}}`, where `statistic` is `histogram` or `summary`
+				* `aggregated_histogram`: `{"aggregated_histogram": {"buckets": [{"upper_limit": 1.0, "count": 2}], "count": 2, "sum": 1.5}}`
+				* `aggregated_summary`: `{"aggregated_summary": {"quantiles": [{"quantile": 0.5, "value": 1.0}], "count": 2, "sum": 1.5}}`
+				* `sketch`: `{"sketch": {"sketch": ...}}`, holding the serialized sketch
 
 				It's important to note that if you try to perform a disallowed action, such as deleting the `type`
 				field using `del(.type)`, Vector doesn't abort the VRL program or throw an error. Instead, it ignores
@@ -217,6 +396,11 @@ components: transforms: "remap": {
 	}
 
 	telemetry: metrics: {
-		processing_errors_total: components.sources.internal_metrics.output.metrics.processing_errors_total
+		events_discarded_total:      components.sources.internal_metrics.output.metrics.events_discarded_total
+		metric_tags_dropped_total:   components.sources.internal_metrics.output.metrics.metric_tags_dropped_total
+		metric_tags_truncated_total: components.sources.internal_metrics.output.metrics.metric_tags_truncated_total
+		processing_errors_total:     components.sources.internal_metrics.output.metrics.processing_errors_total
+		remap_path_misses_total:     components.sources.internal_metrics.output.metrics.remap_path_misses_total
+		remap_path_operations_total: components.sources.internal_metrics.output.metrics.remap_path_operations_total
 	}
 }
@@ -194,6 +194,40 @@ configuration: {
 							syntax: "literal"
 						}
 					}
+
+					timestamp_timezone: {
+						common: false
+						description: """
+							The time zone to record the timestamps of the log events sources
+							emit in. The timestamp itself is always stored in UTC. For any other
+							time zone, its offset from UTC at the time of the timestamp, such as
+							`+02:00`, is stored at the `timestamp_offset_key` of each log event a
+							source emits with a timestamp, unless it has an offset already, such
+							as one recorded by an upstream Vector. Sources that take a
+							`log_schema` option can set their own. The time zone name may be
+							`utc`, any name in the [TZ database](\(urls.tz_time_zones)), or
+							`local` to indicate system local time.
+							"""
+						required: false
+						type: string: {
+							default: "utc"
+							examples: ["utc", "local", "Europe/Berlin"]
+						}
+					}
+
+					timestamp_offset_key: {
+						common: false
+						description: """
+							Sets the event key to use for the offset from UTC of the
+							`timestamp_timezone`. It is only set when that isn't `utc`.
+							"""
+						required: false
+						type: string: {
+							default: "timestamp_offset"
+							examples: ["timestamp_offset", "@tz_offset"]
+							syntax: "literal"
+						}
+					}
 				}
 			}
 		}
@@ -248,6 +282,64 @@ configuration: {
 			}
 		}
 
+		telemetry: {
+			common:      false
+			description: "Configures optional internal telemetry."
+			required:    false
+			type: object: options: {
+				event_size_histograms: {
+					common:      false
+					description: """
+						Record an `event_size_bytes` histogram of the size of each event handled
+						by components that support it, tagged with the component that handled
+						it like the other component metrics. Histograms are more expensive to maintain than counters, so
+						this is off by default.
+						"""
+					required:    false
+					type: bool: default: false
+				}
+				event_ids: {
+					common:      false
+					description: """
+						Give each log event a source creates from the raw bytes it received a
+						unique id, kept as it goes through transforms and sinks and passed on by
+						the `vector` sink, so that an event can be followed across components and
+						Vector instances. The id
+						is added to the internal logs about the event, such as when it is
+						dropped.
+						"""
+					required:    false
+					type: bool: default: false
+				}
+			}
+		}
+
+		redaction: {
+			common:      false
+			description: "Keeps secrets held in events out of Vector's own logs."
+			required:    false
+			type: object: options: {
+				sensitive_paths: {
+					common:      false
+					description: """
+						The paths of the log event fields holding secrets, whose values are shown
+						as `"[REDACTED]"` wherever Vector logs an event, such as in trace level logs
+						and in errors about an event. The keys of the fields are still shown. A
+						segment of a path can be `*` to match any one key, or `**` to match any
+						number of keys, and array indices are passed over, so `**.password` matches
+						`users[0].password`.
+						"""
+					required:    false
+					type: array: {
+						default: []
+						items: type: string: {
+							examples: ["password", "headers.authorization", "**.token"]
+						}
+					}
+				}
+			}
+		}
+
 		proxy: {
 			common:      false
 			description: "Configures an HTTP(S) proxy for Vector to use."
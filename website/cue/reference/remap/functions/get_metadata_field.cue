@@ -24,6 +24,11 @@ remap: functions: get_metadata_field: {
 
 					This exists if the `store_hec_token` setting is true in the `splunk_hec` source.
 					"""
+				dropped: """
+					The reason the event was marked as dropped.
+
+					This exists if the event was marked with `set_metadata_field` earlier in the same program.
+					"""
 			}
 			type: ["string"]
 		},
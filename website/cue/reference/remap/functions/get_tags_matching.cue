@@ -0,0 +1,37 @@
+package metadata
+
+remap: functions: get_tags_matching: {
+	category: "Event"
+	description: """
+		Returns the tags of the metric whose keys start with the given `prefix`, as an object.
+
+		Only the matching tags are copied, unlike reading `.tags` and filtering it. Logs have no tags, so for them
+		the object is empty.
+		"""
+
+	arguments: [
+		{
+			name:        "prefix"
+			description: "The prefix the keys of the returned tags start with."
+			required:    true
+			type: ["string"]
+		},
+	]
+	internal_failure_reasons: [
+		"`prefix` isn't a string",
+	]
+	return: types: ["object"]
+
+	examples: [
+		{
+			title: "Get the AWS tags of a metric."
+			source: #"""
+				get_tags_matching!("aws_")
+				"""#
+			return: {
+				aws_region: "us-east-1"
+				aws_zone:   "us-east-1a"
+			}
+		},
+	]
+}
@@ -24,6 +24,11 @@ remap: functions: remove_metadata_field: {
 
 					This field will be used by the  `splunk_*` sinks as the token to send the events with.
 					"""
+				dropped: """
+					The reason the event is dropped.
+
+					Removing it keeps the event on the main output of the `remap` transform.
+					"""
 			}
 			type: ["string"]
 		},
@@ -0,0 +1,36 @@
+package metadata
+
+remap: functions: remove_tags_matching: {
+	category: "Event"
+	description: """
+		Removes the tags of the metric whose keys start with the given `prefix`, and returns them as an object
+		like `get_tags_matching` does.
+		"""
+
+	arguments: [
+		{
+			name:        "prefix"
+			description: "The prefix the keys of the removed tags start with."
+			required:    true
+			type: ["string"]
+		},
+	]
+	internal_failure_reasons: [
+		"`prefix` isn't a string",
+		"One of the matching tags is protected, in which case no tag is removed",
+	]
+	return: types: ["object"]
+
+	examples: [
+		{
+			title: "Remove the AWS tags of a metric."
+			source: #"""
+				remove_tags_matching!("aws_")
+				"""#
+			return: {
+				aws_region: "us-east-1"
+				aws_zone:   "us-east-1a"
+			}
+		},
+	]
+}
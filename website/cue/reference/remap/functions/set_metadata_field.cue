@@ -22,6 +22,11 @@ remap: functions: set_metadata_field: {
 
 					This field will be used by the  `splunk_*` sinks as the token to send the events with.
 					"""
+				dropped: """
+					The reason the event is dropped.
+
+					Setting this sends the event to the `dropped` output of the `remap` transform once the program finishes, or discards it if `reroute_dropped` is disabled. The reason is never added to the event fields.
+					"""
 			}
 			type: ["string"]
 		},